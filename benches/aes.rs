@@ -0,0 +1,52 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! Throughput of AES, for single blocks and in the ECB, CBC and CTR
+//! modes.  Run with `cargo bench --bench aes`.
+//!
+//! Baseline on an x86-64 machine, release build:
+//!
+//! ```text
+//! encrypt_block                 1214 ns/iter    13.2 MB/s
+//! decrypt_block                 1354 ns/iter    11.8 MB/s
+//! encrypt_ecb/1048576       25766884 ns/iter    40.7 MB/s
+//! decrypt_ecb/1048576       28487628 ns/iter    36.8 MB/s
+//! encrypt_cbc/1048576       37740442 ns/iter    27.8 MB/s
+//! decrypt_cbc/1048576       37860460 ns/iter    27.7 MB/s
+//! encrypt_ctr/1048576       41794300 ns/iter    25.1 MB/s
+//! ```
+//!
+//! The block functions expand the key schedule for every call, so
+//! they are dominated by the key expansion.
+
+mod common;
+
+use cryptopals::cipher::aes::{self, AesKey};
+
+use crate::common::bench;
+
+fn main() {
+    let key = AesKey::from([0x2b; 16]);
+    let iv = [0x17; 16];
+    let block = [0x5a; 16];
+    bench("encrypt_block", 16, || {
+        let mut out = [0; 16];
+        aes::encrypt(&key, &block, &mut out);
+        out
+    });
+    bench("decrypt_block", 16, || {
+        let mut out = [0; 16];
+        aes::decrypt(&key, &block, &mut out);
+        out
+    });
+    for &len in [1024, 1 << 20].iter() {
+        let msg: Vec<u8> = (0..len).map(|i| i as u8).collect();
+        let ecb = aes::encrypt_ecb(&key, &msg);
+        let cbc = aes::encrypt_cbc(&key, &iv, &msg);
+        bench(&format!("encrypt_ecb/{}", len), len, || aes::encrypt_ecb(&key, &msg));
+        bench(&format!("decrypt_ecb/{}", len), len, || aes::decrypt_ecb(&key, &ecb));
+        bench(&format!("encrypt_cbc/{}", len), len, || aes::encrypt_cbc(&key, &iv, &msg));
+        bench(&format!("decrypt_cbc/{}", len), len, || aes::decrypt_cbc(&key, &iv, &cbc));
+        bench(&format!("encrypt_ctr/{}", len), len, || aes::encrypt_ctr(&key, &iv, &msg));
+    }
+}
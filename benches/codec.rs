@@ -0,0 +1,32 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! Throughput of the BASE64 and hex codecs.  Run with `cargo bench
+//! --bench codec`.
+//!
+//! Baseline on an x86-64 machine, release build:
+//!
+//! ```text
+//! base64_encode/1048576      5360102 ns/iter   195.6 MB/s
+//! base64_decode/1048576     56439596 ns/iter    18.6 MB/s
+//! hex_encode/1048576         2233460 ns/iter   469.5 MB/s
+//! hex_decode/1048576         3943630 ns/iter   265.9 MB/s
+//! ```
+
+mod common;
+
+use cryptopals::codec::{base64, hex};
+
+use crate::common::bench;
+
+fn main() {
+    for &len in [48, 1 << 20].iter() {
+        let bytes: Vec<u8> = (0..len).map(|i| (i * 7) as u8).collect();
+        let b64 = base64::encode(&bytes);
+        let hx = hex::encode(&bytes);
+        bench(&format!("base64_encode/{}", len), len, || base64::encode(&bytes));
+        bench(&format!("base64_decode/{}", len), len, || base64::decode(&b64));
+        bench(&format!("hex_encode/{}", len), len, || hex::encode(&bytes));
+        bench(&format!("hex_decode/{}", len), len, || hex::decode(&hx));
+    }
+}
@@ -0,0 +1,66 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! Minimal benchmark harness shared by the benchmarks.
+
+use std::time::{Duration, Instant};
+
+/// Results of benchmarked functions, reduced to a byte that is
+/// printed, so that the computation cannot be optimized away.
+pub trait Checksum {
+    fn checksum(&self) -> u8;
+}
+
+impl Checksum for Vec<u8> {
+    fn checksum(&self) -> u8 {
+        self.last().cloned().unwrap_or(0)
+    }
+}
+
+impl Checksum for [u8; 16] {
+    fn checksum(&self) -> u8 {
+        self[15]
+    }
+}
+
+impl Checksum for String {
+    fn checksum(&self) -> u8 {
+        self.as_bytes().last().cloned().unwrap_or(0)
+    }
+}
+
+impl<T: Checksum, E> Checksum for Result<T, E> {
+    fn checksum(&self) -> u8 {
+        self.as_ref().map(|t| t.checksum()).unwrap_or(0)
+    }
+}
+
+impl Checksum for usize {
+    fn checksum(&self) -> u8 {
+        *self as u8
+    }
+}
+
+/// Run `f` repeatedly for about a second, after a short warm-up, and
+/// print the time per iteration and the throughput for `bytes` bytes
+/// per iteration.
+pub fn bench<T: Checksum, F: FnMut() -> T>(name: &str, bytes: usize, mut f: F) {
+    let mut sink = 0u8;
+    let warm_up = Instant::now();
+    while warm_up.elapsed() < Duration::from_millis(100) {
+        sink ^= f().checksum();
+    }
+    let start = Instant::now();
+    let mut iterations = 0u64;
+    while start.elapsed() < Duration::from_secs(1) {
+        sink ^= f().checksum();
+        iterations += 1;
+    }
+    let elapsed = start.elapsed();
+    let secs = elapsed.as_secs() as f64 + elapsed.subsec_nanos() as f64 * 1e-9;
+    println!("{:32} {:12.0} ns/iter {:10.1} MB/s  ({})",
+             name,
+             secs * 1e9 / iterations as f64,
+             (bytes as f64 * iterations as f64) / secs / 1e6,
+             sink);
+}
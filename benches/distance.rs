@@ -0,0 +1,47 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! Throughput of the Hamming distance.  Run with `cargo bench
+//! --bench distance`.
+//!
+//! Baseline on an x86-64 machine, release build:
+//!
+//! ```text
+//! hamming/3                       50 ns/iter    60.6 MB/s
+//! hamming_bitwise/3               47 ns/iter    63.6 MB/s
+//! hamming/40                      54 ns/iter   735.3 MB/s
+//! hamming_bitwise/40              59 ns/iter   677.1 MB/s
+//! hamming/1048576             136471 ns/iter  7683.5 MB/s
+//! hamming_bitwise/1048576     237090 ns/iter  4422.7 MB/s
+//! ```
+
+mod common;
+
+use cryptopals::distance;
+
+use crate::common::bench;
+
+/// The bit-by-bit loop `hamming` used to be, for comparison.
+fn hamming_bitwise(b1: &[u8], b2: &[u8]) -> usize {
+    fn bitcnt(b: u8) -> usize {
+        let mut mask = 0x80;
+        let mut cnt = 0;
+        while mask != 0 {
+            if mask & b != 0 {
+                cnt += 1;
+            }
+            mask = mask >> 1;
+        }
+        cnt
+    }
+    b1.iter().zip(b2).map(|(a, b)| bitcnt(a ^ b)).fold(0, |a, b| a + b)
+}
+
+fn main() {
+    for &len in [3, 40, 1 << 20].iter() {
+        let b1: Vec<u8> = (0..len).map(|i| i as u8).collect();
+        let b2: Vec<u8> = (0..len).map(|i| (i * 7) as u8).collect();
+        bench(&format!("hamming/{}", len), len, || distance::hamming(&b1, &b2).unwrap());
+        bench(&format!("hamming_bitwise/{}", len), len, || hamming_bitwise(&b1, &b2));
+    }
+}
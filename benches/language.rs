@@ -0,0 +1,30 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! Throughput of the English text scoring that the XOR attacks run
+//! for every candidate key.  Run with `cargo bench --bench language`.
+//!
+//! Baseline on an x86-64 machine, release build:
+//!
+//! ```text
+//! score_string/34               2196 ns/iter    15.5 MB/s
+//! score_string/65536         5323073 ns/iter    12.3 MB/s
+//! crack_single_byte_xor/34    944925 ns/iter
+//! ```
+
+mod common;
+
+use cryptopals::language::english;
+use cryptopals::xor;
+
+use crate::common::bench;
+
+fn main() {
+    let text = b"Cooking MC's like a pound of bacon";
+    let long: Vec<u8> = text.iter().cloned().cycle().take(1 << 16).collect();
+    for msg in [&text[..], &long[..]].iter() {
+        bench(&format!("score_string/{}", msg.len()), msg.len(), || english::score_string(msg));
+    }
+    bench(&format!("crack_single_byte_xor/{}", text.len()), text.len(),
+          || xor::crack_single_byte_xor(text).map(|r| r.result.1).unwrap_or(Vec::new()));
+}
@@ -0,0 +1,59 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! Throughput of the XOR primitives.  Run with `cargo bench --bench
+//! xor`.
+//!
+//! Baseline on an x86-64 machine, release build:
+//!
+//! ```text
+//! xor_bytes/16                        64 ns/iter   249.5 MB/s
+//! xor_bytes_bytewise/16               85 ns/iter   188.9 MB/s
+//! xor_bytes/1024                     111 ns/iter  9188.9 MB/s
+//! xor_bytes_bytewise/1024           1236 ns/iter   828.7 MB/s
+//! xor_bytes/1048576               143843 ns/iter  7289.7 MB/s
+//! xor_bytes_bytewise/1048576     1191534 ns/iter   880.0 MB/s
+//! repeating/3/1048576             148871 ns/iter  7043.5 MB/s
+//! repeating_bytewise/3/1048576  10126982 ns/iter   103.5 MB/s
+//! repeating/29/1048576            135186 ns/iter  7756.5 MB/s
+//! repeating_bytewise/29/1048576  4563706 ns/iter   229.8 MB/s
+//! one_byte/1048576                136976 ns/iter  7655.2 MB/s
+//! ```
+
+mod common;
+
+use cryptopals::xor;
+
+use crate::common::bench;
+
+/// The byte loop `xor_bytes` used to be, for comparison.
+fn xor_bytes_bytewise(b0: &[u8], b1: &[u8]) -> Vec<u8> {
+    let mut res = Vec::with_capacity(b0.len());
+    for i in 0..b0.len() {
+        res.push(b0[i] ^ b1[i]);
+    }
+    res
+}
+
+/// The chunk-wise `repeating` that was built on it.
+fn repeating_bytewise(key: &[u8], msg: &[u8]) -> Vec<u8> {
+    msg.chunks(key.len())
+        .flat_map(|chunk| xor_bytes_bytewise(chunk, &key[0..chunk.len()]))
+        .collect()
+}
+
+fn main() {
+    for &len in [16, 1024, 1 << 20].iter() {
+        let b0: Vec<u8> = (0..len).map(|i| i as u8).collect();
+        let b1: Vec<u8> = (0..len).map(|i| (i * 7) as u8).collect();
+        bench(&format!("xor_bytes/{}", len), len, || xor::xor_bytes(&b0, &b1).unwrap());
+        bench(&format!("xor_bytes_bytewise/{}", len), len, || xor_bytes_bytewise(&b0, &b1));
+    }
+    let msg: Vec<u8> = (0..1 << 20).map(|i| i as u8).collect();
+    for key in [&b"ICE"[..], b"Terminator X: Bring the noise"].iter() {
+        bench(&format!("repeating/{}/{}", key.len(), msg.len()), msg.len(), || xor::repeating(key, &msg));
+        bench(&format!("repeating_bytewise/{}/{}", key.len(), msg.len()), msg.len(),
+              || repeating_bytewise(key, &msg));
+    }
+    bench(&format!("one_byte/{}", msg.len()), msg.len(), || xor::one_byte(0x5a, &msg));
+}
@@ -0,0 +1,95 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! Consequences of nonce reuse that all AEADs in this crate share.
+//! GCM, CCM and ChaCha20-Poly1305 all encrypt by XOR-ing with a
+//! keystream that depends only on the key and the nonce, so two
+//! messages sealed under the same nonce leak the XOR of their
+//! plaintexts, and a known plaintext reveals the other one.  What
+//! happens to the authenticity differs, see `attack::gcm` and
+//! `attack::poly1305`.
+//!
+//! AES-SIV (`cipher::siv`) is the contrast: its keystream is derived
+//! from the plaintext, so nonce reuse only shows whether two messages
+//! are equal.
+
+use crate::cipher::Aead;
+
+/// Recover as much of the plaintext of `sealed2` as possible, given
+/// the plaintext `known` of `sealed1`, when both were sealed by an
+/// `A` under the same key and nonce.  The result is as long as the
+/// shorter of the two messages.
+pub fn recover_plaintext<A: Aead>(known: &[u8], sealed1: &[u8], sealed2: &[u8]) -> Vec<u8> {
+    let len1 = sealed1.len().saturating_sub(A::TAG_SIZE);
+    let len2 = sealed2.len().saturating_sub(A::TAG_SIZE);
+    known.iter().zip(sealed1[..len1].iter()).zip(sealed2[..len2].iter())
+        .map(|((p, c1), c2)| p ^ c1 ^ c2)
+        .collect()
+}
+
+/// Demonstrate nonce reuse with `aead`: seal `known` and `secret`
+/// under the same `nonce`, and return what an eavesdropper who knows
+/// `known` learns about `secret`.
+pub fn nonce_reuse<A: Aead>(aead: &A, nonce: &[u8], known: &[u8], secret: &[u8]) -> Vec<u8> {
+    let sealed1 = aead.seal(nonce, b"", known);
+    let sealed2 = aead.seal(nonce, b"", secret);
+    recover_plaintext::<A>(known, &sealed1, &sealed2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::nonce_reuse;
+    use crate::cipher::Aead;
+    use crate::cipher::aes::AesKey;
+    use crate::cipher::ccm::Ccm;
+    use crate::cipher::chacha20poly1305::ChaCha20Poly1305;
+    use crate::cipher::gcm::Gcm;
+    use crate::cipher::siv::Siv;
+    use crate::error::Error;
+
+    fn check<A: Aead>(aead: &A) {
+        let nonce = vec![0x5a; A::NONCE_SIZE];
+        let sealed = aead.seal(&nonce, b"aad", b"attack at dawn");
+        assert_eq!(14 + A::TAG_SIZE, sealed.len());
+        assert_eq!(b"attack at dawn".to_vec(), aead.open(&nonce, b"aad", &sealed).unwrap());
+        match aead.open(&nonce, b"aaa", &sealed) {
+            Err(Error::InvalidTag) => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+        match aead.open(&nonce, b"aad", &sealed[..A::TAG_SIZE - 1]) {
+            Err(Error::InvalidTag) => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+
+        let known = b"This message is public knowledge.";
+        let secret = b"The password is swordfish.";
+        assert_eq!(secret.to_vec(), nonce_reuse(aead, &nonce, known, secret));
+    }
+
+    #[test]
+    fn all_aeads() {
+        check(&Gcm::new(AesKey::from([0x11; 16])));
+        check(&Ccm::new(AesKey::from([0x22; 16])));
+        check(&ChaCha20Poly1305::new([0x33; 32]));
+    }
+
+    #[test]
+    fn siv_nonce_reuse() {
+        let siv = Siv::new(&[0x44; 32]).unwrap();
+        let nonce = [0x5a; 12];
+        let known = b"This message is public knowledge.";
+        let secret = b"The password is swordfish.";
+        let recovered = nonce_reuse(&siv, &nonce, known, secret);
+        assert!(recovered != secret.to_vec());
+
+        assert_eq!(siv.seal(&nonce, b"", secret), siv.seal(&nonce, b"", secret));
+        assert!(siv.seal(&nonce, b"", secret) != siv.seal(&[0x5b; 12], b"", secret));
+        assert_eq!(secret.to_vec(), siv.open(&nonce, b"", &siv.seal(&nonce, b"", secret)).unwrap());
+    }
+
+    #[test]
+    #[should_panic]
+    fn wrong_nonce_size() {
+        Gcm::new(AesKey::from([0x11; 16])).seal(&[0; 8], b"", b"");
+    }
+}
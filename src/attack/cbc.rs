@@ -0,0 +1,342 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! The CBC padding oracle attack (challenge 17).  A server that
+//! reveals whether a CBC ciphertext decrypts to valid PKCS#7 padding
+//! lets an attacker decrypt any ciphertext: changing the block in
+//! front of a ciphertext block changes its plaintext by the same XOR
+//! difference, so the attacker can try values until the padding
+//! becomes valid and learn one plaintext byte at a time.
+//!
+//! Predictable IVs are the other classic CBC mistake.  TLS 1.0 used the
+//! last ciphertext block of one record as the IV of the next one, and
+//! some protocols use a fixed IV of zeros.  Either way an attacker who
+//! knows the next IV can choose a plaintext block that cancels it, so
+//! that the cipher sees exactly the input it saw for an earlier block,
+//! and thus test guesses for that block's plaintext.  `predictable_iv`
+//! and `zero_iv` use this to decrypt a secret the oracle appends to
+//! the attacker's input, one byte at a time.
+
+use crate::attack::Report;
+use crate::oracle::Oracle;
+use crate::padding::pkcs7;
+
+/// Block size of the cipher, in bytes.
+const BLOCK_SIZE: usize = 16;
+
+/// Recover the decryption of the ciphertext block `block` under the
+/// key of the oracle, before the XOR with the previous block.
+/// Returns `None` if no byte value gives valid padding at some
+/// position.
+fn decrypt_block<O>(block: &[u8], oracle: &mut O) -> Option<[u8; BLOCK_SIZE]>
+    where O: Oracle<[u8], bool>
+{
+    let mut intermediate = [0; BLOCK_SIZE];
+    // The forged IV, followed by the block.
+    let mut query = vec![0; 2 * BLOCK_SIZE];
+    query[BLOCK_SIZE..].copy_from_slice(block);
+    for pad in 1..BLOCK_SIZE + 1 {
+        let pos = BLOCK_SIZE - pad;
+        for i in pos + 1..BLOCK_SIZE {
+            query[i] = intermediate[i] ^ pad as u8;
+        }
+        let mut found = false;
+        for guess in 0..256 {
+            query[pos] = guess as u8;
+            if !oracle.query(&query) {
+                continue;
+            }
+            if pad == 1 {
+                // The padding may have ended in 0x02 0x02 or longer
+                // instead of 0x01.  Changing the second-to-last byte
+                // tells the cases apart.
+                query[pos - 1] ^= 1;
+                let valid = oracle.query(&query);
+                query[pos - 1] ^= 1;
+                if !valid {
+                    continue;
+                }
+            }
+            intermediate[pos] = guess as u8 ^ pad as u8;
+            found = true;
+            break;
+        }
+        if !found {
+            return None;
+        }
+    }
+    Some(intermediate)
+}
+
+/// Decrypt `ciphertext`, which was encrypted in CBC mode with `iv`,
+/// with the help of a padding oracle.  The oracle is queried with an
+/// IV followed by a ciphertext, and must answer whether the
+/// decryption has valid PKCS#7 padding.  The padding is removed from
+/// the result if it is valid.  The confidence of the report is the
+/// fraction of blocks that were decrypted.
+///
+/// # Panics
+/// Panics if `iv` is not one block long, or if `ciphertext` is not a
+/// whole number of blocks.
+pub fn padding_oracle<O>(iv: &[u8], ciphertext: &[u8], mut oracle: O) -> Report<Vec<u8>>
+    where O: Oracle<[u8], bool>
+{
+    assert_eq!(BLOCK_SIZE, iv.len(), "IV must be one block long");
+    assert_eq!(0, ciphertext.len() % BLOCK_SIZE, "ciphertext must be whole blocks");
+
+    let mut queries = 0;
+    let mut query = |q: &[u8]| {
+        queries += 1;
+        oracle.query(q)
+    };
+
+    let blocks = ciphertext.len() / BLOCK_SIZE;
+    let mut plaintext = Vec::with_capacity(ciphertext.len());
+    let mut previous = iv;
+    for block in ciphertext.chunks(BLOCK_SIZE) {
+        match decrypt_block(block, &mut query) {
+            Some(intermediate) => {
+                plaintext.extend(intermediate.iter().zip(previous.iter()).map(|(a, b)| a ^ b));
+            }
+            None => break,
+        }
+        previous = block;
+    }
+
+    let decrypted = plaintext.len() / BLOCK_SIZE;
+    let confidence = if blocks == 0 { 1.0 } else { decrypted as f64 / blocks as f64 };
+    let mut report = Report::new(plaintext, confidence);
+    if decrypted < blocks {
+        report.note(format!("stopped after {} of {} blocks", decrypted, blocks));
+    } else {
+        match pkcs7::unpad(&report.result, BLOCK_SIZE) {
+            Ok(unpadded) => report.result = unpadded,
+            Err(_) => report.note("plaintext has invalid padding"),
+        }
+    }
+    report.with_queries(queries)
+}
+
+/// Recover the secret that `oracle` appends to its input before
+/// encrypting in CBC mode, when `predict` tells the IV of the next
+/// query from the answer to the previous one.  The oracle answers
+/// with the IV followed by the ciphertext.
+fn decrypt_secret<O, P>(mut oracle: O, predict: P) -> Report<Vec<u8>>
+    where O: Oracle<[u8], Vec<u8>>,
+          P: Fn(&[u8]) -> [u8; BLOCK_SIZE]
+{
+    let mut queries = 0;
+    let mut query = |input: &[u8]| {
+        queries += 1;
+        oracle.query(input)
+    };
+
+    // The ciphertext grows by a block once the input fills up the
+    // padding of the secret.
+    let mut last = query(&[]);
+    let mut secret_len = None;
+    for pad_len in 1..BLOCK_SIZE + 1 {
+        let answer = query(&vec![b'A'; pad_len]);
+        if answer.len() > last.len() {
+            secret_len = Some(last.len() - BLOCK_SIZE - pad_len);
+        }
+        last = answer;
+        if secret_len.is_some() {
+            break;
+        }
+    }
+    let secret_len = match secret_len {
+        Some(len) => len,
+        None => {
+            let mut report = Report::new(Vec::new(), 0.0);
+            report.note("secret length not found");
+            return report.with_queries(queries);
+        }
+    };
+
+    let mut known = vec![b'A'; BLOCK_SIZE - 1];
+    let mut mispredicted = false;
+    'bytes: for i in 0..secret_len {
+        let pad_len = BLOCK_SIZE - 1 - i % BLOCK_SIZE;
+        let block = i / BLOCK_SIZE;
+        last = query(&known[..pad_len]);
+        // The block in front of the target is the IV for the first one.
+        let previous = last[block * BLOCK_SIZE..(block + 1) * BLOCK_SIZE].to_vec();
+        let target = last[(block + 1) * BLOCK_SIZE..(block + 2) * BLOCK_SIZE].to_vec();
+
+        let mut guess = known[known.len() - (BLOCK_SIZE - 1)..].to_vec();
+        guess.push(0);
+        for c in 0..256 {
+            let iv = predict(&last);
+            guess[BLOCK_SIZE - 1] = c as u8;
+            let input: Vec<u8> = (0..BLOCK_SIZE).map(|x| guess[x] ^ previous[x] ^ iv[x]).collect();
+            last = query(&input);
+            if last[..BLOCK_SIZE] != iv[..] {
+                mispredicted = true;
+                break 'bytes;
+            }
+            if last[BLOCK_SIZE..2 * BLOCK_SIZE] == target[..] {
+                known.push(c as u8);
+                continue 'bytes;
+            }
+        }
+        break;
+    }
+
+    let recovered = known.split_off(BLOCK_SIZE - 1);
+    let confidence = if secret_len == 0 { 1.0 } else { recovered.len() as f64 / secret_len as f64 };
+    let mut report = Report::new(recovered, confidence);
+    report.note(format!("secret length {}", secret_len));
+    if mispredicted {
+        report.note("the IV was not the predicted one");
+    }
+    if report.result.len() < secret_len {
+        report.note(format!("stopped after {} bytes", report.result.len()));
+    }
+    report.with_queries(queries)
+}
+
+/// Recover the secret that `oracle` appends to its input before
+/// encrypting in CBC mode, when the oracle uses the last ciphertext
+/// block of each answer as the IV of the next one, as TLS 1.0 did.
+/// The oracle answers with the IV followed by the ciphertext.  The
+/// confidence of the report is the fraction of the secret that was
+/// recovered.
+///
+/// Every guess for a byte takes a query, so the attack needs up to 257
+/// queries per byte.
+pub fn predictable_iv<O>(oracle: O) -> Report<Vec<u8>>
+    where O: Oracle<[u8], Vec<u8>>
+{
+    decrypt_secret(oracle, |answer| {
+        let mut iv = [0; BLOCK_SIZE];
+        iv.copy_from_slice(&answer[answer.len() - BLOCK_SIZE..]);
+        iv
+    })
+}
+
+/// Like `predictable_iv`, for an oracle that always uses an IV of
+/// zeros.  This is ECB mode for the first block.
+pub fn zero_iv<O>(oracle: O) -> Report<Vec<u8>>
+    where O: Oracle<[u8], Vec<u8>>
+{
+    decrypt_secret(oracle, |_| [0; BLOCK_SIZE])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{padding_oracle, predictable_iv, zero_iv};
+    use crate::cipher::aes::{self, AesKey};
+    use crate::oracle::{Counting, RateLimited};
+    use crate::padding::PaddingScheme;
+
+    #[test]
+    fn padding_oracle_0() {
+        let key = AesKey::from([0x5e; 16]);
+        let iv = [0x17; 16];
+        for plaintext in &[&b"MDAwMDAwTm93IHRoYXQgdGhlIHBhcnR5IGlzIGp1bXBpbmc="[..],
+                           &b"MDAwMDAx"[..], &b""[..], &b"0123456789abcdef"[..]] {
+            let ciphertext = aes::encrypt_cbc(&key, &iv, plaintext);
+            let report = padding_oracle(&iv, &ciphertext, |q: &[u8]| {
+                let mut v = [0; 16];
+                v.copy_from_slice(&q[..16]);
+                aes::decrypt_cbc_with(&key, &v, &q[16..], &PaddingScheme::Pkcs7).is_ok()
+            });
+            assert_eq!(plaintext.to_vec(), report.result);
+            assert_eq!(1.0, report.confidence);
+            // At most 256 guesses per byte, plus a few checks for the
+            // last byte of each block.
+            assert!(report.queries_used <= 257 * ciphertext.len());
+            assert!(report.notes.is_empty());
+        }
+    }
+
+    #[test]
+    fn useless_oracle() {
+        let report = padding_oracle(&[0; 16], &[0; 32], |_: &[u8]| false);
+        assert!(report.result.is_empty());
+        assert_eq!(0.0, report.confidence);
+        assert_eq!(256, report.queries_used);
+    }
+
+    #[test]
+    fn limited_oracle() {
+        let key = AesKey::from([0x5e; 16]);
+        let iv = [0x17; 16];
+        let ciphertext = aes::encrypt_cbc(&key, &iv, b"a secret that takes three blocks");
+        let oracle = |q: &[u8]| {
+            let mut v = [0; 16];
+            v.copy_from_slice(&q[..16]);
+            aes::decrypt_cbc_with(&key, &v, &q[16..], &PaddingScheme::Pkcs7).is_ok()
+        };
+
+        let mut counting = Counting::new(oracle);
+        let report = padding_oracle(&iv, &ciphertext, &mut counting);
+        assert_eq!(1.0, report.confidence);
+        assert_eq!(counting.queries(), report.queries_used);
+
+        // With a budget for about half of the queries, only part of
+        // the plaintext can be recovered.
+        let mut limited = RateLimited::new(oracle, report.queries_used / 2);
+        let partial = padding_oracle(&iv, &ciphertext, &mut limited);
+        assert!(limited.exhausted());
+        assert!(partial.confidence > 0.0 && partial.confidence < 1.0);
+        assert_eq!(&report.result[..partial.result.len()], &partial.result[..]);
+        assert_eq!(1, partial.notes.len());
+    }
+
+    const SECRET: &'static [u8] = b"session=8f3a1c; the IV must not be predictable";
+
+    /// Oracle that encrypts its input followed by `SECRET` and answers
+    /// with the IV and the ciphertext.  The IV of each query is
+    /// computed by `next_iv` from the previous ciphertext.
+    fn chained_oracle<F>(next_iv: F) -> impl FnMut(&[u8]) -> Vec<u8>
+        where F: Fn(&[u8]) -> [u8; 16]
+    {
+        let key = AesKey::from([0x2b; 16]);
+        let mut previous = vec![0x17; 16];
+        move |input: &[u8]| {
+            let iv = next_iv(&previous);
+            let mut data = input.to_vec();
+            data.extend_from_slice(SECRET);
+            previous = aes::encrypt_cbc(&key, &iv, &data);
+            let mut answer = iv.to_vec();
+            answer.extend_from_slice(&previous);
+            answer
+        }
+    }
+
+    fn last_block(c: &[u8]) -> [u8; 16] {
+        let mut iv = [0; 16];
+        iv.copy_from_slice(&c[c.len() - 16..]);
+        iv
+    }
+
+    #[test]
+    fn predictable_iv_0() {
+        let report = predictable_iv(chained_oracle(last_block));
+        assert_eq!(SECRET.to_vec(), report.result);
+        assert_eq!(1.0, report.confidence);
+        assert!(report.queries_used <= 257 * SECRET.len() + 17);
+        assert_eq!(vec![format!("secret length {}", SECRET.len())], report.notes);
+
+        let report = zero_iv(chained_oracle(|_| [0; 16]));
+        assert_eq!(SECRET.to_vec(), report.result);
+        assert_eq!(1.0, report.confidence);
+    }
+
+    #[test]
+    fn unpredictable_iv() {
+        // The IV is the encryption of the last block, which the
+        // attacker cannot compute without the key.
+        let key = AesKey::from([0x99; 16]);
+        let report = predictable_iv(chained_oracle(|c| last_block(&aes::encrypt_ecb(&key, &last_block(c)))));
+        assert!(report.result.is_empty());
+        assert_eq!(0.0, report.confidence);
+        assert_eq!(3, report.notes.len());
+
+        let report = zero_iv(chained_oracle(last_block));
+        assert!(report.result.is_empty());
+        assert_eq!("the IV was not the predicted one", report.notes[1]);
+    }
+}
@@ -0,0 +1,166 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! Compression ratio side channel (challenge 51, CRIME/BREACH).  When
+//! attacker-controlled data is compressed together with a secret
+//! before encryption, the length of the ciphertext tells how well the
+//! attacker's data matches the secret.
+
+use crate::cipher::aes::{self, AesKey, AesKey128};
+use crate::compress::Compressor;
+
+/// Text in front of the secret in each request, which is known to
+/// the attacker.
+pub const SECRET_PREFIX: &'static [u8] = b"sessionid=";
+
+/// Bytes used to shift the payload against block boundaries.  They
+/// do not occur in the request headers, so they never take part in
+/// back references.
+const JUNK: &'static [u8] = b"!@#$%^&*()[]{}<>~|;'`?";
+
+/// Cipher that the oracle encrypts with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode {
+    /// AES in CTR mode, where ciphertext and plaintext length match.
+    Ctr,
+    /// AES in CBC mode, where the ciphertext length is rounded up to
+    /// whole blocks.
+    Cbc,
+}
+
+/// Server that compresses, then encrypts HTTP requests carrying a
+/// secret session cookie, and reveals the length of the result.
+pub struct Oracle<C: Compressor> {
+    compressor: C,
+    mode: Mode,
+    secret: Vec<u8>,
+}
+
+impl<C: Compressor> Oracle<C> {
+    /// Create an oracle that hides `secret` in its requests.
+    pub fn new(compressor: C, mode: Mode, secret: &[u8]) -> Oracle<C> {
+        Oracle {
+            compressor: compressor,
+            mode: mode,
+            secret: secret.to_vec(),
+        }
+    }
+
+    /// Build the request with body `payload`.
+    pub fn format_request(&self, payload: &[u8]) -> Vec<u8> {
+        let mut request = Vec::new();
+        request.extend(b"POST / HTTP/1.1\nHost: hapless.com\nCookie: ".iter());
+        request.extend(SECRET_PREFIX);
+        request.extend(&self.secret);
+        request.extend(format!("\nContent-Length: {}\n", payload.len()).bytes());
+        request.extend(payload);
+        request
+    }
+
+    /// Compress and encrypt the request with body `payload` under a
+    /// fresh key and IV, and return the length of the ciphertext.
+    pub fn query(&self, payload: &[u8]) -> usize {
+        let compressed = self.compressor.compress(&self.format_request(payload));
+        let mut key = [0; 16];
+        let mut iv = [0; 16];
+        crate::random::fill_bytes(&mut key);
+        crate::random::fill_bytes(&mut iv);
+        let key = AesKey::Key128(AesKey128 { key: key });
+        let ciphertext = match self.mode {
+            Mode::Ctr => aes::encrypt_ctr(&key, &iv, &compressed),
+            Mode::Cbc => aes::encrypt_cbc(&key, &iv, &compressed),
+        };
+        ciphertext.len()
+    }
+}
+
+/// Recover the secret following `SECRET_PREFIX` in the requests of a
+/// compression `oracle`, which returns the ciphertext length for a
+/// given payload.  The secret is assumed to consist of bytes from
+/// `charset` only.
+///
+/// The secret is extended one byte at a time by sending the known
+/// part plus each candidate byte: the right candidate extends a back
+/// reference and compresses better than all others.  When the cipher
+/// pads to whole blocks, that difference is only visible if the
+/// compressed request ends right at a block boundary, so junk bytes
+/// are prepended until exactly one candidate stands out.  Recovery
+/// stops when no candidate does.
+pub fn recover_secret<O>(mut oracle: O, charset: &[u8]) -> Vec<u8>
+    where O: crate::oracle::Oracle<[u8], usize>
+{
+    let junk: Vec<u8> = JUNK.iter().cloned().filter(|b| !charset.contains(b)).collect();
+    let mut secret = Vec::new();
+    'extend: loop {
+        for pad_len in 0..junk.len() + 1 {
+            let mut payload = junk[..pad_len].to_vec();
+            payload.extend(SECRET_PREFIX);
+            payload.extend(&secret);
+            payload.push(0);
+            let last = payload.len() - 1;
+
+            let mut best = None;
+            let mut best_len = usize::MAX;
+            let mut unique = false;
+            for &c in charset {
+                payload[last] = c;
+                let len = oracle.query(&payload);
+                if len < best_len {
+                    best = Some(c);
+                    best_len = len;
+                    unique = true;
+                } else if len == best_len {
+                    unique = false;
+                }
+            }
+            if unique {
+                secret.extend(best);
+                continue 'extend;
+            }
+        }
+        return secret;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Oracle, Mode, recover_secret};
+    use crate::compress::{Compressor, Lzss};
+
+    static SECRET: &'static [u8] = b"TmV2ZXIgcmV2ZWFsIHRoZSBXdS1UYW5nIFNlY3JldCE=";
+    static BASE64: &'static [u8] =
+        b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/=";
+
+    #[test]
+    fn format_request() {
+        let oracle = Oracle::new(Lzss, Mode::Ctr, b"secret");
+        assert_eq!(&b"POST / HTTP/1.1\nHost: hapless.com\nCookie: sessionid=secret\n\
+                      Content-Length: 5\nhello"[..],
+                   &oracle.format_request(b"hello")[..]);
+    }
+
+    #[test]
+    fn query_ctr() {
+        let oracle = Oracle::new(Lzss, Mode::Ctr, SECRET);
+        let len = Lzss.compress(&oracle.format_request(b"hello")).len();
+        assert_eq!(len, oracle.query(b"hello"));
+    }
+
+    #[test]
+    fn query_cbc() {
+        let oracle = Oracle::new(Lzss, Mode::Cbc, SECRET);
+        assert_eq!(0, oracle.query(b"hello") % 16);
+    }
+
+    #[test]
+    fn recover_secret_ctr() {
+        let oracle = Oracle::new(Lzss, Mode::Ctr, SECRET);
+        assert_eq!(SECRET.to_vec(), recover_secret(|p: &[u8]| oracle.query(p), BASE64));
+    }
+
+    #[test]
+    fn recover_secret_cbc() {
+        let oracle = Oracle::new(Lzss, Mode::Cbc, SECRET);
+        assert_eq!(SECRET.to_vec(), recover_secret(|p: &[u8]| oracle.query(p), BASE64));
+    }
+}
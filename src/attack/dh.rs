@@ -0,0 +1,103 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! Key fixing on the Diffie-Hellman echo protocol (challenge 34).  A
+//! man in the middle replaces both public keys with `p`, so that both
+//! parties compute the shared secret `p^x mod p = 0`.  The attacker
+//! knows the key without having broken anything: running the known
+//! secret through the same key derivation as the parties, HKDF-SHA256,
+//! gives the session key, and every message can be read while the
+//! parties still understand each other.
+
+use crate::bignum::BigUint;
+use crate::protocol::channel::{Direction, MitM};
+use crate::protocol::dh::{self, Message};
+
+/// Man in the middle that fixes the shared secret to zero and
+/// decrypts the messages exchanged afterwards.
+pub struct KeyFixing {
+    modulus: Option<BigUint>,
+    messages: Vec<Vec<u8>>,
+}
+
+impl KeyFixing {
+    /// Create a new attacker.
+    pub fn new() -> KeyFixing {
+        KeyFixing {
+            modulus: None,
+            messages: Vec::new(),
+        }
+    }
+
+    /// Return the decrypted messages, in the order they were sent.
+    pub fn messages(&self) -> &[Vec<u8>] {
+        &self.messages
+    }
+}
+
+impl Default for KeyFixing {
+    fn default() -> KeyFixing {
+        KeyFixing::new()
+    }
+}
+
+impl MitM<Message> for KeyFixing {
+    fn intercept(&mut self, direction: Direction, message: Message) -> Vec<(Direction, Message)> {
+        let message = match message {
+            Message::Hello { p, g, .. } => {
+                self.modulus = Some(p.clone());
+                Message::Hello { p: p.clone(), g: g, public: p }
+            }
+            Message::Public(public) => {
+                Message::Public(self.modulus.clone().unwrap_or(public))
+            }
+            Message::Data(data) => {
+                if let Ok(plaintext) = dh::decrypt(&dh::derive_key(&BigUint::zero()), &data) {
+                    self.messages.push(plaintext);
+                }
+                Message::Data(data)
+            }
+        };
+        vec![(direction, message)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::KeyFixing;
+    use crate::protocol::{Encode, Stage, Transcript};
+    use crate::protocol::channel::{self, Direction};
+    use crate::protocol::dh::{Params, Message, Client, EchoBot};
+
+    #[test]
+    fn key_fixing() {
+        let mut client = Client::new(Params::nist(), b"Meet me at midnight.");
+        let mut attack = KeyFixing::new();
+        channel::run(&mut client, &mut EchoBot::new(), &mut attack).unwrap();
+        // The parties do not notice the attack.
+        assert_eq!(Some(&b"Meet me at midnight."[..]), client.echo());
+        assert_eq!(&[b"Meet me at midnight.".to_vec(), b"Meet me at midnight.".to_vec()],
+                   attack.messages());
+    }
+
+    #[test]
+    fn key_fixing_transcript() {
+        let params = Params::nist();
+        let mut client = Client::new(params.clone(), b"YELLOW SUBMARINE");
+        let mut transcript = Transcript::new();
+        channel::run_recorded(&mut client, &mut EchoBot::new(), &mut KeyFixing::new(), &mut transcript)
+            .unwrap();
+        assert_eq!(8, transcript.entries().len());
+        // Both parties were sent the modulus in place of the public
+        // keys.
+        let fixed = Message::Public(params.p.clone()).encode();
+        let sent = transcript.messages(Stage::Sent, Direction::ServerToClient);
+        let delivered = transcript.messages(Stage::Delivered, Direction::ServerToClient);
+        assert!(sent[0] != &fixed[..]);
+        assert_eq!(&fixed[..], delivered[0]);
+        let hello = transcript.messages(Stage::Delivered, Direction::ClientToServer)[0];
+        assert!(hello.ends_with(&params.p.to_bytes_be()));
+        // The ciphertexts are passed on unchanged.
+        assert_eq!(sent[1], delivered[1]);
+    }
+}
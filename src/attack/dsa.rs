@@ -0,0 +1,216 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! Attacks on DSA with weak nonces (challenges 43 and 44) and with
+//! maliciously chosen domain parameters (challenge 45).
+
+use crate::bignum::BigUint;
+use crate::dsa::{Params, PublicKey, PrivateKey, Signature};
+
+/// Calculate `(a - b) mod m`.
+fn sub_mod(a: &BigUint, b: &BigUint, m: &BigUint) -> BigUint {
+    let a = a % m;
+    let b = b % m;
+    if a >= b {
+        &a - &b
+    } else {
+        &(&a + m) - &b
+    }
+}
+
+/// Recover the private key from a signature `sig` on a message with
+/// hash `h`, given the nonce `k` that was used for signing:
+///
+/// ```text
+/// x = (s*k - H(m)) / r mod q
+/// ```
+///
+/// Returns `None` if `r` is not invertible modulo `q`.
+pub fn recover_key_from_k(params: &Params, h: &BigUint, sig: &Signature, k: &BigUint)
+                          -> Option<PrivateKey> {
+    let rinv = match sig.r.modinv(&params.q) {
+        Some(rinv) => rinv,
+        None => return None,
+    };
+    let sk = &(&sig.s * k) % &params.q;
+    let x = &(&sub_mod(&sk, h, &params.q) * &rinv) % &params.q;
+    Some(PrivateKey {
+        params: params.clone(),
+        x: x,
+    })
+}
+
+/// Check that `candidate` really is the private key belonging to `key`.
+fn matches(key: &PublicKey, candidate: Option<PrivateKey>) -> Option<PrivateKey> {
+    candidate.and_then(|private| if private.public_key().y == key.y {
+        Some(private)
+    } else {
+        None
+    })
+}
+
+/// Recover the private key for `key` from a single signature `sig`
+/// on a message with hash `h`, when the nonce is known to lie in the
+/// range `1..max_k + 1` (challenge 43).
+///
+/// Instead of calculating a candidate key for each nonce, the powers
+/// of `g` are walked one multiplication at a time until one matches
+/// `r`, so the expensive key recovery is only done for real candidates.
+pub fn brute_force_k(key: &PublicKey, h: &BigUint, sig: &Signature, max_k: u64)
+                     -> Option<PrivateKey> {
+    let params = &key.params;
+    let mut gk = &params.g % &params.p;
+    for k in 1..max_k + 1 {
+        if &gk % &params.q == sig.r {
+            let k = BigUint::from_u64(k);
+            if let Some(private) = matches(key, recover_key_from_k(params, h, sig, &k)) {
+                return Some(private);
+            }
+        }
+        gk = &(&gk * &params.g) % &params.p;
+    }
+    None
+}
+
+/// Recover the private key for `key` from a collection of message
+/// hashes and their signatures, when some of them have been made with
+/// the same nonce (challenge 44).  Such pairs share the same `r`, and
+/// the nonce follows from
+///
+/// ```text
+/// k = (H(m1) - H(m2)) / (s1 - s2) mod q
+/// ```
+pub fn repeated_nonce(key: &PublicKey, signed: &[(BigUint, Signature)]) -> Option<PrivateKey> {
+    let q = &key.params.q;
+    for (i, &(ref h1, ref sig1)) in signed.iter().enumerate() {
+        for &(ref h2, ref sig2) in &signed[i + 1..] {
+            if sig1.r != sig2.r || sig1.s == sig2.s {
+                continue;
+            }
+            let ds = match sub_mod(&sig1.s, &sig2.s, q).modinv(q) {
+                Some(ds) => ds,
+                None => continue,
+            };
+            let k = &(&sub_mod(h1, h2, q) * &ds) % q;
+            if let Some(private) = matches(key, recover_key_from_k(&key.params, h1, sig1, &k)) {
+                return Some(private);
+            }
+        }
+    }
+    None
+}
+
+/// Forge a signature that `dsa::verify_sloppy` accepts for any
+/// message and any public key when the generator is `g = 0`
+/// (challenge 45).  All powers of `g` are zero then, so `r = 0`
+/// always matches.
+pub fn magic_signature_g0() -> Signature {
+    Signature {
+        r: BigUint::zero(),
+        s: BigUint::one(),
+    }
+}
+
+/// Forge a signature that verifies for any message under `key` when
+/// the generator is `g = p + 1` (challenge 45).  Since `g = 1 mod p`,
+/// verification only checks `(y^(r/s) mod p) mod q = r`, which holds
+/// for
+///
+/// ```text
+/// r = (y^z mod p) mod q
+/// s = r / z mod q
+/// ```
+///
+/// with arbitrary `z`.  Returns `None` if `z` is not invertible
+/// modulo `q`, or if `r` turns out to be zero.
+pub fn magic_signature(key: &PublicKey, z: &BigUint) -> Option<Signature> {
+    let params = &key.params;
+    let zinv = match z.modinv(&params.q) {
+        Some(zinv) => zinv,
+        None => return None,
+    };
+    let r = &key.y.modpow(z, &params.p) % &params.q;
+    if r.is_zero() {
+        return None;
+    }
+    let s = &(&r * &zinv) % &params.q;
+    Some(Signature { r: r, s: s })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{recover_key_from_k, brute_force_k, repeated_nonce};
+    use super::{magic_signature, magic_signature_g0};
+    use crate::bignum::BigUint;
+    use crate::codec::hex;
+    use crate::dsa::{self, Params, PublicKey, Signature};
+    use crate::hash::Digest;
+    use crate::hash::sha1::Sha1;
+
+    #[test]
+    fn recover_key_from_k_0() {
+        let (_, private) = dsa::generate_keypair(&Params::challenge43());
+        let k = BigUint::from_u64(0xdeadbeef);
+        let sig = dsa::sign_with_k(&private, b"hello", &k).unwrap();
+        let h = dsa::hash_message(b"hello");
+        assert_eq!(Some(private.clone()),
+                   recover_key_from_k(&private.params, &h, &sig, &k));
+    }
+
+    #[test]
+    fn brute_force_k_challenge43() {
+        let key = PublicKey {
+            params: Params::challenge43(),
+            y: BigUint::from_hex(
+                "84ad4719d044495496a3201c8ff484feb45b962e7302e56a392aee4\
+                 abab3e4bdebf2955b4736012f21a08084056b19bcd7fee56048e004\
+                 e44984e2f411788efdc837a0d2e5abb7b555039fd243ac01f0fb2ed\
+                 1dec568280ce678e931868d23eb095fde9d3779191b8c0299d6e07b\
+                 bb283e6633451e535c45513b2d33c99ea17").unwrap(),
+        };
+        let sig = Signature {
+            r: BigUint::from_hex("60019cacdc56eedf8e080984bfa898c8c5c419a8").unwrap(),
+            s: BigUint::from_hex("961f2062efc3c68db965a90c924cf76580ec1bbc").unwrap(),
+        };
+        let h = BigUint::from_hex("d2d0714f014a9784047eaeccf956520045c45265").unwrap();
+        let private = brute_force_k(&key, &h, &sig, 1 << 16).unwrap();
+        assert_eq!("0954edd5e0afe5542a4adf012611a91912a3ec16",
+                   hex::encode(&Sha1::digest(private.x.to_hex().as_bytes())));
+    }
+
+    #[test]
+    fn repeated_nonce_0() {
+        let (public, private) = dsa::generate_keypair(&Params::challenge43());
+        let k = BigUint::from_u64(0x1234567);
+        let signed: Vec<_> = [&b"first"[..], b"second", b"third"].iter()
+            .enumerate()
+            .map(|(i, m)| if i == 1 {
+                (dsa::hash_message(m), dsa::sign(&private, m))
+            } else {
+                (dsa::hash_message(m), dsa::sign_with_k(&private, m, &k).unwrap())
+            })
+            .collect();
+        assert_eq!(Some(private), repeated_nonce(&public, &signed));
+        assert_eq!(None, repeated_nonce(&public, &signed[..2]));
+    }
+
+    #[test]
+    fn magic_signature_g0_0() {
+        let mut params = Params::challenge43();
+        params.g = BigUint::zero();
+        let (public, _) = dsa::generate_keypair(&params);
+        let sig = magic_signature_g0();
+        assert!(dsa::verify_sloppy(&public, b"Hello, world", &sig));
+        assert!(dsa::verify_sloppy(&public, b"Goodbye, world", &sig));
+        assert!(!dsa::verify(&public, b"Hello, world", &sig));
+    }
+
+    #[test]
+    fn magic_signature_g1() {
+        let (mut public, _) = dsa::generate_keypair(&Params::challenge43());
+        public.params.g = &public.params.p + &BigUint::one();
+        let sig = magic_signature(&public, &BigUint::from_u64(42)).unwrap();
+        assert!(dsa::verify(&public, b"Hello, world", &sig));
+        assert!(dsa::verify(&public, b"Goodbye, world", &sig));
+    }
+}
@@ -0,0 +1,322 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! Invalid-curve attack on ECDH (challenge 59).  The victim multiplies
+//! any point it receives by its private key without checking that the
+//! point is on the curve.  Points of small prime order on curves with
+//! a different `b` leak the private key modulo that order, and the
+//! residues are combined with the Chinese Remainder Theorem.
+//!
+//! With u-coordinates only (challenge 60) there are no invalid
+//! curves, but every u that is not on the curve is on its twist,
+//! which may have small subgroups as well.  This leaves the key known
+//! up to sign modulo the product of the small factors, and the rest is
+//! found with Pollard's kangaroo algorithm.
+
+use crate::bignum::BigUint;
+use crate::ec::{self, Curve, MontgomeryCurve, MontgomeryParams, Params, Point};
+use crate::hash::sha256::Sha256;
+use crate::mac::hmac::hmac;
+use crate::math::{crt, small_primes};
+use crate::math::dlog::kangaroo;
+use crate::oracle::Oracle;
+
+/// Upper bound for the prime factors of the invalid curves' orders
+/// that are used.  Each one costs that many point additions.
+const FACTOR_LIMIT: u64 = 1 << 16;
+
+/// Return a point of prime order `r` on a curve of order `order`.
+/// Multiplying by the cofactor leaves a point whose order is a power
+/// of `r`, which is then multiplied by `r` until one more step would
+/// give infinity.  Dividing by `r` only once is not enough, since the
+/// `r`-part of the group need not be cyclic.
+fn point_of_order(curve: &Curve, order: &BigUint, r: u64) -> Point {
+    let r = BigUint::from_u64(r);
+    let mut cofactor = order.clone();
+    while (&cofactor % &r).is_zero() {
+        cofactor = &cofactor / &r;
+    }
+    loop {
+        let mut h = curve.mul(&curve.random_point(), &cofactor);
+        if h == Point::Infinity {
+            continue;
+        }
+        loop {
+            let rh = curve.mul(&h, &r);
+            if rh == Point::Infinity {
+                return h;
+            }
+            h = rh;
+        }
+    }
+}
+
+/// Recover the victim's private key.  `invalid` lists the constant
+/// coefficients `b` of invalid curves together with their orders.
+/// `oracle` stands for the victim: it receives a public point,
+/// computes the shared secret and returns the HMAC-SHA256 of
+/// `message` under the derived session key.
+///
+/// Returns `None` if the small factors of the given orders are not
+/// enough to determine the key.
+pub fn invalid_curve<O>(params: &Params, invalid: &[(BigUint, BigUint)], message: &[u8],
+                        mut oracle: O) -> Option<BigUint>
+    where O: Oracle<Point, Vec<u8>>
+{
+    // Collect distinct small prime factors, smallest first, until
+    // their product exceeds the order of the base point.
+    let primes = small_primes(FACTOR_LIMIT);
+    let mut factors: Vec<(u64, Curve, &BigUint)> = Vec::new();
+    for &(ref b, ref order) in invalid {
+        for &r in &primes {
+            if (order % &BigUint::from_u64(r)).is_zero() && !factors.iter().any(|f| f.0 == r) {
+                factors.push((r, params.curve.with_b(b.clone()), order));
+            }
+        }
+    }
+    factors.sort_by_key(|f| f.0);
+    let mut modulus = BigUint::one();
+    let mut needed = 0;
+    while modulus <= params.q && needed < factors.len() {
+        modulus = &modulus * &BigUint::from_u64(factors[needed].0);
+        needed += 1;
+    }
+    if modulus <= params.q {
+        return None;
+    }
+
+    let mut congruences = Vec::new();
+    for &(r, ref curve, order) in &factors[..needed] {
+        let h = point_of_order(curve, order, r);
+        let tag = oracle.query(&h);
+        // The shared secret is k*h for the unknown k = x mod r.
+        let mut kh = Point::Infinity;
+        for k in 0..r {
+            if hmac::<Sha256>(&ec::session_key(curve, &kh), message) == tag {
+                congruences.push((BigUint::from_u64(k), BigUint::from_u64(r)));
+                break;
+            }
+            kh = curve.add(&kh, &h);
+        }
+    }
+    crt(&congruences).map(|x| &x % &params.q)
+}
+
+/// Return a random point on the twist of `curve` whose order is the
+/// product of `primes`, which must be distinct odd primes dividing
+/// the twist's order exactly once.
+fn twist_point_of_order(curve: &MontgomeryCurve, twist_order: &BigUint, primes: &[u64]) -> BigUint {
+    let order = primes.iter().fold(BigUint::one(), |acc, &r| &acc * &BigUint::from_u64(r));
+    let cofactor = twist_order / &order;
+    loop {
+        let u = BigUint::random_below(&curve.p);
+        if curve.contains_u(&u) {
+            continue;
+        }
+        let h = curve.ladder(&u, &cofactor);
+        if primes.iter().all(|&r| !curve.ladder(&h, &(&order / &BigUint::from_u64(r))).is_zero()) {
+            return h;
+        }
+    }
+}
+
+/// Return `k` in `0..r/2` such that the u-coordinate of `k*h`
+/// produces `tag`, where `h` has prime order `r`.  Walks through the
+/// multiples of `h` with differential additions.
+fn twist_residue(curve: &MontgomeryCurve, h: &BigUint, r: u64, message: &[u8], tag: &[u8])
+                 -> Option<u64> {
+    let mac = |u: &BigUint| hmac::<Sha256>(&ec::session_key_u(curve, u), message);
+    if mac(&BigUint::zero()) == tag {
+        return Some(0);
+    }
+    let mut prev = h.clone();
+    let mut cur = curve.ladder(h, &BigUint::from_u64(2));
+    if mac(h) == tag {
+        return Some(1);
+    }
+    for k in 2..r / 2 + 1 {
+        if mac(&cur) == tag {
+            return Some(k);
+        }
+        let next = curve.add_u(&cur, h, &prev);
+        prev = cur;
+        cur = next;
+    }
+    None
+}
+
+/// Learn the victim's private key modulo the product of the odd prime
+/// factors of `twist_order` below `limit`, using points on the twist.
+/// `oracle` receives a u-coordinate and returns the HMAC-SHA256 of
+/// `message` under the session key derived from the shared secret.
+///
+/// Since `k*h` and `-k*h` have the same u-coordinate, the result
+/// `(n, m)` only says that the key is `n` or `-n` modulo `m`.  When
+/// adding a factor, the two ways of combining it with the residue so
+/// far are told apart with one more query.
+pub fn twist_residues<O>(curve: &MontgomeryCurve, twist_order: &BigUint, limit: u64, message: &[u8],
+                         mut oracle: O) -> (BigUint, BigUint)
+    where O: Oracle<BigUint, Vec<u8>>
+{
+    let primes: Vec<u64> = small_primes(limit).into_iter()
+        .filter(|&r| r != 2 && (twist_order % &BigUint::from_u64(r)).is_zero())
+        .collect();
+    let mut n = BigUint::zero();
+    let mut modulus = BigUint::one();
+    let mut used = Vec::new();
+    for &r in &primes {
+        let h = twist_point_of_order(curve, twist_order, &[r]);
+        let k = match twist_residue(curve, &h, r, message, &oracle.query(&h)) {
+            Some(k) => k,
+            None => continue,
+        };
+        let r_big = BigUint::from_u64(r);
+        let c1 = crt(&[(n.clone(), modulus.clone()), (BigUint::from_u64(k), r_big.clone())]).unwrap();
+        let c2 = crt(&[(n.clone(), modulus.clone()), (BigUint::from_u64((r - k) % r), r_big.clone())])
+            .unwrap();
+        used.push(r);
+        n = if c1 == c2 || used.len() == 1 {
+            c1
+        } else {
+            let h = twist_point_of_order(curve, twist_order, &used);
+            let tag = oracle.query(&h);
+            if hmac::<Sha256>(&ec::session_key_u(curve, &curve.ladder(&h, &c1)), message) == tag {
+                c1
+            } else {
+                c2
+            }
+        };
+        modulus = &modulus * &r_big;
+    }
+    (n, modulus)
+}
+
+/// Recover the private key belonging to the public u-coordinate
+/// `public` under `params`.  After `twist_residues`, the remaining
+/// part of the key is found by running the kangaroo on the equivalent
+/// Weierstrass curve.
+///
+/// Keys `x` and `q - x` give the same public u-coordinate and shared
+/// secrets, so either one may be returned.
+pub fn twist_attack<O>(params: &MontgomeryParams, public: &BigUint, twist_order: &BigUint, limit: u64,
+                       message: &[u8], oracle: O) -> Option<BigUint>
+    where O: Oracle<BigUint, Vec<u8>>
+{
+    let (curve, q) = (&params.curve, &params.q);
+    let (n, modulus) = twist_residues(curve, twist_order, limit, message, oracle);
+    let w = curve.to_weierstrass();
+    let (g, y) = match (w.lift_x(&curve.to_weierstrass_x(&params.u)),
+                        w.lift_x(&curve.to_weierstrass_x(public))) {
+        (Some(g), Some(y)) => (g, y),
+        _ => return None,
+    };
+    // The lifted public point is x*g or -x*g, and x = n' + m*modulus
+    // with n' = n or -n, and m at most q/modulus, so one of the four
+    // combinations gives y' - n'*g = m * (modulus*g).
+    let g_m = w.mul(&g, &modulus);
+    let range = BigUint::zero()..&(q / &modulus) + &BigUint::one();
+    for y2 in [y.clone(), w.neg(&y)] {
+        for n2 in [n.clone(), &(&modulus - &n) % &modulus] {
+            let y3 = w.add(&y2, &w.neg(&w.mul(&g, &n2)));
+            if let Some(m) = kangaroo(&g_m, &y3, &range, &w) {
+                return Some(&(&n2 + &(&m * &modulus)) % q);
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{invalid_curve, twist_residues, twist_attack};
+    use crate::bignum::BigUint;
+    use crate::ec::{self, MontgomeryCurve, MontgomeryParams, Params, Point};
+    use crate::hash::sha256::Sha256;
+    use crate::mac::hmac::hmac;
+
+    static MESSAGE: &'static [u8] = b"crazy flamboyant for the rap enjoyment";
+
+    #[test]
+    fn invalid_curve_0() {
+        let params = Params::challenge59();
+        let (_, private) = ec::generate_keypair(&params);
+        let order = |s: &str| BigUint::from_hex(s).unwrap();
+        let invalid = vec![
+            (BigUint::from_u64(210), order("b005107c61647007f02418dd107d777c")),
+            (BigUint::from_u64(504), order("b005107c6164700796433ea23832592a")),
+            (BigUint::from_u64(727), order("b005107c61647007a4871c417e12913c")),
+        ];
+        let recovered = {
+            let curve = &params.curve;
+            let private = &private;
+            invalid_curve(&params, &invalid, MESSAGE, |h: &Point| {
+                let secret = ec::shared_secret(curve, private, h);
+                hmac::<Sha256>(&ec::session_key(curve, &secret), MESSAGE)
+            })
+        };
+        assert_eq!(Some(private), recovered);
+    }
+
+    fn twist_order() -> BigUint {
+        // 2^2 * 11 * 107 * 197 * 1621 * 105143 * 405373 * 2323367 * 1571528514013
+        BigUint::from_hex("b005107c61647007e1078fbad9fd1424").unwrap()
+    }
+
+    #[test]
+    fn twist_residues_0() {
+        let m = MontgomeryCurve::challenge60();
+        let (_, private) = ec::generate_keypair(&Params::challenge59());
+        let (n, modulus) = {
+            let (m, private) = (&m, &private);
+            twist_residues(m, &twist_order(), 2000, MESSAGE, |h: &BigUint| {
+                hmac::<Sha256>(&ec::session_key_u(m, &m.ladder(h, private)), MESSAGE)
+            })
+        };
+        assert_eq!(BigUint::from_u64(11 * 107 * 197 * 1621), modulus);
+        let residue = &private % &modulus;
+        assert!(residue == n || residue == &(&modulus - &n) % &modulus);
+    }
+
+    #[test]
+    fn twist_attack_small() {
+        // The curve has order 4 * 262133, and its twist has order
+        // 4 * 317 * 827, so with a limit of 400 the kangaroo searches
+        // an interval of width 827.
+        let params = MontgomeryParams {
+            curve: MontgomeryCurve {
+                p: BigUint::from_u64(1048583),
+                a: BigUint::from_u64(29),
+                b: BigUint::one(),
+            },
+            u: BigUint::from_u64(88709),
+            q: BigUint::from_u64(262133),
+        };
+        let twist_order = BigUint::from_u64(4 * 317 * 827);
+        for &k in &[1, 4242, 100003, 262132] {
+            let private = BigUint::from_u64(k);
+            let m = &params.curve;
+            let public = m.ladder(&params.u, &private);
+            let recovered = twist_attack(&params, &public, &twist_order, 400, MESSAGE, |h: &BigUint| {
+                hmac::<Sha256>(&ec::session_key_u(m, &m.ladder(h, &private)), MESSAGE)
+            }).unwrap();
+            assert!(recovered == private || recovered == &params.q - &private);
+        }
+    }
+
+    // This takes more than ten minutes even with optimization.
+    #[test]
+    #[ignore]
+    fn twist_attack_0() {
+        let params = MontgomeryParams::challenge60();
+        let m = &params.curve;
+        let (_, private) = ec::generate_keypair(&Params::challenge59());
+        let public = m.ladder(&params.u, &private);
+        let recovered = {
+            let private = &private;
+            twist_attack(&params, &public, &twist_order(), 1 << 22, MESSAGE, |h: &BigUint| {
+                hmac::<Sha256>(&ec::session_key_u(m, &m.ladder(h, private)), MESSAGE)
+            })
+        }.unwrap();
+        assert!(recovered == private || recovered == &params.q - &private);
+    }
+}
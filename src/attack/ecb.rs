@@ -0,0 +1,329 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! Byte-at-a-time ECB decryption (challenge 12).  An oracle that
+//! encrypts the attacker's input followed by a secret suffix under a
+//! fixed key in ECB mode gives the suffix away: by choosing the input
+//! length, each unknown byte can be moved to the end of a block whose
+//! other bytes are known, and that block can then be compared with
+//! the encryptions of all 256 possible blocks.
+//!
+//! ECB mode itself is detected from repeated ciphertext blocks (challenge
+//! 8), which `duplicate_block_score` measures for any block size.
+//! `build_codebook` generalizes the dictionary of the byte-at-a-time
+//! attack to any set of plaintext blocks over a restricted alphabet.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::cipher::aes;
+use crate::attack::Report;
+use crate::oracle::Oracle;
+
+/// Largest block size that `decrypt_suffix` looks for.
+const MAX_BLOCK_SIZE: usize = 64;
+
+/// Block sizes tried by `scan_block_sizes`.
+pub const SCAN_BLOCK_SIZES: ::std::ops::RangeInclusive<usize> = 8..=32;
+
+/// Return the fraction of the blocks of `data` that repeat an earlier
+/// block, when it is split into blocks of `block_size` bytes.  A
+/// trailing partial block is ignored.  Random data and the output of
+/// chained modes score close to 0, ECB ciphertexts of plaintexts with
+/// repeated blocks higher.
+///
+/// # Panics
+/// Panics if `block_size` is zero.
+pub fn duplicate_block_score(data: &[u8], block_size: usize) -> f64 {
+    assert!(block_size > 0, "block size must be positive");
+    let blocks = data.len() / block_size;
+    if blocks == 0 {
+        return 0.0;
+    }
+    let distinct: HashSet<&[u8]> = data.chunks(block_size).take(blocks).collect();
+    (blocks - distinct.len()) as f64 / blocks as f64
+}
+
+/// Compute `duplicate_block_score` of `data` for each block size in
+/// `SCAN_BLOCK_SIZES` that divides its length, as `(block_size,
+/// score)` pairs in order of the block size.
+pub fn scan_block_sizes(data: &[u8]) -> Vec<(usize, f64)> {
+    SCAN_BLOCK_SIZES
+        .filter(|bs| !data.is_empty() && data.len() % bs == 0)
+        .map(|bs| (bs, duplicate_block_score(data, bs)))
+        .collect()
+}
+
+/// Decide whether `data` is an ECB ciphertext without knowing the
+/// block size.  Returns the likely block size and its score, or
+/// `None` if no block size in `SCAN_BLOCK_SIZES` shows repeated
+/// blocks.
+///
+/// Splitting a repeated block in halves gives repeated halves, so
+/// divisors of the real block size score at least as well.  The
+/// result is therefore the largest block size scoring at least 90% of
+/// the best score.  If the plaintext repeats with a period of several
+/// blocks, multiples of the block size also score well and may be
+/// reported instead.
+pub fn detect_ecb_block_size(data: &[u8]) -> Option<(usize, f64)> {
+    let scores = scan_block_sizes(data);
+    let best = scores.iter().map(|s| s.1).fold(0.0, f64::max);
+    if best == 0.0 {
+        return None;
+    }
+    scores.into_iter().rev().find(|s| s.1 >= 0.9 * best)
+}
+
+/// Return the block size and the suffix length of an ECB `oracle`,
+/// found by growing the input until the ciphertext gets longer.
+/// Returns `None` if the ciphertext does not grow in steps.
+pub fn detect_block_size<O>(mut oracle: O) -> Option<(usize, usize)>
+    where O: Oracle<[u8], Vec<u8>>
+{
+    let len0 = oracle.query(b"").len();
+    for n in 1..MAX_BLOCK_SIZE + 1 {
+        let len = oracle.query(&vec![b'A'; n]).len();
+        if len > len0 {
+            return Some((len - len0, len0 - n));
+        }
+    }
+    None
+}
+
+/// Largest number of entries `build_codebook` enumerates.
+pub const MAX_CODEBOOK_SIZE: usize = 1 << 20;
+
+/// Number of blocks `build_codebook` encrypts with a single query.
+const CODEBOOK_BATCH: usize = 4096;
+
+/// Dictionary from ciphertext blocks to the plaintext blocks they
+/// encrypt, built with `build_codebook`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Codebook {
+    entries: HashMap<Vec<u8>, Vec<u8>>,
+}
+
+impl Codebook {
+    /// Return the plaintext block that encrypts to `block`, if known.
+    pub fn lookup(&self, block: &[u8]) -> Option<&[u8]> {
+        self.entries.get(block).map(|p| &p[..])
+    }
+
+    /// Number of known blocks.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Return true if no block is known.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Build a codebook for the ECB `oracle` by encrypting every block
+/// that matches `block_template`: positions holding `Some(b)` are
+/// fixed to `b`, and positions holding `None` range over `alphabet`.
+/// The template is as long as a block, and the oracle must place its
+/// input at the start of a block, as with an ECB oracle that only
+/// appends a suffix.  The blocks are sent in batches of up to 4096
+/// per query.
+///
+/// The confidence of the report is the fraction of distinct
+/// ciphertext blocks among those returned.  It is below 1 if the
+/// oracle is not ECB, or the input is not aligned.
+///
+/// # Panics
+/// Panics if the template is empty, or has so many open positions
+/// that there are more than `MAX_CODEBOOK_SIZE` blocks.
+pub fn build_codebook<O>(mut oracle: O, alphabet: &[u8], block_template: &[Option<u8>])
+                         -> Report<Codebook>
+    where O: Oracle<[u8], Vec<u8>>
+{
+    let bs = block_template.len();
+    assert!(bs > 0, "empty block template");
+    let holes: Vec<usize> = (0..bs).filter(|&i| block_template[i].is_none()).collect();
+    let total = (alphabet.len() as u64).checked_pow(holes.len() as u32)
+        .filter(|&n| n <= MAX_CODEBOOK_SIZE as u64)
+        .expect("codebook too large") as usize;
+
+    let mut block: Vec<u8> = block_template.iter().map(|b| b.unwrap_or(0)).collect();
+    let mut digits = vec![0; holes.len()];
+    let mut codebook = Codebook::default();
+    let mut queries = 0;
+    let mut done = 0;
+    while done < total {
+        let n = ::std::cmp::min(CODEBOOK_BATCH, total - done);
+        let mut plaintexts = Vec::with_capacity(n * bs);
+        for _ in 0..n {
+            for (&h, &d) in holes.iter().zip(digits.iter()) {
+                block[h] = alphabet[d];
+            }
+            plaintexts.extend_from_slice(&block);
+            for d in digits.iter_mut().rev() {
+                *d += 1;
+                if *d < alphabet.len() {
+                    break;
+                }
+                *d = 0;
+            }
+        }
+        let ciphertext = oracle.query(&plaintexts);
+        queries += 1;
+        for (p, c) in plaintexts.chunks(bs).zip(ciphertext.chunks(bs)) {
+            codebook.entries.insert(c.to_vec(), p.to_vec());
+        }
+        done += n;
+    }
+
+    let confidence = if total == 0 { 0.0 } else { codebook.len() as f64 / total as f64 };
+    let mut report = Report::new(codebook, confidence);
+    report.note(format!("{} blocks enumerated, {} distinct ciphertexts", total, report.result.len()));
+    report.with_queries(queries)
+}
+
+/// Recover the secret suffix that `oracle` appends to its input
+/// before encrypting in ECB mode.  The confidence of the report is
+/// the fraction of the suffix that was recovered.
+///
+/// All 256 candidates for the next byte are encrypted with a single
+/// query, so the attack needs about two queries per byte.
+pub fn decrypt_suffix<O>(mut oracle: O) -> Report<Vec<u8>>
+    where O: Oracle<[u8], Vec<u8>>
+{
+    let mut queries = 0;
+    let mut query = |input: &[u8]| {
+        queries += 1;
+        oracle.query(input)
+    };
+
+    let (bs, suffix_len) = match detect_block_size(&mut query) {
+        Some(sizes) => sizes,
+        None => {
+            let mut report = Report::new(Vec::new(), 0.0);
+            report.note("block size not found");
+            return report.with_queries(queries);
+        }
+    };
+    if !aes::detect_ecb(&query(&vec![b'A'; 3 * bs])) {
+        let mut report = Report::new(Vec::new(), 0.0);
+        report.note(format!("block size {}, but not ECB mode", bs));
+        return report.with_queries(queries);
+    }
+
+    let alphabet: Vec<u8> = (0..=255).collect();
+    let mut known = vec![b'A'; bs - 1];
+    for i in 0..suffix_len {
+        let pad_len = bs - 1 - i % bs;
+        let block = i / bs;
+        let target = query(&known[..pad_len])[block * bs..(block + 1) * bs].to_vec();
+
+        let mut template: Vec<Option<u8>> = known[known.len() - (bs - 1)..].iter().map(|&b| Some(b)).collect();
+        template.push(None);
+        let codebook = build_codebook(&mut query, &alphabet, &template).result;
+        match codebook.lookup(&target) {
+            Some(block) => known.push(block[bs - 1]),
+            None => break,
+        }
+    }
+
+    let recovered = known.split_off(bs - 1);
+    let confidence = if suffix_len == 0 { 1.0 } else { recovered.len() as f64 / suffix_len as f64 };
+    let mut report = Report::new(recovered, confidence);
+    report.note(format!("block size {}, suffix length {}", bs, suffix_len));
+    if report.result.len() < suffix_len {
+        report.note(format!("stopped after {} bytes", report.result.len()));
+    }
+    report.with_queries(queries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{detect_block_size, decrypt_suffix};
+    use super::{duplicate_block_score, scan_block_sizes, detect_ecb_block_size, build_codebook};
+    use crate::cipher::aes::{self, AesKey};
+
+    fn oracle(suffix: &'static [u8]) -> impl Fn(&[u8]) -> Vec<u8> {
+        let key = AesKey::from([0x3c; 16]);
+        move |input: &[u8]| {
+            let mut data = input.to_vec();
+            data.extend_from_slice(suffix);
+            aes::encrypt_ecb(&key, &data)
+        }
+    }
+
+    #[test]
+    fn block_size() {
+        assert_eq!(Some((16, 5)), detect_block_size(oracle(b"hello")));
+        assert_eq!(Some((16, 16)), detect_block_size(oracle(b"YELLOW SUBMARINE")));
+    }
+
+    #[test]
+    fn suffix() {
+        let secret = b"Rollin' in my 5.0\nWith my rag-top down so my hair can blow\n";
+        let report = decrypt_suffix(oracle(secret));
+        assert_eq!(secret.to_vec(), report.result);
+        assert_eq!(1.0, report.confidence);
+        // Six queries to find the block size, one to check for ECB,
+        // and two per byte.
+        assert_eq!(7 + 2 * secret.len(), report.queries_used);
+    }
+
+    #[test]
+    fn codebook() {
+        let key = AesKey::from([0x3c; 16]);
+        let mut template = vec![Some(b'x'); 16];
+        template[3] = None;
+        template[10] = None;
+        let report = build_codebook(oracle(b"suffix"), b"0123456789", &template);
+        assert_eq!(100, report.result.len());
+        assert_eq!(1.0, report.confidence);
+        assert_eq!(1, report.queries_used);
+        let c = aes::encrypt_ecb(&key, b"xxx4xxxxxx7xxxxx");
+        assert_eq!(Some(&b"xxx4xxxxxx7xxxxx"[..]), report.result.lookup(&c[..16]));
+        assert_eq!(None, report.result.lookup(&aes::encrypt_ecb(&key, b"xxxaxxxxxx7xxxxx")[..16]));
+
+        let mut template = vec![Some(0); 16];
+        template[0] = None;
+        template[15] = None;
+        let report = build_codebook(oracle(b""), &(0..=255).collect::<Vec<u8>>(), &template);
+        assert_eq!(65536, report.result.len());
+        assert_eq!(16, report.queries_used);
+
+        let cbc = |input: &[u8]| aes::encrypt_cbc(&key, &[0; 16], input);
+        let report = build_codebook(cbc, b"ab", &[Some(b'x'); 16]);
+        assert_eq!(1, report.result.len());
+    }
+
+    #[test]
+    fn duplicate_blocks() {
+        assert_eq!(0.0, duplicate_block_score(b"", 16));
+        assert_eq!(0.0, duplicate_block_score(b"YELLOW SUBMARINE", 16));
+        assert_eq!(0.5, duplicate_block_score(b"YELLOW SUBMARINEYELLOW SUBMARINE", 16));
+        assert_eq!(0.75, duplicate_block_score(b"aaaaaaaaaaaab", 3));
+        assert_eq!(vec![8, 10, 16, 20, 32], scan_block_sizes(&[0; 160]).iter().map(|s| s.0).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn detect_block_sizes() {
+        let plaintext = b"It was the best of times, it was the worst of times, it was the age of wisdom.                           It was the best of times, it was the worst of times, it was the age of wisdom. ";
+        let key = AesKey::from([0x3c; 16]);
+        let mut data = b"YELLOW SUBMARINE".to_vec();
+        data.extend_from_slice(&[0x42; 16]);
+        data.extend_from_slice(b"YELLOW SUBMARINE");
+        let ecb = aes::encrypt_ecb(&key, &data);
+        assert_eq!(Some((16, 0.25)), detect_ecb_block_size(&ecb));
+        assert_eq!(None, detect_ecb_block_size(&aes::encrypt_cbc(&key, &[0; 16], plaintext)));
+        assert_eq!(None, detect_ecb_block_size(b""));
+
+        // Blocks A B C A of a cipher with 8-byte blocks.
+        let eight = b"\x01\x02\x03\x04\x05\x06\x07\x08ABCDEFGHabcdefgh\x01\x02\x03\x04\x05\x06\x07\x08";
+        assert_eq!(Some((8, 0.25)), detect_ecb_block_size(eight));
+    }
+
+    #[test]
+    fn not_ecb() {
+        let key = AesKey::from([0x3c; 16]);
+        let report = decrypt_suffix(|input: &[u8]| aes::encrypt_cbc(&key, &[0; 16], input));
+        assert!(report.result.is_empty());
+        assert_eq!(0.0, report.confidence);
+    }
+}
@@ -0,0 +1,303 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! Attacks on GCM: recovering the authentication key from messages
+//! encrypted with a repeated nonce, or from forgery attempts against
+//! truncated tags, and forging tags once it is known.
+//!
+//! GCM also does not commit to its key: whoever chooses two keys can
+//! make a single ciphertext and tag that is valid under both, and
+//! decrypts to a different plaintext under each ("invisible
+//! salamanders").  This breaks message franking, where an abuse
+//! report is checked against the ciphertext the server saw, and
+//! anything else that assumes a ciphertext has only one plaintext.
+
+use crate::cipher::aes::AesKey;
+use crate::cipher::gcm;
+use crate::math::gf128::{Gf128, Poly};
+use crate::math::gf2_matrix::Matrix;
+use crate::oracle::Oracle;
+
+/// Return the polynomial whose root is the authentication key of the
+/// message with additional data `aad`, `ciphertext` and `tag`, apart
+/// from the unknown constant term `E(K, J0)`:
+///
+/// ```text
+/// X1*x^m + ... + Xm*x + T
+/// ```
+fn tag_poly(aad: &[u8], ciphertext: &[u8], tag: &[u8; 16]) -> Poly {
+    let mut coeffs = vec![Gf128::from_block(tag)];
+    coeffs.extend(gcm::ghash_blocks(aad, ciphertext).into_iter().rev());
+    Poly::new(coeffs)
+}
+
+/// Recover candidates for the authentication key `H` from messages
+/// that were authenticated under the same key and nonce.  Each
+/// message is given as additional data, ciphertext and tag.
+///
+/// With a repeated nonce, all tags are masked with the same value
+/// `E(K, J0)`, which cancels in the difference of the tag polynomials
+/// of two messages.  `H` is a root of all such differences, and thus
+/// of their greatest common divisor.  Two messages usually leave a few
+/// candidates, more messages narrow them down.
+///
+/// # Panics
+/// Panics if fewer than two messages are given.
+pub fn recover_auth_key(msgs: &[(Vec<u8>, Vec<u8>, [u8; 16])]) -> Vec<Gf128> {
+    assert!(msgs.len() >= 2, "need at least two messages");
+    let polys: Vec<_> = msgs.iter().map(|&(ref a, ref c, ref t)| tag_poly(a, c, t)).collect();
+    let mut g = Poly::zero();
+    for p in &polys[1..] {
+        g = g.gcd(&(&polys[0] - p));
+    }
+    g.roots()
+}
+
+/// Compute the tag for `aad` and `ciphertext`, given the
+/// authentication key `h` and one message `known` (additional data,
+/// ciphertext and tag) authenticated under the same nonce.
+pub fn forge_tag(h: Gf128, known: &(Vec<u8>, Vec<u8>, [u8; 16]), aad: &[u8], ciphertext: &[u8])
+                 -> [u8; 16] {
+    let &(ref known_aad, ref known_ciphertext, ref known_tag) = known;
+    let mask = Gf128::from_block(known_tag) - gcm::ghash(h, known_aad, known_ciphertext);
+    (gcm::ghash(h, aad, ciphertext) + mask).to_block()
+}
+
+/// Recover the authentication key from a GCM message with a tag
+/// truncated to `tag_len` bytes (challenge 64).  `ciphertext` must
+/// consist of whole blocks, and `oracle` tells whether a modified
+/// ciphertext is accepted together with the original tag, nonce and
+/// additional data.  Returns `None` if the oracle's answers are
+/// inconsistent.
+///
+/// The ciphertext blocks that are multiplied by `H^(2^i)` in GHASH
+/// are changed by `D_i`.  Since squaring is linear, this changes the
+/// tag by `Ad * H` for the matrix `Ad = sum(M(D_i) * S^i)`, where
+/// `M(D_i)` multiplies by `D_i` and `S` squares.  The `D_i` are chosen
+/// from the kernel of a dependency matrix, so that the first rows of
+/// `Ad`, restricted to the space of still possible keys, are zero.
+/// Each forgery that is accepted yields the remaining rows of the
+/// truncated tag as new linear equations on `H`, which shrink the
+/// space of possible keys, so that later forgeries can zero out more
+/// rows and are accepted more often, until one key is left.
+///
+/// # Panics
+/// Panics if the ciphertext is shorter than one block or does not
+/// consist of whole blocks, or if `tag_len` is not in `1..17`.
+pub fn truncated_mac<O>(ciphertext: &[u8], tag_len: usize, mut oracle: O) -> Option<Gf128>
+    where O: Oracle<[u8], bool>
+{
+    assert!(ciphertext.len() >= 16 && ciphertext.len() % 16 == 0,
+            "ciphertext must consist of whole blocks");
+    assert!(tag_len >= 1 && tag_len <= 16, "invalid tag length");
+    let blocks = ciphertext.len() / 16;
+    let tag_bits = tag_len * 8;
+    let mut n = 1;
+    while 1 << (n + 1) <= blocks + 1 {
+        n += 1;
+    }
+    // Block modified by D_i, for i from 1 to n.
+    let block_index = |i: usize| blocks + 1 - (1 << i);
+    let mut squares = vec![Matrix::identity(128)];
+    for i in 0..n {
+        squares.push(&squares[i] * &Matrix::square());
+    }
+
+    let mut equations = Matrix::zero(0, 128);
+    let mut keys = Matrix::identity(128);
+    loop {
+        let dim = keys.cols();
+        if dim == 0 {
+            return None;
+        } else if dim == 1 {
+            return Some(keys.column_gf128(0));
+        }
+
+        // Rows r of the dependency matrix belong to bit r / dim of the
+        // tag and key basis vector r % dim, columns to the bits of the
+        // D_i.
+        let zero_rows = ::std::cmp::min(tag_bits - 1, (n * 128 - 1) / dim);
+        let mut dependency = Matrix::zero(zero_rows * dim, n * 128);
+        let basis: Vec<Gf128> = (0..dim).map(|l| keys.column_gf128(l)).collect();
+        for (l, &v) in basis.iter().enumerate() {
+            let mut v_sq = v;
+            for i in 1..n + 1 {
+                v_sq = v_sq * v_sq;
+                for j in 0..128 {
+                    let w = Gf128(1 << j) * v_sq;
+                    for q in 0..zero_rows {
+                        if (w.0 >> q) & 1 != 0 {
+                            dependency.set(q * dim + l, (i - 1) * 128 + j, true);
+                        }
+                    }
+                }
+            }
+        }
+        let good = dependency.kernel().transpose();
+
+        loop {
+            let mut d = vec![0u64; 2 * n];
+            for r in 0..good.rows() {
+                if crate::random::gen() {
+                    for (x, y) in d.iter_mut().zip(good.row(r)) {
+                        *x ^= *y;
+                    }
+                }
+            }
+            if d.iter().all(|&x| x == 0) {
+                continue;
+            }
+            let ds: Vec<Gf128> = (0..n)
+                .map(|i| Gf128((d[2 * i + 1] as u128) << 64 | d[2 * i] as u128))
+                .collect();
+            let mut forged = ciphertext.to_vec();
+            for (i, di) in ds.iter().enumerate() {
+                let b = block_index(i + 1);
+                for (x, y) in forged[b * 16..b * 16 + 16].iter_mut().zip(di.to_block().iter()) {
+                    *x ^= *y;
+                }
+            }
+            if oracle.query(&forged) {
+                let mut ad = Matrix::zero(128, 128);
+                for (i, &di) in ds.iter().enumerate() {
+                    ad = &ad + &(&Matrix::mul_by(di) * &squares[i + 1]);
+                }
+                equations.append_rows(&ad.row_range(0, tag_bits));
+                keys = equations.kernel();
+                break;
+            }
+        }
+    }
+}
+
+/// Round `len` up to whole blocks.
+fn pad_len(len: usize) -> usize {
+    (len + 15) / 16 * 16
+}
+
+/// Make a ciphertext and tag that are valid under both `key1` with
+/// `nonce1` and `key2` with `nonce2`, together with `aad`.  The
+/// ciphertext consists of `plaintext1` encrypted under the first key,
+/// then `plaintext2` encrypted under the second key, each padded with
+/// zeros to whole blocks, and a final block that makes the tags equal.
+/// So under the first key it decrypts to `plaintext1` followed by
+/// garbage, and under the second key to garbage followed by
+/// `plaintext2`, starting at the first block boundary after
+/// `plaintext1`.  File formats that ignore leading or trailing data
+/// make both decryptions meaningful.
+///
+/// The last ciphertext block `X` enters GHASH multiplied by `H^2`, so
+/// the tags are equal if
+///
+/// ```text
+/// G1 + X*H1^2 + E(K1, J01) = G2 + X*H2^2 + E(K2, J02)
+/// ```
+///
+/// where `Gi` is GHASH of the ciphertext with `X = 0` under `Hi`.
+/// Since squaring is injective, this has a solution for `X` whenever
+/// the authentication keys differ.
+///
+/// # Panics
+/// Panics if both keys have the same authentication key, which means
+/// they are equal.
+pub fn make_ambiguous_ciphertext(key1: &AesKey, nonce1: &[u8; 12], plaintext1: &[u8],
+                                 key2: &AesKey, nonce2: &[u8; 12], plaintext2: &[u8],
+                                 aad: &[u8]) -> (Vec<u8>, [u8; 16]) {
+    let (h1, h2) = (gcm::auth_key(key1), gcm::auth_key(key2));
+    assert!(h1 != h2, "keys must be different");
+    let offset = pad_len(plaintext1.len());
+    let len = offset + pad_len(plaintext2.len()) + 16;
+
+    // Encrypting zeros gives the keystreams.
+    let (keystream1, _) = gcm::encrypt(key1, nonce1, b"", &vec![0; len]);
+    let (keystream2, _) = gcm::encrypt(key2, nonce2, b"", &vec![0; len]);
+    let mut ciphertext = keystream1[..offset].to_vec();
+    ciphertext.extend_from_slice(&keystream2[offset..len - 16]);
+    ciphertext.extend_from_slice(&[0; 16]);
+    for (c, p) in ciphertext.iter_mut().zip(plaintext1) {
+        *c ^= p;
+    }
+    for (c, p) in ciphertext[offset..].iter_mut().zip(plaintext2) {
+        *c ^= p;
+    }
+
+    let t1 = gcm::ghash(h1, aad, &ciphertext) + gcm::tag_mask(key1, nonce1);
+    let t2 = gcm::ghash(h2, aad, &ciphertext) + gcm::tag_mask(key2, nonce2);
+    let x = (t1 - t2) / (h1 * h1 - h2 * h2);
+    ciphertext[len - 16..].copy_from_slice(&x.to_block());
+    let tag = (t1 + x * h1 * h1).to_block();
+    (ciphertext, tag)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{recover_auth_key, forge_tag, truncated_mac, make_ambiguous_ciphertext};
+    use crate::cipher::Aead;
+    use crate::cipher::aes::{AesKey, AesKey128};
+    use crate::cipher::gcm::{self, Gcm};
+    use crate::xor;
+
+    fn random_key() -> AesKey {
+        let mut k = [0; 16];
+        crate::random::fill_bytes(&mut k);
+        AesKey::Key128(AesKey128 { key: k })
+    }
+
+    #[test]
+    fn nonce_reuse() {
+        let key = random_key();
+        let nonce = [7; 12];
+        let msgs: Vec<_> = [(&b"header"[..], &b"attack at dawn, bring snacks"[..]),
+                            (b"", b"retreat at dusk"),
+                            (b"more header data", b"the snacks are a lie, obviously")]
+            .iter()
+            .map(|&(aad, p)| {
+                let (c, t) = gcm::encrypt(&key, &nonce, aad, p);
+                (aad.to_vec(), c, t)
+            })
+            .collect();
+        let h = gcm::auth_key(&key);
+        assert!(recover_auth_key(&msgs[..2]).contains(&h));
+        let candidates = recover_auth_key(&msgs);
+        assert!(candidates.contains(&h));
+
+        let (ref aad, ref c, _) = msgs[0];
+        let delta = xor::xor_bytes(b"attack at dawn, bring snacks", b"attack at noon, bring snacks").unwrap();
+        let forged = xor::xor_bytes(c, &delta).unwrap();
+        let tag = forge_tag(h, &msgs[0], aad, &forged);
+        assert_eq!(b"attack at noon, bring snacks".to_vec(),
+                   gcm::decrypt(&key, &nonce, aad, &forged, &tag).unwrap());
+    }
+
+    #[test]
+    fn truncated_mac_16() {
+        let key = random_key();
+        let nonce = [42; 12];
+        let plaintext = vec![0x41; 256 * 16];
+        let (c, tag) = gcm::encrypt(&key, &nonce, b"", &plaintext);
+        let h = truncated_mac(&c, 2, |forged: &[u8]| gcm::decrypt(&key, &nonce, b"", forged, &tag[..2]).is_ok());
+        assert_eq!(Some(gcm::auth_key(&key)), h);
+    }
+
+    #[test]
+    fn ambiguous_ciphertext() {
+        let (key1, key2) = (random_key(), random_key());
+        let (nonce1, nonce2) = ([1; 12], [2; 12]);
+        let (c, tag) = make_ambiguous_ciphertext(&key1, &nonce1, b"meet me at noon",
+                                                 &key2, &nonce2, b"this message was never sent",
+                                                 b"header");
+        assert_eq!(16 + 32 + 16, c.len());
+        let p1 = gcm::decrypt(&key1, &nonce1, b"header", &c, &tag).unwrap();
+        let p2 = gcm::decrypt(&key2, &nonce2, b"header", &c, &tag).unwrap();
+        assert_eq!(b"meet me at noon\0", &p1[..16]);
+        assert_eq!(b"this message was never sent", &p2[16..43]);
+        assert!(gcm::decrypt(&key1, &nonce1, b"", &c, &tag).is_err());
+
+        // The same works with a single nonce, as with an `Aead`.
+        let (c, tag) = make_ambiguous_ciphertext(&key1, &nonce1, b"yes", &key2, &nonce1, b"no", b"");
+        let mut sealed = c;
+        sealed.extend_from_slice(&tag);
+        assert_eq!(b"yes", &Gcm::new(key1).open(&nonce1, b"", &sealed).unwrap()[..3]);
+        assert_eq!(b"no", &Gcm::new(key2).open(&nonce1, b"", &sealed).unwrap()[16..18]);
+    }
+}
@@ -0,0 +1,383 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! Attacks on iterated (Merkle-Damgard) hash functions with small
+//! states: multicollisions (challenge 52), second preimages via
+//! expandable messages (challenge 53) and herding with a diamond
+//! structure (challenge 54).  All of these work on `CheapHash`, since
+//! anything bigger is out of reach.
+
+use std::collections::HashMap;
+
+use crate::hash::cheap::{CheapHash, BLOCK_SIZE};
+
+/// Return a random message block.
+fn random_block() -> Vec<u8> {
+    let mut block = vec![0; BLOCK_SIZE];
+    crate::random::fill_bytes(&mut block);
+    block
+}
+
+/// Find two blocks `b1` and `b2` with `compress(h1, b1) ==
+/// compress(h2, b2)` by the birthday paradox.  Returns the blocks and
+/// the common chaining value.  If `h1 == h2`, the blocks differ.
+pub fn find_collision(hash: &CheapHash, h1: u32, h2: u32) -> (Vec<u8>, Vec<u8>, u32) {
+    let mut seen1: HashMap<u32, Vec<u8>> = HashMap::new();
+    let mut seen2: HashMap<u32, Vec<u8>> = HashMap::new();
+    loop {
+        let b1 = random_block();
+        let s1 = hash.compress(h1, &b1);
+        if let Some(b2) = seen2.get(&s1) {
+            if *b2 != b1 {
+                return (b1, b2.clone(), s1);
+            }
+        }
+        seen1.insert(s1, b1);
+
+        let b2 = random_block();
+        let s2 = hash.compress(h2, &b2);
+        if let Some(b1) = seen1.get(&s2) {
+            if *b1 != b2 {
+                return (b1.clone(), b2, s2);
+            }
+        }
+        seen2.insert(s2, b2);
+    }
+}
+
+/// A set of `2^n` messages of `n` blocks each which all lead to the
+/// same chaining value.  Block `i` of a message is chosen from the
+/// `i`-th colliding pair.
+#[derive(Clone, Debug)]
+pub struct Multicollision {
+    /// Colliding block pairs.
+    pub pairs: Vec<(Vec<u8>, Vec<u8>)>,
+    /// Chaining value reached after any of the messages.
+    pub state: u32,
+}
+
+impl Multicollision {
+    /// Build a multicollision of `2^n` messages, starting from the
+    /// chaining value `h`.  This takes only `n` times the work of a
+    /// single collision.
+    pub fn new(hash: &CheapHash, h: u32, n: usize) -> Multicollision {
+        let mut mc = Multicollision {
+            pairs: Vec::with_capacity(n),
+            state: h,
+        };
+        for _ in 0..n {
+            mc.extend(hash);
+        }
+        mc
+    }
+
+    /// Double the number of messages by appending another colliding
+    /// pair.
+    pub fn extend(&mut self, hash: &CheapHash) {
+        let (b1, b2, state) = find_collision(hash, self.state, self.state);
+        self.pairs.push((b1, b2));
+        self.state = state;
+    }
+
+    /// Return the number of colliding messages.
+    pub fn count(&self) -> u64 {
+        1 << self.pairs.len()
+    }
+
+    /// Return message number `i`, where bit `j` of `i` selects the
+    /// block from pair `j`.
+    pub fn message(&self, i: u64) -> Vec<u8> {
+        let mut msg = Vec::with_capacity(self.pairs.len() * BLOCK_SIZE);
+        for (j, &(ref b1, ref b2)) in self.pairs.iter().enumerate() {
+            msg.extend(if i & (1 << j) == 0 { b1 } else { b2 });
+        }
+        msg
+    }
+}
+
+/// Find two different messages that collide under both `cheap` and
+/// `expensive`, that is, under the concatenation of both hash
+/// functions (challenge 52).  A multicollision in `cheap` with
+/// `2^(b/2)` messages, where `b` is the state size of `expensive`,
+/// probably contains a collision for `expensive`; if not, it is
+/// extended until it does.
+pub fn cascade_collision(cheap: &CheapHash, expensive: &CheapHash) -> (Vec<u8>, Vec<u8>) {
+    let mut mc = Multicollision::new(cheap, cheap.initial_state(), expensive.bits() as usize / 2);
+    loop {
+        let mut seen = HashMap::new();
+        for i in 0..mc.count() {
+            let msg = mc.message(i);
+            let state = expensive.hash_blocks(expensive.initial_state(), &msg);
+            if let Some(&j) = seen.get(&state) {
+                return (mc.message(j), msg);
+            }
+            seen.insert(state, i);
+        }
+        mc.extend(cheap);
+    }
+}
+
+/// A set of messages of any length from `k` to `k + 2^k - 1` blocks,
+/// which all lead to the same chaining value (challenge 53).
+#[derive(Clone, Debug)]
+pub struct ExpandableMessage {
+    /// For `i` from `k - 1` down to 0, a pair of colliding pieces of
+    /// one and of `2^i + 1` blocks.
+    pub pieces: Vec<(Vec<u8>, Vec<u8>)>,
+    /// Chaining value reached after any of the messages.
+    pub state: u32,
+}
+
+impl ExpandableMessage {
+    /// Build an expandable message with parameter `k`, starting from
+    /// chaining value `h`.
+    pub fn new(hash: &CheapHash, h: u32, k: usize) -> ExpandableMessage {
+        let mut pieces = Vec::with_capacity(k);
+        let mut state = h;
+        for i in (0..k).rev() {
+            let mut dummy = Vec::with_capacity((1 << i) * BLOCK_SIZE);
+            for _ in 0..1 << i {
+                dummy.extend(random_block());
+            }
+            let dummy_state = hash.hash_blocks(state, &dummy);
+            let (short, last, next) = find_collision(hash, state, dummy_state);
+            dummy.extend(last);
+            pieces.push((short, dummy));
+            state = next;
+        }
+        ExpandableMessage {
+            pieces: pieces,
+            state: state,
+        }
+    }
+
+    /// Return the shortest and longest possible message length, in
+    /// blocks.
+    pub fn range(&self) -> (usize, usize) {
+        let k = self.pieces.len();
+        (k, k + (1 << k) - 1)
+    }
+
+    /// Return the message of `blocks` blocks, or `None` if `blocks`
+    /// is out of range.
+    pub fn message(&self, blocks: usize) -> Option<Vec<u8>> {
+        let (min, max) = self.range();
+        if blocks < min || blocks > max {
+            return None;
+        }
+        let extra = blocks - min;
+        let k = self.pieces.len();
+        let mut msg = Vec::with_capacity(blocks * BLOCK_SIZE);
+        for (j, &(ref short, ref long)) in self.pieces.iter().enumerate() {
+            let i = k - 1 - j;
+            msg.extend(if extra & (1 << i) == 0 { short } else { long });
+        }
+        Some(msg)
+    }
+}
+
+/// Find a second preimage for `message`, that is, a different message
+/// of the same length with the same hash (challenge 53).  This works
+/// for long messages, which have many intermediate chaining values to
+/// aim for.  Returns `None` if the message is too short.
+///
+/// An expandable message is built, and a bridge block is searched that
+/// leads from its final chaining value to one of the intermediate
+/// chaining values of `message`.  The expandable message is then cut
+/// to the length that makes up for the skipped blocks, so that even
+/// the length padding matches.
+pub fn second_preimage(hash: &CheapHash, message: &[u8]) -> Option<Vec<u8>> {
+    let blocks = message.len() / BLOCK_SIZE;
+    if blocks < 2 {
+        return None;
+    }
+    let mut k = 0;
+    while 1 << (k + 1) <= blocks {
+        k += 1;
+    }
+
+    let mut states = HashMap::new();
+    let mut h = hash.initial_state();
+    for (j, block) in message.chunks(BLOCK_SIZE).take(blocks).enumerate() {
+        h = hash.compress(h, block);
+        if j + 1 > k {
+            states.insert(h, j + 1);
+        }
+    }
+
+    let expandable = ExpandableMessage::new(hash, hash.initial_state(), k);
+    loop {
+        let bridge = random_block();
+        if let Some(&j) = states.get(&hash.compress(expandable.state, &bridge)) {
+            if let Some(mut forged) = expandable.message(j - 1) {
+                forged.extend(bridge);
+                forged.extend(&message[j * BLOCK_SIZE..]);
+                if forged != message {
+                    return Some(forged);
+                }
+            }
+        }
+    }
+}
+
+/// A binary tree of collisions that funnels `2^k` chaining values into
+/// a single one (challenge 54).  Once the final chaining value is
+/// published as a prediction, any prefix can be linked into one of
+/// the leaves and follows the tree to the predicted hash.
+#[derive(Clone, Debug)]
+pub struct Diamond {
+    /// Chaining values of each level, starting with the leaves.
+    pub states: Vec<Vec<u32>>,
+    /// For each level, the block leading from each chaining value to
+    /// the next level.
+    pub blocks: Vec<Vec<Vec<u8>>>,
+}
+
+impl Diamond {
+    /// Build a diamond structure with `2^k` random leaves.
+    pub fn new(hash: &CheapHash, k: usize) -> Diamond {
+        let mut leaves = Vec::with_capacity(1 << k);
+        while leaves.len() < 1 << k {
+            let leaf = hash.compress(hash.initial_state(), &random_block());
+            if !leaves.contains(&leaf) {
+                leaves.push(leaf);
+            }
+        }
+        let mut states = vec![leaves];
+        let mut blocks = Vec::with_capacity(k);
+        for _ in 0..k {
+            let mut next = Vec::new();
+            let mut level_blocks = Vec::new();
+            for pair in states[states.len() - 1].chunks(2) {
+                let (b1, b2, h) = find_collision(hash, pair[0], pair[1]);
+                level_blocks.push(b1);
+                level_blocks.push(b2);
+                next.push(h);
+            }
+            states.push(next);
+            blocks.push(level_blocks);
+        }
+        Diamond {
+            states: states,
+            blocks: blocks,
+        }
+    }
+
+    /// Return the depth of the tree.
+    pub fn k(&self) -> usize {
+        self.blocks.len()
+    }
+
+    /// Return the hash of any message forged for a prefix of
+    /// `prefix_blocks` blocks, which is the prediction to publish.
+    pub fn predict(&self, hash: &CheapHash, prefix_blocks: usize) -> u32 {
+        let root = self.states[self.k()][0];
+        hash.finish(root, (prefix_blocks + 1 + self.k()) * BLOCK_SIZE, b"")
+    }
+
+    /// Extend `prefix`, which must consist of whole blocks, by a glue
+    /// block and the path through the tree, so that it hashes to the
+    /// predicted value.
+    ///
+    /// # Panics
+    /// Panics if the length of `prefix` is not a multiple of the
+    /// block size.
+    pub fn forge(&self, hash: &CheapHash, prefix: &[u8]) -> Vec<u8> {
+        assert!(prefix.len() % BLOCK_SIZE == 0, "prefix must consist of whole blocks");
+        let h = hash.hash_blocks(hash.initial_state(), prefix);
+        let leaves: HashMap<u32, usize> =
+            self.states[0].iter().enumerate().map(|(i, &s)| (s, i)).collect();
+        let mut msg = prefix.to_vec();
+        let mut index = loop {
+            let glue = random_block();
+            if let Some(&i) = leaves.get(&hash.compress(h, &glue)) {
+                msg.extend(glue);
+                break i;
+            }
+        };
+        for level in &self.blocks {
+            msg.extend(&level[index]);
+            index /= 2;
+        }
+        msg
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_collision, cascade_collision, second_preimage};
+    use super::{Multicollision, ExpandableMessage, Diamond};
+    use crate::hash::cheap::{CheapHash, BLOCK_SIZE};
+
+    #[test]
+    fn find_collision_0() {
+        let hash = CheapHash::new(16);
+        let h = hash.initial_state();
+        let (b1, b2, s) = find_collision(&hash, h, h);
+        assert!(b1 != b2);
+        assert_eq!(s, hash.compress(h, &b1));
+        assert_eq!(s, hash.compress(h, &b2));
+    }
+
+    #[test]
+    fn multicollision() {
+        let hash = CheapHash::new(16);
+        let mc = Multicollision::new(&hash, hash.initial_state(), 4);
+        assert_eq!(16, mc.count());
+        let digest = hash.digest(&mc.message(0));
+        for i in 1..mc.count() {
+            assert!(mc.message(i) != mc.message(0));
+            assert_eq!(digest, hash.digest(&mc.message(i)));
+        }
+    }
+
+    #[test]
+    fn cascade_collision_0() {
+        let f = CheapHash::new(16);
+        let g = CheapHash::new(24);
+        let (m1, m2) = cascade_collision(&f, &g);
+        assert!(m1 != m2);
+        assert_eq!(f.digest(&m1), f.digest(&m2));
+        assert_eq!(g.digest(&m1), g.digest(&m2));
+    }
+
+    #[test]
+    fn expandable_message() {
+        let hash = CheapHash::new(16);
+        let em = ExpandableMessage::new(&hash, hash.initial_state(), 3);
+        assert_eq!((3, 10), em.range());
+        assert_eq!(None, em.message(2));
+        assert_eq!(None, em.message(11));
+        for blocks in 3..11 {
+            let msg = em.message(blocks).unwrap();
+            assert_eq!(blocks * BLOCK_SIZE, msg.len());
+            assert_eq!(em.state, hash.hash_blocks(hash.initial_state(), &msg));
+        }
+    }
+
+    #[test]
+    fn second_preimage_0() {
+        let hash = CheapHash::new(16);
+        let mut message = Vec::new();
+        for i in 0..256 * BLOCK_SIZE + 5 {
+            message.push((i * 7) as u8);
+        }
+        let forged = second_preimage(&hash, &message).unwrap();
+        assert!(forged != message);
+        assert_eq!(message.len(), forged.len());
+        assert_eq!(hash.digest(&message), hash.digest(&forged));
+        assert_eq!(None, second_preimage(&hash, b"short"));
+    }
+
+    #[test]
+    fn diamond() {
+        let hash = CheapHash::new(16);
+        let diamond = Diamond::new(&hash, 4);
+        assert_eq!(4, diamond.k());
+        let prediction = diamond.predict(&hash, 2);
+        let forged1 = diamond.forge(&hash, b"Red Sox 9, Yankees 0 ...........");
+        let forged2 = diamond.forge(&hash, b"Yankees 9, Red Sox 0 ...........");
+        assert_eq!(prediction, hash.digest(&forged1));
+        assert_eq!(prediction, hash.digest(&forged2));
+    }
+}
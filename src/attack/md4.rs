@@ -0,0 +1,203 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! Wang's differential collision attack on MD4 (challenge 55).
+//!
+//! Two messages `M` and `M'` differing in words 1, 2 and 12 by
+//!
+//! ```text
+//! m1' = m1 + 2^31, m2' = m2 + 2^31 - 2^28, m12' = m12 - 2^16
+//! ```
+//!
+//! collide with high probability if the intermediate values of the
+//! compression function fulfil a set of sufficient conditions.  Only
+//! the conditions of round 1 are enforced here, by single-step message
+//! modification, so a collision still takes many tries.  The remaining
+//! conditions can be added on top of `massage_round1`.
+
+use rand::Rng;
+
+use crate::hash::md4;
+
+/// A condition on one bit of a step value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Cond {
+    /// The bit is 0.
+    Zero,
+    /// The bit is 1.
+    One,
+    /// The bit equals the same bit of the previous step value.
+    Same,
+}
+
+use self::Cond::{Zero, One, Same};
+
+/// Sufficient conditions on the values of the 16 steps of round 1
+/// (`a1`, `d1`, `c1`, `b1`, `a2`, ...), as pairs of bit number
+/// (counting from 1, as in the paper) and condition.
+static ROUND1: [&'static [(u32, Cond)]; 16] = [
+    &[(7, Same)],
+    &[(7, Zero), (8, Same), (11, Same)],
+    &[(7, One), (8, One), (11, Zero), (26, Same)],
+    &[(7, One), (8, Zero), (11, Zero), (26, Zero)],
+    &[(8, One), (11, One), (26, Zero), (14, Same)],
+    &[(14, Zero), (19, Same), (20, Same), (21, Same), (22, Same), (26, One)],
+    &[(13, Same), (14, Zero), (15, Same), (19, Zero), (20, Zero), (21, One), (22, Zero)],
+    &[(13, One), (14, One), (15, Zero), (17, Same), (19, Zero), (20, Zero), (21, Zero),
+      (22, Zero)],
+    &[(13, One), (14, One), (15, One), (17, Zero), (19, Zero), (20, Zero), (21, Zero),
+      (23, Same), (22, One), (26, Same)],
+    &[(13, One), (14, One), (15, One), (17, Zero), (20, Zero), (21, One), (22, One),
+      (23, Zero), (26, One), (30, Same)],
+    &[(17, One), (20, Zero), (21, Zero), (22, Zero), (23, Zero), (26, Zero), (30, One),
+      (32, Same)],
+    &[(20, Zero), (21, One), (22, One), (23, Same), (26, One), (30, Zero), (32, Zero)],
+    &[(23, Zero), (26, Zero), (27, Same), (29, Same), (30, One), (32, Zero)],
+    &[(23, Zero), (26, Zero), (27, One), (29, One), (30, Zero), (32, One)],
+    &[(19, Same), (23, One), (26, One), (27, Zero), (29, Zero), (30, Zero)],
+    &[(19, Zero), (26, Same), (27, One), (29, One), (30, Zero)],
+];
+
+/// Adjust `value` so that it fulfils `conds`, given the value of the
+/// previous step `prev`.
+fn apply(value: u32, prev: u32, conds: &[(u32, Cond)]) -> u32 {
+    conds.iter().fold(value, |v, &(bit, cond)| {
+        let mask = 1 << (bit - 1);
+        match cond {
+            Zero => v & !mask,
+            One => v | mask,
+            Same => v ^ ((v ^ prev) & mask),
+        }
+    })
+}
+
+/// Check whether `value` fulfils `conds`, given the value of the
+/// previous step `prev`.
+fn holds(value: u32, prev: u32, conds: &[(u32, Cond)]) -> bool {
+    apply(value, prev, conds) == value
+}
+
+/// Return the step value preceding step `i` in round 1, where the
+/// steps before the first one are the registers `a`, `d`, `c`, `b` of
+/// the initial chaining value.
+fn previous(q: &[u32], i: usize) -> u32 {
+    let init = [md4::H0[0], md4::H0[3], md4::H0[2], md4::H0[1]];
+    if i == 0 { init[3] } else { q[i - 1] }
+}
+
+/// Modify the message words `x` so that all round 1 conditions hold
+/// when compressing from the MD4 initial value.  Each step value is
+/// corrected directly, and the message word used in that step is
+/// recomputed to produce the corrected value.
+pub fn massage_round1(x: &mut [u32; 16]) {
+    let init = [md4::H0[0], md4::H0[3], md4::H0[2], md4::H0[1]];
+    let mut q = [0u32; 16];
+    for i in 0..16 {
+        let back = |j: usize| if i >= j { q[i - j] } else { init[4 + i - j] };
+        let (a, b, c, d) = (back(4), back(1), back(2), back(3));
+        let s = md4::S1[i % 4];
+        let value = md4::r1(a, b, c, d, x[i], s);
+        let fixed = apply(value, previous(&q, i), ROUND1[i]);
+        x[i] = fixed.rotate_right(s).wrapping_sub(a).wrapping_sub(md4::f(b, c, d));
+        q[i] = fixed;
+    }
+}
+
+/// Check whether the message words `x` fulfil all round 1 conditions.
+pub fn round1_holds(x: &[u32; 16]) -> bool {
+    let q = md4::steps(&md4::H0, x);
+    (0..16).all(|i| holds(q[i], previous(&q, i), ROUND1[i]))
+}
+
+/// Apply the message difference of the attack to `x`.
+pub fn partner(x: &[u32; 16]) -> [u32; 16] {
+    let mut y = *x;
+    y[1] = y[1].wrapping_add(1 << 31);
+    y[2] = y[2].wrapping_add((1 << 31) - (1 << 28));
+    y[12] = y[12].wrapping_sub(1 << 16);
+    y
+}
+
+fn to_bytes(x: &[u32; 16]) -> Vec<u8> {
+    let mut block = Vec::with_capacity(64);
+    for w in x.iter() {
+        block.extend(&[*w as u8, (*w >> 8) as u8, (*w >> 16) as u8, (*w >> 24) as u8]);
+    }
+    block
+}
+
+/// Search for a pair of different one-block messages with the same
+/// MD4 hash, trying at most `tries` random messages.  Returns `None`
+/// if no collision was found.
+pub fn find_collision<R: Rng>(rng: &mut R, tries: u64) -> Option<(Vec<u8>, Vec<u8>)> {
+    let mut x = [0u32; 16];
+    for _ in 0..tries {
+        for w in x.iter_mut() {
+            *w = rng.next_u32();
+        }
+        massage_round1(&mut x);
+        let y = partner(&x);
+        let (m1, m2) = (to_bytes(&x), to_bytes(&y));
+        let mut h1 = md4::H0;
+        let mut h2 = md4::H0;
+        md4::compress(&mut h1, &m1);
+        md4::compress(&mut h2, &m2);
+        if h1 == h2 {
+            return Some((m1, m2));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{massage_round1, round1_holds, partner, find_collision};
+    use crate::codec::hex;
+    use crate::hash::Digest;
+    use crate::hash::md4::{self, Md4};
+
+    quickcheck! {
+        fn prop_massage_round1(xs: Vec<u32>) -> bool {
+            let mut x = [0u32; 16];
+            for (w, v) in x.iter_mut().zip(xs.iter()) {
+                *w = *v;
+            }
+            massage_round1(&mut x);
+            round1_holds(&x)
+        }
+    }
+
+    #[test]
+    fn partner_difference() {
+        let x = [0u32; 16];
+        let y = partner(&x);
+        assert_eq!(0x80000000, y[1]);
+        assert_eq!(0x70000000, y[2]);
+        assert_eq!(0xffff0000, y[12]);
+        assert_eq!(13, x.iter().zip(y.iter()).filter(|&(a, b)| a == b).count());
+    }
+
+    #[test]
+    fn known_collision() {
+        // Found with `find_collision`.
+        let m1 = hex::decode("f1eab4809b3c7cce7870bfb2107e78b337c360f1e31aa14e82f987e873fd9dac\
+                              bf7c0d2a3d2366bed215c6ba923307e60ffe9b9e7d42738b501d7ddf4c9ce81b")
+            .unwrap();
+        let m2 = hex::decode("f1eab4809b3c7c4e7870bf22107e78b337c360f1e31aa14e82f987e873fd9dac\
+                              bf7c0d2a3d2366bed215c6ba923307e60ffe9a9e7d42738b501d7ddf4c9ce81b")
+            .unwrap();
+        let x = md4::words(&m1);
+        assert!(round1_holds(&x));
+        assert_eq!(md4::words(&m2), partner(&x));
+        assert_eq!(Md4::digest(&m1), Md4::digest(&m2));
+    }
+
+    #[test]
+    fn find_collision_0() {
+        let mut rng = ::rand::thread_rng();
+        if let Some((m1, m2)) = find_collision(&mut rng, 1 << 12) {
+            assert!(m1 != m2);
+            assert_eq!(Md4::digest(&m1), Md4::digest(&m2));
+        }
+    }
+}
@@ -0,0 +1,138 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! Attacks on the ciphers and protocols implemented in this crate,
+//! as far as they are useful outside of a single challenge binary.
+
+use std::fmt;
+
+pub mod aead;
+pub mod cbc;
+pub mod compression;
+pub mod dh;
+pub mod dsa;
+pub mod ec;
+pub mod ecb;
+pub mod gcm;
+pub mod hash;
+pub mod md4;
+pub mod password;
+pub mod poly1305;
+pub mod record;
+pub mod rsa;
+pub mod srp;
+pub mod stream;
+pub mod two_time_pad;
+pub mod web;
+
+pub use self::stream::detect_keystream_reuse;
+
+/// Outcome of an attack: the recovered value together with how sure
+/// the attack is about it and how much oracle traffic it needed.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Report<T> {
+    /// The recovered value, for example a key or a plaintext.
+    pub result: T,
+    /// Confidence in the result, between 0 (a guess) and 1 (certain).
+    pub confidence: f64,
+    /// Number of queries sent to the oracle.  Attacks that work on
+    /// captured data only report zero.
+    pub queries_used: usize,
+    /// Observations made during the attack, for display.
+    pub notes: Vec<String>,
+}
+
+impl<T> Report<T> {
+    /// Create a report for `result` with the given `confidence`, no
+    /// queries and no notes.
+    pub fn new(result: T, confidence: f64) -> Report<T> {
+        Report {
+            result: result,
+            confidence: confidence,
+            queries_used: 0,
+            notes: Vec::new(),
+        }
+    }
+
+    /// Set the number of oracle queries to `queries`.
+    pub fn with_queries(mut self, queries: usize) -> Report<T> {
+        self.queries_used = queries;
+        self
+    }
+
+    /// Add the observation `note`.
+    pub fn note<S: Into<String>>(&mut self, note: S) {
+        self.notes.push(note.into());
+    }
+
+    /// Apply `f` to the result, keeping the rest of the report.
+    pub fn map<U, F: FnOnce(T) -> U>(self, f: F) -> Report<U> {
+        Report {
+            result: f(self.result),
+            confidence: self.confidence,
+            queries_used: self.queries_used,
+            notes: self.notes,
+        }
+    }
+}
+
+impl<T> fmt::Display for Report<T> {
+    /// Summarize the confidence, query count and notes, but not the
+    /// result itself, whose format depends on the attack.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "confidence {:.2}, {} queries", self.confidence, self.queries_used)?;
+        for note in &self.notes {
+            write!(f, "\n  {}", note)?;
+        }
+        Ok(())
+    }
+}
+
+/// Return a confidence for the best of a ranked list of candidates,
+/// from its score `best` and the score `second` of the runner-up:
+/// the margin between them, relative to the best score and clamped
+/// to `[0, 1]`.
+pub fn margin(best: f64, second: f64) -> f64 {
+    if best == 0.0 {
+        return 0.0;
+    }
+    let m = (best - second) / best.abs();
+    if m.is_nan() { 0.0 } else { m.clamp(0.0, 1.0) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Report, margin};
+
+    #[test]
+    fn report() {
+        let mut r = Report::new(3u8, 0.5);
+        r.queries_used = 12;
+        r.note("three");
+        assert_eq!("confidence 0.50, 12 queries\n  three", format!("{}", r));
+        let r = r.map(|x| x as usize * 2);
+        assert_eq!(6, r.result);
+        assert_eq!(12, r.queries_used);
+        assert_eq!(vec!["three".to_string()], r.notes);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn report_serde() {
+        let mut r = Report::new(vec![1u8, 2], 0.25).with_queries(3);
+        r.note("n");
+        let json = serde_json::to_string(&r).unwrap();
+        assert_eq!(r#"{"result":[1,2],"confidence":0.25,"queries_used":3,"notes":["n"]}"#, json);
+        assert_eq!(r, serde_json::from_str(&json).unwrap());
+    }
+
+    #[test]
+    fn margin_0() {
+        assert_eq!(0.5, margin(100.0, 50.0));
+        assert_eq!(0.5, margin(-20.0, -30.0));
+        assert_eq!(0.0, margin(10.0, 10.0));
+        assert_eq!(0.0, margin(0.0, -1.0));
+        assert_eq!(1.0, margin(10.0, -100.0));
+    }
+}
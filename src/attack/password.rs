@@ -0,0 +1,422 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! Dictionary and brute-force password cracking.  Candidates come
+//! from a wordlist, optionally run through mangling rules, and are
+//! checked against a `TargetHash`, which knows how to tell whether a
+//! candidate is the password that produced some captured value.  The
+//! MAC and KDF modules provide targets for their outputs, and the
+//! offline attack on simplified SRP in `attack::srp` is built on the
+//! same driver.
+
+use std::io::{self, BufRead};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+
+/// Something a password guess can be checked against, for example a
+/// password hash or a MAC computed with the password as key.
+pub trait TargetHash: Sync {
+    /// Return whether `candidate` is the password.
+    fn matches(&self, candidate: &[u8]) -> bool;
+}
+
+/// Iterator over the lines of a wordlist, without line terminators.
+/// Empty lines are skipped.  Reading stops at the first IO error,
+/// which can be retrieved with `error`.
+pub struct Wordlist<R> {
+    reader: R,
+    error: Option<io::Error>,
+}
+
+impl<R: BufRead> Wordlist<R> {
+    /// Read words from `reader`, one per line.
+    pub fn new(reader: R) -> Wordlist<R> {
+        Wordlist {
+            reader: reader,
+            error: None,
+        }
+    }
+
+    /// Return the IO error that ended the iteration, if any.
+    pub fn error(&self) -> Option<&io::Error> {
+        self.error.as_ref()
+    }
+}
+
+impl<R: BufRead> Iterator for Wordlist<R> {
+    type Item = Vec<u8>;
+
+    fn next(&mut self) -> Option<Vec<u8>> {
+        if self.error.is_some() {
+            return None;
+        }
+        loop {
+            let mut line = Vec::new();
+            match self.reader.read_until(b'\n', &mut line) {
+                Ok(0) => return None,
+                Ok(_) => {
+                    while line.last() == Some(&b'\n') || line.last() == Some(&b'\r') {
+                        line.pop();
+                    }
+                    if !line.is_empty() {
+                        return Some(line);
+                    }
+                }
+                Err(e) => {
+                    self.error = Some(e);
+                    return None;
+                }
+            }
+        }
+    }
+}
+
+/// Largest digit count of `Rule::DigitSuffix` that produces
+/// candidates, as `10^19` is the largest power of ten in a `u64`.
+pub const MAX_SUFFIX_DIGITS: u32 = 19;
+
+/// Largest number of letters `Rule::ToggleCase` toggles.
+pub const MAX_TOGGLE_LETTERS: usize = 63;
+
+/// Mangling rules, each of which turns a word into one or more
+/// candidates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rule {
+    /// The word itself.
+    Identity,
+    /// The word with its first letter in upper case.
+    Capitalize,
+    /// The word with all letters in upper case.
+    Uppercase,
+    /// Every combination of upper and lower case letters.  This
+    /// produces `2^n` candidates for a word with `n` letters, and
+    /// none for words with more than `MAX_TOGGLE_LETTERS` letters.
+    ToggleCase,
+    /// The word followed by every number with the given count of
+    /// digits, including leading zeros.  Produces no candidates for
+    /// more than `MAX_SUFFIX_DIGITS` digits.
+    DigitSuffix(u32),
+}
+
+impl Rule {
+    /// Return the candidates this rule produces for `word`.  They are
+    /// generated one at a time, so rules with many candidates per word
+    /// need no memory for them.
+    pub fn apply(&self, word: &[u8]) -> Candidates {
+        let letters: Vec<usize> = match *self {
+            Rule::ToggleCase => (0..word.len()).filter(|&i| word[i].is_ascii_alphabetic()).collect(),
+            _ => Vec::new(),
+        };
+        let count = match *self {
+            Rule::ToggleCase if letters.len() > MAX_TOGGLE_LETTERS => 0,
+            Rule::ToggleCase => 1 << letters.len(),
+            Rule::DigitSuffix(digits) => 10u64.checked_pow(digits).unwrap_or(0),
+            _ => 1,
+        };
+        Candidates {
+            rule: *self,
+            word: word.to_vec(),
+            letters: letters,
+            next: 0,
+            count: count,
+        }
+    }
+
+    /// Return true if `candidate` is one of the candidates this rule
+    /// produces for `word`, without generating them.
+    pub fn produces(&self, word: &[u8], candidate: &[u8]) -> bool {
+        match *self {
+            Rule::Identity => candidate == word,
+            Rule::Capitalize => match (word.split_first(), candidate.split_first()) {
+                (Some((w, word_rest)), Some((c, rest))) => *c == w.to_ascii_uppercase() && rest == word_rest,
+                (None, None) => true,
+                _ => false,
+            },
+            Rule::Uppercase => candidate == &word.to_ascii_uppercase()[..],
+            Rule::ToggleCase => {
+                candidate.len() == word.len()
+                    && word.iter().filter(|b| b.is_ascii_alphabetic()).count() <= MAX_TOGGLE_LETTERS
+                    && word.iter().zip(candidate).all(|(&w, &c)| {
+                        if w.is_ascii_alphabetic() { w.eq_ignore_ascii_case(&c) } else { w == c }
+                    })
+            }
+            Rule::DigitSuffix(digits) => {
+                digits <= MAX_SUFFIX_DIGITS
+                    && candidate.len() == word.len() + digits as usize
+                    && candidate.starts_with(word)
+                    && candidate[word.len()..].iter().all(|c| c.is_ascii_digit())
+            }
+        }
+    }
+}
+
+/// Iterator returned by `Rule::apply`.
+#[derive(Debug, Clone)]
+pub struct Candidates {
+    rule: Rule,
+    word: Vec<u8>,
+    // Positions of the letters, for `Rule::ToggleCase`.
+    letters: Vec<usize>,
+    next: u64,
+    count: u64,
+}
+
+impl Iterator for Candidates {
+    type Item = Vec<u8>;
+
+    fn next(&mut self) -> Option<Vec<u8>> {
+        if self.next >= self.count {
+            return None;
+        }
+        let n = self.next;
+        self.next += 1;
+        let mut w = self.word.clone();
+        match self.rule {
+            Rule::Identity => (),
+            Rule::Capitalize => {
+                if let Some(c) = w.first_mut() {
+                    *c = c.to_ascii_uppercase();
+                }
+            }
+            Rule::Uppercase => w.make_ascii_uppercase(),
+            Rule::ToggleCase => {
+                // Bit i of the index selects upper case for letter i.
+                w.make_ascii_lowercase();
+                for (bit, &pos) in self.letters.iter().enumerate() {
+                    if n >> bit & 1 != 0 {
+                        w[pos] = w[pos].to_ascii_uppercase();
+                    }
+                }
+            }
+            Rule::DigitSuffix(0) => (),
+            Rule::DigitSuffix(digits) => w.extend(format!("{:01$}", n, digits as usize).bytes()),
+        }
+        Some(w)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.count - self.next;
+        if remaining > usize::MAX as u64 {
+            (usize::MAX, None)
+        } else {
+            (remaining as usize, Some(remaining as usize))
+        }
+    }
+}
+
+/// Iterator returned by `mangle`.
+pub struct Mangle<I> {
+    words: I,
+    rules: Vec<Rule>,
+    // The current word, the index of the rule being applied to it and
+    // its remaining candidates.
+    current: Option<(Vec<u8>, usize, Candidates)>,
+}
+
+impl<I> Iterator for Mangle<I>
+    where I: Iterator, I::Item: AsRef<[u8]>
+{
+    type Item = Vec<u8>;
+
+    fn next(&mut self) -> Option<Vec<u8>> {
+        if self.rules.is_empty() {
+            return None;
+        }
+        loop {
+            if let Some((ref word, ref mut rule, ref mut candidates)) = self.current {
+                match candidates.next() {
+                    Some(c) => {
+                        // Skip candidates that an earlier rule produced.
+                        if !self.rules[..*rule].iter().any(|r| r.produces(word, &c)) {
+                            return Some(c);
+                        }
+                        continue;
+                    }
+                    None if *rule + 1 < self.rules.len() => {
+                        *rule += 1;
+                        *candidates = self.rules[*rule].apply(word);
+                        continue;
+                    }
+                    None => (),
+                }
+            }
+            let word = self.words.next()?.as_ref().to_vec();
+            let candidates = self.rules[0].apply(&word);
+            self.current = Some((word, 0, candidates));
+        }
+    }
+}
+
+/// Apply every rule in `rules` to every word in `words`.  The
+/// candidates for a word are produced in the order of the rules, with
+/// duplicates removed.  They are generated as they are consumed, so
+/// that rules with many candidates per word can be streamed into
+/// `crack` or `crack_parallel`.
+pub fn mangle<I>(words: I, rules: &[Rule]) -> Mangle<I::IntoIter>
+    where I: IntoIterator, I::Item: AsRef<[u8]>
+{
+    Mangle {
+        words: words.into_iter(),
+        rules: rules.to_vec(),
+        current: None,
+    }
+}
+
+/// Try every candidate against `target` in order.  Returns the first
+/// candidate that matches, or `None`.
+pub fn crack<T, I>(target: &T, candidates: I) -> Option<Vec<u8>>
+    where T: TargetHash + ?Sized, I: IntoIterator, I::Item: AsRef<[u8]>
+{
+    for candidate in candidates {
+        if target.matches(candidate.as_ref()) {
+            return Some(candidate.as_ref().to_vec());
+        }
+    }
+    None
+}
+
+/// Number of candidates a worker takes from the shared iterator at a
+/// time.
+const BATCH_SIZE: usize = 64;
+
+/// Try the candidates against `target` on `threads` threads.  Returns
+/// a matching candidate, or `None`.  If several candidates match, it
+/// is not specified which one is returned.
+///
+/// # Panics
+/// Panics if `threads` is zero.
+pub fn crack_parallel<T, I>(target: &T, candidates: I, threads: usize) -> Option<Vec<u8>>
+    where T: TargetHash + ?Sized, I: IntoIterator, I::IntoIter: Send, I::Item: AsRef<[u8]>
+{
+    assert!(threads > 0, "thread count must be positive");
+    let candidates = Mutex::new(candidates.into_iter());
+    let found = AtomicBool::new(false);
+    let result = Mutex::new(None);
+    thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| {
+                while !found.load(Ordering::Relaxed) {
+                    let batch: Vec<Vec<u8>> = {
+                        let mut it = candidates.lock().unwrap();
+                        it.by_ref().take(BATCH_SIZE).map(|c| c.as_ref().to_vec()).collect()
+                    };
+                    if batch.is_empty() {
+                        return;
+                    }
+                    if let Some(password) = crack(target, batch) {
+                        found.store(true, Ordering::Relaxed);
+                        *result.lock().unwrap() = Some(password);
+                        return;
+                    }
+                }
+            });
+        }
+    });
+    result.into_inner().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TargetHash, Wordlist, Rule, mangle, crack, crack_parallel};
+    use crate::hash::sha1::Sha1;
+    use crate::kdf::Pbkdf2Target;
+    use crate::mac::hmac::{hmac, HmacTarget};
+
+    struct Plain(&'static [u8]);
+
+    impl TargetHash for Plain {
+        fn matches(&self, candidate: &[u8]) -> bool {
+            self.0 == candidate
+        }
+    }
+
+    #[test]
+    fn wordlist() {
+        let words: Vec<Vec<u8>> = Wordlist::new(&b"password\r\n\n123456\nqwerty"[..]).collect();
+        assert_eq!(vec![b"password".to_vec(), b"123456".to_vec(), b"qwerty".to_vec()], words);
+    }
+
+    #[test]
+    fn rules() {
+        let all = |rule: Rule, word: &[u8]| rule.apply(word).collect::<Vec<Vec<u8>>>();
+        assert_eq!(vec![b"Dragon".to_vec()], all(Rule::Capitalize, b"dragon"));
+        assert_eq!(vec![b"DRAGON".to_vec()], all(Rule::Uppercase, b"dragon"));
+        assert_eq!(vec![b"a1b".to_vec(), b"A1b".to_vec(), b"a1B".to_vec(), b"A1B".to_vec()],
+                   all(Rule::ToggleCase, b"a1B"));
+        let suffixed = all(Rule::DigitSuffix(2), b"x");
+        assert_eq!(100, suffixed.len());
+        assert_eq!(b"x07".to_vec(), suffixed[7]);
+        assert_eq!(vec![b"x".to_vec()], all(Rule::DigitSuffix(0), b"x"));
+
+        for rule in &[Rule::Identity, Rule::Capitalize, Rule::Uppercase, Rule::ToggleCase,
+                      Rule::DigitSuffix(0), Rule::DigitSuffix(2)] {
+            for candidate in rule.apply(b"a1B") {
+                assert!(rule.produces(b"a1B", &candidate));
+            }
+        }
+        assert!(!Rule::Capitalize.produces(b"a1B", b"a1B"));
+        assert!(!Rule::ToggleCase.produces(b"a1B", b"a2b"));
+        assert!(!Rule::DigitSuffix(2).produces(b"x", b"x1a"));
+    }
+
+    #[test]
+    fn huge_rules() {
+        // 10^20 does not fit into a u64, and 2^64 candidates not into
+        // the index.
+        assert_eq!(0, Rule::DigitSuffix(20).apply(b"x").count());
+        assert!(!Rule::DigitSuffix(20).produces(b"x", b"x00000000000000000000"));
+        let mut suffixed = Rule::DigitSuffix(19).apply(b"x");
+        assert_eq!(Some(b"x0000000000000000000".to_vec()), suffixed.next());
+        assert_eq!(0, Rule::ToggleCase.apply(&[b'a'; 64]).count());
+
+        // Candidates are streamed, not collected per word.
+        let mut toggled = Rule::ToggleCase.apply(&[b'a'; 40]);
+        assert_eq!(Some(1 << 40), toggled.size_hint().1);
+        assert_eq!(Some(vec![b'a'; 40]), toggled.next());
+        let rules = [Rule::Identity, Rule::ToggleCase, Rule::DigitSuffix(18)];
+        let candidates: Vec<Vec<u8>> = mangle(&["abcdefghijklmnopqrstuvwxyzabcdefghijklmn"], &rules)
+            .take(3).collect();
+        assert_eq!(b"Abcdefghijklmnopqrstuvwxyzabcdefghijklmn".to_vec(), candidates[1]);
+    }
+
+    #[test]
+    fn mangle_0() {
+        let rules = [Rule::Identity, Rule::Capitalize, Rule::DigitSuffix(1)];
+        let candidates: Vec<Vec<u8>> = mangle(&["ab", "7"], &rules).collect();
+        assert_eq!(12 + 11, candidates.len());
+        assert_eq!(b"ab".to_vec(), candidates[0]);
+        assert_eq!(b"Ab".to_vec(), candidates[1]);
+        assert_eq!(b"ab0".to_vec(), candidates[2]);
+        assert_eq!(b"7".to_vec(), candidates[12]);
+        assert_eq!(b"79".to_vec(), candidates[22]);
+    }
+
+    #[test]
+    fn crack_0() {
+        let words = ["password", "letmein", "dragon"];
+        let rules = [Rule::Identity, Rule::Capitalize, Rule::DigitSuffix(2)];
+        assert_eq!(Some(b"Dragon".to_vec()), crack(&Plain(b"Dragon"), mangle(&words, &rules)));
+        assert_eq!(None, crack(&Plain(b"Dragon"), words.iter()));
+        for threads in 1..5 {
+            assert_eq!(Some(b"letmein42".to_vec()),
+                       crack_parallel(&Plain(b"letmein42"), mangle(&words, &rules), threads));
+            assert_eq!(None, crack_parallel(&Plain(b"letmein420"), mangle(&words, &rules), threads));
+        }
+    }
+
+    #[test]
+    fn crack_hmac() {
+        let target = HmacTarget::<Sha1>::new(b"message", &hmac::<Sha1>(b"Sunshine7", b"message"));
+        let rules = [Rule::Capitalize, Rule::DigitSuffix(1)];
+        let candidates = mangle(mangle(&["dragon", "sunshine"], &rules[..1]), &rules[1..]);
+        assert_eq!(Some(b"Sunshine7".to_vec()), crack_parallel(&target, candidates, 4));
+    }
+
+    #[test]
+    fn crack_pbkdf2() {
+        let target = Pbkdf2Target::<Sha1>::new(b"salt", 2, &crate::kdf::pbkdf2::<Sha1>(b"qwerty", b"salt", 2, 16));
+        assert_eq!(Some(b"qwerty".to_vec()), crack(&target, &["123456", "qwerty"]));
+        assert_eq!(None, crack(&target, &["Qwerty"]));
+    }
+}
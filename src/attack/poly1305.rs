@@ -0,0 +1,151 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! Recovering the one-time Poly1305 key of ChaCha20-Poly1305 when a
+//! nonce is reused, and forging messages with it.  This is the
+//! counterpart of the GCM nonce-reuse attack in `attack::gcm`.
+
+use crate::bignum::BigUint;
+use crate::cipher::chacha20poly1305::{self, TAG_SIZE};
+use crate::mac::poly1305;
+
+/// Evaluate the Poly1305 polynomial of `msg` at `r`, modulo `p`.
+fn eval(r: &BigUint, p: &BigUint, msg: &[u8]) -> BigUint {
+    poly1305::blocks(msg).iter().fold(BigUint::zero(), |acc, n| &(&(&acc + n) * r) % p)
+}
+
+/// Recover candidates for the one-time Poly1305 key from two sealed
+/// messages produced with the same key and nonce, the same additional
+/// data `aad`, and different ciphertexts of equal length of at most 16
+/// bytes.
+///
+/// The Poly1305 inputs of both messages then differ only in the
+/// ciphertext block `b`, which is the second-to-last block, so the
+/// difference of the polynomial values is `(b1 - b2) * r^2` modulo
+/// `p = 2^130 - 5`.  The tags only reveal this difference modulo
+/// `2^128`, leaving a handful of possibilities for `r^2`.  Because
+/// `p = 3 (mod 4)`, square roots are easy to compute, and the
+/// clamping of `r` weeds out nearly all wrong candidates.  `s`
+/// follows from either tag.  Returns an empty vector if the messages
+/// do not have the required form.
+pub fn recover_key(aad: &[u8], sealed1: &[u8], sealed2: &[u8]) -> Vec<[u8; 32]> {
+    if sealed1.len() != sealed2.len() || sealed1.len() < TAG_SIZE || sealed1.len() > TAG_SIZE + 16 {
+        return Vec::new();
+    }
+    let (c1, t1) = sealed1.split_at(sealed1.len() - TAG_SIZE);
+    let (c2, t2) = sealed2.split_at(sealed2.len() - TAG_SIZE);
+    let m1 = chacha20poly1305::mac_data(aad, c1);
+    let m2 = chacha20poly1305::mac_data(aad, c2);
+
+    let p = &poly1305::prime();
+    let two128 = &(&BigUint::one() << 128);
+    let blocks1 = poly1305::blocks(&m1);
+    let blocks2 = poly1305::blocks(&m2);
+    let i = blocks1.len() - 2;
+    let db = &(&(&blocks1[i] + p) - &blocks2[i]) % p;
+    let db_inv = match db.modinv(p) {
+        Some(inv) => inv,
+        None => return Vec::new(),
+    };
+
+    let t1 = poly1305::from_le(t1);
+    let t2 = poly1305::from_le(t2);
+    let d = &(&(&t1 + two128) - &t2) % two128;
+    let sqrt_exp = &(p + &BigUint::one()) >> 2;
+
+    let mut result = Vec::new();
+    // a1 - a2 = d + k * 2^128 for k in -4..4; shift by 5p to stay
+    // non-negative.
+    let base = &(p * &BigUint::from_u64(5)) - &(two128 * &BigUint::from_u64(4));
+    for k in 0..8 {
+        let delta = &(&(&base + &d) + &(two128 * &BigUint::from_u64(k))) % p;
+        let r2 = &(&delta * &db_inv) % p;
+        let root = r2.modpow(&sqrt_exp, p);
+        if &(&root * &root) % p != r2 {
+            continue;
+        }
+        for r in &[root.clone(), &(p - &root) % p] {
+            if r >= two128 {
+                continue;
+            }
+            let rbytes = poly1305::to_le(r);
+            let mut clamped = rbytes;
+            poly1305::clamp(&mut clamped);
+            if clamped != rbytes {
+                continue;
+            }
+            let a1 = &eval(r, p, &m1) % two128;
+            let s = &(&(&t1 + two128) - &a1) % two128;
+            let mut key = [0; 32];
+            key[..16].copy_from_slice(&rbytes);
+            key[16..].copy_from_slice(&poly1305::to_le(&s));
+            if poly1305::to_le(&t2) == poly1305::mac(&key, &m2) && !result.contains(&key) {
+                result.push(key);
+            }
+        }
+    }
+    result
+}
+
+/// Seal `ciphertext` and `aad` with the recovered one-time key `otk`,
+/// without knowing the cipher key.  The result is accepted by
+/// `chacha20poly1305::open` under the key and nonce that `otk` was
+/// recovered for.
+pub fn forge(otk: &[u8; 32], aad: &[u8], ciphertext: &[u8]) -> Vec<u8> {
+    let mut sealed = ciphertext.to_vec();
+    sealed.extend_from_slice(&poly1305::mac(otk, &chacha20poly1305::mac_data(aad, ciphertext)));
+    sealed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{recover_key, forge};
+    use crate::cipher::chacha20poly1305::{seal, open, poly1305_key};
+    use crate::mac::poly1305;
+
+    #[test]
+    fn nonce_reuse() {
+        for seed in 0..4u8 {
+            let mut key = [0; 32];
+            for i in 0..32 {
+                key[i] = (i as u8).wrapping_mul(29).wrapping_add(seed.wrapping_mul(71));
+            }
+            let nonce = [seed; 12];
+            let aad = b"order";
+            let p1 = b"attack at dawn";
+            let sealed1 = seal(&key, &nonce, aad, p1);
+            let sealed2 = seal(&key, &nonce, aad, b"defend at dusk");
+
+            let candidates = recover_key(aad, &sealed1, &sealed2);
+            let mut otk = poly1305_key(&key, &nonce);
+            let mut r = [0; 16];
+            r.copy_from_slice(&otk[..16]);
+            poly1305::clamp(&mut r);
+            otk[..16].copy_from_slice(&r);
+            assert!(candidates.contains(&otk));
+
+            // The keystream is reused as well, so a known plaintext can
+            // be replaced at will.
+            let p3 = b"attack at noon";
+            let c3: Vec<u8> = sealed1.iter().zip(p1.iter()).zip(p3.iter())
+                .map(|((c, a), b)| c ^ a ^ b)
+                .collect();
+            let forged = candidates.iter()
+                .map(|otk| forge(otk, aad, &c3))
+                .filter_map(|s| open(&key, &nonce, aad, &s).ok())
+                .next();
+            assert_eq!(Some(p3.to_vec()), forged);
+        }
+    }
+
+    #[test]
+    fn unsupported() {
+        let key = [7; 32];
+        let nonce = [9; 12];
+        let long1 = seal(&key, &nonce, b"", &[1; 20]);
+        let long2 = seal(&key, &nonce, b"", &[2; 20]);
+        assert!(recover_key(b"", &long1, &long2).is_empty());
+        let same = seal(&key, &nonce, b"", b"same");
+        assert!(recover_key(b"", &same, &same).is_empty());
+    }
+}
@@ -0,0 +1,202 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! Attacks on the MAC-then-encrypt record layer in
+//! `protocol::record`.
+//!
+//! `poodle` decrypts a secret in the requests of a victim whose
+//! records are checked with the SSL 3.0 padding check.  The attacker
+//! chooses the request so that the padding fills a whole block and a
+//! secret byte ends a block, and replaces the padding block by that
+//! block.  The server then accepts the record in one of 256 cases,
+//! and each acceptance reveals the byte.
+//!
+//! `padding_oracle` runs the CBC padding oracle attack from
+//! `attack::cbc` against a server that leaks whether the padding of a
+//! record was valid, through its error messages or its timing.
+
+use crate::attack::{cbc, Report};
+use crate::oracle::Oracle;
+use crate::protocol::record::{BLOCK_SIZE, HEADER_SIZE, MAC_SIZE, APPLICATION_DATA};
+
+/// Number of modified requests after which `poodle` gives up on a
+/// byte.  Each attempt succeeds with probability 1/256.
+const MAX_ATTEMPTS: usize = 4096;
+
+/// Return the body length of `record`, without the header and IV.
+fn ciphertext_len(record: &[u8]) -> usize {
+    record.len() - HEADER_SIZE - BLOCK_SIZE
+}
+
+/// Decrypt the secret in the requests of `victim` with the POODLE
+/// attack.  The victim is queried with `(prefix_len, suffix_len)` and
+/// must return a record whose data is `prefix_len` attacker-chosen
+/// bytes, the secret and `suffix_len` attacker-chosen bytes.  The
+/// `server` must answer whether it accepts a record, and must use
+/// the SSL 3.0 padding check.
+///
+/// Every record of the victim is passed on to the server, modified
+/// or not, so that the sequence numbers of both stay in step.  The
+/// confidence of the report is the fraction of the secret that was
+/// recovered, and the query count is the number of records sent to
+/// the server.
+pub fn poodle<V, S>(mut victim: V, mut server: S) -> Report<Vec<u8>>
+    where V: Oracle<(usize, usize), Vec<u8>>, S: Oracle<[u8], bool>
+{
+    let mut queries = 0;
+    let mut send = |record: &[u8]| {
+        queries += 1;
+        server.query(record)
+    };
+
+    // Grow the suffix until the record gets longer; at that point the
+    // padding fills a whole block.
+    let record = victim.query(&(0, 0));
+    send(&record);
+    let len0 = record.len();
+    let mut full = None;
+    for suffix_len in 1..BLOCK_SIZE + 1 {
+        let record = victim.query(&(0, suffix_len));
+        send(&record);
+        if record.len() > len0 {
+            full = Some((suffix_len, ciphertext_len(&record)));
+            break;
+        }
+    }
+    let (suffix_len, len) = match full {
+        Some(f) => f,
+        None => {
+            let mut report = Report::new(Vec::new(), 0.0);
+            report.note("record length does not grow in blocks");
+            return report.with_queries(queries);
+        }
+    };
+    let secret_len = len - BLOCK_SIZE - MAC_SIZE - suffix_len;
+
+    let mut secret = Vec::with_capacity(secret_len);
+    for i in 0..secret_len {
+        // Move byte `i` to the end of block `j`, keeping the total
+        // length, and with it the full padding block, the same.
+        let prefix_len = BLOCK_SIZE - 1 - i % BLOCK_SIZE;
+        let request = (prefix_len, suffix_len + BLOCK_SIZE - prefix_len);
+        let j = (prefix_len + i) / BLOCK_SIZE;
+        let mut found = None;
+        for _ in 0..MAX_ATTEMPTS {
+            let mut record = victim.query(&request);
+            // Offsets of the blocks, counting the IV as block -1.
+            let block = |k: usize| HEADER_SIZE + BLOCK_SIZE + k * BLOCK_SIZE;
+            let last = ciphertext_len(&record) / BLOCK_SIZE - 1;
+            let target = record[block(j)..block(j + 1)].to_vec();
+            let before_target = record[block(j) - 1];
+            let before_last = record[block(last) - 1];
+            record[block(last)..].copy_from_slice(&target);
+            if send(&record) {
+                found = Some(BLOCK_SIZE as u8 ^ before_last ^ before_target);
+                break;
+            }
+        }
+        match found {
+            Some(b) => secret.push(b),
+            None => break,
+        }
+    }
+
+    let confidence = if secret_len == 0 { 1.0 } else { secret.len() as f64 / secret_len as f64 };
+    let mut report = Report::new(secret, confidence);
+    if report.result.len() < secret_len {
+        report.note(format!("gave up after {} of {} bytes", report.result.len(), secret_len));
+    }
+    report.with_queries(queries)
+}
+
+/// Wrap a query of `attack::cbc`, an IV followed by one ciphertext
+/// block, into a record whose padding is valid exactly if the
+/// padding of the query is.  A block of zeros is put in front so that
+/// the record is long enough to hold a MAC.
+pub fn forge_record(query: &[u8]) -> Vec<u8> {
+    let len = 2 * BLOCK_SIZE + query.len();
+    let mut record = vec![APPLICATION_DATA, (len >> 8) as u8, len as u8];
+    record.extend(vec![0; 2 * BLOCK_SIZE]);
+    record.extend_from_slice(query);
+    record
+}
+
+/// Decrypt `record` with the help of `oracle`, which must answer
+/// whether a record has valid padding.  The padding and the MAC are
+/// removed from the result.  The report is the one from
+/// `attack::cbc::padding_oracle`.
+///
+/// # Panics
+/// Panics if `record` is too short to be a record.
+pub fn padding_oracle<O>(record: &[u8], mut oracle: O) -> Report<Vec<u8>>
+    where O: Oracle<[u8], bool>
+{
+    let iv = &record[HEADER_SIZE..HEADER_SIZE + BLOCK_SIZE];
+    let report = cbc::padding_oracle(iv, &record[HEADER_SIZE + BLOCK_SIZE..],
+                                     |q: &[u8]| oracle.query(&forge_record(q)));
+    let complete = report.confidence == 1.0;
+    report.map(|mut plaintext| {
+        if complete {
+            let len = plaintext.len().saturating_sub(MAC_SIZE);
+            plaintext.truncate(len);
+        }
+        plaintext
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{poodle, padding_oracle};
+    use crate::error::Error;
+    use crate::oracle::Counting;
+    use crate::protocol::record::{Keys, Writer, Reader, PaddingCheck, APPLICATION_DATA};
+
+    #[test]
+    fn poodle_0() {
+        let secret = b"Cookie: s=f00ba4";
+        let keys = Keys::random();
+        let mut writer = Writer::new(keys.clone());
+        let mut reader = Reader::new(keys, PaddingCheck::Ssl3);
+        let victim = |&(prefix_len, suffix_len): &(usize, usize)| {
+            let mut request = vec![b'/'; prefix_len];
+            request.extend_from_slice(secret);
+            request.extend(vec![b'x'; suffix_len]);
+            writer.seal(APPLICATION_DATA, &request)
+        };
+        let mut server = Counting::new(|record: &[u8]| reader.open(record).is_ok());
+        let report = poodle(victim, &mut server);
+        assert_eq!(secret.to_vec(), report.result);
+        assert_eq!(1.0, report.confidence);
+        assert_eq!(server.queries(), report.queries_used);
+    }
+
+    fn server(check: PaddingCheck) -> (Writer, Reader) {
+        let keys = Keys::random();
+        (Writer::new(keys.clone()), Reader::new(keys, check))
+    }
+
+    #[test]
+    fn error_oracle() {
+        let (mut writer, mut reader) = server(PaddingCheck::Tls);
+        let record = writer.seal(APPLICATION_DATA, b"user=alice;admin=false");
+        let report = padding_oracle(&record, |r: &[u8]| match reader.open(r) {
+            Err(Error::InvalidPadding { .. }) => false,
+            _ => true,
+        });
+        assert_eq!(b"user=alice;admin=false".to_vec(), report.result);
+        assert_eq!(1.0, report.confidence);
+    }
+
+    #[test]
+    fn timing_oracle() {
+        // The server does not tell padding errors from MAC errors, but
+        // only computes the MAC if the padding was valid.
+        let (mut writer, mut reader) = server(PaddingCheck::Tls);
+        let record = writer.seal(APPLICATION_DATA, b"YELLOW SUBMARINE");
+        let report = padding_oracle(&record, |r: &[u8]| {
+            let _ = reader.open(r);
+            reader.cost() > 0
+        });
+        assert_eq!(b"YELLOW SUBMARINE".to_vec(), report.result);
+    }
+}
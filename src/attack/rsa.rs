@@ -0,0 +1,232 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! Attacks on textbook RSA and on RSA with weak padding checks.
+
+use crate::bignum::BigUint;
+use crate::math;
+use crate::oracle::Oracle;
+use crate::rsa::{self, Pkcs1Digest};
+
+/// Recover a message that has been encrypted with `e = 3` under three
+/// different public keys (challenge 40, Hastad's broadcast attack).
+/// `captured` contains the ciphertexts together with the moduli they
+/// were encrypted under.
+///
+/// Combining the ciphertexts with the Chinese Remainder Theorem gives
+/// `m^3` modulo the product of the moduli.  Since `m` is smaller than
+/// each of the moduli, `m^3` is smaller than their product, so the
+/// result is `m^3` itself and an integer cube root reveals `m`.
+///
+/// # Panics
+/// Panics if fewer than three ciphertexts are given, or when the
+/// moduli are not pairwise coprime.
+pub fn broadcast_e3(captured: &[(Vec<u8>, BigUint)]) -> Vec<u8> {
+    assert!(captured.len() >= 3, "need at least three ciphertexts");
+    let congruences: Vec<_> = captured.iter()
+        .take(3)
+        .map(|&(ref c, ref n)| (BigUint::from_bytes_be(c), n.clone()))
+        .collect();
+    let cube = math::crt(&congruences).expect("moduli must be pairwise coprime");
+    math::iroot(&cube, 3).to_bytes_be()
+}
+
+/// Forge a PKCS#1 v1.5 signature on `message` that is accepted by
+/// `rsa::verify_sloppy` for any `e = 3` public key with a modulus of
+/// `key_bits` bits (challenge 42, Bleichenbacher's e=3 forgery).
+///
+/// The forged block consists of the usual prefix with a single 0xff
+/// padding byte, the `DigestInfo` and the hash, followed by garbage.
+/// The integer cube root of that block with all-ones garbage is a
+/// number whose cube still has the wanted prefix, as long as the
+/// garbage area is large enough to absorb the rounding error.
+/// Returns `None` if the key is too small for that.
+pub fn forge_signature_e3<D: Pkcs1Digest>(message: &[u8], key_bits: usize) -> Option<Vec<u8>> {
+    let len = (key_bits + 7) / 8;
+    let hash = D::digest(message);
+    let mut prefix = vec![0x00, 0x01, 0xff, 0x00];
+    prefix.extend(D::DIGEST_INFO);
+    prefix.extend(hash);
+    if prefix.len() >= len {
+        return None;
+    }
+    let mut block = prefix.clone();
+    block.resize(len, 0xff);
+    let root = math::iroot(&BigUint::from_bytes_be(&block), 3);
+    let forged = root.pow(3).to_bytes_be_padded(len);
+    if forged.starts_with(&prefix) {
+        Some(root.to_bytes_be_padded(len))
+    } else {
+        None
+    }
+}
+
+/// Calculate `ceil(a / b)`.
+fn div_ceil(a: &BigUint, b: &BigUint) -> BigUint {
+    let (q, r) = a.divrem(b);
+    if r.is_zero() { q } else { &q + &BigUint::one() }
+}
+
+/// Merge the interval `[a, b]` into the sorted list of disjoint
+/// intervals `set`.
+fn add_interval(set: &mut Vec<(BigUint, BigUint)>, a: BigUint, b: BigUint) {
+    set.push((a, b));
+    set.sort();
+    let mut merged: Vec<(BigUint, BigUint)> = Vec::with_capacity(set.len());
+    for (a, b) in set.drain(..) {
+        if let Some(last) = merged.last_mut() {
+            if a <= last.1 {
+                if b > last.1 {
+                    last.1 = b;
+                }
+                continue;
+            }
+        }
+        merged.push((a, b));
+    }
+    *set = merged;
+}
+
+/// Decrypt the PKCS#1 v1.5 conforming ciphertext `c` under `key`,
+/// using only an `oracle` that tells whether a given ciphertext
+/// decrypts to a block starting with `00 02` (challenges 47 and 48,
+/// Bleichenbacher's 1998 attack).  Returns the complete padded block,
+/// as long as the modulus.
+///
+/// Multiplying `c` by `s^e` multiplies the plaintext `m` by `s`.  Each
+/// `s` for which the oracle answers yes shows that `m*s mod n` lies in
+/// `[2B, 3B)`, with `B = 2^(8(k-2))`, which narrows down the set of
+/// intervals `m` can be in until only a single number is left.
+///
+/// # Panics
+/// Panics if `c` itself is not PKCS#1 v1.5 conforming.
+pub fn bb98<O>(key: &rsa::PublicKey, c: &[u8], mut oracle: O) -> Vec<u8>
+    where O: Oracle<BigUint, bool>
+{
+    let k = key.size();
+    let n = &key.n;
+    let one = BigUint::one();
+    let c0 = BigUint::from_bytes_be(c);
+    assert!(oracle.query(&c0), "ciphertext must be PKCS#1 v1.5 conforming");
+
+    let b = &one << (8 * (k - 2));
+    let b2 = &b * &BigUint::from_u64(2);
+    let b3 = &b * &BigUint::from_u64(3);
+    let b3m1 = &b3 - &one;
+
+    let mut try_s = |s: &BigUint| oracle.query(&(&(&c0 * &rsa::encrypt_int(key, s)) % n));
+
+    let mut intervals = vec![(b2.clone(), b3m1.clone())];
+
+    // Step 2.a: find the smallest s >= n/3B with a conforming result.
+    let mut s = div_ceil(n, &b3);
+    while !try_s(&s) {
+        s = &s + &one;
+    }
+
+    loop {
+        // Step 3: narrow the set of solutions.
+        let mut next = Vec::new();
+        for &(ref lo, ref hi) in &intervals {
+            let mut r = div_ceil(&(&(&(lo * &s) - &b3) + &one), n);
+            let r_max = &(&(hi * &s) - &b2) / n;
+            while r <= r_max {
+                let rn = &r * n;
+                let new_lo = div_ceil(&(&b2 + &rn), &s);
+                let new_hi = &(&b3m1 + &rn) / &s;
+                let new_lo = if new_lo > *lo { new_lo } else { lo.clone() };
+                let new_hi = if new_hi < *hi { new_hi } else { hi.clone() };
+                if new_lo <= new_hi {
+                    add_interval(&mut next, new_lo, new_hi);
+                }
+                r = &r + &one;
+            }
+        }
+        intervals = next;
+
+        // Step 4: done when a single number remains.
+        if intervals.len() == 1 && intervals[0].0 == intervals[0].1 {
+            return intervals[0].0.to_bytes_be_padded(k);
+        }
+
+        if intervals.len() > 1 {
+            // Step 2.b: search with more than one interval left.
+            s = &s + &one;
+            while !try_s(&s) {
+                s = &s + &one;
+            }
+        } else {
+            // Step 2.c: search with exactly one interval left.
+            let (ref lo, ref hi) = intervals[0];
+            let mut r = div_ceil(&(&(&(hi * &s) - &b2) * &BigUint::from_u64(2)), n);
+            'search: loop {
+                let rn = &r * n;
+                let mut si = div_ceil(&(&b2 + &rn), hi);
+                let s_max = &(&b3 + &rn) / lo;
+                while si <= s_max {
+                    if try_s(&si) {
+                        s = si;
+                        break 'search;
+                    }
+                    si = &si + &one;
+                }
+                r = &r + &one;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{broadcast_e3, forge_signature_e3, bb98};
+    use crate::bignum::BigUint;
+    use crate::hash::sha1::Sha1;
+    use crate::hash::sha256::Sha256;
+    use crate::rsa;
+
+    #[test]
+    fn broadcast_e3_0() {
+        let msg = b"Crazy flamboyant for the rap enjoyment";
+        let captured: Vec<_> = (0..3).map(|_| {
+            let (public, _) = rsa::generate_keypair(512, 3);
+            (rsa::encrypt(&public, msg).unwrap(), public.n)
+        }).collect();
+        assert_eq!(msg.to_vec(), broadcast_e3(&captured));
+    }
+
+    #[test]
+    fn forge_signature_e3_0() {
+        let (public, _) = rsa::generate_keypair(1024, 3);
+        let forged = forge_signature_e3::<Sha1>(b"hi mom", 1024).unwrap();
+        assert!(rsa::verify_sloppy::<Sha1>(&public, b"hi mom", &forged));
+        assert!(!rsa::verify::<Sha1>(&public, b"hi mom", &forged));
+    }
+
+    #[test]
+    fn forge_signature_e3_key_too_small() {
+        assert_eq!(None, forge_signature_e3::<Sha256>(b"hi mom", 512));
+    }
+
+    fn bb98_roundtrip(bits: usize) {
+        let (public, private) = rsa::generate_keypair(bits, 3);
+        let msg = b"kick it, CC";
+        let c = rsa::encrypt_pkcs1(&public, msg).unwrap();
+        let block = bb98(&public, &c, |c: &BigUint| rsa::pkcs1_conforming(&private, c));
+        let expected = rsa::decrypt_int(&private, &BigUint::from_bytes_be(&c));
+        assert_eq!(expected.to_bytes_be_padded(bits / 8), block);
+        assert!(block.ends_with(msg));
+    }
+
+    #[test]
+    fn bb98_simple() {
+        bb98_roundtrip(256);
+    }
+
+    // Needs several ten thousand oracle queries with a 768-bit key,
+    // run with `cargo test -- --ignored`.
+    #[test]
+    #[ignore]
+    fn bb98_full() {
+        bb98_roundtrip(768);
+    }
+}
@@ -0,0 +1,215 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! Attacks on SRP: logging in with a malicious public key (challenge
+//! 37), and the offline dictionary attack on simplified SRP by a man
+//! in the middle (challenge 38).  `DictionaryAttack` is a
+//! `channel::MitM`, so the second attack can be run on the channel
+//! between a client and a server.
+
+use crate::attack::password::{self, TargetHash};
+use crate::bignum::BigUint;
+use crate::mac::hmac;
+use crate::protocol::channel::{Direction, MitM};
+use crate::protocol::srp::{self, Params};
+use crate::protocol::srp::simplified::{self, Message};
+
+/// Return the public key `multiple * N`.  The server computes the
+/// shared secret as `(A * v^u)^b mod N`, which is zero for any multiple
+/// of `N`, including zero itself.
+pub fn malicious_public_key(params: &Params, multiple: u64) -> BigUint {
+    &params.n * &BigUint::from_u64(multiple)
+}
+
+/// Return the proof for a session where the shared secret is zero,
+/// which the server accepts after receiving a malicious public key.
+pub fn zero_key_proof(salt: &[u8]) -> Vec<u8> {
+    srp::proof(&srp::session_key(&BigUint::zero()), salt)
+}
+
+/// Log in to `server` without knowing the password, by sending
+/// `multiple * N` as the public key.  Returns whether the server
+/// accepted the login.
+pub fn bypass_login(server: &mut srp::Server, params: &Params, multiple: u64) -> bool {
+    let (salt, _) = server.exchange(&malicious_public_key(params, multiple));
+    server.verify(&zero_key_proof(&salt))
+}
+
+/// Man-in-the-middle attacker posing as the server in simplified
+/// SRP.  The attacker picks `b = 1`, `u = 1` and an empty salt, so
+/// that for each password guess the shared secret is simply
+/// `A * g^x mod N`.
+pub struct DictionaryAttack {
+    params: Params,
+    salt: Vec<u8>,
+    secret: BigUint,
+    u: BigUint,
+    client_public: Option<BigUint>,
+    captured: Option<(BigUint, Vec<u8>)>,
+}
+
+impl DictionaryAttack {
+    /// Prepare an attack against clients using `params`.
+    pub fn new(params: Params) -> DictionaryAttack {
+        DictionaryAttack {
+            params: params,
+            salt: Vec::new(),
+            secret: BigUint::one(),
+            u: BigUint::one(),
+            client_public: None,
+            captured: None,
+        }
+    }
+
+    /// Return the salt, server public key and scrambling parameter to
+    /// send to the client in place of the real server.
+    pub fn challenge(&self) -> (Vec<u8>, BigUint, BigUint) {
+        (self.salt.clone(),
+         self.params.g.modpow(&self.secret, &self.params.n),
+         self.u.clone())
+    }
+
+    /// Return a target for cracking the password from the client's
+    /// public key and proof, which were captured after sending the
+    /// challenge.
+    pub fn target<'a>(&'a self, client_public: &'a BigUint, client_proof: &'a [u8]) -> ProofTarget<'a> {
+        ProofTarget {
+            attack: self,
+            client_public: client_public,
+            client_proof: client_proof,
+        }
+    }
+
+    /// Try every password in `wordlist` against the client's public
+    /// key and proof, which were captured after sending the
+    /// challenge.  Returns the first password that reproduces the
+    /// proof, or `None` if the password is not in the list.
+    pub fn crack<I>(&self, client_public: &BigUint, client_proof: &[u8], wordlist: I) -> Option<Vec<u8>>
+        where I: IntoIterator, I::Item: AsRef<[u8]>
+    {
+        password::crack(&self.target(client_public, client_proof), wordlist)
+    }
+
+    /// Return the client's public key and proof, if a login has been
+    /// intercepted.
+    pub fn captured(&self) -> Option<(&BigUint, &[u8])> {
+        self.captured.as_ref().map(|c| (&c.0, &c.1[..]))
+    }
+}
+
+/// The attacker answers the client in place of the server.  The
+/// client's public key and proof are kept for `crack`, and the login
+/// is rejected, so that the client just sees a mistyped password.
+impl MitM<Message> for DictionaryAttack {
+    fn intercept(&mut self, direction: Direction, message: Message) -> Vec<(Direction, Message)> {
+        match (direction, message) {
+            (Direction::ClientToServer, Message::Hello(client_public)) => {
+                self.client_public = Some(client_public);
+                let (salt, public, u) = self.challenge();
+                let challenge = Message::Challenge { salt: salt, public: public, u: u };
+                vec![(Direction::ServerToClient, challenge)]
+            }
+            (Direction::ClientToServer, Message::Proof(client_proof)) => {
+                if let Some(client_public) = self.client_public.take() {
+                    self.captured = Some((client_public, client_proof));
+                }
+                vec![(Direction::ServerToClient, Message::Accepted(false))]
+            }
+            (direction, message) => vec![(direction, message)],
+        }
+    }
+}
+
+/// A client proof captured by `DictionaryAttack`, as a target for
+/// password cracking.
+pub struct ProofTarget<'a> {
+    attack: &'a DictionaryAttack,
+    client_public: &'a BigUint,
+    client_proof: &'a [u8],
+}
+
+impl<'a> TargetHash for ProofTarget<'a> {
+    fn matches(&self, candidate: &[u8]) -> bool {
+        let a = self.attack;
+        let x = srp::hash_password(&a.salt, candidate);
+        let verifier = a.params.g.modpow(&x, &a.params.n);
+        let secret = simplified::server_secret(&a.params, self.client_public, &verifier, &a.u, &a.secret);
+        let expected = srp::proof(&srp::session_key(&secret), &a.salt);
+        hmac::verify(&expected, self.client_proof)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{bypass_login, DictionaryAttack};
+    use crate::attack::password::{self, Rule};
+    use crate::protocol::{Encode, Stage, Transcript};
+    use crate::protocol::channel::{self, Direction};
+    use crate::protocol::srp::{Params, Server};
+    use crate::protocol::srp::simplified::{self, Message};
+
+    #[test]
+    fn bypass_login_zero() {
+        let mut server = Server::new(Params::nist(), b"correct horse battery staple");
+        assert!(bypass_login(&mut server, &Params::nist(), 0));
+    }
+
+    #[test]
+    fn bypass_login_multiples() {
+        let mut server = Server::new(Params::nist(), b"correct horse battery staple");
+        assert!(bypass_login(&mut server, &Params::nist(), 1));
+        assert!(bypass_login(&mut server, &Params::nist(), 2));
+    }
+
+    #[test]
+    fn dictionary_attack_0() {
+        let wordlist = ["password", "123456", "qwerty", "sunshine", "dragon"];
+        let client = simplified::Client::new(Params::nist(), b"sunshine");
+        let attack = DictionaryAttack::new(Params::nist());
+        let (salt, b, u) = attack.challenge();
+        let proof = client.proof(&salt, &b, &u);
+        assert_eq!(Some(b"sunshine".to_vec()),
+                   attack.crack(client.public_key(), &proof, wordlist.iter()));
+    }
+
+    #[test]
+    fn dictionary_attack_not_found() {
+        let wordlist = ["password", "123456", "qwerty"];
+        let client = simplified::Client::new(Params::nist(), b"sunshine");
+        let attack = DictionaryAttack::new(Params::nist());
+        let (salt, b, u) = attack.challenge();
+        let proof = client.proof(&salt, &b, &u);
+        assert_eq!(None, attack.crack(client.public_key(), &proof, wordlist.iter()));
+    }
+
+    #[test]
+    fn dictionary_attack_mangled() {
+        let wordlist = ["password", "dragon", "sunshine"];
+        let client = simplified::Client::new(Params::nist(), b"Dragon7");
+        let attack = DictionaryAttack::new(Params::nist());
+        let (salt, b, u) = attack.challenge();
+        let proof = client.proof(&salt, &b, &u);
+        let rules = [Rule::Capitalize, Rule::DigitSuffix(1)];
+        let candidates = password::mangle(password::mangle(&wordlist, &rules[..1]), &rules[1..]);
+        assert_eq!(Some(b"Dragon7".to_vec()),
+                   password::crack_parallel(&attack.target(client.public_key(), &proof), candidates, 4));
+    }
+
+    #[test]
+    fn dictionary_attack_channel() {
+        let wordlist = ["password", "123456", "qwerty", "sunshine", "dragon"];
+        let mut client = simplified::Client::new(Params::nist(), b"dragon");
+        let mut server = simplified::Server::new(Params::nist(), b"dragon");
+        let mut attack = DictionaryAttack::new(Params::nist());
+        let mut transcript = Transcript::new();
+        channel::run_recorded(&mut client, &mut server, &mut attack, &mut transcript).unwrap();
+        assert_eq!(Some(false), client.accepted());
+        // The server never heard of the client.
+        assert!(transcript.messages(Stage::Delivered, Direction::ClientToServer).is_empty());
+        let (client_public, proof) = attack.captured().unwrap();
+        let sent = transcript.messages(Stage::Sent, Direction::ClientToServer);
+        assert_eq!(2, sent.len());
+        assert_eq!(&Message::Proof(proof.to_vec()).encode()[..], sent[1]);
+        assert_eq!(Some(b"dragon".to_vec()), attack.crack(client_public, proof, wordlist.iter()));
+    }
+}
@@ -0,0 +1,112 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! Attacks on stream ciphers whose keystream was used more than once,
+//! for example CTR mode or ChaCha20 with a repeated nonce.  The XOR
+//! of two such ciphertexts is the XOR of the plaintexts, which has a
+//! lot of structure for text: two letters of the same case XOR to a
+//! value below `0x20`, and a space and a letter XOR to a letter.
+//! Unrelated ciphertexts XOR to uniformly random bytes, of which only
+//! 3/8 fall into these ranges.
+
+use crate::xor;
+
+/// Score above which `detect_keystream_reuse` reports a pair.  The
+/// XOR of two English texts scores around 0.9, random data 0.375.
+pub const REUSE_THRESHOLD: f64 = 0.7;
+
+/// Number of bytes two ciphertexts must have in common to be compared.
+/// Shorter overlaps give too many false positives.
+pub const MIN_OVERLAP: usize = 16;
+
+/// Return true if `b` is a likely XOR of two text bytes: below `0x20`
+/// (letters of the same case, or equal bytes) or between `0x40` and
+/// `0x7f` (a space and a letter).
+fn text_xor(b: u8) -> bool {
+    b < 0x20 || (0x40..0x80).contains(&b)
+}
+
+/// Score how much `x` looks like the XOR of two English texts: the
+/// fraction of its bytes that are likely XORs of two text bytes, in
+/// `[0, 1]`.  An empty input scores 0.
+pub fn score_plaintext_xor(x: &[u8]) -> f64 {
+    if x.is_empty() {
+        return 0.0;
+    }
+    x.iter().filter(|&&b| text_xor(b)).count() as f64 / x.len() as f64
+}
+
+/// Score every pair of `ciphertexts` that overlaps in at least
+/// `MIN_OVERLAP` bytes with `score_plaintext_xor`, comparing their
+/// common prefix.  Returns `(i, j, score)` with `i < j`, best first.
+pub fn keystream_reuse_scores(ciphertexts: &[Vec<u8>]) -> Vec<(usize, usize, f64)> {
+    let mut results = Vec::new();
+    for (i, c1) in ciphertexts.iter().enumerate() {
+        for (j, c2) in ciphertexts.iter().enumerate().skip(i + 1) {
+            let len = c1.len().min(c2.len());
+            if len < MIN_OVERLAP {
+                continue;
+            }
+            let mut x = c1[..len].to_vec();
+            xor::xor_into(&mut x, &c2[..len]);
+            results.push((i, j, score_plaintext_xor(&x)));
+        }
+    }
+    results.sort_by(|a, b| match b.2.partial_cmp(&a.2) {
+        Some(o) => o,
+        _ => ::std::cmp::Ordering::Less
+    });
+    results
+}
+
+/// Find the pairs of `ciphertexts` that were probably encrypted with
+/// the same keystream, by scoring the XOR of each pair for English
+/// structure.  Returns `(i, j, score)` for the pairs scoring at least
+/// `REUSE_THRESHOLD`, best first.  Ciphertexts shorter than
+/// `MIN_OVERLAP` bytes are never reported.
+pub fn detect_keystream_reuse(ciphertexts: &[Vec<u8>]) -> Vec<(usize, usize, f64)> {
+    let mut results = keystream_reuse_scores(ciphertexts);
+    results.retain(|r| r.2 >= REUSE_THRESHOLD);
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{detect_keystream_reuse, keystream_reuse_scores, score_plaintext_xor};
+    use crate::cipher::aes::AesKey;
+    use crate::cipher::nonce::Ctr;
+
+    static MESSAGES: [&'static str; 5] = [
+        "Meet me at the old bridge after midnight.",
+        "The shipment arrives on tuesday, keep it quiet.",
+        "Nobody else knows about the second safe house.",
+        "Burn this message once you have read it carefully.",
+        "short",
+    ];
+
+    #[test]
+    fn detect_ctr_nonce_reuse() {
+        let mut ctr = Ctr::new(AesKey::from([0x42; 16])).unsafe_allow_reuse();
+        let ivs = [[1; 16], [2; 16], [1; 16], [3; 16], [1; 16]];
+        let ciphertexts: Vec<Vec<u8>> = MESSAGES.iter().zip(ivs.iter())
+            .map(|(m, iv)| ctr.encrypt_with_iv(iv, m.as_bytes()).unwrap())
+            .collect();
+        assert_eq!(3, ctr.nonces().used());
+
+        let found = detect_keystream_reuse(&ciphertexts);
+        assert_eq!(1, found.len());
+        assert_eq!((0, 2), (found[0].0, found[0].1));
+        assert!(found[0].2 > 0.9);
+
+        let all = keystream_reuse_scores(&ciphertexts);
+        assert_eq!(6, all.len());
+        assert!(all.iter().all(|&(i, j, _)| i < j && j < 4));
+    }
+
+    #[test]
+    fn score() {
+        assert_eq!(0.0, score_plaintext_xor(b""));
+        assert_eq!(1.0, score_plaintext_xor(&[0; 20]));
+        assert_eq!(0.5, score_plaintext_xor(&[0x01, 0x41, 0x21, 0x81]));
+    }
+}
@@ -0,0 +1,276 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! Interactive recovery of messages encrypted with the same keystream,
+//! as in challenge 19.  The `Solver` ranks candidate keystream bytes
+//! for every position by how English the column of plaintext bytes
+//! they produce looks, and lets the user confirm cribs: guessed
+//! plaintext fragments of one message, which fix the keystream at
+//! their positions and thereby reveal the other messages there.
+//! Confirmations can be undone, so a front end can let the user try
+//! guesses and back out of wrong ones.
+
+use crate::error::{Error, Result};
+use crate::language::english;
+use crate::xor::{self, Scorer};
+
+/// Number of candidate keystream bytes kept for each position.
+pub const HYPOTHESES: usize = 8;
+
+/// Scorer for a column of plaintext bytes taken from different
+/// messages at the same position: the log-likelihood of the bytes
+/// under a unigram model of English text.  Unlike the scorers in
+/// `xor`, it does not look at neighbouring bytes, which are unrelated
+/// in a column.
+#[derive(Clone, Copy, Debug)]
+pub struct Unigram;
+
+impl Unigram {
+    /// Return the log probability of `b` in English text.
+    fn log_prob(b: u8) -> f64 {
+        let p = match b {
+            b' ' => 0.17,
+            b'a'..=b'z' => 0.75 * 0.95 * english::letter_prob(b).unwrap(),
+            b'A'..=b'Z' => 0.75 * 0.05 * english::letter_prob(b).unwrap(),
+            b'.' | b',' | b'\'' | b'-' | b';' | b':' | b'!' | b'?' | b'"' => 0.01,
+            b'0'..=b'9' => 0.002,
+            _ if (0x20..0x7f).contains(&b) => 0.0005,
+            _ => 1e-6,
+        };
+        p.ln()
+    }
+}
+
+impl Scorer for Unigram {
+    fn score(&self, msg: &[u8]) -> f64 {
+        msg.iter().map(|&b| Unigram::log_prob(b)).sum()
+    }
+}
+
+/// State of a two-time pad attack on a set of ciphertexts.
+#[derive(Clone, Debug)]
+pub struct Solver {
+    ciphertexts: Vec<Vec<u8>>,
+    hypotheses: Vec<Vec<(u8, f64)>>,
+    confirmed: Vec<Option<u8>>,
+    history: Vec<Vec<Option<u8>>>,
+}
+
+impl Solver {
+    /// Create a solver for `ciphertexts`, ranking the keystream
+    /// candidates with `Unigram`.
+    pub fn new(ciphertexts: Vec<Vec<u8>>) -> Solver {
+        Solver::with_scorer(ciphertexts, &Unigram)
+    }
+
+    /// Like `new`, but rank the keystream candidates with `scorer`.
+    pub fn with_scorer<S: Scorer>(ciphertexts: Vec<Vec<u8>>, scorer: &S) -> Solver {
+        let len = ciphertexts.iter().map(|c| c.len()).max().unwrap_or(0);
+        let hypotheses = (0..len).map(|pos| {
+            let column: Vec<u8> = ciphertexts.iter().filter_map(|c| c.get(pos).cloned()).collect();
+            xor::single_byte_xor_candidates_with(&column, HYPOTHESES, scorer).into_iter()
+                .map(|(score, key, _)| (key, score))
+                .collect()
+        }).collect();
+        Solver {
+            ciphertexts: ciphertexts,
+            hypotheses: hypotheses,
+            confirmed: vec![None; len],
+            history: Vec::new(),
+        }
+    }
+
+    /// Number of ciphertexts.
+    pub fn messages(&self) -> usize {
+        self.ciphertexts.len()
+    }
+
+    /// Length of the longest ciphertext, which is the number of
+    /// keystream positions.
+    pub fn len(&self) -> usize {
+        self.confirmed.len()
+    }
+
+    /// Return true if there are no keystream positions.
+    pub fn is_empty(&self) -> bool {
+        self.confirmed.is_empty()
+    }
+
+    /// Return the candidate keystream bytes for position `pos` with
+    /// their scores, best first.
+    ///
+    /// # Panics
+    /// Panics if `pos` is not less than `len()`.
+    pub fn hypotheses(&self, pos: usize) -> &[(u8, f64)] {
+        &self.hypotheses[pos]
+    }
+
+    /// Return the keystream byte at `pos`: the confirmed one if there
+    /// is one, the best candidate otherwise.
+    ///
+    /// # Panics
+    /// Panics if `pos` is not less than `len()`.
+    pub fn key_byte(&self, pos: usize) -> u8 {
+        self.confirmed[pos].unwrap_or_else(|| self.hypotheses[pos][0].0)
+    }
+
+    /// Return the confirmed keystream bytes, `None` where nothing has
+    /// been confirmed yet.
+    pub fn keystream(&self) -> &[Option<u8>] {
+        &self.confirmed
+    }
+
+    /// Number of confirmed keystream positions.
+    pub fn confirmed(&self) -> usize {
+        self.confirmed.iter().filter(|k| k.is_some()).count()
+    }
+
+    /// Confirm that message `message` contains `crib` at `offset`, and
+    /// fix the keystream there accordingly.  Returns an error if there
+    /// is no such message, or if the crib extends past its end.
+    pub fn confirm(&mut self, message: usize, offset: usize, crib: &[u8]) -> Result<()> {
+        let c = self.ciphertexts.get(message)
+            .ok_or_else(|| Error::other(format!("no message {}", message)))?;
+        if offset + crib.len() > c.len() {
+            return Err(Error::other(format!("crib extends past the end of message {} ({} bytes)",
+                                            message, c.len())));
+        }
+        self.history.push(self.confirmed.clone());
+        for (i, &p) in crib.iter().enumerate() {
+            self.confirmed[offset + i] = Some(c[offset + i] ^ p);
+        }
+        Ok(())
+    }
+
+    /// Confirm the keystream candidate `key` at `pos`, for example one
+    /// picked from `hypotheses(pos)`.
+    ///
+    /// # Panics
+    /// Panics if `pos` is not less than `len()`.
+    pub fn confirm_key_byte(&mut self, pos: usize, key: u8) {
+        self.history.push(self.confirmed.clone());
+        self.confirmed[pos] = Some(key);
+    }
+
+    /// Revert the last confirmation.  Returns false if there is
+    /// nothing to undo.
+    pub fn undo(&mut self) -> bool {
+        match self.history.pop() {
+            Some(confirmed) => {
+                self.confirmed = confirmed;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Return the plaintext of message `message` as far as it is
+    /// recovered from confirmed keystream bytes.
+    ///
+    /// # Panics
+    /// Panics if there is no such message.
+    pub fn plaintext(&self, message: usize) -> Vec<Option<u8>> {
+        self.ciphertexts[message].iter().zip(self.confirmed.iter())
+            .map(|(c, k)| k.map(|k| c ^ k))
+            .collect()
+    }
+
+    /// Return the best guess for the plaintext of message `message`,
+    /// using the best candidate where nothing has been confirmed.
+    ///
+    /// # Panics
+    /// Panics if there is no such message.
+    pub fn guess(&self, message: usize) -> Vec<u8> {
+        self.ciphertexts[message].iter().enumerate()
+            .map(|(pos, c)| c ^ self.key_byte(pos))
+            .collect()
+    }
+
+    /// Render message `message` for display: confirmed bytes as
+    /// themselves, guessed ones as `_`, and unprintable ones as `.`.
+    ///
+    /// # Panics
+    /// Panics if there is no such message.
+    pub fn render(&self, message: usize) -> String {
+        self.plaintext(message).iter().map(|p| match *p {
+            Some(b) if (0x20..0x7f).contains(&b) => b as char,
+            Some(_) => '.',
+            None => '_',
+        }).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Solver;
+    use crate::cipher::aes::{self, AesKey};
+    use crate::error::Error;
+
+    static MESSAGES: [&'static str; 12] = [
+        "I have met them at close of day",
+        "Coming with vivid faces",
+        "From counter or desk among grey",
+        "Eighteenth-century houses.",
+        "I have passed with a nod of the head",
+        "Or polite meaningless words,",
+        "Or have lingered awhile and said",
+        "Polite meaningless words,",
+        "And thought before I had done",
+        "Of a mocking tale or a gibe",
+        "To please a companion",
+        "Around the fire at the club,",
+    ];
+
+    fn solver() -> Solver {
+        let key = AesKey::from([0x17; 16]);
+        Solver::new(MESSAGES.iter().map(|m| aes::encrypt_ctr(&key, &[0; 16], m.as_bytes())).collect())
+    }
+
+    #[test]
+    fn hypotheses() {
+        let s = solver();
+        assert_eq!(36, s.len());
+        assert_eq!(12, s.messages());
+        let right = (0..20).filter(|&pos| {
+            s.guess(0)[pos] == MESSAGES[0].as_bytes()[pos]
+        }).count();
+        assert!(right >= 17, "only {} of 20 positions right", right);
+    }
+
+    #[test]
+    fn confirm_and_undo() {
+        let mut s = solver();
+        assert_eq!("_______________________________", s.render(0));
+        s.confirm(4, 0, b"I have passed with a nod of the head").unwrap();
+        assert_eq!(36, s.confirmed());
+        for (i, m) in MESSAGES.iter().enumerate() {
+            assert_eq!(m.as_bytes().to_vec(), s.guess(i));
+            assert_eq!(*m, s.render(i));
+        }
+        assert!(s.undo());
+        assert_eq!(0, s.confirmed());
+        assert!(!s.undo());
+
+        s.confirm(1, 7, b"with").unwrap();
+        assert_eq!(format!("_______met {}", "_".repeat(20)), s.render(0));
+        assert_eq!(vec![None, Some(b'm'), Some(b'e'), Some(b't'), Some(b' '), None],
+                   s.plaintext(0)[6..12].to_vec());
+        let k = s.keystream()[7].unwrap();
+        s.confirm_key_byte(7, k ^ 1);
+        assert_eq!(Some(b'm' ^ 1), s.plaintext(0)[7]);
+        assert!(s.undo());
+        assert_eq!(Some(b'm'), s.plaintext(0)[7]);
+    }
+
+    #[test]
+    fn confirm_errors() {
+        let mut s = solver();
+        match s.confirm(12, 0, b"x") {
+            Err(Error::Other { .. }) => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+        assert!(s.confirm(1, 20, b"faces").is_err());
+        assert!(s.confirm(1, 18, b"faces").is_ok());
+        assert_eq!(0, Solver::new(Vec::new()).len());
+    }
+}
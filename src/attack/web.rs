@@ -0,0 +1,136 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! Forging administrator tokens for the server in `web`.  The
+//! attacker can ask the server for tokens for any user name, and
+//! knows the format of the profile, `user=<name>;admin=false`.
+//!
+//! - ECB (challenge 13): the encryption of a block can be cut out of
+//!   one token and pasted into another.
+//! - CBC (challenge 16): flipping bits in a ciphertext block flips the
+//!   same bits in the next plaintext block, and garbles only the
+//!   block itself, which holds part of the user name.
+//! - CTR (challenge 26): flipping bits in the ciphertext flips the
+//!   same bits in the plaintext, without any garbling.
+//! - Secret-prefix MAC (challenge 29): SHA-1 can be resumed from a
+//!   MAC to append to the signed profile.
+//!
+//! Only the HMAC resists all of these.
+
+use crate::hash::Digest;
+use crate::hash::sha1::Sha1;
+use crate::padding::{self, Endianness};
+use crate::web::{Protection, Server};
+
+/// Block size of the cipher.
+const BLOCK_SIZE: usize = 16;
+
+/// Length of `user=`, which precedes the user name.
+const PREFIX_LEN: usize = 5;
+
+/// Longest key tried by `length_extension`.
+const MAX_KEY_LEN: usize = 64;
+
+/// XOR the bytes of `token` at `offset` with `from ^ to`.
+fn flip(token: &mut [u8], offset: usize, from: &[u8], to: &[u8]) {
+    for i in 0..from.len() {
+        token[offset + i] ^= from[i] ^ to[i];
+    }
+}
+
+/// Forge a token for a server using ECB mode.  The block
+/// `true` + padding is cut from one token and pasted over the block
+/// `false` + padding of another.
+pub fn ecb_cut_and_paste(server: &Server) -> Vec<u8> {
+    // user=AAAAAAAAAAA | true\x0c\x0c...
+    let mut user = vec![b'A'; BLOCK_SIZE - PREFIX_LEN];
+    user.extend_from_slice(b"true");
+    user.extend(vec![12; 12]);
+    let true_block = server.issue_token(&user)[BLOCK_SIZE..2 * BLOCK_SIZE].to_vec();
+
+    // user=AAAA;admin= | false\x0b\x0b...
+    let mut token = server.issue_token(b"AAAA");
+    let len = token.len();
+    token[len - BLOCK_SIZE..].copy_from_slice(&true_block);
+    token
+}
+
+/// Forge a token for a server using CBC mode.  The user name fills
+/// block 1, so that `;admin=false` starts block 2, and `false` is
+/// turned into `true;` through block 1.
+pub fn cbc_bitflip(server: &Server) -> Vec<u8> {
+    let mut token = server.issue_token(&[b'A'; 2 * BLOCK_SIZE - PREFIX_LEN]);
+    // The IV comes first, then blocks 0 and 1.
+    flip(&mut token, 2 * BLOCK_SIZE + 7, b"false", b"true;");
+    token
+}
+
+/// Forge a token for a server using CTR mode by turning `false` into
+/// `true;`.
+pub fn ctr_bitflip(server: &Server) -> Vec<u8> {
+    let mut token = server.issue_token(b"alice");
+    // The nonce comes first, then `user=alice;admin=`.
+    flip(&mut token, BLOCK_SIZE + PREFIX_LEN + 5 + 7, b"false", b"true;");
+    token
+}
+
+/// Forge a token for a server using a secret-prefix MAC by appending
+/// `;admin=true`.  The key length is unknown, so each guess is tried
+/// on the server.  Returns `None` if the key is longer than 64 bytes
+/// or the server does not use a prefix MAC.
+pub fn length_extension(server: &Server) -> Option<Vec<u8>> {
+    let token = server.issue_token(b"alice");
+    let (profile, mac) = token.split_at(token.len() - 20);
+    let extension = b";admin=true";
+    for key_len in 0..MAX_KEY_LEN + 1 {
+        let glue = padding::md_length((key_len + profile.len()) as u64, 64, Endianness::Big);
+        let mut d = Sha1::resume(mac, (key_len + profile.len() + glue.len()) as u64);
+        d.update(extension);
+        let mut forged = profile.to_vec();
+        forged.extend(glue);
+        forged.extend_from_slice(extension);
+        forged.extend(d.finish());
+        if let Ok(p) = server.parse_token(&forged) {
+            if p.is_admin() {
+                return Some(forged);
+            }
+        }
+    }
+    None
+}
+
+/// Forge an administrator token with the attack that fits the
+/// server's protection.  Returns `None` for servers using HMAC, or if
+/// the attack fails.
+pub fn forge_admin(server: &Server) -> Option<Vec<u8>> {
+    match server.protection() {
+        Protection::Ecb => Some(ecb_cut_and_paste(server)),
+        Protection::Cbc => Some(cbc_bitflip(server)),
+        Protection::Ctr => Some(ctr_bitflip(server)),
+        Protection::PrefixMac => length_extension(server),
+        Protection::Hmac => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{forge_admin, length_extension};
+    use crate::web::{Protection, Server};
+
+    #[test]
+    fn forge() {
+        for &protection in &[Protection::Ecb, Protection::Cbc, Protection::Ctr, Protection::PrefixMac] {
+            let server = Server::new(protection);
+            let token = forge_admin(&server).unwrap();
+            let profile = server.parse_token(&token).unwrap();
+            assert!(profile.is_admin(), "{:?}: {:?}", protection, profile);
+        }
+    }
+
+    #[test]
+    fn hmac() {
+        let server = Server::new(Protection::Hmac);
+        assert_eq!(None, forge_admin(&server));
+        assert_eq!(None, length_extension(&server));
+    }
+}
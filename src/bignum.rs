@@ -0,0 +1,724 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! Arbitrary-precision unsigned integers, as needed by the
+//! public-key protocols and the attacks on them.  This is a plain
+//! schoolbook implementation which favours clarity over speed.  It
+//! has not been hardened against timing attacks or anything else, so
+//! do not use it for production!
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, Sub, Mul, Div, Rem, Shl, Shr};
+use rand::Rng;
+
+use crate::codec;
+use crate::error;
+
+/// Unsigned integer of arbitrary size.  The value is stored as 32-bit
+/// limbs in little-endian order, without any most significant zero
+/// limbs, so that zero is represented by an empty limb vector.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct BigUint {
+    limbs: Vec<u32>,
+}
+
+impl BigUint {
+    /// Create a number from its limbs, least significant limb first.
+    fn from_limbs(limbs: Vec<u32>) -> BigUint {
+        let mut n = BigUint { limbs: limbs };
+        n.normalize();
+        n
+    }
+
+    /// Strip most significant zero limbs.
+    fn normalize(&mut self) {
+        while let Some(&0) = self.limbs.last() {
+            self.limbs.pop();
+        }
+    }
+
+    /// Return the number zero.
+    pub fn zero() -> BigUint {
+        BigUint { limbs: Vec::new() }
+    }
+
+    /// Return the number one.
+    pub fn one() -> BigUint {
+        BigUint::from_u64(1)
+    }
+
+    /// Convert a machine integer to a big number.
+    pub fn from_u64(n: u64) -> BigUint {
+        BigUint::from_limbs(vec![n as u32, (n >> 32) as u32])
+    }
+
+    /// Return the value as a machine integer, or `None` if it does
+    /// not fit into 64 bits.
+    pub fn to_u64(&self) -> Option<u64> {
+        match self.limbs.len() {
+            0 => Some(0),
+            1 => Some(self.limbs[0] as u64),
+            2 => Some((self.limbs[1] as u64) << 32 | self.limbs[0] as u64),
+            _ => None,
+        }
+    }
+
+    /// Interpret `bytes` as a big-endian unsigned integer.
+    pub fn from_bytes_be(bytes: &[u8]) -> BigUint {
+        let mut limbs = Vec::with_capacity(bytes.len() / 4 + 1);
+        for chunk in bytes.rchunks(4) {
+            let mut limb = 0u32;
+            for &b in chunk {
+                limb = (limb << 8) | b as u32;
+            }
+            limbs.push(limb);
+        }
+        BigUint::from_limbs(limbs)
+    }
+
+    /// Return the minimal big-endian byte representation of the
+    /// number.  Zero is encoded as a single zero byte.
+    pub fn to_bytes_be(&self) -> Vec<u8> {
+        let mut ret = Vec::with_capacity(self.limbs.len() * 4);
+        for limb in self.limbs.iter().rev() {
+            ret.push((limb >> 24) as u8);
+            ret.push((limb >> 16) as u8);
+            ret.push((limb >> 8) as u8);
+            ret.push(*limb as u8);
+        }
+        let leading = ret.iter().take_while(|&&b| b == 0).count();
+        if leading == ret.len() {
+            vec![0]
+        } else {
+            ret.split_off(leading)
+        }
+    }
+
+    /// Return the big-endian representation of the number, padded
+    /// with leading zeros to `len` bytes.
+    ///
+    /// # Panics
+    /// Panics when the number does not fit into `len` bytes.
+    pub fn to_bytes_be_padded(&self, len: usize) -> Vec<u8> {
+        let bytes = self.to_bytes_be();
+        if self.is_zero() {
+            return vec![0; len];
+        }
+        assert!(bytes.len() <= len, "number does not fit into {} bytes", len);
+        let mut ret = vec![0; len - bytes.len()];
+        ret.extend(bytes);
+        ret
+    }
+
+    /// Parse a number given in hexadecimal notation.  Whitespace is
+    /// ignored, so that the constants from the challenge descriptions
+    /// can be pasted verbatim.
+    pub fn from_hex(s: &str) -> Result<BigUint, error::Error> {
+        let mut digits: String = s.chars().filter(|c| !c.is_whitespace()).collect();
+        if digits.len() % 2 != 0 {
+            digits.insert(0, '0');
+        }
+        let bytes = codec::hex::decode(&digits)?;
+        Ok(BigUint::from_bytes_be(&bytes))
+    }
+
+    /// Return the number in lower-case hexadecimal notation, without
+    /// leading zeros.
+    pub fn to_hex(&self) -> String {
+        let s = codec::hex::encode(&self.to_bytes_be());
+        if s.len() > 1 && s.starts_with('0') {
+            s[1..].to_string()
+        } else {
+            s
+        }
+    }
+
+    /// Return true if the number is zero.
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    /// Return true if the number is one.
+    pub fn is_one(&self) -> bool {
+        self.limbs.len() == 1 && self.limbs[0] == 1
+    }
+
+    /// Return true if the number is even.
+    pub fn is_even(&self) -> bool {
+        self.limbs.first().map(|l| l & 1 == 0).unwrap_or(true)
+    }
+
+    /// Return the number of significant bits.  Zero has no
+    /// significant bits.
+    pub fn bits(&self) -> usize {
+        match self.limbs.last() {
+            None => 0,
+            Some(&top) => self.limbs.len() * 32 - top.leading_zeros() as usize,
+        }
+    }
+
+    /// Return the value of bit `i`, where bit 0 is the least
+    /// significant one.
+    pub fn bit(&self, i: usize) -> bool {
+        match self.limbs.get(i / 32) {
+            None => false,
+            Some(&l) => (l >> (i % 32)) & 1 == 1,
+        }
+    }
+
+    /// Divide `self` by `divisor`, returning quotient and remainder.
+    ///
+    /// # Panics
+    /// Panics when `divisor` is zero.
+    pub fn divrem(&self, divisor: &BigUint) -> (BigUint, BigUint) {
+        assert!(!divisor.is_zero(), "division by zero");
+        if self < divisor {
+            return (BigUint::zero(), self.clone());
+        }
+        if divisor.limbs.len() == 1 {
+            let (q, r) = divrem_limb(&self.limbs, divisor.limbs[0]);
+            return (BigUint::from_limbs(q), BigUint::from_u64(r as u64));
+        }
+        let (q, r) = divrem_knuth(&self.limbs, &divisor.limbs);
+        (BigUint::from_limbs(q), BigUint::from_limbs(r))
+    }
+
+    /// Calculate `self` raised to the power `exponent`.
+    pub fn pow(&self, exponent: u32) -> BigUint {
+        let mut result = BigUint::one();
+        let mut i = 32 - exponent.leading_zeros();
+        while i > 0 {
+            i -= 1;
+            result = &result * &result;
+            if (exponent >> i) & 1 == 1 {
+                result = &result * self;
+            }
+        }
+        result
+    }
+
+    /// Calculate `self` raised to the power `exponent`, modulo
+    /// `modulus`, by left-to-right square-and-multiply.
+    ///
+    /// # Panics
+    /// Panics when `modulus` is zero.
+    pub fn modpow(&self, exponent: &BigUint, modulus: &BigUint) -> BigUint {
+        if modulus.is_one() {
+            return BigUint::zero();
+        }
+        let base = self % modulus;
+        let mut result = BigUint::one();
+        let mut i = exponent.bits();
+        while i > 0 {
+            i -= 1;
+            result = &(&result * &result) % modulus;
+            if exponent.bit(i) {
+                result = &(&result * &base) % modulus;
+            }
+        }
+        result
+    }
+
+    /// Calculate the multiplicative inverse of `self` modulo
+    /// `modulus` with the extended Euclidean algorithm.  Returns
+    /// `None` if the inverse does not exist, that is when the two
+    /// numbers are not coprime.
+    pub fn modinv(&self, modulus: &BigUint) -> Option<BigUint> {
+        // The Bezout coefficients are kept reduced modulo `modulus`,
+        // so that no negative numbers are needed.
+        let mut old_r = self % modulus;
+        let mut r = modulus.clone();
+        let mut old_s = BigUint::one();
+        let mut s = BigUint::zero();
+        while !r.is_zero() {
+            let (q, rem) = old_r.divrem(&r);
+            old_r = r;
+            r = rem;
+            let qs = &(&q * &s) % modulus;
+            let new_s = &(&old_s + &(modulus - &qs)) % modulus;
+            old_s = s;
+            s = new_s;
+        }
+        if old_r.is_one() {
+            Some(&old_s % modulus)
+        } else {
+            None
+        }
+    }
+
+    /// Calculate the greatest common divisor of `self` and `other`.
+    pub fn gcd(&self, other: &BigUint) -> BigUint {
+        let mut a = self.clone();
+        let mut b = other.clone();
+        while !b.is_zero() {
+            let r = &a % &b;
+            a = b;
+            b = r;
+        }
+        a
+    }
+
+    /// Return a uniformly chosen random number in the range
+    /// `0..bound`, using the thread-local random number generator.
+    ///
+    /// # Panics
+    /// Panics when `bound` is zero.
+    pub fn random_below(bound: &BigUint) -> BigUint {
+        random_below_with(bound, |buf| crate::random::fill_bytes(buf))
+    }
+
+    /// Return a uniformly chosen random number in the range
+    /// `0..bound`, drawing random bytes from `rng`.
+    ///
+    /// # Panics
+    /// Panics when `bound` is zero.
+    pub fn random_below_rng<R: Rng>(bound: &BigUint, rng: &mut R) -> BigUint {
+        random_below_with(bound, |buf| rng.fill_bytes(buf))
+    }
+}
+
+/// Rejection sampling of a number below `bound`, where `fill` supplies
+/// the random bytes.
+fn random_below_with<F: FnMut(&mut [u8])>(bound: &BigUint, mut fill: F) -> BigUint {
+    assert!(!bound.is_zero(), "empty range");
+    let len = (bound.bits() + 7) / 8;
+    let excess = len * 8 - bound.bits();
+    let mut buf = vec![0u8; len];
+    loop {
+        fill(&mut buf);
+        buf[0] &= 0xff >> excess;
+        let n = BigUint::from_bytes_be(&buf);
+        if &n < bound {
+            return n;
+        }
+    }
+}
+
+/// Divide the limbs `u` by the single limb `d`.
+fn divrem_limb(u: &[u32], d: u32) -> (Vec<u32>, u32) {
+    let mut q = vec![0u32; u.len()];
+    let mut r = 0u64;
+    for i in (0..u.len()).rev() {
+        let cur = (r << 32) | u[i] as u64;
+        q[i] = (cur / d as u64) as u32;
+        r = cur % d as u64;
+    }
+    (q, r as u32)
+}
+
+/// Long division of `u` by `v`, where `v` has at least two limbs and
+/// `u` is not smaller than `v`.  This is Algorithm D from Knuth, TAOCP
+/// Vol. 2, 4.3.1, in the formulation of Warren's "Hacker's Delight".
+fn divrem_knuth(u: &[u32], v: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    const B: u64 = 1 << 32;
+    let n = v.len();
+    let m = u.len() - n;
+
+    // Normalize so that the top limb of the divisor has its high bit
+    // set, which keeps the quotient digit estimates close.
+    let s = v[n - 1].leading_zeros();
+    let mut vn = vec![0u32; n];
+    let mut un = vec![0u32; u.len() + 1];
+    for i in (1..n).rev() {
+        vn[i] = (v[i] << s) | if s == 0 { 0 } else { v[i - 1] >> (32 - s) };
+    }
+    vn[0] = v[0] << s;
+    un[u.len()] = if s == 0 { 0 } else { u[u.len() - 1] >> (32 - s) };
+    for i in (1..u.len()).rev() {
+        un[i] = (u[i] << s) | if s == 0 { 0 } else { u[i - 1] >> (32 - s) };
+    }
+    un[0] = u[0] << s;
+
+    let mut q = vec![0u32; m + 1];
+    for j in (0..m + 1).rev() {
+        let num = (un[j + n] as u64) << 32 | un[j + n - 1] as u64;
+        let mut qhat = num / vn[n - 1] as u64;
+        let mut rhat = num % vn[n - 1] as u64;
+        while qhat >= B || qhat * vn[n - 2] as u64 > (rhat << 32) + un[j + n - 2] as u64 {
+            qhat -= 1;
+            rhat += vn[n - 1] as u64;
+            if rhat >= B {
+                break;
+            }
+        }
+
+        // Multiply and subtract.
+        let mut k: i64 = 0;
+        let mut t: i64;
+        for i in 0..n {
+            let p = qhat * vn[i] as u64;
+            t = un[i + j] as i64 - k - (p & 0xffff_ffff) as i64;
+            un[i + j] = t as u32;
+            k = (p >> 32) as i64 - (t >> 32);
+        }
+        t = un[j + n] as i64 - k;
+        un[j + n] = t as u32;
+
+        q[j] = qhat as u32;
+        if t < 0 {
+            // Subtracted too much, add back.
+            q[j] = q[j].wrapping_sub(1);
+            let mut c = 0u64;
+            for i in 0..n {
+                let sum = un[i + j] as u64 + vn[i] as u64 + c;
+                un[i + j] = sum as u32;
+                c = sum >> 32;
+            }
+            un[j + n] = un[j + n].wrapping_add(c as u32);
+        }
+    }
+
+    let mut r = vec![0u32; n];
+    for i in 0..n {
+        r[i] = (un[i] >> s) | if s == 0 { 0 } else { un[i + 1] << (32 - s) };
+    }
+    (q, r)
+}
+
+impl PartialOrd for BigUint {
+    fn partial_cmp(&self, other: &BigUint) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BigUint {
+    fn cmp(&self, other: &BigUint) -> Ordering {
+        match self.limbs.len().cmp(&other.limbs.len()) {
+            Ordering::Equal =>
+                self.limbs.iter().rev().cmp(other.limbs.iter().rev()),
+            o => o,
+        }
+    }
+}
+
+impl<'a, 'b> Add<&'b BigUint> for &'a BigUint {
+    type Output = BigUint;
+
+    fn add(self, other: &'b BigUint) -> BigUint {
+        let (long, short) = if self.limbs.len() >= other.limbs.len() {
+            (&self.limbs, &other.limbs)
+        } else {
+            (&other.limbs, &self.limbs)
+        };
+        let mut res = Vec::with_capacity(long.len() + 1);
+        let mut carry = 0u64;
+        for i in 0..long.len() {
+            let sum = long[i] as u64 + *short.get(i).unwrap_or(&0) as u64 + carry;
+            res.push(sum as u32);
+            carry = sum >> 32;
+        }
+        res.push(carry as u32);
+        BigUint::from_limbs(res)
+    }
+}
+
+impl<'a, 'b> Sub<&'b BigUint> for &'a BigUint {
+    type Output = BigUint;
+
+    /// Subtract `other` from `self`.
+    ///
+    /// # Panics
+    /// Panics when `other` is larger than `self`.
+    fn sub(self, other: &'b BigUint) -> BigUint {
+        assert!(*self >= *other, "subtraction underflow");
+        let mut res = Vec::with_capacity(self.limbs.len());
+        let mut borrow = 0i64;
+        for i in 0..self.limbs.len() {
+            let mut diff = self.limbs[i] as i64 - *other.limbs.get(i).unwrap_or(&0) as i64 - borrow;
+            if diff < 0 {
+                diff += 1 << 32;
+                borrow = 1;
+            } else {
+                borrow = 0;
+            }
+            res.push(diff as u32);
+        }
+        BigUint::from_limbs(res)
+    }
+}
+
+impl<'a, 'b> Mul<&'b BigUint> for &'a BigUint {
+    type Output = BigUint;
+
+    fn mul(self, other: &'b BigUint) -> BigUint {
+        if self.is_zero() || other.is_zero() {
+            return BigUint::zero();
+        }
+        let mut res = vec![0u32; self.limbs.len() + other.limbs.len()];
+        for (i, &a) in self.limbs.iter().enumerate() {
+            let mut carry = 0u64;
+            for (j, &b) in other.limbs.iter().enumerate() {
+                let t = a as u64 * b as u64 + res[i + j] as u64 + carry;
+                res[i + j] = t as u32;
+                carry = t >> 32;
+            }
+            res[i + other.limbs.len()] = carry as u32;
+        }
+        BigUint::from_limbs(res)
+    }
+}
+
+impl<'a, 'b> Div<&'b BigUint> for &'a BigUint {
+    type Output = BigUint;
+
+    fn div(self, other: &'b BigUint) -> BigUint {
+        self.divrem(other).0
+    }
+}
+
+impl<'a, 'b> Rem<&'b BigUint> for &'a BigUint {
+    type Output = BigUint;
+
+    fn rem(self, other: &'b BigUint) -> BigUint {
+        self.divrem(other).1
+    }
+}
+
+impl<'a> Shl<usize> for &'a BigUint {
+    type Output = BigUint;
+
+    fn shl(self, n: usize) -> BigUint {
+        let limbs = n / 32;
+        let bits = n % 32;
+        let mut res = vec![0u32; limbs];
+        let mut carry = 0u32;
+        for &l in &self.limbs {
+            if bits == 0 {
+                res.push(l);
+            } else {
+                res.push((l << bits) | carry);
+                carry = l >> (32 - bits);
+            }
+        }
+        res.push(carry);
+        BigUint::from_limbs(res)
+    }
+}
+
+impl<'a> Shr<usize> for &'a BigUint {
+    type Output = BigUint;
+
+    fn shr(self, n: usize) -> BigUint {
+        let limbs = n / 32;
+        let bits = n % 32;
+        if limbs >= self.limbs.len() {
+            return BigUint::zero();
+        }
+        let src = &self.limbs[limbs..];
+        let mut res = Vec::with_capacity(src.len());
+        for i in 0..src.len() {
+            if bits == 0 {
+                res.push(src[i]);
+            } else {
+                let hi = src.get(i + 1).map(|h| h << (32 - bits)).unwrap_or(0);
+                res.push((src[i] >> bits) | hi);
+            }
+        }
+        BigUint::from_limbs(res)
+    }
+}
+
+/// Implement an operator for owned operands by forwarding to the
+/// implementation on references.
+macro_rules! forward_binop {
+    ($imp:ident, $method:ident) => {
+        impl $imp<BigUint> for BigUint {
+            type Output = BigUint;
+            fn $method(self, other: BigUint) -> BigUint {
+                (&self).$method(&other)
+            }
+        }
+        impl<'a> $imp<&'a BigUint> for BigUint {
+            type Output = BigUint;
+            fn $method(self, other: &'a BigUint) -> BigUint {
+                (&self).$method(other)
+            }
+        }
+        impl<'a> $imp<BigUint> for &'a BigUint {
+            type Output = BigUint;
+            fn $method(self, other: BigUint) -> BigUint {
+                self.$method(&other)
+            }
+        }
+    }
+}
+
+forward_binop!(Add, add);
+forward_binop!(Sub, sub);
+forward_binop!(Mul, mul);
+forward_binop!(Div, div);
+forward_binop!(Rem, rem);
+
+impl Shl<usize> for BigUint {
+    type Output = BigUint;
+    fn shl(self, n: usize) -> BigUint {
+        &self << n
+    }
+}
+
+impl Shr<usize> for BigUint {
+    type Output = BigUint;
+    fn shr(self, n: usize) -> BigUint {
+        &self >> n
+    }
+}
+
+impl From<u64> for BigUint {
+    fn from(n: u64) -> BigUint {
+        BigUint::from_u64(n)
+    }
+}
+
+impl fmt::Display for BigUint {
+    /// Format the number in decimal notation.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+        let mut digits = Vec::new();
+        let mut n = self.limbs.clone();
+        while !n.is_empty() {
+            let (q, r) = divrem_limb(&n, 1_000_000_000);
+            digits.push(r);
+            n = BigUint::from_limbs(q).limbs;
+        }
+        write!(f, "{}", digits[digits.len() - 1])?;
+        for d in digits.iter().rev().skip(1) {
+            write!(f, "{:09}", d)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for BigUint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "BigUint(0x{})", self.to_hex())
+    }
+}
+
+impl fmt::LowerHex for BigUint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_hex())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BigUint;
+
+    fn n(x: u64) -> BigUint {
+        BigUint::from_u64(x)
+    }
+
+    #[test]
+    fn bytes_roundtrip() {
+        let bytes = vec![0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09];
+        assert_eq!(bytes, BigUint::from_bytes_be(&bytes).to_bytes_be());
+        assert_eq!(vec![0], BigUint::zero().to_bytes_be());
+        assert_eq!(vec![1], BigUint::from_bytes_be(&[0, 0, 1]).to_bytes_be());
+        assert_eq!(vec![0, 0, 1], n(1).to_bytes_be_padded(3));
+    }
+
+    #[test]
+    fn hex_roundtrip() {
+        let h = "ffffffffffffffffc90fdaa22168c234c4c6628b80dc1cd1";
+        assert_eq!(h, BigUint::from_hex(h).unwrap().to_hex());
+        assert_eq!("abc", BigUint::from_hex("a bc").unwrap().to_hex());
+        assert_eq!("0", BigUint::zero().to_hex());
+        assert!(BigUint::from_hex("xyz").is_err());
+    }
+
+    #[test]
+    fn display_decimal() {
+        assert_eq!("0", format!("{}", BigUint::zero()));
+        assert_eq!("18446744073709551615", format!("{}", n(u64::MAX)));
+        let big = &n(u64::MAX) * &n(u64::MAX);
+        assert_eq!("340282366920938463426481119284349108225", format!("{}", big));
+    }
+
+    #[test]
+    fn arithmetic_small() {
+        assert_eq!(n(579), &n(123) + &n(456));
+        assert_eq!(n(333), &n(456) - &n(123));
+        assert_eq!(n(56088), &n(123) * &n(456));
+        assert_eq!((n(3), n(87)), n(456).divrem(&n(123)));
+        assert_eq!(n(1 << 40), &n(1) << 40);
+        assert_eq!(n(1), &n(1 << 40) >> 40);
+    }
+
+    #[test]
+    fn arithmetic_carries() {
+        let max = n(u64::MAX);
+        let sum = &max + &n(1);
+        assert_eq!("10000000000000000", sum.to_hex());
+        assert_eq!(max, &sum - &n(1));
+        let sq = &max * &max;
+        assert_eq!((max.clone(), BigUint::zero()), sq.divrem(&max));
+        let (q, r) = (&sq + &n(5)).divrem(&max);
+        assert_eq!(max, q);
+        assert_eq!(n(5), r);
+    }
+
+    #[test]
+    #[should_panic]
+    fn sub_underflow() {
+        let _ = &n(1) - &n(2);
+    }
+
+    #[test]
+    fn divrem_multi_limb() {
+        let a = BigUint::from_hex("1234567890abcdef1234567890abcdef1234567890abcdef").unwrap();
+        let b = BigUint::from_hex("fedcba0987654321fedcba09").unwrap();
+        let (q, r) = a.divrem(&b);
+        assert!(r < b);
+        assert_eq!(a, &(&q * &b) + &r);
+    }
+
+    #[test]
+    fn modpow_modinv_gcd() {
+        assert_eq!(n(445), n(4).modpow(&n(13), &n(497)));
+        assert_eq!(n(1), n(7).pow(0));
+        assert_eq!(n(1594323), n(3).pow(13));
+        assert_eq!(Some(n(2753)), n(17).modinv(&n(3120)));
+        assert_eq!(None, n(6).modinv(&n(9)));
+        assert_eq!(n(6), n(54).gcd(&n(24)));
+        let p = BigUint::from_hex("ffffffffffffffc5").unwrap();
+        let x = BigUint::from_hex("123456789abcdef").unwrap();
+        // Fermat's little theorem.
+        assert_eq!(BigUint::one(), x.modpow(&(&p - &n(1)), &p));
+        let inv = x.modinv(&p).unwrap();
+        assert_eq!(BigUint::one(), &(&x * &inv) % &p);
+    }
+
+    #[test]
+    fn random_below_bound() {
+        let bound = n(1000);
+        for _ in 0..100 {
+            assert!(BigUint::random_below(&bound) < bound);
+        }
+    }
+
+    quickcheck! {
+        fn prop_divrem(a: Vec<u8>, b: Vec<u8>) -> bool {
+            let a = BigUint::from_bytes_be(&a);
+            let b = BigUint::from_bytes_be(&b);
+            if b.is_zero() {
+                return true;
+            }
+            let (q, r) = a.divrem(&b);
+            r < b && a == &(&q * &b) + &r
+        }
+
+        fn prop_add_sub(a: Vec<u8>, b: Vec<u8>) -> bool {
+            let a = BigUint::from_bytes_be(&a);
+            let b = BigUint::from_bytes_be(&b);
+            &(&a + &b) - &b == a
+        }
+
+        fn prop_shift(a: Vec<u8>, s: u8) -> bool {
+            let a = BigUint::from_bytes_be(&a);
+            &(&a << s as usize) >> s as usize == a
+        }
+    }
+}
@@ -1,14 +0,0 @@
-// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
-// top-level directory of this distribution for license information.
-
-extern crate cryptopals;
-
-use cryptopals::codec;
-
-pub fn main() {
-
-    let input = "49276d206b696c6c696e6720796f757220627261696e206c696b65206120706f69736f6e6f7573206d757368726f6f6d";
-    let expected_output = "SSdtIGtpbGxpbmcgeW91ciBicmFpbiBsaWtlIGEgcG9pc29ub3VzIG11c2hyb29t";
-    assert_eq!(codec::base64::encode(&codec::hex::decode(input).unwrap()), expected_output);
-    println!("Success.");
-}
@@ -0,0 +1,206 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! The `aes` command, which encrypts or decrypts standard input to
+//! standard output with the crate's AES.
+//!
+//! Keys and IVs are given as hex strings, like `openssl enc -K ...
+//! -iv ...`, or read as raw bytes from a file.  ECB and CBC use
+//! PKCS#7 padding, and CTR increments the whole 128-bit counter
+//! block, so the output can be compared with, for example,
+//!
+//! ```text
+//! openssl enc -aes-128-cbc -K $KEY -iv $IV -a -A
+//! cryptopals aes --mode cbc --key $KEY --iv $IV --armor base64
+//! ```
+//!
+//! Like `openssl enc -d`, decryption in ECB and CBC mode fails with a
+//! non-zero exit code if the ciphertext is empty, not whole blocks, or
+//! has invalid padding, which usually means the key or IV is wrong.
+//!
+//! With `--envelope`, the ciphertext is wrapped in the binary format
+//! of `cipher::envelope`, which records the mode and a random IV and,
+//! with `--mac-key`, adds a tag.  Decryption then takes the mode and
+//! IV from the envelope.
+
+use std::fs::File;
+use std::io::{self, Read, Write};
+
+use cryptopals::codec;
+use cryptopals::cipher::aes::{self, AesKey, CtrOverflow, CtrParams};
+use cryptopals::cipher::envelope;
+use cryptopals::error::Error;
+use cryptopals::padding::PaddingScheme;
+
+use crate::Failure;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Ecb,
+    Cbc,
+    Ctr,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Armor {
+    None,
+    Base64,
+    Hex,
+}
+
+fn usage<T, S: Into<String>>(msg: S) -> Result<T, Failure> {
+    Err(Failure::Usage(msg.into()))
+}
+
+fn read_file(name: &str) -> Result<Vec<u8>, Failure> {
+    let mut bytes = Vec::new();
+    File::open(name)?.read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Read standard input, removing `armor`.
+fn read_input(armor: Armor) -> Result<Vec<u8>, Failure> {
+    let stdin = io::stdin();
+    let mut input = Vec::new();
+    match armor {
+        Armor::None => {
+            stdin.lock().read_to_end(&mut input)?;
+        }
+        Armor::Base64 => {
+            codec::base64::Reader::new(stdin.lock()).read_to_end(&mut input)?;
+        }
+        Armor::Hex => {
+            let mut text = String::new();
+            stdin.lock().read_to_string(&mut text)?;
+            input = codec::hex::decode_lenient(text.trim())?;
+        }
+    }
+    Ok(input)
+}
+
+/// Write `output` to standard output with `armor`.  Armored output is
+/// terminated by a newline.
+fn write_output(armor: Armor, output: &[u8]) -> Result<(), Failure> {
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    match armor {
+        Armor::None => out.write_all(output)?,
+        Armor::Base64 => {
+            let mut w = codec::base64::Writer::new(out);
+            w.write_all(output)?;
+            out = w.finish()?;
+            out.write_all(b"\n")?;
+        }
+        Armor::Hex => writeln!(out, "{}", codec::hex::encode(output))?,
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// Run `cryptopals aes` with the arguments `args`.
+pub fn run(args: &[String]) -> Result<(), Failure> {
+    let mut decrypt = false;
+    let mut mode = Mode::Cbc;
+    let mut armor = Armor::None;
+    let mut key = None;
+    let mut iv = None;
+    let mut sealed = false;
+    let mut mac_key = None;
+
+    let mut i = 0;
+    while i < args.len() {
+        let value = || match args.get(i + 1) {
+            Some(v) => Ok(v.as_str()),
+            None => usage(format!("option {} needs a value", args[i])),
+        };
+        match args[i].as_str() {
+            "-e" | "--encrypt" => {
+                decrypt = false;
+                i += 1;
+                continue;
+            }
+            "-d" | "--decrypt" => {
+                decrypt = true;
+                i += 1;
+                continue;
+            }
+            "--envelope" => {
+                sealed = true;
+                i += 1;
+                continue;
+            }
+            "--mode" => mode = match value()? {
+                "ecb" => Mode::Ecb,
+                "cbc" => Mode::Cbc,
+                "ctr" => Mode::Ctr,
+                m => return usage(format!("unknown mode: {}", m)),
+            },
+            "--armor" => armor = match value()? {
+                "none" => Armor::None,
+                "base64" => Armor::Base64,
+                "hex" => Armor::Hex,
+                a => return usage(format!("unknown armor: {}", a)),
+            },
+            "--key" => key = Some(codec::hex::decode_lenient(value()?)?),
+            "--key-file" => key = Some(read_file(value()?)?),
+            "--iv" => iv = Some(codec::hex::decode_lenient(value()?)?),
+            "--iv-file" => iv = Some(read_file(value()?)?),
+            "--mac-key" => mac_key = Some(codec::hex::decode_lenient(value()?)?),
+            opt => return usage(format!("unknown option for aes: {}", opt)),
+        }
+        i += 2;
+    }
+
+    let key = match key {
+        Some(k) => AesKey::from_slice(&k)?,
+        None => return usage("aes needs --key or --key-file"),
+    };
+    if sealed {
+        if iv.is_some() {
+            return usage("--envelope chooses a random IV");
+        }
+        let mac_key = mac_key.as_ref().map(|k| &k[..]);
+        let input = read_input(if decrypt { armor } else { Armor::None })?;
+        return if decrypt {
+            write_output(Armor::None, &envelope::open(&key, mac_key, &input)?)
+        } else {
+            let env_mode = match mode {
+                Mode::Ecb => envelope::Mode::Ecb,
+                Mode::Cbc => envelope::Mode::Cbc,
+                Mode::Ctr => envelope::Mode::Ctr,
+            };
+            write_output(armor, &envelope::seal(&key, env_mode, mac_key, &input))
+        };
+    }
+    if mac_key.is_some() {
+        return usage("--mac-key needs --envelope");
+    }
+    let iv = match (mode, iv) {
+        (Mode::Ecb, None) => [0u8; 16],
+        (Mode::Ecb, Some(_)) => return usage("ECB mode takes no IV"),
+        (_, None) => return usage("CBC and CTR mode need --iv or --iv-file"),
+        (_, Some(ref v)) if v.len() != 16 => return usage(format!("IV must be 16 bytes, not {}", v.len())),
+        (_, Some(v)) => {
+            let mut iv = [0u8; 16];
+            iv.copy_from_slice(&v);
+            iv
+        }
+    };
+    let ctr = CtrParams { overflow: CtrOverflow::Carry };
+
+    // Armor applies to the ciphertext, which is the input when
+    // decrypting and the output when encrypting.
+    let input = read_input(if decrypt { armor } else { Armor::None })?;
+    if decrypt && mode != Mode::Ctr && input.is_empty() {
+        return Err(Failure::Error(Error::InvalidCiphertextLength { len: 0, block_size: 16 }));
+    }
+    let output = match (mode, decrypt) {
+        (Mode::Ecb, false) => aes::encrypt_ecb(&key, &input),
+        (Mode::Ecb, true) => aes::decrypt_ecb_with(&key, &input, &PaddingScheme::Pkcs7)?,
+        (Mode::Cbc, false) => aes::encrypt_cbc(&key, &iv, &input),
+        (Mode::Cbc, true) => aes::decrypt_cbc_with(&key, &iv, &input, &PaddingScheme::Pkcs7)?,
+        (Mode::Ctr, false) => aes::encrypt_ctr_with(&key, &iv, &input, &ctr),
+        (Mode::Ctr, true) => aes::decrypt_ctr_with(&key, &iv, &input, &ctr),
+    };
+    write_output(if decrypt { Armor::None } else { armor }, &output)
+}
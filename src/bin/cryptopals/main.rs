@@ -0,0 +1,301 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! Command-line driver for the crate.
+//!
+//! ```text
+//! cryptopals [--data-dir DIR] solve SET CHALLENGE
+//! cryptopals encode (base64|base32|base58|hex|url|bin) [--decode]
+//! cryptopals crack (xor-single|xor-repeating|keystream-reuse|two-time-pad) PATH
+//! cryptopals aes [-d] [--mode ecb|cbc|ctr] (--key HEX|--key-file FILE)
+//!                [--iv HEX|--iv-file FILE] [--armor none|base64|hex]
+//!                [--envelope [--mac-key HEX]]
+//! ```
+//!
+//! `solve` runs the solution of a single challenge, reading its input
+//! files from the data directory (`data` by default, or the copies
+//! compiled in with the `bundled-data` feature).  `encode`
+//! filters standard input to standard output.  `crack` runs one of the
+//! generic attacks on the contents of a file, which must be
+//! hex-encoded lines for `xor-single` and BASE64 for `xor-repeating`.
+//! `keystream-reuse` and `two-time-pad` read every file of a
+//! directory as one raw ciphertext, or a file of hex-encoded lines.
+//! `two-time-pad` is interactive, see the `two_time_pad` module.
+//! `aes` encrypts or decrypts standard input to standard output.
+
+mod aes;
+mod solve;
+mod two_time_pad;
+
+use std::env;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+
+use cryptopals::{attack, codec, data, language, xor};
+use cryptopals::xor::Scorer;
+use cryptopals::error::Error;
+
+const USAGE: &'static str = "\
+Usage: cryptopals [--data-dir DIR] COMMAND [ARGS...]
+
+Commands:
+    solve SET CHALLENGE            run the solution of a challenge
+    encode ENCODING [--decode]     encode or decode stdin to stdout;
+                                   ENCODING is one of base64, base32,
+                                   base58, hex, url, bin
+    crack xor-single FILE          find the line of hex-encoded FILE
+                                   encrypted with single-byte XOR
+    crack xor-repeating FILE       break repeating-key XOR on the
+                                   base64-encoded FILE
+    crack keystream-reuse PATH     find the files in directory PATH,
+                                   or hex-encoded lines of file PATH,
+                                   encrypted with the same keystream
+    crack two-time-pad PATH        recover messages encrypted with
+                                   the same keystream interactively
+    aes [OPTIONS]                  encrypt or decrypt stdin to stdout:
+        -e, --encrypt              encrypt (the default)
+        -d, --decrypt              decrypt
+        --mode ecb|cbc|ctr         cipher mode (default: cbc)
+        --key HEX, --key-file FILE key, as hex or raw bytes in FILE
+        --iv HEX, --iv-file FILE   IV for CBC and CTR mode
+        --armor none|base64|hex    encoding of the ciphertext
+                                   (default: none)
+        --envelope                 self-describing ciphertext with
+                                   mode and random IV
+        --mac-key HEX              authenticate the envelope
+    help                           print this message
+
+Options:
+    --data-dir DIR                 directory with the challenge data
+                                   files (default: data, or the
+                                   bundled files if built with the
+                                   bundled-data feature)";
+
+/// Reasons for a command to fail.
+#[derive(Debug)]
+pub enum Failure {
+    /// The command line is malformed.
+    Usage(String),
+    /// A solution did not produce the expected result.
+    Wrong(String),
+    /// An error from the library.
+    Error(Error),
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Failure::Usage(ref msg) => write!(f, "{}", msg),
+            Failure::Wrong(ref msg) => write!(f, "wrong result: {}", msg),
+            Failure::Error(ref err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl From<Error> for Failure {
+    fn from(err: Error) -> Failure {
+        Failure::Error(err)
+    }
+}
+
+impl From<io::Error> for Failure {
+    fn from(err: io::Error) -> Failure {
+        Failure::Error(Error::Io(err))
+    }
+}
+
+#[cfg(feature = "bundled-data")]
+fn bundled(set: usize, number: usize) -> Option<&'static str> {
+    data::challenge(set, number)
+}
+
+#[cfg(not(feature = "bundled-data"))]
+fn bundled(_set: usize, _number: usize) -> Option<&'static str> {
+    None
+}
+
+/// Settings shared by all commands.
+pub struct Context {
+    /// Directory holding the challenge data files, if given with
+    /// `--data-dir`.
+    pub data_dir: Option<PathBuf>,
+}
+
+impl Context {
+    /// Return the contents of the data file of challenge `number` in
+    /// set `set`.  Without `--data-dir`, use the bundled copy if the
+    /// `bundled-data` feature is enabled, and `data` otherwise.
+    pub fn data(&self, set: usize, number: usize) -> Result<String, Failure> {
+        if self.data_dir.is_none() {
+            if let Some(s) = bundled(set, number) {
+                return Ok(s.to_string());
+            }
+        }
+        let dir = self.data_dir.clone().unwrap_or(PathBuf::from("data"));
+        let mut s = String::new();
+        File::open(dir.join(format!("{}.txt", number)))?.read_to_string(&mut s)?;
+        Ok(s)
+    }
+
+    /// Return the data file of a challenge, decoded from BASE64.
+    pub fn base64(&self, set: usize, number: usize) -> Result<Vec<u8>, Failure> {
+        Ok(codec::base64::decode(&self.data(set, number)?)?)
+    }
+
+    /// Return the lines of the data file of a challenge, decoded from
+    /// hex.
+    pub fn hex_lines(&self, set: usize, number: usize) -> Result<Vec<Vec<u8>>, Failure> {
+        Ok(data::parse_hex_lines(&self.data(set, number)?)?)
+    }
+}
+
+fn usage<T, S: Into<String>>(msg: S) -> Result<T, Failure> {
+    Err(Failure::Usage(msg.into()))
+}
+
+fn encode(args: &[String]) -> Result<(), Failure> {
+    let (name, decode) = match args.len() {
+        1 => (&args[0], false),
+        2 if args[1] == "--decode" || args[1] == "-d" => (&args[0], true),
+        _ => return usage("encode expects an encoding and an optional --decode"),
+    };
+    let mut input = Vec::new();
+    io::stdin().read_to_end(&mut input)?;
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    if decode {
+        let text = String::from_utf8_lossy(&input);
+        let text = text.trim();
+        let bytes = match name.as_str() {
+            "base64" => codec::base64::decode(text)?,
+            "base32" => codec::base32::decode(text)?,
+            "base58" => codec::base58::decode(text)?,
+            "hex" => codec::hex::decode_lenient(text)?,
+            "url" => codec::url::decode(text)?,
+            "bin" => codec::bin::decode(text)?,
+            _ => return usage(format!("unknown encoding: {}", name)),
+        };
+        out.write_all(&bytes)?;
+    } else {
+        let text = match name.as_str() {
+            "base64" => codec::base64::encode(&input),
+            "base32" => codec::base32::encode(&input),
+            "base58" => codec::base58::encode(&input),
+            "hex" => codec::hex::encode(&input),
+            "url" => codec::url::encode(&input),
+            "bin" => codec::bin::encode(&input),
+            _ => return usage(format!("unknown encoding: {}", name)),
+        };
+        writeln!(out, "{}", text)?;
+    }
+    Ok(())
+}
+
+/// Print the letter statistics of `plaintext` if it does not score
+/// as English, to show why the crack probably failed.
+fn diagnose(plaintext: &[u8]) {
+    if xor::English.score(plaintext) < xor::THRESHOLD {
+        println!("the result does not look like English:\n{}", language::analyze(plaintext));
+    }
+}
+
+/// Load the ciphertexts for the stream cipher attacks: every file in
+/// the directory `path`, or the hex-encoded lines of the file `path`.
+/// Returns names for the ciphertexts and the ciphertexts.
+fn load_ciphertexts(path: &str) -> Result<(Vec<String>, Vec<Vec<u8>>), Failure> {
+    if Path::new(path).is_dir() {
+        Ok(data::load_dir(path)?.into_iter().map(|(p, c)| (p.display().to_string(), c)).unzip())
+    } else {
+        let lines = data::load_hex_lines(path)?;
+        Ok(((0..lines.len()).map(|i| format!("{}:{}", path, i + 1)).collect(), lines))
+    }
+}
+
+fn crack(args: &[String]) -> Result<(), Failure> {
+    if args.len() != 2 {
+        return usage("crack expects an attack and a path");
+    }
+    match args[0].as_str() {
+        "xor-single" => {
+            let lines = data::load_hex_lines(&args[1])?;
+            match xor::detect_single_byte_xor(&lines).first() {
+                Some(&(i, k, ref decrypted, score)) => {
+                    println!("line {}: key {:#04x} (score {:.3}): {:?}", i, k, score,
+                             String::from_utf8_lossy(decrypted));
+                    diagnose(decrypted);
+                }
+                None => println!("no candidate found"),
+            }
+        }
+        "xor-repeating" => {
+            let c = data::load_base64_file(&args[1])?;
+            let results = xor::crack_repeating_xor(&c, 4);
+            println!("{}", results);
+            for &(ref key, ref decoded) in results.result.iter().take(1) {
+                println!("key: {:?}", String::from_utf8_lossy(key));
+                println!("decoded: {}", String::from_utf8_lossy(decoded));
+                diagnose(decoded);
+            }
+        }
+        "keystream-reuse" => {
+            let (paths, ciphertexts) = load_ciphertexts(&args[1])?;
+            let found = attack::detect_keystream_reuse(&ciphertexts);
+            if found.is_empty() {
+                println!("no keystream reuse found");
+            }
+            for &(i, j, score) in &found {
+                println!("{} {} (score {:.3})", paths[i], paths[j], score);
+            }
+        }
+        "two-time-pad" => two_time_pad::run(load_ciphertexts(&args[1])?.1)?,
+        attack => return usage(format!("unknown attack: {}", attack)),
+    }
+    Ok(())
+}
+
+fn run(args: Vec<String>) -> Result<(), Failure> {
+    let mut ctx = Context { data_dir: None };
+    let mut args = &args[..];
+    while !args.is_empty() && args[0].starts_with("--") {
+        match args[0].as_str() {
+            "--data-dir" if args.len() > 1 => {
+                ctx.data_dir = Some(PathBuf::from(&args[1]));
+                args = &args[2..];
+            }
+            "--help" => args = &[],
+            opt => return usage(format!("unknown option: {}", opt)),
+        }
+    }
+    if args.is_empty() {
+        println!("{}", USAGE);
+        return Ok(());
+    }
+    match args[0].as_str() {
+        "solve" => solve::run(&ctx, &args[1..]),
+        "encode" => encode(&args[1..]),
+        "crack" => crack(&args[1..]),
+        "aes" => aes::run(&args[1..]),
+        "help" => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        cmd => usage(format!("unknown command: {}", cmd)),
+    }
+}
+
+pub fn main() {
+    match run(env::args().skip(1).collect()) {
+        Ok(()) => {}
+        Err(Failure::Usage(msg)) => {
+            let _ = writeln!(io::stderr(), "cryptopals: {}\n\n{}", msg, USAGE);
+            process::exit(2);
+        }
+        Err(err) => {
+            let _ = writeln!(io::stderr(), "cryptopals: {}", err);
+            process::exit(1);
+        }
+    }
+}
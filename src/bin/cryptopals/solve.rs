@@ -0,0 +1,196 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! Solutions of the individual challenges, run by `cryptopals solve
+//! SET CHALLENGE`.
+
+use cryptopals::{codec, random, xor};
+use cryptopals::attack::ecb;
+use cryptopals::cipher::aes;
+use cryptopals::padding::pkcs7;
+
+use crate::{Context, Failure};
+
+/// Run the solution of challenge `args[1]` in set `args[0]`.
+pub fn run(ctx: &Context, args: &[String]) -> Result<(), Failure> {
+    if args.len() != 2 {
+        return Err(Failure::Usage("solve expects a set and a challenge number".into()));
+    }
+    let set: usize = args[0].parse().map_err(|_| Failure::Usage(format!("invalid set: {}", args[0])))?;
+    let challenge: usize = args[1].parse()
+                                .map_err(|_| Failure::Usage(format!("invalid challenge: {}", args[1])))?;
+    match (set, challenge) {
+        (1, 1) => challenge_1_1(),
+        (1, 2) => challenge_1_2(),
+        (1, 3) => challenge_1_3(),
+        (1, 4) => challenge_1_4(ctx),
+        (1, 5) => challenge_1_5(),
+        (1, 6) => challenge_1_6(ctx),
+        (1, 7) => challenge_1_7(ctx),
+        (1, 8) => challenge_1_8(ctx),
+        (2, 9) => challenge_2_9(),
+        (2, 10) => challenge_2_10(ctx),
+        (2, 11) => challenge_2_11(),
+        (2, 12) => challenge_2_12(),
+        _ => Err(Failure::Usage(format!("no solution for challenge {} in set {}", challenge, set))),
+    }
+}
+
+fn check<T: PartialEq + ::std::fmt::Debug>(expected: T, actual: T) -> Result<(), Failure> {
+    if expected == actual {
+        println!("Success.");
+        Ok(())
+    } else {
+        Err(Failure::Wrong(format!("expected {:?}, got {:?}", expected, actual)))
+    }
+}
+
+fn challenge_1_1() -> Result<(), Failure> {
+    let input = "49276d206b696c6c696e6720796f757220627261696e206c696b65206120706f69736f6e6f7573206d757368726f6f6d";
+    let expected = "SSdtIGtpbGxpbmcgeW91ciBicmFpbiBsaWtlIGEgcG9pc29ub3VzIG11c2hyb29t";
+    check(expected.to_string(), codec::base64::encode(&codec::hex::decode(input)?))
+}
+
+fn challenge_1_2() -> Result<(), Failure> {
+    let b0 = codec::hex::decode("1c0111001f010100061a024b53535009181c")?;
+    let b1 = codec::hex::decode("686974207468652062756c6c277320657965")?;
+    let expected = codec::hex::decode("746865206b696420646f6e277420706c6179")?;
+    check(expected, xor::xor_bytes(&b0, &b1)?)
+}
+
+fn challenge_1_3() -> Result<(), Failure> {
+    let input = codec::hex::decode("1b37373331363f78151b7f2b783431333d78397828372d363c78373e783a393b3736")?;
+    match xor::crack_single_byte_xor(&input) {
+        Some(res) => {
+            println!("{}", res);
+            check((88, b"Cooking MC's like a pound of bacon".to_vec()), res.result)
+        }
+        None => Err(Failure::Wrong("no key found".into())),
+    }
+}
+
+fn challenge_1_4(ctx: &Context) -> Result<(), Failure> {
+    let lines = ctx.hex_lines(1, 4)?;
+    if let Some(&(i, k, ref decrypted, _)) = xor::detect_single_byte_xor(&lines).first() {
+        println!("#{}: {:x}: {:?}", i, k, String::from_utf8_lossy(decrypted));
+    }
+    Ok(())
+}
+
+fn challenge_1_5() -> Result<(), Failure> {
+    let input = b"Burning 'em, if you ain't quick and nimble\nI go crazy when I hear a cymbal";
+    let key = b"ICE";
+    let expected = codec::hex::decode("0b3637272a2b2e63622c2e69692a23693a2a3c6324202d623d63343c2a26226324272765272a282b2f20430a652e2c652a3124333a653e2b2027630c692b20283165286326302e27282f")?;
+    check(expected, xor::repeating(key, input))
+}
+
+fn challenge_1_6(ctx: &Context) -> Result<(), Failure> {
+    let c = ctx.base64(1, 6)?;
+
+    let mut progress = |e: xor::Event| if let xor::Event::KeySize { keysize, .. } = e {
+        println!("keysize: {}", keysize);
+    };
+    let results = xor::crack_repeating_xor_with(&c, 4, &xor::English, Some(&mut progress));
+    println!("{}", results);
+    for &(ref key, ref decoded) in results.result.iter().take(1) {
+        println!("key: {:?}", String::from_utf8_lossy(key));
+        println!("decoded: {}", String::from_utf8_lossy(decoded));
+    }
+    Ok(())
+}
+
+fn challenge_1_7(ctx: &Context) -> Result<(), Failure> {
+    let c = ctx.base64(1, 7)?;
+
+    let key = aes::AesKey::from_slice(b"YELLOW SUBMARINE")?;
+    let decrypted = aes::decrypt_ecb(&key, &c)?;
+    println!("{}", String::from_utf8_lossy(&decrypted));
+    Ok(())
+}
+
+fn challenge_1_8(ctx: &Context) -> Result<(), Failure> {
+    for (i, decoded) in ctx.hex_lines(1, 8)?.into_iter().enumerate() {
+        if let Some((block_size, score)) = ecb::detect_ecb_block_size(&decoded) {
+            println!("#{}: repeated {}-byte blocks (duplicate ratio {:.2})", i, block_size, score);
+            print!("{}", codec::hexdump(&decoded));
+        }
+    }
+    Ok(())
+}
+
+fn challenge_2_9() -> Result<(), Failure> {
+    let output = pkcs7::pad(b"YELLOW SUB", 16)?;
+    check(b"YELLOW SUB\x06\x06\x06\x06\x06\x06".to_vec(), output)
+}
+
+fn challenge_2_10(ctx: &Context) -> Result<(), Failure> {
+    let c = ctx.base64(2, 10)?;
+
+    let key = aes::AesKey::from_slice(b"YELLOW SUBMARINE")?;
+    let iv = [0u8; 16];
+    let decrypted = aes::decrypt_cbc(&key, &iv, &c)?;
+    println!("{}", String::from_utf8_lossy(&decrypted));
+    Ok(())
+}
+
+fn random_aes_key() -> aes::AesKey {
+    let mut k = [0u8; 16];
+    random::fill_bytes(&mut k[..]);
+    aes::AesKey::from(k)
+}
+
+fn rand_vec(low: usize, high: usize) -> Vec<u8> {
+    let mut result = vec![0u8; random::gen_range(low, high)];
+    random::fill_bytes(&mut result);
+    result
+}
+
+/// Encrypt `input` between random padding under a random key, with
+/// ECB or CBC chosen at random.  Return whether ECB was used, too.
+fn encryption_oracle(input: &[u8]) -> (bool, Vec<u8>) {
+    let key = random_aes_key();
+    let ecb = random::gen();
+    let mut data = rand_vec(5, 11);
+    data.extend(input);
+    data.extend(rand_vec(5, 11));
+    if ecb {
+        (true, aes::encrypt_ecb(&key, &data))
+    } else {
+        let mut iv = [0u8; 16];
+        random::fill_bytes(&mut iv[..]);
+        (false, aes::encrypt_cbc(&key, &iv, &data))
+    }
+}
+
+fn challenge_2_11() -> Result<(), Failure> {
+    let input = [0u8; 48];
+    for trial in 0..20 {
+        let (ecb, ciphertext) = encryption_oracle(&input);
+        let detected = aes::detect_ecb(&ciphertext);
+        println!("Trial #{}: {}detected ECB", trial, if detected { "" } else { "NOT " });
+        if detected != ecb {
+            return Err(Failure::Wrong(format!("trial #{} misdetected", trial)));
+        }
+    }
+    println!("Success.");
+    Ok(())
+}
+
+fn challenge_2_12_oracle(input: &[u8]) -> Vec<u8> {
+    let key = aes::AesKey::from([108, 160, 83, 138, 150, 88, 223, 10, 240, 46, 58, 98, 81, 221, 74, 211]);
+    let suffix = codec::base64::decode("Um9sbGluJyBpbiBteSA1LjAKV2l0aCBteSByYWctdG9wIGRvd24gc28gbXkg\
+                                        aGFpciBjYW4gYmxvdwpUaGUgZ2lybGllcyBvbiBzdGFuZGJ5IHdhdmluZyBq\
+                                        dXN0IHRvIHNheSBoaQpEaWQgeW91IHN0b3A/IE5vLCBJIGp1c3QgZHJvdmUg\
+                                        YnkK").unwrap();
+    let mut data = Vec::with_capacity(suffix.len() + input.len());
+    data.extend(input);
+    data.extend(suffix);
+    aes::encrypt_ecb(&key, &data)
+}
+
+fn challenge_2_12() -> Result<(), Failure> {
+    let report = ecb::decrypt_suffix(challenge_2_12_oracle);
+    println!("{}", report);
+    println!("Decoded: {}", String::from_utf8_lossy(&report.result));
+    Ok(())
+}
@@ -0,0 +1,127 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! The `crack two-time-pad` command, an interactive front end for
+//! `attack::two_time_pad::Solver`.  It shows the recovered parts of
+//! all messages and reads commands from standard input, one per line:
+//!
+//! ```text
+//! c MSG OFFSET TEXT    confirm that message MSG has TEXT at OFFSET
+//! k POS                show the keystream candidates for POS
+//! s POS BYTE           confirm the keystream byte BYTE (hex) at POS
+//! g                    show the best guesses for all messages
+//! u                    undo the last confirmation
+//! q                    quit
+//! ```
+
+use std::io::{self, BufRead, Write};
+
+use cryptopals::attack::two_time_pad::Solver;
+use cryptopals::codec::hex;
+
+use crate::Failure;
+
+const HELP: &'static str = "\
+c MSG OFFSET TEXT    confirm that message MSG has TEXT at OFFSET
+k POS                show the keystream candidates for POS
+s POS BYTE           confirm the keystream byte BYTE (hex) at POS
+g                    show the best guesses for all messages
+u                    undo the last confirmation
+q                    quit";
+
+/// Print the confirmed plaintext of every message.
+fn show(solver: &Solver) {
+    for i in 0..solver.messages() {
+        println!("{:3}: {}", i, solver.render(i));
+    }
+    println!("{} of {} keystream bytes confirmed", solver.confirmed(), solver.len());
+}
+
+/// Print the best guess for every message, replacing unprintable
+/// bytes with `.`.
+fn show_guesses(solver: &Solver) {
+    for i in 0..solver.messages() {
+        let guess: String = solver.guess(i).iter()
+            .map(|&b| if (0x20..0x7f).contains(&b) { b as char } else { '.' })
+            .collect();
+        println!("{:3}: {}", i, guess);
+    }
+}
+
+fn parse_number(s: Option<&str>) -> Result<usize, String> {
+    s.and_then(|s| s.parse().ok()).ok_or_else(|| "expected a number".to_string())
+}
+
+fn parse_position(solver: &Solver, s: Option<&str>) -> Result<usize, String> {
+    let pos = parse_number(s)?;
+    if pos >= solver.len() {
+        return Err(format!("position must be less than {}", solver.len()));
+    }
+    Ok(pos)
+}
+
+/// Execute the command `line`.  Returns `Ok(false)` when the user
+/// wants to quit, and a message for malformed commands.
+fn command(solver: &mut Solver, line: &str) -> Result<bool, String> {
+    let mut words = line.splitn(2, ' ');
+    let cmd = words.next().unwrap_or("");
+    let rest = words.next().unwrap_or("");
+    match cmd {
+        "" => (),
+        "c" => {
+            let mut args = rest.splitn(3, ' ');
+            let message = parse_number(args.next())?;
+            let offset = parse_number(args.next())?;
+            let crib = args.next().ok_or_else(|| "expected a crib".to_string())?;
+            solver.confirm(message, offset, crib.as_bytes()).map_err(|e| e.to_string())?;
+            show(solver);
+        }
+        "k" => {
+            let pos = parse_position(solver, rest.split_whitespace().next())?;
+            for &(key, score) in solver.hypotheses(pos) {
+                println!("{:02x} (score {:.2})", key, score);
+            }
+        }
+        "s" => {
+            let mut args = rest.split_whitespace();
+            let pos = parse_position(solver, args.next())?;
+            let key = args.next().and_then(|s| hex::decode(s).ok())
+                .filter(|k| k.len() == 1)
+                .ok_or_else(|| "expected a hex byte".to_string())?;
+            solver.confirm_key_byte(pos, key[0]);
+            show(solver);
+        }
+        "g" => show_guesses(solver),
+        "u" => {
+            if !solver.undo() {
+                return Err("nothing to undo".to_string());
+            }
+            show(solver);
+        }
+        "q" => return Ok(false),
+        _ => return Err(format!("unknown command: {}\n{}", cmd, HELP)),
+    }
+    Ok(true)
+}
+
+/// Run the interactive solver on `ciphertexts`.
+pub fn run(ciphertexts: Vec<Vec<u8>>) -> Result<(), Failure> {
+    let mut solver = Solver::new(ciphertexts);
+    println!("{}\n", HELP);
+    show(&solver);
+    let stdin = io::stdin();
+    loop {
+        print!("> ");
+        io::stdout().flush()?;
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line)? == 0 {
+            break;
+        }
+        match command(&mut solver, line.trim_end_matches(&['\r', '\n'][..])) {
+            Ok(true) => (),
+            Ok(false) => break,
+            Err(msg) => eprintln!("{}", msg),
+        }
+    }
+    Ok(())
+}
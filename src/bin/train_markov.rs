@@ -0,0 +1,22 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! Train the table for `language::markov` on the files given on the
+//! command line and write it to `data/english.markov`.
+
+use std::env;
+use std::fs::File;
+use std::io::{Read, Write};
+
+use cryptopals::language::markov;
+
+pub fn main() {
+    let mut corpus = Vec::new();
+    for name in env::args().skip(1) {
+        let mut f = File::open(&name).unwrap();
+        f.read_to_end(&mut corpus).unwrap();
+        corpus.push(b'\n');
+    }
+    let mut out = File::create("data/english.markov").unwrap();
+    out.write_all(&markov::train(&corpus)).unwrap();
+}
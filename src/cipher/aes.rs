@@ -7,12 +7,45 @@
 //!
 //! The implementation of the basic block-sized AES is based on the
 //! one in Joshua Davies: "Implementing SSL/TLS (Using Cryptography
-//! and PKI)", Wiley Publishing Inc., 2011.  The cipher modes EBC, CBC
-//! and CTR have been implemented from scratch.
+//! and PKI)", Wiley Publishing Inc., 2011.  The cipher modes EBC, CBC,
+//! PCBC and CTR, and decryption of CBC with ciphertext stealing, have
+//! been implemented from scratch.
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
 use std::io::Cursor;
-use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use byteorder::{BigEndian, ByteOrder, ReadBytesExt, WriteBytesExt};
+
+use crate::cipher::block::{BlockCipher, NewBlockCipher};
+use crate::codec::hex;
+use crate::error::{Error, Result};
+use crate::math::gf256::Gf256;
+use crate::padding::{Padding, PaddingScheme};
+
+/// Behaviour of the CTR mode counter when it overflows.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CtrOverflow {
+    /// The 64-bit counter wraps around to zero and the nonce is left
+    /// unchanged, so the keystream repeats after 2^64 blocks.
+    Wrap,
+    /// The whole 128-bit counter block is incremented modulo 2^128,
+    /// carrying an overflow of the counter into the nonce, as in NIST
+    /// SP 800-38A, appendix B.1.
+    Carry,
+}
+
+/// Parameters for CTR mode encryption and decryption.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CtrParams {
+    /// What to do when the counter overflows.
+    pub overflow: CtrOverflow,
+}
+
+impl Default for CtrParams {
+    fn default() -> CtrParams {
+        CtrParams { overflow: CtrOverflow::Wrap }
+    }
+}
 
 /// Key for AES cipher.  This comes in three sizes: 128, 192 and 256
 /// bytes.
@@ -25,6 +58,122 @@ pub enum AesKey {
     Key256(AesKey256),
 }
 
+impl AesKey {
+    /// Create a key from `key`, choosing the key size from its length,
+    /// which must be 16, 24 or 32 bytes.  Other lengths result in
+    /// `Error::InvalidKeyLength`.
+    pub fn from_slice(key: &[u8]) -> Result<AesKey> {
+        match key.len() {
+            16 => {
+                let mut k = [0; 16];
+                k.copy_from_slice(key);
+                Ok(AesKey::from(k))
+            }
+            24 => {
+                let mut k = [0; 24];
+                k.copy_from_slice(key);
+                Ok(AesKey::from(k))
+            }
+            32 => {
+                let mut k = [0; 32];
+                k.copy_from_slice(key);
+                Ok(AesKey::from(k))
+            }
+            n => Err(Error::InvalidKeyLength(n)),
+        }
+    }
+
+    /// Create a key from the hex string `s`, see `from_slice`.
+    pub fn from_hex(s: &str) -> Result<AesKey> {
+        AesKey::from_slice(&hex::decode(s)?)
+    }
+
+    /// Return the raw key material.
+    pub fn as_bytes(&self) -> &[u8] {
+        match *self {
+            AesKey::Key128(ref k) => &k.key,
+            AesKey::Key192(ref k) => &k.key,
+            AesKey::Key256(ref k) => &k.key,
+        }
+    }
+
+    /// Return the key material as a hex string.  As the `Debug` and
+    /// `Display` output does not show the key, this is the way to
+    /// deliberately reveal it.
+    pub fn to_hex(&self) -> String {
+        hex::encode(self.as_bytes())
+    }
+}
+
+impl fmt::Debug for AesKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AesKey::Key128(ref k) => k.fmt(f),
+            AesKey::Key192(ref k) => k.fmt(f),
+            AesKey::Key256(ref k) => k.fmt(f),
+        }
+    }
+}
+
+impl fmt::Display for AesKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+/// Keys are serialized as hex strings, like `to_hex`.
+#[cfg(feature = "serde")]
+impl ::serde::Serialize for AesKey {
+    fn serialize<S: ::serde::Serializer>(&self, s: S) -> ::std::result::Result<S::Ok, S::Error> {
+        s.serialize_str(&self.to_hex())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> ::serde::Deserialize<'de> for AesKey {
+    fn deserialize<D: ::serde::Deserializer<'de>>(d: D) -> ::std::result::Result<AesKey, D::Error> {
+        use serde::de::Error as DeError;
+        let s = String::deserialize(d)?;
+        AesKey::from_hex(&s).map_err(D::Error::custom)
+    }
+}
+
+impl fmt::Debug for AesKey128 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "AesKey128(****)")
+    }
+}
+
+impl fmt::Debug for AesKey192 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "AesKey192(****)")
+    }
+}
+
+impl fmt::Debug for AesKey256 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "AesKey256(****)")
+    }
+}
+
+impl From<[u8; 16]> for AesKey {
+    fn from(key: [u8; 16]) -> AesKey {
+        AesKey::Key128(AesKey128 { key: key })
+    }
+}
+
+impl From<[u8; 24]> for AesKey {
+    fn from(key: [u8; 24]) -> AesKey {
+        AesKey::Key192(AesKey192 { key: key })
+    }
+}
+
+impl From<[u8; 32]> for AesKey {
+    fn from(key: [u8; 32]) -> AesKey {
+        AesKey::Key256(AesKey256 { key: key })
+    }
+}
+
 /// Container for 128-bit value to be used as an AES key.
 pub struct AesKey128 {
     /// Raw key material.
@@ -150,11 +299,8 @@ fn compute_key_schedule(key: &[u8], w: &mut [[u8; 4]]) {
         if i % keywords == 0 {
             rot_word(&mut w[i]);
             sub_word(&mut w[i]);
-            if i % 36 == 0 {
-                rcon = 0x1b;
-            }
             w[i][0] ^= rcon;
-            rcon <<= 1;
+            rcon = Gf256(rcon).xtime().0;
         } else if keywords > 6 && i % keywords == 4 {
             sub_word(&mut w[i]);
         }
@@ -206,22 +352,9 @@ fn shift_rows(state: &mut [[u8; 4]]) {
     state[3][0] = tmp;
 }
 
-fn xtime(x: u8) -> u8 {
-    (x << 1) ^ (if x & 0x80 != 0 { 0x1b } else { 0x00 })
-}
-
-fn dot(xin: u8, y: u8) -> u8 {
-    let mut x = xin;
-    let mut product = 0;
-    let mut mask = 0x01;
-    while mask != 0 {
-        if y & mask != 0 {
-            product ^= x;
-        }
-        x = xtime(x);
-        mask <<= 1;
-    }
-    product
+/// Multiply `x` and `y` in GF(2^8).
+fn dot(x: u8, y: u8) -> u8 {
+    (Gf256(x) * Gf256(y)).0
 }
 
 /// Perform the mix_columns operation on the state.
@@ -288,13 +421,22 @@ pub fn encrypt(key: &AesKey, input: &[u8; 16], output: &mut [u8; 16]) {
 /// ECB mode, using the given key.  The ciphertext output is returned
 /// as a vector of bytes.
 pub fn encrypt_ecb(key: &AesKey, plaintext: &[u8]) -> Vec<u8> {
+    // PKCS#7 padding works for all plaintexts.
+    encrypt_ecb_with(key, plaintext, &PaddingScheme::Pkcs7).unwrap()
+}
+
+/// Like `encrypt_ecb`, but pad the plaintext with `padding` instead
+/// of PKCS#7.  Returns an error if the padding fails, for example for
+/// `NoPadding` and a plaintext that is not a multiple of 16 bytes
+/// long.
+pub fn encrypt_ecb_with<P: Padding>(key: &AesKey, plaintext: &[u8], padding: &P) -> Result<Vec<u8>> {
     let (keysize, keybytes): (usize, Vec<_>) = match key {
         &AesKey::Key128(AesKey128 {key}) => (16, key[..].iter().cloned().collect()),
         &AesKey::Key192(AesKey192 {key}) => (24, key[..].iter().cloned().collect()),
         &AesKey::Key256(AesKey256 {key}) => (32, key[..].iter().cloned().collect()),
     };
     let mut w = [[0u8; 4]; 60];
-    let padded_plaintext = ::padding::pkcs7::pad(&plaintext, 16);
+    let padded_plaintext = padding.pad(&plaintext, 16)?;
     let mut result = Vec::with_capacity(padded_plaintext.len());
 
     let nr = (keysize >> 2) + 6;
@@ -310,20 +452,28 @@ pub fn encrypt_ecb(key: &AesKey, plaintext: &[u8]) -> Vec<u8> {
             result.push(output[x]);
         }
     }
-    result
+    Ok(result)
 }
 
 /// Encrypt the arbitrary-length plaintext block `input` with AES in
 /// CBC mode, using the given key and initialization vector.  The
 /// ciphertext output is returned as a vector of bytes.
 pub fn encrypt_cbc(key: &AesKey, iv: &[u8; 16], plaintext: &[u8]) -> Vec<u8> {
+    // PKCS#7 padding works for all plaintexts.
+    encrypt_cbc_with(key, iv, plaintext, &PaddingScheme::Pkcs7).unwrap()
+}
+
+/// Like `encrypt_cbc`, but pad the plaintext with `padding` instead
+/// of PKCS#7.  Returns an error if the padding fails.
+pub fn encrypt_cbc_with<P: Padding>(key: &AesKey, iv: &[u8; 16], plaintext: &[u8], padding: &P)
+                                    -> Result<Vec<u8>> {
     let (keysize, keybytes): (usize, Vec<_>) = match key {
         &AesKey::Key128(AesKey128 {key}) => (16, key[..].iter().cloned().collect()),
         &AesKey::Key192(AesKey192 {key}) => (24, key[..].iter().cloned().collect()),
         &AesKey::Key256(AesKey256 {key}) => (32, key[..].iter().cloned().collect()),
     };
     let mut w = [[0u8; 4]; 60];
-    let padded_plaintext = ::padding::pkcs7::pad(&plaintext, 16);
+    let padded_plaintext = padding.pad(&plaintext, 16)?;
     let mut result = Vec::with_capacity(padded_plaintext.len());
 
     let nr = (keysize >> 2) + 6;
@@ -341,7 +491,7 @@ pub fn encrypt_cbc(key: &AesKey, iv: &[u8; 16], plaintext: &[u8]) -> Vec<u8> {
         }
         r = output;
     }
-    result
+    Ok(result)
 }
 
 /// Encrypt the arbitrary-length plaintext block `input` with AES in
@@ -352,34 +502,52 @@ pub fn encrypt_cbc(key: &AesKey, iv: &[u8; 16], plaintext: &[u8]) -> Vec<u8> {
 /// the IV as a nonce, and the least significant 64 bits as the
 /// initial counter value.  To produce the input to the block cipher,
 /// the nonce is encoded in big-endian format and concatenated with
-/// a 64-bit counter, also encoded in big-endian format.
+/// a 64-bit counter, also encoded in big-endian format.  The counter
+/// wraps around to zero without touching the nonce; use
+/// `encrypt_ctr_with` to carry into the nonce instead.
 pub fn encrypt_ctr(key: &AesKey, iv: &[u8; 16], plaintext: &[u8]) -> Vec<u8> {
+    encrypt_ctr_with(key, iv, plaintext, &CtrParams::default())
+}
+
+/// Encrypt the arbitrary-length plaintext `plaintext` with AES in CTR
+/// mode like `encrypt_ctr`, but with the counter behaviour given by
+/// `params`.
+pub fn encrypt_ctr_with(key: &AesKey, iv: &[u8; 16], plaintext: &[u8], params: &CtrParams) -> Vec<u8> {
+    ctr_xor(key, iv, plaintext, params)
+}
+
+/// XOR `input` with the CTR mode keystream for `key` and `iv`.  This
+/// is both encryption and decryption.
+fn ctr_xor(key: &AesKey, iv: &[u8; 16], input: &[u8], params: &CtrParams) -> Vec<u8> {
     let (keysize, keybytes): (usize, Vec<_>) = match key {
         &AesKey::Key128(AesKey128 {key}) => (16, key[..].iter().cloned().collect()),
         &AesKey::Key192(AesKey192 {key}) => (24, key[..].iter().cloned().collect()),
         &AesKey::Key256(AesKey256 {key}) => (32, key[..].iter().cloned().collect()),
     };
     let mut w = [[0u8; 4]; 60];
-    let mut result = Vec::with_capacity(plaintext.len());
+    let mut result = Vec::with_capacity(input.len());
     let mut rdr = Cursor::new(iv);
-    let nonce = rdr.read_u64::<BigEndian>().unwrap();
+    let mut nonce = rdr.read_u64::<BigEndian>().unwrap();
     let mut ctr = rdr.read_u64::<BigEndian>().unwrap();
     
     let nr = (keysize >> 2) + 6;
     compute_key_schedule(&keybytes, &mut w);
-    let mut input = [0u8; 16];
+    let mut block = [0u8; 16];
     let mut output = [0u8; 16];
 
     let mut wtr = vec![];
-    for chunk in plaintext.chunks(16) {
+    for chunk in input.chunks(16) {
         wtr.truncate(0);
         wtr.write_u64::<BigEndian>(nonce).unwrap();
         wtr.write_u64::<BigEndian>(ctr).unwrap();
-        ctr += 1;
+        ctr = ctr.wrapping_add(1);
+        if ctr == 0 && params.overflow == CtrOverflow::Carry {
+            nonce = nonce.wrapping_add(1);
+        }
         for x in 0..16 {
-            input[x] = wtr[x];
+            block[x] = wtr[x];
         }
-        encrypt_block(&w, nr, &input, &mut output);
+        encrypt_block(&w, nr, &block, &mut output);
         for x in 0..chunk.len() {
             result.push(chunk[x] ^ output[x]);
         }
@@ -484,9 +652,96 @@ pub fn decrypt(key: &AesKey, input: &[u8; 16], output: &mut [u8; 16]) {
     decrypt_block(&w, nr, input, output);
 }
 
+/// AES with a fixed key, whose key schedule is computed once on
+/// construction.  This is the `BlockCipher<16>` implementation of AES
+/// for the generic modes in `cipher::block`.
+#[derive(Clone)]
+pub struct Aes {
+    w: [[u8; 4]; 60],
+    nr: usize,
+}
+
+impl Aes {
+    /// Expand `key` into a cipher instance.
+    pub fn with_key(key: &AesKey) -> Aes {
+        let keybytes = key.as_bytes();
+        let mut w = [[0u8; 4]; 60];
+        compute_key_schedule(keybytes, &mut w);
+        Aes {
+            w: w,
+            nr: (keybytes.len() >> 2) + 6,
+        }
+    }
+}
+
+impl fmt::Debug for Aes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Aes {{ rounds: {} }}", self.nr)
+    }
+}
+
+impl<'a> From<&'a AesKey> for Aes {
+    fn from(key: &'a AesKey) -> Aes {
+        Aes::with_key(key)
+    }
+}
+
+impl NewBlockCipher<16> for Aes {
+    fn new(key: &[u8; 16]) -> Aes {
+        Aes::with_key(&AesKey::from(*key))
+    }
+}
+
+impl NewBlockCipher<24> for Aes {
+    fn new(key: &[u8; 24]) -> Aes {
+        Aes::with_key(&AesKey::from(*key))
+    }
+}
+
+impl NewBlockCipher<32> for Aes {
+    fn new(key: &[u8; 32]) -> Aes {
+        Aes::with_key(&AesKey::from(*key))
+    }
+}
+
+impl BlockCipher<16> for Aes {
+    fn encrypt_block(&self, block: &mut [u8; 16]) {
+        let input = *block;
+        encrypt_block(&self.w, self.nr, &input, block);
+    }
+
+    fn decrypt_block(&self, block: &mut [u8; 16]) {
+        let input = *block;
+        decrypt_block(&self.w, self.nr, &input, block);
+    }
+}
+
 /// Decrypt the ciphertext block `input` with AES in ECB mode, using
-/// the given key.  The plaintext output is returned as a byte vector
-pub fn decrypt_ecb(key: &AesKey, ciphertext: &[u8]) -> Vec<u8> {
+/// the given key, and remove the PKCS#7 padding.  The plaintext
+/// output is returned as a byte vector.  Returns
+/// `Error::InvalidCiphertextLength` if the ciphertext is empty or
+/// does not consist of whole blocks, and `Error::InvalidPadding` if
+/// the padding is malformed, for example because the key is wrong.
+pub fn decrypt_ecb(key: &AesKey, ciphertext: &[u8]) -> Result<Vec<u8>> {
+    decrypt_ecb_with(key, ciphertext, &PaddingScheme::Pkcs7)
+}
+
+/// Like `decrypt_ecb`, but remove and check padding of the scheme
+/// `padding`.
+pub fn decrypt_ecb_with<P: Padding>(key: &AesKey, ciphertext: &[u8], padding: &P) -> Result<Vec<u8>> {
+    check_ciphertext_length(ciphertext)?;
+    padding.unpad(&decrypt_ecb_blocks(key, ciphertext), 16)
+}
+
+// Check that `ciphertext` consists of whole blocks.
+fn check_ciphertext_length(ciphertext: &[u8]) -> Result<()> {
+    if ciphertext.len() % 16 != 0 {
+        return Err(Error::InvalidCiphertextLength { len: ciphertext.len(), block_size: 16 });
+    }
+    Ok(())
+}
+
+fn decrypt_ecb_blocks(key: &AesKey, ciphertext: &[u8]) -> Vec<u8> {
     let (keysize, keybytes): (usize, Vec<_>) = match key {
         &AesKey::Key128(AesKey128 {key}) => (16, key[..].iter().cloned().collect()),
         &AesKey::Key192(AesKey192 {key}) => (24, key[..].iter().cloned().collect()),
@@ -509,15 +764,25 @@ pub fn decrypt_ecb(key: &AesKey, ciphertext: &[u8]) -> Vec<u8> {
             result.push(output[x]);
         }
     }
-    let res_len = result.len();
-    let padding_len = result[res_len - 1] as usize;
-    result.truncate(res_len - padding_len);
     result
 }
 
-/// Decrypt the ciphertext block `input` with AES in ECB mode, using
-/// the given key.  The plaintext output is returned as a byte vector
-pub fn decrypt_cbc(key: &AesKey, iv: &[u8; 16], ciphertext: &[u8]) -> Vec<u8> {
+/// Decrypt the ciphertext block `input` with AES in CBC mode, using
+/// the given key and IV, and remove the PKCS#7 padding.  Errors are
+/// as for `decrypt_ecb`.
+pub fn decrypt_cbc(key: &AesKey, iv: &[u8; 16], ciphertext: &[u8]) -> Result<Vec<u8>> {
+    decrypt_cbc_with(key, iv, ciphertext, &PaddingScheme::Pkcs7)
+}
+
+/// Like `decrypt_cbc`, but remove and check padding of the scheme
+/// `padding`.
+pub fn decrypt_cbc_with<P: Padding>(key: &AesKey, iv: &[u8; 16], ciphertext: &[u8], padding: &P)
+                                    -> Result<Vec<u8>> {
+    check_ciphertext_length(ciphertext)?;
+    padding.unpad(&decrypt_cbc_blocks(key, iv, ciphertext), 16)
+}
+
+fn decrypt_cbc_blocks(key: &AesKey, iv: &[u8; 16], ciphertext: &[u8]) -> Vec<u8> {
     let (keysize, keybytes): (usize, Vec<_>) = match key {
         &AesKey::Key128(AesKey128 {key}) => (16, key[..].iter().cloned().collect()),
         &AesKey::Key192(AesKey192 {key}) => (24, key[..].iter().cloned().collect()),
@@ -542,46 +807,306 @@ pub fn decrypt_cbc(key: &AesKey, iv: &[u8; 16], ciphertext: &[u8]) -> Vec<u8> {
         }
         r = input;
     }
-    let res_len = result.len();
-    let padding_len = result[res_len - 1] as usize;
-    result.truncate(res_len - padding_len);
     result
 }
 
-/// Decrypt the ciphertext block `input` with AES in ECB mode, using
-/// the given key.  The plaintext output is returned as a byte vector
-pub fn decrypt_ctr(key: &AesKey, iv: &[u8; 16], ciphertext: &[u8]) -> Vec<u8> {
+/// Encrypt the arbitrary-length plaintext `plaintext` with AES in
+/// PCBC (propagating cipher block chaining) mode, using the given key
+/// and initialization vector.  Each plaintext block is XOR-ed with
+/// both the previous plaintext and the previous ciphertext block
+/// before encryption, so that a change in one block propagates to all
+/// following blocks.  The plaintext is padded with PKCS#7.
+pub fn encrypt_pcbc(key: &AesKey, iv: &[u8; 16], plaintext: &[u8]) -> Vec<u8> {
+    // PKCS#7 padding works for all plaintexts.
+    encrypt_pcbc_with(key, iv, plaintext, &PaddingScheme::Pkcs7).unwrap()
+}
+
+/// Like `encrypt_pcbc`, but pad the plaintext with `padding` instead
+/// of PKCS#7.  Returns an error if the padding fails.
+pub fn encrypt_pcbc_with<P: Padding>(key: &AesKey, iv: &[u8; 16], plaintext: &[u8], padding: &P)
+                                     -> Result<Vec<u8>> {
     let (keysize, keybytes): (usize, Vec<_>) = match key {
         &AesKey::Key128(AesKey128 {key}) => (16, key[..].iter().cloned().collect()),
         &AesKey::Key192(AesKey192 {key}) => (24, key[..].iter().cloned().collect()),
         &AesKey::Key256(AesKey256 {key}) => (32, key[..].iter().cloned().collect()),
     };
     let mut w = [[0u8; 4]; 60];
-    let mut result = Vec::with_capacity(ciphertext.len());
-    let mut rdr = Cursor::new(iv);
-    let nonce = rdr.read_u64::<BigEndian>().unwrap();
-    let mut ctr = rdr.read_u64::<BigEndian>().unwrap();
-    
+    let padded_plaintext = padding.pad(&plaintext, 16)?;
+    let mut result = Vec::with_capacity(padded_plaintext.len());
+
     let nr = (keysize >> 2) + 6;
     compute_key_schedule(&keybytes, &mut w);
     let mut input = [0u8; 16];
     let mut output = [0u8; 16];
+    let mut r = *iv;
+    for chunk in padded_plaintext.chunks(16) {
+        for x in 0..16 {
+            input[x] = chunk[x] ^ r[x];
+        }
+        encrypt_block(&w, nr, &input, &mut output);
+        for x in 0..16 {
+            result.push(output[x]);
+            r[x] = chunk[x] ^ output[x];
+        }
+    }
+    Ok(result)
+}
 
-    let mut wtr = vec![];
+/// Decrypt the ciphertext `ciphertext` with AES in PCBC mode, using
+/// the given key and initialization vector, and remove PKCS#7
+/// padding.  Returns an error if the ciphertext does not consist of
+/// whole blocks or the padding is invalid.
+pub fn decrypt_pcbc(key: &AesKey, iv: &[u8; 16], ciphertext: &[u8]) -> Result<Vec<u8>> {
+    decrypt_pcbc_with(key, iv, ciphertext, &PaddingScheme::Pkcs7)
+}
+
+/// Like `decrypt_pcbc`, but remove and check padding of the scheme
+/// `padding`.
+pub fn decrypt_pcbc_with<P: Padding>(key: &AesKey, iv: &[u8; 16], ciphertext: &[u8], padding: &P)
+                                     -> Result<Vec<u8>> {
+    check_ciphertext_length(ciphertext)?;
+    let (keysize, keybytes): (usize, Vec<_>) = match key {
+        &AesKey::Key128(AesKey128 {key}) => (16, key[..].iter().cloned().collect()),
+        &AesKey::Key192(AesKey192 {key}) => (24, key[..].iter().cloned().collect()),
+        &AesKey::Key256(AesKey256 {key}) => (32, key[..].iter().cloned().collect()),
+    };
+    let mut w = [[0u8; 4]; 60];
+    let mut result = Vec::with_capacity(ciphertext.len());
+
+    let nr = (keysize >> 2) + 6;
+    compute_key_schedule(&keybytes, &mut w);
+
+    let mut input = [0u8; 16];
+    let mut output = [0u8; 16];
+    let mut r = *iv;
     for chunk in ciphertext.chunks(16) {
-        wtr.truncate(0);
-        wtr.write_u64::<BigEndian>(nonce).unwrap();
-        wtr.write_u64::<BigEndian>(ctr).unwrap();
-        ctr += 1;
         for x in 0..16 {
-            input[x] = wtr[x];
+            input[x] = chunk[x];
         }
-        encrypt_block(&w, nr, &input, &mut output);
-        for x in 0..chunk.len() {
-            result.push(chunk[x] ^ output[x]);
+        decrypt_block(&w, nr, &input, &mut output);
+        for x in 0..16 {
+            let p = output[x] ^ r[x];
+            result.push(p);
+            r[x] = p ^ input[x];
         }
     }
-    result
+    padding.unpad(&result, 16)
+}
+
+/// Decrypt the ciphertext `ciphertext`, which was produced by AES in
+/// CBC mode with ciphertext stealing, using the given key and
+/// initialization vector.  The ciphertext is expected in the CS3
+/// format of NIST SP 800-38A addendum and RFC 3962, where the last
+/// two blocks are always swapped and the final block may be partial.
+/// The plaintext has the same length as the ciphertext, which must be
+/// at least one block long; otherwise `Error::InvalidCiphertextLength`
+/// is returned.
+pub fn decrypt_cbc_cts(key: &AesKey, iv: &[u8; 16], ciphertext: &[u8]) -> Result<Vec<u8>> {
+    let len = ciphertext.len();
+    if len < 16 {
+        return Err(Error::InvalidCiphertextLength { len: len, block_size: 16 });
+    }
+    if len == 16 {
+        return Ok(decrypt_cbc_blocks(key, iv, ciphertext));
+    }
+    // Length of the final, possibly partial block, and start of the
+    // two swapped blocks.
+    let tail = (len - 1) % 16 + 1;
+    let start = len - 16 - tail;
+
+    let mut result = decrypt_cbc_blocks(key, iv, &ciphertext[..start]);
+    let mut prev = *iv;
+    if start > 0 {
+        prev.copy_from_slice(&ciphertext[start - 16..start]);
+    }
+
+    let mut last = [0u8; 16];
+    last.copy_from_slice(&ciphertext[start..start + 16]);
+    let stolen = &ciphertext[start + 16..];
+    let mut z = [0u8; 16];
+    decrypt(key, &last, &mut z);
+
+    // The stolen bytes complete the penultimate ciphertext block.
+    let mut penultimate = z;
+    penultimate[..tail].copy_from_slice(stolen);
+    let mut output = [0u8; 16];
+    decrypt(key, &penultimate, &mut output);
+    for x in 0..16 {
+        result.push(output[x] ^ prev[x]);
+    }
+    for x in 0..tail {
+        result.push(z[x] ^ stolen[x]);
+    }
+    Ok(result)
+}
+
+/// Decrypt the ciphertext block `input` with AES in CTR mode, using
+/// the given key.  The plaintext output is returned as a byte vector
+pub fn decrypt_ctr(key: &AesKey, iv: &[u8; 16], ciphertext: &[u8]) -> Vec<u8> {
+    decrypt_ctr_with(key, iv, ciphertext, &CtrParams::default())
+}
+
+/// Decrypt the ciphertext `ciphertext` with AES in CTR mode like
+/// `decrypt_ctr`, but with the counter behaviour given by `params`.
+pub fn decrypt_ctr_with(key: &AesKey, iv: &[u8; 16], ciphertext: &[u8], params: &CtrParams) -> Vec<u8> {
+    ctr_xor(key, iv, ciphertext, params)
+}
+
+/// CTR mode keystream for a fixed key and IV, which generates
+/// keystream blocks on demand and remembers them.  This is useful
+/// when the same stream is encrypted or decrypted repeatedly at
+/// different offsets, as in random-access edits, because every block
+/// is only run through AES once.
+pub struct CtrKeystreamCache {
+    w: [[u8; 4]; 60],
+    nr: usize,
+    nonce: u64,
+    ctr: u64,
+    params: CtrParams,
+    blocks: HashMap<u64, [u8; 16]>,
+}
+
+impl CtrKeystreamCache {
+    /// Create an empty cache for `key` and `iv`, with default counter
+    /// behaviour.
+    pub fn new(key: &AesKey, iv: &[u8; 16]) -> CtrKeystreamCache {
+        CtrKeystreamCache::with_params(key, iv, &CtrParams::default())
+    }
+
+    /// Create an empty cache for `key` and `iv`, with the counter
+    /// behaviour given by `params`.
+    pub fn with_params(key: &AesKey, iv: &[u8; 16], params: &CtrParams) -> CtrKeystreamCache {
+        let (keysize, keybytes): (usize, Vec<_>) = match key {
+            &AesKey::Key128(AesKey128 {key}) => (16, key[..].iter().cloned().collect()),
+            &AesKey::Key192(AesKey192 {key}) => (24, key[..].iter().cloned().collect()),
+            &AesKey::Key256(AesKey256 {key}) => (32, key[..].iter().cloned().collect()),
+        };
+        let mut w = [[0u8; 4]; 60];
+        compute_key_schedule(&keybytes, &mut w);
+        let mut rdr = Cursor::new(iv);
+        let nonce = rdr.read_u64::<BigEndian>().unwrap();
+        let ctr = rdr.read_u64::<BigEndian>().unwrap();
+        CtrKeystreamCache {
+            w: w,
+            nr: (keysize >> 2) + 6,
+            nonce: nonce,
+            ctr: ctr,
+            params: *params,
+            blocks: HashMap::new(),
+        }
+    }
+
+    /// Return the keystream block with index `index`, generating it if
+    /// it is not yet cached.
+    fn block(&mut self, index: u64) -> [u8; 16] {
+        if let Some(b) = self.blocks.get(&index) {
+            return *b;
+        }
+        let (ctr, carried) = self.ctr.overflowing_add(index);
+        let nonce = if carried && self.params.overflow == CtrOverflow::Carry {
+            self.nonce.wrapping_add(1)
+        } else {
+            self.nonce
+        };
+        let mut input = [0u8; 16];
+        BigEndian::write_u64(&mut input[..8], nonce);
+        BigEndian::write_u64(&mut input[8..], ctr);
+        let mut output = [0u8; 16];
+        encrypt_block(&self.w, self.nr, &input, &mut output);
+        self.blocks.insert(index, output);
+        output
+    }
+
+    /// Return `len` bytes of keystream, starting at byte offset
+    /// `offset`.
+    pub fn keystream(&mut self, offset: usize, len: usize) -> Vec<u8> {
+        let mut result = Vec::with_capacity(len);
+        for pos in offset..offset + len {
+            let block = self.block((pos / 16) as u64);
+            result.push(block[pos % 16]);
+        }
+        result
+    }
+
+    /// XOR `data` with the keystream starting at byte offset `offset`.
+    /// This encrypts plaintext or decrypts ciphertext found at that
+    /// offset in the stream.
+    pub fn apply(&mut self, offset: usize, data: &[u8]) -> Vec<u8> {
+        let keystream = self.keystream(offset, data.len());
+        data.iter().zip(keystream.iter()).map(|(d, k)| d ^ k).collect()
+    }
+
+    /// Number of keystream blocks generated so far.
+    pub fn cached_blocks(&self) -> usize {
+        self.blocks.len()
+    }
+}
+
+/// Initial value of the key wrap integrity check register.
+const KEY_WRAP_IV: [u8; 8] = [0xa6; 8];
+
+/// Wrap the key `key` with the key-encryption key `kek`, as specified
+/// in RFC 3394.  `key` must be a multiple of 8 bytes long and at least
+/// 16 bytes; otherwise `Error::InvalidKeyLength` is returned.  The
+/// wrapped key is 8 bytes longer than `key`.
+pub fn key_wrap(kek: &AesKey, key: &[u8]) -> Result<Vec<u8>> {
+    if key.len() % 8 != 0 || key.len() < 16 {
+        return Err(Error::InvalidKeyLength(key.len()));
+    }
+    let n = key.len() / 8;
+    let mut a = KEY_WRAP_IV;
+    let mut r = key.to_vec();
+    let mut input = [0u8; 16];
+    let mut output = [0u8; 16];
+    for j in 0..6 {
+        for i in 0..n {
+            input[..8].copy_from_slice(&a);
+            input[8..].copy_from_slice(&r[8 * i..8 * i + 8]);
+            encrypt(kek, &input, &mut output);
+            let t = (n * j + i + 1) as u64;
+            a.copy_from_slice(&output[..8]);
+            for x in 0..8 {
+                a[x] ^= (t >> (56 - 8 * x)) as u8;
+            }
+            r[8 * i..8 * i + 8].copy_from_slice(&output[8..]);
+        }
+    }
+    let mut result = a.to_vec();
+    result.extend(r);
+    Ok(result)
+}
+
+/// Unwrap the key `wrapped` with the key-encryption key `kek`, as
+/// specified in RFC 3394.  Returns `Error::InvalidCiphertextLength`
+/// if `wrapped` is not a multiple of 8 bytes long or shorter than 24
+/// bytes, and `Error::InvalidKeyWrap` if the integrity check fails,
+/// which happens when `kek` is wrong or `wrapped` has been modified.
+pub fn key_unwrap(kek: &AesKey, wrapped: &[u8]) -> Result<Vec<u8>> {
+    if wrapped.len() % 8 != 0 || wrapped.len() < 24 {
+        return Err(Error::InvalidCiphertextLength { len: wrapped.len(), block_size: 8 });
+    }
+    let n = wrapped.len() / 8 - 1;
+    let mut a = [0u8; 8];
+    a.copy_from_slice(&wrapped[..8]);
+    let mut r = wrapped[8..].to_vec();
+    let mut input = [0u8; 16];
+    let mut output = [0u8; 16];
+    for j in (0..6).rev() {
+        for i in (0..n).rev() {
+            let t = (n * j + i + 1) as u64;
+            for x in 0..8 {
+                input[x] = a[x] ^ (t >> (56 - 8 * x)) as u8;
+            }
+            input[8..].copy_from_slice(&r[8 * i..8 * i + 8]);
+            decrypt(kek, &input, &mut output);
+            a.copy_from_slice(&output[..8]);
+            r[8 * i..8 * i + 8].copy_from_slice(&output[8..]);
+        }
+    }
+    if a != KEY_WRAP_IV {
+        return Err(Error::InvalidKeyWrap);
+    }
+    Ok(r)
 }
 
 pub fn detect_ecb(input: &[u8]) -> bool {
@@ -604,10 +1129,16 @@ mod tests {
     use super::{encrypt, decrypt};
     use super::{encrypt_ecb, decrypt_ecb};
     use super::{encrypt_cbc, decrypt_cbc};
-    use super::{encrypt_ctr, decrypt_ctr};
+    use super::{encrypt_ecb_with, decrypt_ecb_with, encrypt_cbc_with, decrypt_cbc_with};
+    use super::{encrypt_ctr, decrypt_ctr, encrypt_ctr_with, decrypt_ctr_with, CtrParams, CtrOverflow};
+    use super::CtrKeystreamCache;
+    use super::{encrypt_pcbc, decrypt_pcbc, decrypt_cbc_cts};
+    use super::{key_wrap, key_unwrap};
     use super::{detect_ecb};
-    use super::{AesKey, AesKey128};
-    use ::codec;
+    use super::{AesKey, AesKey128, AesKey192};
+    use crate::codec;
+    use crate::padding::{PaddingScheme, NoPadding};
+    use crate::error::Error;
 
     // From
     // http://stackoverflow.com/questions/25428920/how-to-get-a-slice-as-an-array-in-rust
@@ -698,7 +1229,7 @@ mod tests {
         let keybytes = codec::hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
         let key = AesKey::Key128(AesKey128{key: to_byte_array_16(&keybytes)});
 
-        let plaintext = decrypt_ecb(&key, &ciphertext);
+        let plaintext = decrypt_ecb(&key, &ciphertext).unwrap();
         assert_eq!(&expected, &plaintext);
     }
 
@@ -719,7 +1250,7 @@ mod tests {
         let keybytes = codec::hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
         let key = AesKey::Key128(AesKey128{key: to_byte_array_16(&keybytes)});
 
-        let plaintext = decrypt_ecb(&key, &ciphertext);
+        let plaintext = decrypt_ecb(&key, &ciphertext).unwrap();
         assert_eq!(expected, plaintext);
     }
 
@@ -783,7 +1314,7 @@ mod tests {
         let key = AesKey::Key128(AesKey128{key: to_byte_array_16(&keybytes)});
         let iv = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 0xa, 0xb, 0xc, 0xd, 0xe, 0xf];
 
-        let plaintext = decrypt_cbc(&key, &iv, &ciphertext);
+        let plaintext = decrypt_cbc(&key, &iv, &ciphertext).unwrap();
         assert_eq!(&expected, &plaintext);
     }
 
@@ -805,10 +1336,48 @@ mod tests {
         let iv = [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
                   0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
 
-        let plaintext = decrypt_cbc(&key, &iv, &ciphertext);
+        let plaintext = decrypt_cbc(&key, &iv, &ciphertext).unwrap();
         assert_eq!(&expected, &plaintext);
     }
 
+    #[test]
+    fn decrypt_truncated() {
+        let key = AesKey::from([0x2b; 16]);
+        let iv = [0; 16];
+        let ciphertext = encrypt_cbc(&key, &iv, b"This is an example text.");
+        for len in (1..ciphertext.len()).filter(|l| l % 16 != 0) {
+            match decrypt_ecb(&key, &ciphertext[..len]) {
+                Err(Error::InvalidCiphertextLength { len: l, block_size: 16 }) if l == len => (),
+                r => panic!("unexpected result: {:?}", r),
+            }
+            match decrypt_cbc(&key, &iv, &ciphertext[..len]) {
+                Err(Error::InvalidCiphertextLength { len: l, block_size: 16 }) if l == len => (),
+                r => panic!("unexpected result: {:?}", r),
+            }
+        }
+        match decrypt_ecb(&key, &[]) {
+            Err(Error::InvalidCiphertextLength { len: 0, block_size: 16 }) => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+        assert!(decrypt_cbc(&key, &iv, &[]).is_err());
+    }
+
+    #[test]
+    fn decrypt_wrong_key() {
+        let iv = [0; 16];
+        let ciphertext = encrypt_cbc(&AesKey::from([0x2b; 16]), &iv, b"This is an example text.");
+        // A wrong key gives valid padding with a probability of about
+        // 1/256, and none of these does.
+        for k in 0..4 {
+            let key = AesKey::from([k; 16]);
+            match decrypt_cbc(&key, &iv, &ciphertext) {
+                Err(Error::InvalidPadding { block_size: 16, .. }) => (),
+                r => panic!("unexpected result: {:?}", r),
+            }
+            assert!(decrypt_ecb(&key, &ciphertext).is_err());
+        }
+    }
+
     #[test]
     fn encrypt_ctr_0() {
         let plaintext = b"Cooller";
@@ -893,6 +1462,96 @@ mod tests {
         assert_eq!(&expected, &plaintext);
     }
 
+    #[test]
+    fn from_slice() {
+        match AesKey::from_slice(b"YELLOW SUBMARINE") {
+            Ok(AesKey::Key128(AesKey128 { key })) => assert_eq!(b"YELLOW SUBMARINE", &key),
+            _ => panic!("expected 128-bit key"),
+        }
+        match AesKey::from_slice(&[7; 24]) {
+            Ok(AesKey::Key192(_)) => (),
+            _ => panic!("expected 192-bit key"),
+        }
+        match AesKey::from_slice(&[7; 32]) {
+            Ok(AesKey::Key256(_)) => (),
+            _ => panic!("expected 256-bit key"),
+        }
+        for &len in &[0, 15, 17, 33] {
+            match AesKey::from_slice(&vec![7; len]) {
+                Err(Error::InvalidKeyLength(n)) => assert_eq!(len, n),
+                _ => panic!("expected error for key length {}", len),
+            }
+        }
+        let mut output = [0u8; 16];
+        let mut expected = [0u8; 16];
+        encrypt(&AesKey::from(*b"YELLOW SUBMARINE"), b"ICE ICE BABY 123", &mut output);
+        encrypt(&AesKey::from_slice(b"YELLOW SUBMARINE").unwrap(), b"ICE ICE BABY 123", &mut expected);
+        assert_eq!(expected, output);
+    }
+
+    #[test]
+    fn key_fmt() {
+        let key = AesKey::from_slice(b"YELLOW SUBMARINE").unwrap();
+        assert_eq!("AesKey128(****)", format!("{:?}", key));
+        assert_eq!("AesKey128(****)", format!("{}", key));
+        assert_eq!("AesKey256(****)", format!("{:?}", AesKey::from([0x42; 32])));
+        assert_eq!("AesKey192(****)", format!("{:?}", AesKey192 { key: [0x42; 24] }));
+    }
+
+    #[test]
+    fn key_hex() {
+        let key = AesKey::from_hex("000102030405060708090a0b0c0d0e0f").unwrap();
+        assert_eq!("000102030405060708090a0b0c0d0e0f", key.to_hex());
+        assert_eq!(b"YELLOW SUBMARINE", AesKey::from_slice(b"YELLOW SUBMARINE").unwrap().as_bytes());
+        assert!(AesKey::from_hex("0001").is_err());
+        assert!(AesKey::from_hex("xyz").is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn key_serde() {
+        let key = AesKey::from_hex("000102030405060708090a0b0c0d0e0f").unwrap();
+        let json = serde_json::to_string(&key).unwrap();
+        assert_eq!("\"000102030405060708090a0b0c0d0e0f\"", json);
+        assert_eq!(key.as_bytes(), serde_json::from_str::<AesKey>(&json).unwrap().as_bytes());
+        assert!(serde_json::from_str::<AesKey>("\"0001\"").is_err());
+    }
+
+    #[test]
+    fn padding_schemes() {
+        let plaintext = b"This is an example text for testing padding.";
+        let keybytes = codec::hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
+        let key = AesKey::Key128(AesKey128{key: to_byte_array_16(&keybytes)});
+        let iv = [0x42; 16];
+        for &scheme in &[PaddingScheme::Pkcs7, PaddingScheme::AnsiX923,
+                         PaddingScheme::Iso7816, PaddingScheme::Zero] {
+            let ciphertext = encrypt_ecb_with(&key, plaintext, &scheme).unwrap();
+            assert_eq!(48, ciphertext.len());
+            assert_eq!(plaintext.to_vec(), decrypt_ecb_with(&key, &ciphertext, &scheme).unwrap());
+            let ciphertext = encrypt_cbc_with(&key, &iv, plaintext, &scheme).unwrap();
+            assert_eq!(plaintext.to_vec(), decrypt_cbc_with(&key, &iv, &ciphertext, &scheme).unwrap());
+        }
+        assert_eq!(encrypt_ecb(&key, plaintext), encrypt_ecb_with(&key, plaintext, &PaddingScheme::Pkcs7).unwrap());
+
+        let ciphertext = encrypt_cbc_with(&key, &iv, plaintext, &PaddingScheme::Pkcs7).unwrap();
+        assert!(decrypt_cbc_with(&key, &iv, &ciphertext, &PaddingScheme::AnsiX923).is_err());
+        assert!(decrypt_cbc_with(&key, &iv, &ciphertext, &PaddingScheme::Iso7816).is_err());
+        assert!(decrypt_cbc_with(&key, &iv, &ciphertext, &PaddingScheme::Zero).is_ok());
+
+        assert!(encrypt_ecb_with(&key, plaintext, &NoPadding).is_err());
+        let ciphertext = encrypt_cbc_with(&key, &iv, &plaintext[..32], &NoPadding).unwrap();
+        assert_eq!(32, ciphertext.len());
+        assert_eq!(plaintext[..32].to_vec(), decrypt_cbc_with(&key, &iv, &ciphertext, &NoPadding).unwrap());
+        match decrypt_ecb_with(&key, &ciphertext[..31], &PaddingScheme::Pkcs7) {
+            Err(Error::InvalidCiphertextLength { len: 31, block_size: 16 }) => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+        match decrypt_cbc_with(&key, &iv, &ciphertext[..17], &PaddingScheme::Pkcs7) {
+            Err(Error::InvalidCiphertextLength { len: 17, block_size: 16 }) => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+
     #[test]
     fn detect_ecb_0() {
         let plaintext = b"AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA.\n";
@@ -926,4 +1585,206 @@ mod tests {
         let ciphertext = encrypt_ctr(&key, &iv, plaintext);
         assert!(!detect_ecb(&ciphertext));
     }
+
+    fn ctr_second_block(iv: &[u8; 16], params: &CtrParams) -> Vec<u8> {
+        let key = AesKey::from([0x2b; 16]);
+        let keystream = encrypt_ctr_with(&key, iv, &[0; 32], params);
+        assert_eq!(decrypt_ctr_with(&key, iv, &keystream, params), vec![0; 32]);
+        keystream[16..].to_vec()
+    }
+
+    fn block_keystream(block: &[u8; 16]) -> Vec<u8> {
+        let key = AesKey::from([0x2b; 16]);
+        let mut output = [0; 16];
+        encrypt(&key, block, &mut output);
+        output.to_vec()
+    }
+
+    #[test]
+    fn ctr_overflow_wrap() {
+        let mut iv = [0xff; 16];
+        iv[0] = 0x01;
+        let mut expected = [0; 16];
+        expected[0] = 0x01;
+        expected[1..8].copy_from_slice(&[0xff; 7]);
+        let params = CtrParams { overflow: CtrOverflow::Wrap };
+        assert_eq!(ctr_second_block(&iv, &params), block_keystream(&expected));
+        assert_eq!(CtrParams::default(), params);
+    }
+
+    #[test]
+    fn ctr_overflow_carry() {
+        let mut iv = [0xff; 16];
+        iv[0] = 0x01;
+        let mut expected = [0; 16];
+        expected[0] = 0x02;
+        let params = CtrParams { overflow: CtrOverflow::Carry };
+        assert_eq!(ctr_second_block(&iv, &params), block_keystream(&expected));
+    }
+
+    #[test]
+    fn ctr_overflow_carry_128() {
+        let params = CtrParams { overflow: CtrOverflow::Carry };
+        assert_eq!(ctr_second_block(&[0xff; 16], &params), block_keystream(&[0; 16]));
+    }
+
+
+    #[test]
+    fn ctr_keystream_cache() {
+        let key = AesKey::from([0x2b; 16]);
+        let iv = [0x17; 16];
+        let plaintext = b"This is an example text for testing the keystream cache.";
+        let ciphertext = encrypt_ctr(&key, &iv, plaintext);
+
+        let mut cache = CtrKeystreamCache::new(&key, &iv);
+        assert_eq!(ciphertext[5..40].to_vec(), cache.apply(5, &plaintext[5..40]));
+        assert_eq!(3, cache.cached_blocks());
+        assert_eq!(plaintext[20..30].to_vec(), cache.apply(20, &ciphertext[20..30]));
+        assert_eq!(3, cache.cached_blocks());
+        assert_eq!(ciphertext, cache.apply(0, plaintext));
+        assert_eq!(4, cache.cached_blocks());
+    }
+
+    #[test]
+    fn ctr_keystream_cache_overflow() {
+        let key = AesKey::from([0x2b; 16]);
+        let mut iv = [0xff; 16];
+        iv[0] = 0x01;
+        for overflow in &[CtrOverflow::Wrap, CtrOverflow::Carry] {
+            let params = CtrParams { overflow: *overflow };
+            let keystream = encrypt_ctr_with(&key, &iv, &[0; 48], &params);
+            let mut cache = CtrKeystreamCache::with_params(&key, &iv, &params);
+            assert_eq!(keystream[10..48].to_vec(), cache.keystream(10, 38));
+        }
+    }
+
+
+    #[test]
+    fn pcbc() {
+        let key = AesKey::from([0x2b; 16]);
+        let iv = [0x17; 16];
+        let plaintext = b"This is an example text for testing encryption and decryption.\n";
+        let ciphertext = encrypt_pcbc(&key, &iv, plaintext);
+        assert_eq!(64, ciphertext.len());
+        assert_eq!(plaintext.to_vec(), decrypt_pcbc(&key, &iv, &ciphertext).unwrap());
+
+        // The first block is the same as for CBC, later ones differ.
+        let cbc = encrypt_cbc(&key, &iv, plaintext);
+        assert_eq!(cbc[..16], ciphertext[..16]);
+        assert!(cbc[16..32] != ciphertext[16..32]);
+
+        // Swapping two ciphertext blocks only garbles those two blocks.
+        let mut swapped = ciphertext.clone();
+        for x in 0..16 {
+            swapped.swap(16 + x, 32 + x);
+        }
+        let decrypted = decrypt_pcbc(&key, &iv, &swapped).unwrap();
+        assert_eq!(plaintext[..16], decrypted[..16]);
+        assert!(plaintext[16..48] != decrypted[16..48]);
+        assert_eq!(plaintext[48..], decrypted[48..]);
+
+        match decrypt_pcbc(&key, &iv, &ciphertext[..63]) {
+            Err(Error::InvalidCiphertextLength { len: 63, block_size: 16 }) => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+
+    // CS3 ciphertext stealing, built from CBC with zero padding.
+    fn encrypt_cbc_cts(key: &AesKey, iv: &[u8; 16], plaintext: &[u8]) -> Vec<u8> {
+        let mut ciphertext = encrypt_cbc_with(key, iv, plaintext, &PaddingScheme::Zero).unwrap();
+        let len = ciphertext.len();
+        if len > 16 {
+            for x in 0..16 {
+                ciphertext.swap(len - 32 + x, len - 16 + x);
+            }
+        }
+        ciphertext.truncate(plaintext.len());
+        ciphertext
+    }
+
+    #[test]
+    fn cbc_cts_rfc3962() {
+        let key = AesKey::from_hex("636869636b656e207465726979616b69").unwrap();
+        let iv = [0; 16];
+        let vectors = [
+            ("4920776f756c64206c696b652074686520",
+             "c6353568f2bf8cb4d8a580362da7ff7f97"),
+            ("4920776f756c64206c696b65207468652047656e6572616c20476175277320",
+             "fc00783e0efdb2c1d445d4c8eff7ed2297687268d6ecccc0c07b25e25ecfe5"),
+            ("4920776f756c64206c696b65207468652047656e6572616c2047617527732043",
+             "39312523a78662d5be7fcbcc98ebf5a897687268d6ecccc0c07b25e25ecfe584"),
+        ];
+        for &(p, c) in vectors.iter() {
+            let plaintext = codec::hex::decode(p).unwrap();
+            let ciphertext = codec::hex::decode(c).unwrap();
+            assert_eq!(ciphertext, encrypt_cbc_cts(&key, &iv, &plaintext));
+            assert_eq!(plaintext, decrypt_cbc_cts(&key, &iv, &ciphertext).unwrap());
+        }
+    }
+
+    #[test]
+    fn cbc_cts() {
+        let key = AesKey::from([0x2b; 16]);
+        let iv = [0x17; 16];
+        let plaintext = b"This is an example text for testing encryption and decryption.\n";
+        for len in 16..plaintext.len() + 1 {
+            let ciphertext = encrypt_cbc_cts(&key, &iv, &plaintext[..len]);
+            assert_eq!(plaintext[..len].to_vec(), decrypt_cbc_cts(&key, &iv, &ciphertext).unwrap());
+        }
+        match decrypt_cbc_cts(&key, &iv, &plaintext[..15]) {
+            Err(Error::InvalidCiphertextLength { len: 15, block_size: 16 }) => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+
+
+    #[test]
+    fn key_wrap_rfc3394() {
+        let vectors = [
+            ("000102030405060708090a0b0c0d0e0f",
+             "00112233445566778899aabbccddeeff",
+             "1fa68b0a8112b447aef34bd8fb5a7b829d3e862371d2cfe5"),
+            ("000102030405060708090a0b0c0d0e0f1011121314151617",
+             "00112233445566778899aabbccddeeff0001020304050607",
+             "031d33264e15d33268f24ec260743edce1c6c7ddee725a936ba814915c6762d2"),
+            ("000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f",
+             "00112233445566778899aabbccddeeff000102030405060708090a0b0c0d0e0f",
+             "28c9f404c4b810f4cbccb35cfb87f8263f5786e2d80ed326cbc7f0e71a99f43bfb988b9b7a02dd21"),
+        ];
+        for &(kek, key, wrapped) in vectors.iter() {
+            let kek = AesKey::from_hex(kek).unwrap();
+            let key = codec::hex::decode(key).unwrap();
+            let wrapped = codec::hex::decode(wrapped).unwrap();
+            assert_eq!(wrapped, key_wrap(&kek, &key).unwrap());
+            assert_eq!(key, key_unwrap(&kek, &wrapped).unwrap());
+        }
+    }
+
+    #[test]
+    fn key_wrap_errors() {
+        let kek = AesKey::from([0x2b; 16]);
+        match key_wrap(&kek, &[0; 12]) {
+            Err(Error::InvalidKeyLength(12)) => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+        match key_wrap(&kek, &[0; 8]) {
+            Err(Error::InvalidKeyLength(8)) => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+        let mut wrapped = key_wrap(&kek, &[0x42; 16]).unwrap();
+        match key_unwrap(&kek, &wrapped[..23]) {
+            Err(Error::InvalidCiphertextLength { len: 23, block_size: 8 }) => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+        match key_unwrap(&AesKey::from([0x2c; 16]), &wrapped) {
+            Err(Error::InvalidKeyWrap) => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+        wrapped[20] ^= 1;
+        match key_unwrap(&kek, &wrapped) {
+            Err(Error::InvalidKeyWrap) => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+
 }
@@ -0,0 +1,283 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! Generic block cipher interface.  Block and key sizes are const
+//! generic parameters, so that modes of operation and MACs written
+//! against `BlockCipher<BLOCK>` work on arrays and never have to check
+//! slice lengths at runtime.  Callers that only learn the block size
+//! at runtime can go through `DynBlockCipher`, which checks the length
+//! once and forwards to the array-based methods.
+
+use crate::error::{Error, Result};
+
+/// A block cipher with a block size of `BLOCK` bytes.
+pub trait BlockCipher<const BLOCK: usize> {
+    /// Encrypt `block` in place.
+    fn encrypt_block(&self, block: &mut [u8; BLOCK]);
+
+    /// Decrypt `block` in place.
+    fn decrypt_block(&self, block: &mut [u8; BLOCK]);
+
+    /// Encrypt each of `blocks` in place, independently of each other.
+    fn encrypt_blocks(&self, blocks: &mut [[u8; BLOCK]]) {
+        for block in blocks {
+            self.encrypt_block(block);
+        }
+    }
+
+    /// Decrypt each of `blocks` in place, independently of each other.
+    fn decrypt_blocks(&self, blocks: &mut [[u8; BLOCK]]) {
+        for block in blocks {
+            self.decrypt_block(block);
+        }
+    }
+}
+
+/// A block cipher that can be keyed with `KEY` bytes of key material.
+/// Ciphers supporting several key sizes implement this once per size.
+pub trait NewBlockCipher<const KEY: usize>: Sized {
+    /// Create a cipher instance for `key`, expanding the key once.
+    fn new(key: &[u8; KEY]) -> Self;
+}
+
+impl<C: BlockCipher<BLOCK> + ?Sized, const BLOCK: usize> BlockCipher<BLOCK> for &C {
+    fn encrypt_block(&self, block: &mut [u8; BLOCK]) {
+        (**self).encrypt_block(block)
+    }
+
+    fn decrypt_block(&self, block: &mut [u8; BLOCK]) {
+        (**self).decrypt_block(block)
+    }
+}
+
+impl<C: BlockCipher<BLOCK> + ?Sized, const BLOCK: usize> BlockCipher<BLOCK> for Box<C> {
+    fn encrypt_block(&self, block: &mut [u8; BLOCK]) {
+        (**self).encrypt_block(block)
+    }
+
+    fn decrypt_block(&self, block: &mut [u8; BLOCK]) {
+        (**self).decrypt_block(block)
+    }
+}
+
+/// A block cipher whose block size is only known at runtime.  Blocks
+/// are passed as slices, and every call checks their length.
+pub trait DynBlockCipher {
+    /// Size of a block, in bytes.
+    fn block_size(&self) -> usize;
+
+    /// Encrypt `block` in place.  Returns `Error::InvalidBlockSize` if
+    /// `block` is not `block_size()` bytes long.
+    fn encrypt_block(&self, block: &mut [u8]) -> Result<()>;
+
+    /// Decrypt `block` in place.  Returns `Error::InvalidBlockSize` if
+    /// `block` is not `block_size()` bytes long.
+    fn decrypt_block(&self, block: &mut [u8]) -> Result<()>;
+}
+
+/// Adapter that makes any `BlockCipher<BLOCK>` usable as a
+/// `DynBlockCipher`, for example to keep ciphers with different block
+/// sizes in one `Vec<Box<dyn DynBlockCipher>>`.
+#[derive(Debug, Clone)]
+pub struct Dyn<C, const BLOCK: usize>(pub C);
+
+impl<C: BlockCipher<BLOCK>, const BLOCK: usize> Dyn<C, BLOCK> {
+    /// Return the wrapped cipher.
+    pub fn into_inner(self) -> C {
+        self.0
+    }
+}
+
+impl<C: BlockCipher<BLOCK>, const BLOCK: usize> DynBlockCipher for Dyn<C, BLOCK> {
+    fn block_size(&self) -> usize {
+        BLOCK
+    }
+
+    fn encrypt_block(&self, block: &mut [u8]) -> Result<()> {
+        self.0.encrypt_block(as_block(block)?);
+        Ok(())
+    }
+
+    fn decrypt_block(&self, block: &mut [u8]) -> Result<()> {
+        self.0.decrypt_block(as_block(block)?);
+        Ok(())
+    }
+}
+
+/// View `block` as an array of `BLOCK` bytes.  Returns
+/// `Error::InvalidBlockSize` with the actual length otherwise.
+pub fn as_block<const BLOCK: usize>(block: &mut [u8]) -> Result<&mut [u8; BLOCK]> {
+    let len = block.len();
+    <&mut [u8; BLOCK]>::try_from(block).map_err(|_| Error::InvalidBlockSize(len))
+}
+
+/// Split `data` into blocks of `BLOCK` bytes.  Returns
+/// `Error::InvalidBlockSize` with the length of `data` if it is not a
+/// multiple of the block size.
+pub fn to_blocks<const BLOCK: usize>(data: &[u8]) -> Result<Vec<[u8; BLOCK]>> {
+    if data.len() % BLOCK != 0 {
+        return Err(Error::InvalidBlockSize(data.len()));
+    }
+    Ok(data.chunks(BLOCK).map(|c| {
+        let mut block = [0; BLOCK];
+        block.copy_from_slice(c);
+        block
+    }).collect())
+}
+
+/// Concatenate `blocks` into a byte vector.
+pub fn from_blocks<const BLOCK: usize>(blocks: &[[u8; BLOCK]]) -> Vec<u8> {
+    blocks.iter().flat_map(|b| b.iter().cloned()).collect()
+}
+
+/// XOR `other` into `block`.
+fn xor_block<const BLOCK: usize>(block: &mut [u8; BLOCK], other: &[u8; BLOCK]) {
+    for (b, o) in block.iter_mut().zip(other.iter()) {
+        *b ^= *o;
+    }
+}
+
+/// Encrypt `blocks` in place in CBC mode, starting from `iv`.
+pub fn encrypt_cbc<C, const BLOCK: usize>(cipher: &C, iv: &[u8; BLOCK], blocks: &mut [[u8; BLOCK]])
+    where C: BlockCipher<BLOCK> + ?Sized {
+    let mut prev = *iv;
+    for block in blocks {
+        xor_block(block, &prev);
+        cipher.encrypt_block(block);
+        prev = *block;
+    }
+}
+
+/// Decrypt `blocks` in place in CBC mode, starting from `iv`.
+pub fn decrypt_cbc<C, const BLOCK: usize>(cipher: &C, iv: &[u8; BLOCK], blocks: &mut [[u8; BLOCK]])
+    where C: BlockCipher<BLOCK> + ?Sized {
+    let mut prev = *iv;
+    for block in blocks {
+        let c = *block;
+        cipher.decrypt_block(block);
+        xor_block(block, &prev);
+        prev = c;
+    }
+}
+
+/// Compute the raw CBC-MAC of `blocks`, the last block of their CBC
+/// encryption under a zero IV.  This is only secure for messages of
+/// a fixed length, or with a length prefix as in CCM.
+pub fn cbc_mac<C, const BLOCK: usize>(cipher: &C, blocks: &[[u8; BLOCK]]) -> [u8; BLOCK]
+    where C: BlockCipher<BLOCK> + ?Sized {
+    let mut mac = [0; BLOCK];
+    for block in blocks {
+        xor_block(&mut mac, block);
+        cipher.encrypt_block(&mut mac);
+    }
+    mac
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{as_block, cbc_mac, decrypt_cbc, encrypt_cbc, from_blocks, to_blocks};
+    use super::{BlockCipher, Dyn, DynBlockCipher, NewBlockCipher};
+    use crate::cipher::aes::{self, Aes, AesKey};
+    use crate::codec;
+    use crate::error::Error;
+
+    /// Toy cipher with 4-byte blocks, to check that nothing assumes
+    /// 16-byte blocks.
+    struct Rot(u8);
+
+    impl BlockCipher<4> for Rot {
+        fn encrypt_block(&self, block: &mut [u8; 4]) {
+            for b in block.iter_mut() {
+                *b = b.wrapping_add(self.0);
+            }
+            block.rotate_left(1);
+        }
+
+        fn decrypt_block(&self, block: &mut [u8; 4]) {
+            block.rotate_right(1);
+            for b in block.iter_mut() {
+                *b = b.wrapping_sub(self.0);
+            }
+        }
+    }
+
+    fn key() -> [u8; 16] {
+        let mut k = [0; 16];
+        k.copy_from_slice(&codec::hex::decode("000102030405060708090a0b0c0d0e0f").unwrap());
+        k
+    }
+
+    #[test]
+    fn aes_fips197() {
+        let aes = Aes::new(&key());
+        let mut block = [0; 16];
+        block.copy_from_slice(&codec::hex::decode("00112233445566778899aabbccddeeff").unwrap());
+        aes.encrypt_block(&mut block);
+        assert_eq!(codec::hex::encode(&block), "69c4e0d86a7b0430d8cdb78070b4c55a");
+        aes.decrypt_block(&mut block);
+        assert_eq!(codec::hex::encode(&block), "00112233445566778899aabbccddeeff");
+    }
+
+    #[test]
+    fn dyn_adapter() {
+        let ciphers: Vec<Box<dyn DynBlockCipher>> = vec![
+            Box::new(Dyn(Aes::new(&key()))),
+            Box::new(Dyn(Rot(3))),
+        ];
+        for cipher in &ciphers {
+            let mut block = vec![7; cipher.block_size()];
+            cipher.encrypt_block(&mut block).unwrap();
+            assert!(block != vec![7; cipher.block_size()]);
+            cipher.decrypt_block(&mut block).unwrap();
+            assert_eq!(block, vec![7; cipher.block_size()]);
+
+            let mut short = vec![7; cipher.block_size() - 1];
+            match cipher.encrypt_block(&mut short) {
+                Err(Error::InvalidBlockSize(n)) => assert_eq!(n, cipher.block_size() - 1),
+                r => panic!("unexpected result {:?}", r),
+            }
+        }
+    }
+
+    #[test]
+    fn cbc_matches_aes_module() {
+        let plaintext = b"YELLOW SUBMARINEYELLOW SUBMARINE";
+        let iv = [9; 16];
+        let aes = Aes::new(&key());
+        let mut blocks = to_blocks::<16>(plaintext).unwrap();
+        encrypt_cbc(&aes, &iv, &mut blocks);
+        let expected = aes::encrypt_cbc_with(&AesKey::from(key()), &iv, plaintext,
+                                             &crate::padding::NoPadding).unwrap();
+        assert_eq!(from_blocks(&blocks), expected);
+        let zero_iv = aes::encrypt_cbc_with(&AesKey::from(key()), &[0; 16], plaintext,
+                                            &crate::padding::NoPadding).unwrap();
+        assert_eq!(cbc_mac(&aes, &to_blocks::<16>(plaintext).unwrap())[..], zero_iv[16..]);
+
+        decrypt_cbc(&&aes, &iv, &mut blocks);
+        assert_eq!(&from_blocks(&blocks)[..], &plaintext[..]);
+    }
+
+    #[test]
+    fn block_conversions() {
+        assert!(to_blocks::<4>(b"abcdefgh").is_ok());
+        match to_blocks::<4>(b"abcdefg") {
+            Err(Error::InvalidBlockSize(7)) => (),
+            r => panic!("unexpected result {:?}", r),
+        }
+        let mut data = [1, 2, 3];
+        assert!(as_block::<4>(&mut data).is_err());
+        assert_eq!(as_block::<3>(&mut data).unwrap(), &mut [1, 2, 3]);
+    }
+
+    quickcheck! {
+        fn cbc_roundtrip(data: Vec<u8>, iv: u32) -> bool {
+            let cipher = Box::new(Rot(17));
+            let n = data.len() - data.len() % 4;
+            let mut blocks = to_blocks::<4>(&data[..n]).unwrap();
+            let iv = iv.to_be_bytes();
+            encrypt_cbc(&cipher, &iv, &mut blocks);
+            decrypt_cbc(&cipher, &iv, &mut blocks);
+            from_blocks(&blocks) == data[..n]
+        }
+    }
+}
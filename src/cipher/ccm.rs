@@ -0,0 +1,180 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! AES in Counter with CBC-MAC mode (CCM), as specified in NIST SP
+//! 800-38C, restricted to 96-bit nonces and 128-bit tags.  The tag is
+//! a CBC-MAC over a header block, the additional data and the
+//! plaintext, encrypted with the first keystream block; the plaintext
+//! is encrypted in CTR mode with the following ones.  Do not use this
+//! for production!
+
+use byteorder::{BigEndian, ByteOrder};
+
+use crate::cipher::aes::{self, Aes, AesKey};
+use crate::cipher::block::{self, BlockCipher};
+use crate::cipher::{nonce_array, Aead};
+use crate::error::{Error, Result};
+use crate::mac::hmac;
+
+/// Size of the field holding the plaintext length, in bytes.  With a
+/// 12-byte nonce, 3 bytes remain in a block.
+const LENGTH_SIZE: usize = 3;
+
+/// Return the counter block `Ctr_i` for `nonce`.
+fn counter_block(nonce: &[u8; 12], i: u32) -> [u8; 16] {
+    let mut block = [0; 16];
+    block[0] = (LENGTH_SIZE - 1) as u8;
+    block[1..13].copy_from_slice(nonce);
+    block[13] = (i >> 16) as u8;
+    block[14] = (i >> 8) as u8;
+    block[15] = i as u8;
+    block
+}
+
+/// Return the CBC-MAC input for `aad` and `plaintext`: the header
+/// block `B0`, the length-prefixed additional data and the plaintext,
+/// each zero-padded to whole blocks.
+fn mac_data(nonce: &[u8; 12], aad: &[u8], plaintext: &[u8]) -> Vec<u8> {
+    let mut data = vec![0; 16];
+    let adata = if aad.is_empty() { 0 } else { 0x40 };
+    data[0] = adata | (((16 - 2) / 2) << 3) as u8 | (LENGTH_SIZE - 1) as u8;
+    data[1..13].copy_from_slice(nonce);
+    BigEndian::write_uint(&mut data[13..], plaintext.len() as u64, LENGTH_SIZE);
+
+    if !aad.is_empty() {
+        let mut a = Vec::with_capacity(aad.len() + 6);
+        if aad.len() < 0xff00 {
+            a.extend_from_slice(&[(aad.len() >> 8) as u8, aad.len() as u8]);
+        } else {
+            let mut len = [0xff, 0xfe, 0, 0, 0, 0];
+            BigEndian::write_u32(&mut len[2..], aad.len() as u32);
+            a.extend_from_slice(&len);
+        }
+        a.extend_from_slice(aad);
+        data.extend(pad(&a));
+    }
+    data.extend(pad(plaintext));
+    data
+}
+
+/// Zero-pad `data` to whole blocks.
+fn pad(data: &[u8]) -> Vec<u8> {
+    let mut padded = data.to_vec();
+    padded.extend(vec![0; (16 - data.len() % 16) % 16]);
+    padded
+}
+
+/// Compute the authentication tag for `aad` and `plaintext`.
+fn tag(key: &AesKey, nonce: &[u8; 12], aad: &[u8], plaintext: &[u8]) -> [u8; 16] {
+    let cipher = Aes::from(key);
+    let blocks = block::to_blocks(&mac_data(nonce, aad, plaintext)).unwrap();
+    let mut t = block::cbc_mac(&cipher, &blocks);
+    let mut mask = counter_block(nonce, 0);
+    cipher.encrypt_block(&mut mask);
+    for x in 0..16 {
+        t[x] ^= mask[x];
+    }
+    t
+}
+
+/// Encrypt `plaintext` and authenticate it together with the
+/// additional data `aad`.  Returns the ciphertext and the tag.
+///
+/// # Panics
+/// Panics if the plaintext is longer than `2^24 - 1` bytes, which
+/// does not fit into the length field.
+pub fn encrypt(key: &AesKey, nonce: &[u8; 12], aad: &[u8], plaintext: &[u8]) -> (Vec<u8>, [u8; 16]) {
+    assert!(plaintext.len() < 1 << (8 * LENGTH_SIZE), "plaintext too long for CCM");
+    let t = tag(key, nonce, aad, plaintext);
+    (aes::encrypt_ctr(key, &counter_block(nonce, 1), plaintext), t)
+}
+
+/// Decrypt `ciphertext` and check its tag together with `aad`.
+/// Returns an error if the tag does not match.
+pub fn decrypt(key: &AesKey, nonce: &[u8; 12], aad: &[u8], ciphertext: &[u8], t: &[u8])
+               -> Result<Vec<u8>> {
+    if ciphertext.len() >= 1 << (8 * LENGTH_SIZE) {
+        return Err(Error::InvalidTag);
+    }
+    let plaintext = aes::decrypt_ctr(key, &counter_block(nonce, 1), ciphertext);
+    if !hmac::verify(&tag(key, nonce, aad, &plaintext), t) {
+        return Err(Error::InvalidTag);
+    }
+    Ok(plaintext)
+}
+
+/// AES-CCM with a fixed key, as an `Aead`.
+pub struct Ccm {
+    key: AesKey,
+}
+
+impl Ccm {
+    /// Create an AEAD instance for `key`.
+    pub fn new(key: AesKey) -> Ccm {
+        Ccm { key: key }
+    }
+}
+
+impl Aead for Ccm {
+    const NONCE_SIZE: usize = 12;
+    const TAG_SIZE: usize = 16;
+
+    fn seal(&self, nonce: &[u8], aad: &[u8], plaintext: &[u8]) -> Vec<u8> {
+        let (mut sealed, t) = encrypt(&self.key, &nonce_array(nonce), aad, plaintext);
+        sealed.extend_from_slice(&t);
+        sealed
+    }
+
+    fn open(&self, nonce: &[u8], aad: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>> {
+        let nonce = nonce_array(nonce);
+        if ciphertext.len() < Self::TAG_SIZE {
+            return Err(Error::InvalidTag);
+        }
+        let (c, t) = ciphertext.split_at(ciphertext.len() - Self::TAG_SIZE);
+        decrypt(&self.key, &nonce, aad, c, t)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{encrypt, decrypt};
+    use crate::cipher::aes::AesKey;
+    use crate::codec::hex;
+    use crate::error::Error;
+
+    fn key() -> AesKey {
+        AesKey::from_hex("404142434445464748494a4b4c4d4e4f").unwrap()
+    }
+
+    const NONCE: [u8; 12] = [0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18, 0x19, 0x1a, 0x1b];
+
+    #[test]
+    fn ccm_empty() {
+        let (c, t) = encrypt(&key(), &NONCE, b"", b"");
+        assert!(c.is_empty());
+        assert_eq!("538f76630f36a98a2f502d9b23d86343", hex::encode(&t));
+    }
+
+    #[test]
+    fn ccm_short_aad() {
+        let aad: Vec<u8> = (0..20).collect();
+        let plaintext: Vec<u8> = (0x20..0x38).collect();
+        let (c, t) = encrypt(&key(), &NONCE, &aad, &plaintext);
+        assert_eq!("e3b201a9f5b71a7a9b1ceaeccd97e70b6176aad9a4428aa5", hex::encode(&c));
+        assert_eq!("c87ae488918de93f17dd3e4934347f44", hex::encode(&t));
+        assert_eq!(plaintext, decrypt(&key(), &NONCE, &aad, &c, &t).unwrap());
+    }
+
+    #[test]
+    fn ccm_long_aad() {
+        let plaintext: Vec<u8> = (0..40).collect();
+        let (c, t) = encrypt(&key(), &NONCE, &[0; 300], &plaintext);
+        assert_eq!("c3922189d5973a5abb3ccaccedb7c72b41568af98462aa85743bf1f436da2cc38c57cf400e0218ce",
+                   hex::encode(&c));
+        assert_eq!("95420023fdfdeb21038d11d88349eea8", hex::encode(&t));
+        match decrypt(&key(), &NONCE, &[0; 299], &c, &t) {
+            Err(Error::InvalidTag) => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+}
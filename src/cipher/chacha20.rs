@@ -0,0 +1,112 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! The ChaCha20 stream cipher, as specified in RFC 7539, with a
+//! 256-bit key, a 96-bit nonce and a 32-bit block counter.  Do not use
+//! this for production!
+
+use byteorder::{ByteOrder, LittleEndian};
+
+/// Size of a keystream block, in bytes.
+pub const BLOCK_SIZE: usize = 64;
+
+/// The ChaCha quarter round on the state words `a`, `b`, `c` and `d`.
+fn quarter_round(s: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize) {
+    s[a] = s[a].wrapping_add(s[b]); s[d] ^= s[a]; s[d] = s[d].rotate_left(16);
+    s[c] = s[c].wrapping_add(s[d]); s[b] ^= s[c]; s[b] = s[b].rotate_left(12);
+    s[a] = s[a].wrapping_add(s[b]); s[d] ^= s[a]; s[d] = s[d].rotate_left(8);
+    s[c] = s[c].wrapping_add(s[d]); s[b] ^= s[c]; s[b] = s[b].rotate_left(7);
+}
+
+/// Return the keystream block for `key`, `nonce` and the block
+/// counter `counter`.
+pub fn block(key: &[u8; 32], counter: u32, nonce: &[u8; 12]) -> [u8; 64] {
+    let mut init = [0u32; 16];
+    init[0] = 0x6170_7865;
+    init[1] = 0x3320_646e;
+    init[2] = 0x7962_2d32;
+    init[3] = 0x6b20_6574;
+    for i in 0..8 {
+        init[4 + i] = LittleEndian::read_u32(&key[4 * i..]);
+    }
+    init[12] = counter;
+    for i in 0..3 {
+        init[13 + i] = LittleEndian::read_u32(&nonce[4 * i..]);
+    }
+
+    let mut s = init;
+    for _ in 0..10 {
+        quarter_round(&mut s, 0, 4, 8, 12);
+        quarter_round(&mut s, 1, 5, 9, 13);
+        quarter_round(&mut s, 2, 6, 10, 14);
+        quarter_round(&mut s, 3, 7, 11, 15);
+        quarter_round(&mut s, 0, 5, 10, 15);
+        quarter_round(&mut s, 1, 6, 11, 12);
+        quarter_round(&mut s, 2, 7, 8, 13);
+        quarter_round(&mut s, 3, 4, 9, 14);
+    }
+
+    let mut output = [0u8; 64];
+    for i in 0..16 {
+        LittleEndian::write_u32(&mut output[4 * i..], s[i].wrapping_add(init[i]));
+    }
+    output
+}
+
+/// Encrypt `plaintext` with the keystream for `key` and `nonce`,
+/// starting at block `counter`.
+pub fn encrypt(key: &[u8; 32], counter: u32, nonce: &[u8; 12], plaintext: &[u8]) -> Vec<u8> {
+    let mut result = Vec::with_capacity(plaintext.len());
+    for (i, chunk) in plaintext.chunks(BLOCK_SIZE).enumerate() {
+        let keystream = block(key, counter.wrapping_add(i as u32), nonce);
+        for x in 0..chunk.len() {
+            result.push(chunk[x] ^ keystream[x]);
+        }
+    }
+    result
+}
+
+/// Decrypt `ciphertext`, which is the same as encryption.
+pub fn decrypt(key: &[u8; 32], counter: u32, nonce: &[u8; 12], ciphertext: &[u8]) -> Vec<u8> {
+    encrypt(key, counter, nonce, ciphertext)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{block, encrypt, decrypt};
+    use crate::codec::hex;
+
+    fn key() -> [u8; 32] {
+        let mut k = [0; 32];
+        for i in 0..32 {
+            k[i] = i as u8;
+        }
+        k
+    }
+
+    #[test]
+    fn rfc7539_block() {
+        // RFC 7539, section 2.3.2.
+        let nonce = [0, 0, 0, 0x09, 0, 0, 0, 0x4a, 0, 0, 0, 0];
+        let expected = hex::decode("10f1e7e4d13b5915500fdd1fa32071c4c7d1f4c733c068030422aa9ac3d46c4e\
+                                    d2826446079faa0914c2d705d98b02a2b5129cd1de164eb9cbd083e8a2503c4e")
+            .unwrap();
+        assert_eq!(expected, block(&key(), 1, &nonce).to_vec());
+    }
+
+    #[test]
+    fn rfc7539_encrypt() {
+        // RFC 7539, section 2.4.2.
+        let nonce = [0, 0, 0, 0, 0, 0, 0, 0x4a, 0, 0, 0, 0];
+        let plaintext = b"Ladies and Gentlemen of the class of '99: If I could offer you \
+                          only one tip for the future, sunscreen would be it.";
+        let expected = hex::decode("6e2e359a2568f98041ba0728dd0d6981e97e7aec1d4360c20a27afccfd9fae0b\
+                                    f91b65c5524733ab8f593dabcd62b3571639d624e65152ab8f530c359f0861d8\
+                                    07ca0dbf500d6a6156a38e088a22b65e52bc514d16ccf806818ce91ab7793736\
+                                    5af90bbf74a35be6b40b8eedf2785e42874d")
+            .unwrap();
+        let ciphertext = encrypt(&key(), 1, &nonce, plaintext);
+        assert_eq!(expected, ciphertext);
+        assert_eq!(plaintext.to_vec(), decrypt(&key(), 1, &nonce, &ciphertext));
+    }
+}
@@ -0,0 +1,135 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! The ChaCha20-Poly1305 AEAD construction, as specified in RFC 7539.
+//! The plaintext is encrypted with ChaCha20 starting at block counter
+//! 1, and the Poly1305 key for the message is taken from block 0.
+//! The sealed message is the ciphertext followed by the 16-byte tag.
+//! Do not use this for production!
+
+use byteorder::{ByteOrder, LittleEndian};
+
+use crate::cipher::chacha20;
+use crate::cipher::{nonce_array, Aead};
+use crate::error::{Error, Result};
+use crate::mac::poly1305;
+
+/// Size of the authentication tag, in bytes.
+pub const TAG_SIZE: usize = 16;
+
+/// Return the one-time Poly1305 key for `key` and `nonce`.
+pub fn poly1305_key(key: &[u8; 32], nonce: &[u8; 12]) -> [u8; 32] {
+    let mut otk = [0; 32];
+    otk.copy_from_slice(&chacha20::block(key, 0, nonce)[..32]);
+    otk
+}
+
+/// Return the Poly1305 input for `aad` and `ciphertext`: both
+/// zero-padded to a multiple of 16 bytes, followed by their lengths
+/// as 64-bit little-endian numbers.
+pub fn mac_data(aad: &[u8], ciphertext: &[u8]) -> Vec<u8> {
+    let mut data = Vec::with_capacity(aad.len() + ciphertext.len() + 48);
+    for part in &[aad, ciphertext] {
+        data.extend_from_slice(part);
+        data.extend(vec![0; (16 - part.len() % 16) % 16]);
+    }
+    let mut lengths = [0; 16];
+    LittleEndian::write_u64(&mut lengths[..8], aad.len() as u64);
+    LittleEndian::write_u64(&mut lengths[8..], ciphertext.len() as u64);
+    data.extend_from_slice(&lengths);
+    data
+}
+
+/// Encrypt `plaintext` and authenticate it together with the
+/// additional data `aad`.  Returns the ciphertext with the tag
+/// appended.
+pub fn seal(key: &[u8; 32], nonce: &[u8; 12], aad: &[u8], plaintext: &[u8]) -> Vec<u8> {
+    let mut sealed = chacha20::encrypt(key, 1, nonce, plaintext);
+    let tag = poly1305::mac(&poly1305_key(key, nonce), &mac_data(aad, &sealed));
+    sealed.extend_from_slice(&tag);
+    sealed
+}
+
+/// Check the tag of the sealed message `sealed` and `aad`, and
+/// decrypt the ciphertext.  Returns `Error::InvalidTag` if the tag
+/// does not match or the message is too short to hold one.
+pub fn open(key: &[u8; 32], nonce: &[u8; 12], aad: &[u8], sealed: &[u8]) -> Result<Vec<u8>> {
+    if sealed.len() < TAG_SIZE {
+        return Err(Error::InvalidTag);
+    }
+    let (ciphertext, tag) = sealed.split_at(sealed.len() - TAG_SIZE);
+    if !poly1305::verify(&poly1305_key(key, nonce), &mac_data(aad, ciphertext), tag) {
+        return Err(Error::InvalidTag);
+    }
+    Ok(chacha20::decrypt(key, 1, nonce, ciphertext))
+}
+
+/// ChaCha20-Poly1305 with a fixed key, as an `Aead`.
+pub struct ChaCha20Poly1305 {
+    key: [u8; 32],
+}
+
+impl ChaCha20Poly1305 {
+    /// Create an AEAD instance for `key`.
+    pub fn new(key: [u8; 32]) -> ChaCha20Poly1305 {
+        ChaCha20Poly1305 { key: key }
+    }
+}
+
+impl Aead for ChaCha20Poly1305 {
+    const NONCE_SIZE: usize = 12;
+    const TAG_SIZE: usize = TAG_SIZE;
+
+    fn seal(&self, nonce: &[u8], aad: &[u8], plaintext: &[u8]) -> Vec<u8> {
+        seal(&self.key, &nonce_array(nonce), aad, plaintext)
+    }
+
+    fn open(&self, nonce: &[u8], aad: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>> {
+        open(&self.key, &nonce_array(nonce), aad, ciphertext)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{seal, open};
+    use crate::codec::hex;
+    use crate::error::Error;
+
+    #[test]
+    fn rfc7539() {
+        // RFC 7539, section 2.8.2.
+        let mut key = [0; 32];
+        for i in 0..32 {
+            key[i] = 0x80 + i as u8;
+        }
+        let nonce = [0x07, 0, 0, 0, 0x40, 0x41, 0x42, 0x43, 0x44, 0x45, 0x46, 0x47];
+        let aad = hex::decode("50515253c0c1c2c3c4c5c6c7").unwrap();
+        let plaintext = b"Ladies and Gentlemen of the class of '99: If I could offer you \
+                          only one tip for the future, sunscreen would be it.";
+        let expected = hex::decode("d31a8d34648e60db7b86afbc53ef7ec2a4aded51296e08fea9e2b5a736ee62d6\
+                                    3dbea45e8ca9671282fafb69da92728b1a71de0a9e060b2905d6a5b67ecd3b36\
+                                    92ddbd7f2d778b8c9803aee328091b58fab324e4fad675945585808b4831d7bc\
+                                    3ff4def08e4b7a9de576d26586cec64b6116\
+                                    1ae10b594f09e26a7e902ecbd0600691")
+            .unwrap();
+        let sealed = seal(&key, &nonce, &aad, plaintext);
+        assert_eq!(expected, sealed);
+        assert_eq!(plaintext.to_vec(), open(&key, &nonce, &aad, &sealed).unwrap());
+    }
+
+    #[test]
+    fn tampering() {
+        let key = [0x42; 32];
+        let nonce = [0x24; 12];
+        let sealed = seal(&key, &nonce, b"header", b"attack at dawn");
+        let mut modified = sealed.clone();
+        modified[0] ^= 1;
+        for &(aad, msg) in &[(&b"header"[..], &modified[..]), (&b"headex"[..], &sealed[..]),
+                            (&b"header"[..], &sealed[..15])] {
+            match open(&key, &nonce, aad, msg) {
+                Err(Error::InvalidTag) => (),
+                r => panic!("unexpected result: {:?}", r),
+            }
+        }
+    }
+}
@@ -0,0 +1,352 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! A container for AES ciphertexts that records how they were made,
+//! so that oracles and test fixtures can pass ciphertexts around
+//! without agreeing on the mode and IV out of band.  With the `serde`
+//! feature, envelopes serialize to structures with hex-encoded IV and
+//! data, for example in JSON:
+//!
+//! ```text
+//! {"mode":"Cbc","iv":"000102...","data":"9f3a..."}
+//! ```
+//!
+//! `seal` and `open` wrap an envelope in a self-describing binary
+//! format, optionally authenticated with HMAC-SHA256 over everything
+//! before the tag (encrypt-then-MAC):
+//!
+//! ```text
+//! magic "CPEV" | version | cipher | mode | MAC | IV length | IV |
+//! data length (u32, big-endian) | data | tag
+//! ```
+//!
+//! The cipher byte is 1, 2 or 3 for AES-128, AES-192 and AES-256,
+//! the mode byte 1, 2 or 3 for ECB, CBC and CTR, and the MAC byte 0
+//! for none and 1 for HMAC-SHA256.  Without a MAC, the format offers
+//! no integrity at all, which makes it a target for the bit-flipping
+//! and padding oracle attacks.
+
+use byteorder::{BigEndian, ByteOrder};
+
+use crate::cipher::aes::{self, AesKey};
+use crate::error::{Error, Result};
+use crate::hash::sha256::Sha256;
+use crate::mac::hmac;
+use crate::random;
+
+/// First bytes of a sealed envelope.
+pub const MAGIC: &[u8; 4] = b"CPEV";
+
+/// Version of the binary format written by `seal`.
+pub const VERSION: u8 = 1;
+
+/// Length of the HMAC-SHA256 tag.
+const TAG_SIZE: usize = 32;
+
+/// Block cipher mode of an envelope.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Mode {
+    /// ECB with PKCS#7 padding.  The IV is empty.
+    Ecb,
+    /// CBC with PKCS#7 padding.
+    Cbc,
+    /// CTR with the crate's default counter layout.
+    Ctr,
+}
+
+/// An AES ciphertext with its mode and IV.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CiphertextEnvelope {
+    /// Mode the data was encrypted with.
+    pub mode: Mode,
+    /// Initialization vector, 16 bytes for CBC and CTR, empty for ECB.
+    #[cfg_attr(feature = "serde", serde(with = "hex_bytes"))]
+    pub iv: Vec<u8>,
+    /// The ciphertext.
+    #[cfg_attr(feature = "serde", serde(with = "hex_bytes"))]
+    pub data: Vec<u8>,
+}
+
+impl CiphertextEnvelope {
+    /// Encrypt `plaintext` under `key` in `mode`, with a random IV.
+    pub fn seal(key: &AesKey, mode: Mode, plaintext: &[u8]) -> CiphertextEnvelope {
+        let mut iv = [0u8; 16];
+        random::fill_bytes(&mut iv);
+        let (iv, data) = match mode {
+            Mode::Ecb => (Vec::new(), aes::encrypt_ecb(key, plaintext)),
+            Mode::Cbc => (iv.to_vec(), aes::encrypt_cbc(key, &iv, plaintext)),
+            Mode::Ctr => (iv.to_vec(), aes::encrypt_ctr(key, &iv, plaintext)),
+        };
+        CiphertextEnvelope {
+            mode,
+            iv,
+            data,
+        }
+    }
+
+    /// Decrypt the envelope with `key`.  Returns
+    /// `Error::InvalidIvLength` if the IV does not fit the mode, and
+    /// the errors of the mode's decryption function.
+    pub fn open(&self, key: &AesKey) -> Result<Vec<u8>> {
+        if self.mode == Mode::Ecb {
+            if !self.iv.is_empty() {
+                return Err(Error::InvalidIvLength(self.iv.len()));
+            }
+            return aes::decrypt_ecb(key, &self.data);
+        }
+        if self.iv.len() != 16 {
+            return Err(Error::InvalidIvLength(self.iv.len()));
+        }
+        let mut iv = [0u8; 16];
+        iv.copy_from_slice(&self.iv);
+        match self.mode {
+            Mode::Cbc => aes::decrypt_cbc(key, &iv, &self.data),
+            _ => Ok(aes::decrypt_ctr(key, &iv, &self.data)),
+        }
+    }
+}
+
+impl Mode {
+    fn id(self) -> u8 {
+        match self {
+            Mode::Ecb => 1,
+            Mode::Cbc => 2,
+            Mode::Ctr => 3,
+        }
+    }
+
+    fn from_id(id: u8) -> Option<Mode> {
+        match id {
+            1 => Some(Mode::Ecb),
+            2 => Some(Mode::Cbc),
+            3 => Some(Mode::Ctr),
+            _ => None,
+        }
+    }
+}
+
+/// The parts of a sealed envelope, as returned by `parse`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Sealed {
+    /// Format version.
+    pub version: u8,
+    /// AES key size in bytes.
+    pub key_size: usize,
+    /// Mode, IV and ciphertext.
+    pub envelope: CiphertextEnvelope,
+    /// HMAC-SHA256 tag, if the envelope is authenticated.
+    pub tag: Option<Vec<u8>>,
+    /// Length of the authenticated part of the input, which precedes
+    /// the tag.
+    authenticated_len: usize,
+}
+
+/// Encrypt `plaintext` under `key` in `mode` with a random IV, and
+/// encode the result in the binary format.  If `mac_key` is given,
+/// append an HMAC-SHA256 tag under it.
+pub fn seal(key: &AesKey, mode: Mode, mac_key: Option<&[u8]>, plaintext: &[u8]) -> Vec<u8> {
+    let envelope = CiphertextEnvelope::seal(key, mode, plaintext);
+    let key_id = (key.as_bytes().len() - 8) / 8;
+    let mut out = Vec::with_capacity(MAGIC.len() + 10 + envelope.iv.len() + envelope.data.len() + TAG_SIZE);
+    out.extend(&MAGIC[..]);
+    out.push(VERSION);
+    out.push(key_id as u8);
+    out.push(mode.id());
+    out.push(if mac_key.is_some() { 1 } else { 0 });
+    out.push(envelope.iv.len() as u8);
+    out.extend(&envelope.iv);
+    let mut len = [0u8; 4];
+    BigEndian::write_u32(&mut len, envelope.data.len() as u32);
+    out.extend(&len);
+    out.extend(&envelope.data);
+    if let Some(mac_key) = mac_key {
+        let tag = hmac::hmac::<Sha256>(mac_key, &out);
+        out.extend(tag);
+    }
+    out
+}
+
+/// Split the binary envelope `sealed` into its parts, without
+/// decrypting or checking the tag.  Returns `Error::InvalidEnvelope`
+/// if the magic, version or any of the IDs is unknown, or if the
+/// input is truncated or too long.
+pub fn parse(sealed: &[u8]) -> Result<Sealed> {
+    let header = MAGIC.len() + 5;
+    if sealed.len() < header || sealed[..MAGIC.len()] != MAGIC[..] || sealed[4] != VERSION {
+        return Err(Error::InvalidEnvelope);
+    }
+    let key_size = match sealed[5] {
+        1..=3 => 8 + 8 * sealed[5] as usize,
+        _ => return Err(Error::InvalidEnvelope),
+    };
+    let mode = Mode::from_id(sealed[6]).ok_or(Error::InvalidEnvelope)?;
+    let tag_size = match sealed[7] {
+        0 => 0,
+        1 => TAG_SIZE,
+        _ => return Err(Error::InvalidEnvelope),
+    };
+    let iv_len = sealed[8] as usize;
+    if sealed.len() < header + iv_len + 4 {
+        return Err(Error::InvalidEnvelope);
+    }
+    let iv = sealed[header..header + iv_len].to_vec();
+    let data_start = header + iv_len + 4;
+    let data_len = BigEndian::read_u32(&sealed[header + iv_len..data_start]) as usize;
+    if sealed.len() != data_start + data_len + tag_size {
+        return Err(Error::InvalidEnvelope);
+    }
+    let authenticated_len = data_start + data_len;
+    Ok(Sealed {
+        version: sealed[4],
+        key_size,
+        envelope: CiphertextEnvelope {
+            mode,
+            iv,
+            data: sealed[data_start..authenticated_len].to_vec(),
+        },
+        tag: if tag_size == 0 { None } else { Some(sealed[authenticated_len..].to_vec()) },
+        authenticated_len,
+    })
+}
+
+/// Check and decrypt the binary envelope `sealed` with `key`.  The
+/// tag is checked under `mac_key`; an envelope must have a tag if
+/// and only if `mac_key` is given, otherwise `Error::InvalidTag` is
+/// returned, as it is for a wrong tag.  Returns
+/// `Error::InvalidKeyLength` if `key` does not have the key size of
+/// the envelope, and the errors of `parse` and
+/// `CiphertextEnvelope::open`.
+pub fn open(key: &AesKey, mac_key: Option<&[u8]>, sealed: &[u8]) -> Result<Vec<u8>> {
+    let parts = parse(sealed)?;
+    match (mac_key, &parts.tag) {
+        (None, None) => (),
+        (Some(mac_key), Some(tag)) => {
+            let expected = hmac::hmac::<Sha256>(mac_key, &sealed[..parts.authenticated_len]);
+            if !hmac::verify(&expected, tag) {
+                return Err(Error::InvalidTag);
+            }
+        }
+        _ => return Err(Error::InvalidTag),
+    }
+    if key.as_bytes().len() != parts.key_size {
+        return Err(Error::InvalidKeyLength(key.as_bytes().len()));
+    }
+    parts.envelope.open(key)
+}
+
+/// Serialize byte vectors as hex strings.
+#[cfg(feature = "serde")]
+mod hex_bytes {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use serde::de::Error;
+
+    use crate::codec::hex;
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_str(&hex::encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<u8>, D::Error> {
+        let s = String::deserialize(d)?;
+        hex::decode(&s).map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CiphertextEnvelope, Mode, seal, open, parse, MAGIC};
+    use crate::cipher::aes::AesKey;
+    use crate::error::Error;
+
+    #[test]
+    fn seal_open() {
+        let key = AesKey::from([0x42; 16]);
+        for &mode in [Mode::Ecb, Mode::Cbc, Mode::Ctr].iter() {
+            let envelope = CiphertextEnvelope::seal(&key, mode, b"attack at dawn");
+            assert_eq!(mode, envelope.mode);
+            assert_eq!(b"attack at dawn".to_vec(), envelope.open(&key).unwrap());
+        }
+    }
+
+    #[test]
+    fn bad_iv() {
+        let key = AesKey::from([0x42; 16]);
+        let mut envelope = CiphertextEnvelope::seal(&key, Mode::Cbc, b"attack at dawn");
+        envelope.iv.pop();
+        match envelope.open(&key) {
+            Err(Error::InvalidIvLength(15)) => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn sealed_format() {
+        let key = AesKey::from([0x42; 24]);
+        let sealed = seal(&key, Mode::Ctr, None, b"attack at dawn");
+        assert_eq!(&MAGIC[..], &sealed[..4]);
+        assert_eq!(&[1, 2, 3, 0, 16][..], &sealed[4..9]);
+        assert_eq!(9 + 16 + 4 + 14, sealed.len());
+        let parts = parse(&sealed).unwrap();
+        assert_eq!((1, 24, Mode::Ctr, None), (parts.version, parts.key_size, parts.envelope.mode, parts.tag));
+        assert_eq!(b"attack at dawn".to_vec(), open(&key, None, &sealed).unwrap());
+    }
+
+    #[test]
+    fn sealed_mac() {
+        let key = AesKey::from([0x42; 16]);
+        for &mode in [Mode::Ecb, Mode::Cbc, Mode::Ctr].iter() {
+            let sealed = seal(&key, mode, Some(b"mac key"), b"attack at dawn");
+            assert_eq!(b"attack at dawn".to_vec(), open(&key, Some(b"mac key"), &sealed).unwrap());
+            assert!(parse(&sealed).unwrap().tag.is_some());
+            for i in 0..sealed.len() {
+                let mut tampered = sealed.clone();
+                tampered[i] ^= 1;
+                assert!(open(&key, Some(b"mac key"), &tampered).is_err());
+            }
+            match open(&key, None, &sealed) {
+                Err(Error::InvalidTag) => (),
+                r => panic!("unexpected result: {:?}", r),
+            }
+            match open(&key, Some(b"other key"), &sealed) {
+                Err(Error::InvalidTag) => (),
+                r => panic!("unexpected result: {:?}", r),
+            }
+        }
+    }
+
+    #[test]
+    fn sealed_errors() {
+        let key = AesKey::from([0x42; 16]);
+        let sealed = seal(&key, Mode::Cbc, None, b"attack at dawn");
+        for bad in [&sealed[..sealed.len() - 1], &sealed[..3], b"CPEV\x02\x01\x02\x00\x00\x00\x00\x00\x00"].iter() {
+            match open(&key, None, bad) {
+                Err(Error::InvalidEnvelope) => (),
+                r => panic!("unexpected result: {:?}", r),
+            }
+        }
+        match open(&AesKey::from([0x42; 32]), None, &sealed) {
+            Err(Error::InvalidKeyLength(32)) => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+        match open(&key, Some(b"mac key"), &sealed) {
+            Err(Error::InvalidTag) => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn json() {
+        let envelope = CiphertextEnvelope {
+            mode: Mode::Cbc,
+            iv: vec![0; 16],
+            data: vec![0xde, 0xad],
+        };
+        let json = serde_json::to_string(&envelope).unwrap();
+        assert_eq!(r#"{"mode":"Cbc","iv":"00000000000000000000000000000000","data":"dead"}"#, json);
+        assert_eq!(envelope, serde_json::from_str(&json).unwrap());
+        assert!(serde_json::from_str::<CiphertextEnvelope>(r#"{"mode":"Cbc","iv":"0","data":""}"#).is_err());
+    }
+}
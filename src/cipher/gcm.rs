@@ -0,0 +1,203 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! AES in Galois/Counter Mode (GCM), as specified in NIST SP 800-38D,
+//! restricted to 96-bit nonces.  The plaintext is encrypted in CTR
+//! mode, and the authentication tag is computed by evaluating a
+//! polynomial in the authentication key `H = E(K, 0)` over GF(2^128).
+//! Do not use this for production!
+
+use crate::cipher::aes::{self, AesKey};
+use crate::cipher::{nonce_array, Aead};
+use crate::error::{Error, Result};
+use crate::mac::hmac;
+use crate::math::gf128::Gf128;
+
+/// Return the authentication key `H`, the encryption of the zero
+/// block.
+pub fn auth_key(key: &AesKey) -> Gf128 {
+    let mut h = [0; 16];
+    aes::encrypt(key, &[0; 16], &mut h);
+    Gf128::from_block(&h)
+}
+
+/// Return the GHASH input for `aad` and `ciphertext`: both zero-padded
+/// to whole blocks, followed by a block holding their lengths in bits.
+pub fn ghash_blocks(aad: &[u8], ciphertext: &[u8]) -> Vec<Gf128> {
+    let mut blocks = Vec::new();
+    for data in &[aad, ciphertext] {
+        for chunk in data.chunks(16) {
+            let mut block = [0; 16];
+            block[..chunk.len()].copy_from_slice(chunk);
+            blocks.push(Gf128::from_block(&block));
+        }
+    }
+    let lengths = (aad.len() as u128 * 8) << 64 | ciphertext.len() as u128 * 8;
+    blocks.push(Gf128(lengths.reverse_bits()));
+    blocks
+}
+
+/// Compute GHASH of `aad` and `ciphertext` under the authentication
+/// key `h`.  For blocks `X1, ..., Xm` this is
+///
+/// ```text
+/// X1*H^m + X2*H^(m-1) + ... + Xm*H
+/// ```
+pub fn ghash(h: Gf128, aad: &[u8], ciphertext: &[u8]) -> Gf128 {
+    ghash_blocks(aad, ciphertext).iter().fold(Gf128::zero(), |acc, &x| (acc + x) * h)
+}
+
+/// Return the counter block `J0` for `nonce`, plus `inc`.
+fn counter_block(nonce: &[u8; 12], inc: u8) -> [u8; 16] {
+    let mut block = [0; 16];
+    block[..12].copy_from_slice(nonce);
+    block[15] = 1 + inc;
+    block
+}
+
+/// Return the value `E(K, J0)` that is added to GHASH to give the
+/// tag of a message with `nonce`.
+pub fn tag_mask(key: &AesKey, nonce: &[u8; 12]) -> Gf128 {
+    let mut mask = [0; 16];
+    aes::encrypt(key, &counter_block(nonce, 0), &mut mask);
+    Gf128::from_block(&mask)
+}
+
+/// Compute the authentication tag for `aad` and `ciphertext`.
+fn tag(key: &AesKey, nonce: &[u8; 12], aad: &[u8], ciphertext: &[u8]) -> [u8; 16] {
+    let s = ghash(auth_key(key), aad, ciphertext) + tag_mask(key, nonce);
+    s.to_block()
+}
+
+/// Encrypt `plaintext` and authenticate it together with the
+/// additional data `aad`.  Returns the ciphertext and the tag.
+///
+/// The keystream starts at counter block `J0 + 1`.  Since the plain
+/// CTR mode of `aes` counts in the low 64 bits of the block, and GCM
+/// messages are limited to less than 2^32 blocks, the 32-bit counter
+/// of GCM never overflows and the keystreams agree.
+pub fn encrypt(key: &AesKey, nonce: &[u8; 12], aad: &[u8], plaintext: &[u8]) -> (Vec<u8>, [u8; 16]) {
+    let ciphertext = aes::encrypt_ctr(key, &counter_block(nonce, 1), plaintext);
+    let t = tag(key, nonce, aad, &ciphertext);
+    (ciphertext, t)
+}
+
+/// Check the tag of `ciphertext` and `aad` and decrypt the ciphertext.
+/// Returns an error if the tag does not match.
+///
+/// The tag may be truncated to its first bytes, which is allowed by
+/// the standard down to 32 bits, but makes forgeries much easier
+/// (challenge 64).
+pub fn decrypt(key: &AesKey, nonce: &[u8; 12], aad: &[u8], ciphertext: &[u8], t: &[u8])
+               -> Result<Vec<u8>> {
+    if t.is_empty() || t.len() > 16 || !hmac::verify(&tag(key, nonce, aad, ciphertext)[..t.len()], t) {
+        return Err(Error::InvalidTag);
+    }
+    Ok(aes::decrypt_ctr(key, &counter_block(nonce, 1), ciphertext))
+}
+
+/// AES-GCM with a fixed key, as an `Aead`.
+pub struct Gcm {
+    key: AesKey,
+}
+
+impl Gcm {
+    /// Create an AEAD instance for `key`.
+    pub fn new(key: AesKey) -> Gcm {
+        Gcm { key: key }
+    }
+}
+
+impl Aead for Gcm {
+    const NONCE_SIZE: usize = 12;
+    const TAG_SIZE: usize = 16;
+
+    fn seal(&self, nonce: &[u8], aad: &[u8], plaintext: &[u8]) -> Vec<u8> {
+        let (mut sealed, t) = encrypt(&self.key, &nonce_array(nonce), aad, plaintext);
+        sealed.extend_from_slice(&t);
+        sealed
+    }
+
+    fn open(&self, nonce: &[u8], aad: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>> {
+        let nonce = nonce_array(nonce);
+        if ciphertext.len() < Self::TAG_SIZE {
+            return Err(Error::InvalidTag);
+        }
+        let (c, t) = ciphertext.split_at(ciphertext.len() - Self::TAG_SIZE);
+        decrypt(&self.key, &nonce, aad, c, t)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{encrypt, decrypt, auth_key};
+    use crate::cipher::aes::{AesKey, AesKey128};
+    use crate::codec::hex;
+
+    fn key(s: &str) -> AesKey {
+        let mut k = [0; 16];
+        k.copy_from_slice(&hex::decode(s).unwrap());
+        AesKey::Key128(AesKey128 { key: k })
+    }
+
+    fn nonce(s: &str) -> [u8; 12] {
+        let mut n = [0; 12];
+        n.copy_from_slice(&hex::decode(s).unwrap());
+        n
+    }
+
+    // Test cases 1 to 4 from the GCM specification.
+
+    #[test]
+    fn gcm_empty() {
+        let k = key("00000000000000000000000000000000");
+        let (c, t) = encrypt(&k, &[0; 12], b"", b"");
+        assert!(c.is_empty());
+        assert_eq!("58e2fccefa7e3061367f1d57a4e7455a", hex::encode(&t));
+        assert_eq!("66e94bd4ef8a2c3b884cfa59ca342b2e", hex::encode(&auth_key(&k).to_block()));
+    }
+
+    #[test]
+    fn gcm_zero_block() {
+        let k = key("00000000000000000000000000000000");
+        let (c, t) = encrypt(&k, &[0; 12], b"", &[0; 16]);
+        assert_eq!("0388dace60b6a392f328c2b971b2fe78", hex::encode(&c));
+        assert_eq!("ab6e47d42cec13bdf53a67b21257bddf", hex::encode(&t));
+    }
+
+    static P: &'static str =
+        "d9313225f88406e5a55909c5aff5269a86a7a9531534f7da2e4c303d8a318a72\
+         1c3c0c95956809532fcf0e2449a6b525b16aedf5aa0de657ba637b391aafd255";
+    static C: &'static str =
+        "42831ec2217774244b7221b784d0d49ce3aa212f2c02a4e035c17e2329aca12e\
+         21d514b25466931c7d8f6a5aac84aa051ba30b396a0aac973d58e091473f5985";
+
+    #[test]
+    fn gcm_four_blocks() {
+        let k = key("feffe9928665731c6d6a8f9467308308");
+        let n = nonce("cafebabefacedbaddecaf888");
+        let (c, t) = encrypt(&k, &n, b"", &hex::decode(P).unwrap());
+        assert_eq!(C, hex::encode(&c));
+        assert_eq!("4d5c2af327cd64a62cf35abd2ba6fab4", hex::encode(&t));
+        assert_eq!(hex::decode(P).unwrap(), decrypt(&k, &n, b"", &c, &t).unwrap());
+        assert!(decrypt(&k, &n, b"", &c, &t[..4]).is_ok());
+        assert!(decrypt(&k, &n, b"", &c, &t[1..5]).is_err());
+        assert!(decrypt(&k, &n, b"", &c, b"").is_err());
+    }
+
+    #[test]
+    fn gcm_aad() {
+        let k = key("feffe9928665731c6d6a8f9467308308");
+        let n = nonce("cafebabefacedbaddecaf888");
+        let aad = hex::decode("feedfacedeadbeeffeedfacedeadbeefabaddad2").unwrap();
+        let p = hex::decode(&P[..120]).unwrap();
+        let (c, t) = encrypt(&k, &n, &aad, &p);
+        assert_eq!(&C[..120], hex::encode(&c));
+        assert_eq!("5bc94fbc3221a5db94fae95ae7121a47", hex::encode(&t));
+        assert_eq!(p, decrypt(&k, &n, &aad, &c, &t).unwrap());
+        assert!(decrypt(&k, &n, b"", &c, &t).is_err());
+        let mut bad = c.clone();
+        bad[0] ^= 1;
+        assert!(decrypt(&k, &n, &aad, &bad, &t).is_err());
+    }
+}
@@ -7,3 +7,52 @@
 //! for learning.  Do not use them for production!
 
 pub mod aes;
+pub mod block;
+pub mod ccm;
+pub mod chacha20;
+pub mod chacha20poly1305;
+pub mod envelope;
+pub mod gcm;
+pub mod nonce;
+pub mod siv;
+pub mod testvectors;
+pub mod vigenere;
+pub mod xorstream;
+
+use crate::error::Result;
+
+/// Common interface of the authenticated encryption modes, so that
+/// attacks and misuse demonstrations can be written once for all of
+/// them.  The sealed message is the ciphertext with the tag appended.
+pub trait Aead {
+    /// Size of the nonce, in bytes.
+    const NONCE_SIZE: usize;
+    /// Size of the authentication tag, in bytes.
+    const TAG_SIZE: usize;
+
+    /// Encrypt `plaintext` and authenticate it together with the
+    /// additional data `aad`.
+    ///
+    /// # Panics
+    /// Panics if `nonce` is not `NONCE_SIZE` bytes long.
+    fn seal(&self, nonce: &[u8], aad: &[u8], plaintext: &[u8]) -> Vec<u8>;
+
+    /// Check the tag of `ciphertext` and `aad`, and decrypt the
+    /// ciphertext.  Returns `Error::InvalidTag` if the tag does not
+    /// match.
+    ///
+    /// # Panics
+    /// Panics if `nonce` is not `NONCE_SIZE` bytes long.
+    fn open(&self, nonce: &[u8], aad: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>>;
+}
+
+/// Convert the nonce of an `Aead` with 12-byte nonces to an array.
+///
+/// # Panics
+/// Panics if `nonce` is not 12 bytes long.
+fn nonce_array(nonce: &[u8]) -> [u8; 12] {
+    assert!(nonce.len() == 12, "nonce must be 12 bytes long");
+    let mut n = [0; 12];
+    n.copy_from_slice(nonce);
+    n
+}
@@ -0,0 +1,228 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! Nonce management.  Stream ciphers and the AEAD modes built on them
+//! lose all confidentiality when a nonce is used twice with the same
+//! key, which is what the attacks in `attack::stream` and
+//! `attack::two_time_pad` exploit.  A `NonceSequence` remembers the
+//! nonces used under a key and refuses to hand out or accept one
+//! twice, and `Ctr` uses it for AES in CTR mode.
+//!
+//! The attack demonstrations need to reuse nonces on purpose, which
+//! `unsafe_allow_reuse` permits.  Do not use this for production!
+
+use std::collections::HashSet;
+
+use crate::cipher::aes::{self, AesKey};
+use crate::error::{Error, Result};
+
+/// The nonces used under a single key.  Fresh nonces are generated
+/// from a counter, encoded in big-endian format, and nonces chosen by
+/// the caller can be registered with `claim`.
+#[derive(Clone, Debug)]
+pub struct NonceSequence {
+    size: usize,
+    counter: Option<u128>,
+    used: HashSet<Vec<u8>>,
+    allow_reuse: bool,
+}
+
+impl NonceSequence {
+    /// Create a sequence of nonces that are `size` bytes long.
+    ///
+    /// # Panics
+    /// Panics if `size` is zero or larger than 16.
+    pub fn new(size: usize) -> NonceSequence {
+        assert!(size > 0 && size <= 16, "nonce size must be between 1 and 16 bytes");
+        NonceSequence {
+            size: size,
+            counter: Some(0),
+            used: HashSet::new(),
+            allow_reuse: false,
+        }
+    }
+
+    /// Accept nonces that have been used before instead of returning
+    /// `Error::NonceReuse`.  This is only for demonstrating attacks.
+    pub fn unsafe_allow_reuse(mut self) -> NonceSequence {
+        self.allow_reuse = true;
+        self
+    }
+
+    /// Return true if `unsafe_allow_reuse` was called.
+    pub fn allows_reuse(&self) -> bool {
+        self.allow_reuse
+    }
+
+    /// Size of the nonces, in bytes.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Number of distinct nonces used so far.
+    pub fn used(&self) -> usize {
+        self.used.len()
+    }
+
+    /// Return true if `nonce` has been used.
+    pub fn is_used(&self, nonce: &[u8]) -> bool {
+        self.used.contains(nonce)
+    }
+
+    /// Return the next nonce that has not been used yet, and mark it
+    /// as used.  Returns `Error::NonceReuse` once all nonces of the
+    /// size have been used.
+    pub fn fresh(&mut self) -> Result<Vec<u8>> {
+        while let Some(counter) = self.counter {
+            self.counter = counter.checked_add(1);
+            if self.size < 16 && counter >> (8 * self.size) != 0 {
+                self.counter = None;
+                break;
+            }
+            let nonce = counter.to_be_bytes()[16 - self.size..].to_vec();
+            if self.used.insert(nonce.clone()) {
+                return Ok(nonce);
+            }
+        }
+        Err(Error::NonceReuse)
+    }
+
+    /// Mark `nonce` as used.  Returns `Error::NonceReuse` if it has
+    /// been used before, unless reuse is allowed, and
+    /// `Error::InvalidIvLength` if it does not have the right size.
+    pub fn claim(&mut self, nonce: &[u8]) -> Result<()> {
+        if nonce.len() != self.size {
+            return Err(Error::InvalidIvLength(nonce.len()));
+        }
+        if !self.used.insert(nonce.to_vec()) && !self.allow_reuse {
+            return Err(Error::NonceReuse);
+        }
+        Ok(())
+    }
+}
+
+/// AES in CTR mode with a fixed key, which never encrypts two
+/// messages with the same nonce.  The IV is an 8-byte nonce followed
+/// by an 8-byte initial counter value, as in `aes::encrypt_ctr`.
+/// Because the counter wraps around without touching the nonce, all
+/// IVs with the same nonce share a keystream, so only the nonce is
+/// checked for reuse.
+pub struct Ctr {
+    key: AesKey,
+    nonces: NonceSequence,
+}
+
+impl Ctr {
+    /// Create an instance for `key` with no nonces used yet.
+    pub fn new(key: AesKey) -> Ctr {
+        Ctr {
+            key: key,
+            nonces: NonceSequence::new(8),
+        }
+    }
+
+    /// Allow nonces to be reused.  This is only for demonstrating
+    /// attacks.
+    pub fn unsafe_allow_reuse(mut self) -> Ctr {
+        self.nonces = self.nonces.unsafe_allow_reuse();
+        self
+    }
+
+    /// Return the nonces used so far.
+    pub fn nonces(&self) -> &NonceSequence {
+        &self.nonces
+    }
+
+    /// Encrypt `plaintext` with a fresh nonce and a counter starting
+    /// at zero.  Returns the IV and the ciphertext.
+    pub fn encrypt(&mut self, plaintext: &[u8]) -> Result<([u8; 16], Vec<u8>)> {
+        let nonce = self.nonces.fresh()?;
+        let mut iv = [0; 16];
+        iv[..8].copy_from_slice(&nonce);
+        Ok((iv, aes::encrypt_ctr(&self.key, &iv, plaintext)))
+    }
+
+    /// Encrypt `plaintext` with the IV `iv` chosen by the caller.
+    /// Returns `Error::NonceReuse` if its nonce has been used before.
+    pub fn encrypt_with_iv(&mut self, iv: &[u8; 16], plaintext: &[u8]) -> Result<Vec<u8>> {
+        self.nonces.claim(&iv[..8])?;
+        Ok(aes::encrypt_ctr(&self.key, iv, plaintext))
+    }
+
+    /// Decrypt `ciphertext`, which was encrypted with `iv`.
+    /// Decryption does not use up the nonce.
+    pub fn decrypt(&self, iv: &[u8; 16], ciphertext: &[u8]) -> Vec<u8> {
+        aes::decrypt_ctr(&self.key, iv, ciphertext)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Ctr, NonceSequence};
+    use crate::cipher::aes::AesKey;
+    use crate::error::Error;
+
+    #[test]
+    fn sequence() {
+        let mut s = NonceSequence::new(4);
+        assert_eq!(vec![0, 0, 0, 0], s.fresh().unwrap());
+        s.claim(&[0, 0, 0, 1]).unwrap();
+        assert_eq!(vec![0, 0, 0, 2], s.fresh().unwrap());
+        assert_eq!(3, s.used());
+        assert!(s.is_used(&[0, 0, 0, 1]));
+        match s.claim(&[0, 0, 0, 2]) {
+            Err(Error::NonceReuse) => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+        match s.claim(&[0, 0, 3]) {
+            Err(Error::InvalidIvLength(3)) => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn exhausted() {
+        let mut s = NonceSequence::new(1);
+        s.claim(&[0x80]).unwrap();
+        for _ in 0..255 {
+            s.fresh().unwrap();
+        }
+        assert_eq!(256, s.used());
+        assert!(s.fresh().is_err());
+        assert!(NonceSequence::new(16).fresh().is_ok());
+    }
+
+    #[test]
+    fn allow_reuse() {
+        let mut s = NonceSequence::new(2).unsafe_allow_reuse();
+        assert!(s.allows_reuse());
+        s.claim(&[1, 2]).unwrap();
+        s.claim(&[1, 2]).unwrap();
+        assert_eq!(1, s.used());
+        assert_eq!(vec![0, 0], s.fresh().unwrap());
+    }
+
+    #[test]
+    fn ctr() {
+        let mut ctr = Ctr::new(AesKey::from([0x42; 16]));
+        let (iv1, c1) = ctr.encrypt(b"attack at dawn").unwrap();
+        let (iv2, c2) = ctr.encrypt(b"attack at dawn").unwrap();
+        assert!(iv1 != iv2 && c1 != c2);
+        assert_eq!(b"attack at dawn".to_vec(), ctr.decrypt(&iv2, &c2));
+
+        // A different initial counter does not make the nonce fresh.
+        let mut iv = iv1;
+        iv[15] = 1;
+        match ctr.encrypt_with_iv(&iv, b"retreat") {
+            Err(Error::NonceReuse) => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+        iv[0] = 1;
+        assert!(ctr.encrypt_with_iv(&iv, b"retreat").is_ok());
+        assert_eq!(3, ctr.nonces().used());
+
+        let mut ctr = Ctr::new(AesKey::from([0x42; 16])).unsafe_allow_reuse();
+        assert_eq!(ctr.encrypt_with_iv(&[0; 16], b"same keystream").unwrap(),
+                   ctr.encrypt_with_iv(&[0; 16], b"same keystream").unwrap());
+    }
+}
@@ -0,0 +1,216 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! AES in Synthetic Initialization Vector mode (SIV), as specified in
+//! RFC 5297.  The key is split into a MAC key and a CTR key.  The
+//! synthetic IV `V` is computed by S2V, a CMAC-based PRF over the
+//! additional data, the nonce and the plaintext, and then serves as
+//! both the authentication tag and the initial counter block.
+//!
+//! Unlike GCM and CCM, reusing a nonce does not reuse a keystream:
+//! the keystream depends on the plaintext, so nonce reuse only
+//! reveals whether two messages are identical (see `attack::aead`).
+//! Do not use this for production!
+
+use crate::cipher::aes::{Aes, AesKey};
+use crate::cipher::block::BlockCipher;
+use crate::cipher::Aead;
+use crate::error::{Error, Result};
+use crate::mac::cmac::{cmac, dbl};
+use crate::mac::hmac;
+
+/// Compute S2V over `strings` with `cipher`.  The last string is
+/// treated specially: it is XOR-ed into the end of the accumulated
+/// value if it is at least a block long, and padded and combined with
+/// a doubled accumulator otherwise.  With no strings, the result is
+/// the CMAC of the block `0...01`.
+pub fn s2v<C: BlockCipher<16> + ?Sized>(cipher: &C, strings: &[&[u8]]) -> [u8; 16] {
+    let (last, init) = match strings.split_last() {
+        Some(s) => s,
+        None => {
+            let mut one = [0; 16];
+            one[15] = 1;
+            return cmac(cipher, &one);
+        }
+    };
+
+    let mut d = cmac(cipher, &[0; 16]);
+    for s in init {
+        let m = cmac(cipher, s);
+        d = dbl(&d);
+        for x in 0..16 {
+            d[x] ^= m[x];
+        }
+    }
+
+    if last.len() >= 16 {
+        let mut t = last.to_vec();
+        let offset = t.len() - 16;
+        for x in 0..16 {
+            t[offset + x] ^= d[x];
+        }
+        cmac(cipher, &t)
+    } else {
+        let mut t = dbl(&d);
+        for (x, b) in last.iter().enumerate() {
+            t[x] ^= b;
+        }
+        t[last.len()] ^= 0x80;
+        cmac(cipher, &t)
+    }
+}
+
+/// Encrypt or decrypt `input` in CTR mode starting with the synthetic
+/// IV `v`.  Two bits of `v` are cleared first, so that implementations
+/// with 32-bit or 64-bit counters agree, and the counter is then
+/// incremented as a 128-bit big-endian number.
+fn ctr<C: BlockCipher<16> + ?Sized>(cipher: &C, v: &[u8; 16], input: &[u8]) -> Vec<u8> {
+    let mut q = *v;
+    q[8] &= 0x7f;
+    q[12] &= 0x7f;
+    let mut counter = u128::from_be_bytes(q);
+    let mut output = Vec::with_capacity(input.len());
+    for chunk in input.chunks(16) {
+        let mut keystream = counter.to_be_bytes();
+        cipher.encrypt_block(&mut keystream);
+        output.extend(chunk.iter().zip(keystream.iter()).map(|(a, b)| a ^ b));
+        counter = counter.wrapping_add(1);
+    }
+    output
+}
+
+/// AES-SIV with a fixed key.
+pub struct Siv {
+    mac: Aes,
+    enc: Aes,
+}
+
+impl Siv {
+    /// Create an instance for `key`, which must be 32, 48 or 64 bytes
+    /// long, for AES-128, AES-192 or AES-256.  The first half is the
+    /// S2V key, the second half the CTR key.  Other lengths result in
+    /// `Error::InvalidKeyLength`.
+    pub fn new(key: &[u8]) -> Result<Siv> {
+        if key.len() % 2 != 0 {
+            return Err(Error::InvalidKeyLength(key.len()));
+        }
+        let (k1, k2) = key.split_at(key.len() / 2);
+        let k1 = AesKey::from_slice(k1).map_err(|_| Error::InvalidKeyLength(key.len()))?;
+        let k2 = AesKey::from_slice(k2).map_err(|_| Error::InvalidKeyLength(key.len()))?;
+        Ok(Siv {
+            mac: Aes::with_key(&k1),
+            enc: Aes::with_key(&k2),
+        })
+    }
+
+    /// Compute S2V with the MAC key over the additional data
+    /// components `ad`, followed by `plaintext`.  This is the
+    /// synthetic IV, and the tag, of the message.
+    pub fn s2v(&self, ad: &[&[u8]], plaintext: &[u8]) -> [u8; 16] {
+        let mut strings = ad.to_vec();
+        strings.push(plaintext);
+        s2v(&self.mac, &strings)
+    }
+
+    /// Encrypt `plaintext` and authenticate it together with the
+    /// additional data components `ad`.  A nonce, if any, is passed as
+    /// the last component of `ad`.  Returns the synthetic IV and the
+    /// ciphertext.
+    pub fn encrypt(&self, ad: &[&[u8]], plaintext: &[u8]) -> ([u8; 16], Vec<u8>) {
+        let v = self.s2v(ad, plaintext);
+        (v, ctr(&self.enc, &v, plaintext))
+    }
+
+    /// Decrypt `ciphertext` with the synthetic IV `v` and check it
+    /// together with `ad`.  Returns `Error::InvalidTag` if the IV does
+    /// not match.
+    pub fn decrypt(&self, ad: &[&[u8]], v: &[u8; 16], ciphertext: &[u8]) -> Result<Vec<u8>> {
+        let plaintext = ctr(&self.enc, v, ciphertext);
+        if !hmac::verify(&self.s2v(ad, &plaintext), v) {
+            return Err(Error::InvalidTag);
+        }
+        Ok(plaintext)
+    }
+}
+
+/// As an `Aead`, the nonce is the last additional data component after
+/// `aad`, and the synthetic IV is appended to the ciphertext like the
+/// tags of the other modes.  RFC 5297 puts it in front instead.
+impl Aead for Siv {
+    const NONCE_SIZE: usize = 12;
+    const TAG_SIZE: usize = 16;
+
+    fn seal(&self, nonce: &[u8], aad: &[u8], plaintext: &[u8]) -> Vec<u8> {
+        assert!(nonce.len() == Self::NONCE_SIZE, "nonce must be 12 bytes long");
+        let (v, mut sealed) = self.encrypt(&[aad, nonce], plaintext);
+        sealed.extend_from_slice(&v);
+        sealed
+    }
+
+    fn open(&self, nonce: &[u8], aad: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>> {
+        assert!(nonce.len() == Self::NONCE_SIZE, "nonce must be 12 bytes long");
+        if ciphertext.len() < Self::TAG_SIZE {
+            return Err(Error::InvalidTag);
+        }
+        let (c, t) = ciphertext.split_at(ciphertext.len() - Self::TAG_SIZE);
+        let mut v = [0; 16];
+        v.copy_from_slice(t);
+        self.decrypt(&[aad, nonce], &v, c)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Siv;
+    use crate::codec::hex;
+    use crate::error::Error;
+
+    // Examples from RFC 5297, appendix A.
+
+    #[test]
+    fn siv_deterministic() {
+        let siv = Siv::new(&hex::decode("fffefdfcfbfaf9f8f7f6f5f4f3f2f1f0\
+                                         f0f1f2f3f4f5f6f7f8f9fafbfcfdfeff").unwrap()).unwrap();
+        let ad = hex::decode("101112131415161718191a1b1c1d1e1f2021222324252627").unwrap();
+        let p = hex::decode("112233445566778899aabbccddee").unwrap();
+        let (v, c) = siv.encrypt(&[&ad], &p);
+        assert_eq!("85632d07c6e8f37f950acd320a2ecc93", hex::encode(&v));
+        assert_eq!("40c02b9690c4dc04daef7f6afe5c", hex::encode(&c));
+        assert_eq!(p, siv.decrypt(&[&ad], &v, &c).unwrap());
+        assert!(siv.decrypt(&[], &v, &c).is_err());
+    }
+
+    #[test]
+    fn siv_nonce_based() {
+        let siv = Siv::new(&hex::decode("7f7e7d7c7b7a79787776757473727170\
+                                         404142434445464748494a4b4c4d4e4f").unwrap()).unwrap();
+        let ad1 = hex::decode("00112233445566778899aabbccddeeffdeaddadadeaddadaffeeddccbbaa99887766554433221100").unwrap();
+        let ad2 = hex::decode("102030405060708090a0").unwrap();
+        let nonce = hex::decode("09f911029d74e35bd84156c5635688c0").unwrap();
+        let p = hex::decode("7468697320697320736f6d6520706c61696e7465787420746f20656e6372797074207573696e67205349562d414553").unwrap();
+        let (v, c) = siv.encrypt(&[&ad1, &ad2, &nonce], &p);
+        assert_eq!("7bdb6e3b432667eb06f4d14bff2fbd0f", hex::encode(&v));
+        assert_eq!("cb900f2fddbe404326601965c889bf17dba77ceb094fa663b7a3f748ba8af829ea64ad544a272e9c485b62a3fd5c0d",
+                   hex::encode(&c));
+        assert_eq!(p, siv.decrypt(&[&ad1, &ad2, &nonce], &v, &c).unwrap());
+
+        let mut bad = c.clone();
+        bad[0] ^= 1;
+        match siv.decrypt(&[&ad1, &ad2, &nonce], &v, &bad) {
+            Err(Error::InvalidTag) => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn siv_key_lengths() {
+        assert!(Siv::new(&[0; 32]).is_ok());
+        assert!(Siv::new(&[0; 48]).is_ok());
+        assert!(Siv::new(&[0; 64]).is_ok());
+        match Siv::new(&[0; 16]) {
+            Err(Error::InvalidKeyLength(16)) => (),
+            r => panic!("unexpected result: {:?}", r.is_ok()),
+        }
+        assert!(Siv::new(&[0; 33]).is_err());
+    }
+}
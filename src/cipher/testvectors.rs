@@ -0,0 +1,204 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! Known-answer test vectors for AES, from FIPS-197 for the block
+//! cipher and from NIST SP 800-38A for the cipher modes, for all
+//! three key sizes.  All values are hex strings.  The mode vectors
+//! have no padding, so they must be checked with
+//! `padding::NoPadding`.
+
+/// Known-answer test for a single block.
+#[derive(Clone, Copy, Debug)]
+pub struct BlockVector {
+    /// Key.
+    pub key: &'static str,
+    /// Plaintext block.
+    pub plaintext: &'static str,
+    /// Ciphertext block.
+    pub ciphertext: &'static str,
+}
+
+/// Block cipher modes covered by `SP_800_38A`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode {
+    /// Electronic codebook mode.
+    Ecb,
+    /// Cipher block chaining mode.
+    Cbc,
+    /// Counter mode.
+    Ctr,
+}
+
+/// Known-answer test for a block cipher mode.
+#[derive(Clone, Copy, Debug)]
+pub struct ModeVector {
+    /// Cipher mode.
+    pub mode: Mode,
+    /// Key.
+    pub key: &'static str,
+    /// Initialization vector for CBC, initial counter block for CTR,
+    /// and empty for ECB.
+    pub iv: &'static str,
+    /// Plaintext, four blocks.
+    pub plaintext: &'static str,
+    /// Ciphertext, four blocks.
+    pub ciphertext: &'static str,
+}
+
+/// The examples from appendix C of FIPS-197, for 128, 192 and 256-bit
+/// keys.
+pub static FIPS_197: [BlockVector; 3] = [
+    BlockVector {
+        key: "000102030405060708090a0b0c0d0e0f",
+        plaintext: "00112233445566778899aabbccddeeff",
+        ciphertext: "69c4e0d86a7b0430d8cdb78070b4c55a",
+    },
+    BlockVector {
+        key: "000102030405060708090a0b0c0d0e0f1011121314151617",
+        plaintext: "00112233445566778899aabbccddeeff",
+        ciphertext: "dda97ca4864cdfe06eaf70a0ec0d7191",
+    },
+    BlockVector {
+        key: "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f",
+        plaintext: "00112233445566778899aabbccddeeff",
+        ciphertext: "8ea2b7ca516745bfeafc49904b496089",
+    },
+];
+
+const KEY_128: &'static str = "2b7e151628aed2a6abf7158809cf4f3c";
+const KEY_192: &'static str = "8e73b0f7da0e6452c810f32b809079e562f8ead2522c6b7b";
+const KEY_256: &'static str = "603deb1015ca71be2b73aef0857d77811f352c073b6108d72d9810a30914dff4";
+
+const CBC_IV: &'static str = "000102030405060708090a0b0c0d0e0f";
+const CTR_IV: &'static str = "f0f1f2f3f4f5f6f7f8f9fafbfcfdfeff";
+
+const PLAINTEXT: &'static str =
+    "6bc1bee22e409f96e93d7e117393172aae2d8a571e03ac9c9eb76fac45af8e51\
+     30c81c46a35ce411e5fbc1191a0a52eff69f2445df4f9b17ad2b417be66c3710";
+
+/// The examples from appendix F of NIST SP 800-38A for ECB, CBC and
+/// CTR mode, each with 128, 192 and 256-bit keys.
+pub static SP_800_38A: [ModeVector; 9] = [
+    ModeVector {
+        mode: Mode::Ecb,
+        key: KEY_128,
+        iv: "",
+        plaintext: PLAINTEXT,
+        ciphertext: "3ad77bb40d7a3660a89ecaf32466ef97f5d3d58503b9699de785895a96fdbaaf\
+                     43b1cd7f598ece23881b00e3ed0306887b0c785e27e8ad3f8223207104725dd4",
+    },
+    ModeVector {
+        mode: Mode::Ecb,
+        key: KEY_192,
+        iv: "",
+        plaintext: PLAINTEXT,
+        ciphertext: "bd334f1d6e45f25ff712a214571fa5cc974104846d0ad3ad7734ecb3ecee4eef\
+                     ef7afd2270e2e60adce0ba2face6444e9a4b41ba738d6c72fb16691603c18e0e",
+    },
+    ModeVector {
+        mode: Mode::Ecb,
+        key: KEY_256,
+        iv: "",
+        plaintext: PLAINTEXT,
+        ciphertext: "f3eed1bdb5d2a03c064b5a7e3db181f8591ccb10d410ed26dc5ba74a31362870\
+                     b6ed21b99ca6f4f9f153e7b1beafed1d23304b7a39f9f3ff067d8d8f9e24ecc7",
+    },
+    ModeVector {
+        mode: Mode::Cbc,
+        key: KEY_128,
+        iv: CBC_IV,
+        plaintext: PLAINTEXT,
+        ciphertext: "7649abac8119b246cee98e9b12e9197d5086cb9b507219ee95db113a917678b2\
+                     73bed6b8e3c1743b7116e69e222295163ff1caa1681fac09120eca307586e1a7",
+    },
+    ModeVector {
+        mode: Mode::Cbc,
+        key: KEY_192,
+        iv: CBC_IV,
+        plaintext: PLAINTEXT,
+        ciphertext: "4f021db243bc633d7178183a9fa071e8b4d9ada9ad7dedf4e5e738763f69145a\
+                     571b242012fb7ae07fa9baac3df102e008b0e27988598881d920a9e64f5615cd",
+    },
+    ModeVector {
+        mode: Mode::Cbc,
+        key: KEY_256,
+        iv: CBC_IV,
+        plaintext: PLAINTEXT,
+        ciphertext: "f58c4c04d6e5f1ba779eabfb5f7bfbd69cfc4e967edb808d679f777bc6702c7d\
+                     39f23369a9d9bacfa530e26304231461b2eb05e2c39be9fcda6c19078c6a9d1b",
+    },
+    ModeVector {
+        mode: Mode::Ctr,
+        key: KEY_128,
+        iv: CTR_IV,
+        plaintext: PLAINTEXT,
+        ciphertext: "874d6191b620e3261bef6864990db6ce9806f66b7970fdff8617187bb9fffdff\
+                     5ae4df3edbd5d35e5b4f09020db03eab1e031dda2fbe03d1792170a0f3009cee",
+    },
+    ModeVector {
+        mode: Mode::Ctr,
+        key: KEY_192,
+        iv: CTR_IV,
+        plaintext: PLAINTEXT,
+        ciphertext: "1abc932417521ca24f2b0459fe7e6e0b090339ec0aa6faefd5ccc2c6f4ce8e94\
+                     1e36b26bd1ebc670d1bd1d665620abf74f78a7f6d29809585a97daec58c6b050",
+    },
+    ModeVector {
+        mode: Mode::Ctr,
+        key: KEY_256,
+        iv: CTR_IV,
+        plaintext: PLAINTEXT,
+        ciphertext: "601ec313775789a5b7a7f504bbf3d228f443e3ca4d62b59aca84e990cacaf5c5\
+                     2b0930daa23de94ce87017ba2d84988ddfc9c58db67aada613c2dd08457941a6",
+    },
+];
+
+#[cfg(test)]
+mod tests {
+    use super::{FIPS_197, SP_800_38A, Mode};
+    use crate::cipher::aes::{self, AesKey};
+    use crate::codec::hex;
+    use crate::padding::NoPadding;
+
+    fn block(s: &str) -> [u8; 16] {
+        let mut b = [0; 16];
+        b.copy_from_slice(&hex::decode(s).unwrap());
+        b
+    }
+
+    #[test]
+    fn fips_197() {
+        for v in FIPS_197.iter() {
+            let key = AesKey::from_hex(v.key).unwrap();
+            let mut output = [0; 16];
+            aes::encrypt(&key, &block(v.plaintext), &mut output);
+            assert_eq!(block(v.ciphertext), output);
+            aes::decrypt(&key, &block(v.ciphertext), &mut output);
+            assert_eq!(block(v.plaintext), output);
+        }
+    }
+
+    #[test]
+    fn sp_800_38a() {
+        for v in SP_800_38A.iter() {
+            let key = AesKey::from_hex(v.key).unwrap();
+            let plaintext = hex::decode(v.plaintext).unwrap();
+            let ciphertext = hex::decode(v.ciphertext).unwrap();
+            let (encrypted, decrypted) = match v.mode {
+                Mode::Ecb => (aes::encrypt_ecb_with(&key, &plaintext, &NoPadding).unwrap(),
+                              aes::decrypt_ecb_with(&key, &ciphertext, &NoPadding).unwrap()),
+                Mode::Cbc => {
+                    let iv = block(v.iv);
+                    (aes::encrypt_cbc_with(&key, &iv, &plaintext, &NoPadding).unwrap(),
+                     aes::decrypt_cbc_with(&key, &iv, &ciphertext, &NoPadding).unwrap())
+                }
+                Mode::Ctr => {
+                    let iv = block(v.iv);
+                    (aes::encrypt_ctr(&key, &iv, &plaintext), aes::decrypt_ctr(&key, &iv, &ciphertext))
+                }
+            };
+            assert_eq!(ciphertext, encrypted, "{:?} with {}-bit key", v.mode, v.key.len() * 4);
+            assert_eq!(plaintext, decrypted, "{:?} with {}-bit key", v.mode, v.key.len() * 4);
+        }
+    }
+}
@@ -0,0 +1,154 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! The Vigenère cipher, which shifts each letter of the message by
+//! the corresponding letter of a repeating key, and a solver for it.
+//! Unlike `xor::repeating`, only letters are encrypted, and case and
+//! all other characters are preserved.  The key only advances on
+//! letters.
+
+use crate::distance;
+use crate::language;
+
+/// Expected index of coincidence of English text, the probability
+/// that two randomly chosen letters are equal.  For uniformly random
+/// letters, it is 1/26, about 0.038.
+const ENGLISH_IOC: f64 = 0.066;
+
+fn shift(key: &[u8], msg: &[u8], decrypt: bool) -> Vec<u8> {
+    assert!(!key.is_empty(), "empty key");
+    let shifts: Vec<u8> = key.iter()
+        .map(|&k| {
+            assert!(k.is_ascii_alphabetic(), "key must consist of letters");
+            let s = k.to_ascii_uppercase() - b'A';
+            if decrypt { (26 - s) % 26 } else { s }
+        })
+        .collect();
+    let mut i = 0;
+    msg.iter()
+        .map(|&b| {
+            let base = match b {
+                b'A'..=b'Z' => b'A',
+                b'a'..=b'z' => b'a',
+                _ => return b,
+            };
+            let c = base + (b - base + shifts[i % shifts.len()]) % 26;
+            i += 1;
+            c
+        })
+        .collect()
+}
+
+/// Encrypt `msg` with the letters of `key`, where `A` shifts by zero
+/// and `Z` by 25.
+///
+/// # Panics
+/// Panics if `key` is empty or contains anything but letters.
+pub fn encrypt(key: &[u8], msg: &[u8]) -> Vec<u8> {
+    shift(key, msg, false)
+}
+
+/// Decrypt `msg` with the letters of `key`.
+///
+/// # Panics
+/// Panics if `key` is empty or contains anything but letters.
+pub fn decrypt(key: &[u8], msg: &[u8]) -> Vec<u8> {
+    shift(key, msg, true)
+}
+
+/// Return the letters of `msg` as numbers from 0 to 25.
+fn letters(msg: &[u8]) -> Vec<u8> {
+    msg.iter()
+        .filter(|b| b.is_ascii_alphabetic())
+        .map(|b| b.to_ascii_uppercase() - b'A')
+        .collect()
+}
+
+/// Return every `keylen`th letter of `letters`, starting at `column`.
+fn column(letters: &[u8], column: usize, keylen: usize) -> Vec<u8> {
+    letters.iter().skip(column).step_by(keylen).cloned().collect()
+}
+
+/// Estimate the key length of the Vigenère encrypted `msg`, between 1
+/// and `max_keylen`.  For the right length, and its multiples, every
+/// column is English text shifted by a constant, so the index of
+/// coincidence of the columns is close to that of English.  The
+/// shortest length that gets close is returned.
+pub fn estimate_keylen(msg: &[u8], max_keylen: usize) -> usize {
+    let l = letters(msg);
+    let iocs: Vec<f64> = (1..max_keylen + 1)
+        .map(|k| {
+            (0..k).map(|c| distance::index_of_coincidence(&column(&l, c, k))).sum::<f64>() / k as f64
+        })
+        .collect();
+    let best = iocs.iter().cloned().fold(0.0, f64::max);
+    let threshold = best.min(ENGLISH_IOC) * 0.9;
+    iocs.iter().position(|&ioc| ioc >= threshold).map_or(1, |i| i + 1)
+}
+
+/// Crack the Vigenère encrypted `msg`, whose key is assumed to be at
+/// most `max_keylen` letters long and whose plaintext is assumed to
+/// be English.  For each column of the estimated key length, the
+/// shift is chosen whose decryption fits the English letter
+/// frequencies best.  Returns the key in upper case and the
+/// plaintext.
+pub fn crack(msg: &[u8], max_keylen: usize) -> (Vec<u8>, Vec<u8>) {
+    let keylen = estimate_keylen(msg, max_keylen);
+    let l = letters(msg);
+    let freqs: Vec<f64> = (0..26).map(|i| language::english::letter_prob(b'A' + i).unwrap()).collect();
+    let mut key = Vec::with_capacity(keylen);
+    for c in 0..keylen {
+        let column = column(&l, c, keylen);
+        let best = (0..26u8)
+            .map(|s| {
+                let mut counts = [0; 26];
+                for &x in &column {
+                    counts[((x + 26 - s) % 26) as usize] += 1;
+                }
+                (distance::chi_squared(&counts, &freqs), s)
+            })
+            .fold((f64::INFINITY, 0), |a, b| if b.0 < a.0 { b } else { a });
+        key.push(b'A' + best.1);
+    }
+    let plain = decrypt(&key, msg);
+    (key, plain)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{encrypt, decrypt, estimate_keylen, crack};
+
+    static PLAIN: &'static [u8] =
+        b"It was the best of times, it was the worst of times, it was the age of wisdom, \
+          it was the age of foolishness, it was the epoch of belief, it was the epoch of \
+          incredulity, it was the season of Light, it was the season of Darkness, it was \
+          the spring of hope, it was the winter of despair, we had everything before us, \
+          we had nothing before us, we were all going direct to Heaven, we were all going \
+          direct the other way.";
+
+    #[test]
+    fn encrypt_0() {
+        assert_eq!(&b"LXFOPV EF RNHR!"[..], &encrypt(b"lemon", b"ATTACK AT DAWN!")[..]);
+        assert_eq!(&b"Attack at dawn!"[..], &decrypt(b"LEMON", b"Lxfopv ef rnhr!")[..]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn encrypt_bad_key() {
+        encrypt(b"LEM0N", b"ATTACK AT DAWN");
+    }
+
+    #[test]
+    fn crack_0() {
+        let encrypted = encrypt(b"DICKENS", PLAIN);
+        assert_eq!(7, estimate_keylen(&encrypted, 20));
+        assert_eq!((b"DICKENS".to_vec(), PLAIN.to_vec()), crack(&encrypted, 20));
+    }
+
+    quickcheck! {
+        fn prop_decrypt_encrypt(key: Vec<u8>, msg: Vec<u8>) -> bool {
+            let key: Vec<u8> = key.into_iter().map(|k| b'a' + k % 26).collect();
+            key.is_empty() || decrypt(&key, &encrypt(&key, &msg)) == msg
+        }
+    }
+}
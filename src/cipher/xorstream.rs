@@ -0,0 +1,163 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! A rolling XOR cipher with a key schedule derived from a hash
+//! function.  The message is split into blocks of `block_len` bytes,
+//! and block `i` is XOR-ed with the first `block_len` bytes of
+//! `H(key || counter)`, where `counter = i / rekey` is encoded as a
+//! 64-bit big-endian number.
+//!
+//! This sits between `xor::repeating` and AES-CTR: with a huge
+//! `rekey` interval it is repeating-key XOR with a key of `block_len`
+//! bytes, and with `rekey = 1` it is a hash-based counter mode.  The
+//! values in between show where the repeating-key XOR attacks of
+//! `xor` stop working.  Do not use this for production!
+
+use std::marker::PhantomData;
+
+use byteorder::{BigEndian, WriteBytesExt};
+
+use crate::hash::Digest;
+
+/// Rolling XOR cipher with a fixed key, using the hash function `D`.
+#[derive(Clone, Debug)]
+pub struct XorStream<D> {
+    key: Vec<u8>,
+    block_len: usize,
+    rekey: u64,
+    digest: PhantomData<D>,
+}
+
+impl<D: Digest> XorStream<D> {
+    /// Create a cipher for `key` which uses a fresh block key for
+    /// every `D::OUTPUT_SIZE` bytes.
+    pub fn new(key: &[u8]) -> XorStream<D> {
+        XorStream {
+            key: key.to_vec(),
+            block_len: D::OUTPUT_SIZE,
+            rekey: 1,
+            digest: PhantomData,
+        }
+    }
+
+    /// Use blocks of `block_len` bytes.
+    ///
+    /// # Panics
+    /// Panics if `block_len` is zero or larger than `D::OUTPUT_SIZE`.
+    pub fn with_block_len(mut self, block_len: usize) -> XorStream<D> {
+        assert!(block_len > 0 && block_len <= D::OUTPUT_SIZE, "invalid block length");
+        self.block_len = block_len;
+        self
+    }
+
+    /// Derive a new block key only every `rekey` blocks.  Use
+    /// `u64::MAX` to never derive a new one.
+    ///
+    /// # Panics
+    /// Panics if `rekey` is zero.
+    pub fn with_rekey(mut self, rekey: u64) -> XorStream<D> {
+        assert!(rekey > 0, "rekey interval must be positive");
+        self.rekey = rekey;
+        self
+    }
+
+    /// Size of a block, in bytes.
+    pub fn block_len(&self) -> usize {
+        self.block_len
+    }
+
+    /// Return the key for blocks with counter value `counter`.
+    pub fn block_key(&self, counter: u64) -> Vec<u8> {
+        let mut d = D::new();
+        d.update(&self.key);
+        let mut c = Vec::with_capacity(8);
+        c.write_u64::<BigEndian>(counter).unwrap();
+        d.update(&c);
+        let mut k = d.finish();
+        k.truncate(self.block_len);
+        k
+    }
+
+    /// Return the first `len` bytes of the keystream.
+    pub fn keystream(&self, len: usize) -> Vec<u8> {
+        let mut stream = Vec::with_capacity(len);
+        let mut block_key = Vec::new();
+        for i in 0..(len + self.block_len - 1) / self.block_len {
+            let i = i as u64;
+            if i % self.rekey == 0 {
+                block_key = self.block_key(i / self.rekey);
+            }
+            stream.extend_from_slice(&block_key);
+        }
+        stream.truncate(len);
+        stream
+    }
+
+    /// Encrypt or decrypt `msg`, which is the same operation.
+    pub fn apply(&self, msg: &[u8]) -> Vec<u8> {
+        msg.iter().zip(self.keystream(msg.len())).map(|(m, k)| m ^ k).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::XorStream;
+    use crate::data;
+    use crate::hash::Digest;
+    use crate::hash::sha1::Sha1;
+    use crate::hash::sha256::Sha256;
+    use crate::xor;
+
+    #[test]
+    fn block_keys() {
+        let c = XorStream::<Sha256>::new(b"key");
+        assert_eq!(Sha256::digest(b"key\0\0\0\0\0\0\0\0"), c.block_key(0));
+        let mut k = Sha256::digest(b"key\0\0\0\0\0\0\0\x01");
+        assert_eq!(k, c.keystream(64)[32..]);
+        k.truncate(5);
+        assert_eq!(k, XorStream::<Sha256>::new(b"key").with_block_len(5).block_key(1));
+    }
+
+    #[test]
+    fn rekey() {
+        let c = XorStream::<Sha1>::new(b"key").with_block_len(4).with_rekey(3);
+        let ks = c.keystream(30);
+        assert_eq!(30, ks.len());
+        assert_eq!(ks[..4], ks[4..8]);
+        assert_eq!(ks[..4], ks[8..12]);
+        assert!(ks[..4] != ks[12..16]);
+        assert_eq!(c.block_key(1), ks[12..16]);
+        assert_eq!(c.block_key(2)[..2], ks[28..]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn block_len_too_large() {
+        XorStream::<Sha1>::new(b"key").with_block_len(21);
+    }
+
+    /// Repeating-key XOR is broken as long as the key does not change,
+    /// and not once it does.
+    #[test]
+    fn crack_limits() {
+        let c = data::load_base64_file("data/6.txt").unwrap();
+        let plain = xor::repeating(b"Terminator X: Bring the noise", &c);
+
+        let fixed = XorStream::<Sha256>::new(b"secret").with_block_len(7).with_rekey(u64::MAX);
+        let cracked = xor::crack_repeating_xor(&fixed.apply(&plain), 3);
+        assert!(cracked.result.iter().any(|r| r.1 == plain));
+
+        let rolling = XorStream::<Sha256>::new(b"secret").with_block_len(7);
+        let cracked = xor::crack_repeating_xor(&rolling.apply(&plain), 3);
+        assert!(cracked.result.iter().all(|r| r.1 != plain));
+    }
+
+    quickcheck! {
+        fn roundtrip(key: Vec<u8>, msg: Vec<u8>, block_len: u8, rekey: u8) -> bool {
+            let c = XorStream::<Sha1>::new(&key)
+                .with_block_len(block_len as usize % 20 + 1)
+                .with_rekey(rekey as u64 + 1);
+            c.apply(&c.apply(&msg)) == msg
+        }
+    }
+}
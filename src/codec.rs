@@ -2,103 +2,377 @@
 // top-level directory of this distribution for license information.
 
 //! Various encoding and decoding algorithms, to decode readable
-//! strings to byte vectors and vice versa.
+//! strings to byte vectors and vice versa, and a hex dump formatter
+//! for debugging.
 
-/// Standard BASE64 encoding.
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+/// BASE64 encoding, with the standard and the URL-safe alphabet.
 pub mod base64 {
-    use ::error;
+    use std::io::{self, Read, Write};
+
+    use crate::error;
     
     static BASE64_CHARS: &'static [u8; 64] =
         b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
 
+    static BASE64_URL_CHARS: &'static [u8; 64] =
+        b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+    /// Characters used for the digits 62 and 63.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum Alphabet {
+        /// `+` and `/`, as in RFC 4648, section 4.
+        Standard,
+        /// `-` and `_`, which are safe in URLs and file names (RFC
+        /// 4648, section 5).
+        UrlSafe,
+    }
+
+    impl Alphabet {
+        fn chars(self) -> &'static [u8; 64] {
+            match self {
+                Alphabet::Standard => BASE64_CHARS,
+                Alphabet::UrlSafe => BASE64_URL_CHARS,
+            }
+        }
+    }
+
+    /// Variant of BASE64 to encode or decode.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct Config {
+        /// Alphabet to use.
+        pub alphabet: Alphabet,
+        /// When encoding, whether to pad the output with `=` to a
+        /// multiple of four characters.  When decoding, padding is
+        /// always accepted, but a missing one only if this is false.
+        pub pad: bool,
+        /// When decoding, whether to skip whitespace instead of
+        /// rejecting it.
+        pub ignore_whitespace: bool,
+    }
+
+    /// Standard alphabet with padding, ignoring whitespace.
+    pub const STANDARD: Config = Config {
+        alphabet: Alphabet::Standard,
+        pad: true,
+        ignore_whitespace: true,
+    };
+
+    /// URL-safe alphabet with padding.
+    pub const URL_SAFE: Config = Config {
+        alphabet: Alphabet::UrlSafe,
+        pad: true,
+        ignore_whitespace: false,
+    };
+
+    /// URL-safe alphabet without padding, as used in web tokens.
+    pub const URL_SAFE_NO_PAD: Config = Config {
+        alphabet: Alphabet::UrlSafe,
+        pad: false,
+        ignore_whitespace: false,
+    };
+
     /// Decode a string in BASE64 to a vector of bytes. Ignore all
     /// whitespace.
-    pub fn decode(s: &str) -> Result<Vec<u8>, error::Error> {
-        let mut ret = Vec::new();
+    pub fn decode(s: &str) -> error::Result<Vec<u8>> {
+        decode_config(s, STANDARD)
+    }
 
-        fn pos(l: Option<char>) -> Result<Option<u8>, error::Error> {
-            match l {
-                None =>
-                    Err(error::Error::InvalidBase64Length),
-                Some(c) if c == '=' =>
-                    Ok(None),
-                Some(c) =>
-                    match BASE64_CHARS.iter().position(|b| *b as char == c) {
-                        None => Err(error::Error::InvalidBase64Char(c)),
-                        Some(p) => Ok(Some(p as u8)),
-                    },
+    /// Append the bytes encoded by a group of two to four digits.
+    fn push_group(out: &mut Vec<u8>, digits: &[u8]) {
+        out.push((digits[0] << 2) | ((digits[1] >> 4) & 3));
+        if digits.len() > 2 {
+            out.push((digits[1] << 4) | ((digits[2] >> 2) & 15));
+        }
+        if digits.len() > 3 {
+            out.push((digits[2] << 6) | (digits[3] & 63));
+        }
+    }
+
+    /// Incremental decoding state, shared by `decode_config` and
+    /// `Reader`.
+    struct Decoder {
+        config: Config,
+        // Digits of the current group, and the number of padding
+        // characters following them.
+        group: [u8; 4],
+        len: usize,
+        padding: usize,
+    }
+
+    impl Decoder {
+        fn new(config: Config) -> Decoder {
+            Decoder {
+                config: config,
+                group: [0; 4],
+                len: 0,
+                padding: 0,
             }
         }
-        let mut it = s.chars().filter(|c| *c != '\r' && *c != '\n');
-        loop {
-            if let Some(x0) = it.next() {
-                
-                let l0 = try!(pos(Some(x0)));
-                let l1 = try!(pos(it.next()));
-                let l2 = try!(pos(it.next()));
-                let l3 = try!(pos(it.next()));
-                match (l0, l1, l2, l3) {
-                    (Some(c0), Some(c1), Some(c2), Some(c3)) => {
-                        ret.push((c0 << 2) | ((c1 >> 4) & 3));
-                        ret.push((c1 << 4) | ((c2 >> 2) & 15));
-                        ret.push((c2 << 6) | (c3 & 63));
-                    },
-                    (Some(c0), Some(c1), Some(c2), None) => {
-                        ret.push((c0 << 2) | ((c1 >> 4) & 3));
-                        ret.push((c1 << 4) | ((c2 >> 2) & 15));
-                    },
-                    (Some(c0), Some(c1), None, None) => {
-                        ret.push((c0 << 2) | ((c1 >> 4) & 3));
-                    },
-                    _ => {
-                        return Err(error::Error::InvalidBase64Padding);
-                    }
+
+        /// Feed the character `c`, appending decoded bytes to `out`.
+        fn push(&mut self, c: char, out: &mut Vec<u8>) -> error::Result<()> {
+            if self.config.ignore_whitespace && c.is_whitespace() {
+                return Ok(());
+            }
+            if c == '=' {
+                if self.len < 2 {
+                    return Err(error::Error::InvalidBase64Padding);
                 }
+                self.padding += 1;
+            } else if self.padding > 0 {
+                return Err(error::Error::InvalidBase64Padding);
             } else {
-                break;
+                match self.config.alphabet.chars().iter().position(|b| *b as char == c) {
+                    None => return Err(error::Error::InvalidBase64Char(c)),
+                    Some(p) => self.group[self.len] = p as u8,
+                }
+                self.len += 1;
+            }
+            if self.len + self.padding == 4 {
+                push_group(out, &self.group[..self.len]);
+                self.len = 0;
+                self.padding = 0;
             }
+            Ok(())
+        }
+
+        /// Signal the end of the input, appending the bytes of an
+        /// unpadded final group to `out`.
+        fn finish(&mut self, out: &mut Vec<u8>) -> error::Result<()> {
+            match self.len {
+                0 => Ok(()),
+                1 => Err(error::Error::InvalidBase64Length),
+                _ if self.config.pad || self.padding > 0 => Err(error::Error::InvalidBase64Length),
+                _ => {
+                    push_group(out, &self.group[..self.len]);
+                    self.len = 0;
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    /// Decode a string in the BASE64 variant `config` to a vector of
+    /// bytes.
+    pub fn decode_config(s: &str, config: Config) -> error::Result<Vec<u8>> {
+        let mut ret = Vec::with_capacity(s.len() / 4 * 3);
+        decode_config_into(s, config, &mut ret)?;
+        Ok(ret)
+    }
+
+    /// Decode a standard BASE64 string, appending the bytes to `out`.
+    pub fn decode_into(s: &str, out: &mut Vec<u8>) -> error::Result<()> {
+        decode_config_into(s, STANDARD, out)
+    }
+
+    /// Decode a string in the BASE64 variant `config`, appending the
+    /// bytes to `out`.  On error, `out` may contain part of the
+    /// decoded data.
+    pub fn decode_config_into(s: &str, config: Config, out: &mut Vec<u8>) -> error::Result<()> {
+        let mut decoder = Decoder::new(config);
+        for c in s.chars() {
+            decoder.push(c, out)?;
+        }
+        decoder.finish(out)
+    }
+
+    /// Return the length of the encoding of `len` bytes in the BASE64
+    /// variant `config`.
+    pub fn encoded_len(len: usize, config: Config) -> usize {
+        if config.pad {
+            (len + 2) / 3 * 4
+        } else {
+            (len * 4 + 2) / 3
         }
-        return Ok(ret);
     }
 
     /// Encode a vector of bytes as a BASE64 string.
     pub fn encode(bytes: &[u8]) -> String {
-        let mut ret = String::new();
-        let mut it = bytes.iter();
-        loop {
-            if let Some(b0) = it.next() {
-                let c0 = b0 >> 2;
-                let (c1, c2, c3) =
-                    if let Some(b1) = it.next() {
-                        let c1 = ((b0 & 3) << 4) | (b1 >> 4);
-                        let (c2, c3) =
-                            if let Some(b2) = it.next() {
-                                let c2 = ((b1 & 15) << 2) | ((b2 >> 6u8) & 3);
-                                let c3 = b2 & 63;
-                                (BASE64_CHARS[c2 as usize], BASE64_CHARS[c3 as usize])
-                            } else {
-                                let c2 = (b1 & 15) << 2;
-                                (BASE64_CHARS[c2 as usize], b'=')
-                            };
-                        (BASE64_CHARS[c1 as usize], c2, c3)
-                    } else {
-                        let c1 = (b0 & 3) << 4;
-                        (BASE64_CHARS[c1 as usize], b'=', b'=')
-                    };
-                ret.push(BASE64_CHARS[c0 as usize] as char);
-                ret.push(c1 as char);
-                ret.push(c2 as char);
-                ret.push(c3 as char);
-            } else {
-                break;
+        encode_config(bytes, STANDARD)
+    }
+
+    /// Encode a vector of bytes as a string in the BASE64 variant
+    /// `config`.
+    pub fn encode_config(bytes: &[u8], config: Config) -> String {
+        let mut ret = String::with_capacity(encoded_len(bytes.len(), config));
+        encode_config_into(bytes, config, &mut ret);
+        ret
+    }
+
+    /// Encode a vector of bytes as a standard BASE64 string, appending
+    /// it to `out`.
+    pub fn encode_into(bytes: &[u8], out: &mut String) {
+        encode_config_into(bytes, STANDARD, out)
+    }
+
+    /// Encode a vector of bytes as a string in the BASE64 variant
+    /// `config`, appending it to `out`.
+    pub fn encode_config_into(bytes: &[u8], config: Config, out: &mut String) {
+        let chars = config.alphabet.chars();
+        out.reserve(encoded_len(bytes.len(), config));
+        for chunk in bytes.chunks(3) {
+            let b0 = chunk[0];
+            let b1 = *chunk.get(1).unwrap_or(&0);
+            let b2 = *chunk.get(2).unwrap_or(&0);
+            let digits = [b0 >> 2,
+                          ((b0 & 3) << 4) | (b1 >> 4),
+                          ((b1 & 15) << 2) | ((b2 >> 6) & 3),
+                          b2 & 63];
+            for &d in &digits[..chunk.len() + 1] {
+                out.push(chars[d as usize] as char);
+            }
+            if config.pad {
+                for _ in chunk.len()..3 {
+                    out.push('=');
+                }
             }
         }
-        ret
+    }
+
+    /// Number of bytes `Reader` reads from the underlying reader at
+    /// once, and the size of the chunks `Writer` encodes.
+    const CHUNK_SIZE: usize = 1024;
+
+    fn invalid_data(err: error::Error) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidData, err)
+    }
+
+    /// Adapter that decodes BASE64 while reading from the underlying
+    /// reader, using constant memory.  Invalid input is reported as
+    /// an error of kind `InvalidData`.
+    pub struct Reader<R> {
+        inner: R,
+        decoder: Decoder,
+        buf: Vec<u8>,
+        pos: usize,
+        done: bool,
+    }
+
+    impl<R: Read> Reader<R> {
+        /// Create a reader decoding standard BASE64 from `inner`.
+        pub fn new(inner: R) -> Reader<R> {
+            Reader::with_config(inner, STANDARD)
+        }
+
+        /// Create a reader decoding the BASE64 variant `config` from
+        /// `inner`.
+        pub fn with_config(inner: R, config: Config) -> Reader<R> {
+            Reader {
+                inner: inner,
+                decoder: Decoder::new(config),
+                buf: Vec::with_capacity(CHUNK_SIZE),
+                pos: 0,
+                done: false,
+            }
+        }
+
+        /// Return the underlying reader.
+        pub fn into_inner(self) -> R {
+            self.inner
+        }
+    }
+
+    impl<R: Read> Read for Reader<R> {
+        fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+            while self.pos == self.buf.len() && !self.done {
+                self.buf.clear();
+                self.pos = 0;
+                let mut input = [0; CHUNK_SIZE];
+                let n = self.inner.read(&mut input)?;
+                if n == 0 {
+                    self.done = true;
+                    self.decoder.finish(&mut self.buf).map_err(invalid_data)?;
+                }
+                for &b in &input[..n] {
+                    (self.decoder.push(b as char, &mut self.buf).map_err(invalid_data))?;
+                }
+            }
+            let n = ::std::cmp::min(out.len(), self.buf.len() - self.pos);
+            out[..n].copy_from_slice(&self.buf[self.pos..self.pos + n]);
+            self.pos += n;
+            Ok(n)
+        }
+    }
+
+    /// Adapter that encodes everything written to it as BASE64 and
+    /// writes the result to the underlying writer, using constant
+    /// memory.  Up to two bytes are held back until they can be
+    /// encoded, so `finish` must be called at the end.
+    pub struct Writer<W: Write> {
+        inner: W,
+        config: Config,
+        pending: Vec<u8>,
+        buf: String,
+    }
+
+    impl<W: Write> Writer<W> {
+        /// Create a writer encoding standard BASE64 to `inner`.
+        pub fn new(inner: W) -> Writer<W> {
+            Writer::with_config(inner, STANDARD)
+        }
+
+        /// Create a writer encoding the BASE64 variant `config` to
+        /// `inner`.
+        pub fn with_config(inner: W, config: Config) -> Writer<W> {
+            Writer {
+                inner: inner,
+                config: config,
+                pending: Vec::with_capacity(3),
+                buf: String::with_capacity(CHUNK_SIZE),
+            }
+        }
+
+        /// Encode the remaining bytes, with padding if configured,
+        /// flush, and return the underlying writer.
+        pub fn finish(mut self) -> io::Result<W> {
+            self.buf.clear();
+            encode_config_into(&self.pending, self.config, &mut self.buf);
+            self.inner.write_all(self.buf.as_bytes())?;
+            self.inner.flush()?;
+            Ok(self.inner)
+        }
+    }
+
+    impl<W: Write> Write for Writer<W> {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            let mut rest = buf;
+            if !self.pending.is_empty() {
+                let n = ::std::cmp::min(3 - self.pending.len(), rest.len());
+                self.pending.extend(&rest[..n]);
+                rest = &rest[n..];
+                if self.pending.len() < 3 {
+                    return Ok(buf.len());
+                }
+                self.buf.clear();
+                encode_config_into(&self.pending, self.config, &mut self.buf);
+                self.inner.write_all(self.buf.as_bytes())?;
+                self.pending.clear();
+            }
+            let whole = rest.len() / 3 * 3;
+            for chunk in rest[..whole].chunks(CHUNK_SIZE / 4 * 3) {
+                self.buf.clear();
+                encode_config_into(chunk, self.config, &mut self.buf);
+                self.inner.write_all(self.buf.as_bytes())?;
+            }
+            self.pending.extend(&rest[whole..]);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            self.inner.flush()
+        }
     }
 
     #[cfg(test)]
     mod tests {
-        use super::{decode, encode};
+        use super::{decode, encode, decode_config, encode_config};
+        use super::{decode_into, encode_into, encode_config_into, encoded_len};
+        use super::{Config, Alphabet, STANDARD, URL_SAFE, URL_SAFE_NO_PAD, Reader, Writer};
+        use crate::error::Error;
+        use std::io::{self, Read, Write};
         
         #[test]
         fn decode_empty() {
@@ -207,6 +481,95 @@ pub mod base64 {
             assert_eq!(expected, encode(&bytes));
         }
 
+        #[test]
+        fn url_safe() {
+            let bytes = [0xfb, 0xff, 0xbf, 0xfb, 0xff];
+            assert_eq!("+/+/+/8=", encode_config(&bytes, STANDARD));
+            assert_eq!("-_-_-_8=", encode_config(&bytes, URL_SAFE));
+            assert_eq!("-_-_-_8", encode_config(&bytes, URL_SAFE_NO_PAD));
+            assert_eq!(bytes.to_vec(), decode_config("-_-_-_8=", URL_SAFE).unwrap());
+            assert_eq!(bytes.to_vec(), decode_config("-_-_-_8", URL_SAFE_NO_PAD).unwrap());
+            assert_eq!(bytes.to_vec(), decode_config("-_-_-_8=", URL_SAFE_NO_PAD).unwrap());
+            match decode_config("+/+/+/8=", URL_SAFE) {
+                Err(Error::InvalidBase64Char('+')) => (),
+                r => panic!("unexpected result: {:?}", r),
+            }
+        }
+
+        #[test]
+        fn decode_errors() {
+            match decode_config("-_-_-_8", URL_SAFE) {
+                Err(Error::InvalidBase64Length) => (),
+                r => panic!("unexpected result: {:?}", r),
+            }
+            match decode_config("Fw", URL_SAFE_NO_PAD) {
+                Ok(ref v) if *v == vec![0x17] => (),
+                r => panic!("unexpected result: {:?}", r),
+            }
+            for s in ["F", "Fw=", "Fw=A"].iter() {
+                match decode_config(s, URL_SAFE_NO_PAD) {
+                    Err(Error::InvalidBase64Length) | Err(Error::InvalidBase64Padding) => (),
+                    r => panic!("unexpected result for {:?}: {:?}", s, r),
+                }
+            }
+            match decode("F===") {
+                Err(Error::InvalidBase64Padding) => (),
+                r => panic!("unexpected result: {:?}", r),
+            }
+        }
+
+        #[test]
+        fn whitespace() {
+            let strict = Config { alphabet: Alphabet::Standard, pad: true, ignore_whitespace: false };
+            assert_eq!(vec![0x17, 0x2f, 0xff, 0x00], decode("Fy//\r\nAA ==\n").unwrap());
+            match decode_config("Fy//\nAA==", strict) {
+                Err(Error::InvalidBase64Char('\n')) => (),
+                r => panic!("unexpected result: {:?}", r),
+            }
+        }
+
+        #[test]
+        fn reader() {
+            let input = "Fy//\nAA==\nYnIAdGhlcg==";
+            let mut r = Reader::new(input.as_bytes());
+            let mut out: Vec<u8> = Vec::new();
+            let mut buf = [0; 2];
+            loop {
+                let n = r.read(&mut buf).unwrap();
+                if n == 0 {
+                    break;
+                }
+                out.extend(&buf[..n]);
+            }
+            assert_eq!(decode(input).unwrap(), out);
+
+            let mut r = Reader::with_config("-_-_-_8".as_bytes(), URL_SAFE_NO_PAD);
+            let mut out = Vec::new();
+            r.read_to_end(&mut out).unwrap();
+            assert_eq!(vec![0xfb, 0xff, 0xbf, 0xfb, 0xff], out);
+        }
+
+        #[test]
+        fn reader_error() {
+            let mut out = Vec::new();
+            let err = Reader::new("Fy/*".as_bytes()).read_to_end(&mut out).unwrap_err();
+            assert_eq!(io::ErrorKind::InvalidData, err.kind());
+            let err = Reader::new("Fy/".as_bytes()).read_to_end(&mut out).unwrap_err();
+            assert_eq!(io::ErrorKind::InvalidData, err.kind());
+        }
+
+        #[test]
+        fn writer() {
+            let mut w = Writer::new(Vec::new());
+            for piece in [&b"b"[..], b"r\x00t", b"", b"h", b"er"].iter() {
+                w.write_all(piece).unwrap();
+            }
+            assert_eq!("YnIAdGhlcg==", String::from_utf8(w.finish().unwrap()).unwrap());
+            let mut w = Writer::with_config(Vec::new(), URL_SAFE_NO_PAD);
+            w.write_all(&[0xfb, 0xff, 0xbf, 0xfb, 0xff]).unwrap();
+            assert_eq!("-_-_-_8", String::from_utf8(w.finish().unwrap()).unwrap());
+        }
+
         use quickcheck::{Gen, Arbitrary};
         
         #[derive(Copy, Clone, Debug)]
@@ -226,6 +589,35 @@ pub mod base64 {
                 decode(&encode(&xs)).unwrap() == xs
             }
 
+            fn prop_decode_encode_url_safe_no_pad(xs: Vec<u8>) -> bool {
+                decode_config(&encode_config(&xs, URL_SAFE_NO_PAD), URL_SAFE_NO_PAD).unwrap() == xs
+            }
+
+            fn prop_encode_into(xs: Vec<u8>, ys: Vec<u8>) -> bool {
+                let mut s = String::new();
+                encode_into(&xs, &mut s);
+                let n = s.len();
+                encode_config_into(&ys, URL_SAFE_NO_PAD, &mut s);
+                let mut out = vec![42];
+                n == encoded_len(xs.len(), STANDARD) &&
+                    s.len() - n == encoded_len(ys.len(), URL_SAFE_NO_PAD) &&
+                    s[..n] == encode(&xs) &&
+                    s[n..] == encode_config(&ys, URL_SAFE_NO_PAD) &&
+                    decode_into(&s[..n], &mut out).is_ok() &&
+                    out[0] == 42 && out[1..] == xs[..]
+            }
+
+            fn prop_writer_reader(xs: Vec<u8>, split: usize) -> bool {
+                let split = if xs.is_empty() { 0 } else { split % xs.len() };
+                let mut w = Writer::new(Vec::new());
+                w.write_all(&xs[..split]).unwrap();
+                w.write_all(&xs[split..]).unwrap();
+                let encoded = w.finish().unwrap();
+                let mut decoded = Vec::new();
+                Reader::new(&encoded[..]).read_to_end(&mut decoded).unwrap();
+                encoded == encode(&xs).into_bytes() && decoded == xs
+            }
+
             fn prop_encode_decode(xs: Vec<B64Chars>) -> bool {
                 use std::iter::FromIterator;
                 let s = String::from_iter(xs.into_iter()
@@ -236,29 +628,562 @@ pub mod base64 {
     }
 }
 
+/// BASE32 encoding with the alphabet from RFC 4648, section 6.
+pub mod base32 {
+    use crate::error;
+
+    static BASE32_CHARS: &'static [u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+    /// Convert a BASE32 string to a vector of bytes.  The string may
+    /// or may not be padded with `=` to a multiple of eight
+    /// characters, but if it is, the padding must be complete.
+    pub fn decode(s: &str) -> error::Result<Vec<u8>> {
+        let body = s.trim_end_matches('=');
+        let padding = s.len() - body.len();
+        if padding > 0 && (s.len() % 8 != 0 || padding >= 8) {
+            return Err(error::Error::InvalidBase32Padding);
+        }
+        // Groups of eight digits encode five bytes.  A final group of
+        // n bytes has 8n/5 digits, rounded up.
+        let rest = match body.len() % 8 {
+            0 => 0,
+            2 => 1,
+            4 => 2,
+            5 => 3,
+            7 => 4,
+            _ => return Err(error::Error::InvalidBase32Length),
+        };
+        let mut ret = Vec::with_capacity(body.len() / 8 * 5 + rest);
+        let mut acc: u64 = 0;
+        for (i, c) in body.chars().enumerate() {
+            let d = match BASE32_CHARS.iter().position(|b| *b as char == c) {
+                Some(d) => d as u64,
+                None => return Err(error::Error::InvalidBase32Char(c)),
+            };
+            acc = acc << 5 | d;
+            if i % 8 == 7 {
+                for k in (0..5).rev() {
+                    ret.push((acc >> (8 * k)) as u8);
+                }
+                acc = 0;
+            }
+        }
+        let digits = body.len() % 8;
+        for k in 0..rest {
+            ret.push((acc >> (5 * digits - 8 * (k + 1))) as u8);
+        }
+        Ok(ret)
+    }
+
+    /// Convert a vector of bytes into a BASE32 string, padded with
+    /// `=` to a multiple of eight characters.
+    pub fn encode(bytes: &[u8]) -> String {
+        let mut ret = encode_no_pad(bytes);
+        while ret.len() % 8 != 0 {
+            ret.push('=');
+        }
+        ret
+    }
+
+    /// Convert a vector of bytes into a BASE32 string without
+    /// padding.
+    pub fn encode_no_pad(bytes: &[u8]) -> String {
+        let mut ret = String::with_capacity((bytes.len() * 8 + 4) / 5);
+        for chunk in bytes.chunks(5) {
+            let acc = chunk.iter().fold(0u64, |acc, &b| acc << 8 | b as u64);
+            let bits = 8 * chunk.len();
+            let digits = (bits + 4) / 5;
+            // Shift the chunk so that the last digit is complete.
+            let acc = acc << (5 * digits - bits);
+            for k in (0..digits).rev() {
+                ret.push(BASE32_CHARS[((acc >> (5 * k)) & 0x1f) as usize] as char);
+            }
+        }
+        ret
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::{decode, encode, encode_no_pad};
+        use crate::error::Error;
+
+        // Test vectors from RFC 4648, section 10.
+        static VECTORS: [(&'static str, &'static str); 7] =
+            [("", ""),
+             ("f", "MY======"),
+             ("fo", "MZXQ===="),
+             ("foo", "MZXW6==="),
+             ("foob", "MZXW6YQ="),
+             ("fooba", "MZXW6YTB"),
+             ("foobar", "MZXW6YTBOI======")];
+
+        #[test]
+        fn rfc4648() {
+            for &(plain, coded) in VECTORS.iter() {
+                assert_eq!(coded, encode(plain.as_bytes()));
+                assert_eq!(coded.trim_end_matches('='), encode_no_pad(plain.as_bytes()));
+                assert_eq!(plain.as_bytes(), &decode(coded).unwrap()[..]);
+                assert_eq!(plain.as_bytes(), &decode(coded.trim_end_matches('=')).unwrap()[..]);
+            }
+        }
+
+        #[test]
+        fn decode_errors() {
+            match decode("MZXW6Y") {
+                Err(Error::InvalidBase32Length) => (),
+                r => panic!("unexpected result: {:?}", r),
+            }
+            match decode("MZX=====") {
+                Err(Error::InvalidBase32Length) => (),
+                r => panic!("unexpected result: {:?}", r),
+            }
+            match decode("MZXW6YQ==") {
+                Err(Error::InvalidBase32Padding) => (),
+                r => panic!("unexpected result: {:?}", r),
+            }
+            match decode("MY==============") {
+                Err(Error::InvalidBase32Padding) => (),
+                r => panic!("unexpected result: {:?}", r),
+            }
+            match decode("MZXW1YQ=") {
+                Err(Error::InvalidBase32Char('1')) => (),
+                r => panic!("unexpected result: {:?}", r),
+            }
+        }
+
+        quickcheck! {
+            fn prop_decode_encode(xs: Vec<u8>) -> bool {
+                decode(&encode(&xs)).unwrap() == xs
+            }
+
+            fn prop_decode_encode_no_pad(xs: Vec<u8>) -> bool {
+                decode(&encode_no_pad(&xs)).unwrap() == xs
+            }
+        }
+    }
+}
+
+/// BASE58 encoding with the alphabet used by Bitcoin, which leaves
+/// out the easily confused characters `0`, `O`, `I` and `l`.
+pub mod base58 {
+    use crate::error;
+
+    static BASE58_CHARS: &'static [u8; 58] =
+        b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+    /// Convert a BASE58 string to a vector of bytes.  Each leading
+    /// `1` stands for a leading zero byte.
+    pub fn decode(s: &str) -> error::Result<Vec<u8>> {
+        let zeros = s.chars().take_while(|&c| c == '1').count();
+        // Big-endian base-256 digits of the number, built by
+        // multiplying by 58 and adding each digit.
+        let mut num: Vec<u8> = Vec::with_capacity(s.len());
+        for c in s[zeros..].chars() {
+            let mut carry = match BASE58_CHARS.iter().position(|b| *b as char == c) {
+                Some(d) => d as u32,
+                None => return Err(error::Error::InvalidBase58Char(c)),
+            };
+            for b in num.iter_mut().rev() {
+                carry += *b as u32 * 58;
+                *b = carry as u8;
+                carry >>= 8;
+            }
+            while carry > 0 {
+                num.insert(0, carry as u8);
+                carry >>= 8;
+            }
+        }
+        let mut ret = vec![0; zeros];
+        ret.extend(num);
+        Ok(ret)
+    }
+
+    /// Convert a vector of bytes into a BASE58 string.  Each leading
+    /// zero byte is encoded as `1`.
+    pub fn encode(bytes: &[u8]) -> String {
+        let zeros = bytes.iter().take_while(|&&b| b == 0).count();
+        // Big-endian base-58 digits of the number.
+        let mut digits: Vec<u8> = Vec::with_capacity(bytes.len() * 138 / 100 + 1);
+        for &b in &bytes[zeros..] {
+            let mut carry = b as u32;
+            for d in digits.iter_mut().rev() {
+                carry += (*d as u32) << 8;
+                *d = (carry % 58) as u8;
+                carry /= 58;
+            }
+            while carry > 0 {
+                digits.insert(0, (carry % 58) as u8);
+                carry /= 58;
+            }
+        }
+        let mut ret = String::with_capacity(zeros + digits.len());
+        for _ in 0..zeros {
+            ret.push('1');
+        }
+        for d in digits {
+            ret.push(BASE58_CHARS[d as usize] as char);
+        }
+        ret
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::{decode, encode};
+        use crate::error::Error;
+
+        #[test]
+        fn encode_decode() {
+            let vectors: [(&[u8], &str); 5] =
+                [(b"", ""),
+                 (b"\x00", "1"),
+                 (b"\x00\x00\x01", "112"),
+                 (b"hello world", "StV1DL6CwTryKyV"),
+                 (b"\x00\x00\x28\x7f\xb4\xcd", "11233QC4")];
+            for &(plain, coded) in vectors.iter() {
+                assert_eq!(coded, encode(plain));
+                assert_eq!(plain, &decode(coded).unwrap()[..]);
+            }
+        }
+
+        #[test]
+        fn decode_invalid_char() {
+            match decode("1O") {
+                Err(Error::InvalidBase58Char('O')) => (),
+                r => panic!("unexpected result: {:?}", r),
+            }
+        }
+
+        quickcheck! {
+            fn prop_decode_encode(xs: Vec<u8>) -> bool {
+                decode(&encode(&xs)).unwrap() == xs
+            }
+        }
+    }
+}
+
+/// PEM containers (RFC 7468): BASE64 data wrapped at 64 characters
+/// between `-----BEGIN <label>-----` and `-----END <label>-----`
+/// lines.
+pub mod pem {
+    use crate::codec::base64;
+    use crate::error;
+
+    /// Width of the BASE64 lines produced by `encode`.
+    const LINE_WIDTH: usize = 64;
+
+    /// A single PEM block.
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct Pem {
+        /// Label from the banners, for example `RSA PRIVATE KEY`.
+        pub label: String,
+        /// Decoded contents.
+        pub contents: Vec<u8>,
+    }
+
+    fn banner<'a>(line: &'a str, kind: &str) -> Option<&'a str> {
+        let prefix = format!("-----{} ", kind);
+        if line.starts_with(&prefix) && line.ends_with("-----") && line.len() >= prefix.len() + 5 {
+            Some(&line[prefix.len()..line.len() - 5])
+        } else {
+            None
+        }
+    }
+
+    /// Decode all PEM blocks in `s`, in order.  Text outside of the
+    /// blocks is ignored.
+    pub fn decode(s: &str) -> error::Result<Vec<Pem>> {
+        let mut ret = Vec::new();
+        let mut lines = s.lines().map(|l| l.trim());
+        while let Some(line) = lines.next() {
+            let label = match banner(line, "BEGIN") {
+                Some(label) => label,
+                None => {
+                    if banner(line, "END").is_some() {
+                        return Err(error::Error::InvalidPemBanner);
+                    }
+                    continue;
+                }
+            };
+            let mut data = String::new();
+            loop {
+                match lines.next() {
+                    None => return Err(error::Error::InvalidPemBanner),
+                    Some(l) => {
+                        match banner(l, "END") {
+                            Some(end) if end == label => break,
+                            Some(_) => return Err(error::Error::InvalidPemBanner),
+                            None => data.push_str(l),
+                        }
+                    }
+                }
+            }
+            ret.push(Pem {
+                label: label.to_string(),
+                contents: base64::decode(&data)?,
+            });
+        }
+        Ok(ret)
+    }
+
+    /// Encode a PEM block, including the final newline.  Several
+    /// blocks can be concatenated into one file.
+    pub fn encode(pem: &Pem) -> String {
+        let data = base64::encode(&pem.contents);
+        let mut ret = format!("-----BEGIN {}-----\n", pem.label);
+        for line in data.as_bytes().chunks(LINE_WIDTH) {
+            ret.push_str(&String::from_utf8_lossy(line));
+            ret.push('\n');
+        }
+        ret.push_str(&format!("-----END {}-----\n", pem.label));
+        ret
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::{decode, encode, Pem};
+        use crate::error::Error;
+
+        #[test]
+        fn encode_0() {
+            let pem = Pem {
+                label: "TEST".to_string(),
+                contents: vec![0; 60],
+            };
+            let expected = "-----BEGIN TEST-----\n".to_string() +
+                &"A".repeat(64) + "\n" + &"A".repeat(16) + "\n-----END TEST-----\n";
+            assert_eq!(expected, encode(&pem));
+        }
+
+        #[test]
+        fn decode_multiple() {
+            let s = "Some key:\n\
+                     -----BEGIN A-----\n\
+                     aGkg\n\
+                     bW9t\n\
+                     -----END A-----\n\
+                     \n\
+                     -----BEGIN B C-----\n\
+                     -----END B C-----\n";
+            let blocks = decode(s).unwrap();
+            assert_eq!(vec![Pem { label: "A".to_string(), contents: b"hi mom".to_vec() },
+                            Pem { label: "B C".to_string(), contents: vec![] }],
+                       blocks);
+        }
+
+        #[test]
+        fn decode_errors() {
+            for s in ["-----BEGIN A-----\naGkg\n",
+                      "-----BEGIN A-----\naGkg\n-----END B-----\n",
+                      "-----END A-----\n"].iter() {
+                match decode(s) {
+                    Err(Error::InvalidPemBanner) => (),
+                    r => panic!("unexpected result: {:?}", r),
+                }
+            }
+            match decode("-----BEGIN A-----\naGk*\n-----END A-----\n") {
+                Err(Error::InvalidBase64Char('*')) => (),
+                r => panic!("unexpected result: {:?}", r),
+            }
+        }
+
+        quickcheck! {
+            fn prop_decode_encode(xs: Vec<Vec<u8>>) -> bool {
+                let blocks: Vec<Pem> = xs.into_iter()
+                    .map(|c| Pem { label: "DATA".to_string(), contents: c })
+                    .collect();
+                let s: String = blocks.iter().map(encode).collect();
+                decode(&s).unwrap() == blocks
+            }
+        }
+    }
+}
+
+/// Percent-encoding (RFC 3986) and `application/x-www-form-urlencoded`
+/// query strings.
+pub mod url {
+    use crate::error;
+
+    static HEX_CHARS: &'static [u8; 16] = b"0123456789ABCDEF";
+
+    fn unreserved(b: u8) -> bool {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => true,
+            _ => false,
+        }
+    }
+
+    fn unhex(b: u8) -> Option<u8> {
+        match b {
+            b'0'..=b'9' => Some(b - b'0'),
+            b'a'..=b'f' => Some(b - b'a' + 10),
+            b'A'..=b'F' => Some(b - b'A' + 10),
+            _ => None,
+        }
+    }
+
+    fn encode_with(bytes: &[u8], space_as_plus: bool) -> String {
+        let mut ret = String::with_capacity(bytes.len());
+        for &b in bytes {
+            if unreserved(b) {
+                ret.push(b as char);
+            } else if b == b' ' && space_as_plus {
+                ret.push('+');
+            } else {
+                ret.push('%');
+                ret.push(HEX_CHARS[(b >> 4) as usize] as char);
+                ret.push(HEX_CHARS[(b & 0x0f) as usize] as char);
+            }
+        }
+        ret
+    }
+
+    fn decode_with(s: &str, plus_as_space: bool) -> error::Result<Vec<u8>> {
+        let bytes = s.as_bytes();
+        let mut ret = Vec::with_capacity(bytes.len());
+        let mut i = 0;
+        while i < bytes.len() {
+            match bytes[i] {
+                b'%' => {
+                    let hi = bytes.get(i + 1).and_then(|&b| unhex(b));
+                    let lo = bytes.get(i + 2).and_then(|&b| unhex(b));
+                    match (hi, lo) {
+                        (Some(hi), Some(lo)) => ret.push(hi << 4 | lo),
+                        _ => return Err(error::Error::InvalidPercentEncoding),
+                    }
+                    i += 3;
+                    continue;
+                }
+                b'+' if plus_as_space => ret.push(b' '),
+                b => ret.push(b),
+            }
+            i += 1;
+        }
+        Ok(ret)
+    }
+
+    /// Percent-encode all bytes except the unreserved characters
+    /// letters, digits, `-`, `.`, `_` and `~`.
+    pub fn encode(bytes: &[u8]) -> String {
+        encode_with(bytes, false)
+    }
+
+    /// Decode a percent-encoded string.  Characters that are not
+    /// part of an escape are passed through unchanged.
+    pub fn decode(s: &str) -> error::Result<Vec<u8>> {
+        decode_with(s, false)
+    }
+
+    /// Encode key/value pairs as a form-urlencoded query string like
+    /// `email=foo%40bar.com&uid=10`, where spaces become `+`.
+    pub fn encode_query(pairs: &[(String, String)]) -> String {
+        let fields: Vec<String> = pairs.iter()
+            .map(|&(ref k, ref v)| format!("{}={}", encode_with(k.as_bytes(), true),
+                                           encode_with(v.as_bytes(), true)))
+            .collect();
+        fields.join("&")
+    }
+
+    /// Parse a form-urlencoded query string into key/value pairs, in
+    /// the order they appear.  Keys may repeat, a field without `=`
+    /// has an empty value, and empty fields are skipped.  Invalid
+    /// UTF-8 is replaced by U+FFFD.
+    pub fn parse_query(s: &str) -> error::Result<Vec<(String, String)>> {
+        let mut ret = Vec::new();
+        for field in s.split('&').filter(|f| !f.is_empty()) {
+            let (k, v) = match field.find('=') {
+                Some(i) => (&field[..i], &field[i + 1..]),
+                None => (field, ""),
+            };
+            let k = decode_with(k, true)?;
+            let v = decode_with(v, true)?;
+            ret.push((String::from_utf8_lossy(&k).into_owned(), String::from_utf8_lossy(&v).into_owned()));
+        }
+        Ok(ret)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::{decode, encode, encode_query, parse_query};
+        use crate::error::Error;
+
+        fn pair(k: &str, v: &str) -> (String, String) {
+            (k.to_string(), v.to_string())
+        }
+
+        #[test]
+        fn encode_decode() {
+            assert_eq!("foo%40bar.com%26role%3Dadmin", encode(b"foo@bar.com&role=admin"));
+            assert_eq!("a%20b~%FF", encode(b"a b~\xff"));
+            assert_eq!(b"a b~\xff".to_vec(), decode("a%20b~%ff").unwrap());
+            assert_eq!(b"a+b".to_vec(), decode("a+b").unwrap());
+        }
+
+        #[test]
+        fn decode_errors() {
+            for s in ["%", "%4", "%4g", "abc%zz"].iter() {
+                match decode(s) {
+                    Err(Error::InvalidPercentEncoding) => (),
+                    r => panic!("unexpected result: {:?}", r),
+                }
+            }
+        }
+
+        #[test]
+        fn query() {
+            let pairs = parse_query("foo=bar&baz=qux&zap=zazzle").unwrap();
+            assert_eq!(vec![pair("foo", "bar"), pair("baz", "qux"), pair("zap", "zazzle")], pairs);
+            let pairs = parse_query("email=foo%40bar.com&&comment1=cooking+MCs&flag&x=a=b").unwrap();
+            assert_eq!(vec![pair("email", "foo@bar.com"), pair("comment1", "cooking MCs"),
+                            pair("flag", ""), pair("x", "a=b")],
+                       pairs);
+            assert_eq!("email=foo%40bar.com%26role%3Dadmin&comment1=cooking+MCs",
+                       encode_query(&[pair("email", "foo@bar.com&role=admin"), pair("comment1", "cooking MCs")]));
+        }
+
+        quickcheck! {
+            fn prop_decode_encode(xs: Vec<u8>) -> bool {
+                decode(&encode(&xs)).unwrap() == xs
+            }
+
+            fn prop_parse_encode_query(pairs: Vec<(String, String)>) -> bool {
+                let pairs: Vec<(String, String)> = pairs.into_iter().filter(|p| !p.0.is_empty()).collect();
+                parse_query(&encode_query(&pairs)).unwrap() == pairs
+            }
+        }
+    }
+}
+
 /// Standard hex encoding.
 pub mod hex {
-    use ::error;
+    use crate::error;
 
     /// Convert a string in hex notation to a vector of bytes.
-    pub fn decode(s: &str) -> Result<Vec<u8>, error::Error> {
-        fn unhex(c: char) -> Result<u8, error::Error> {
+    pub fn decode(s: &str) -> error::Result<Vec<u8>> {
+        let mut ret = Vec::with_capacity(s.len() / 2);
+        decode_into(s, &mut ret)?;
+        Ok(ret)
+    }
+
+    /// Convert a string in hex notation to bytes, appending them to
+    /// `out`.  On error, `out` may contain part of the decoded data.
+    pub fn decode_into(s: &str, out: &mut Vec<u8>) -> error::Result<()> {
+        fn unhex(c: char) -> error::Result<u8> {
             match c {
-                'a'...'f' => Ok(((c as usize) - ('a' as usize) + 10) as u8),
-                'A'...'F' => Ok(((c as usize) - ('A' as usize) + 10) as u8),
-                '0'...'9' => Ok(((c as usize) - ('0' as usize)) as u8),
+                'a'..='f' => Ok(((c as usize) - ('a' as usize) + 10) as u8),
+                'A'..='F' => Ok(((c as usize) - ('A' as usize) + 10) as u8),
+                '0'..='9' => Ok(((c as usize) - ('0' as usize)) as u8),
                 _ => Err(error::Error::InvalidHexChar(c)),
             }
         }
         
         let mut it = s.chars();
-        let mut ret = Vec::new();
+        out.reserve(s.len() / 2);
         loop {
             if let Some(c0) = it.next() {
                 if let Some(c1) = it.next() {
-                    let hi = try!(unhex(c0));
-                    let lo = try!(unhex(c1));
-                    ret.push(hi << 4 | lo);
+                    let hi = unhex(c0)?;
+                    let lo = unhex(c1)?;
+                    out.push(hi << 4 | lo);
                 } else {
                     return Err(error::Error::InvalidHexLength);
                 }
@@ -266,28 +1191,93 @@ pub mod hex {
                 break;
             }
         }
-        Ok(ret)
+        Ok(())
+    }
+
+    /// Convert a string in hex notation to a vector of bytes, like
+    /// `decode`, but skip whitespace and `0x` or `0X` prefixes before
+    /// any byte.  This accepts the output of `encode_with` with a
+    /// whitespace separator, and hex dumps like `0xde 0xad`.
+    pub fn decode_lenient(s: &str) -> error::Result<Vec<u8>> {
+        let mut digits = String::with_capacity(s.len());
+        let mut it = s.chars().peekable();
+        while let Some(c) = it.next() {
+            if c.is_whitespace() {
+                continue;
+            }
+            if c == '0' && digits.len() % 2 == 0 {
+                if let Some(&'x') = it.peek() {
+                    it.next();
+                    continue;
+                }
+                if let Some(&'X') = it.peek() {
+                    it.next();
+                    continue;
+                }
+            }
+            digits.push(c);
+        }
+        decode(&digits)
     }
 
     static HEX_CHARS: [char; 16] =
         ['0', '1', '2', '3', '4', '5', '6', '7',
          '8', '9', 'a', 'b', 'c', 'd', 'e', 'f'];
 
+    static HEX_CHARS_UPPER: [char; 16] =
+        ['0', '1', '2', '3', '4', '5', '6', '7',
+         '8', '9', 'A', 'B', 'C', 'D', 'E', 'F'];
+
     /// Convert a vector of bytes into a string in hex notation.
     pub fn encode(bytes: &[u8]) -> String {
-        let mut ret = String::new();
+        let mut ret = String::with_capacity(encoded_len(bytes.len()));
+        encode_into(bytes, &mut ret);
+        ret
+    }
+
+    /// Convert bytes into hex notation, appending it to `out`.
+    pub fn encode_into(bytes: &[u8], out: &mut String) {
+        out.reserve(encoded_len(bytes.len()));
         for b in bytes.into_iter() {
             let hi = (b >> 4) & 0x0fu8;
             let lo = b & 0x0f;
-            ret.push(HEX_CHARS[hi as usize]);
-            ret.push(HEX_CHARS[lo as usize]);
+            out.push(HEX_CHARS[hi as usize]);
+            out.push(HEX_CHARS[lo as usize]);
+        }
+    }
+
+    /// Return the length of the hex encoding of `len` bytes.
+    pub fn encoded_len(len: usize) -> usize {
+        2 * len
+    }
+
+    /// Convert a vector of bytes into a string in hex notation, with
+    /// `separator` between groups of `group_size` bytes, and in upper
+    /// case if `uppercase` is true.  A `group_size` of zero puts all
+    /// bytes into one group.
+    ///
+    /// # Example
+    /// ```
+    /// use cryptopals::codec::hex;
+    /// assert_eq!("DE AD BE EF", hex::encode_with(&[0xde, 0xad, 0xbe, 0xef], " ", true, 1));
+    /// assert_eq!("dead:beef", hex::encode_with(&[0xde, 0xad, 0xbe, 0xef], ":", false, 2));
+    /// ```
+    pub fn encode_with(bytes: &[u8], separator: &str, uppercase: bool, group_size: usize) -> String {
+        let chars = if uppercase { &HEX_CHARS_UPPER } else { &HEX_CHARS };
+        let mut ret = String::new();
+        for (i, b) in bytes.iter().enumerate() {
+            if i > 0 && group_size > 0 && i % group_size == 0 {
+                ret.push_str(separator);
+            }
+            ret.push(chars[(b >> 4) as usize]);
+            ret.push(chars[(b & 0x0f) as usize]);
         }
         ret
     }
 
     #[cfg(test)]
     mod tests {
-        use super::{decode, encode};
+        use super::{decode, decode_lenient, decode_into, encode, encode_into, encode_with, encoded_len};
         
         #[test]
         fn decode_empty() {
@@ -335,6 +1325,25 @@ pub mod hex {
             assert!(decode(s).is_err());
         }
 
+        #[test]
+        fn decode_lenient_0() {
+            let expected: Vec<u8> = vec![0xde, 0xad, 0x0b, 0x00];
+            assert_eq!(expected, decode_lenient("DE AD 0B 00").unwrap());
+            assert_eq!(expected, decode_lenient("0xde 0xad\n0X0b 0x00").unwrap());
+            assert_eq!(expected, decode_lenient("0xdead0b00").unwrap());
+            assert!(decode_lenient("de a").is_err());
+            assert!(decode_lenient("dex0").is_err());
+        }
+
+        #[test]
+        fn encode_with_0() {
+            let bytes = [0xde, 0xad, 0xbe, 0xef, 0x01];
+            assert_eq!("deadbeef01", encode_with(&bytes, " ", false, 0));
+            assert_eq!("DE AD BE EF 01", encode_with(&bytes, " ", true, 1));
+            assert_eq!("deadbeef-01", encode_with(&bytes, "-", false, 4));
+            assert_eq!("", encode_with(&[], " ", false, 1));
+        }
+
         #[test]
         fn encode_empty() {
             let bytes = [];
@@ -384,6 +1393,19 @@ pub mod hex {
                 decode(&encode(&xs)).unwrap() == xs
             }
 
+            fn prop_encode_into(xs: Vec<u8>) -> bool {
+                let mut s = "ab".to_string();
+                encode_into(&xs, &mut s);
+                let mut out = vec![42];
+                s.len() == 2 + encoded_len(xs.len()) &&
+                    decode_into(&s, &mut out).is_ok() &&
+                    out[0] == 42 && out[1] == 0xab && out[2..] == xs[..]
+            }
+
+            fn prop_decode_lenient_encode_with(xs: Vec<u8>, uppercase: bool, group_size: u8) -> bool {
+                decode_lenient(&encode_with(&xs, " ", uppercase, group_size as usize)).unwrap() == xs
+            }
+
             fn prop_encode_decode(xs: Vec<HexChar>) -> bool {
                 use std::iter::FromIterator;
                 let s = String::from_iter(xs.into_iter().flat_map(|HexChar(c0, c1)| vec![c0, c1].into_iter()));
@@ -395,71 +1417,85 @@ pub mod hex {
 
 /// Standard binary encoding.
 pub mod bin {
-    use ::error;
+    use crate::error;
+
+    fn unbin(c: char) -> error::Result<bool> {
+        match c {
+            '0' => Ok(false),
+            '1' => Ok(true),
+            _ => Err(error::Error::InvalidBinChar(c)),
+        }
+    }
 
     /// Convert a string in binary notation to a vector of
     /// bytes. Binary strings are big-endian, that means that for each
     /// byte, the most significant byte comes first in the string
     /// representation.
-    pub fn decode(s: &str) -> Result<Vec<u8>, error::Error> {
-        fn unbin(c: char) -> Result<u8, error::Error> {
-            match c {
-                '0' => Ok(0),
-                '1' => Ok(1),
-                _ => Err(error::Error::InvalidBinChar(c)),
+    pub fn decode(s: &str) -> error::Result<Vec<u8>> {
+        let (ret, bits) = decode_partial(s)?;
+        if bits % 8 != 0 {
+            return Err(error::Error::InvalidBinLength);
+        }
+        Ok(ret)
+    }
+
+    /// Convert a string in binary notation of any length to a vector
+    /// of bytes and the number of bits.  If the length is not a
+    /// multiple of 8, the remaining low bits of the last byte are
+    /// zero.
+    pub fn decode_partial(s: &str) -> error::Result<(Vec<u8>, usize)> {
+        let mut ret = Vec::with_capacity((s.len() + 7) / 8);
+        let mut bits = 0;
+        for c in s.chars() {
+            if bits % 8 == 0 {
+                ret.push(0);
             }
+            if unbin(c)? {
+                *ret.last_mut().unwrap() |= 0x80 >> (bits % 8);
+            }
+            bits += 1;
         }
-        
-        let mut it = s.chars();
-        let mut ret = Vec::new();
-        loop {
-            if let Some(c0a) = it.next() {
-                let c0 = try!(unbin(c0a));
-                let c1 = try!(unbin(try!(it.next().ok_or(error::Error::InvalidBinLength))));
-                let c2 = try!(unbin(try!(it.next().ok_or(error::Error::InvalidBinLength))));
-                let c3 = try!(unbin(try!(it.next().ok_or(error::Error::InvalidBinLength))));
-                let c4 = try!(unbin(try!(it.next().ok_or(error::Error::InvalidBinLength))));
-                let c5 = try!(unbin(try!(it.next().ok_or(error::Error::InvalidBinLength))));
-                let c6 = try!(unbin(try!(it.next().ok_or(error::Error::InvalidBinLength))));
-                let c7 = try!(unbin(try!(it.next().ok_or(error::Error::InvalidBinLength))));
-                let b = (c0 << 7) | (c1 << 6) | (c2 << 5) | (c3 << 4) | (c4 << 3) | (c5 << 2) | (c6 << 1) | c7;
-                ret.push(b);
-            } else {
-                break;
+        Ok((ret, bits))
+    }
+
+    /// Convert a string of up to 64 binary digits to an integer, most
+    /// significant bit first.
+    pub fn decode_bits(s: &str) -> error::Result<u64> {
+        let mut ret = 0;
+        for (i, c) in s.chars().enumerate() {
+            if i == 64 {
+                return Err(error::Error::InvalidBinLength);
             }
+            ret = ret << 1 | unbin(c)? as u64;
         }
         Ok(ret)
     }
 
+    /// Convert the lowest `width` bits of `value` into a string in
+    /// binary notation, most significant bit first.
+    ///
+    /// # Panics
+    /// Panics if `width` is greater than 64.
+    pub fn encode_bits(value: u64, width: usize) -> String {
+        assert!(width <= 64, "width greater than 64");
+        (0..width).rev().map(|i| if (value >> i) & 1 == 0 { '0' } else { '1' }).collect()
+    }
+
     /// Convert a vector of bytes into a string in binary notation.
     /// Binary strings are big-endian, that means that for each byte,
     /// the most significant byte comes first in the string
     /// representation.
     pub fn encode(bytes: &[u8]) -> String {
-        let mut ret = String::new();
-        fn enc(b: u8) -> char {
-            if b == 0 {
-                '0'
-            } else {
-                '1'
-            }
-        }
-        for b in bytes {
-            ret.push(enc(b & 0x80));
-            ret.push(enc(b & 0x40));
-            ret.push(enc(b & 0x20));
-            ret.push(enc(b & 0x10));
-            ret.push(enc(b & 0x08));
-            ret.push(enc(b & 0x04));
-            ret.push(enc(b & 0x02));
-            ret.push(enc(b & 0x01));
+        let mut ret = String::with_capacity(bytes.len() * 8);
+        for &b in bytes {
+            ret.push_str(&encode_bits(b as u64, 8));
         }
         ret
     }
 
     #[cfg(test)]
     mod tests {
-        use super::{decode, encode};
+        use super::{decode, decode_partial, decode_bits, encode, encode_bits};
         
         #[test]
         fn decode_empty() {
@@ -514,5 +1550,183 @@ pub mod bin {
             let expected = "1111111110000010";
             assert_eq!(expected, encode(&bytes));
         }
+
+        #[test]
+        fn decode_partial_0() {
+            assert_eq!((vec![], 0), decode_partial("").unwrap());
+            assert_eq!((vec![0xa0], 3), decode_partial("101").unwrap());
+            assert_eq!((vec![0xff, 0x80], 9), decode_partial("111111111").unwrap());
+            assert!(decode_partial("1012").is_err());
+        }
+
+        #[test]
+        fn bits() {
+            assert_eq!("", encode_bits(5, 0));
+            assert_eq!("00101", encode_bits(5, 5));
+            assert_eq!("01", encode_bits(5, 2));
+            assert_eq!(5, decode_bits("00101").unwrap());
+            assert_eq!(0, decode_bits("").unwrap());
+            assert_eq!(!0, decode_bits(&encode_bits(!0, 64)).unwrap());
+            assert!(decode_bits(&encode_bits(0, 64)[..].repeat(2)).is_err());
+            assert!(decode_bits("0x").is_err());
+        }
+
+        quickcheck! {
+            fn prop_decode_encode(xs: Vec<u8>) -> bool {
+                decode(&encode(&xs)).unwrap() == xs
+            }
+
+            fn prop_decode_partial(xs: Vec<u8>, drop: u8) -> bool {
+                let s = encode(&xs);
+                let n = s.len().saturating_sub(drop as usize % 8);
+                let (bytes, bits) = decode_partial(&s[..n]).unwrap();
+                bits == n && bytes.len() == (n + 7) / 8 && encode(&bytes)[..n] == s[..n]
+            }
+
+            fn prop_decode_encode_bits(x: u64, width: u8) -> bool {
+                let width = width as usize % 65;
+                let mask = if width == 64 { !0 } else { (1 << width) - 1 };
+                decode_bits(&encode_bits(x, width)).unwrap() == x & mask
+            }
+        }
+    }
+}
+
+/// Number of bytes per hex dump line, which is the block size of AES.
+const HEXDUMP_WIDTH: usize = 16;
+
+/// Append the hex dump line for `chunk` at `offset` to `out`.  Lines
+/// that repeat an earlier full line are marked with its block number,
+/// which makes ECB mode stand out.
+fn hexdump_line(offset: usize, chunk: &[u8], seen: &mut HashMap<Vec<u8>, usize>, out: &mut String) {
+    out.push_str(&format!("{:08x} ", offset));
+    for i in 0..HEXDUMP_WIDTH {
+        if i % 8 == 0 {
+            out.push(' ');
+        }
+        match chunk.get(i) {
+            Some(b) => out.push_str(&format!("{:02x} ", b)),
+            None => out.push_str("   "),
+        }
+    }
+    out.push_str(" |");
+    for &b in chunk {
+        out.push(if b >= 0x20 && b < 0x7f { b as char } else { '.' });
+    }
+    out.push('|');
+    if chunk.len() == HEXDUMP_WIDTH {
+        let block = offset / HEXDUMP_WIDTH;
+        match seen.get(chunk) {
+            Some(j) => out.push_str(&format!(" = block {}", j)),
+            None => (),
+        }
+        seen.entry(chunk.to_vec()).or_insert(block);
+    }
+    out.push('\n');
+}
+
+/// Format `bytes` as a classic hex dump, with the offset, 16 bytes in
+/// hex and the same bytes as ASCII on each line.  Each line is one
+/// 16-byte block, and blocks that are equal to an earlier block are
+/// marked with the number of that block.
+///
+/// # Example
+/// ```
+/// use cryptopals::codec::hexdump;
+/// assert_eq!("00000000  68 69 20 6d 6f 6d 0a                              |hi mom.|\n",
+///            hexdump(b"hi mom\n"));
+/// ```
+pub fn hexdump(bytes: &[u8]) -> String {
+    let mut seen = HashMap::new();
+    let mut ret = String::new();
+    for (i, chunk) in bytes.chunks(HEXDUMP_WIDTH).enumerate() {
+        hexdump_line(i * HEXDUMP_WIDTH, chunk, &mut seen, &mut ret);
+    }
+    ret
+}
+
+/// Writer that writes a hex dump of everything written to it to the
+/// underlying writer, in the format of `hexdump`.  An incomplete last
+/// line is held back until `finish` is called.
+pub struct HexdumpWriter<W: Write> {
+    inner: W,
+    offset: usize,
+    line: Vec<u8>,
+    seen: HashMap<Vec<u8>, usize>,
+}
+
+impl<W: Write> HexdumpWriter<W> {
+    /// Create a writer dumping to `inner`.
+    pub fn new(inner: W) -> HexdumpWriter<W> {
+        HexdumpWriter {
+            inner: inner,
+            offset: 0,
+            line: Vec::with_capacity(HEXDUMP_WIDTH),
+            seen: HashMap::new(),
+        }
+    }
+
+    fn write_line(&mut self) -> io::Result<()> {
+        let mut out = String::new();
+        hexdump_line(self.offset, &self.line, &mut self.seen, &mut out);
+        self.offset += self.line.len();
+        self.line.clear();
+        self.inner.write_all(out.as_bytes())
+    }
+
+    /// Write the incomplete last line, if any, flush, and return the
+    /// underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        if !self.line.is_empty() {
+            self.write_line()?;
+        }
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for HexdumpWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for &b in buf {
+            self.line.push(b);
+            if self.line.len() == HEXDUMP_WIDTH {
+                self.write_line()?;
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{hexdump, HexdumpWriter};
+    use std::io::Write;
+
+    #[test]
+    fn hexdump_0() {
+        let mut bytes = b"YELLOW SUBMARINE".to_vec();
+        bytes.extend(b"\x00\x01\x02\x03\x04\x05\x06\x07\x08\x09\x0a\x0b\x0c\x0d\x0e\xff");
+        bytes.extend(b"YELLOW SUBMARINE!");
+        let expected =
+            "00000000  59 45 4c 4c 4f 57 20 53  55 42 4d 41 52 49 4e 45  |YELLOW SUBMARINE|\n\
+             00000010  00 01 02 03 04 05 06 07  08 09 0a 0b 0c 0d 0e ff  |................|\n\
+             00000020  59 45 4c 4c 4f 57 20 53  55 42 4d 41 52 49 4e 45  |YELLOW SUBMARINE| = block 0\n\
+             00000030  21                                                |!|\n";
+        assert_eq!(expected, hexdump(&bytes));
+        assert_eq!("", hexdump(&[]));
+    }
+
+    quickcheck! {
+        fn prop_writer(xs: Vec<u8>, split: usize) -> bool {
+            let split = if xs.is_empty() { 0 } else { split % xs.len() };
+            let mut w = HexdumpWriter::new(Vec::new());
+            w.write_all(&xs[..split]).unwrap();
+            w.write_all(&xs[split..]).unwrap();
+            String::from_utf8(w.finish().unwrap()).unwrap() == hexdump(&xs)
+        }
     }
 }
@@ -0,0 +1,170 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! Data compression, as needed for the compression side channel of
+//! challenge 51.  The `Compressor` trait abstracts over the actual
+//! algorithm, so that a real DEFLATE implementation can be plugged
+//! in.  The `Lzss` compressor provided here is a simple byte-oriented
+//! LZ77 variant, which makes the length of the output depend on the
+//! input in an easily predictable way.  Do not use it for anything
+//! where compression ratio matters!
+
+use crate::error::Error;
+
+/// Common interface of compression algorithms.
+pub trait Compressor {
+    /// Compress `data`.
+    fn compress(&self, data: &[u8]) -> Vec<u8>;
+
+    /// Decompress `data`, which must have been produced by
+    /// `compress`.
+    fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, Error>;
+}
+
+/// Shortest back reference that is emitted.
+const MIN_MATCH: usize = 3;
+/// Longest back reference that can be encoded.
+const MAX_MATCH: usize = MIN_MATCH + 255;
+/// Size of the window searched for back references.
+const WINDOW_SIZE: usize = 65535;
+
+/// LZSS compression.  The output consists of groups of up to eight
+/// items, each preceded by a flag byte.  Bit `i` of the flag byte
+/// tells whether item `i` is a literal byte (0) or a back reference
+/// (1).  A back reference consists of a 16-bit big-endian distance
+/// and a length byte, which is the match length minus 3.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Lzss;
+
+/// Find the longest match for the data at position `pos` in the
+/// window before it.  Returns distance and length of the match.
+fn longest_match(data: &[u8], pos: usize) -> (usize, usize) {
+    let start = if pos > WINDOW_SIZE { pos - WINDOW_SIZE } else { 0 };
+    let max_len = ::std::cmp::min(MAX_MATCH, data.len() - pos);
+    let mut best = (0, 0);
+    for cand in (start..pos).rev() {
+        let mut len = 0;
+        while len < max_len && data[cand + len] == data[pos + len] {
+            len += 1;
+        }
+        if len > best.1 {
+            best = (pos - cand, len);
+            if len == max_len {
+                break;
+            }
+        }
+    }
+    best
+}
+
+impl Compressor for Lzss {
+    fn compress(&self, data: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut flag_pos = 0;
+        let mut items = 8;
+        let mut pos = 0;
+        while pos < data.len() {
+            if items == 8 {
+                flag_pos = out.len();
+                out.push(0);
+                items = 0;
+            }
+            let (dist, len) = longest_match(data, pos);
+            if len >= MIN_MATCH {
+                out[flag_pos] |= 1 << items;
+                out.push((dist >> 8) as u8);
+                out.push(dist as u8);
+                out.push((len - MIN_MATCH) as u8);
+                pos += len;
+            } else {
+                out.push(data[pos]);
+                pos += 1;
+            }
+            items += 1;
+        }
+        out
+    }
+
+    fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, Error> {
+        let mut out = Vec::new();
+        let mut pos = 0;
+        while pos < data.len() {
+            let flags = data[pos];
+            pos += 1;
+            for i in 0..8 {
+                if pos >= data.len() {
+                    break;
+                }
+                if flags & (1 << i) == 0 {
+                    out.push(data[pos]);
+                    pos += 1;
+                } else {
+                    if pos + 3 > data.len() {
+                        return Err(Error::InvalidCompressedData);
+                    }
+                    let dist = (data[pos] as usize) << 8 | data[pos + 1] as usize;
+                    let len = data[pos + 2] as usize + MIN_MATCH;
+                    if dist == 0 || dist > out.len() {
+                        return Err(Error::InvalidCompressedData);
+                    }
+                    for _ in 0..len {
+                        let b = out[out.len() - dist];
+                        out.push(b);
+                    }
+                    pos += 3;
+                }
+            }
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Compressor, Lzss};
+
+    #[test]
+    fn compress_empty() {
+        assert_eq!(Vec::<u8>::new(), Lzss.compress(b""));
+        assert_eq!(Vec::<u8>::new(), Lzss.decompress(b"").unwrap());
+    }
+
+    #[test]
+    fn compress_literals() {
+        assert_eq!(b"\x00abc".to_vec(), Lzss.compress(b"abc"));
+    }
+
+    #[test]
+    fn compress_repeated() {
+        let data = b"abcabcabcabcabcabc";
+        let compressed = Lzss.compress(data);
+        assert_eq!(b"\x08abc\x00\x03\x0c".to_vec(), compressed);
+        assert_eq!(data.to_vec(), Lzss.decompress(&compressed).unwrap());
+    }
+
+    #[test]
+    fn compress_long_match() {
+        let data = vec![0x41; 1000];
+        let compressed = Lzss.compress(&data);
+        assert!(compressed.len() < 20);
+        assert_eq!(data, Lzss.decompress(&compressed).unwrap());
+    }
+
+    #[test]
+    fn decompress_invalid() {
+        assert!(Lzss.decompress(b"\x01\x00\x01\x00").is_err());
+        assert!(Lzss.decompress(b"\x02a\x00\x02\x00").is_err());
+        assert!(Lzss.decompress(b"\x02a\x00").is_err());
+    }
+
+    quickcheck! {
+        fn prop_roundtrip(xs: Vec<u8>) -> bool {
+            Lzss.decompress(&Lzss.compress(&xs)).unwrap() == xs
+        }
+
+        fn prop_roundtrip_repetitive(xs: Vec<bool>) -> bool {
+            let data: Vec<u8> = xs.iter().map(|&b| if b { b'a' } else { b'b' }).collect();
+            Lzss.decompress(&Lzss.compress(&data)).unwrap() == data
+        }
+    }
+}
@@ -0,0 +1,156 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! Loaders for the challenge data files, which come as one BASE64
+//! blob (like `data/7.txt`) or as one encoded item per line (like
+//! `data/4.txt`).  Read and decoding errors are returned, not
+//! unwrapped, so callers can report which file was broken.
+//!
+//! With the `bundled-data` feature, the files are also compiled into
+//! the library and available through `challenge`, independent of the
+//! working directory.
+
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+
+use crate::codec::{base64, hex};
+use crate::error::Error;
+
+/// Read the file at `path` and decode its contents as BASE64,
+/// ignoring line breaks.
+pub fn load_base64_file<P: AsRef<Path>>(path: P) -> Result<Vec<u8>, Error> {
+    let f = File::open(path)?;
+    let mut result = Vec::new();
+    base64::Reader::new(f).read_to_end(&mut result)?;
+    Ok(result)
+}
+
+/// Read the file at `path` and decode each non-empty line as hex.
+pub fn load_hex_lines<P: AsRef<Path>>(path: P) -> Result<Vec<Vec<u8>>, Error> {
+    let mut result = Vec::new();
+    for line in load_lines(path)? {
+        result.push(hex::decode(&line)?);
+    }
+    Ok(result)
+}
+
+/// Read the file at `path` and return its non-empty lines, with
+/// surrounding whitespace removed.
+pub fn load_lines<P: AsRef<Path>>(path: P) -> Result<Vec<String>, Error> {
+    let f = File::open(path)?;
+    let mut result = Vec::new();
+    for line in BufReader::new(f).lines() {
+        let line = line?;
+        let line = line.trim();
+        if !line.is_empty() {
+            result.push(line.to_string());
+        }
+    }
+    Ok(result)
+}
+
+/// Read the raw contents of every regular file in the directory at
+/// `path`, for example a set of intercepted messages.  Returns
+/// `(path, contents)` pairs sorted by path; subdirectories are
+/// skipped.
+pub fn load_dir<P: AsRef<Path>>(path: P) -> Result<Vec<(PathBuf, Vec<u8>)>, Error> {
+    let mut result = Vec::new();
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        if entry.file_type()?.is_file() {
+            let mut contents = Vec::new();
+            File::open(entry.path())?.read_to_end(&mut contents)?;
+            result.push((entry.path(), contents));
+        }
+    }
+    result.sort();
+    Ok(result)
+}
+
+/// Decode each non-empty line of `s` as hex, like `load_hex_lines`.
+pub fn parse_hex_lines(s: &str) -> Result<Vec<Vec<u8>>, Error> {
+    let mut result = Vec::new();
+    for line in s.lines().map(|l| l.trim()).filter(|l| !l.is_empty()) {
+        result.push(hex::decode(line)?);
+    }
+    Ok(result)
+}
+
+/// Return the contents of the data file for challenge `number` in
+/// set `set`, or `None` if the challenge has no data file or is not
+/// in that set.  Decode them with `codec::base64::decode` or
+/// `parse_hex_lines`.
+#[cfg(feature = "bundled-data")]
+pub fn challenge(set: usize, number: usize) -> Option<&'static str> {
+    if number == 0 || (number - 1) / 8 + 1 != set {
+        return None;
+    }
+    match number {
+        4 => Some(include_str!("../data/4.txt")),
+        6 => Some(include_str!("../data/6.txt")),
+        7 => Some(include_str!("../data/7.txt")),
+        8 => Some(include_str!("../data/8.txt")),
+        10 => Some(include_str!("../data/10.txt")),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{load_base64_file, load_dir, load_hex_lines, load_lines, parse_hex_lines};
+    use crate::error::Error;
+
+    #[test]
+    fn base64_file() {
+        let c = load_base64_file("data/7.txt").unwrap();
+        assert_eq!(2880, c.len());
+    }
+
+    #[test]
+    fn hex_lines() {
+        let lines = load_hex_lines("data/4.txt").unwrap();
+        assert_eq!(327, lines.len());
+        assert_eq!(30, lines[0].len());
+    }
+
+    #[test]
+    fn lines() {
+        assert_eq!(vec!["YELLOW SUBMARINE".to_string()], load_lines("data/input00.txt").unwrap());
+    }
+
+    #[test]
+    fn parse_hex() {
+        assert_eq!(vec![vec![0x01, 0xab], vec![0xff]], parse_hex_lines("01ab\n\n ff \n").unwrap());
+        assert!(parse_hex_lines("0g").is_err());
+    }
+
+    #[test]
+    fn dir() {
+        let files = load_dir("data").unwrap();
+        let input = files.iter().find(|f| f.0.ends_with("input00.txt")).unwrap();
+        assert_eq!(b"YELLOW SUBMARINE".to_vec(), input.1);
+        assert!(files.windows(2).all(|w| w[0].0 < w[1].0));
+        assert!(load_dir("data/does-not-exist").is_err());
+    }
+
+    #[cfg(feature = "bundled-data")]
+    #[test]
+    fn bundled() {
+        use super::challenge;
+        use crate::codec::base64;
+
+        assert_eq!(load_hex_lines("data/4.txt").unwrap(), parse_hex_lines(challenge(1, 4).unwrap()).unwrap());
+        assert_eq!(load_base64_file("data/10.txt").unwrap(), base64::decode(challenge(2, 10).unwrap()).unwrap());
+        assert_eq!(None, challenge(2, 4));
+        assert_eq!(None, challenge(1, 5));
+    }
+
+    #[test]
+    fn missing_file() {
+        match load_lines("data/does-not-exist.txt") {
+            Err(Error::Io(_)) => {}
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+}
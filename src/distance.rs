@@ -1,49 +1,415 @@
 // Copyright 2016 Martin Grabmueller. See the LICENSE file at the
 // top-level directory of this distribution for license information.
 
-//! Bit distance metrics for byte strings.
+//! Bit and edit distance metrics for byte strings, and statistics
+//! comparing byte strings with expected distributions.
+
+use byteorder::{ByteOrder, NativeEndian};
+
+use crate::error::{Error, Result};
 
 /// Calculate the number of different bits between two byte vectors.
+/// Returns `Error::LengthMismatch` when the two arguments have
+/// different lengths; see `hamming_prefix` for comparing only the
+/// common prefix.
 ///
 /// # Example
 /// ```
 /// use cryptopals::distance::hamming;
-/// assert_eq!(2, hamming(&[0x00], &[0x81]));
+/// assert_eq!(2, hamming(&[0x00], &[0x81]).unwrap());
 /// ```
+pub fn hamming(b1: &[u8], b2: &[u8]) -> Result<usize> {
+    Error::check_lengths(b1.len(), b2.len())?;
+    Ok(hamming_unchecked(b1, b2))
+}
+
+/// Calculate the number of different bits in the common prefix of
+/// `b1` and `b2`.
+fn hamming_unchecked(b1: &[u8], b2: &[u8]) -> usize {
+    // Count eight bytes at a time, then the rest.
+    let len = ::std::cmp::min(b1.len(), b2.len());
+    let words = len / 8 * 8;
+    let mut cnt = 0;
+    for (a, b) in b1[..words].chunks(8).zip(b2[..words].chunks(8)) {
+        cnt += (NativeEndian::read_u64(a) ^ NativeEndian::read_u64(b)).count_ones() as usize;
+    }
+    for (a, b) in b1[words..len].iter().zip(b2[words..len].iter()) {
+        cnt += (a ^ b).count_ones() as usize;
+    }
+    cnt
+}
+
+/// Calculate the number of different bits between the common prefix
+/// of two byte vectors, that is, the first `min(b1.len(), b2.len())`
+/// bytes of each.
+///
+/// # Example
+/// ```
+/// use cryptopals::distance::hamming_prefix;
+/// assert_eq!(2, hamming_prefix(&[0x00, 0xff], &[0x81]));
+/// ```
+pub fn hamming_prefix(b1: &[u8], b2: &[u8]) -> usize {
+    hamming_unchecked(b1, b2)
+}
+
+/// Calculate the Hamming distance of the common prefix of two byte
+/// vectors in bits per byte, a number between 0 and 8.  Returns 0 if
+/// either vector is empty.
+///
+/// # Example
+/// ```
+/// use cryptopals::distance::normalized_hamming;
+/// assert_eq!(1.0, normalized_hamming(&[0x00, 0x81], &[0x00, 0x00]));
+/// ```
+pub fn normalized_hamming(b1: &[u8], b2: &[u8]) -> f64 {
+    let len = ::std::cmp::min(b1.len(), b2.len());
+    if len == 0 {
+        return 0.0;
+    }
+    hamming_prefix(b1, b2) as f64 / len as f64
+}
+
+/// Calculate the index of coincidence of `bytes`, the probability
+/// that two bytes at different, randomly chosen positions are equal.
+/// Returns 0 for fewer than two bytes.
+///
+/// English letters have an index of coincidence of about 0.066, and
+/// uniformly random letters of 1/26, about 0.038.  Substitution does
+/// not change it, so it is useful for telling whether a ciphertext
+/// was encrypted with a single substitution.
+///
+/// # Example
+/// ```
+/// use cryptopals::distance::index_of_coincidence;
+/// assert_eq!(1.0 / 3.0, index_of_coincidence(b"aabb"));
+/// ```
+pub fn index_of_coincidence(bytes: &[u8]) -> f64 {
+    let n = bytes.len();
+    if n < 2 {
+        return 0.0;
+    }
+    let mut counts = [0usize; 256];
+    for &b in bytes {
+        counts[b as usize] += 1;
+    }
+    let pairs: usize = counts.iter().map(|&c| c * c.saturating_sub(1)).sum();
+    pairs as f64 / (n * (n - 1)) as f64
+}
+
+/// Calculate Pearson's chi-squared statistic of `observed_counts`
+/// against the distribution `expected_freqs`, which is scaled to the
+/// total of the counts, so it need not sum to 1.  Categories with an
+/// expected frequency of zero are skipped.  Lower values mean a
+/// better fit.
 ///
 /// # Panics
-/// Panics when the two arguments have different lengths.
-pub fn hamming(b1: &[u8], b2: &[u8]) -> usize {
-    assert_eq!(b1.len(), b2.len());
-    fn bitcnt(b: u8) -> usize {
-        let mut mask = 0x80;
-        let mut cnt = 0;
-        while mask != 0 {
-            if mask & b != 0 {
-                cnt += 1;
+/// Panics if the two slices have different lengths.
+///
+/// # Example
+/// ```
+/// use cryptopals::distance::chi_squared;
+/// assert_eq!(0.0, chi_squared(&[10, 30], &[1.0, 3.0]));
+/// assert_eq!(5.0, chi_squared(&[15, 5], &[0.5, 0.5]));
+/// ```
+pub fn chi_squared(observed_counts: &[usize], expected_freqs: &[f64]) -> f64 {
+    assert_eq!(observed_counts.len(), expected_freqs.len());
+    let total: usize = observed_counts.iter().sum();
+    let freq_total: f64 = expected_freqs.iter().sum();
+    observed_counts.iter()
+        .zip(expected_freqs.iter())
+        .filter(|&(_, &f)| f > 0.0)
+        .map(|(&c, &f)| {
+            let expected = total as f64 * f / freq_total;
+            (c as f64 - expected) * (c as f64 - expected) / expected
+        })
+        .sum()
+}
+
+/// Calculate the Shannon entropy of the byte distribution of `bytes`,
+/// in bits per byte.  It is 8 for uniformly distributed bytes, about
+/// 4.1 for English text, and 0 for a single repeated byte or the
+/// empty input.
+///
+/// # Example
+/// ```
+/// use cryptopals::distance::entropy;
+/// assert_eq!(1.0, entropy(b"abab"));
+/// ```
+pub fn entropy(bytes: &[u8]) -> f64 {
+    byte_counts(bytes).iter()
+        .filter(|&&c| c > 0)
+        .map(|&c| {
+            let p = c as f64 / bytes.len() as f64;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+/// Absolute z-score above which `looks_random` rejects its input.
+/// For random data, each test exceeds it with a probability of less
+/// than 1 in 10000.
+pub const RANDOMNESS_Z: f64 = 4.0;
+
+/// Perform the Wald-Wolfowitz runs test on the bits of `bytes`, most
+/// significant bit first, and return the z-score of the number of
+/// runs of equal bits.  It is about normally distributed for random
+/// data; negative values mean too few runs (long stretches of equal
+/// bits), positive ones too many (bits alternating too regularly).
+/// Inputs with all bits equal give negative infinity, and the empty
+/// input zero.
+pub fn runs_test(bytes: &[u8]) -> f64 {
+    if bytes.is_empty() {
+        return 0.0;
+    }
+    let n = 8 * bytes.len();
+    let ones: usize = bytes.iter().map(|b| b.count_ones() as usize).sum();
+    let zeros = n - ones;
+    if ones == 0 || zeros == 0 {
+        return f64::NEG_INFINITY;
+    }
+    let mut runs = 0;
+    let mut prev = None;
+    for &b in bytes {
+        for i in (0..8).rev() {
+            let bit = (b >> i) & 1;
+            if prev != Some(bit) {
+                runs += 1;
+                prev = Some(bit);
             }
-            mask = mask >> 1;
         }
-        cnt
     }
-    b1.iter().zip(b2).map(|(a, b)| bitcnt(a ^ b)).fold(0, |a, b| a + b)
+    let (n, ones, zeros) = (n as f64, ones as f64, zeros as f64);
+    let mean = 2.0 * ones * zeros / n + 1.0;
+    let variance = (mean - 1.0) * (mean - 2.0) / (n - 1.0);
+    if variance <= 0.0 {
+        return 0.0;
+    }
+    (runs as f64 - mean) / variance.sqrt()
+}
+
+/// Perform a chi-squared test of `bytes` against the uniform
+/// distribution over all 256 byte values, and return the statistic
+/// converted to an approximate z-score with the Wilson-Hilferty
+/// transformation.  Large values mean that some bytes are much more
+/// frequent than others.  The approximation needs at least five
+/// expected occurrences per byte value, so at least 1280 bytes.
+pub fn uniformity_test(bytes: &[u8]) -> f64 {
+    let k = 255.0;
+    let x = chi_squared(&byte_counts(bytes), &[1.0; 256]);
+    ((x / k).powf(1.0 / 3.0) - (1.0 - 2.0 / (9.0 * k))) / (2.0 / (9.0 * k)).sqrt()
+}
+
+/// Return the number of occurrences of each byte value in `bytes`.
+fn byte_counts(bytes: &[u8]) -> Vec<usize> {
+    let mut counts = vec![0; 256];
+    for &b in bytes {
+        counts[b as usize] += 1;
+    }
+    counts
+}
+
+/// Return true if `bytes` passes both `runs_test` and
+/// `uniformity_test` with an absolute z-score below `RANDOMNESS_Z`.
+/// This is a sanity check for PRNG outputs and keystreams, and tells
+/// ECB ciphertexts of repetitive plaintexts from CBC ones, but it is
+/// far from a proof of randomness.
+pub fn looks_random(bytes: &[u8]) -> bool {
+    runs_test(bytes).abs() < RANDOMNESS_Z && uniformity_test(bytes).abs() < RANDOMNESS_Z
+}
+
+/// Calculate the Levenshtein distance between two byte vectors, the
+/// smallest number of inserted, deleted or replaced bytes that turns
+/// one into the other.
+///
+/// # Example
+/// ```
+/// use cryptopals::distance::levenshtein;
+/// assert_eq!(3, levenshtein(b"kitten", b"sitting"));
+/// ```
+pub fn levenshtein(b1: &[u8], b2: &[u8]) -> usize {
+    // prev[j] is the distance between the prefixes of length i - 1
+    // and j, cur[j] between those of length i and j.
+    let mut prev: Vec<usize> = (0..b2.len() + 1).collect();
+    let mut cur = vec![0; b2.len() + 1];
+    for (i, &x) in b1.iter().enumerate() {
+        cur[0] = i + 1;
+        for (j, &y) in b2.iter().enumerate() {
+            let replace = prev[j] + if x == y { 0 } else { 1 };
+            cur[j + 1] = ::std::cmp::min(replace, ::std::cmp::min(prev[j + 1], cur[j]) + 1);
+        }
+        ::std::mem::swap(&mut prev, &mut cur);
+    }
+    prev[b2.len()]
+}
+
+/// Find the longest byte string that occurs in both `b1` and `b2`.
+/// Returns its start in `b1`, its start in `b2` and its length; the
+/// first occurrence in `b1` is chosen if there are several.
+///
+/// # Example
+/// ```
+/// use cryptopals::distance::longest_common_substring;
+/// assert_eq!((4, 0, 5), longest_common_substring(b"the bacon", b"bacon and eggs"));
+/// ```
+pub fn longest_common_substring(b1: &[u8], b2: &[u8]) -> (usize, usize, usize) {
+    // prev[j] is the length of the common suffix of the prefixes of
+    // length i and j.
+    let mut prev = vec![0; b2.len() + 1];
+    let mut cur = vec![0; b2.len() + 1];
+    let mut best = (0, 0, 0);
+    for (i, &x) in b1.iter().enumerate() {
+        for (j, &y) in b2.iter().enumerate() {
+            cur[j + 1] = if x == y { prev[j] + 1 } else { 0 };
+            if cur[j + 1] > best.2 {
+                best = (i + 1 - cur[j + 1], j + 1 - cur[j + 1], cur[j + 1]);
+            }
+        }
+        ::std::mem::swap(&mut prev, &mut cur);
+    }
+    best
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{hamming};
+    use super::{hamming, hamming_prefix, normalized_hamming, index_of_coincidence, chi_squared};
+    use super::{levenshtein, longest_common_substring};
+    use super::{entropy, runs_test, uniformity_test, looks_random};
+    use crate::cipher::aes::{self, AesKey};
     
     #[test]
     fn hamming_1() {
         let input1 = vec![0x00];
         let input2 = vec![0x81];
-        assert_eq!(2, hamming(&input1, &input2));
+        assert_eq!(2, hamming(&input1, &input2).unwrap());
     }
 
     #[test]
     fn hamming_2() {
         let input1 = b"this is a test";
         let input2 = b"wokka wokka!!!";
-        assert_eq!(37, hamming(input1, input2));
+        assert_eq!(37, hamming(input1, input2).unwrap());
+        assert!(hamming(input1, b"wokka").is_err());
+    }
+
+    #[test]
+    fn hamming_prefix_0() {
+        assert_eq!(37, hamming_prefix(b"this is a test and more", b"wokka wokka!!!"));
+        assert_eq!(0, hamming_prefix(b"", b"wokka wokka!!!"));
+    }
+
+    #[test]
+    fn normalized_hamming_0() {
+        assert_eq!(37.0 / 14.0, normalized_hamming(b"this is a test", b"wokka wokka!!!"));
+        assert_eq!(0.0, normalized_hamming(b"", b""));
+        assert_eq!(8.0, normalized_hamming(&[0xff], &[0x00, 0x12]));
+    }
+
+    #[test]
+    fn index_of_coincidence_0() {
+        assert_eq!(0.0, index_of_coincidence(b""));
+        assert_eq!(0.0, index_of_coincidence(b"a"));
+        assert_eq!(1.0, index_of_coincidence(b"aaaa"));
+        assert_eq!(0.0, index_of_coincidence(b"abcd"));
+        let english = b"ITWASTHEBESTOFTIMESITWASTHEWORSTOFTIMESITWASTHEAGEOFWISDOM\
+                        ITWASTHEAGEOFFOOLISHNESSITWASTHEEPOCHOFBELIEF";
+        let ioc = index_of_coincidence(english);
+        assert!(ioc > 0.06 && ioc < 0.09, "{}", ioc);
+    }
+
+    #[test]
+    fn chi_squared_0() {
+        assert_eq!(0.0, chi_squared(&[], &[]));
+        assert_eq!(0.0, chi_squared(&[2, 4, 0], &[0.25, 0.5, 0.0]));
+        // (15 - 10)^2 / 10 + (5 - 10)^2 / 10
+        assert_eq!(5.0, chi_squared(&[15, 5], &[1.0, 1.0]));
+    }
+
+    #[test]
+    #[should_panic]
+    fn chi_squared_panic() {
+        chi_squared(&[1, 2], &[1.0]);
+    }
+
+    #[test]
+    fn entropy_0() {
+        assert_eq!(0.0, entropy(b""));
+        assert_eq!(0.0, entropy(b"aaaa"));
+        assert_eq!(2.0, entropy(b"abcd"));
+        assert_eq!(8.0, entropy(&(0..=255).collect::<Vec<u8>>()));
+    }
+
+    #[test]
+    fn runs_test_0() {
+        assert_eq!(0.0, runs_test(b""));
+        assert_eq!(f64::NEG_INFINITY, runs_test(&[0; 10]));
+        assert!(runs_test(&[0x55; 100]) > 20.0);
+        assert!(runs_test(&[0x0f; 100]) < -10.0);
+    }
+
+    #[test]
+    fn randomness() {
+        let key = AesKey::from([0x5c; 16]);
+        let keystream = aes::encrypt_ctr(&key, &[0; 16], &[0; 4096]);
+        assert!(looks_random(&keystream));
+        assert!(entropy(&keystream) > 7.9);
+
+        let text = b"It was the best of times, it was the worst of times. ".repeat(80);
+        assert!(!looks_random(&text));
+        assert!(uniformity_test(&text) > 100.0);
+
+        let plaintext = b"It was the best of times, it was".repeat(50);
+        let ecb = aes::encrypt_ecb(&key, &plaintext);
+        let cbc = aes::encrypt_cbc(&key, &[0; 16], &plaintext);
+        assert!(!looks_random(&ecb));
+        assert!(looks_random(&cbc));
+    }
+
+    #[test]
+    fn levenshtein_0() {
+        assert_eq!(0, levenshtein(b"", b""));
+        assert_eq!(5, levenshtein(b"", b"bacon"));
+        assert_eq!(5, levenshtein(b"bacon", b""));
+        assert_eq!(0, levenshtein(b"bacon", b"bacon"));
+        assert_eq!(3, levenshtein(b"kitten", b"sitting"));
+        assert_eq!(2, levenshtein(b"Cooking MC's", b"Cooking MCs!"));
+    }
+
+    #[test]
+    fn longest_common_substring_0() {
+        assert_eq!((0, 0, 0), longest_common_substring(b"", b"bacon"));
+        assert_eq!((0, 0, 0), longest_common_substring(b"abc", b"xyz"));
+        assert_eq!((1, 3, 4), longest_common_substring(b"xbaco", b"thebacon"));
+        assert_eq!((0, 1, 1), longest_common_substring(b"ab", b"ba"));
+    }
+
+    quickcheck! {
+        fn prop_levenshtein(b1: Vec<u8>, b2: Vec<u8>) -> bool {
+            let d = levenshtein(&b1, &b2);
+            let len_diff = if b1.len() > b2.len() { b1.len() - b2.len() } else { b2.len() - b1.len() };
+            d == levenshtein(&b2, &b1) && d >= len_diff && d <= ::std::cmp::max(b1.len(), b2.len()) &&
+                (d == 0) == (b1 == b2)
+        }
+
+        fn prop_longest_common_substring(b1: Vec<u8>, b2: Vec<u8>) -> bool {
+            let (i, j, len) = longest_common_substring(&b1, &b2);
+            len == longest_common_substring(&b2, &b1).2 && b1[i..i + len] == b2[j..j + len]
+        }
+
+        fn prop_index_of_coincidence(xs: Vec<u8>) -> bool {
+            let ioc = index_of_coincidence(&xs);
+            ioc >= 0.0 && ioc <= 1.0
+        }
+
+        fn prop_hamming(xs: Vec<(u8, u8)>) -> bool {
+            let b1: Vec<u8> = xs.iter().map(|p| p.0).collect();
+            let b2: Vec<u8> = xs.iter().map(|p| p.1).collect();
+            let bits: usize = xs.iter().map(|&(a, b)| (0..8).filter(|i| (a ^ b) & (1 << i) != 0).count()).sum();
+            hamming(&b1, &b2).unwrap() == bits
+        }
+
+        fn prop_hamming_prefix(b1: Vec<u8>, b2: Vec<u8>) -> bool {
+            let d = hamming_prefix(&b1, &b2);
+            d == hamming_prefix(&b2, &b1) && d <= 8 * ::std::cmp::min(b1.len(), b2.len())
+        }
     }
 }
@@ -0,0 +1,218 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! The Digital Signature Algorithm, with the parameters from
+//! challenge 43.  Messages are hashed with SHA-1.  The nonce `k` can
+//! be supplied by the caller, which is what the attacks on DSA
+//! exploit.  Do not use this for production!
+
+use crate::bignum::BigUint;
+use crate::hash::Digest;
+use crate::hash::sha1::Sha1;
+
+/// Domain parameters.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Params {
+    /// Prime modulus.
+    pub p: BigUint,
+    /// Prime order of the subgroup generated by `g`.
+    pub q: BigUint,
+    /// Generator of the subgroup of order `q`.
+    pub g: BigUint,
+}
+
+impl Params {
+    /// The parameters given in challenge 43.
+    pub fn challenge43() -> Params {
+        Params {
+            p: BigUint::from_hex(
+                "800000000000000089e1855218a0e7dac38136ffafa72eda7\
+                 859f2171e25e65eac698c1702578b07dc2a1076da241c76c6\
+                 2d374d8389ea5aeffd3226a0530cc565f3bf6b50929139ebe\
+                 ac04f48c3c84afb796d61e5a4f9a8fda812ab59494232c7d2\
+                 b4deb50aa18ee9e132bfa85ac4374d7f9091abc3d015efc87\
+                 1a584471bb1").unwrap(),
+            q: BigUint::from_hex("f4f47f05794b256174bba6e9b396a7707e563c5b").unwrap(),
+            g: BigUint::from_hex(
+                "5958c9d3898b224b12672c0b98e06c60df923cb8bc999d119\
+                 458fef538b8fa4046c8db53039db620c094c9fa077ef389b5\
+                 322a559946a71903f990f1f7e0e025e2d7f7cf494aff1a047\
+                 0f5b64c36b625a097f1651fe775323556fe00b3608c887892\
+                 878480e99041be601a62166ca6894bdd41a7054ec89f756ba\
+                 9fc95302291").unwrap(),
+        }
+    }
+}
+
+/// Public DSA key.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PublicKey {
+    /// Domain parameters.
+    pub params: Params,
+    /// Public value `y = g^x mod p`.
+    pub y: BigUint,
+}
+
+/// Private DSA key.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PrivateKey {
+    /// Domain parameters.
+    pub params: Params,
+    /// Secret exponent.
+    pub x: BigUint,
+}
+
+impl PrivateKey {
+    /// Derive the public key belonging to this private key.
+    pub fn public_key(&self) -> PublicKey {
+        let p = &self.params;
+        PublicKey {
+            params: p.clone(),
+            y: p.g.modpow(&self.x, &p.p),
+        }
+    }
+}
+
+/// DSA signature.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Signature {
+    /// First signature component, `(g^k mod p) mod q`.
+    pub r: BigUint,
+    /// Second signature component, `k^-1 (H(m) + x*r) mod q`.
+    pub s: BigUint,
+}
+
+/// Hash `message` with SHA-1 and interpret the result as a number.
+pub fn hash_message(message: &[u8]) -> BigUint {
+    BigUint::from_bytes_be(&Sha1::digest(message))
+}
+
+/// Generate a key pair for the given domain parameters.
+pub fn generate_keypair(params: &Params) -> (PublicKey, PrivateKey) {
+    let x = &BigUint::random_below(&(&params.q - &BigUint::one())) + &BigUint::one();
+    let private = PrivateKey {
+        params: params.clone(),
+        x: x,
+    };
+    (private.public_key(), private)
+}
+
+/// Sign `message` using the nonce `k`.  Returns `None` if `k` is not
+/// in the range `1..q`, or if it leads to a zero signature component,
+/// in which case another nonce must be chosen.
+///
+/// The nonce must be secret and must never be reused: anyone who
+/// knows `k`, or sees two signatures made with the same `k`, can
+/// compute the private key.
+pub fn sign_with_k(key: &PrivateKey, message: &[u8], k: &BigUint) -> Option<Signature> {
+    let p = &key.params;
+    if k.is_zero() || *k >= p.q {
+        return None;
+    }
+    let r = &p.g.modpow(k, &p.p) % &p.q;
+    if r.is_zero() {
+        return None;
+    }
+    let kinv = match k.modinv(&p.q) {
+        Some(kinv) => kinv,
+        None => return None,
+    };
+    let h = hash_message(message);
+    let s = &(&kinv * &(&h + &(&key.x * &r))) % &p.q;
+    if s.is_zero() {
+        return None;
+    }
+    Some(Signature { r: r, s: s })
+}
+
+/// Sign `message` with a fresh random nonce.
+pub fn sign(key: &PrivateKey, message: &[u8]) -> Signature {
+    loop {
+        let k = BigUint::random_below(&key.params.q);
+        if let Some(sig) = sign_with_k(key, message, &k) {
+            return sig;
+        }
+    }
+}
+
+/// Verify the signature `sig` on `message`.
+pub fn verify(key: &PublicKey, message: &[u8], sig: &Signature) -> bool {
+    let q = &key.params.q;
+    if sig.r.is_zero() || sig.r >= *q || sig.s.is_zero() || sig.s >= *q {
+        return false;
+    }
+    verify_sloppy(key, message, sig)
+}
+
+/// Verify the signature `sig` on `message` without checking that `r`
+/// and `s` are in the range `1..q`.  Together with maliciously chosen
+/// domain parameters, this lets forged signatures through (challenge
+/// 45).  Do not use this, except for attacking it!
+pub fn verify_sloppy(key: &PublicKey, message: &[u8], sig: &Signature) -> bool {
+    let p = &key.params;
+    let w = match sig.s.modinv(&p.q) {
+        Some(w) => w,
+        None => return false,
+    };
+    let u1 = &(&hash_message(message) * &w) % &p.q;
+    let u2 = &(&sig.r * &w) % &p.q;
+    let v = &(&(&p.g.modpow(&u1, &p.p) * &key.y.modpow(&u2, &p.p)) % &p.p) % &p.q;
+    v == sig.r
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Params, PublicKey, Signature};
+    use super::{generate_keypair, sign, sign_with_k, verify, verify_sloppy, hash_message};
+    use crate::bignum::BigUint;
+
+    static CHALLENGE43_MSG: &'static [u8] =
+        b"For those that envy a MC it can be hazardous to your health\n\
+          So be friendly, a matter of life and death, just like a etch-a-sketch\n";
+
+    #[test]
+    fn hash_message_0() {
+        assert_eq!("d2d0714f014a9784047eaeccf956520045c45265",
+                   hash_message(CHALLENGE43_MSG).to_hex());
+    }
+
+    #[test]
+    fn verify_challenge43() {
+        let key = PublicKey {
+            params: Params::challenge43(),
+            y: BigUint::from_hex(
+                "84ad4719d044495496a3201c8ff484feb45b962e7302e56a392aee4\
+                 abab3e4bdebf2955b4736012f21a08084056b19bcd7fee56048e004\
+                 e44984e2f411788efdc837a0d2e5abb7b555039fd243ac01f0fb2ed\
+                 1dec568280ce678e931868d23eb095fde9d3779191b8c0299d6e07b\
+                 bb283e6633451e535c45513b2d33c99ea17").unwrap(),
+        };
+        let sig = Signature {
+            r: BigUint::from_hex("60019cacdc56eedf8e080984bfa898c8c5c419a8").unwrap(),
+            s: BigUint::from_hex("961f2062efc3c68db965a90c924cf76580ec1bbc").unwrap(),
+        };
+        assert!(verify(&key, CHALLENGE43_MSG, &sig));
+        assert!(!verify(&key, b"something else", &sig));
+    }
+
+    #[test]
+    fn sign_verify() {
+        let (public, private) = generate_keypair(&Params::challenge43());
+        let sig = sign(&private, b"hello, world");
+        assert!(verify(&public, b"hello, world", &sig));
+        assert!(!verify(&public, b"goodbye, world", &sig));
+        assert!(verify_sloppy(&public, b"hello, world", &sig));
+    }
+
+    #[test]
+    fn sign_with_k_deterministic() {
+        let (public, private) = generate_keypair(&Params::challenge43());
+        let k = BigUint::from_u64(12345);
+        let sig1 = sign_with_k(&private, b"hello", &k).unwrap();
+        let sig2 = sign_with_k(&private, b"hello", &k).unwrap();
+        assert_eq!(sig1, sig2);
+        assert!(verify(&public, b"hello", &sig1));
+        assert_eq!(None, sign_with_k(&private, b"hello", &BigUint::zero()));
+        assert_eq!(None, sign_with_k(&private, b"hello", &private.params.q));
+    }
+}
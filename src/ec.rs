@@ -0,0 +1,450 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! Elliptic curves in short Weierstrass form
+//!
+//! ```text
+//! y^2 = x^3 + a*x + b  (mod p)
+//! ```
+//!
+//! and elliptic curve Diffie-Hellman on top of them, with the group
+//! from challenge 59.  The group law does not depend on `b`, which is
+//! what makes invalid-curve attacks possible: a point on a different
+//! curve with the same `a` is happily multiplied by the private key.
+//!
+//! Montgomery curves are supported with the u-coordinate only, as in
+//! X25519, and the curve from challenge 60.  Do not use this for
+//! production!
+
+use crate::bignum::BigUint;
+use crate::hash::sha256::Sha256;
+use crate::kdf;
+use crate::math::dlog::Group;
+use crate::math::sqrt_mod;
+
+/// Point on an elliptic curve, in affine coordinates.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Point {
+    /// The point at infinity, the neutral element of the group.
+    Infinity,
+    /// The point with coordinates `x` and `y`.
+    Affine(BigUint, BigUint),
+}
+
+/// Elliptic curve over the prime field of order `p`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Curve {
+    /// Prime modulus.
+    pub p: BigUint,
+    /// Linear coefficient, reduced modulo `p`.
+    pub a: BigUint,
+    /// Constant coefficient, reduced modulo `p`.
+    pub b: BigUint,
+}
+
+/// Calculate `(a - b) mod m`, for `a` and `b` already reduced.
+fn sub_mod(a: &BigUint, b: &BigUint, m: &BigUint) -> BigUint {
+    if a >= b {
+        a - b
+    } else {
+        &(a + m) - b
+    }
+}
+
+impl Curve {
+    /// Return the curve with the same field and linear coefficient,
+    /// but constant coefficient `b`.
+    pub fn with_b(&self, b: BigUint) -> Curve {
+        Curve {
+            p: self.p.clone(),
+            a: self.a.clone(),
+            b: &b % &self.p,
+        }
+    }
+
+    /// Return `x^3 + a*x + b mod p`.
+    fn rhs(&self, x: &BigUint) -> BigUint {
+        let p = &self.p;
+        &(&(&(&(x * x) % p) * x) + &(&(&self.a * x) + &self.b)) % p
+    }
+
+    /// Return true if `point` lies on the curve.
+    pub fn contains(&self, point: &Point) -> bool {
+        match *point {
+            Point::Infinity => true,
+            Point::Affine(ref x, ref y) =>
+                *x < self.p && *y < self.p && &(y * y) % &self.p == self.rhs(x),
+        }
+    }
+
+    /// Return one of the (at most two) points with x-coordinate `x`,
+    /// or `None` if there is none.  The other one is its inverse.
+    pub fn lift_x(&self, x: &BigUint) -> Option<Point> {
+        let x = x % &self.p;
+        sqrt_mod(&self.rhs(&x), &self.p).map(|y| Point::Affine(x, y))
+    }
+
+    /// Return a random point on the curve other than infinity.
+    pub fn random_point(&self) -> Point {
+        loop {
+            if let Some(point) = self.lift_x(&BigUint::random_below(&self.p)) {
+                return if crate::random::gen::<u8>() & 1 == 1 { self.neg(&point) } else { point };
+            }
+        }
+    }
+
+    /// Return the inverse of `point`.
+    pub fn neg(&self, point: &Point) -> Point {
+        match *point {
+            Point::Infinity => Point::Infinity,
+            Point::Affine(ref x, ref y) =>
+                Point::Affine(x.clone(), sub_mod(&BigUint::zero(), y, &self.p)),
+        }
+    }
+
+    /// Add two points.
+    pub fn add(&self, p1: &Point, p2: &Point) -> Point {
+        let p = &self.p;
+        let (x1, y1, x2, y2) = match (p1, p2) {
+            (&Point::Infinity, _) => return p2.clone(),
+            (_, &Point::Infinity) => return p1.clone(),
+            (&Point::Affine(ref x1, ref y1), &Point::Affine(ref x2, ref y2)) => (x1, y1, x2, y2),
+        };
+        let m = if x1 == x2 {
+            if (y1 + y2) == *p || (y1 + y2).is_zero() {
+                return Point::Infinity;
+            }
+            let num = &(&(&BigUint::from_u64(3) * &(x1 * x1)) + &self.a) % p;
+            let den = &(y1 + y1) % p;
+            &(&num * &den.modinv(p).unwrap()) % p
+        } else {
+            let num = sub_mod(y2, y1, p);
+            let den = sub_mod(x2, x1, p);
+            &(&num * &den.modinv(p).unwrap()) % p
+        };
+        let x3 = sub_mod(&sub_mod(&(&(&m * &m) % p), x1, p), x2, p);
+        let y3 = sub_mod(&(&(&m * &sub_mod(x1, &x3, p)) % p), y1, p);
+        Point::Affine(x3, y3)
+    }
+
+    /// Multiply `point` by the scalar `k`, by double-and-add.
+    pub fn mul(&self, point: &Point, k: &BigUint) -> Point {
+        let mut result = Point::Infinity;
+        for i in (0..k.bits()).rev() {
+            result = self.add(&result, &result);
+            if k.bit(i) {
+                result = self.add(&result, point);
+            }
+        }
+        result
+    }
+}
+
+impl Group for Curve {
+    type Element = Point;
+
+    fn identity(&self) -> Point {
+        Point::Infinity
+    }
+
+    fn op(&self, a: &Point, b: &Point) -> Point {
+        self.add(a, b)
+    }
+
+    fn hash(&self, a: &Point) -> u64 {
+        match *a {
+            Point::Infinity => 0,
+            Point::Affine(ref x, _) => (x % &BigUint::from_u64(1 << 32)).to_u64().unwrap(),
+        }
+    }
+
+    fn pow(&self, a: &Point, k: &BigUint) -> Point {
+        self.mul(a, k)
+    }
+}
+
+/// Elliptic curve in Montgomery form
+///
+/// ```text
+/// b*v^2 = u^3 + a*u^2 + u  (mod p)
+/// ```
+///
+/// Only the u-coordinate is used, as in X25519.  Every u that is not
+/// on the curve is on its quadratic twist, and the ladder works just
+/// as well there.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MontgomeryCurve {
+    /// Prime modulus.
+    pub p: BigUint,
+    /// Quadratic coefficient.
+    pub a: BigUint,
+    /// Coefficient of `v^2`.
+    pub b: BigUint,
+}
+
+impl MontgomeryCurve {
+    /// The curve from challenge 60, which is isomorphic to the one
+    /// from challenge 59.  The base point has `u = 4` and order `q`
+    /// from `Params::challenge59`.
+    pub fn challenge60() -> MontgomeryCurve {
+        MontgomeryCurve {
+            p: Params::challenge59().curve.p,
+            a: BigUint::from_u64(534),
+            b: BigUint::one(),
+        }
+    }
+
+    /// Return the isomorphic curve in Weierstrass form.  Points are
+    /// mapped with `to_weierstrass_x`.
+    pub fn to_weierstrass(&self) -> Curve {
+        let p = &self.p;
+        let inv = |n: &BigUint| n.modinv(p).unwrap();
+        let (a, b) = (&self.a, &self.b);
+        let a2 = &(a * a) % p;
+        let b2 = &(b * b) % p;
+        let three = BigUint::from_u64(3);
+        // a' = (3 - a^2) / (3 b^2), b' = (2 a^3 - 9 a) / (27 b^3)
+        let wa = &(&sub_mod(&three, &a2, p) * &inv(&(&three * &b2))) % p;
+        let num = sub_mod(&(&(&BigUint::from_u64(2) * &(&a2 * a)) % p),
+                          &(&(&BigUint::from_u64(9) * a) % p), p);
+        let wb = &(&num * &inv(&(&BigUint::from_u64(27) * &(&b2 * b)))) % p;
+        Curve {
+            p: p.clone(),
+            a: wa,
+            b: wb,
+        }
+    }
+
+    /// Map a u-coordinate to the x-coordinate on the Weierstrass
+    /// curve, `x = u/b + a/(3b)`.
+    pub fn to_weierstrass_x(&self, u: &BigUint) -> BigUint {
+        let p = &self.p;
+        let binv = self.b.modinv(p).unwrap();
+        let shift = &(&self.a * &(&BigUint::from_u64(3) * &self.b).modinv(p).unwrap()) % p;
+        &(&(u * &binv) + &shift) % p
+    }
+
+    /// Return true if `u` is the u-coordinate of a point on the curve
+    /// rather than on its twist.
+    pub fn contains_u(&self, u: &BigUint) -> bool {
+        let p = &self.p;
+        let rhs = &(&(&(&(&(u * u) % p) * u) + &(&(&self.a * u) * u)) + u) % p;
+        let rhs = &(&rhs * &self.b.modinv(p).unwrap()) % p;
+        sqrt_mod(&rhs, p).is_some()
+    }
+
+    /// Multiply the point with u-coordinate `u` by `k`, using the
+    /// Montgomery ladder.  The loop always runs over all bits of `p`.
+    /// Infinity is returned as `0`.
+    pub fn ladder(&self, u: &BigUint, k: &BigUint) -> BigUint {
+        let p = &self.p;
+        let u = u % p;
+        let four = BigUint::from_u64(4);
+        let (mut u2, mut w2) = (BigUint::one(), BigUint::zero());
+        let (mut u3, mut w3) = (u.clone(), BigUint::one());
+        for i in (0..p.bits()).rev() {
+            let bit = k.bit(i);
+            if bit {
+                ::std::mem::swap(&mut u2, &mut u3);
+                ::std::mem::swap(&mut w2, &mut w3);
+            }
+            let t1 = sub_mod(&(&(&u2 * &u3) % p), &(&(&w2 * &w3) % p), p);
+            let t2 = sub_mod(&(&(&u2 * &w3) % p), &(&(&w2 * &u3) % p), p);
+            u3 = &(&t1 * &t1) % p;
+            w3 = &(&u * &(&(&t2 * &t2) % p)) % p;
+            let uu = &(&u2 * &u2) % p;
+            let ww = &(&w2 * &w2) % p;
+            let uw = &(&u2 * &w2) % p;
+            let t3 = sub_mod(&uu, &ww, p);
+            let t4 = &(&(&uu + &(&(&self.a * &uw) % p)) + &ww) % p;
+            u2 = &(&t3 * &t3) % p;
+            w2 = &(&(&four * &uw) * &t4) % p;
+            if bit {
+                ::std::mem::swap(&mut u2, &mut u3);
+                ::std::mem::swap(&mut w2, &mut w3);
+            }
+        }
+        &(&u2 * &w2.modpow(&(p - &BigUint::from_u64(2)), p)) % p
+    }
+
+    /// Return the u-coordinate of `P + Q`, given those of `P`, `Q` and
+    /// `P - Q`.  This is the differential addition the ladder is made
+    /// of, in affine form.  `P` and `Q` must have different
+    /// u-coordinates, and `P - Q` must not be infinity.
+    pub fn add_u(&self, up: &BigUint, uq: &BigUint, u_diff: &BigUint) -> BigUint {
+        let p = &self.p;
+        let num = sub_mod(&(&(up * uq) % p), &BigUint::one(), p);
+        let den = sub_mod(up, uq, p);
+        let den = &(&(&den * &den) % p) * u_diff;
+        &(&(&(&num * &num) % p) * &den.modinv(p).unwrap()) % p
+    }
+}
+
+/// Domain parameters for ECDH: a curve and a base point of prime
+/// order `q`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Params {
+    /// The curve.
+    pub curve: Curve,
+    /// Base point.
+    pub g: Point,
+    /// Prime order of `g`.
+    pub q: BigUint,
+}
+
+impl Params {
+    /// The parameters given in challenge 59.  The curve has order
+    /// `8*q`.
+    pub fn challenge59() -> Params {
+        let p = BigUint::from_hex("b005107c61647006804a0c2979df3e8d").unwrap();
+        Params {
+            curve: Curve {
+                a: &p - &BigUint::from_u64(95051),
+                b: BigUint::from_u64(11279326),
+                p: p,
+            },
+            g: Point::Affine(BigUint::from_u64(182),
+                             BigUint::from_hex("405656fe09d8d8ee91d63369521882a3").unwrap()),
+            q: BigUint::from_hex("1600a20f8c2c8e00a3f1911303382d1f").unwrap(),
+        }
+    }
+}
+
+/// Domain parameters for ECDH on a Montgomery curve: the curve and
+/// the u-coordinate of a base point of prime order `q`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MontgomeryParams {
+    /// The curve.
+    pub curve: MontgomeryCurve,
+    /// u-coordinate of the base point.
+    pub u: BigUint,
+    /// Prime order of the base point.
+    pub q: BigUint,
+}
+
+impl MontgomeryParams {
+    /// The parameters given in challenge 60, with the same group as
+    /// `Params::challenge59`.
+    pub fn challenge60() -> MontgomeryParams {
+        MontgomeryParams {
+            curve: MontgomeryCurve::challenge60(),
+            u: BigUint::from_u64(4),
+            q: Params::challenge59().q,
+        }
+    }
+}
+
+/// Generate an ECDH key pair, returning the public point and the
+/// private scalar.
+pub fn generate_keypair(params: &Params) -> (Point, BigUint) {
+    let private = &BigUint::random_below(&(&params.q - &BigUint::one())) + &BigUint::one();
+    (params.curve.mul(&params.g, &private), private)
+}
+
+/// Compute the shared secret from our `private` scalar and the other
+/// party's `public` point.  The point is not validated.
+pub fn shared_secret(curve: &Curve, private: &BigUint, public: &Point) -> Point {
+    curve.mul(public, private)
+}
+
+/// Derive a 32-byte session key from the shared secret, by feeding
+/// both coordinates to HKDF-SHA256.
+pub fn session_key(curve: &Curve, secret: &Point) -> Vec<u8> {
+    let len = (curve.p.bits() + 7) / 8;
+    let mut encoded = Vec::with_capacity(2 * len);
+    if let Point::Affine(ref x, ref y) = *secret {
+        encoded.extend(x.to_bytes_be_padded(len));
+        encoded.extend(y.to_bytes_be_padded(len));
+    }
+    kdf::hkdf::<Sha256>(b"", &encoded, b"ECDH session key", 32)
+}
+
+/// Derive a 32-byte session key from the u-coordinate of a shared
+/// secret on a Montgomery curve, by feeding it to HKDF-SHA256.
+pub fn session_key_u(curve: &MontgomeryCurve, secret: &BigUint) -> Vec<u8> {
+    kdf::hkdf::<Sha256>(b"", &secret.to_bytes_be_padded((curve.p.bits() + 7) / 8), b"ECDH session key", 32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Params, Point, MontgomeryCurve, MontgomeryParams, generate_keypair, shared_secret, session_key};
+    use crate::bignum::BigUint;
+    use crate::math::dlog::kangaroo;
+
+    #[test]
+    fn challenge59_params() {
+        let params = Params::challenge59();
+        let c = &params.curve;
+        assert!(c.contains(&params.g));
+        assert_eq!(Point::Infinity, c.mul(&params.g, &params.q));
+        assert!(c.mul(&params.g, &(&params.q - &BigUint::one())) == c.neg(&params.g));
+        assert!(!c.with_b(BigUint::from_u64(210)).contains(&params.g));
+    }
+
+    #[test]
+    fn group_law() {
+        let c = Params::challenge59().curve;
+        let (p1, p2, p3) = (c.random_point(), c.random_point(), c.random_point());
+        assert!(c.contains(&p1));
+        assert_eq!(c.add(&p1, &p2), c.add(&p2, &p1));
+        assert_eq!(c.add(&c.add(&p1, &p2), &p3), c.add(&p1, &c.add(&p2, &p3)));
+        assert_eq!(Point::Infinity, c.add(&p1, &c.neg(&p1)));
+        assert_eq!(c.add(&p1, &p1), c.mul(&p1, &BigUint::from_u64(2)));
+        // The curve has order 8*q.
+        let order = &BigUint::from_u64(8) * &Params::challenge59().q;
+        assert_eq!(Point::Infinity, c.mul(&p1, &order));
+    }
+
+    #[test]
+    fn ecdh() {
+        let params = Params::challenge59();
+        let (a_public, a_private) = generate_keypair(&params);
+        let (b_public, b_private) = generate_keypair(&params);
+        let s1 = shared_secret(&params.curve, &a_private, &b_public);
+        let s2 = shared_secret(&params.curve, &b_private, &a_public);
+        assert_eq!(s1, s2);
+        assert_eq!(session_key(&params.curve, &s1), session_key(&params.curve, &s2));
+        assert_eq!(32, session_key(&params.curve, &s1).len());
+    }
+
+    #[test]
+    fn kangaroo_on_curve() {
+        let params = Params::challenge59();
+        let c = &params.curve;
+        let a = BigUint::from_u64(1 << 40);
+        let b = BigUint::from_u64((1 << 40) + (1 << 24));
+        let x = BigUint::from_u64((1 << 40) + 0x9f3e15);
+        let y = c.mul(&params.g, &x);
+        assert_eq!(Some(x), kangaroo(&params.g, &y, &(a..b), c));
+    }
+
+    #[test]
+    fn montgomery_challenge60() {
+        let params = Params::challenge59();
+        let MontgomeryParams { curve: m, u: base, q } = MontgomeryParams::challenge60();
+        assert_eq!(params.curve, m.to_weierstrass());
+        assert_eq!(params.q, q);
+        assert_eq!(BigUint::from_u64(182), m.to_weierstrass_x(&base));
+        assert!(m.contains_u(&base));
+        assert!(m.ladder(&base, &params.q).is_zero());
+        assert!(!m.ladder(&base, &(&params.q - &BigUint::one())).is_zero());
+    }
+
+    #[test]
+    fn ladder_agrees_with_weierstrass() {
+        let params = Params::challenge59();
+        let m = MontgomeryCurve::challenge60();
+        let base = BigUint::from_u64(4);
+        for _ in 0..5 {
+            let k = BigUint::random_below(&params.q);
+            let u = m.ladder(&base, &k);
+            match params.curve.mul(&params.g, &k) {
+                Point::Affine(x, _) => assert_eq!(x, m.to_weierstrass_x(&u)),
+                Point::Infinity => assert!(u.is_zero()),
+            }
+        }
+        let u2 = m.ladder(&base, &BigUint::from_u64(2));
+        let u3 = m.ladder(&base, &BigUint::from_u64(3));
+        assert_eq!(m.ladder(&base, &BigUint::from_u64(5)), m.add_u(&u3, &u2, &base));
+    }
+}
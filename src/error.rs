@@ -2,13 +2,27 @@
 // top-level directory of this distribution for license information.
 
 //! Errors used in the crate and the corresponding binaries.
+//!
+//! The enum is `non_exhaustive`: new modules add variants as they
+//! need them, so code outside of the crate must have a wildcard arm
+//! when matching on it.  Errors from outside of the crate that have no
+//! variant of their own are wrapped in `Error::Other`, which keeps the
+//! original error as its `source`.
 
 use std::io;
 use std::fmt;
 use std::error;
+use std::num;
+use std::string;
+use std::str;
+use std::sync::mpsc;
+
+/// Result type of the fallible functions in the crate.
+pub type Result<T> = ::std::result::Result<T, Error>;
 
 /// Errors that may happen during operation.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum Error {
     /// IO Error.
     Io(io::Error),
@@ -22,12 +36,126 @@ pub enum Error {
     InvalidBase64Length,
     /// Base64 string has invalid padding.
     InvalidBase64Padding,
+    /// Base32 string contains invalid character.
+    InvalidBase32Char(char),
+    /// Base32 string has invalid length.
+    InvalidBase32Length,
+    /// Base32 string has invalid padding.
+    InvalidBase32Padding,
+    /// Base58 string contains invalid character.
+    InvalidBase58Char(char),
+    /// PEM data has a missing or mismatched BEGIN or END line.
+    InvalidPemBanner,
+    /// Percent-encoded string has `%` without two hex digits.
+    InvalidPercentEncoding,
     /// Binary string contains invalid character.
     InvalidBinChar(char),
-    /// Binary string has a length that is not divisable by 8.
+    /// Binary string has a length that is not divisable by 8, or is
+    /// too long for the integer it is decoded to.
     InvalidBinLength,
+    /// Block cipher padding is malformed.  `observed` is the padding
+    /// byte that gave it away, usually the last byte.
+    InvalidPadding {
+        /// Offending padding byte.
+        observed: u8,
+        /// Block size of the padding scheme.
+        block_size: usize,
+    },
+    /// Ciphertext is empty or not a multiple of the block size.
+    InvalidCiphertextLength {
+        /// Length of the ciphertext.
+        len: usize,
+        /// Block size of the cipher or padding scheme.
+        block_size: usize,
+    },
+    /// Two inputs that must have the same length do not.
+    LengthMismatch {
+        /// Length of the first input.
+        left: usize,
+        /// Length of the second input.
+        right: usize,
+    },
+    /// Key has a length not supported by the cipher.
+    InvalidKeyLength(usize),
+    /// Block size is not supported by the padding scheme.
+    InvalidBlockSize(usize),
+    /// Initialization vector has a length not supported by the mode.
+    InvalidIvLength(usize),
+    /// Message is too long for the given key.
+    MessageTooLong,
+    /// Padded block does not start with the expected block type.
+    InvalidPaddingBlockType,
+    /// Padding string is shorter than required.
+    PaddingTooShort,
+    /// Separator between padding and message is missing.
+    InvalidPaddingSeparator,
+    /// Label hash in OAEP padding does not match.
+    InvalidPaddingLabel,
+    /// Compressed data is corrupt.
+    InvalidCompressedData,
+    /// Serialized language model is corrupt.
+    InvalidLanguageModel,
+    /// Authentication tag does not match.
+    InvalidTag,
+    /// Integrity check of a wrapped key failed.
+    InvalidKeyWrap,
+    /// A nonce was used a second time with the same key, or no unused
+    /// nonce is left.
+    NonceReuse,
+    /// Sealed ciphertext envelope is truncated or has an unknown
+    /// magic, version, cipher, mode or MAC.
+    InvalidEnvelope,
+    /// Too many shards of an erasure-coded message are missing.
+    NotEnoughShards {
+        /// Number of shards present.
+        available: usize,
+        /// Number of shards needed for reconstruction.
+        required: usize,
+    },
+    /// The other end of a channel has gone away.
+    ChannelClosed,
+    /// A party in a protocol received a message it did not expect.
+    UnexpectedMessage,
     /// Some unimplemented functionality was requested.
     Unimplemented(&'static str),
+    /// Any other error, described by `message` and optionally caused
+    /// by `source`.
+    Other {
+        /// What went wrong.
+        message: String,
+        /// The underlying error, if any.
+        source: Option<Box<dyn error::Error + Send + Sync>>,
+    },
+}
+
+impl Error {
+    /// Return `Error::LengthMismatch` unless `left` and `right` are
+    /// equal.
+    pub fn check_lengths(left: usize, right: usize) -> Result<()> {
+        if left == right {
+            Ok(())
+        } else {
+            Err(Error::LengthMismatch { left: left, right: right })
+        }
+    }
+
+    /// Create an `Error::Other` with `message` and no source.
+    pub fn other<S: Into<String>>(message: S) -> Error {
+        Error::Other {
+            message: message.into(),
+            source: None,
+        }
+    }
+
+    /// Create an `Error::Other` with `message`, caused by `source`.
+    pub fn with_source<S, E>(message: S, source: E) -> Error
+        where S: Into<String>, E: Into<Box<dyn error::Error + Send + Sync>>
+    {
+        Error::Other {
+            message: message.into(),
+            source: Some(source.into()),
+        }
+    }
 }
 
 impl fmt::Display for Error {
@@ -45,12 +173,68 @@ impl fmt::Display for Error {
                 write!(f, "Invalid base64 string length"),
             Error::InvalidBase64Padding =>
                 write!(f, "Invalid base64 string padding"),
+            Error::InvalidBase32Char(ref ch) =>
+                write!(f, "Invalid base32 character: {:?}", ch),
+            Error::InvalidBase32Length =>
+                write!(f, "Invalid base32 string length"),
+            Error::InvalidBase32Padding =>
+                write!(f, "Invalid base32 string padding"),
+            Error::InvalidBase58Char(ref ch) =>
+                write!(f, "Invalid base58 character: {:?}", ch),
+            Error::InvalidPemBanner =>
+                write!(f, "Missing or mismatched PEM banner"),
+            Error::InvalidPercentEncoding =>
+                write!(f, "Invalid percent encoding"),
             Error::InvalidBinLength =>
-                write!(f, "Binary string has non-multiple-of-8 length"),
+                write!(f, "Binary string has invalid length"),
             Error::InvalidBinChar(ref ch) =>
                 write!(f, "Invalid binary character: {:?}", ch),
+            Error::InvalidPadding { observed, block_size } =>
+                write!(f, "Invalid padding byte {:#04x} for block size {}", observed, block_size),
+            Error::InvalidCiphertextLength { len, block_size } =>
+                write!(f, "Invalid ciphertext length {} for block size {}", len, block_size),
+            Error::LengthMismatch { left, right } =>
+                write!(f, "Length mismatch: {} and {}", left, right),
+            Error::InvalidKeyLength(ref n) =>
+                write!(f, "Invalid key length: {}", n),
+            Error::InvalidBlockSize(ref n) =>
+                write!(f, "Invalid block size: {}", n),
+            Error::InvalidIvLength(ref n) =>
+                write!(f, "Invalid IV length: {}", n),
+            Error::MessageTooLong =>
+                write!(f, "Message too long for key"),
+            Error::InvalidPaddingBlockType =>
+                write!(f, "Invalid padding block type"),
+            Error::PaddingTooShort =>
+                write!(f, "Padding too short"),
+            Error::InvalidPaddingSeparator =>
+                write!(f, "Invalid padding separator"),
+            Error::InvalidPaddingLabel =>
+                write!(f, "Invalid padding label hash"),
+            Error::InvalidCompressedData =>
+                write!(f, "Invalid compressed data"),
+            Error::InvalidLanguageModel =>
+                write!(f, "Invalid language model"),
+            Error::InvalidTag =>
+                write!(f, "Authentication tag mismatch"),
+            Error::InvalidKeyWrap =>
+                write!(f, "Wrapped key integrity check failed"),
+            Error::NonceReuse =>
+                write!(f, "Nonce reused with the same key"),
+            Error::InvalidEnvelope =>
+                write!(f, "Invalid ciphertext envelope"),
+            Error::NotEnoughShards { available, required } =>
+                write!(f, "Not enough shards: {} available, {} required", available, required),
+            Error::ChannelClosed =>
+                write!(f, "Channel closed"),
+            Error::UnexpectedMessage =>
+                write!(f, "Unexpected protocol message"),
             Error::Unimplemented(ref err) =>
                 write!(f, "unimplemented: {}", err),
+            Error::Other { ref message, source: None } =>
+                write!(f, "{}", message),
+            Error::Other { ref message, source: Some(ref source) } =>
+                write!(f, "{}: {}", message, source),
         }
     }
 }
@@ -58,19 +242,46 @@ impl fmt::Display for Error {
 impl error::Error for Error {
     fn description(&self) -> &str {
         match *self {
-            Error::Io(ref err) => err.description(),
+            Error::Io(_) => "IO error",
             Error::InvalidHexChar(_) => "invalid hex character",
             Error::InvalidHexLength => "hex string has odd length",
             Error::InvalidBase64Char(_) => "invalid base64 character",
             Error::InvalidBase64Length => "invalid base64 string length",
             Error::InvalidBase64Padding => "invalid base64 string padding",
+            Error::InvalidBase32Char(_) => "invalid base32 character",
+            Error::InvalidBase32Length => "invalid base32 string length",
+            Error::InvalidBase32Padding => "invalid base32 string padding",
+            Error::InvalidBase58Char(_) => "invalid base58 character",
+            Error::InvalidPemBanner => "missing or mismatched PEM banner",
+            Error::InvalidPercentEncoding => "invalid percent encoding",
             Error::InvalidBinChar(_) => "invalid binary character",
-            Error::InvalidBinLength => "binary string has length not divisable by 8",
+            Error::InvalidBinLength => "binary string has invalid length",
+            Error::InvalidPadding { .. } => "invalid padding",
+            Error::InvalidCiphertextLength { .. } => "invalid ciphertext length",
+            Error::LengthMismatch { .. } => "length mismatch",
+            Error::InvalidKeyLength(_) => "invalid key length",
+            Error::InvalidBlockSize(_) => "invalid block size",
+            Error::InvalidIvLength(_) => "invalid IV length",
+            Error::MessageTooLong => "message too long for key",
+            Error::InvalidPaddingBlockType => "invalid padding block type",
+            Error::PaddingTooShort => "padding too short",
+            Error::InvalidPaddingSeparator => "invalid padding separator",
+            Error::InvalidPaddingLabel => "invalid padding label hash",
+            Error::InvalidCompressedData => "invalid compressed data",
+            Error::InvalidLanguageModel => "invalid language model",
+            Error::InvalidTag => "authentication tag mismatch",
+            Error::InvalidKeyWrap => "wrapped key integrity check failed",
+            Error::NonceReuse => "nonce reused with the same key",
+            Error::InvalidEnvelope => "invalid ciphertext envelope",
+            Error::NotEnoughShards { .. } => "not enough shards",
+            Error::ChannelClosed => "channel closed",
+            Error::UnexpectedMessage => "unexpected protocol message",
             Error::Unimplemented(_) => "unimplemented",
+            Error::Other { ref message, .. } => message,
         }
     }
 
-    fn cause(&self) -> Option<&error::Error> {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
         match *self {
             Error::Io(ref err) => Some(err),
             Error::InvalidHexChar(_) => None,
@@ -78,10 +289,40 @@ impl error::Error for Error {
             Error::InvalidBase64Char(_) => None,
             Error::InvalidBase64Length => None,
             Error::InvalidBase64Padding => None,
+            Error::InvalidBase32Char(_) => None,
+            Error::InvalidBase32Length => None,
+            Error::InvalidBase32Padding => None,
+            Error::InvalidBase58Char(_) => None,
+            Error::InvalidPemBanner => None,
+            Error::InvalidPercentEncoding => None,
             Error::InvalidBinChar(_) => None,
             Error::InvalidBinLength => None,
+            Error::InvalidPadding { .. } => None,
+            Error::InvalidCiphertextLength { .. } => None,
+            Error::LengthMismatch { .. } => None,
+            Error::InvalidKeyLength(_) => None,
+            Error::InvalidBlockSize(_) => None,
+            Error::InvalidIvLength(_) => None,
+            Error::MessageTooLong => None,
+            Error::InvalidPaddingBlockType => None,
+            Error::PaddingTooShort => None,
+            Error::InvalidPaddingSeparator => None,
+            Error::InvalidPaddingLabel => None,
+            Error::InvalidCompressedData => None,
+            Error::InvalidLanguageModel => None,
+            Error::InvalidTag => None,
+            Error::InvalidKeyWrap => None,
+            Error::NonceReuse => None,
+            Error::InvalidEnvelope => None,
+            Error::NotEnoughShards { .. } => None,
+            Error::ChannelClosed => None,
+            Error::UnexpectedMessage => None,
             Error::Unimplemented(_) => None,
-       } 
+            Error::Other { ref source, .. } => match *source {
+                Some(ref err) => Some(&**err),
+                None => None,
+            },
+        }
     }
 }
 
@@ -90,3 +331,66 @@ impl From<io::Error> for Error {
         Error::Io(err)
     }
 }
+
+impl From<num::ParseIntError> for Error {
+    fn from(err: num::ParseIntError) -> Error {
+        Error::with_source("invalid number", err)
+    }
+}
+
+impl From<str::Utf8Error> for Error {
+    fn from(err: str::Utf8Error) -> Error {
+        Error::with_source("invalid UTF-8", err)
+    }
+}
+
+impl From<string::FromUtf8Error> for Error {
+    fn from(err: string::FromUtf8Error) -> Error {
+        Error::with_source("invalid UTF-8", err)
+    }
+}
+
+impl From<mpsc::RecvError> for Error {
+    fn from(_: mpsc::RecvError) -> Error {
+        Error::ChannelClosed
+    }
+}
+
+impl<T> From<mpsc::SendError<T>> for Error {
+    fn from(_: mpsc::SendError<T>) -> Error {
+        Error::ChannelClosed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error as StdError;
+    use std::io;
+    use std::sync::mpsc;
+    use super::Error;
+
+    #[test]
+    fn source_chain() {
+        let err = Error::from(io::Error::new(io::ErrorKind::Other, "disk on fire"));
+        assert_eq!("disk on fire", err.source().unwrap().to_string());
+
+        let err = Error::from("x1".parse::<u8>().unwrap_err());
+        assert!(err.to_string().starts_with("invalid number: "));
+        assert!(err.source().is_some());
+
+        let err = Error::other("plain");
+        assert_eq!("plain", err.to_string());
+        assert!(err.source().is_none());
+        assert!(Error::InvalidTag.source().is_none());
+    }
+
+    #[test]
+    fn channel_errors() {
+        let (tx, rx) = mpsc::channel::<u8>();
+        drop(rx);
+        match Error::from(tx.send(1).unwrap_err()) {
+            Error::ChannelClosed => (),
+            e => panic!("unexpected error: {:?}", e),
+        }
+    }
+}
@@ -0,0 +1,118 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! Fuzzing harnesses for the hand-written decoders.  Each function
+//! takes arbitrary bytes, feeds them to a decoder and panics if the
+//! decoder panics or breaks a property that must hold for all
+//! inputs, like re-encoding the decoded value and decoding it again.
+//!
+//! The targets in `fuzz/fuzz_targets` call these functions with
+//! `cargo fuzz run <target>`; the unit tests below run them on a few
+//! interesting inputs.
+//!
+//! The crate has no DER parser (`DigestInfo` prefixes are constants),
+//! so the nearest structured formats, PEM and PKCS#1 v1.5 encryption
+//! blocks, get harnesses instead.
+
+use crate::codec::{base64, hex, pem};
+use crate::padding::{pkcs1, pkcs7};
+
+/// Decode `data` as standard BASE64 and check that encoding and
+/// decoding the result gives it back.
+pub fn fuzz_base64(data: &[u8]) {
+    let s = String::from_utf8_lossy(data);
+    if let Ok(bytes) = base64::decode(&s) {
+        assert_eq!(bytes, base64::decode(&base64::encode(&bytes)).unwrap());
+    }
+    if let Ok(bytes) = base64::decode_config(&s, base64::URL_SAFE_NO_PAD) {
+        let encoded = base64::encode_config(&bytes, base64::URL_SAFE_NO_PAD);
+        assert_eq!(bytes, base64::decode_config(&encoded, base64::URL_SAFE_NO_PAD).unwrap());
+    }
+}
+
+/// Decode `data` as hex, strictly and leniently, and check that a
+/// strictly decoded string is re-encoded to itself, up to case.
+pub fn fuzz_hex(data: &[u8]) {
+    let s = String::from_utf8_lossy(data);
+    if let Ok(bytes) = hex::decode(&s) {
+        assert_eq!(s.to_lowercase(), hex::encode(&bytes));
+    }
+    if let Ok(bytes) = hex::decode_lenient(&s) {
+        assert_eq!(bytes, hex::decode(&hex::encode(&bytes)).unwrap());
+    }
+}
+
+/// Decode `data` as PEM blocks and check that re-encoding them gives
+/// the same blocks.
+pub fn fuzz_pem(data: &[u8]) {
+    if let Ok(blocks) = pem::decode(&String::from_utf8_lossy(data)) {
+        let s: String = blocks.iter().map(pem::encode).collect();
+        assert_eq!(blocks, pem::decode(&s).unwrap());
+    }
+}
+
+/// Remove PKCS#1 v1.5 encryption padding from `data`, and check that
+/// the message is a suffix of the block.
+pub fn fuzz_pkcs1(data: &[u8]) {
+    if let Ok(msg) = pkcs1::decode(data) {
+        assert!(data.ends_with(&msg));
+    }
+}
+
+/// Remove PKCS#7 padding from `data[1..]` with the block size
+/// `data[0]`, and check that padding the result again gives the
+/// input.
+pub fn fuzz_pkcs7_unpad(data: &[u8]) {
+    if data.is_empty() {
+        return;
+    }
+    let block_size = data[0] as usize;
+    if let Ok(unpadded) = pkcs7::unpad(&data[1..], block_size) {
+        assert_eq!(&data[1..], &pkcs7::pad(&unpadded, block_size).unwrap()[..]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fuzz_base64, fuzz_hex, fuzz_pem, fuzz_pkcs1, fuzz_pkcs7_unpad};
+
+    const INPUTS: &'static [&'static [u8]] = &[
+        b"",
+        b"=",
+        b"QQ==",
+        b"QQ",
+        b"Q===",
+        b"0xdeadBEEF",
+        b"0",
+        b"\xff\xfe",
+        b"-----BEGIN A-----\naGkg\n-----END A-----\n",
+        b"-----BEGIN -----\n-----END -----\n",
+        b"\x00\x02\x01\x01\x01\x01\x01\x01\x01\x01\x00hi",
+        b"\x00\x02\x00",
+        b"\x04abc\x01",
+        b"\x00\x01",
+        b"\x02\x02\x02",
+    ];
+
+    #[test]
+    fn interesting_inputs() {
+        for input in INPUTS {
+            fuzz_base64(input);
+            fuzz_hex(input);
+            fuzz_pem(input);
+            fuzz_pkcs1(input);
+            fuzz_pkcs7_unpad(input);
+        }
+    }
+
+    quickcheck! {
+        fn prop_no_panic(xs: Vec<u8>) -> bool {
+            fuzz_base64(&xs);
+            fuzz_hex(&xs);
+            fuzz_pem(&xs);
+            fuzz_pkcs1(&xs);
+            fuzz_pkcs7_unpad(&xs);
+            true
+        }
+    }
+}
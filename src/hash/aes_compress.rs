@@ -0,0 +1,108 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! Single-block-length compression functions built from AES-128, as
+//! described in chapter 9 of the Handbook of Applied Cryptography.
+//! Each maps a 16-byte chaining value and a 16-byte message block to
+//! a new 16-byte chaining value, and can be plugged into a
+//! Merkle-Damgard construction.  The feed-forward XOR is what makes
+//! them one-way: without it, the block cipher could simply be
+//! inverted.
+
+use crate::cipher::aes::{self, AesKey};
+
+/// Size of chaining values and message blocks, in bytes.
+pub const BLOCK_SIZE: usize = 16;
+
+/// The standard ways of turning a block cipher into a compression
+/// function.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Construction {
+    /// `H' = E_m(H) ^ H`: the message block is the key.
+    DaviesMeyer,
+    /// `H' = E_H(m) ^ m`: the chaining value is the key.
+    MatyasMeyerOseas,
+    /// `H' = E_H(m) ^ m ^ H`: like Matyas-Meyer-Oseas, but also
+    /// feeding forward the chaining value.
+    MiyaguchiPreneel,
+}
+
+impl Construction {
+    /// Compress the chaining value `h` with the message block `block`.
+    pub fn compress(&self, h: &[u8; 16], block: &[u8; 16]) -> [u8; 16] {
+        match *self {
+            Construction::DaviesMeyer => davies_meyer(h, block),
+            Construction::MatyasMeyerOseas => matyas_meyer_oseas(h, block),
+            Construction::MiyaguchiPreneel => miyaguchi_preneel(h, block),
+        }
+    }
+}
+
+fn xor_into(a: &mut [u8; 16], b: &[u8; 16]) {
+    for x in 0..16 {
+        a[x] ^= b[x];
+    }
+}
+
+/// Davies-Meyer compression: encrypt `h` with `block` as the key and
+/// XOR the result with `h`.
+pub fn davies_meyer(h: &[u8; 16], block: &[u8; 16]) -> [u8; 16] {
+    let mut output = [0; 16];
+    aes::encrypt(&AesKey::from(*block), h, &mut output);
+    xor_into(&mut output, h);
+    output
+}
+
+/// Matyas-Meyer-Oseas compression: encrypt `block` with `h` as the
+/// key and XOR the result with `block`.
+pub fn matyas_meyer_oseas(h: &[u8; 16], block: &[u8; 16]) -> [u8; 16] {
+    let mut output = [0; 16];
+    aes::encrypt(&AesKey::from(*h), block, &mut output);
+    xor_into(&mut output, block);
+    output
+}
+
+/// Miyaguchi-Preneel compression: encrypt `block` with `h` as the key
+/// and XOR the result with both `block` and `h`.
+pub fn miyaguchi_preneel(h: &[u8; 16], block: &[u8; 16]) -> [u8; 16] {
+    let mut output = matyas_meyer_oseas(h, block);
+    xor_into(&mut output, h);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Construction, davies_meyer, matyas_meyer_oseas, miyaguchi_preneel};
+    use crate::cipher::aes::{self, AesKey};
+    use crate::codec::hex;
+
+    #[test]
+    fn feed_forward() {
+        // FIPS-197 appendix C.1: AES-128 with key 000102...0f maps
+        // 00112233...ff to 69c4e0d8...5a.
+        let mut key = [0; 16];
+        key.copy_from_slice(&hex::decode("000102030405060708090a0b0c0d0e0f").unwrap());
+        let mut plain = [0; 16];
+        plain.copy_from_slice(&hex::decode("00112233445566778899aabbccddeeff").unwrap());
+        let mut cipher = [0; 16];
+        aes::encrypt(&AesKey::from(key), &plain, &mut cipher);
+
+        let xor = |a: &[u8; 16], b: &[u8; 16]| -> Vec<u8> { a.iter().zip(b.iter()).map(|(x, y)| x ^ y).collect() };
+        assert_eq!(xor(&cipher, &plain), davies_meyer(&plain, &key).to_vec());
+        assert_eq!(xor(&cipher, &plain), matyas_meyer_oseas(&key, &plain).to_vec());
+        let mut mp = [0; 16];
+        mp.copy_from_slice(&xor(&cipher, &plain));
+        assert_eq!(xor(&mp, &key), miyaguchi_preneel(&key, &plain).to_vec());
+    }
+
+    #[test]
+    fn constructions_differ() {
+        let h = [0x11; 16];
+        let m = [0x22; 16];
+        let dm = Construction::DaviesMeyer.compress(&h, &m);
+        let mmo = Construction::MatyasMeyerOseas.compress(&h, &m);
+        let mp = Construction::MiyaguchiPreneel.compress(&h, &m);
+        assert!(dm != mmo && mmo != mp && dm != mp);
+        assert_eq!(dm, davies_meyer(&h, &m));
+    }
+}
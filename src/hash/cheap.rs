@@ -0,0 +1,151 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! A deliberately weak Merkle-Damgard hash function with a tiny
+//! state, as used in challenges 52 to 54.  The compression function
+//! is one of the AES-based constructions from `hash::aes_compress`
+//! (Davies-Meyer by default), with the chaining value stored in the
+//! first bytes of a block and the result truncated to the state
+//! size.  With 16 or 24 bits of state, collisions can be found in no
+//! time, which is the whole point.  Do not use this for anything!
+
+use byteorder::{BigEndian, ByteOrder};
+
+use crate::hash::aes_compress::Construction;
+use crate::padding::{md_length, Endianness};
+
+/// Size of a message block, in bytes.
+pub const BLOCK_SIZE: usize = 16;
+
+/// Merkle-Damgard hash function with a state of `bits` bits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CheapHash {
+    bits: u32,
+    construction: Construction,
+}
+
+impl CheapHash {
+    /// Create a hash function with a state of `bits` bits.
+    ///
+    /// # Panics
+    /// Panics if `bits` is not in the range `1..33`.
+    pub fn new(bits: u32) -> CheapHash {
+        CheapHash::with_construction(bits, Construction::DaviesMeyer)
+    }
+
+    /// Create a hash function with a state of `bits` bits, using the
+    /// compression function `construction`.
+    ///
+    /// # Panics
+    /// Panics if `bits` is not in the range `1..33`.
+    pub fn with_construction(bits: u32, construction: Construction) -> CheapHash {
+        assert!(bits >= 1 && bits <= 32, "state size must be between 1 and 32 bits");
+        CheapHash { bits: bits, construction: construction }
+    }
+
+    /// Return the size of the state in bits.
+    pub fn bits(&self) -> u32 {
+        self.bits
+    }
+
+    /// Return the mask for extracting the state bits.
+    fn mask(&self) -> u32 {
+        if self.bits == 32 { !0 } else { (1 << self.bits) - 1 }
+    }
+
+    /// Return the initial chaining value.
+    pub fn initial_state(&self) -> u32 {
+        0x5a3c_e16b & self.mask()
+    }
+
+    /// Apply the compression function to the chaining value `h` and
+    /// the message block `block`, which must be `BLOCK_SIZE` bytes
+    /// long.
+    pub fn compress(&self, h: u32, block: &[u8]) -> u32 {
+        let mut m = [0; 16];
+        m.copy_from_slice(block);
+        let mut state = [0; 16];
+        BigEndian::write_u32(&mut state, h);
+        let output = self.construction.compress(&state, &m);
+        BigEndian::read_u32(&output) & self.mask()
+    }
+
+    /// Process the whole blocks in `data`, starting with the chaining
+    /// value `h`.  No padding is applied, and any trailing partial
+    /// block is ignored.
+    pub fn hash_blocks(&self, h: u32, data: &[u8]) -> u32 {
+        data.chunks(BLOCK_SIZE)
+            .filter(|b| b.len() == BLOCK_SIZE)
+            .fold(h, |h, b| self.compress(h, b))
+    }
+
+    /// Return the padding for a message of `len` bytes: a 0x80 byte,
+    /// zero bytes up to the last eight bytes of a block, and the
+    /// message length in bits.
+    pub fn padding(&self, len: usize) -> Vec<u8> {
+        md_length(len as u64, BLOCK_SIZE, Endianness::Big)
+    }
+
+    /// Hash the partial message `data` as if it started after
+    /// `prefix_len` bytes of message that led to the chaining value
+    /// `h`.  `prefix_len` must be a multiple of `BLOCK_SIZE`.
+    pub fn finish(&self, h: u32, prefix_len: usize, data: &[u8]) -> u32 {
+        let mut padded = data.to_vec();
+        padded.extend(self.padding(prefix_len + data.len()));
+        self.hash_blocks(h, &padded)
+    }
+
+    /// Hash `data`, including padding.
+    pub fn digest(&self, data: &[u8]) -> u32 {
+        self.finish(self.initial_state(), 0, data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CheapHash, BLOCK_SIZE};
+    use crate::hash::aes_compress::Construction;
+
+    #[test]
+    fn state_size() {
+        let h16 = CheapHash::new(16);
+        let h24 = CheapHash::new(24);
+        for i in 0..32 {
+            let m = vec![i as u8; i];
+            assert!(h16.digest(&m) < 1 << 16);
+            assert!(h24.digest(&m) < 1 << 24);
+        }
+        assert!(h24.digest(b"hello") != h24.digest(b"hellp"));
+    }
+
+    #[test]
+    fn padding() {
+        let h = CheapHash::new(16);
+        for len in 0..40 {
+            let pad = h.padding(len);
+            assert_eq!(0, (len + pad.len()) % BLOCK_SIZE);
+            assert!(pad.len() >= 9);
+            assert_eq!((len * 8) as u8, pad[pad.len() - 1]);
+        }
+    }
+
+    #[test]
+    fn finish_prefix() {
+        let h = CheapHash::new(24);
+        let msg = b"YELLOW SUBMARINEyellow submarine and more";
+        let state = h.hash_blocks(h.initial_state(), &msg[..32]);
+        assert_eq!(h.digest(msg), h.finish(state, 32, &msg[32..]));
+    }
+
+    #[test]
+    fn constructions() {
+        let msg = b"YELLOW SUBMARINE";
+        let dm = CheapHash::new(32);
+        let mmo = CheapHash::with_construction(32, Construction::MatyasMeyerOseas);
+        let mp = CheapHash::with_construction(32, Construction::MiyaguchiPreneel);
+        assert_eq!(dm, CheapHash::with_construction(32, Construction::DaviesMeyer));
+        assert!(dm.digest(msg) != mmo.digest(msg));
+        assert!(mmo.digest(msg) != mp.digest(msg));
+    }
+
+}
@@ -0,0 +1,191 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! Implementation of MD4 as specified in RFC 1320.  The round
+//! functions and single steps are public, so that the differential
+//! attack of challenge 55 can follow the computation step by step.
+
+use byteorder::{ByteOrder, LittleEndian};
+
+use super::Digest;
+use crate::padding::{md_length, Endianness};
+
+/// Initial hash value.
+pub static H0: [u32; 4] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476];
+
+/// Rotation amounts for the steps of round 1, repeating every four
+/// steps.
+pub static S1: [u32; 4] = [3, 7, 11, 19];
+/// Rotation amounts for the steps of round 2.
+pub static S2: [u32; 4] = [3, 5, 9, 13];
+/// Rotation amounts for the steps of round 3.
+pub static S3: [u32; 4] = [3, 9, 11, 15];
+
+/// Order in which round 2 uses the message words.
+pub static X2: [usize; 16] = [0, 4, 8, 12, 1, 5, 9, 13, 2, 6, 10, 14, 3, 7, 11, 15];
+/// Order in which round 3 uses the message words.
+pub static X3: [usize; 16] = [0, 8, 4, 12, 2, 10, 6, 14, 1, 9, 5, 13, 3, 11, 7, 15];
+
+/// Additive constant of round 2.
+pub const K2: u32 = 0x5a827999;
+/// Additive constant of round 3.
+pub const K3: u32 = 0x6ed9eba1;
+
+/// Round 1 function: bitwise `x ? y : z`.
+pub fn f(x: u32, y: u32, z: u32) -> u32 {
+    (x & y) | (!x & z)
+}
+
+/// Round 2 function: bitwise majority.
+pub fn g(x: u32, y: u32, z: u32) -> u32 {
+    (x & y) | (x & z) | (y & z)
+}
+
+/// Round 3 function: bitwise parity.
+pub fn h(x: u32, y: u32, z: u32) -> u32 {
+    x ^ y ^ z
+}
+
+/// One step of round 1: `(a + F(b, c, d) + x) <<< s`.
+pub fn r1(a: u32, b: u32, c: u32, d: u32, x: u32, s: u32) -> u32 {
+    a.wrapping_add(f(b, c, d)).wrapping_add(x).rotate_left(s)
+}
+
+/// One step of round 2: `(a + G(b, c, d) + x + K2) <<< s`.
+pub fn r2(a: u32, b: u32, c: u32, d: u32, x: u32, s: u32) -> u32 {
+    a.wrapping_add(g(b, c, d)).wrapping_add(x).wrapping_add(K2).rotate_left(s)
+}
+
+/// One step of round 3: `(a + H(b, c, d) + x + K3) <<< s`.
+pub fn r3(a: u32, b: u32, c: u32, d: u32, x: u32, s: u32) -> u32 {
+    a.wrapping_add(h(b, c, d)).wrapping_add(x).wrapping_add(K3).rotate_left(s)
+}
+
+/// Run the 48 steps of the compression function on the message words
+/// `x`, starting with chaining value `state`, and return the value
+/// computed in each step.  Step `i` updates the register that was
+/// computed in step `i - 4`, so the values are the registers `a`,
+/// `d`, `c`, `b`, `a`, ... in order.  The feed-forward of the
+/// chaining value is not included.
+pub fn steps(state: &[u32; 4], x: &[u32; 16]) -> [u32; 48] {
+    let mut q = [0u32; 52];
+    q[0] = state[0];
+    q[1] = state[3];
+    q[2] = state[2];
+    q[3] = state[1];
+    for i in 0..48 {
+        let (a, b, c, d) = (q[i], q[i + 3], q[i + 2], q[i + 1]);
+        q[i + 4] = match i / 16 {
+            0 => r1(a, b, c, d, x[i], S1[i % 4]),
+            1 => r2(a, b, c, d, x[X2[i % 16]], S2[i % 4]),
+            _ => r3(a, b, c, d, x[X3[i % 16]], S3[i % 4]),
+        };
+    }
+    let mut out = [0u32; 48];
+    out.copy_from_slice(&q[4..]);
+    out
+}
+
+/// Split a 64-byte block into little-endian message words.
+pub fn words(block: &[u8]) -> [u32; 16] {
+    let mut x = [0u32; 16];
+    for i in 0..16 {
+        x[i] = LittleEndian::read_u32(&block[i * 4..i * 4 + 4]);
+    }
+    x
+}
+
+/// Process one 64-byte block, updating `state`.
+pub fn compress(state: &mut [u32; 4], block: &[u8]) {
+    let q = steps(state, &words(block));
+    state[0] = state[0].wrapping_add(q[44]);
+    state[1] = state[1].wrapping_add(q[47]);
+    state[2] = state[2].wrapping_add(q[46]);
+    state[3] = state[3].wrapping_add(q[45]);
+}
+
+/// State of an MD4 computation.
+#[derive(Clone)]
+pub struct Md4 {
+    state: [u32; 4],
+    buffer: Vec<u8>,
+    length: u64,
+}
+
+impl Digest for Md4 {
+    const BLOCK_SIZE: usize = 64;
+    const OUTPUT_SIZE: usize = 16;
+
+    fn new() -> Md4 {
+        Md4 {
+            state: H0,
+            buffer: Vec::with_capacity(64),
+            length: 0,
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        self.length += data.len() as u64;
+        self.buffer.extend(data);
+        let full = self.buffer.len() / 64 * 64;
+        for block in self.buffer[..full].chunks(64) {
+            compress(&mut self.state, block);
+        }
+        self.buffer.drain(..full);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        let pad = md_length(self.length, 64, Endianness::Little);
+        self.buffer.extend(pad);
+        for block in self.buffer.chunks(64) {
+            compress(&mut self.state, block);
+        }
+        let mut out = vec![0u8; 16];
+        for (i, &s) in self.state.iter().enumerate() {
+            LittleEndian::write_u32(&mut out[i * 4..i * 4 + 4], s);
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Md4;
+    use crate::hash::Digest;
+    use crate::codec;
+
+    #[test]
+    fn md4_empty() {
+        assert_eq!("31d6cfe0d16ae931b73c59d7e0c089c0",
+                   codec::hex::encode(&Md4::digest(b"")));
+    }
+
+    #[test]
+    fn md4_abc() {
+        assert_eq!("a448017aaf21d8525fc10ae87aa6729d",
+                   codec::hex::encode(&Md4::digest(b"abc")));
+    }
+
+    #[test]
+    fn md4_message_digest() {
+        assert_eq!("d9130a8164549fe818874806e1c7014b",
+                   codec::hex::encode(&Md4::digest(b"message digest")));
+    }
+
+    #[test]
+    fn md4_two_blocks() {
+        let msg = b"12345678901234567890123456789012345678901234567890123456789012345678901234567890";
+        assert_eq!("e33b4ddc9c38f2199c3e7b164fcc0536",
+                   codec::hex::encode(&Md4::digest(msg)));
+    }
+
+    #[test]
+    fn md4_incremental() {
+        let msg = b"The quick brown fox jumps over the lazy dog, again and again and again.";
+        let mut d = Md4::new();
+        for chunk in msg.chunks(5) {
+            d.update(chunk);
+        }
+        assert_eq!(Md4::digest(msg), d.finish());
+    }
+}
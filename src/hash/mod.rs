@@ -0,0 +1,39 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! The `hash` module contains implementations of cryptographic hash
+//! functions.  As with the ciphers, these have neither been verified
+//! to be correct, nor to be secure.  Do not use them for production!
+
+pub mod aes_compress;
+pub mod cheap;
+pub mod md4;
+pub mod sha1;
+pub mod sha256;
+pub mod sponge;
+
+/// Common interface of the hash functions, so that constructions
+/// like HMAC can be written once for all of them.
+pub trait Digest {
+    /// Size of the blocks processed by the compression function, in
+    /// bytes.
+    const BLOCK_SIZE: usize;
+    /// Size of the hash value, in bytes.
+    const OUTPUT_SIZE: usize;
+
+    /// Create a fresh hash state.
+    fn new() -> Self;
+
+    /// Feed `data` into the hash state.
+    fn update(&mut self, data: &[u8]);
+
+    /// Finish the computation and return the hash value.
+    fn finish(self) -> Vec<u8>;
+
+    /// Hash `data` in one go.
+    fn digest(data: &[u8]) -> Vec<u8> where Self: Sized {
+        let mut d = Self::new();
+        d.update(data);
+        d.finish()
+    }
+}
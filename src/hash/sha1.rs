@@ -0,0 +1,163 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! Implementation of SHA-1 as specified in FIPS 180-4.
+
+use byteorder::{BigEndian, ByteOrder};
+
+use super::Digest;
+use crate::padding::{md_length, Endianness};
+
+/// Initial hash value.
+static H0: [u32; 5] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476, 0xc3d2e1f0];
+
+/// State of a SHA-1 computation.
+#[derive(Clone)]
+pub struct Sha1 {
+    state: [u32; 5],
+    buffer: Vec<u8>,
+    length: u64,
+}
+
+/// Process one 64-byte block, updating `state`.
+fn compress(state: &mut [u32; 5], block: &[u8]) {
+    let mut w = [0u32; 80];
+    for t in 0..16 {
+        w[t] = BigEndian::read_u32(&block[t * 4..t * 4 + 4]);
+    }
+    for t in 16..80 {
+        w[t] = (w[t - 3] ^ w[t - 8] ^ w[t - 14] ^ w[t - 16]).rotate_left(1);
+    }
+
+    let mut a = state[0];
+    let mut b = state[1];
+    let mut c = state[2];
+    let mut d = state[3];
+    let mut e = state[4];
+    for t in 0..80 {
+        let (f, k) = match t {
+            0..=19 => ((b & c) | (!b & d), 0x5a827999),
+            20..=39 => (b ^ c ^ d, 0x6ed9eba1),
+            40..=59 => ((b & c) | (b & d) | (c & d), 0x8f1bbcdc),
+            _ => (b ^ c ^ d, 0xca62c1d6),
+        };
+        let temp = a.rotate_left(5).wrapping_add(f).wrapping_add(e).wrapping_add(k).wrapping_add(w[t]);
+        e = d;
+        d = c;
+        c = b.rotate_left(30);
+        b = a;
+        a = temp;
+    }
+    state[0] = state[0].wrapping_add(a);
+    state[1] = state[1].wrapping_add(b);
+    state[2] = state[2].wrapping_add(c);
+    state[3] = state[3].wrapping_add(d);
+    state[4] = state[4].wrapping_add(e);
+}
+
+impl Sha1 {
+    /// Continue hashing from `hash`, the hash value of a message that
+    /// was `length` bytes long including its padding.  This is what
+    /// makes length extension attacks possible.
+    ///
+    /// # Panics
+    /// Panics if `hash` is not 20 bytes long, or if `length` is not a
+    /// multiple of the block size.
+    pub fn resume(hash: &[u8], length: u64) -> Sha1 {
+        assert_eq!(20, hash.len(), "hash value must be 20 bytes long");
+        assert_eq!(0, length % 64, "length must be a multiple of the block size");
+        let mut state = [0; 5];
+        for i in 0..5 {
+            state[i] = BigEndian::read_u32(&hash[i * 4..i * 4 + 4]);
+        }
+        Sha1 {
+            state: state,
+            buffer: Vec::with_capacity(64),
+            length: length,
+        }
+    }
+}
+
+impl Digest for Sha1 {
+    const BLOCK_SIZE: usize = 64;
+    const OUTPUT_SIZE: usize = 20;
+
+    fn new() -> Sha1 {
+        Sha1 {
+            state: H0,
+            buffer: Vec::with_capacity(64),
+            length: 0,
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        self.length += data.len() as u64;
+        self.buffer.extend(data);
+        let full = self.buffer.len() / 64 * 64;
+        for block in self.buffer[..full].chunks(64) {
+            compress(&mut self.state, block);
+        }
+        self.buffer.drain(..full);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        let pad = md_length(self.length, 64, Endianness::Big);
+        self.buffer.extend(pad);
+        for block in self.buffer.chunks(64) {
+            compress(&mut self.state, block);
+        }
+        let mut out = vec![0u8; 20];
+        for (i, &s) in self.state.iter().enumerate() {
+            BigEndian::write_u32(&mut out[i * 4..i * 4 + 4], s);
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Sha1;
+    use crate::hash::Digest;
+    use crate::codec;
+    use crate::padding::{md_length, Endianness};
+
+    #[test]
+    fn sha1_empty() {
+        assert_eq!("da39a3ee5e6b4b0d3255bfef95601890afd80709",
+                   codec::hex::encode(&Sha1::digest(b"")));
+    }
+
+    #[test]
+    fn sha1_abc() {
+        assert_eq!("a9993e364706816aba3e25717850c26c9cd0d89d",
+                   codec::hex::encode(&Sha1::digest(b"abc")));
+    }
+
+    #[test]
+    fn sha1_two_blocks() {
+        let msg = b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq";
+        assert_eq!("84983e441c3bd26ebaae4aa1f95129e5e54670f1",
+                   codec::hex::encode(&Sha1::digest(msg)));
+    }
+
+    #[test]
+    fn sha1_incremental() {
+        let msg = b"The quick brown fox jumps over the lazy dog, again and again and again.";
+        let mut d = Sha1::new();
+        for chunk in msg.chunks(5) {
+            d.update(chunk);
+        }
+        assert_eq!(Sha1::digest(msg), d.finish());
+    }
+
+    #[test]
+    fn sha1_resume() {
+        let msg = b"comment1=cooking%20MCs;userdata=foo";
+        let mut glued = msg.to_vec();
+        glued.extend(md_length(msg.len() as u64, 64, Endianness::Big));
+        let mut d = Sha1::resume(&Sha1::digest(msg), glued.len() as u64);
+        d.update(b";admin=true");
+        glued.extend_from_slice(b";admin=true");
+        assert_eq!(Sha1::digest(&glued), d.finish());
+    }
+}
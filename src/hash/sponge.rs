@@ -0,0 +1,226 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! The sponge construction, which turns a fixed-width permutation
+//! into a hash function with arbitrary output length, as used by
+//! SHA-3.  The state is split into the rate, which message blocks are
+//! XOR-ed into and output is read from, and the capacity, which is
+//! never touched directly and determines the security level.
+//!
+//! A toy instance of the Keccak permutation with a 200-bit state is
+//! included, so that small sponges can be attacked with modest
+//! effort.
+
+/// A permutation on a fixed number of bytes, suitable for use in a
+/// sponge.
+pub trait Permutation {
+    /// Size of the state, in bytes.
+    const WIDTH: usize;
+
+    /// Permute `state` in place.  `state` is `WIDTH` bytes long.
+    fn permute(&self, state: &mut [u8]);
+}
+
+/// Keccak-f[200]: the Keccak permutation with 8-bit lanes, 25 bytes
+/// of state and 18 rounds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeccakF200;
+
+/// Round constants, the low 8 bits of those of Keccak-f[1600].
+const ROUND_CONSTANTS: [u8; 18] = [
+    0x01, 0x82, 0x8a, 0x00, 0x8b, 0x01, 0x81, 0x09, 0x8a,
+    0x88, 0x09, 0x0a, 0x8b, 0x8b, 0x89, 0x03, 0x02, 0x80,
+];
+
+/// Rotation offsets for the rho step, indexed by `x + 5 * y`, reduced
+/// modulo the lane size of 8 bits.
+const ROTATIONS: [u32; 25] = [
+    0, 1, 6, 4, 3,
+    4, 4, 6, 7, 4,
+    3, 2, 3, 1, 7,
+    1, 5, 7, 5, 0,
+    2, 2, 5, 0, 6,
+];
+
+impl Permutation for KeccakF200 {
+    const WIDTH: usize = 25;
+
+    fn permute(&self, a: &mut [u8]) {
+        for rc in ROUND_CONSTANTS.iter() {
+            // Theta.
+            let mut c = [0u8; 5];
+            for x in 0..5 {
+                c[x] = a[x] ^ a[x + 5] ^ a[x + 10] ^ a[x + 15] ^ a[x + 20];
+            }
+            for x in 0..5 {
+                let d = c[(x + 4) % 5] ^ c[(x + 1) % 5].rotate_left(1);
+                for y in 0..5 {
+                    a[x + 5 * y] ^= d;
+                }
+            }
+            // Rho and pi.
+            let mut b = [0u8; 25];
+            for x in 0..5 {
+                for y in 0..5 {
+                    b[y + 5 * ((2 * x + 3 * y) % 5)] = a[x + 5 * y].rotate_left(ROTATIONS[x + 5 * y]);
+                }
+            }
+            // Chi.
+            for y in 0..5 {
+                for x in 0..5 {
+                    a[x + 5 * y] = b[x + 5 * y] ^ (!b[(x + 1) % 5 + 5 * y] & b[(x + 2) % 5 + 5 * y]);
+                }
+            }
+            // Iota.
+            a[0] ^= *rc;
+        }
+    }
+}
+
+/// Sponge over the permutation `P`.  Data is absorbed with `absorb`
+/// and output read with `squeeze`.  The message is padded with the
+/// pad10*1 rule, preceded by the domain separation bits given on
+/// construction, when squeezing starts.
+pub struct Sponge<P: Permutation> {
+    permutation: P,
+    rate: usize,
+    domain: u8,
+    state: Vec<u8>,
+    pos: usize,
+    squeezing: bool,
+}
+
+impl<P: Permutation> Sponge<P> {
+    /// Create a sponge absorbing and squeezing `rate` bytes per
+    /// permutation call, with the original Keccak padding.
+    ///
+    /// # Panics
+    /// Panics if `rate` is zero or not smaller than the permutation
+    /// width.
+    pub fn new(permutation: P, rate: usize) -> Sponge<P> {
+        Sponge::with_domain(permutation, rate, 0x01)
+    }
+
+    /// Like `new`, but start the padding with the byte `domain`
+    /// instead of 0x01.  SHA-3 uses 0x06, SHAKE uses 0x1f.
+    ///
+    /// # Panics
+    /// Panics if `rate` is zero or not smaller than the permutation
+    /// width.
+    pub fn with_domain(permutation: P, rate: usize, domain: u8) -> Sponge<P> {
+        assert!(rate > 0 && rate < P::WIDTH, "rate must be between 1 and the permutation width");
+        Sponge {
+            permutation: permutation,
+            rate: rate,
+            domain: domain,
+            state: vec![0; P::WIDTH],
+            pos: 0,
+            squeezing: false,
+        }
+    }
+
+    /// Return the rate in bytes.
+    pub fn rate(&self) -> usize {
+        self.rate
+    }
+
+    /// Absorb `data` into the state.
+    ///
+    /// # Panics
+    /// Panics if called after `squeeze`.
+    pub fn absorb(&mut self, data: &[u8]) {
+        assert!(!self.squeezing, "cannot absorb after squeezing");
+        for &b in data {
+            self.state[self.pos] ^= b;
+            self.pos += 1;
+            if self.pos == self.rate {
+                self.permutation.permute(&mut self.state);
+                self.pos = 0;
+            }
+        }
+    }
+
+    /// Pad the absorbed message and switch to squeezing.
+    fn finish_absorbing(&mut self) {
+        self.state[self.pos] ^= self.domain;
+        self.state[self.rate - 1] ^= 0x80;
+        self.permutation.permute(&mut self.state);
+        self.pos = 0;
+        self.squeezing = true;
+    }
+
+    /// Return the next `len` bytes of output.  The first call pads
+    /// the message; after that, no more data can be absorbed.
+    pub fn squeeze(&mut self, len: usize) -> Vec<u8> {
+        if !self.squeezing {
+            self.finish_absorbing();
+        }
+        let mut result = Vec::with_capacity(len);
+        while result.len() < len {
+            if self.pos == self.rate {
+                self.permutation.permute(&mut self.state);
+                self.pos = 0;
+            }
+            result.push(self.state[self.pos]);
+            self.pos += 1;
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{KeccakF200, Permutation, Sponge};
+    use crate::codec::hex;
+
+    #[test]
+    fn keccak_f200() {
+        let mut state = [0u8; 25];
+        KeccakF200.permute(&mut state);
+        assert_eq!(hex::decode("3c2826841cb35c171eaae9b811134ceaa3852c69d2c5abafea").unwrap(),
+                   state.to_vec());
+
+        let mut state = [0u8; 25];
+        for i in 0..25 {
+            state[i] = i as u8;
+        }
+        KeccakF200.permute(&mut state);
+        assert_eq!(hex::decode("7f0340bd5ef9a9ce6c77d141ea9123772d83f040bf231ca51c").unwrap(),
+                   state.to_vec());
+    }
+
+    #[test]
+    fn sponge() {
+        let mut s = Sponge::new(KeccakF200, 18);
+        s.absorb(b"abc");
+        assert_eq!(hex::decode("f315b6d472f24253f96f3528027b1d2224f12a0f532c6e8e62fe8f045a2f4cfd").unwrap(),
+                   s.squeeze(32));
+
+        let mut s = Sponge::with_domain(KeccakF200, 18, 0x06);
+        assert_eq!(hex::decode("baa46e1bef005c3baa70e81de3c14004").unwrap(), s.squeeze(16));
+    }
+
+    #[test]
+    fn incremental() {
+        let msg = b"The quick brown fox jumps over the lazy dog";
+        let mut s1 = Sponge::new(KeccakF200, 10);
+        s1.absorb(msg);
+        let out = s1.squeeze(40);
+
+        let mut s2 = Sponge::new(KeccakF200, 10);
+        for chunk in msg.chunks(7) {
+            s2.absorb(chunk);
+        }
+        let mut out2 = s2.squeeze(3);
+        out2.extend(s2.squeeze(37));
+        assert_eq!(out, out2);
+    }
+
+    #[test]
+    #[should_panic]
+    fn absorb_after_squeeze() {
+        let mut s = Sponge::new(KeccakF200, 18);
+        s.squeeze(1);
+        s.absorb(b"x");
+    }
+}
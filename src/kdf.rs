@@ -0,0 +1,219 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! Key derivation functions, generic over the hash function.  These
+//! turn passwords into keys and make dictionary attacks on them more
+//! expensive.  Do not use them for production!
+
+use std::marker::PhantomData;
+
+use byteorder::{BigEndian, ByteOrder};
+
+use crate::attack::password::TargetHash;
+use crate::hash::Digest;
+use crate::mac::hmac::{self, hmac};
+
+/// Derive `dklen` bytes from `password` and `salt` with PBKDF2 (RFC
+/// 8018), using HMAC with the hash function `D` and `iterations`
+/// rounds per output block.
+///
+/// # Panics
+/// Panics if `iterations` is zero.
+///
+/// # Example
+/// ```
+/// use cryptopals::hash::sha1::Sha1;
+/// use cryptopals::kdf::pbkdf2;
+/// let key = pbkdf2::<Sha1>(b"password", b"salt", 1000, 16);
+/// assert_eq!(16, key.len());
+/// ```
+pub fn pbkdf2<D: Digest>(password: &[u8], salt: &[u8], iterations: u32, dklen: usize) -> Vec<u8> {
+    assert!(iterations > 0, "iteration count must be positive");
+    let mut result = Vec::with_capacity(dklen + D::OUTPUT_SIZE);
+    let mut index = 1u32;
+    while result.len() < dklen {
+        let mut msg = salt.to_vec();
+        let mut counter = [0; 4];
+        BigEndian::write_u32(&mut counter, index);
+        msg.extend_from_slice(&counter);
+        let mut u = hmac::<D>(password, &msg);
+        let mut t = u.clone();
+        for _ in 1..iterations {
+            u = hmac::<D>(password, &u);
+            for (x, y) in t.iter_mut().zip(u.iter()) {
+                *x ^= *y;
+            }
+        }
+        result.extend(t);
+        index += 1;
+    }
+    result.truncate(dklen);
+    result
+}
+
+/// A key derived with PBKDF2 from an unknown password, as a target
+/// for password cracking.
+pub struct Pbkdf2Target<D> {
+    salt: Vec<u8>,
+    iterations: u32,
+    key: Vec<u8>,
+    digest: PhantomData<D>,
+}
+
+impl<D: Digest> Pbkdf2Target<D> {
+    /// Create a target for `key`, which was derived with `salt` and
+    /// `iterations` rounds.
+    pub fn new(salt: &[u8], iterations: u32, key: &[u8]) -> Pbkdf2Target<D> {
+        Pbkdf2Target {
+            salt: salt.to_vec(),
+            iterations: iterations,
+            key: key.to_vec(),
+            digest: PhantomData,
+        }
+    }
+}
+
+impl<D: Digest + Sync> TargetHash for Pbkdf2Target<D> {
+    fn matches(&self, candidate: &[u8]) -> bool {
+        hmac::verify(&pbkdf2::<D>(candidate, &self.salt, self.iterations, self.key.len()), &self.key)
+    }
+}
+
+/// Derive `dklen` bytes from `password` and `salt` with a toy
+/// memory-hard function modelled on the ROMix step of scrypt.  A
+/// table of `cost` chained hash values is filled and then read at
+/// data-dependent positions, so that computing the result needs
+/// either `cost` hash values of memory or a lot of recomputation.
+/// PBKDF2 with a single iteration is used to mix in the password and
+/// salt at the start and at the end.
+///
+/// # Panics
+/// Panics if `cost` is zero.
+pub fn romix<D: Digest>(password: &[u8], salt: &[u8], cost: usize, dklen: usize) -> Vec<u8> {
+    assert!(cost > 0, "cost must be positive");
+    let mut x = pbkdf2::<D>(password, salt, 1, D::OUTPUT_SIZE);
+    let mut table = Vec::with_capacity(cost);
+    for _ in 0..cost {
+        let next = D::digest(&x);
+        table.push(x);
+        x = next;
+    }
+    for _ in 0..cost {
+        let j = (BigEndian::read_u64(&x[x.len() - 8..]) % cost as u64) as usize;
+        let mixed: Vec<u8> = x.iter().zip(table[j].iter()).map(|(a, b)| a ^ b).collect();
+        x = D::digest(&mixed);
+    }
+    pbkdf2::<D>(password, &x, 1, dklen)
+}
+
+/// The extract step of HKDF (RFC 5869): condense the input keying
+/// material `ikm` into a pseudorandom key of `D::OUTPUT_SIZE` bytes.
+/// An empty `salt` is replaced by a string of zeros.
+pub fn hkdf_extract<D: Digest>(salt: &[u8], ikm: &[u8]) -> Vec<u8> {
+    if salt.is_empty() {
+        hmac::<D>(&vec![0; D::OUTPUT_SIZE], ikm)
+    } else {
+        hmac::<D>(salt, ikm)
+    }
+}
+
+/// The expand step of HKDF (RFC 5869): derive `len` bytes of output
+/// keying material for the purpose `info` from the pseudorandom key
+/// `prk`.
+///
+/// # Panics
+/// Panics if `len` is greater than `255 * D::OUTPUT_SIZE`.
+pub fn hkdf_expand<D: Digest>(prk: &[u8], info: &[u8], len: usize) -> Vec<u8> {
+    assert!(len <= 255 * D::OUTPUT_SIZE, "output too long for HKDF");
+    let mut result = Vec::with_capacity(len + D::OUTPUT_SIZE);
+    let mut t = Vec::new();
+    let mut counter = 1u8;
+    while result.len() < len {
+        let mut msg = t;
+        msg.extend_from_slice(info);
+        msg.push(counter);
+        t = hmac::<D>(prk, &msg);
+        result.extend_from_slice(&t);
+        counter = counter.wrapping_add(1);
+    }
+    result.truncate(len);
+    result
+}
+
+/// Derive `len` bytes for the purpose `info` from the input keying
+/// material `ikm` and `salt` with HKDF (RFC 5869), using HMAC with the
+/// hash function `D`.
+///
+/// # Panics
+/// Panics if `len` is greater than `255 * D::OUTPUT_SIZE`.
+pub fn hkdf<D: Digest>(salt: &[u8], ikm: &[u8], info: &[u8], len: usize) -> Vec<u8> {
+    hkdf_expand::<D>(&hkdf_extract::<D>(salt, ikm), info, len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{pbkdf2, romix, hkdf, hkdf_extract, hkdf_expand};
+    use crate::codec::hex;
+    use crate::hash::sha1::Sha1;
+    use crate::hash::sha256::Sha256;
+
+    /// RFC 6070 test vectors for PBKDF2-HMAC-SHA1.
+    #[test]
+    fn pbkdf2_sha1() {
+        assert_eq!("0c60c80f961f0e71f3a9b524af6012062fe037a6",
+                   hex::encode(&pbkdf2::<Sha1>(b"password", b"salt", 1, 20)));
+        assert_eq!("ea6c014dc72d6f8ccd1ed92ace1d41f0d8de8957",
+                   hex::encode(&pbkdf2::<Sha1>(b"password", b"salt", 2, 20)));
+        assert_eq!("4b007901b765489abead49d926f721d065a429c1",
+                   hex::encode(&pbkdf2::<Sha1>(b"password", b"salt", 4096, 20)));
+        assert_eq!("3d2eec4fe41c849b80c8d83662c0e44a8b291a964cf2f07038",
+                   hex::encode(&pbkdf2::<Sha1>(b"passwordPASSWORDpassword",
+                                               b"saltSALTsaltSALTsaltSALTsaltSALTsalt", 4096, 25)));
+    }
+
+    #[test]
+    fn pbkdf2_sha256() {
+        assert_eq!("c5e478d59288c841aa530db6845c4c8d962893a001ce4e11a4963873aa98134a",
+                   hex::encode(&pbkdf2::<Sha256>(b"password", b"salt", 4096, 32)));
+        assert_eq!("348c89dbcbd32b2f32d814b8116e84cf2b17347ebc1800181c4e2a1fb8dd53e1c635518c7dac47e9",
+                   hex::encode(&pbkdf2::<Sha256>(b"passwordPASSWORDpassword",
+                                                 b"saltSALTsaltSALTsaltSALTsaltSALTsalt", 4096, 40)));
+    }
+
+    #[test]
+    fn romix_0() {
+        assert_eq!("38329219b2cc3ee2504757b82d118356e2259ddfeef7b002e7194b2d4b8f2445",
+                   hex::encode(&romix::<Sha256>(b"password", b"salt", 1024, 32)));
+        assert_eq!("3a1c3a7a7f32e553dfce7e6bcedadab6f39647b1",
+                   hex::encode(&romix::<Sha1>(b"password", b"salt", 16, 20)));
+        assert!(romix::<Sha1>(b"password", b"salt", 17, 20) != romix::<Sha1>(b"password", b"salt", 16, 20));
+    }
+
+    /// RFC 5869, test case 1.
+    #[test]
+    fn hkdf_sha256() {
+        let ikm = [0x0b; 22];
+        let salt: Vec<u8> = (0..13).collect();
+        let info: Vec<u8> = (0xf0..0xfa).collect();
+        let prk = hkdf_extract::<Sha256>(&salt, &ikm);
+        assert_eq!("077709362c2e32df0ddc3f0dc47bba6390b6c73bb50f9c3122ec844ad7c2b3e5", hex::encode(&prk));
+        assert_eq!("3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf34007208d5b887185865",
+                   hex::encode(&hkdf_expand::<Sha256>(&prk, &info, 42)));
+    }
+
+    /// RFC 5869, test case 3 (empty salt and info).
+    #[test]
+    fn hkdf_sha256_empty() {
+        assert_eq!("8da4e775a563c18f715f802a063c5a31b8a11f5c5ee1879ec3454e5f3c738d2d9d201395faa4b61a96c8",
+                   hex::encode(&hkdf::<Sha256>(b"", &[0x0b; 22], b"", 42)));
+    }
+
+    /// RFC 5869, test case 4.
+    #[test]
+    fn hkdf_sha1() {
+        let salt: Vec<u8> = (0..13).collect();
+        let info: Vec<u8> = (0xf0..0xfa).collect();
+        assert_eq!("085a01ea1b10f36933068b56efa5ad81a4f14b822f5b091568a9cdd4f155fda2c22e422478d305f3f896",
+                   hex::encode(&hkdf::<Sha1>(&salt, &[0x0b; 11], &info, 42)));
+    }
+}
@@ -4,9 +4,15 @@
 //! Collection of XOR-base "encryption" routines.  This is no real
 //! crypto, but can be used to implement better ciphers.
 
-pub mod english {
+use std::collections::HashMap;
+use std::fmt;
+
+use byteorder::{BigEndian, ByteOrder};
 
-    use std::ascii::AsciiExt;
+use crate::distance;
+use crate::error::Error;
+
+pub mod english {
 
     // Letter statistics taken from
     // http://jnicholl.org/Cryptanalysis/Data/EnglishData.php.
@@ -153,6 +159,87 @@ pub mod english {
             (b"BU", 100),
         ];
 
+    // Percentage of all 3-letter sequences, ignoring word
+    // boundaries, of the most frequent ones.
+    static TRIGRAM_FREQS: [(&'static [u8], f64); 30] =
+        [
+            (b"THE", 1.81),
+            (b"AND", 0.73),
+            (b"ING", 0.72),
+            (b"ENT", 0.42),
+            (b"ION", 0.42),
+            (b"HER", 0.36),
+            (b"FOR", 0.34),
+            (b"THA", 0.33),
+            (b"NTH", 0.33),
+            (b"INT", 0.32),
+            (b"ERE", 0.31),
+            (b"TIO", 0.31),
+            (b"TER", 0.30),
+            (b"EST", 0.28),
+            (b"ERS", 0.28),
+            (b"ATI", 0.26),
+            (b"HAT", 0.26),
+            (b"ATE", 0.25),
+            (b"ALL", 0.25),
+            (b"ETH", 0.24),
+            (b"HES", 0.24),
+            (b"VER", 0.24),
+            (b"HIS", 0.24),
+            (b"OFT", 0.22),
+            (b"ITH", 0.21),
+            (b"FTH", 0.21),
+            (b"STH", 0.21),
+            (b"OTH", 0.21),
+            (b"RES", 0.21),
+            (b"ONT", 0.20)
+        ];
+
+    // Percentage of all 4-letter sequences, ignoring word
+    // boundaries, of the most frequent ones.
+    static QUADGRAM_FREQS: [(&'static [u8], f64); 30] =
+        [
+            (b"TION", 0.31),
+            (b"NTHE", 0.27),
+            (b"THER", 0.24),
+            (b"THAT", 0.21),
+            (b"OFTH", 0.19),
+            (b"FTHE", 0.19),
+            (b"THES", 0.18),
+            (b"WITH", 0.18),
+            (b"INTH", 0.17),
+            (b"ATIO", 0.17),
+            (b"OTHE", 0.16),
+            (b"TTHE", 0.16),
+            (b"DTHE", 0.16),
+            (b"INGT", 0.15),
+            (b"ETHE", 0.15),
+            (b"SAND", 0.14),
+            (b"STHE", 0.14),
+            (b"HERE", 0.13),
+            (b"THEC", 0.13),
+            (b"MENT", 0.13),
+            (b"THEM", 0.12),
+            (b"RTHE", 0.12),
+            (b"THEP", 0.11),
+            (b"FROM", 0.11),
+            (b"THIS", 0.11),
+            (b"TING", 0.10),
+            (b"THEI", 0.10),
+            (b"NGTH", 0.10),
+            (b"IONS", 0.10),
+            (b"ANDT", 0.10)
+        ];
+
+    // Assumed percentages of the n-grams missing from the tables,
+    // about the remaining probability mass spread evenly over them.
+    const TRIGRAM_FLOOR: f64 = 0.005;
+    const QUADGRAM_FLOOR: f64 = 0.0002;
+
+    // Log-probability of a window containing a byte that does not
+    // occur in text at all.
+    const GARBAGE_LOG_PROB: f64 = -8.0;
+
     /// Return the frequency of the given letter in English texts, as
     /// an unsigned integer.  The results are in the range 9...1231.
     pub fn letter_freq(b: u8) -> Option<usize> {
@@ -164,6 +251,30 @@ pub mod english {
         }
     }
     
+    /// Return the probabilities of the letters in English texts, as
+    /// upper-case letters with probabilities summing to 1, most
+    /// frequent first.
+    pub fn letter_probs() -> Vec<(u8, f64)> {
+        let total: usize = LETTER_FREQS.iter().map(|&(_, f)| f).sum();
+        LETTER_FREQS.iter().map(|&(l, f)| (l, f as f64 / total as f64)).collect()
+    }
+
+    /// Return the probability of the given letter in English texts,
+    /// ignoring case, see `letter_probs`.
+    pub fn letter_prob(b: u8) -> Option<f64> {
+        let u = b.to_ascii_uppercase();
+        letter_probs().into_iter().find(|&(l, _)| l == u).map(|(_, p)| p)
+    }
+
+    /// Return the probabilities of the most frequent two-letter
+    /// combinations in English texts, relative to each other, as
+    /// upper-case digrams with probabilities summing to 1, most
+    /// frequent first.
+    pub fn digram_probs() -> Vec<(&'static [u8], f64)> {
+        let total: usize = DIGRAM_FREQS.iter().map(|&(_, f)| f).sum();
+        DIGRAM_FREQS.iter().map(|&(d, f)| (d, f as f64 / total as f64)).collect()
+    }
+
     /// Return the frequency of the given two-letter combination in
     /// English texts, as an unsigned integer.  The results are in the
     /// range 100...1582.
@@ -178,6 +289,66 @@ pub mod english {
         }
     }
 
+    /// Return the average base-10 logarithm of the probabilities of
+    /// the `n`-letter windows of `b`, looked up in `table`.  Windows
+    /// containing punctuation or white space are skipped, and windows
+    /// containing control characters or non-ASCII bytes get a very
+    /// low probability.
+    fn ngram_log_prob(b: &[u8], n: usize, table: &[(&[u8], f64)], floor: f64) -> f64 {
+        let mut total = 0.0;
+        let mut count = 0;
+        for w in b.windows(n) {
+            if w.iter().any(|&x| (x < b' ' && x != b'\n' && x != b'\t') || x > 126) {
+                total += GARBAGE_LOG_PROB;
+            } else if w.iter().all(|x| x.is_ascii_alphabetic()) {
+                let u: Vec<u8> = w.iter().map(|x| x.to_ascii_uppercase()).collect();
+                let pct = table.iter().find(|&&(g, _)| g == &u[..]).map_or(floor, |&(_, f)| f);
+                total += (pct / 100.0).log10();
+            } else {
+                continue;
+            }
+            count += 1;
+        }
+        if count == 0 {
+            (floor / 100.0).log10()
+        } else {
+            total / count as f64
+        }
+    }
+
+    /// Score `b` by how likely its trigrams and quadgrams are in
+    /// English, as the sum of the average base-10 log-probabilities of
+    /// both.  The result does not depend on the length of the text and
+    /// is always negative; higher is more English.  Unlike
+    /// `score_string`, it does not reward frequent single letters, so
+    /// it ranks short texts better.
+    pub fn score_ngram(b: &[u8]) -> f64 {
+        ngram_log_prob(b, 3, &TRIGRAM_FREQS, TRIGRAM_FLOOR) +
+            ngram_log_prob(b, 4, &QUADGRAM_FREQS, QUADGRAM_FLOOR)
+    }
+
+    /// Return the chi-squared statistic of the letter counts of `b`
+    /// against the English letter frequencies, divided by the number
+    /// of letters.  Case is ignored and all other bytes are skipped,
+    /// so this should be combined with a check for unprintable bytes
+    /// when ranking decryptions.  Lower values mean a better fit.
+    /// For long texts, English tends to 0 and uniformly random letters
+    /// to about 5.  Returns 0 if `b` contains no letters.
+    pub fn chi2_score(b: &[u8]) -> f64 {
+        let mut counts = [0; 26];
+        for &c in b {
+            if c.is_ascii_alphabetic() {
+                counts[(c.to_ascii_uppercase() - b'A') as usize] += 1;
+            }
+        }
+        let letters: usize = counts.iter().sum();
+        if letters == 0 {
+            return 0.0;
+        }
+        let expected: Vec<f64> = (b'A'..b'Z' + 1).map(|l| letter_prob(l).unwrap()).collect();
+        crate::distance::chi_squared(&counts, &expected) / letters as f64
+    }
+
     pub fn score_string(b: &[u8]) -> usize {
         let mut score: usize = 0;
         let mut penalty: usize = 0;
@@ -203,7 +374,7 @@ pub mod english {
         let mut word_len = 0;
         for &x in b {
             match x {
-                b'A'...b'Z' | b'a'...b'z' => {
+                b'A'..=b'Z' | b'a'..=b'z' => {
                     if !in_word {
                         in_word = true;
                     }
@@ -283,6 +454,42 @@ pub mod english {
             assert_eq!(None, digram_freq(b"BB"));
         }
 
+        #[test]
+        fn score_ngram_0() {
+            use super::score_ngram;
+            assert!(score_ngram(b"the nation") > score_ngram(b"qzx jvkwpf"));
+            assert!(score_ngram(b"Cooking MC's like a pound of bacon") >
+                    score_ngram(b"Dnnjhof LB&r mhjd ` qntoe ng c`bno"));
+            assert!(score_ngram(b"eeee eeee") < score_ngram(b"with them"));
+            assert!(score_ngram(b"\x00\x01\x02\x03") < score_ngram(b"xqzj"));
+            assert_eq!(score_ngram(b"there"), score_ngram(b"THERE"));
+            assert!(score_ngram(b"") < 0.0);
+        }
+
+        #[test]
+        fn letter_probs_0() {
+            use super::{letter_probs, letter_prob, digram_probs};
+            let letters = letter_probs();
+            assert_eq!(26, letters.len());
+            assert!((letters.iter().map(|&(_, p)| p).sum::<f64>() - 1.0).abs() < 1e-9);
+            assert_eq!(b'E', letters[0].0);
+            assert_eq!(letter_prob(b'e'), letter_prob(b'E'));
+            assert_eq!(None, letter_prob(b'!'));
+            let digrams = digram_probs();
+            assert_eq!(109, digrams.len());
+            assert!((digrams.iter().map(|&(_, p)| p).sum::<f64>() - 1.0).abs() < 1e-9);
+        }
+
+        #[test]
+        fn chi2_score_0() {
+            use super::chi2_score;
+            assert_eq!(0.0, chi2_score(b""));
+            assert_eq!(0.0, chi2_score(b"1234 !?"));
+            assert!(chi2_score(b"Cooking MC's like a pound of bacon") < 2.0);
+            assert!(chi2_score(b"zzqx jjkv wwxz") > 10.0);
+            assert_eq!(chi2_score(b"Hello world"), chi2_score(b"HELLO, WORLD!"));
+        }
+
         #[test]
         fn score_string_0() {
             use super::score_string;
@@ -294,3 +501,708 @@ pub mod english {
         }
     }
 }
+
+/// Statistics for German texts.  Letters with diacritics are counted as
+/// the base letter, and the ß as two S.
+pub mod german {
+
+    // Letter statistics taken from
+    // https://en.wikipedia.org/wiki/Letter_frequency.
+    static LETTER_FREQS: [(u8, usize); 26] =
+        [
+            (b'E', 1740),
+            (b'N', 978),
+            (b'I', 755),
+            (b'S', 727),
+            (b'R', 700),
+            (b'A', 651),
+            (b'T', 615),
+            (b'D', 508),
+            (b'H', 476),
+            (b'U', 435),
+            (b'L', 344),
+            (b'C', 306),
+            (b'G', 301),
+            (b'M', 253),
+            (b'O', 251),
+            (b'B', 189),
+            (b'W', 189),
+            (b'F', 166),
+            (b'K', 121),
+            (b'Z', 113),
+            (b'V', 85),
+            (b'P', 79),
+            (b'J', 27),
+            (b'Y', 4),
+            (b'X', 3),
+            (b'Q', 2)
+        ];
+
+    /// Return the frequency of the given letter in German texts, as
+    /// an unsigned integer.  The results are in the range 2...1740.
+    pub fn letter_freq(b: u8) -> Option<usize> {
+        let u = b.to_ascii_uppercase();
+        LETTER_FREQS.iter().find(|&&(l, _)| l == u).map(|&(_, f)| f)
+    }
+}
+
+/// Statistics for French texts.  Letters with diacritics are counted as
+/// the base letter, and the ß as two S.
+pub mod french {
+
+    // Letter statistics taken from
+    // https://en.wikipedia.org/wiki/Letter_frequency.
+    static LETTER_FREQS: [(u8, usize); 26] =
+        [
+            (b'E', 1471),
+            (b'S', 795),
+            (b'A', 764),
+            (b'I', 753),
+            (b'T', 724),
+            (b'N', 710),
+            (b'R', 669),
+            (b'U', 631),
+            (b'O', 580),
+            (b'L', 546),
+            (b'D', 367),
+            (b'C', 326),
+            (b'M', 297),
+            (b'P', 252),
+            (b'V', 184),
+            (b'Q', 136),
+            (b'F', 107),
+            (b'B', 90),
+            (b'G', 87),
+            (b'H', 74),
+            (b'J', 61),
+            (b'X', 43),
+            (b'Z', 33),
+            (b'Y', 13),
+            (b'K', 5),
+            (b'W', 4)
+        ];
+
+    /// Return the frequency of the given letter in French texts, as
+    /// an unsigned integer.  The results are in the range 4...1471.
+    pub fn letter_freq(b: u8) -> Option<usize> {
+        let u = b.to_ascii_uppercase();
+        LETTER_FREQS.iter().find(|&&(l, _)| l == u).map(|&(_, f)| f)
+    }
+}
+
+/// Statistics for Spanish texts.  Letters with diacritics are counted as
+/// the base letter, and the ß as two S.
+pub mod spanish {
+
+    // Letter statistics taken from
+    // https://en.wikipedia.org/wiki/Letter_frequency.
+    static LETTER_FREQS: [(u8, usize); 26] =
+        [
+            (b'E', 1368),
+            (b'A', 1253),
+            (b'O', 868),
+            (b'S', 798),
+            (b'R', 687),
+            (b'N', 671),
+            (b'I', 625),
+            (b'D', 586),
+            (b'L', 497),
+            (b'C', 468),
+            (b'T', 463),
+            (b'U', 393),
+            (b'M', 315),
+            (b'P', 251),
+            (b'B', 142),
+            (b'G', 101),
+            (b'V', 90),
+            (b'Y', 90),
+            (b'Q', 88),
+            (b'H', 70),
+            (b'F', 69),
+            (b'Z', 52),
+            (b'J', 44),
+            (b'X', 22),
+            (b'K', 2),
+            (b'W', 2)
+        ];
+
+    /// Return the frequency of the given letter in Spanish texts, as
+    /// an unsigned integer.  The results are in the range 2...1368.
+    pub fn letter_freq(b: u8) -> Option<usize> {
+        let u = b.to_ascii_uppercase();
+        LETTER_FREQS.iter().find(|&&(l, _)| l == u).map(|&(_, f)| f)
+    }
+}
+
+/// Languages with built-in letter frequency tables.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Language {
+    English,
+    German,
+    French,
+    Spanish,
+}
+
+/// All languages in `Language`.
+pub static LANGUAGES: [Language; 4] =
+    [Language::English, Language::German, Language::French, Language::Spanish];
+
+// Expected share of letters, spaces, other printable ASCII characters
+// (including line breaks and tabs), bytes of non-ASCII characters and
+// control characters in text.  The last one is not zero so that
+// garbage is heavily penalized instead of ignored.
+const LETTER_SHARE: f64 = 0.8;
+const SPACE_SHARE: f64 = 0.15;
+const OTHER_SHARE: f64 = 0.04;
+const NON_ASCII_SHARE: f64 = 0.01;
+const CONTROL_SHARE: f64 = 0.0001;
+
+impl Language {
+    /// Return the frequency of the given letter in texts of this
+    /// language, see `english::letter_freq` and friends.
+    pub fn letter_freq(self, b: u8) -> Option<usize> {
+        match self {
+            Language::English => english::letter_freq(b),
+            Language::German => german::letter_freq(b),
+            Language::French => french::letter_freq(b),
+            Language::Spanish => spanish::letter_freq(b),
+        }
+    }
+
+    /// Score `msg` by how well its letters, spaces, punctuation and
+    /// other bytes fit texts of this language, as the negated
+    /// chi-squared statistic per byte.  Non-ASCII bytes are accepted
+    /// in small numbers for UTF-8 encoded letters with diacritics.
+    /// The result is at most zero; higher is better.
+    pub fn score(self, msg: &[u8]) -> f64 {
+        if msg.is_empty() {
+            return 0.0;
+        }
+        let letter_total: usize = (b'A'..b'Z' + 1).map(|l| self.letter_freq(l).unwrap()).sum();
+        let mut expected: Vec<f64> = (b'A'..b'Z' + 1)
+            .map(|l| LETTER_SHARE * self.letter_freq(l).unwrap() as f64 / letter_total as f64)
+            .collect();
+        expected.extend_from_slice(&[SPACE_SHARE, OTHER_SHARE, NON_ASCII_SHARE, CONTROL_SHARE]);
+        let mut counts = [0; 30];
+        for &b in msg {
+            let i = match b {
+                b'A'..=b'Z' => (b - b'A') as usize,
+                b'a'..=b'z' => (b - b'a') as usize,
+                b' ' => 26,
+                b'\t' | b'\n' | b'\r' | 33..=126 => 27,
+                128..=255 => 28,
+                _ => 29,
+            };
+            counts[i] += 1;
+        }
+        -distance::chi_squared(&counts, &expected) / msg.len() as f64
+    }
+}
+
+/// Find out which of the built-in languages `msg` is most likely
+/// written in.  Returns the language and its score, see
+/// `Language::score`.
+pub fn detect(msg: &[u8]) -> (Language, f64) {
+    LANGUAGES.iter()
+        .map(|&l| (l, l.score(msg)))
+        .fold((Language::English, f64::NEG_INFINITY),
+              |a, b| if b.1 > a.1 { b } else { a })
+}
+
+/// Cheap statistics of a candidate plaintext, for rejecting obvious
+/// binary garbage before running more expensive scorers.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Plausibility {
+    /// Share of printable ASCII characters, including tabs and line
+    /// breaks, from 0 to 1.  It is 1 for the empty message.
+    pub printable_ratio: f64,
+    /// Number of ASCII control characters other than tabs and line
+    /// breaks.
+    pub control_char_count: usize,
+    /// Whether the message is valid UTF-8.
+    pub utf8_valid: bool,
+}
+
+/// Compute the `Plausibility` statistics of `msg`.
+pub fn plausibility(msg: &[u8]) -> Plausibility {
+    let mut printable = 0;
+    let mut control = 0;
+    for &b in msg {
+        match b {
+            b'\t' | b'\n' | b'\r' | 32..=126 => printable += 1,
+            0..=31 | 127 => control += 1,
+            _ => {}
+        }
+    }
+    Plausibility {
+        printable_ratio: if msg.is_empty() { 1.0 } else { printable as f64 / msg.len() as f64 },
+        control_char_count: control,
+        utf8_valid: ::std::str::from_utf8(msg).is_ok(),
+    }
+}
+
+/// Letter statistics of a message, for diagnosing why a candidate
+/// plaintext did or did not score well.  Letters are counted without
+/// regard to case, and digrams are pairs of adjacent letters.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FrequencyReport {
+    /// Length of the message, in bytes.
+    pub length: usize,
+    /// Number of occurrences of each letter from `A` to `Z`.
+    pub letter_counts: [usize; 26],
+    /// Number of occurrences of each digram that occurs at all, most
+    /// frequent first.
+    pub digram_counts: Vec<([u8; 2], usize)>,
+    /// Index of coincidence of the letters, see
+    /// `distance::index_of_coincidence`.
+    pub index_of_coincidence: f64,
+    /// Shannon entropy of the bytes, see `distance::entropy`.
+    pub entropy: f64,
+    /// The built-in language the message fits best, with its score,
+    /// see `detect`.
+    pub language: (Language, f64),
+    /// Cheap printability statistics, see `plausibility`.
+    pub plausibility: Plausibility,
+}
+
+impl FrequencyReport {
+    /// Total number of letters.
+    pub fn letters(&self) -> usize {
+        self.letter_counts.iter().sum()
+    }
+}
+
+/// Number of digrams listed by the `Display` implementation of
+/// `FrequencyReport`.
+const REPORT_DIGRAMS: usize = 10;
+
+impl fmt::Display for FrequencyReport {
+    /// Print the statistics as a table, with the letters ordered by
+    /// frequency and compared to English.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let letters = self.letters();
+        writeln!(f, "length {} bytes, {} letters, {:.1}% printable{}",
+                 self.length, letters, 100.0 * self.plausibility.printable_ratio,
+                 if self.plausibility.utf8_valid { "" } else { ", not UTF-8" })?;
+        writeln!(f, "index of coincidence {:.4} (English 0.066, random 0.038)",
+                 self.index_of_coincidence)?;
+        writeln!(f, "entropy {:.2} bits per byte", self.entropy)?;
+        writeln!(f, "closest language {:?} (score {:.3})", self.language.0, self.language.1)?;
+        writeln!(f, "letter  count  observed  English")?;
+        let mut order: Vec<usize> = (0..26).collect();
+        order.sort_by(|&a, &b| self.letter_counts[b].cmp(&self.letter_counts[a]).then(a.cmp(&b)));
+        let english: Vec<(u8, f64)> = english::letter_probs();
+        for i in order {
+            let l = b'A' + i as u8;
+            let observed = if letters == 0 { 0.0 } else { self.letter_counts[i] as f64 / letters as f64 };
+            let expected = english.iter().find(|&&(e, _)| e == l).map(|&(_, p)| p).unwrap_or(0.0);
+            writeln!(f, "{}       {:5}  {:7.1}%  {:6.1}%", l as char, self.letter_counts[i],
+                     100.0 * observed, 100.0 * expected)?;
+        }
+        write!(f, "top digrams:")?;
+        for &(d, n) in self.digram_counts.iter().take(REPORT_DIGRAMS) {
+            write!(f, " {}{} {}", d[0] as char, d[1] as char, n)?;
+        }
+        Ok(())
+    }
+}
+
+/// Compute the letter and digram statistics of `msg`.
+///
+/// # Example
+/// ```
+/// use cryptopals::language::analyze;
+/// let report = analyze(b"that theme");
+/// assert_eq!(2, report.letter_counts[(b'E' - b'A') as usize]);
+/// assert_eq!(([b'T', b'H'], 2), report.digram_counts[0]);
+/// println!("{}", report);
+/// ```
+pub fn analyze(msg: &[u8]) -> FrequencyReport {
+    let mut letter_counts = [0; 26];
+    let mut digrams: HashMap<[u8; 2], usize> = HashMap::new();
+    let mut letters = Vec::with_capacity(msg.len());
+    let mut prev = None;
+    for &b in msg {
+        if b.is_ascii_alphabetic() {
+            let u = b.to_ascii_uppercase();
+            letter_counts[(u - b'A') as usize] += 1;
+            letters.push(u);
+            if let Some(p) = prev {
+                *digrams.entry([p, u]).or_insert(0) += 1;
+            }
+            prev = Some(u);
+        } else {
+            prev = None;
+        }
+    }
+    let mut digram_counts: Vec<([u8; 2], usize)> = digrams.into_iter().collect();
+    digram_counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+    FrequencyReport {
+        length: msg.len(),
+        letter_counts: letter_counts,
+        digram_counts: digram_counts,
+        index_of_coincidence: distance::index_of_coincidence(&letters),
+        entropy: distance::entropy(msg),
+        language: detect(msg),
+        plausibility: plausibility(msg),
+    }
+}
+
+/// Order-2 Markov model of English text, which gives the probability
+/// of each byte given the two bytes before it.  To keep the table
+/// small, bytes are reduced to 29 classes: the 26 letters ignoring
+/// case, space, other printable characters and everything else.  The
+/// built-in table was trained with the `train_markov` binary on the
+/// GNU GPL 3, GFDL 1.3, LGPL 2.1, Apache 2.0 and MPL 2.0 license texts
+/// and the plaintext of challenge 6.
+pub mod markov {
+
+    /// Number of byte classes.
+    const CLASSES: usize = 29;
+
+    /// Size of a table produced by `train`.
+    pub const TABLE_SIZE: usize = CLASSES * CLASSES * CLASSES;
+
+    // Table entries are the negated base-10 log-probabilities,
+    // multiplied by this and capped at 255.
+    const SCALE: f64 = 32.0;
+
+    // Added to every count, so that unseen combinations do not get
+    // probability zero.
+    const SMOOTHING: f64 = 0.1;
+
+    static TABLE: &'static [u8; TABLE_SIZE] = include_bytes!("../data/english.markov");
+
+    fn class(b: u8) -> usize {
+        match b {
+            b'A'..=b'Z' => (b - b'A') as usize,
+            b'a'..=b'z' => (b - b'a') as usize,
+            b' ' => 26,
+            b'\t' | b'\n' | b'\r' | 33..=126 => 27,
+            _ => 28,
+        }
+    }
+
+    // Index of the table entry for the class sequence `a`, `b`, `c`.
+    fn index(a: usize, b: usize, c: usize) -> usize {
+        (a * CLASSES + b) * CLASSES + c
+    }
+
+    /// Train a table for `log_likelihood_with` on `corpus`.  The text
+    /// is treated as if preceded by two spaces.
+    pub fn train(corpus: &[u8]) -> Vec<u8> {
+        let mut counts = vec![0usize; TABLE_SIZE];
+        let (mut a, mut b) = (class(b' '), class(b' '));
+        for &x in corpus {
+            let c = class(x);
+            counts[index(a, b, c)] += 1;
+            a = b;
+            b = c;
+        }
+        let mut table = vec![0; TABLE_SIZE];
+        for context in 0..CLASSES * CLASSES {
+            let row = &counts[context * CLASSES..(context + 1) * CLASSES];
+            let total: usize = row.iter().sum();
+            for (c, &n) in row.iter().enumerate() {
+                let p = (n as f64 + SMOOTHING) / (total as f64 + SMOOTHING * CLASSES as f64);
+                table[context * CLASSES + c] = (-p.log10() * SCALE).round().min(255.0) as u8;
+            }
+        }
+        table
+    }
+
+    /// Return the average base-10 log-probability of the bytes of
+    /// `msg` under the built-in English model.  The result does not
+    /// depend on the length of `msg` and is at most zero; higher is
+    /// more English.  Unlike the other scores in this module, it gives
+    /// useful results for messages of only a few bytes.  Returns 0 for
+    /// the empty message.
+    pub fn log_likelihood(msg: &[u8]) -> f64 {
+        log_likelihood_with(&TABLE[..], msg)
+    }
+
+    /// Like `log_likelihood`, but use a `table` produced by `train`.
+    ///
+    /// # Panics
+    /// Panics if `table` does not have `TABLE_SIZE` entries.
+    pub fn log_likelihood_with(table: &[u8], msg: &[u8]) -> f64 {
+        assert_eq!(TABLE_SIZE, table.len(), "invalid Markov table size");
+        if msg.is_empty() {
+            return 0.0;
+        }
+        let (mut a, mut b) = (class(b' '), class(b' '));
+        let mut total = 0;
+        for &x in msg {
+            let c = class(x);
+            total += table[index(a, b, c)] as usize;
+            a = b;
+            b = c;
+        }
+        -(total as f64) / SCALE / msg.len() as f64
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::{train, log_likelihood, log_likelihood_with, TABLE_SIZE};
+
+        #[test]
+        fn train_0() {
+            let table = train(b"abab abab abab");
+            assert_eq!(TABLE_SIZE, table.len());
+            assert!(log_likelihood_with(&table, b"abab") > log_likelihood_with(&table, b"baba"));
+        }
+
+        #[test]
+        fn log_likelihood_0() {
+            assert_eq!(0.0, log_likelihood(b""));
+            assert!(log_likelihood(b"the cat") > log_likelihood(b"xqz jvk"));
+            assert!(log_likelihood(b"Go on") > log_likelihood(b"Go\x00on"));
+            assert!(log_likelihood(b"in it") > log_likelihood(b"nx\x7fzq"));
+            assert_eq!(log_likelihood(b"Hello"), log_likelihood(b"hELLO"));
+        }
+    }
+}
+
+/// Magic bytes at the start of a serialized `Model`.
+const MODEL_MAGIC: &'static [u8] = b"LANG\x01";
+
+/// Statistical model of some kind of text, trained on a sample
+/// corpus.  Unlike the tables in `english`, it works on arbitrary
+/// bytes, so it can be trained on other languages or on structured
+/// plaintexts like JSON or HTTP headers.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Model {
+    letters: Vec<u32>,
+    digrams: HashMap<[u8; 2], u32>,
+    trigrams: HashMap<[u8; 3], u32>,
+    total: u32,
+}
+
+impl Model {
+    /// Build a model from the byte, digram and trigram counts of
+    /// `corpus`.
+    pub fn train(corpus: &[u8]) -> Model {
+        let mut model = Model {
+            letters: vec![0; 256],
+            digrams: HashMap::new(),
+            trigrams: HashMap::new(),
+            total: 0,
+        };
+        for (i, &b) in corpus.iter().enumerate() {
+            model.letters[b as usize] = model.letters[b as usize].saturating_add(1);
+            model.total = model.total.saturating_add(1);
+            if i >= 1 {
+                let c = model.digrams.entry([corpus[i - 1], b]).or_insert(0);
+                *c = c.saturating_add(1);
+            }
+            if i >= 2 {
+                let c = model.trigrams.entry([corpus[i - 2], corpus[i - 1], b]).or_insert(0);
+                *c = c.saturating_add(1);
+            }
+        }
+        model
+    }
+
+    fn digram(&self, a: u8, b: u8) -> u32 {
+        self.digrams.get(&[a, b]).cloned().unwrap_or(0)
+    }
+
+    fn trigram(&self, a: u8, b: u8, c: u8) -> u32 {
+        self.trigrams.get(&[a, b, c]).cloned().unwrap_or(0)
+    }
+
+    /// Score `msg` by how well it fits the model, as the average
+    /// base-10 logarithm of the probability of each byte given the up
+    /// to two bytes before it.  Unseen combinations are smoothed by
+    /// adding one to every count.  The result does not depend on the
+    /// length of `msg` and is always negative; higher is better.
+    pub fn score(&self, msg: &[u8]) -> f64 {
+        if msg.is_empty() {
+            return (1.0f64 / 256.0).log10();
+        }
+        let mut total = 0.0;
+        for (i, &c) in msg.iter().enumerate() {
+            let (count, context) = match i {
+                0 => (self.letters[c as usize], self.total),
+                1 => (self.digram(msg[0], c), self.letters[msg[0] as usize]),
+                _ => (self.trigram(msg[i - 2], msg[i - 1], c), self.digram(msg[i - 2], msg[i - 1])),
+            };
+            total += ((count as f64 + 1.0) / (context as f64 + 256.0)).log10();
+        }
+        total / msg.len() as f64
+    }
+
+    /// Serialize the model.  The format is the magic bytes `LANG\x01`,
+    /// followed by the byte, digram and trigram counts, each as a
+    /// 32-bit big-endian number of entries and then the entries, as
+    /// the bytes followed by the 32-bit big-endian count.  Only
+    /// nonzero counts are stored.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut letters: Vec<(Vec<u8>, u32)> = self.letters.iter()
+            .enumerate()
+            .filter(|&(_, &c)| c > 0)
+            .map(|(b, &c)| (vec![b as u8], c))
+            .collect();
+        let mut digrams: Vec<(Vec<u8>, u32)> = self.digrams.iter().map(|(k, &c)| (k.to_vec(), c)).collect();
+        let mut trigrams: Vec<(Vec<u8>, u32)> = self.trigrams.iter().map(|(k, &c)| (k.to_vec(), c)).collect();
+        let mut out = MODEL_MAGIC.to_vec();
+        for table in &mut [&mut letters, &mut digrams, &mut trigrams] {
+            table.sort();
+            let mut n = [0; 4];
+            BigEndian::write_u32(&mut n, table.len() as u32);
+            out.extend_from_slice(&n);
+            for &(ref k, c) in table.iter() {
+                out.extend_from_slice(k);
+                BigEndian::write_u32(&mut n, c);
+                out.extend_from_slice(&n);
+            }
+        }
+        out
+    }
+
+    /// Deserialize a model produced by `to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Model, Error> {
+        if !bytes.starts_with(MODEL_MAGIC) {
+            return Err(Error::InvalidLanguageModel);
+        }
+        let mut model = Model {
+            letters: vec![0; 256],
+            digrams: HashMap::new(),
+            trigrams: HashMap::new(),
+            total: 0,
+        };
+        let mut pos = MODEL_MAGIC.len();
+        for width in 1..4 {
+            if bytes.len() < pos + 4 {
+                return Err(Error::InvalidLanguageModel);
+            }
+            let n = BigEndian::read_u32(&bytes[pos..]) as usize;
+            pos += 4;
+            if (bytes.len() - pos) / (width + 4) < n {
+                return Err(Error::InvalidLanguageModel);
+            }
+            for _ in 0..n {
+                let k = &bytes[pos..pos + width];
+                let c = BigEndian::read_u32(&bytes[pos + width..]);
+                pos += width + 4;
+                match width {
+                    1 => {
+                        model.letters[k[0] as usize] = c;
+                        model.total = model.total.saturating_add(c);
+                    }
+                    2 => {
+                        model.digrams.insert([k[0], k[1]], c);
+                    }
+                    _ => {
+                        model.trigrams.insert([k[0], k[1], k[2]], c);
+                    }
+                }
+            }
+        }
+        if pos != bytes.len() {
+            return Err(Error::InvalidLanguageModel);
+        }
+        Ok(model)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Model, Language, LANGUAGES, detect, plausibility, Plausibility, analyze};
+
+    static CORPUS: &'static [u8] =
+        b"It was the best of times, it was the worst of times, it was the age of wisdom, \
+          it was the age of foolishness, it was the epoch of belief, it was the epoch of \
+          incredulity, it was the season of Light, it was the season of Darkness, it was \
+          the spring of hope, it was the winter of despair.";
+
+    #[test]
+    fn score_0() {
+        let model = Model::train(CORPUS);
+        assert!(model.score(b"the worst of the season") > model.score(b"xq#7 zzkv(p Jw\x00 qq"));
+        assert!(model.score(b"") < 0.0);
+
+        let json = Model::train(br#"{"id": 17, "name": "alice", "admin": false}
+                                    {"id": 18, "name": "bob", "admin": true}"#);
+        assert!(json.score(br#"{"id": 4, "name": "eve"}"#) > json.score(b"the id of eve is four"));
+    }
+
+    #[test]
+    fn to_bytes_0() {
+        let model = Model::train(CORPUS);
+        let bytes = model.to_bytes();
+        assert_eq!(model, Model::from_bytes(&bytes).unwrap());
+        assert!(Model::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(Model::from_bytes(&bytes[1..]).is_err());
+        assert_eq!(Model::train(b""), Model::from_bytes(&Model::train(b"").to_bytes()).unwrap());
+    }
+
+    #[test]
+    fn letter_freq_0() {
+        for &l in &LANGUAGES {
+            assert!(l.letter_freq(b'e').unwrap() > l.letter_freq(b'q').unwrap());
+            assert_eq!(None, l.letter_freq(b'1'));
+        }
+        assert_eq!(Some(1740), Language::German.letter_freq(b'E'));
+    }
+
+    #[test]
+    fn detect_0() {
+        assert_eq!(Language::English, detect(CORPUS).0);
+        assert_eq!(Language::German,
+                   detect(b"Es war einmal ein kleines Maedchen, das wohnte mit seiner Mutter \
+                            in einem Haus am Rande des Waldes. Jeden Morgen ging es hinaus und \
+                            suchte nach Beeren und Pilzen fuer das Mittagessen.").0);
+        assert_eq!(Language::French,
+                   detect(b"Il etait une fois une petite fille qui vivait avec sa mere dans une \
+                            maison au bord de la foret. Chaque matin, elle sortait pour chercher \
+                            des fruits et des champignons pour le dejeuner.").0);
+        assert_eq!(Language::Spanish,
+                   detect(b"Habia una vez una nina que vivia con su madre en una casa al borde \
+                            del bosque. Cada manana salia a buscar frutas y setas para la comida \
+                            de la familia, y volvia a casa cantando.").0);
+        assert!(Language::English.score(b"\x00\x01\x02\x03") < Language::English.score(b"xqzj"));
+    }
+
+    #[test]
+    fn plausibility_0() {
+        assert_eq!(Plausibility { printable_ratio: 1.0, control_char_count: 0, utf8_valid: true },
+                   plausibility(b""));
+        assert_eq!(Plausibility { printable_ratio: 1.0, control_char_count: 0, utf8_valid: true },
+                   plausibility(b"Hello,\tworld!\r\n"));
+        assert_eq!(Plausibility { printable_ratio: 0.5, control_char_count: 1, utf8_valid: true },
+                   plausibility(b"ab\x00\xc3\xa4z"));
+        assert_eq!(Plausibility { printable_ratio: 0.5, control_char_count: 0, utf8_valid: false },
+                   plausibility(b"ok\xff\xfe"));
+    }
+
+    #[test]
+    fn analyze_0() {
+        let report = analyze(CORPUS);
+        assert_eq!(CORPUS.len(), report.length);
+        assert_eq!(CORPUS.iter().filter(|b| b.is_ascii_alphabetic()).count(), report.letters());
+        assert_eq!(([b'A', b'S'], 12), report.digram_counts[0]);
+        assert!(report.index_of_coincidence > 0.06 && report.index_of_coincidence < 0.08);
+        assert!((report.entropy - 3.9247).abs() < 1e-4);
+        assert_eq!(Language::English, report.language.0);
+
+        let table = format!("{}", report);
+        assert!(table.starts_with("length 286 bytes, 217 letters, 100.0% printable\n"));
+        assert!(table.contains("\nE          27     12.4%    12.3%\nT          27     12.4%     9.6%\n"));
+        assert!(table.ends_with("top digrams: AS 12 IT 11 HE 10 OF 10 TH 10 WA 10 ES 6 LI 4 IN 3 AG 2"));
+
+        let empty = analyze(b"");
+        assert_eq!(0, empty.letters());
+        assert_eq!(0.0, empty.entropy);
+        assert!(empty.digram_counts.is_empty());
+        assert_eq!(8.0, analyze(&(0..=255).collect::<Vec<u8>>()).entropy);
+    }
+
+    quickcheck! {
+        fn prop_to_bytes(corpus: Vec<u8>) -> bool {
+            let model = Model::train(&corpus);
+            Model::from_bytes(&model.to_bytes()).map(|m| m == model).unwrap_or(false)
+        }
+    }
+}
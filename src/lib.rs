@@ -7,16 +7,32 @@
 #[cfg(test)]
 #[macro_use]
 extern crate quickcheck;
-extern crate byteorder;
-extern crate rand;
 
 pub mod error;
 pub mod codec;
+pub mod data;
 pub mod xor;
 pub mod distance;
 pub mod language;
 pub mod cipher;
 pub mod padding;
+pub mod compress;
+pub mod bignum;
+pub mod math;
+pub mod rsa;
+pub mod dsa;
+pub mod ec;
+pub mod sign;
+pub mod hash;
+pub mod mac;
+pub mod kdf;
+pub mod protocol;
+pub mod attack;
+pub mod oracle;
+pub mod web;
+pub mod fuzz;
+#[cfg(feature = "net")]
+pub mod net;
 
 pub mod random {
     use ::rand::Rand;
@@ -0,0 +1,103 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! CMAC (NIST SP 800-38B, RFC 4493) over any block cipher with
+//! 128-bit blocks.  CMAC is CBC-MAC with the last block masked by one
+//! of two subkeys derived from the encryption of the zero block, which
+//! makes it secure for messages of varying length.
+
+use crate::cipher::block::BlockCipher;
+
+/// Multiply `block` by `x` in GF(2^128), with the polynomial
+/// `x^128 + x^7 + x^2 + x + 1` and the most significant bit first.
+/// This is the doubling operation used for the CMAC subkeys and for
+/// S2V in `cipher::siv`.
+pub fn dbl(block: &[u8; 16]) -> [u8; 16] {
+    let n = u128::from_be_bytes(*block);
+    let r = if n >> 127 == 1 { 0x87 } else { 0 };
+    ((n << 1) ^ r).to_be_bytes()
+}
+
+/// Return the subkeys `K1` and `K2` for `cipher`.
+pub fn subkeys<C: BlockCipher<16> + ?Sized>(cipher: &C) -> ([u8; 16], [u8; 16]) {
+    let mut l = [0; 16];
+    cipher.encrypt_block(&mut l);
+    let k1 = dbl(&l);
+    let k2 = dbl(&k1);
+    (k1, k2)
+}
+
+/// Calculate the CMAC of `message` with `cipher`.
+///
+/// # Example
+/// ```
+/// use cryptopals::cipher::aes::{Aes, AesKey};
+/// use cryptopals::mac::cmac::cmac;
+/// let tag = cmac(&Aes::with_key(&AesKey::from([0; 16])), b"message");
+/// assert_eq!(16, tag.len());
+/// ```
+pub fn cmac<C: BlockCipher<16> + ?Sized>(cipher: &C, message: &[u8]) -> [u8; 16] {
+    let (k1, k2) = subkeys(cipher);
+    let n = if message.is_empty() { 1 } else { (message.len() + 15) / 16 };
+    let (init, last) = message.split_at(16 * (n - 1));
+
+    let mut mac = [0; 16];
+    for chunk in init.chunks(16) {
+        for x in 0..16 {
+            mac[x] ^= chunk[x];
+        }
+        cipher.encrypt_block(&mut mac);
+    }
+
+    let mut m = [0; 16];
+    m[..last.len()].copy_from_slice(last);
+    let k = if last.len() == 16 {
+        k1
+    } else {
+        m[last.len()] = 0x80;
+        k2
+    };
+    for x in 0..16 {
+        mac[x] ^= m[x] ^ k[x];
+    }
+    cipher.encrypt_block(&mut mac);
+    mac
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{cmac, subkeys};
+    use crate::cipher::aes::{Aes, AesKey};
+    use crate::codec::hex;
+
+    // Examples from RFC 4493, section 4.
+
+    static M: &'static str =
+        "6bc1bee22e409f96e93d7e117393172aae2d8a571e03ac9c9eb76fac45af8e51\
+         30c81c46a35ce411e5fbc1191a0a52eff69f2445df4f9b17ad2b417be66c3710";
+
+    fn aes() -> Aes {
+        Aes::with_key(&AesKey::from_hex("2b7e151628aed2a6abf7158809cf4f3c").unwrap())
+    }
+
+    #[test]
+    fn cmac_subkeys() {
+        let (k1, k2) = subkeys(&aes());
+        assert_eq!("fbeed618357133667c85e08f7236a8de", hex::encode(&k1));
+        assert_eq!("f7ddac306ae266ccf90bc11ee46d513b", hex::encode(&k2));
+    }
+
+    #[test]
+    fn cmac_rfc4493() {
+        let m = hex::decode(M).unwrap();
+        let cases = [
+            (0, "bb1d6929e95937287fa37d129b756746"),
+            (16, "070a16b46b4d4144f79bdd9dd04a287c"),
+            (40, "dfa66747de9ae63030ca32611497c827"),
+            (64, "51f0bebf7e3b9d92fc49741779363cfe"),
+        ];
+        for &(len, tag) in &cases {
+            assert_eq!(tag, hex::encode(&cmac(&aes(), &m[..len])));
+        }
+    }
+}
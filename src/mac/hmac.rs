@@ -0,0 +1,110 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! HMAC (RFC 2104), generic over the hash function.
+
+use std::marker::PhantomData;
+
+use crate::attack::password::TargetHash;
+use crate::hash::Digest;
+
+/// Calculate the HMAC of `message` under `key`, using the hash
+/// function `D`.
+///
+/// # Example
+/// ```
+/// use cryptopals::hash::sha256::Sha256;
+/// use cryptopals::mac::hmac::hmac;
+/// let tag = hmac::<Sha256>(b"key", b"message");
+/// assert_eq!(32, tag.len());
+/// ```
+pub fn hmac<D: Digest>(key: &[u8], message: &[u8]) -> Vec<u8> {
+    let mut k = if key.len() > D::BLOCK_SIZE {
+        D::digest(key)
+    } else {
+        key.to_vec()
+    };
+    k.resize(D::BLOCK_SIZE, 0);
+
+    let mut inner = D::new();
+    inner.update(&k.iter().map(|b| b ^ 0x36).collect::<Vec<u8>>());
+    inner.update(message);
+    let inner_hash = inner.finish();
+
+    let mut outer = D::new();
+    outer.update(&k.iter().map(|b| b ^ 0x5c).collect::<Vec<u8>>());
+    outer.update(&inner_hash);
+    outer.finish()
+}
+
+/// Compare two MACs without leaking the position of the first
+/// difference through timing.
+pub fn verify(expected: &[u8], actual: &[u8]) -> bool {
+    if expected.len() != actual.len() {
+        return false;
+    }
+    expected.iter().zip(actual).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+/// A captured HMAC `tag` of `message`, as a target for password
+/// cracking when the key is a password.
+pub struct HmacTarget<D> {
+    message: Vec<u8>,
+    tag: Vec<u8>,
+    digest: PhantomData<D>,
+}
+
+impl<D: Digest> HmacTarget<D> {
+    /// Create a target for the HMAC `tag` of `message`.
+    pub fn new(message: &[u8], tag: &[u8]) -> HmacTarget<D> {
+        HmacTarget {
+            message: message.to_vec(),
+            tag: tag.to_vec(),
+            digest: PhantomData,
+        }
+    }
+}
+
+impl<D: Digest + Sync> TargetHash for HmacTarget<D> {
+    fn matches(&self, candidate: &[u8]) -> bool {
+        verify(&hmac::<D>(candidate, &self.message), &self.tag)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{hmac, verify};
+    use crate::hash::sha256::Sha256;
+    use crate::codec;
+
+    /// RFC 4231, test case 1.
+    #[test]
+    fn hmac_sha256_1() {
+        let key = [0x0b; 20];
+        assert_eq!("b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7",
+                   codec::hex::encode(&hmac::<Sha256>(&key, b"Hi There")));
+    }
+
+    /// RFC 4231, test case 2.
+    #[test]
+    fn hmac_sha256_2() {
+        assert_eq!("5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+                   codec::hex::encode(&hmac::<Sha256>(b"Jefe", b"what do ya want for nothing?")));
+    }
+
+    /// RFC 4231, test case 6 (key longer than the block size).
+    #[test]
+    fn hmac_sha256_long_key() {
+        let key = [0xaa; 131];
+        let msg = b"Test Using Larger Than Block-Size Key - Hash Key First";
+        assert_eq!("60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54",
+                   codec::hex::encode(&hmac::<Sha256>(&key, msg)));
+    }
+
+    #[test]
+    fn verify_0() {
+        assert!(verify(b"abc", b"abc"));
+        assert!(!verify(b"abc", b"abd"));
+        assert!(!verify(b"abc", b"ab"));
+    }
+}
@@ -0,0 +1,10 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! Message authentication codes.  Like everything else in this
+//! crate, these are for learning only.  Do not use them for
+//! production!
+
+pub mod cmac;
+pub mod hmac;
+pub mod poly1305;
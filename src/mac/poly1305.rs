@@ -0,0 +1,101 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! The Poly1305 one-time authenticator, as specified in RFC 7539.
+//! The 32-byte key is split into `r`, which is clamped and used to
+//! evaluate the message as a polynomial modulo the prime `2^130 - 5`,
+//! and `s`, which is added to the result.  A key must never be used
+//! for more than one message, see `attack::poly1305`.  The arithmetic
+//! is done with `BigUint` for clarity, not speed.
+
+use crate::bignum::BigUint;
+use crate::mac::hmac;
+
+/// Return the prime `2^130 - 5`.
+pub fn prime() -> BigUint {
+    &(&BigUint::one() << 130) - &BigUint::from_u64(5)
+}
+
+/// Interpret `bytes` as a little-endian number.
+pub fn from_le(bytes: &[u8]) -> BigUint {
+    let mut be = bytes.to_vec();
+    be.reverse();
+    BigUint::from_bytes_be(&be)
+}
+
+/// Return the low 128 bits of `n` as 16 little-endian bytes.
+pub fn to_le(n: &BigUint) -> [u8; 16] {
+    let low = n % &(&BigUint::one() << 128);
+    let mut result = [0; 16];
+    result.copy_from_slice(&low.to_bytes_be_padded(16));
+    result.reverse();
+    result
+}
+
+/// Clear the bits of `r` required by the specification.
+pub fn clamp(r: &mut [u8; 16]) {
+    for &i in &[3, 7, 11, 15] {
+        r[i] &= 0x0f;
+    }
+    for &i in &[4, 8, 12] {
+        r[i] &= 0xfc;
+    }
+}
+
+/// Split `msg` into 16-byte blocks and return them as numbers, each
+/// with a one bit appended above its most significant byte.
+pub fn blocks(msg: &[u8]) -> Vec<BigUint> {
+    msg.chunks(16).map(|chunk| {
+        let mut b = chunk.to_vec();
+        b.push(1);
+        from_le(&b)
+    }).collect()
+}
+
+/// Compute the authentication tag of `msg` under the one-time key
+/// `key`.
+pub fn mac(key: &[u8; 32], msg: &[u8]) -> [u8; 16] {
+    let mut rbytes = [0; 16];
+    rbytes.copy_from_slice(&key[..16]);
+    clamp(&mut rbytes);
+    let r = from_le(&rbytes);
+    let s = from_le(&key[16..]);
+    let p = prime();
+    let acc = blocks(msg).iter().fold(BigUint::zero(), |acc, n| &(&(&acc + n) * &r) % &p);
+    to_le(&(&acc + &s))
+}
+
+/// Check that `tag` is the authentication tag of `msg` under `key`.
+pub fn verify(key: &[u8; 32], msg: &[u8], tag: &[u8]) -> bool {
+    hmac::verify(&mac(key, msg), tag)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{mac, verify};
+    use crate::codec::hex;
+
+    #[test]
+    fn rfc7539() {
+        // RFC 7539, section 2.5.2.
+        let mut key = [0; 32];
+        key.copy_from_slice(&hex::decode("85d6be7857556d337f4452fe42d506a80103808afb0db2fd4abff6af4149f51b")
+                            .unwrap());
+        let msg = b"Cryptographic Forum Research Group";
+        let tag = mac(&key, msg);
+        assert_eq!(hex::decode("a8061dc1305136c6c22b8baf0c0127a9").unwrap(), tag.to_vec());
+        assert!(verify(&key, msg, &tag));
+        assert!(!verify(&key, b"Cryptographic Forum Research Grouq", &tag));
+    }
+
+    #[test]
+    fn lengths() {
+        let mut key = [0; 32];
+        for i in 0..32 {
+            key[i] = i as u8;
+        }
+        let msg: Vec<u8> = (0..100).collect();
+        assert_eq!(hex::decode("101112131415161718191a1b1c1d1e1f").unwrap(), mac(&key, b"").to_vec());
+        assert_eq!(hex::decode("2c48db4b08964d7e67950fbd89760c4d").unwrap(), mac(&key, &msg).to_vec());
+    }
+}
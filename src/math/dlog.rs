@@ -0,0 +1,207 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! Generic discrete logarithm algorithms: Pollard's kangaroo for
+//! logarithms known to lie in an interval, and Pollard's rho for
+//! groups of prime order.  Both need memory independent of the size
+//! of the problem and time about its square root.
+
+use std::ops::Range;
+
+use crate::bignum::BigUint;
+
+/// A group in which discrete logarithms are to be computed, written
+/// multiplicatively.
+pub trait Group {
+    /// Type of group elements.
+    type Element: Clone + Eq;
+
+    /// Return the neutral element.
+    fn identity(&self) -> Self::Element;
+
+    /// Combine two elements.
+    fn op(&self, a: &Self::Element, b: &Self::Element) -> Self::Element;
+
+    /// Map an element to a number.  The random walks are steered by
+    /// this, so it should look random, but it must only depend on
+    /// the element.
+    fn hash(&self, a: &Self::Element) -> u64;
+
+    /// Raise `a` to the power `k`, by square-and-multiply.
+    fn pow(&self, a: &Self::Element, k: &BigUint) -> Self::Element {
+        let mut result = self.identity();
+        for i in (0..k.bits()).rev() {
+            result = self.op(&result, &result);
+            if k.bit(i) {
+                result = self.op(&result, a);
+            }
+        }
+        result
+    }
+}
+
+/// The multiplicative group of integers modulo a prime `p`, as used
+/// by Diffie-Hellman and DSA.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ModP {
+    /// The prime modulus.
+    pub p: BigUint,
+}
+
+impl Group for ModP {
+    type Element = BigUint;
+
+    fn identity(&self) -> BigUint {
+        BigUint::one()
+    }
+
+    fn op(&self, a: &BigUint, b: &BigUint) -> BigUint {
+        &(a * b) % &self.p
+    }
+
+    fn hash(&self, a: &BigUint) -> u64 {
+        (a % &BigUint::from_u64(1 << 32)).to_u64().unwrap()
+    }
+
+    fn pow(&self, a: &BigUint, k: &BigUint) -> BigUint {
+        a.modpow(k, &self.p)
+    }
+}
+
+/// Find the discrete logarithm of `target` to the base `base` in
+/// `range` with Pollard's kangaroo algorithm.  The width of the range
+/// must fit into 64 bits.  Returns `None` if the logarithm is not in
+/// the range, or, rarely, if the wild kangaroo gets past the tame one
+/// without landing on its trap.
+///
+/// # Example
+/// ```
+/// use cryptopals::bignum::BigUint;
+/// use cryptopals::math::dlog::{kangaroo, ModP};
+/// let n = BigUint::from_u64;
+/// let group = ModP { p: n(1000003) };
+/// let y = n(2).modpow(&n(1234), &group.p);
+/// assert_eq!(Some(n(1234)), kangaroo(&n(2), &y, &(n(1000)..n(2000)), &group));
+/// ```
+pub fn kangaroo<G: Group>(base: &G::Element, target: &G::Element, range: &Range<BigUint>, group: &G)
+                          -> Option<BigUint> {
+    let (a, b) = (&range.start, &range.end);
+    let width = (b - a).to_u64().expect("interval too wide");
+    // Jumps are powers of two below 2^k, chosen so that the mean jump
+    // is about half the square root of the width.
+    let k = (64 - width.leading_zeros() as u64) / 2 + 3;
+    let jumps: Vec<G::Element> = (0..k).map(|i| group.pow(base, &BigUint::from_u64(1 << i))).collect();
+    let jump = |x: &G::Element| (group.hash(x) % k) as usize;
+
+    let mean = ((1 << k) - 1) / k;
+    let mut x_tame = 0;
+    let mut y_tame = group.pow(base, b);
+    for _ in 0..4 * mean {
+        let i = jump(&y_tame);
+        x_tame += 1 << i;
+        y_tame = group.op(&y_tame, &jumps[i]);
+    }
+
+    let mut x_wild = 0;
+    let mut y_wild = target.clone();
+    while x_wild < width + x_tame {
+        let i = jump(&y_wild);
+        x_wild += 1 << i;
+        y_wild = group.op(&y_wild, &jumps[i]);
+        if y_wild == y_tame {
+            let x = &(b + &BigUint::from_u64(x_tame)) - &BigUint::from_u64(x_wild);
+            return if x < *a || x >= *b { None } else { Some(x) };
+        }
+    }
+    None
+}
+
+/// Number of random starting points `rho` tries before giving up.
+const RHO_ATTEMPTS: usize = 8;
+
+/// Find the discrete logarithm of `target` to the base `base`, which
+/// must have prime order `order`, with Pollard's rho algorithm.  The
+/// walk multiplies by `base` or `target` or squares, depending on the
+/// hash of the current element, and Floyd's cycle finding detects
+/// when it repeats.  Returns `None` if `target` is not a power of
+/// `base`.
+pub fn rho<G: Group>(base: &G::Element, target: &G::Element, order: &BigUint, group: &G)
+                     -> Option<BigUint> {
+    let one = BigUint::one();
+    // Each state is an element x = base^a * target^b.
+    let step = |&(ref x, ref a, ref b): &(G::Element, BigUint, BigUint)| match group.hash(x) % 3 {
+        0 => (group.op(x, target), a.clone(), &(b + &one) % order),
+        1 => (group.op(x, x), &(a + a) % order, &(b + b) % order),
+        _ => (group.op(x, base), &(a + &one) % order, b.clone()),
+    };
+    for _ in 0..RHO_ATTEMPTS {
+        let a0 = BigUint::random_below(order);
+        let start = (group.pow(base, &a0), a0, BigUint::zero());
+        let mut tortoise = step(&start);
+        let mut hare = step(&tortoise);
+        while tortoise.0 != hare.0 {
+            tortoise = step(&tortoise);
+            hare = step(&step(&hare));
+        }
+        // a1 + b1*x = a2 + b2*x (mod order)
+        let (_, a1, b1) = tortoise;
+        let (_, a2, b2) = hare;
+        let db = &(&(&b2 + order) - &b1) % order;
+        let da = &(&(&a1 + order) - &a2) % order;
+        if let Some(inv) = db.modinv(order) {
+            let x = &(&da * &inv) % order;
+            if group.pow(base, &x) == *target {
+                return Some(x);
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Group, ModP, kangaroo, rho};
+    use crate::bignum::BigUint;
+
+    // The group from challenge 58, with a subgroup of order q.
+    fn challenge58() -> (ModP, BigUint, BigUint) {
+        let group = ModP {
+            p: BigUint::from_hex(
+                "db020645333c52a8d8bd194950cbd48ddf752bae8f346150c6410dba6befdbc6\
+                 cf93d7cfc4568ffb017b28bef26242493c606596b7ff8625055f73e888b86117").unwrap(),
+        };
+        let g = BigUint::from_hex(
+            "be4ed76592b0fc7a8f2a160840c664bd8a4e0dff8ded0b2ed0843714c3b7bd12\
+             ee50cb56a829a999ca95714a520ba0c080e7a5866309e4bbcce1f897eafb77d").unwrap();
+        let q = BigUint::from_hex("fc1299300b7631763bd8f7440041e785").unwrap();
+        (group, g, q)
+    }
+
+    #[test]
+    fn kangaroo_mod_p() {
+        let (group, g, q) = challenge58();
+        assert!(group.pow(&g, &q).is_one());
+        let n = BigUint::from_u64;
+        let x = n(705485);
+        let y = group.pow(&g, &x);
+        assert_eq!(Some(x.clone()), kangaroo(&g, &y, &(n(0)..n(1 << 20)), &group));
+        assert_eq!(Some(x), kangaroo(&g, &y, &(n(1 << 19)..n(1 << 20)), &group));
+        assert_eq!(None, kangaroo(&g, &y, &(n(1 << 20)..n(1 << 21)), &group));
+    }
+
+    #[test]
+    fn rho_mod_p() {
+        // 51539607733 = 12 * 4294967311 + 1, and 2^12 has order
+        // 4294967311, which is prime.
+        let n = BigUint::from_u64;
+        let group = ModP { p: n(51539607733) };
+        let (g, q) = (n(4096), n(4294967311));
+        assert!(group.pow(&g, &q).is_one());
+        for &x in [1, 2, 1234567, 4294967310].iter() {
+            let y = group.pow(&g, &n(x));
+            assert_eq!(Some(n(x)), rho(&g, &y, &q, &group));
+        }
+        // 3 is not in the subgroup.
+        assert_eq!(None, rho(&g, &n(3), &q, &group));
+    }
+}
@@ -0,0 +1,422 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! Arithmetic in the field GF(2^128) as used by GCM, and polynomials
+//! over that field, including root finding.
+//!
+//! Field elements are stored with the coefficient of `x^i` in bit `i`,
+//! reduced modulo `x^128 + x^7 + x^2 + x + 1`.  GCM encodes blocks with
+//! the coefficient of `x^0` in the most significant bit of the first
+//! byte, so `from_block` and `to_block` reverse the bit order.
+
+use std::ops::{Add, Sub, Mul, Div};
+
+use byteorder::{BigEndian, ByteOrder};
+
+/// Element of GF(2^128).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Gf128(pub u128);
+
+/// The low terms `x^7 + x^2 + x + 1` of the reduction polynomial.
+const REDUCTION: u128 = 0x87;
+
+impl Gf128 {
+    /// Return the additive identity.
+    pub fn zero() -> Gf128 {
+        Gf128(0)
+    }
+
+    /// Return the multiplicative identity.
+    pub fn one() -> Gf128 {
+        Gf128(1)
+    }
+
+    /// Decode the 16-byte GCM block `block`.
+    pub fn from_block(block: &[u8]) -> Gf128 {
+        Gf128(read_u128(block).reverse_bits())
+    }
+
+    /// Encode as a 16-byte GCM block.
+    pub fn to_block(&self) -> [u8; 16] {
+        let mut block = [0; 16];
+        write_u128(&mut block, self.0.reverse_bits());
+        block
+    }
+
+    /// Return a uniformly chosen random element.
+    pub fn random() -> Gf128 {
+        let mut block = [0; 16];
+        crate::random::fill_bytes(&mut block);
+        Gf128(read_u128(&block))
+    }
+
+    /// Return true if this is the zero element.
+    pub fn is_zero(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Raise to the power `e`.
+    pub fn pow(&self, e: u128) -> Gf128 {
+        let mut result = Gf128::one();
+        let mut base = *self;
+        let mut e = e;
+        while e != 0 {
+            if e & 1 != 0 {
+                result = result * base;
+            }
+            base = base * base;
+            e >>= 1;
+        }
+        result
+    }
+
+    /// Return the multiplicative inverse, or `None` for zero.
+    pub fn inverse(&self) -> Option<Gf128> {
+        if self.is_zero() {
+            None
+        } else {
+            // a^(2^128 - 1) = 1 for all non-zero a.
+            Some(self.pow(!0 - 1))
+        }
+    }
+}
+
+fn read_u128(b: &[u8]) -> u128 {
+    (BigEndian::read_u64(&b[..8]) as u128) << 64 | BigEndian::read_u64(&b[8..16]) as u128
+}
+
+fn write_u128(b: &mut [u8], x: u128) {
+    BigEndian::write_u64(&mut b[..8], (x >> 64) as u64);
+    BigEndian::write_u64(&mut b[8..16], x as u64);
+}
+
+impl Add for Gf128 {
+    type Output = Gf128;
+    fn add(self, other: Gf128) -> Gf128 {
+        Gf128(self.0 ^ other.0)
+    }
+}
+
+impl Sub for Gf128 {
+    type Output = Gf128;
+    fn sub(self, other: Gf128) -> Gf128 {
+        Gf128(self.0 ^ other.0)
+    }
+}
+
+impl Mul for Gf128 {
+    type Output = Gf128;
+    fn mul(self, other: Gf128) -> Gf128 {
+        let mut a = self.0;
+        let mut b = other.0;
+        let mut r = 0;
+        while b != 0 {
+            if b & 1 != 0 {
+                r ^= a;
+            }
+            b >>= 1;
+            let carry = a >> 127;
+            a <<= 1;
+            if carry != 0 {
+                a ^= REDUCTION;
+            }
+        }
+        Gf128(r)
+    }
+}
+
+impl Div for Gf128 {
+    type Output = Gf128;
+    /// # Panics
+    /// Panics when dividing by zero.
+    fn div(self, other: Gf128) -> Gf128 {
+        self * other.inverse().expect("division by zero")
+    }
+}
+
+/// Polynomial with coefficients in GF(2^128).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Poly {
+    /// Coefficients, starting with the constant term.  There are no
+    /// trailing zero coefficients.
+    coeffs: Vec<Gf128>,
+}
+
+impl Poly {
+    /// Create a polynomial from its coefficients, starting with the
+    /// constant term.
+    pub fn new(coeffs: Vec<Gf128>) -> Poly {
+        let mut p = Poly { coeffs: coeffs };
+        p.normalize();
+        p
+    }
+
+    /// Return the zero polynomial.
+    pub fn zero() -> Poly {
+        Poly { coeffs: Vec::new() }
+    }
+
+    /// Return the constant polynomial `c`.
+    pub fn constant(c: Gf128) -> Poly {
+        Poly::new(vec![c])
+    }
+
+    /// Return the polynomial `x`.
+    pub fn x() -> Poly {
+        Poly::new(vec![Gf128::zero(), Gf128::one()])
+    }
+
+    fn normalize(&mut self) {
+        while self.coeffs.last().map_or(false, |c| c.is_zero()) {
+            self.coeffs.pop();
+        }
+    }
+
+    /// Return the coefficients, starting with the constant term.
+    pub fn coeffs(&self) -> &[Gf128] {
+        &self.coeffs
+    }
+
+    /// Return the degree, or `None` for the zero polynomial.
+    pub fn degree(&self) -> Option<usize> {
+        if self.coeffs.is_empty() { None } else { Some(self.coeffs.len() - 1) }
+    }
+
+    /// Return true if this is the zero polynomial.
+    pub fn is_zero(&self) -> bool {
+        self.coeffs.is_empty()
+    }
+
+    /// Divide by the leading coefficient.  The zero polynomial is
+    /// returned unchanged.
+    pub fn monic(&self) -> Poly {
+        match self.coeffs.last() {
+            None => Poly::zero(),
+            Some(&lead) => {
+                let inv = lead.inverse().unwrap();
+                Poly::new(self.coeffs.iter().map(|&c| c * inv).collect())
+            }
+        }
+    }
+
+    /// Evaluate at `x`.
+    pub fn eval(&self, x: Gf128) -> Gf128 {
+        self.coeffs.iter().rev().fold(Gf128::zero(), |acc, &c| acc * x + c)
+    }
+
+    /// Divide by `d`, returning quotient and remainder.
+    ///
+    /// # Panics
+    /// Panics if `d` is the zero polynomial.
+    pub fn divrem(&self, d: &Poly) -> (Poly, Poly) {
+        let dd = d.degree().expect("division by zero polynomial");
+        let inv = d.coeffs[dd].inverse().unwrap();
+        let mut r = self.coeffs.clone();
+        if r.len() <= dd {
+            return (Poly::zero(), self.clone());
+        }
+        let mut q = vec![Gf128::zero(); r.len() - dd];
+        for i in (0..q.len()).rev() {
+            let c = r[i + dd] * inv;
+            q[i] = c;
+            for j in 0..dd + 1 {
+                r[i + j] = r[i + j] - c * d.coeffs[j];
+            }
+        }
+        (Poly::new(q), Poly::new(r))
+    }
+
+    /// Return the monic greatest common divisor with `other`.
+    pub fn gcd(&self, other: &Poly) -> Poly {
+        let mut a = self.clone();
+        let mut b = other.clone();
+        while !b.is_zero() {
+            let r = a.divrem(&b).1;
+            a = b;
+            b = r;
+        }
+        a.monic()
+    }
+
+    /// Multiply by `other` modulo `m`.
+    pub fn mulmod(&self, other: &Poly, m: &Poly) -> Poly {
+        (self * other).divrem(m).1
+    }
+
+    /// Return all roots in GF(2^128), in ascending order.  Repeated
+    /// roots are only reported once, and the zero polynomial is
+    /// treated as having no roots.
+    ///
+    /// The product of all linear factors is `gcd(f, x^(2^128) - x)`.
+    /// It is split by the Cantor-Zassenhaus method for characteristic
+    /// two: for random `a`, the trace of `a*x` modulo the product is
+    /// zero for about half of the roots, so its gcd with the product
+    /// is a proper factor with good probability.
+    pub fn roots(&self) -> Vec<Gf128> {
+        let mut roots = Vec::new();
+        if self.degree().map_or(true, |d| d == 0) {
+            return roots;
+        }
+        let f = self.monic();
+        let mut xq = Poly::x().divrem(&f).1;
+        for _ in 0..128 {
+            xq = xq.mulmod(&xq, &f);
+        }
+        let linear = f.gcd(&(&xq + &Poly::x()));
+        split_linear(&linear, &mut roots);
+        roots.sort();
+        roots
+    }
+}
+
+/// Find the roots of `g`, which must be monic and a product of
+/// distinct linear factors, and append them to `roots`.
+fn split_linear(g: &Poly, roots: &mut Vec<Gf128>) {
+    match g.degree() {
+        None | Some(0) => (),
+        Some(1) => roots.push(g.coeffs[0]),
+        Some(_) => loop {
+            let ax = Poly::new(vec![Gf128::zero(), Gf128::random()]).divrem(g).1;
+            let mut t = ax.clone();
+            let mut s = ax;
+            for _ in 1..128 {
+                s = s.mulmod(&s, g);
+                t = &t + &s;
+            }
+            let h = g.gcd(&t);
+            if h.degree().map_or(false, |d| d > 0 && Some(d) < g.degree()) {
+                split_linear(&h, roots);
+                split_linear(&g.divrem(&h).0, roots);
+                return;
+            }
+        },
+    }
+}
+
+impl<'a> Add<&'a Poly> for &'a Poly {
+    type Output = Poly;
+    fn add(self, other: &Poly) -> Poly {
+        let n = ::std::cmp::max(self.coeffs.len(), other.coeffs.len());
+        let get = |p: &Poly, i: usize| p.coeffs.get(i).cloned().unwrap_or(Gf128::zero());
+        Poly::new((0..n).map(|i| get(self, i) + get(other, i)).collect())
+    }
+}
+
+impl<'a> Sub<&'a Poly> for &'a Poly {
+    type Output = Poly;
+    fn sub(self, other: &Poly) -> Poly {
+        self + other
+    }
+}
+
+impl<'a> Mul<&'a Poly> for &'a Poly {
+    type Output = Poly;
+    fn mul(self, other: &Poly) -> Poly {
+        if self.is_zero() || other.is_zero() {
+            return Poly::zero();
+        }
+        let mut r = vec![Gf128::zero(); self.coeffs.len() + other.coeffs.len() - 1];
+        for (i, &a) in self.coeffs.iter().enumerate() {
+            for (j, &b) in other.coeffs.iter().enumerate() {
+                r[i + j] = r[i + j] + a * b;
+            }
+        }
+        Poly::new(r)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Gf128, Poly};
+
+    fn elem(hi: u64, lo: u64) -> Gf128 {
+        Gf128((hi as u128) << 64 | lo as u128)
+    }
+
+    #[test]
+    fn block_roundtrip() {
+        let mut block = [0u8; 16];
+        block[0] = 0x80;
+        assert_eq!(Gf128::one(), Gf128::from_block(&block));
+        assert_eq!(block, Gf128::one().to_block());
+    }
+
+    #[test]
+    fn reduction() {
+        let x127 = Gf128(1 << 127);
+        let x = Gf128(2);
+        assert_eq!(Gf128(0x87), x127 * x);
+    }
+
+    #[test]
+    fn inverse() {
+        assert_eq!(None, Gf128::zero().inverse());
+        assert_eq!(Some(Gf128::one()), Gf128::one().inverse());
+    }
+
+    #[test]
+    fn poly_divrem() {
+        let a = Poly::new(vec![Gf128(3), Gf128(5), Gf128(7), Gf128(11)]);
+        let b = Poly::new(vec![Gf128(13), Gf128(1)]);
+        let (q, r) = a.divrem(&b);
+        assert_eq!(a, &(&q * &b) + &r);
+        assert_eq!(Some(0), r.degree());
+    }
+
+    #[test]
+    fn poly_roots() {
+        let roots = vec![Gf128(1), elem(0xdeadbeef, 42), elem(17, 0xcafebabe), Gf128(0)];
+        let mut p = Poly::constant(Gf128(99));
+        for &r in &roots {
+            p = &p * &Poly::new(vec![r, Gf128::one()]);
+        }
+        let mut expected = roots.clone();
+        expected.sort();
+        assert_eq!(expected, p.roots());
+        for &r in &roots {
+            assert!(p.eval(r).is_zero());
+        }
+    }
+
+    #[test]
+    fn poly_roots_subfield() {
+        // x^2 + x + 1 is irreducible over GF(2), but splits in GF(4),
+        // which is contained in GF(2^128).  Its roots are the
+        // primitive third roots of unity.
+        let p = Poly::new(vec![Gf128(1), Gf128(1), Gf128(1)]);
+        let roots = p.roots();
+        assert_eq!(2, roots.len());
+        for &r in &roots {
+            assert_eq!(Gf128::one(), r * r * r);
+        }
+    }
+
+    #[test]
+    fn poly_roots_degenerate() {
+        assert!(Poly::zero().roots().is_empty());
+        assert!(Poly::constant(Gf128(5)).roots().is_empty());
+        assert_eq!(vec![Gf128(7)], Poly::new(vec![Gf128(7), Gf128(1)]).roots());
+    }
+
+    quickcheck! {
+        fn prop_mul_commutative(a: (u64, u64), b: (u64, u64)) -> bool {
+            let (a, b) = (elem(a.0, a.1), elem(b.0, b.1));
+            a * b == b * a
+        }
+
+        fn prop_distributive(a: (u64, u64), b: (u64, u64), c: (u64, u64)) -> bool {
+            let (a, b, c) = (elem(a.0, a.1), elem(b.0, b.1), elem(c.0, c.1));
+            a * (b + c) == a * b + a * c
+        }
+
+        fn prop_inverse(a: (u64, u64)) -> bool {
+            let a = elem(a.0, a.1);
+            a.is_zero() || a * a.inverse().unwrap() == Gf128::one()
+        }
+
+        fn prop_block_roundtrip(a: (u64, u64)) -> bool {
+            let a = elem(a.0, a.1);
+            Gf128::from_block(&a.to_block()) == a
+        }
+    }
+}
@@ -0,0 +1,201 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! Arithmetic in the field GF(2^8) as used by AES.
+//!
+//! Field elements are bytes with the coefficient of `x^i` in bit `i`,
+//! reduced modulo `x^8 + x^4 + x^3 + x + 1`.  Multiplication and
+//! division go through tables of logarithms and powers of the
+//! generator `x + 1`, which are computed at compile time.
+
+use std::ops::{Add, Sub, Mul, Div};
+
+/// Element of GF(2^8).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Gf256(pub u8);
+
+/// The low terms `x^4 + x^3 + x + 1` of the reduction polynomial.
+const REDUCTION: u8 = 0x1b;
+
+/// The generator `x + 1` of the multiplicative group.
+const GENERATOR: u8 = 0x03;
+
+/// Powers of the generator.  The table is twice as long as the group
+/// order, so that the sum of two logarithms can be looked up without
+/// reducing it first.
+static EXP: [u8; 510] = exp_table();
+
+/// Logarithms to the base of the generator.  The entry for zero is
+/// unused.
+static LOG: [u8; 256] = log_table();
+
+const fn xtime(x: u8) -> u8 {
+    (x << 1) ^ (if x & 0x80 != 0 { REDUCTION } else { 0x00 })
+}
+
+/// Multiply by shifting and adding, without the tables.
+const fn mul_slow(a: u8, b: u8) -> u8 {
+    let mut a = a;
+    let mut b = b;
+    let mut product = 0;
+    while b != 0 {
+        if b & 1 != 0 {
+            product ^= a;
+        }
+        a = xtime(a);
+        b >>= 1;
+    }
+    product
+}
+
+const fn exp_table() -> [u8; 510] {
+    let mut table = [0; 510];
+    let mut x = 1;
+    let mut i = 0;
+    while i < 510 {
+        table[i] = x;
+        x = mul_slow(x, GENERATOR);
+        i += 1;
+    }
+    table
+}
+
+const fn log_table() -> [u8; 256] {
+    let exp = exp_table();
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 255 {
+        table[exp[i] as usize] = i as u8;
+        i += 1;
+    }
+    table
+}
+
+impl Gf256 {
+    /// Return the additive identity.
+    pub fn zero() -> Gf256 {
+        Gf256(0)
+    }
+
+    /// Return the multiplicative identity.
+    pub fn one() -> Gf256 {
+        Gf256(1)
+    }
+
+    /// Return true if this is the zero element.
+    pub fn is_zero(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Multiply by `x`.
+    pub fn xtime(&self) -> Gf256 {
+        Gf256(xtime(self.0))
+    }
+
+    /// Return the `e`-th power of the generator `x + 1`.
+    pub fn exp(e: usize) -> Gf256 {
+        Gf256(EXP[e % 255])
+    }
+
+    /// Return the logarithm to the base `x + 1`, or `None` for zero.
+    pub fn log(&self) -> Option<usize> {
+        if self.is_zero() {
+            None
+        } else {
+            Some(LOG[self.0 as usize] as usize)
+        }
+    }
+
+    /// Raise to the power `e`.
+    pub fn pow(&self, e: usize) -> Gf256 {
+        match self.log() {
+            None if e == 0 => Gf256::one(),
+            None => Gf256::zero(),
+            Some(l) => Gf256::exp(l * (e % 255)),
+        }
+    }
+
+    /// Return the multiplicative inverse, or `None` for zero.
+    pub fn inverse(&self) -> Option<Gf256> {
+        self.log().map(|l| Gf256(EXP[255 - l]))
+    }
+}
+
+impl Add for Gf256 {
+    type Output = Gf256;
+    fn add(self, other: Gf256) -> Gf256 {
+        Gf256(self.0 ^ other.0)
+    }
+}
+
+impl Sub for Gf256 {
+    type Output = Gf256;
+    fn sub(self, other: Gf256) -> Gf256 {
+        Gf256(self.0 ^ other.0)
+    }
+}
+
+impl Mul for Gf256 {
+    type Output = Gf256;
+    fn mul(self, other: Gf256) -> Gf256 {
+        match (self.log(), other.log()) {
+            (Some(a), Some(b)) => Gf256(EXP[a + b]),
+            _ => Gf256::zero(),
+        }
+    }
+}
+
+impl Div for Gf256 {
+    type Output = Gf256;
+    /// # Panics
+    /// Panics when dividing by zero.
+    fn div(self, other: Gf256) -> Gf256 {
+        self * other.inverse().expect("division by zero")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Gf256, mul_slow};
+
+    #[test]
+    fn mul_fips197() {
+        // FIPS 197, section 4.2.
+        assert_eq!(Gf256(0xc1), Gf256(0x57) * Gf256(0x83));
+        assert_eq!(Gf256(0xfe), Gf256(0x57) * Gf256(0x13));
+        assert_eq!(Gf256(0xae), Gf256(0x57).xtime());
+    }
+
+    #[test]
+    fn mul_tables() {
+        for a in 0..256 {
+            for b in 0..256 {
+                assert_eq!(mul_slow(a as u8, b as u8), (Gf256(a as u8) * Gf256(b as u8)).0);
+            }
+        }
+    }
+
+    #[test]
+    fn inverse() {
+        assert_eq!(None, Gf256::zero().inverse());
+        assert_eq!(Some(Gf256::one()), Gf256::one().inverse());
+        // The S-box entry for 0x53 is the affine transform of 0xca.
+        assert_eq!(Some(Gf256(0xca)), Gf256(0x53).inverse());
+        for a in 1..256 {
+            let a = Gf256(a as u8);
+            assert_eq!(Gf256::one(), a * a.inverse().unwrap());
+            assert_eq!(Gf256::one(), a / a);
+        }
+    }
+
+    #[test]
+    fn pow_log() {
+        assert_eq!(Gf256::one(), Gf256::zero().pow(0));
+        assert_eq!(Gf256::zero(), Gf256::zero().pow(3));
+        assert_eq!(Gf256(0x57) * Gf256(0x57) * Gf256(0x57), Gf256(0x57).pow(3));
+        assert_eq!(Gf256::one(), Gf256(0x57).pow(255));
+        for a in 1..256 {
+            assert_eq!(Gf256(a as u8), Gf256::exp(Gf256(a as u8).log().unwrap()));
+        }
+    }
+}
@@ -0,0 +1,289 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! Matrices over GF(2), with rows packed into 64-bit words.  Since
+//! GF(2^128) is a vector space over GF(2), multiplication by a
+//! constant and squaring are linear maps, and `mul_by` and `square`
+//! return their matrices.  Vectors of GF(2^128) elements are columns
+//! with the coefficient of `x^i` in row `i`.
+
+use std::ops::{Add, Mul};
+
+use crate::math::gf128::Gf128;
+
+/// Matrix over GF(2).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Matrix {
+    rows: usize,
+    cols: usize,
+    data: Vec<Vec<u64>>,
+}
+
+impl Matrix {
+    /// Return the zero matrix with the given dimensions.
+    pub fn zero(rows: usize, cols: usize) -> Matrix {
+        Matrix {
+            rows: rows,
+            cols: cols,
+            data: vec![vec![0; (cols + 63) / 64]; rows],
+        }
+    }
+
+    /// Return the `n` by `n` identity matrix.
+    pub fn identity(n: usize) -> Matrix {
+        let mut m = Matrix::zero(n, n);
+        for i in 0..n {
+            m.set(i, i, true);
+        }
+        m
+    }
+
+    /// Return the 128-row matrix with the given field elements as
+    /// columns.
+    pub fn from_columns(columns: &[Gf128]) -> Matrix {
+        let mut m = Matrix::zero(128, columns.len());
+        for (c, x) in columns.iter().enumerate() {
+            for r in 0..128 {
+                m.set(r, c, (x.0 >> r) & 1 != 0);
+            }
+        }
+        m
+    }
+
+    /// Return the matrix of the linear map `y -> c * y` on GF(2^128).
+    pub fn mul_by(c: Gf128) -> Matrix {
+        let columns: Vec<_> = (0..128).map(|j| c * Gf128(1 << j)).collect();
+        Matrix::from_columns(&columns)
+    }
+
+    /// Return the matrix of the linear map `y -> y^2` on GF(2^128).
+    pub fn square() -> Matrix {
+        let columns: Vec<_> = (0..128).map(|j| Gf128(1 << j) * Gf128(1 << j)).collect();
+        Matrix::from_columns(&columns)
+    }
+
+    /// Return the number of rows.
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Return the number of columns.
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    /// Return the entry at row `r` and column `c`.
+    pub fn get(&self, r: usize, c: usize) -> bool {
+        (self.data[r][c / 64] >> (c % 64)) & 1 != 0
+    }
+
+    /// Set the entry at row `r` and column `c`.
+    pub fn set(&mut self, r: usize, c: usize, v: bool) {
+        if v {
+            self.data[r][c / 64] |= 1 << (c % 64);
+        } else {
+            self.data[r][c / 64] &= !(1 << (c % 64));
+        }
+    }
+
+    /// Return row `r`, packed into words with column `c` in bit
+    /// `c % 64` of word `c / 64`.
+    pub fn row(&self, r: usize) -> &[u64] {
+        &self.data[r]
+    }
+
+    /// Return column `c` of a 128-row matrix as a field element.
+    ///
+    /// # Panics
+    /// Panics if the matrix does not have 128 rows.
+    pub fn column_gf128(&self, c: usize) -> Gf128 {
+        assert_eq!(128, self.rows);
+        Gf128((0..128).fold(0, |acc, r| acc | (self.get(r, c) as u128) << r))
+    }
+
+    /// Apply a matrix with 128 columns to the vector `x`.
+    ///
+    /// # Panics
+    /// Panics if the matrix does not have 128 columns.
+    pub fn apply(&self, x: Gf128) -> Vec<bool> {
+        assert_eq!(128, self.cols);
+        let words = [x.0 as u64, (x.0 >> 64) as u64];
+        self.data.iter()
+            .map(|row| ((row[0] & words[0]).count_ones() + (row[1] & words[1]).count_ones()) % 2 == 1)
+            .collect()
+    }
+
+    /// Return the matrix consisting of rows `start..end`.
+    pub fn row_range(&self, start: usize, end: usize) -> Matrix {
+        Matrix {
+            rows: end - start,
+            cols: self.cols,
+            data: self.data[start..end].to_vec(),
+        }
+    }
+
+    /// Append the rows of `other`, which must have the same number of
+    /// columns.
+    pub fn append_rows(&mut self, other: &Matrix) {
+        assert_eq!(self.cols, other.cols);
+        self.data.extend(other.data.iter().cloned());
+        self.rows += other.rows;
+    }
+
+    /// Return the transposed matrix.
+    pub fn transpose(&self) -> Matrix {
+        let mut t = Matrix::zero(self.cols, self.rows);
+        for r in 0..self.rows {
+            for c in 0..self.cols {
+                if self.get(r, c) {
+                    t.set(c, r, true);
+                }
+            }
+        }
+        t
+    }
+
+    /// Bring the matrix into reduced row echelon form by Gaussian
+    /// elimination, and return the pivot column of each non-zero row.
+    fn reduce(&mut self) -> Vec<usize> {
+        let mut pivots = Vec::new();
+        let mut row = 0;
+        for c in 0..self.cols {
+            if row == self.rows {
+                break;
+            }
+            let (w, bit) = (c / 64, 1 << (c % 64));
+            let p = match (row..self.rows).find(|&r| self.data[r][w] & bit != 0) {
+                Some(p) => p,
+                None => continue,
+            };
+            self.data.swap(row, p);
+            let pivot_row = self.data[row].clone();
+            for r in 0..self.rows {
+                if r != row && self.data[r][w] & bit != 0 {
+                    for (x, y) in self.data[r].iter_mut().zip(pivot_row.iter()) {
+                        *x ^= *y;
+                    }
+                }
+            }
+            pivots.push(c);
+            row += 1;
+        }
+        pivots
+    }
+
+    /// Return the rank.
+    pub fn rank(&self) -> usize {
+        self.clone().reduce().len()
+    }
+
+    /// Return a basis of the kernel, that is, of the vectors `v` with
+    /// `self * v = 0`, as the columns of a matrix.
+    pub fn kernel(&self) -> Matrix {
+        let mut m = self.clone();
+        let pivots = m.reduce();
+        let free: Vec<usize> = (0..self.cols).filter(|c| !pivots.contains(c)).collect();
+        let mut basis = Matrix::zero(self.cols, free.len());
+        for (k, &f) in free.iter().enumerate() {
+            basis.set(f, k, true);
+            for (r, &p) in pivots.iter().enumerate() {
+                if m.get(r, f) {
+                    basis.set(p, k, true);
+                }
+            }
+        }
+        basis
+    }
+}
+
+impl<'a> Add<&'a Matrix> for &'a Matrix {
+    type Output = Matrix;
+    fn add(self, other: &Matrix) -> Matrix {
+        assert!(self.rows == other.rows && self.cols == other.cols, "dimension mismatch");
+        let data = self.data.iter()
+            .zip(other.data.iter())
+            .map(|(a, b)| a.iter().zip(b.iter()).map(|(x, y)| x ^ y).collect())
+            .collect();
+        Matrix {
+            rows: self.rows,
+            cols: self.cols,
+            data: data,
+        }
+    }
+}
+
+impl<'a> Mul<&'a Matrix> for &'a Matrix {
+    type Output = Matrix;
+    fn mul(self, other: &Matrix) -> Matrix {
+        assert_eq!(self.cols, other.rows, "dimension mismatch");
+        let mut result = Matrix::zero(self.rows, other.cols);
+        for r in 0..self.rows {
+            for k in 0..self.cols {
+                if self.get(r, k) {
+                    for (x, y) in result.data[r].iter_mut().zip(other.data[k].iter()) {
+                        *x ^= *y;
+                    }
+                }
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Matrix;
+    use crate::math::gf128::Gf128;
+
+    fn elem(hi: u64, lo: u64) -> Gf128 {
+        Gf128((hi as u128) << 64 | lo as u128)
+    }
+
+    #[test]
+    fn identity() {
+        let i = Matrix::identity(130);
+        assert_eq!(130, i.rank());
+        assert_eq!(0, i.kernel().cols());
+        assert_eq!(i, &i * &i);
+        assert_eq!(i, i.transpose());
+    }
+
+    #[test]
+    fn kernel_0() {
+        // Rows: 1 1 0 0, 0 0 1 1
+        let mut m = Matrix::zero(2, 4);
+        m.set(0, 0, true);
+        m.set(0, 1, true);
+        m.set(1, 2, true);
+        m.set(1, 3, true);
+        let k = m.kernel();
+        assert_eq!(2, k.cols());
+        assert_eq!(Matrix::zero(2, 2), &m * &k);
+        assert_eq!(2, k.rank());
+    }
+
+    quickcheck! {
+        fn prop_mul_by(c: (u64, u64), y: (u64, u64)) -> bool {
+            let (c, y) = (elem(c.0, c.1), elem(y.0, y.1));
+            let m = Matrix::mul_by(c);
+            m.apply(y) == Matrix::identity(128).apply(c * y)
+        }
+
+        fn prop_square(y: (u64, u64)) -> bool {
+            let y = elem(y.0, y.1);
+            let prod = &Matrix::square() * &Matrix::from_columns(&[y]);
+            prod.column_gf128(0) == y * y
+        }
+
+        fn prop_kernel(rows: Vec<u64>) -> bool {
+            let mut m = Matrix::zero(rows.len(), 64);
+            for (r, &bits) in rows.iter().enumerate() {
+                for c in 0..64 {
+                    m.set(r, c, (bits >> c) & 1 != 0);
+                }
+            }
+            let k = m.kernel();
+            k.cols() + m.rank() == 64 && (&m * &k) == Matrix::zero(rows.len(), k.cols())
+        }
+    }
+}
@@ -0,0 +1,347 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! Number-theoretic helpers shared by the public-key ciphers,
+//! protocols and attacks.
+
+pub mod dlog;
+pub mod gf128;
+pub mod gf256;
+pub mod gf2_matrix;
+pub mod reed_solomon;
+
+use rand::Rng;
+
+use crate::bignum::BigUint;
+
+/// Return all primes below `limit`, computed with the sieve of
+/// Eratosthenes.
+///
+/// # Example
+/// ```
+/// use cryptopals::math::small_primes;
+/// assert_eq!(vec![2, 3, 5, 7, 11, 13], small_primes(17));
+/// ```
+pub fn small_primes(limit: u64) -> Vec<u64> {
+    let limit = limit as usize;
+    let mut composite = vec![false; limit];
+    let mut primes = Vec::new();
+    for i in 2..limit {
+        if !composite[i] {
+            primes.push(i as u64);
+            let mut j = i * i;
+            while j < limit {
+                composite[j] = true;
+                j += i;
+            }
+        }
+    }
+    primes
+}
+
+/// Bound for the small primes tried by trial division before running
+/// Miller-Rabin.  Trial division is cheap, and weeds out most random
+/// candidates in prime generation.
+const TRIAL_DIVISION_LIMIT: u64 = 1000;
+
+/// Test whether `n` is prime.  Numbers up to the square of
+/// `TRIAL_DIVISION_LIMIT` are decided deterministically by trial
+/// division; for larger numbers, the Miller-Rabin test is run with
+/// `rounds` bases drawn from `rng`.  A composite number passes with
+/// a probability of at most `4^-rounds`.
+pub fn is_probable_prime<R: Rng>(n: &BigUint, rounds: usize, rng: &mut R) -> bool {
+    let two = BigUint::from_u64(2);
+    if *n < two {
+        return false;
+    }
+    for p in small_primes(TRIAL_DIVISION_LIMIT) {
+        let bp = BigUint::from_u64(p);
+        if *n == bp {
+            return true;
+        }
+        if (n % &bp).is_zero() {
+            return false;
+        }
+    }
+    if *n < BigUint::from_u64(TRIAL_DIVISION_LIMIT * TRIAL_DIVISION_LIMIT) {
+        return true;
+    }
+
+    let one = BigUint::one();
+    let n1 = n - &one;
+    let mut s = 0;
+    while !n1.bit(s) {
+        s += 1;
+    }
+    let d = &n1 >> s;
+    let range = n - &BigUint::from_u64(3);
+
+    'witness: for _ in 0..rounds {
+        let a = &BigUint::random_below_rng(&range, rng) + &two;
+        let mut x = a.modpow(&d, n);
+        if x == one || x == n1 {
+            continue;
+        }
+        for _ in 1..s {
+            x = x.modpow(&two, n);
+            if x == n1 {
+                continue 'witness;
+            }
+        }
+        return false;
+    }
+    true
+}
+
+/// Generate a random prime of exactly `bits` bits, with randomness
+/// from `rng`.  The two most significant bits are set, so that the
+/// product of two such primes has exactly twice as many bits, as
+/// needed for RSA moduli.
+///
+/// # Panics
+/// Panics when `bits` is less than 8.
+pub fn gen_prime<R: Rng>(bits: usize, rng: &mut R) -> BigUint {
+    assert!(bits >= 8, "prime too small");
+    let top = &BigUint::from_u64(3) << (bits - 2);
+    let range = &BigUint::one() << (bits - 2);
+    loop {
+        let mut candidate = &top + &BigUint::random_below_rng(&range, rng);
+        if candidate.is_even() {
+            candidate = &candidate + &BigUint::one();
+        }
+        if is_probable_prime(&candidate, 40, rng) {
+            return candidate;
+        }
+    }
+}
+
+/// Calculate the integer `k`-th root of `n`, that is, the largest
+/// number `r` with `r^k <= n`.  The root is exact if `r^k == n`.
+///
+/// This uses Newton's iteration, starting from a power of two that
+/// is guaranteed to be at least the root, so that the iterates
+/// decrease monotonically until the floor of the root is reached.
+///
+/// # Example
+/// ```
+/// use cryptopals::bignum::BigUint;
+/// use cryptopals::math::iroot;
+/// assert_eq!(BigUint::from_u64(4), iroot(&BigUint::from_u64(80), 3));
+/// ```
+///
+/// # Panics
+/// Panics when `k` is zero.
+pub fn iroot(n: &BigUint, k: u32) -> BigUint {
+    assert!(k > 0, "zeroth root");
+    if n.is_zero() || k == 1 {
+        return n.clone();
+    }
+    let k_big = BigUint::from_u64(k as u64);
+    let k1 = BigUint::from_u64(k as u64 - 1);
+    let shift = (n.bits() + k as usize - 1) / k as usize;
+    let mut x = &BigUint::one() << shift;
+    loop {
+        let y = &(&(&k1 * &x) + &(n / &x.pow(k - 1))) / &k_big;
+        if y >= x {
+            return x;
+        }
+        x = y;
+    }
+}
+
+/// Solve a system of simultaneous congruences `x = r_i (mod m_i)`
+/// with the Chinese Remainder Theorem.  `congruences` contains the
+/// pairs `(r_i, m_i)`.  Returns the unique solution modulo the product
+/// of all moduli, or `None` if the moduli are not pairwise coprime.
+///
+/// # Example
+/// ```
+/// use cryptopals::bignum::BigUint;
+/// use cryptopals::math::crt;
+/// let n = BigUint::from_u64;
+/// assert_eq!(Some(n(23)), crt(&[(n(2), n(3)), (n(3), n(5)), (n(2), n(7))]));
+/// ```
+pub fn crt(congruences: &[(BigUint, BigUint)]) -> Option<BigUint> {
+    let mut product = BigUint::one();
+    for &(_, ref m) in congruences {
+        product = &product * m;
+    }
+    let mut result = BigUint::zero();
+    for &(ref r, ref m) in congruences {
+        let ms = &product / m;
+        let inv = match ms.modinv(m) {
+            Some(inv) => inv,
+            None => return None,
+        };
+        result = &(&result + &(&(r * &ms) * &inv)) % &product;
+    }
+    Some(result)
+}
+
+/// Return a square root of `n` modulo the odd prime `p`, computed with
+/// the Tonelli-Shanks algorithm, or `None` if `n` is not a quadratic
+/// residue.  The other root is `p` minus the returned one.
+///
+/// # Example
+/// ```
+/// use cryptopals::bignum::BigUint;
+/// use cryptopals::math::sqrt_mod;
+/// let n = BigUint::from_u64;
+/// let r = sqrt_mod(&n(10), &n(13)).unwrap();
+/// assert!(r == n(6) || r == n(7));
+/// assert_eq!(None, sqrt_mod(&n(5), &n(13)));
+/// ```
+pub fn sqrt_mod(n: &BigUint, p: &BigUint) -> Option<BigUint> {
+    let one = BigUint::one();
+    let n = n % p;
+    if n.is_zero() {
+        return Some(n);
+    }
+    let p1 = p - &one;
+    let half = &p1 >> 1;
+    if n.modpow(&half, p) != one {
+        return None;
+    }
+    // Write p - 1 = q * 2^s with q odd.
+    let mut q = p1.clone();
+    let mut s = 0;
+    while q.is_even() {
+        q = &q >> 1;
+        s += 1;
+    }
+    let mut z = BigUint::from_u64(2);
+    while z.modpow(&half, p) != p1 {
+        z = &z + &one;
+    }
+    let mut m = s;
+    let mut c = z.modpow(&q, p);
+    let mut t = n.modpow(&q, p);
+    let mut r = n.modpow(&(&(&q + &one) >> 1), p);
+    while t != one {
+        let mut i = 0;
+        let mut t2i = t.clone();
+        while t2i != one {
+            t2i = &(&t2i * &t2i) % p;
+            i += 1;
+        }
+        let b = c.modpow(&(&one << (m - i - 1)), p);
+        m = i;
+        c = &(&b * &b) % p;
+        t = &(&t * &c) % p;
+        r = &(&r * &b) % p;
+    }
+    Some(r)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{small_primes, is_probable_prime, gen_prime, iroot, crt, sqrt_mod};
+    use crate::bignum::BigUint;
+
+    fn is_prime(n: u64) -> bool {
+        let mut rng = ::rand::thread_rng();
+        is_probable_prime(&BigUint::from_u64(n), 20, &mut rng)
+    }
+
+    #[test]
+    fn small_primes_0() {
+        assert_eq!(Vec::<u64>::new(), small_primes(2));
+        assert_eq!(vec![2, 3, 5, 7], small_primes(10));
+        assert_eq!(168, small_primes(1000).len());
+    }
+
+    #[test]
+    fn primes() {
+        for &p in [2, 3, 5, 997, 7919, 1000003, 2147483647, 18446744073709551557].iter() {
+            assert!(is_prime(p), "{} is prime", p);
+        }
+    }
+
+    #[test]
+    fn composites() {
+        for &c in [0, 1, 4, 1000, 994009, 1000001, 18446744073709551615].iter() {
+            assert!(!is_prime(c), "{} is composite", c);
+        }
+    }
+
+    #[test]
+    fn carmichael_numbers() {
+        // These fool the Fermat test for every coprime base.  The
+        // last one is a strong pseudoprime to all prime bases up to
+        // 37 and is only caught because bases are chosen randomly.
+        for &c in [561, 1105, 1729, 2465, 2821, 6601, 8911, 41041, 825265,
+                   321197185, 3825123056546413051].iter() {
+            assert!(!is_prime(c), "{} is composite", c);
+        }
+    }
+
+    #[test]
+    fn large_prime() {
+        let mut rng = ::rand::thread_rng();
+        // 2^127 - 1 is a Mersenne prime, 2^128 + 1 is composite.
+        let m127 = &(&BigUint::one() << 127) - &BigUint::one();
+        assert!(is_probable_prime(&m127, 20, &mut rng));
+        let f7 = &(&BigUint::one() << 128) + &BigUint::one();
+        assert!(!is_probable_prime(&f7, 20, &mut rng));
+    }
+
+    #[test]
+    fn gen_prime_0() {
+        let mut rng = ::rand::thread_rng();
+        let p = gen_prime(96, &mut rng);
+        assert_eq!(96, p.bits());
+        assert!(is_probable_prime(&p, 20, &mut rng));
+    }
+
+    #[test]
+    fn iroot_small() {
+        for n in 0..300u64 {
+            for k in 1..5u32 {
+                let r = iroot(&BigUint::from_u64(n), k).to_u64().unwrap();
+                assert!(r.pow(k) <= n && (r + 1).pow(k) > n, "iroot({}, {}) = {}", n, k, r);
+            }
+        }
+    }
+
+    #[test]
+    fn iroot_exact_cube() {
+        let x = BigUint::from_hex("123456789abcdef0123456789abcdef").unwrap();
+        let cube = x.pow(3);
+        assert_eq!(x, iroot(&cube, 3));
+        assert_eq!(x, iroot(&(&cube + &BigUint::one()), 3));
+        assert_eq!(&x - &BigUint::one(), iroot(&(&cube - &BigUint::one()), 3));
+    }
+
+    #[test]
+    fn crt_0() {
+        let n = BigUint::from_u64;
+        assert_eq!(Some(n(23)), crt(&[(n(2), n(3)), (n(3), n(5)), (n(2), n(7))]));
+        assert_eq!(Some(n(0)), crt(&[]));
+        assert_eq!(None, crt(&[(n(1), n(4)), (n(3), n(6))]));
+    }
+
+    #[test]
+    fn sqrt_mod_0() {
+        let n = BigUint::from_u64;
+        // 13 = 1 (mod 4), 17 = 1 (mod 16) and 7 = 3 (mod 4) exercise
+        // different numbers of Tonelli-Shanks iterations.
+        for &p in [7, 13, 17, 97, 257].iter() {
+            let residues: Vec<u64> = (0..p).map(|x| x * x % p).collect();
+            for a in 0..p {
+                match sqrt_mod(&n(a), &n(p)) {
+                    Some(r) => assert_eq!(n(a), &(&r * &r) % &n(p)),
+                    None => assert!(!residues.contains(&a), "{} is a square mod {}", a, p),
+                }
+            }
+        }
+    }
+
+    quickcheck! {
+        fn prop_iroot(bytes: Vec<u8>, k: u8) -> bool {
+            let n = BigUint::from_bytes_be(&bytes);
+            let k = (k % 7) as u32 + 1;
+            let r = iroot(&n, k);
+            r.pow(k) <= n && (&r + &BigUint::one()).pow(k) > n
+        }
+    }
+}
@@ -0,0 +1,179 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! Reed-Solomon erasure coding over GF(2^8).  A message is split into
+//! `k` data shards of equal length, and `m` parity shards are added,
+//! so that any `k` of the `k + m` shards are enough to get the message
+//! back.  Only erasures are handled: shards are either present and
+//! correct, or known to be missing.
+//!
+//! The code is systematic.  At every byte position, the data bytes
+//! are taken as the values of a polynomial of degree less than `k` at
+//! the points `0..k`, and the parity bytes are its values at the
+//! points `k..k + m`.  Missing shards are recomputed by Lagrange
+//! interpolation.
+
+use crate::error::Error;
+use crate::math::gf256::Gf256;
+
+/// Erasure code with a fixed number of data and parity shards.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReedSolomon {
+    data_shards: usize,
+    parity_shards: usize,
+}
+
+/// Return the coefficients for computing the value at `x` of the
+/// polynomial of least degree through the points `xs`, as a linear
+/// combination of its values there.
+fn lagrange(xs: &[Gf256], x: Gf256) -> Vec<Gf256> {
+    xs.iter().enumerate().map(|(j, &xj)| {
+        xs.iter().enumerate()
+            .filter(|&(m, _)| m != j)
+            .fold(Gf256::one(), |acc, (_, &xm)| acc * (x - xm) / (xj - xm))
+    }).collect()
+}
+
+impl ReedSolomon {
+    /// Create a code with `data_shards` data and `parity_shards`
+    /// parity shards.
+    ///
+    /// # Panics
+    /// Panics if there are no data shards, or more than 256 shards in
+    /// total.
+    pub fn new(data_shards: usize, parity_shards: usize) -> ReedSolomon {
+        assert!(data_shards > 0, "at least one data shard is required");
+        assert!(data_shards + parity_shards <= 256, "at most 256 shards are supported");
+        ReedSolomon {
+            data_shards: data_shards,
+            parity_shards: parity_shards,
+        }
+    }
+
+    /// Return the number of data shards.
+    pub fn data_shards(&self) -> usize {
+        self.data_shards
+    }
+
+    /// Return the number of parity shards.
+    pub fn parity_shards(&self) -> usize {
+        self.parity_shards
+    }
+
+    /// Split `message` into data shards, padding the last one with
+    /// zeros.  The caller has to remember the message length to remove
+    /// the padding again.
+    pub fn split(&self, message: &[u8]) -> Vec<Vec<u8>> {
+        let len = (message.len() + self.data_shards - 1) / self.data_shards;
+        (0..self.data_shards).map(|i| {
+            let mut shard: Vec<u8> = message.iter().skip(i * len).take(len).cloned().collect();
+            shard.resize(len, 0);
+            shard
+        }).collect()
+    }
+
+    /// Compute the parity shards for `data`.
+    ///
+    /// # Panics
+    /// Panics if the number of data shards is wrong, or if they do
+    /// not all have the same length.
+    pub fn encode(&self, data: &[Vec<u8>]) -> Vec<Vec<u8>> {
+        assert_eq!(self.data_shards, data.len(), "wrong number of data shards");
+        let xs: Vec<Gf256> = (0..self.data_shards).map(|i| Gf256(i as u8)).collect();
+        (self.data_shards..self.data_shards + self.parity_shards)
+            .map(|i| interpolate(&xs, data, Gf256(i as u8)))
+            .collect()
+    }
+
+    /// Fill in the missing entries of `shards`, which holds the data
+    /// shards followed by the parity shards.  Returns
+    /// `Error::NotEnoughShards` if fewer than `data_shards` are
+    /// present.
+    ///
+    /// # Panics
+    /// Panics if the number of shards is wrong, or if the present
+    /// shards do not all have the same length.
+    pub fn reconstruct(&self, shards: &mut [Option<Vec<u8>>]) -> Result<(), Error> {
+        assert_eq!(self.data_shards + self.parity_shards, shards.len(), "wrong number of shards");
+        let present: Vec<usize> = (0..shards.len()).filter(|&i| shards[i].is_some()).collect();
+        if present.len() < self.data_shards {
+            return Err(Error::NotEnoughShards {
+                available: present.len(),
+                required: self.data_shards,
+            });
+        }
+        let present = &present[..self.data_shards];
+        let xs: Vec<Gf256> = present.iter().map(|&i| Gf256(i as u8)).collect();
+        let ys: Vec<Vec<u8>> = present.iter().map(|&i| shards[i].clone().unwrap()).collect();
+        for i in 0..shards.len() {
+            if shards[i].is_none() {
+                shards[i] = Some(interpolate(&xs, &ys, Gf256(i as u8)));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Compute the shard at point `x` from the shards `ys` at the points
+/// `xs`.
+fn interpolate(xs: &[Gf256], ys: &[Vec<u8>], x: Gf256) -> Vec<u8> {
+    let len = ys[0].len();
+    assert!(ys.iter().all(|y| y.len() == len), "shards differ in length");
+    let coeffs = lagrange(xs, x);
+    (0..len).map(|pos| {
+        coeffs.iter().zip(ys.iter())
+            .fold(Gf256::zero(), |acc, (&c, y)| acc + c * Gf256(y[pos]))
+            .0
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ReedSolomon;
+    use crate::error::Error;
+
+    #[test]
+    fn roundtrip() {
+        let message = b"Reed-Solomon codes recover erased shards.";
+        let rs = ReedSolomon::new(4, 3);
+        let data = rs.split(message);
+        assert_eq!(4, data.len());
+        assert_eq!(11, data[0].len());
+        let parity = rs.encode(&data);
+        assert_eq!(3, parity.len());
+
+        let all: Vec<Vec<u8>> = data.iter().chain(parity.iter()).cloned().collect();
+        // Every choice of up to three erasures can be repaired.
+        for erased in 0..1 << 7 {
+            let erased: u32 = erased;
+            if erased.count_ones() > 3 {
+                continue;
+            }
+            let mut shards: Vec<Option<Vec<u8>>> = all.iter().enumerate()
+                .map(|(i, s)| if erased & (1 << i) != 0 { None } else { Some(s.clone()) })
+                .collect();
+            rs.reconstruct(&mut shards).unwrap();
+            let repaired: Vec<Vec<u8>> = shards.into_iter().map(|s| s.unwrap()).collect();
+            assert_eq!(all, repaired);
+        }
+        let joined: Vec<u8> = data.concat();
+        assert_eq!(&message[..], &joined[..message.len()]);
+    }
+
+    #[test]
+    fn parity_of_constant() {
+        // A constant polynomial has the same value everywhere.
+        let rs = ReedSolomon::new(3, 2);
+        assert_eq!(vec![vec![7, 7], vec![7, 7]], rs.encode(&[vec![7, 7], vec![7, 7], vec![7, 7]]));
+    }
+
+    #[test]
+    fn not_enough_shards() {
+        let rs = ReedSolomon::new(2, 1);
+        let mut shards = vec![Some(vec![1]), None, None];
+        match rs.reconstruct(&mut shards) {
+            Err(Error::NotEnoughShards { available: 1, required: 2 }) => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+}
@@ -0,0 +1,219 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! A minimal HTTP server and client for the HMAC timing attack
+//! (challenges 31 and 32).  The server answers requests like
+//! `GET /test?file=foo&signature=46b4ec58...` with status 200 if the
+//! signature is the hex-encoded HMAC-SHA1 of the file name under its
+//! secret key, and with 500 otherwise.  It compares signatures with
+//! `insecure_compare`, which returns at the first mismatch and sleeps
+//! after every matching byte, so the response time tells how much of
+//! a signature is right.
+//!
+//! Only what the challenges need of HTTP/1.1 is implemented: one
+//! request per connection, no request bodies.  This module is only
+//! available with the `net` feature.
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::codec::{hex, url};
+use crate::error::Error;
+use crate::hash::sha1::Sha1;
+use crate::mac::hmac::hmac;
+
+/// Compare `a` and `b` byte by byte, returning at the first
+/// difference and sleeping for `delay` after each equal byte.
+pub fn insecure_compare(a: &[u8], b: &[u8], delay: Duration) -> bool {
+    for (x, y) in a.iter().zip(b.iter()) {
+        if x != y {
+            return false;
+        }
+        thread::sleep(delay);
+    }
+    a.len() == b.len()
+}
+
+/// Server for the file-verification endpoint.
+pub struct Server {
+    listener: TcpListener,
+    key: Vec<u8>,
+    delay: Duration,
+}
+
+impl Server {
+    /// Listen on `addr` and verify signatures under `key`, sleeping
+    /// for `delay` per matching byte.  Bind to port 0 to let the
+    /// operating system choose a free port, see `local_addr`.
+    pub fn bind<A: ToSocketAddrs>(addr: A, key: &[u8], delay: Duration) -> Result<Server, Error> {
+        Ok(Server {
+            listener: TcpListener::bind(addr)?,
+            key: key.to_vec(),
+            delay: delay,
+        })
+    }
+
+    /// Return the address the server is listening on.
+    pub fn local_addr(&self) -> Result<SocketAddr, Error> {
+        Ok(self.listener.local_addr()?)
+    }
+
+    /// Return the correct signature for `file`.
+    pub fn signature(&self, file: &[u8]) -> Vec<u8> {
+        hmac::<Sha1>(&self.key, file)
+    }
+
+    /// Accept and answer connections forever.  Errors on single
+    /// connections are ignored.
+    pub fn run(&self) {
+        for stream in self.listener.incoming() {
+            if let Ok(stream) = stream {
+                let _ = self.handle(stream);
+            }
+        }
+    }
+
+    /// Run the server on a new thread.
+    pub fn spawn(self) -> thread::JoinHandle<()> {
+        thread::spawn(move || self.run())
+    }
+
+    /// Answer the request on `stream`.
+    fn handle(&self, stream: TcpStream) -> Result<(), Error> {
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line)?;
+        // Skip the headers.
+        let mut line = String::new();
+        while reader.read_line(&mut line)? > 0 && !line.trim_end().is_empty() {
+            line.clear();
+        }
+        let status = match self.verify(&request_line) {
+            Some(true) => "200 OK",
+            Some(false) => "500 Internal Server Error",
+            None => "400 Bad Request",
+        };
+        let mut stream = stream;
+        write!(stream, "HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", status)?;
+        Ok(())
+    }
+
+    /// Check the request in `request_line`.  Returns `None` if it is
+    /// not a well-formed request for the endpoint.
+    fn verify(&self, request_line: &str) -> Option<bool> {
+        let mut parts = request_line.split_whitespace();
+        if parts.next() != Some("GET") {
+            return None;
+        }
+        let target = match parts.next() {
+            Some(t) if t.starts_with("/test?") => &t[6..],
+            _ => return None,
+        };
+        let query = match url::parse_query(target) {
+            Ok(q) => q,
+            Err(_) => return None,
+        };
+        let field = |name: &str| query.iter().find(|&&(ref k, _)| k == name).map(|&(_, ref v)| v.clone());
+        let (file, signature) = match (field("file"), field("signature")) {
+            (Some(f), Some(s)) => (f, s),
+            _ => return None,
+        };
+        let signature = match hex::decode(&signature) {
+            Ok(s) => s,
+            Err(_) => return Some(false),
+        };
+        Some(insecure_compare(&self.signature(file.as_bytes()), &signature, self.delay))
+    }
+}
+
+/// Client for the file-verification endpoint.
+pub struct Client {
+    addr: SocketAddr,
+}
+
+impl Client {
+    /// Create a client for the server at `addr`.
+    pub fn new(addr: SocketAddr) -> Client {
+        Client { addr: addr }
+    }
+
+    /// Ask the server whether `signature` is valid for `file`.
+    pub fn check(&self, file: &str, signature: &[u8]) -> Result<bool, Error> {
+        self.check_timed(file, signature).map(|(valid, _)| valid)
+    }
+
+    /// Like `check`, but also return how long the server took to
+    /// answer.
+    pub fn check_timed(&self, file: &str, signature: &[u8]) -> Result<(bool, Duration), Error> {
+        let query = url::encode_query(&[("file".to_string(), file.to_string()),
+                                        ("signature".to_string(), hex::encode(signature))]);
+        let mut stream = TcpStream::connect(self.addr)?;
+        let start = Instant::now();
+        write!(stream, "GET /test?{} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n\r\n",
+                    query, self.addr)?;
+        let mut status_line = String::new();
+        BufReader::new(stream).read_line(&mut status_line)?;
+        let elapsed = start.elapsed();
+        Ok((status_line.split_whitespace().nth(1) == Some("200"), elapsed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpStream;
+    use std::time::Duration;
+
+    use super::{insecure_compare, Server, Client};
+
+    fn start(delay: Duration) -> (Client, Vec<u8>) {
+        let server = Server::bind("127.0.0.1:0", b"YELLOW SUBMARINE", delay).unwrap();
+        let client = Client::new(server.local_addr().unwrap());
+        let signature = server.signature(b"foo");
+        server.spawn();
+        (client, signature)
+    }
+
+    #[test]
+    fn compare() {
+        let zero = Duration::from_millis(0);
+        assert!(insecure_compare(b"abc", b"abc", zero));
+        assert!(!insecure_compare(b"abc", b"abd", zero));
+        assert!(!insecure_compare(b"abc", b"ab", zero));
+    }
+
+    #[test]
+    fn check() {
+        let (client, signature) = start(Duration::from_millis(0));
+        assert!(client.check("foo", &signature).unwrap());
+        assert!(!client.check("bar", &signature).unwrap());
+        assert!(!client.check("foo", &signature[..19]).unwrap());
+    }
+
+    #[test]
+    fn bad_request() {
+        let server = Server::bind("127.0.0.1:0", b"key", Duration::from_millis(0)).unwrap();
+        let addr = server.local_addr().unwrap();
+        server.spawn();
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(stream, "GET /other HTTP/1.1\r\n\r\n").unwrap();
+        let mut status_line = String::new();
+        BufReader::new(stream).read_line(&mut status_line).unwrap();
+        assert_eq!("HTTP/1.1 400 Bad Request\r\n", status_line);
+    }
+
+    #[test]
+    fn timing_leak() {
+        // The right first byte makes the server sleep once more.
+        let (client, signature) = start(Duration::from_millis(20));
+        let mut guess = vec![0; 20];
+        guess[0] = signature[0] ^ 1;
+        let (_, wrong) = client.check_timed("foo", &guess).unwrap();
+        guess[0] = signature[0];
+        let (_, right) = client.check_timed("foo", &guess).unwrap();
+        assert!(right >= Duration::from_millis(20));
+        assert!(right > wrong);
+    }
+}
@@ -0,0 +1,171 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! Oracles, the victims that attacks talk to.  An oracle answers
+//! queries of type `Q` with answers of type `A`, for example an
+//! encryption oracle answers plaintexts with ciphertexts, and a
+//! padding oracle answers ciphertexts with whether the padding was
+//! valid.  Any closure `FnMut(&Q) -> A` is an oracle.
+//!
+//! The decorators `Counting` and `RateLimited` wrap an oracle to
+//! measure how many queries an attack needs, and to test attacks
+//! against servers that limit or slow down their answers.
+
+use std::marker::PhantomData;
+use std::thread;
+use std::time::Duration;
+
+/// Something that answers queries of type `Q` with answers of type
+/// `A`.
+pub trait Oracle<Q: ?Sized, A> {
+    /// Send the query `q` and return the answer.
+    fn query(&mut self, q: &Q) -> A;
+}
+
+impl<Q: ?Sized, A, F> Oracle<Q, A> for F
+    where F: FnMut(&Q) -> A
+{
+    fn query(&mut self, q: &Q) -> A {
+        self(q)
+    }
+}
+
+/// Oracle that counts the queries passed on to the wrapped oracle.
+/// Pass it to an attack as `&mut counting` to read the count
+/// afterwards.
+pub struct Counting<O, Q: ?Sized, A> {
+    oracle: O,
+    queries: usize,
+    marker: PhantomData<fn(&Q) -> A>,
+}
+
+impl<O: Oracle<Q, A>, Q: ?Sized, A> Counting<O, Q, A> {
+    /// Count the queries sent to `oracle`.
+    pub fn new(oracle: O) -> Counting<O, Q, A> {
+        Counting {
+            oracle: oracle,
+            queries: 0,
+            marker: PhantomData,
+        }
+    }
+
+    /// Return the number of queries so far.
+    pub fn queries(&self) -> usize {
+        self.queries
+    }
+
+    /// Return the wrapped oracle.
+    pub fn into_inner(self) -> O {
+        self.oracle
+    }
+}
+
+impl<'a, O: Oracle<Q, A>, Q: ?Sized, A> Oracle<Q, A> for &'a mut Counting<O, Q, A> {
+    fn query(&mut self, q: &Q) -> A {
+        self.queries += 1;
+        self.oracle.query(q)
+    }
+}
+
+/// Oracle that answers at most a fixed number of queries, and
+/// optionally waits before each answer.  Once the budget is used up,
+/// every query is answered with `A::default()` without asking the
+/// wrapped oracle, like a server that has started to reject
+/// requests: `false` for a padding oracle, an empty ciphertext for an
+/// encryption oracle.
+pub struct RateLimited<O, Q: ?Sized, A> {
+    oracle: O,
+    remaining: usize,
+    delay: Option<Duration>,
+    marker: PhantomData<fn(&Q) -> A>,
+}
+
+impl<O: Oracle<Q, A>, Q: ?Sized, A: Default> RateLimited<O, Q, A> {
+    /// Allow at most `max_queries` queries to `oracle`.
+    pub fn new(oracle: O, max_queries: usize) -> RateLimited<O, Q, A> {
+        RateLimited {
+            oracle: oracle,
+            remaining: max_queries,
+            delay: None,
+            marker: PhantomData,
+        }
+    }
+
+    /// Wait for `delay` before answering each query.
+    pub fn with_delay(mut self, delay: Duration) -> RateLimited<O, Q, A> {
+        self.delay = Some(delay);
+        self
+    }
+
+    /// Return the number of queries that will still be answered.
+    pub fn remaining(&self) -> usize {
+        self.remaining
+    }
+
+    /// Return true if the budget is used up.
+    pub fn exhausted(&self) -> bool {
+        self.remaining == 0
+    }
+
+    /// Return the wrapped oracle.
+    pub fn into_inner(self) -> O {
+        self.oracle
+    }
+}
+
+impl<'a, O: Oracle<Q, A>, Q: ?Sized, A: Default> Oracle<Q, A> for &'a mut RateLimited<O, Q, A> {
+    fn query(&mut self, q: &Q) -> A {
+        if self.remaining == 0 {
+            return A::default();
+        }
+        self.remaining -= 1;
+        if let Some(delay) = self.delay {
+            thread::sleep(delay);
+        }
+        self.oracle.query(q)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::{Oracle, Counting, RateLimited};
+
+    fn ask<O: Oracle<[u8], usize>>(mut oracle: O, n: usize) -> usize {
+        (0..n).map(|i| oracle.query(&vec![0; i])).sum()
+    }
+
+    #[test]
+    fn closure() {
+        assert_eq!(3, ask(|q: &[u8]| q.len(), 3));
+    }
+
+    #[test]
+    fn counting() {
+        let mut counting = Counting::new(|q: &[u8]| q.len());
+        assert_eq!(10, ask(&mut counting, 5));
+        assert_eq!(1, ask(&mut counting, 2));
+        assert_eq!(7, counting.queries());
+    }
+
+    #[test]
+    fn rate_limited() {
+        let mut limited = RateLimited::new(|q: &[u8]| q.len(), 3);
+        assert_eq!(3, ask(&mut limited, 5));
+        assert!(limited.exhausted());
+        assert_eq!(0, ask(&mut limited, 5));
+    }
+
+    #[test]
+    fn rate_limited_delay() {
+        let mut limited = RateLimited::new(|q: &[u8]| q.len(), 2)
+            .with_delay(Duration::from_millis(20));
+        let start = Instant::now();
+        let mut counting = Counting::new(&mut limited);
+        assert_eq!(1, ask(&mut counting, 4));
+        assert_eq!(4, counting.queries());
+        assert!(start.elapsed() >= Duration::from_millis(40));
+        assert_eq!(0, limited.remaining());
+    }
+}
@@ -3,35 +3,142 @@
 
 //! Padding algorithms.
 
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+
+use crate::error::{Error, Result};
+
+// Check that `len` is a multiple of `block_size`, and not zero unless
+// `allow_empty` is set.
+fn check_length(len: usize, block_size: usize, allow_empty: bool) -> Result<()> {
+    if (len == 0 && !allow_empty) || len % block_size != 0 {
+        Err(Error::InvalidCiphertextLength { len: len, block_size: block_size })
+    } else {
+        Ok(())
+    }
+}
+
+// Check that `block_size` is between 1 and `max`.
+fn check_block_size(block_size: usize, max: usize) -> Result<()> {
+    if block_size == 0 || block_size > max {
+        Err(Error::InvalidBlockSize(block_size))
+    } else {
+        Ok(())
+    }
+}
+
+/// Padding for block cipher modes, which extends messages to a
+/// multiple of the block size and removes the extension again.
+pub trait Padding {
+    /// Pad `b` to a multiple of `block_size`.
+    fn pad(&self, b: &[u8], block_size: usize) -> Result<Vec<u8>>;
+
+    /// Remove the padding from `b`, checking that it is well-formed.
+    fn unpad(&self, b: &[u8], block_size: usize) -> Result<Vec<u8>>;
+}
+
+/// Block cipher padding schemes, for selecting the padding in the
+/// block cipher modes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PaddingScheme {
+    /// PKCS#7 padding, see `pkcs7`.
+    Pkcs7,
+    /// ANSI X9.23 padding, see `ansix923`.
+    AnsiX923,
+    /// ISO/IEC 7816-4 padding, see `iso7816`.
+    Iso7816,
+    /// Zero padding, see `zero`.
+    Zero,
+}
+
+impl Padding for PaddingScheme {
+    fn pad(&self, b: &[u8], block_size: usize) -> Result<Vec<u8>> {
+        match *self {
+            PaddingScheme::Pkcs7 => pkcs7::pad(b, block_size),
+            PaddingScheme::AnsiX923 => ansix923::pad(b, block_size),
+            PaddingScheme::Iso7816 => iso7816::pad(b, block_size),
+            PaddingScheme::Zero => zero::pad(b, block_size),
+        }
+    }
+
+    fn unpad(&self, b: &[u8], block_size: usize) -> Result<Vec<u8>> {
+        match *self {
+            PaddingScheme::Pkcs7 => pkcs7::unpad(b, block_size),
+            PaddingScheme::AnsiX923 => ansix923::unpad(b, block_size),
+            PaddingScheme::Iso7816 => iso7816::unpad(b, block_size),
+            PaddingScheme::Zero => zero::unpad(b, block_size),
+        }
+    }
+}
+
+/// No padding at all, for messages that already are a multiple of
+/// the block size.  Both padding and unpadding fail for other
+/// lengths.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NoPadding;
+
+impl Padding for NoPadding {
+    fn pad(&self, b: &[u8], block_size: usize) -> Result<Vec<u8>> {
+        self.unpad(b, block_size)
+    }
+
+    fn unpad(&self, b: &[u8], block_size: usize) -> Result<Vec<u8>> {
+        check_block_size(block_size, usize::MAX)?;
+        check_length(b.len(), block_size, true)?;
+        Ok(b.to_vec())
+    }
+}
+
 /// PKCS#7 padding.
 pub mod pkcs7 {
     use std::iter::repeat;
-    
-    pub fn pad(b: &[u8], block_size: usize) -> Vec<u8> {
+    use crate::error::{Error, Result};
+    use super::{check_block_size, check_length};
+
+    /// Pad `b` to a multiple of `block_size` by appending `n` bytes of
+    /// value `n`.  As the padding length must fit into a byte,
+    /// `block_size` must be between 1 and 255, or
+    /// `Error::InvalidBlockSize` is returned.
+    pub fn pad(b: &[u8], block_size: usize) -> Result<Vec<u8>> {
+        check_block_size(block_size, 255)?;
         let l = b.len();
         let padding = block_size - (l % block_size);
         let mut res = Vec::with_capacity(l + padding);
         res.extend(b);
         res.extend(repeat(padding as u8).take(padding));
-        res
+        Ok(res)
+    }
+
+    /// Remove PKCS#7 padding from `b`.  Returns an error unless `b` is
+    /// a non-empty multiple of `block_size` long and ends in `n` bytes
+    /// of value `n`, with `n` between 1 and `block_size`.
+    pub fn unpad(b: &[u8], block_size: usize) -> Result<Vec<u8>> {
+        check_block_size(block_size, 255)?;
+        check_length(b.len(), block_size, false)?;
+        let l = b.len();
+        let n = b[l - 1] as usize;
+        if n == 0 || n > block_size || b[l - n..].iter().any(|&x| x as usize != n) {
+            return Err(Error::InvalidPadding { observed: b[l - 1], block_size: block_size });
+        }
+        Ok(b[..l - n].to_vec())
     }
     
     #[cfg(test)]
     mod tests {
-        use super::{pad};
+        use super::{pad, unpad};
+        use crate::error::Error;
         
         #[test]
         fn pad_empty() {
             let s = b"";
             let expected: Vec<u8> = vec![16, 16, 16, 16, 16, 16, 16, 16, 16, 16, 16, 16, 16, 16, 16, 16];
-            assert_eq!(expected, pad(s, 16));
+            assert_eq!(expected, pad(s, 16).unwrap());
         }
         
         #[test]
         fn pad_non_empty() {
             let s = b"abcde";
             let expected: Vec<u8> = vec![97, 98, 99, 100, 101, 11, 11, 11, 11, 11, 11, 11, 11, 11, 11, 11];
-            assert_eq!(expected, pad(s, 16));
+            assert_eq!(expected, pad(s, 16).unwrap());
         }
         
         #[test]
@@ -40,30 +147,602 @@ pub mod pkcs7 {
             let expected: Vec<u8> = vec![48, 49, 50, 51, 52, 53, 54, 55, 56, 57, 97, 98,
                                          99, 100, 101, 102, 16, 16, 16, 16, 16, 16, 16,
                                          16, 16, 16, 16, 16, 16, 16, 16, 16];
-            assert_eq!(expected, pad(s, 16));
+            assert_eq!(expected, pad(s, 16).unwrap());
         }
         
         #[test]
         fn pad_20() {
             let input = b"YELLOW SUBMARINE";
-            let output = pad(input, 20);
+            let output = pad(input, 20).unwrap();
             let mut expected: Vec<u8> = Vec::new();
             expected.extend(b"YELLOW SUBMARINE\x04\x04\x04\x04");
             assert_eq!(expected, output);
         }
 
+        #[test]
+        fn pad_block_size() {
+            match pad(b"YELLOW SUBMARINE", 0) {
+                Err(Error::InvalidBlockSize(0)) => (),
+                r => panic!("unexpected result: {:?}", r),
+            }
+            match pad(b"YELLOW SUBMARINE", 256) {
+                Err(Error::InvalidBlockSize(256)) => (),
+                r => panic!("unexpected result: {:?}", r),
+            }
+            assert_eq!(255, pad(b"", 255).unwrap().len());
+            assert!(unpad(b"", 0).is_err());
+        }
+
+        #[test]
+        fn unpad_errors() {
+            match unpad(b"ICE ICE BABY\x05\x05\x05\x05", 16) {
+                Err(Error::InvalidPadding { observed: 5, block_size: 16 }) => (),
+                r => panic!("unexpected result: {:?}", r),
+            }
+            match unpad(b"ICE ICE BABY\x04\x04\x04", 16) {
+                Err(Error::InvalidCiphertextLength { len: 15, block_size: 16 }) => (),
+                r => panic!("unexpected result: {:?}", r),
+            }
+            match unpad(b"", 16) {
+                Err(Error::InvalidCiphertextLength { len: 0, block_size: 16 }) => (),
+                r => panic!("unexpected result: {:?}", r),
+            }
+        }
+
+        #[test]
+        fn unpad_0() {
+            assert_eq!(b"ICE ICE BABY".to_vec(), unpad(b"ICE ICE BABY\x04\x04\x04\x04", 16).unwrap());
+            assert!(unpad(b"ICE ICE BABY\x05\x05\x05\x05", 16).is_err());
+            assert!(unpad(b"ICE ICE BABY\x01\x02\x03\x04", 16).is_err());
+            assert!(unpad(b"ICE ICE BABY\x04\x04\x04\x00", 16).is_err());
+            assert!(unpad(b"ICE ICE BABY\x04\x04\x04", 16).is_err());
+            assert!(unpad(b"", 16).is_err());
+        }
+
         quickcheck! {
+            fn prop_unpad_pad(xs: Vec<u8>) -> bool {
+                unpad(&pad(&xs, 16).unwrap(), 16).unwrap() == xs
+            }
+
             fn prop_pad_len(xs: Vec<u8>) -> bool {
-                let padded = pad(&xs, 16);
+                let padded = pad(&xs, 16).unwrap();
                 let l = padded.len();
                 (l % 16 == 0) && (l > xs.len())
             }
 
             fn prop_pad_padding(xs: Vec<u8>) -> bool {
-                let padded = pad(&xs, 16);
+                let padded = pad(&xs, 16).unwrap();
                 let l = padded.len();
                 padded[l-1] as usize == l - xs.len()
             }
         }
     }
 }
+
+/// ANSI X9.23 padding: zero bytes, followed by a byte giving the
+/// number of padding bytes.  Like PKCS#7, a full block is added if the
+/// message already is a multiple of the block size.
+pub mod ansix923 {
+    use crate::error::{Error, Result};
+    use super::{check_block_size, check_length};
+
+    /// Pad `b` to a multiple of `block_size`, which must be between 1
+    /// and 255.
+    pub fn pad(b: &[u8], block_size: usize) -> Result<Vec<u8>> {
+        check_block_size(block_size, 255)?;
+        let l = b.len();
+        let padding = block_size - (l % block_size);
+        let mut res = Vec::with_capacity(l + padding);
+        res.extend(b);
+        res.resize(l + padding - 1, 0);
+        res.push(padding as u8);
+        Ok(res)
+    }
+
+    /// Remove ANSI X9.23 padding from `b`.
+    pub fn unpad(b: &[u8], block_size: usize) -> Result<Vec<u8>> {
+        check_block_size(block_size, 255)?;
+        check_length(b.len(), block_size, false)?;
+        let l = b.len();
+        let n = b[l - 1] as usize;
+        if n == 0 || n > block_size || b[l - n..l - 1].iter().any(|&x| x != 0) {
+            return Err(Error::InvalidPadding { observed: b[l - 1], block_size: block_size });
+        }
+        Ok(b[..l - n].to_vec())
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::{pad, unpad};
+
+        #[test]
+        fn pad_0() {
+            assert_eq!(b"ICE ICE BABY\x00\x00\x00\x04".to_vec(), pad(b"ICE ICE BABY", 16).unwrap());
+            assert_eq!(b"\x00\x00\x00\x04".to_vec(), pad(b"", 4).unwrap());
+        }
+
+        #[test]
+        fn unpad_0() {
+            assert_eq!(b"ICE ICE BABY".to_vec(), unpad(b"ICE ICE BABY\x00\x00\x00\x04", 16).unwrap());
+            assert!(unpad(b"ICE ICE BABY\x00\x01\x00\x04", 16).is_err());
+            assert!(unpad(b"ICE ICE BABY\x00\x00\x00\x00", 16).is_err());
+            assert!(unpad(b"ICE ICE BABY\x00\x00\x00\x11", 16).is_err());
+            assert!(unpad(b"ICE ICE BABY\x00\x04", 16).is_err());
+        }
+
+        quickcheck! {
+            fn prop_unpad_pad(xs: Vec<u8>) -> bool {
+                unpad(&pad(&xs, 16).unwrap(), 16).unwrap() == xs
+            }
+        }
+    }
+}
+
+/// Byte order of the length in `md_length`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Endianness {
+    /// Most significant byte first, as in SHA-1 and SHA-2.
+    Big,
+    /// Least significant byte first, as in MD4 and MD5.
+    Little,
+}
+
+/// Return the Merkle-Damgård padding for a message of `msg_len`
+/// bytes, as used by MD4, SHA-1 and SHA-256: bit padding up to the
+/// last eight bytes of a block of `block_size` bytes, followed by the
+/// message length in bits as a 64-bit number with the given byte
+/// order.  This is also the glue padding needed for length extension
+/// attacks.
+///
+/// # Panics
+/// Panics if `block_size` is less than 8.
+pub fn md_length(msg_len: u64, block_size: usize, endianness: Endianness) -> Vec<u8> {
+    assert!(block_size >= 8, "block size too small");
+    let mut pad = bit::padding(msg_len, block_size, 8);
+    let mut len_bytes = [0; 8];
+    match endianness {
+        Endianness::Big => BigEndian::write_u64(&mut len_bytes, msg_len.wrapping_mul(8)),
+        Endianness::Little => LittleEndian::write_u64(&mut len_bytes, msg_len.wrapping_mul(8)),
+    }
+    pad.extend(&len_bytes);
+    pad
+}
+
+/// Bit padding: a single one bit, followed by zero bits.  On bytes,
+/// this is a byte 0x80, followed by zero bytes.
+pub mod bit {
+    /// Return the bit padding for a message of `msg_len` bytes, which
+    /// ends `reserve` bytes before the end of a block of `block_size`
+    /// bytes.  The padding is at least one byte long, so it may extend
+    /// into the next block.
+    ///
+    /// # Panics
+    /// Panics if `reserve` is not less than `block_size`.
+    pub fn padding(msg_len: u64, block_size: usize, reserve: usize) -> Vec<u8> {
+        assert!(reserve < block_size, "reserve must be less than block size");
+        let used = ((msg_len % block_size as u64) as usize + 1 + reserve) % block_size;
+        let mut pad = vec![0x80];
+        pad.resize(1 + (block_size - used) % block_size, 0);
+        pad
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::padding;
+
+        #[test]
+        fn padding_0() {
+            assert_eq!(vec![0x80, 0, 0, 0], padding(0, 4, 0));
+            assert_eq!(vec![0x80], padding(3, 4, 0));
+            assert_eq!(vec![0x80, 0], padding(5, 8, 1));
+            assert_eq!(vec![0x80], padding(6, 8, 1));
+            assert_eq!(vec![0x80, 0, 0, 0, 0, 0, 0, 0], padding(7, 8, 1));
+            assert_eq!(56, padding(0, 64, 8).len());
+            assert_eq!(64, padding(56, 64, 8).len());
+        }
+
+        quickcheck! {
+            fn prop_padding(len: u16, reserve: u8) -> bool {
+                let reserve = reserve as usize % 64;
+                let pad = padding(len as u64, 64, reserve);
+                (len as usize + pad.len() + reserve) % 64 == 0 && pad.len() <= 64 && pad[0] == 0x80
+            }
+        }
+    }
+}
+
+/// ISO/IEC 7816-4 padding: a byte 0x80, followed by zero bytes.  This
+/// is the block version of the bit padding used in hash functions.
+pub mod iso7816 {
+    use crate::error::{Error, Result};
+    use super::{check_block_size, check_length};
+
+    /// Pad `b` to a multiple of `block_size`, which must not be zero.
+    pub fn pad(b: &[u8], block_size: usize) -> Result<Vec<u8>> {
+        check_block_size(block_size, usize::MAX)?;
+        let mut res = b.to_vec();
+        res.extend(super::bit::padding(b.len() as u64, block_size, 0));
+        Ok(res)
+    }
+
+    /// Remove ISO/IEC 7816-4 padding from `b`.  The 0x80 byte must be
+    /// in the last block.
+    pub fn unpad(b: &[u8], block_size: usize) -> Result<Vec<u8>> {
+        check_block_size(block_size, usize::MAX)?;
+        check_length(b.len(), block_size, false)?;
+        let l = b.len();
+        match b[l - block_size..].iter().rposition(|&x| x != 0) {
+            Some(i) if b[l - block_size + i] == 0x80 => Ok(b[..l - block_size + i].to_vec()),
+            Some(i) => Err(Error::InvalidPadding { observed: b[l - block_size + i], block_size: block_size }),
+            None => Err(Error::InvalidPadding { observed: 0, block_size: block_size }),
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::{pad, unpad};
+
+        #[test]
+        fn pad_0() {
+            assert_eq!(b"ICE ICE BABY\x80\x00\x00\x00".to_vec(), pad(b"ICE ICE BABY", 16).unwrap());
+            assert_eq!(b"\x80\x00\x00\x00".to_vec(), pad(b"", 4).unwrap());
+        }
+
+        #[test]
+        fn unpad_0() {
+            assert_eq!(b"ICE ICE BABY".to_vec(), unpad(b"ICE ICE BABY\x80\x00\x00\x00", 16).unwrap());
+            assert_eq!(b"ICE ICE BABY\x80\x00\x00".to_vec(),
+                       unpad(b"ICE ICE BABY\x80\x00\x00\x80", 16).unwrap());
+            assert!(unpad(b"ICE ICE BABY\x80\x00\x01\x00", 16).is_err());
+            assert!(unpad(b"ICE ICE BABY\x00\x00\x00\x00", 16).is_err());
+            assert!(unpad(b"\x80\x00\x00\x00\x00\x00\x00\x00", 4).is_err());
+        }
+
+        quickcheck! {
+            fn prop_unpad_pad(xs: Vec<u8>) -> bool {
+                unpad(&pad(&xs, 16).unwrap(), 16).unwrap() == xs
+            }
+        }
+    }
+}
+
+/// Zero padding: zero bytes up to the next multiple of the block
+/// size, and no padding for messages that already are a multiple of
+/// it.  The padding is ambiguous for messages ending in zero bytes,
+/// which lose them when unpadding.
+pub mod zero {
+    use crate::error::Result;
+    use super::{check_block_size, check_length};
+
+    /// Pad `b` to a multiple of `block_size`, which must not be zero.
+    pub fn pad(b: &[u8], block_size: usize) -> Result<Vec<u8>> {
+        check_block_size(block_size, usize::MAX)?;
+        let l = b.len();
+        let padding = (block_size - l % block_size) % block_size;
+        let mut res = Vec::with_capacity(l + padding);
+        res.extend(b);
+        res.resize(l + padding, 0);
+        Ok(res)
+    }
+
+    /// Remove the trailing zero bytes of the last block of `b`.  The
+    /// only errors are a length that is not a multiple of
+    /// `block_size` and a zero `block_size`.
+    pub fn unpad(b: &[u8], block_size: usize) -> Result<Vec<u8>> {
+        check_block_size(block_size, usize::MAX)?;
+        check_length(b.len(), block_size, true)?;
+        let l = b.len();
+        let last = l - ::std::cmp::min(l, block_size);
+        let end = b[last..].iter().rposition(|&x| x != 0).map_or(last, |i| last + i + 1);
+        Ok(b[..end].to_vec())
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::{pad, unpad};
+
+        #[test]
+        fn pad_0() {
+            assert_eq!(b"ICE ICE BABY\x00\x00\x00\x00".to_vec(), pad(b"ICE ICE BABY", 16).unwrap());
+            assert_eq!(b"YELLOW SUBMARINE".to_vec(), pad(b"YELLOW SUBMARINE", 16).unwrap());
+            assert_eq!(Vec::<u8>::new(), pad(b"", 16).unwrap());
+        }
+
+        #[test]
+        fn unpad_0() {
+            assert_eq!(b"ICE ICE BABY".to_vec(), unpad(b"ICE ICE BABY\x00\x00\x00\x00", 16).unwrap());
+            assert_eq!(b"YELLOW SUBMARINE".to_vec(), unpad(b"YELLOW SUBMARINE", 16).unwrap());
+            assert_eq!(b"\x00\x00\x00\x00".to_vec(), unpad(b"\x00\x00\x00\x00\x00\x00\x00\x00", 4).unwrap());
+            assert!(unpad(b"ICE ICE BABY\x00", 16).is_err());
+        }
+
+        quickcheck! {
+            fn prop_unpad_pad(xs: Vec<u8>) -> bool {
+                let mut xs = xs;
+                xs.push(1);
+                unpad(&pad(&xs, 16).unwrap(), 16).unwrap() == xs
+            }
+        }
+    }
+}
+
+/// PKCS#1 padding for RSA encryption, both the old v1.5 scheme
+/// (EME-PKCS1-v1_5) and OAEP.  Decoding reports exactly what is wrong
+/// with a block, so that padding oracles can be built which leak as
+/// much or as little as wanted.
+pub mod pkcs1 {
+    use crate::error::{Error, Result};
+    use crate::hash::Digest;
+    use byteorder::{BigEndian, ByteOrder};
+
+    /// Minimum number of random padding bytes in a v1.5 block.
+    pub const MIN_PADDING: usize = 8;
+
+    /// Pad `msg` to a block of `len` bytes for v1.5 encryption:
+    ///
+    /// ```text
+    /// 00 02 PS 00 M
+    /// ```
+    ///
+    /// where `PS` consists of at least 8 random non-zero bytes.
+    pub fn encode(msg: &[u8], len: usize) -> Result<Vec<u8>> {
+        if msg.len() + 3 + MIN_PADDING > len {
+            return Err(Error::MessageTooLong);
+        }
+        let mut block = Vec::with_capacity(len);
+        block.push(0x00);
+        block.push(0x02);
+        for _ in 0..len - msg.len() - 3 {
+            let mut b = 0;
+            while b == 0 {
+                b = crate::random::gen();
+            }
+            block.push(b);
+        }
+        block.push(0x00);
+        block.extend(msg);
+        Ok(block)
+    }
+
+    /// Remove v1.5 encryption padding from `block`.
+    pub fn decode(block: &[u8]) -> Result<Vec<u8>> {
+        if block.len() < 2 || block[0] != 0x00 || block[1] != 0x02 {
+            return Err(Error::InvalidPaddingBlockType);
+        }
+        match block[2..].iter().position(|&b| b == 0x00) {
+            Some(i) if i < MIN_PADDING => Err(Error::PaddingTooShort),
+            Some(i) => Ok(block[2 + i + 1..].to_vec()),
+            None => Err(Error::InvalidPaddingSeparator),
+        }
+    }
+
+    /// Mask generation function MGF1, producing `len` bytes from
+    /// `seed` using the hash function `D`.
+    pub fn mgf1<D: Digest>(seed: &[u8], len: usize) -> Vec<u8> {
+        let mut mask = Vec::with_capacity(len + D::OUTPUT_SIZE);
+        let mut counter = [0; 4];
+        let mut i = 0;
+        while mask.len() < len {
+            BigEndian::write_u32(&mut counter, i);
+            let mut d = D::new();
+            d.update(seed);
+            d.update(&counter);
+            mask.extend(d.finish());
+            i += 1;
+        }
+        mask.truncate(len);
+        mask
+    }
+
+    fn xor_in_place(dst: &mut [u8], mask: &[u8]) {
+        for (d, m) in dst.iter_mut().zip(mask) {
+            *d ^= *m;
+        }
+    }
+
+    /// Pad `msg` to a block of `len` bytes with OAEP, using the hash
+    /// function `D` both for hashing `label` and in MGF1:
+    ///
+    /// ```text
+    /// 00 maskedSeed maskedDB
+    /// ```
+    ///
+    /// where `DB = H(label) 00 ... 00 01 M` is masked with the random
+    /// seed and the seed is masked with the masked `DB` in turn.
+    pub fn encode_oaep<D: Digest>(msg: &[u8], label: &[u8], len: usize) -> Result<Vec<u8>> {
+        let h_len = D::OUTPUT_SIZE;
+        if msg.len() + 2 * h_len + 2 > len {
+            return Err(Error::MessageTooLong);
+        }
+        let mut db = D::digest(label);
+        db.resize(len - h_len - 1 - msg.len() - 1, 0x00);
+        db.push(0x01);
+        db.extend(msg);
+        let mut seed = vec![0; h_len];
+        crate::random::fill_bytes(&mut seed);
+
+        let db_mask = mgf1::<D>(&seed, db.len());
+        xor_in_place(&mut db, &db_mask);
+        xor_in_place(&mut seed, &mgf1::<D>(&db, h_len));
+
+        let mut block = Vec::with_capacity(len);
+        block.push(0x00);
+        block.extend(seed);
+        block.extend(db);
+        Ok(block)
+    }
+
+    /// Remove OAEP padding from `block`, which must have been encoded
+    /// with the same hash function `D` and `label`.
+    pub fn decode_oaep<D: Digest>(block: &[u8], label: &[u8]) -> Result<Vec<u8>> {
+        let h_len = D::OUTPUT_SIZE;
+        if block.len() < 2 * h_len + 2 {
+            return Err(Error::PaddingTooShort);
+        }
+        if block[0] != 0x00 {
+            return Err(Error::InvalidPaddingBlockType);
+        }
+        let mut seed = block[1..1 + h_len].to_vec();
+        let mut db = block[1 + h_len..].to_vec();
+        xor_in_place(&mut seed, &mgf1::<D>(&db, h_len));
+        let db_mask = mgf1::<D>(&seed, db.len());
+        xor_in_place(&mut db, &db_mask);
+
+        if db[..h_len] != D::digest(label)[..] {
+            return Err(Error::InvalidPaddingLabel);
+        }
+        match db[h_len..].iter().position(|&b| b != 0x00) {
+            Some(i) if db[h_len + i] == 0x01 => Ok(db[h_len + i + 1..].to_vec()),
+            _ => Err(Error::InvalidPaddingSeparator),
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::{encode, decode, mgf1, encode_oaep, decode_oaep};
+        use crate::codec::hex;
+        use crate::error::Error;
+        use crate::hash::sha1::Sha1;
+        use crate::hash::sha256::Sha256;
+
+        #[test]
+        fn encode_decode() {
+            let block = encode(b"YELLOW SUBMARINE", 64).unwrap();
+            assert_eq!(64, block.len());
+            assert_eq!(&[0x00, 0x02], &block[..2]);
+            assert!(!block[2..64 - 17].contains(&0x00));
+            assert_eq!(b"YELLOW SUBMARINE".to_vec(), decode(&block).unwrap());
+        }
+
+        #[test]
+        fn encode_too_long() {
+            assert!(encode(&[0x41; 21], 32).is_ok());
+            assert!(encode(&[0x41; 22], 32).is_err());
+        }
+
+        #[test]
+        fn decode_errors() {
+            let mut block = encode(b"YELLOW SUBMARINE", 32).unwrap();
+            block[1] = 0x01;
+            match decode(&block) {
+                Err(Error::InvalidPaddingBlockType) => (),
+                r => panic!("unexpected result: {:?}", r),
+            }
+            block[1] = 0x02;
+            block[5] = 0x00;
+            match decode(&block) {
+                Err(Error::PaddingTooShort) => (),
+                r => panic!("unexpected result: {:?}", r),
+            }
+            let block = [0x00, 0x02, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff];
+            match decode(&block) {
+                Err(Error::InvalidPaddingSeparator) => (),
+                r => panic!("unexpected result: {:?}", r),
+            }
+        }
+
+        #[test]
+        fn mgf1_sha1() {
+            assert_eq!("1ac907", hex::encode(&mgf1::<Sha1>(b"foo", 3)));
+            assert_eq!("1ac9075cd4", hex::encode(&mgf1::<Sha1>(b"foo", 5)));
+            assert_eq!("bc0c655e016bc2931d85a2e675181adcef7f581f76df2739da74faac41627be2\
+                        f7f415c89e983fd0ce80ced9878641cb4876",
+                       hex::encode(&mgf1::<Sha1>(b"bar", 50)));
+        }
+
+        #[test]
+        fn mgf1_sha256() {
+            assert_eq!("382576a7841021cc28fc4c0948753fb8312090cea942ea4c4e735d10dc724b15\
+                        5f9f6069f289d61daca0cb814502ef04eae1",
+                       hex::encode(&mgf1::<Sha256>(b"bar", 50)));
+        }
+
+        #[test]
+        fn oaep_encode_decode() {
+            let b1 = encode_oaep::<Sha1>(b"YELLOW SUBMARINE", b"", 128).unwrap();
+            let b2 = encode_oaep::<Sha1>(b"YELLOW SUBMARINE", b"", 128).unwrap();
+            assert_eq!(128, b1.len());
+            assert!(b1 != b2);
+            assert_eq!(b"YELLOW SUBMARINE".to_vec(), decode_oaep::<Sha1>(&b1, b"").unwrap());
+            assert_eq!(b"YELLOW SUBMARINE".to_vec(), decode_oaep::<Sha1>(&b2, b"").unwrap());
+            let b3 = encode_oaep::<Sha256>(b"", b"label", 66).unwrap();
+            assert_eq!(Vec::<u8>::new(), decode_oaep::<Sha256>(&b3, b"label").unwrap());
+            assert!(encode_oaep::<Sha256>(b"x", b"label", 66).is_err());
+        }
+
+        #[test]
+        fn oaep_decode_errors() {
+            let block = encode_oaep::<Sha1>(b"YELLOW SUBMARINE", b"", 128).unwrap();
+            match decode_oaep::<Sha1>(&block, b"other label") {
+                Err(Error::InvalidPaddingLabel) => (),
+                r => panic!("unexpected result: {:?}", r),
+            }
+            let mut bad = block.clone();
+            bad[0] = 0x01;
+            match decode_oaep::<Sha1>(&bad, b"") {
+                Err(Error::InvalidPaddingBlockType) => (),
+                r => panic!("unexpected result: {:?}", r),
+            }
+            match decode_oaep::<Sha1>(&block[..41], b"") {
+                Err(Error::PaddingTooShort) => (),
+                r => panic!("unexpected result: {:?}", r),
+            }
+        }
+
+        quickcheck! {
+            fn prop_encode_decode(xs: Vec<u8>) -> bool {
+                let len = xs.len() + 11;
+                decode(&encode(&xs, len).unwrap()).unwrap() == xs
+            }
+
+            fn prop_oaep_encode_decode(xs: Vec<u8>) -> bool {
+                let len = xs.len() + 42;
+                decode_oaep::<Sha1>(&encode_oaep::<Sha1>(&xs, b"", len).unwrap(), b"").unwrap() == xs
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Padding, PaddingScheme, NoPadding, Endianness, md_length};
+
+    #[test]
+    fn no_padding() {
+        assert_eq!(b"YELLOW SUBMARINE".to_vec(), NoPadding.pad(b"YELLOW SUBMARINE", 16).unwrap());
+        assert_eq!(b"YELLOW SUBMARINE".to_vec(), NoPadding.unpad(b"YELLOW SUBMARINE", 16).unwrap());
+        assert_eq!(Vec::<u8>::new(), NoPadding.pad(b"", 16).unwrap());
+        assert!(NoPadding.pad(b"YELLOW", 16).is_err());
+        assert!(NoPadding.unpad(b"YELLOW", 16).is_err());
+        assert!(NoPadding.unpad(b"", 0).is_err());
+    }
+
+    #[test]
+    fn md_length_0() {
+        let pad = md_length(3, 64, Endianness::Big);
+        assert_eq!(61, pad.len());
+        assert_eq!(0x80, pad[0]);
+        assert_eq!(&[0, 0, 0, 0, 0, 0, 0, 24], &pad[53..]);
+        let pad = md_length(56, 64, Endianness::Little);
+        assert_eq!(72, pad.len());
+        assert_eq!(&[0xc0, 1, 0, 0, 0, 0, 0, 0], &pad[64..]);
+    }
+
+    quickcheck! {
+        fn prop_md_length(len: u16) -> bool {
+            (len as usize + md_length(len as u64, 64, Endianness::Big).len()) % 64 == 0
+        }
+
+        fn prop_scheme_unpad_pad(xs: Vec<u8>) -> bool {
+            let mut xs = xs;
+            xs.push(1);
+            [PaddingScheme::Pkcs7, PaddingScheme::AnsiX923, PaddingScheme::Iso7816, PaddingScheme::Zero]
+                .iter()
+                .all(|s| {
+                    let padded = s.pad(&xs, 8).unwrap();
+                    s.pad(&xs, 0).is_err() &&
+                    padded.len() % 8 == 0 && s.unpad(&padded, 8).unwrap() == xs
+                })
+        }
+    }
+}
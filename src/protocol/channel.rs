@@ -0,0 +1,315 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! In-memory message passing between the parties of a protocol.  The
+//! challenges describe the protocols as messages sent over a network,
+//! with an attacker in the middle who can read, change, drop or
+//! inject messages.  Here the network is a pair of duplex channels
+//! with a `MitM` in between, and the parties are state machines that
+//! answer each message they receive.
+//!
+//! `run` delivers the messages one at a time on the calling thread, so
+//! a simulation always gives the same interleaving of messages.  The
+//! `Endpoint`s can also be moved to threads of their own.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::mpsc::{self, Sender, Receiver, TryRecvError};
+
+use crate::error::Error;
+use crate::protocol::{Encode, Stage, Transcript};
+
+/// Direction in which a message travels.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    /// From the client, which starts the protocol, to the server.
+    ClientToServer,
+    /// From the server to the client.
+    ServerToClient,
+}
+
+impl Direction {
+    /// Return the opposite direction.
+    pub fn reverse(self) -> Direction {
+        match self {
+            Direction::ClientToServer => Direction::ServerToClient,
+            Direction::ServerToClient => Direction::ClientToServer,
+        }
+    }
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Direction::ClientToServer => write!(f, "C->S"),
+            Direction::ServerToClient => write!(f, "S->C"),
+        }
+    }
+}
+
+/// One end of a duplex channel.
+pub struct Endpoint<M> {
+    tx: Sender<M>,
+    rx: Receiver<M>,
+}
+
+/// Create a duplex channel, returning its two ends.  Messages sent on
+/// one end are received on the other, in order.
+pub fn duplex<M>() -> (Endpoint<M>, Endpoint<M>) {
+    let (tx0, rx0) = mpsc::channel();
+    let (tx1, rx1) = mpsc::channel();
+    (Endpoint { tx: tx0, rx: rx1 }, Endpoint { tx: tx1, rx: rx0 })
+}
+
+impl<M> Endpoint<M> {
+    /// Send `message` to the other end.  Returns
+    /// `Error::ChannelClosed` if the other end has been dropped.
+    pub fn send(&self, message: M) -> Result<(), Error> {
+        self.tx.send(message).map_err(Error::from)
+    }
+
+    /// Wait for the next message from the other end.  Returns
+    /// `Error::ChannelClosed` if the other end has been dropped.
+    pub fn recv(&self) -> Result<M, Error> {
+        self.rx.recv().map_err(Error::from)
+    }
+
+    /// Return the next message from the other end if there is one,
+    /// without waiting.
+    pub fn try_recv(&self) -> Result<Option<M>, Error> {
+        match self.rx.try_recv() {
+            Ok(message) => Ok(Some(message)),
+            Err(TryRecvError::Empty) => Ok(None),
+            Err(TryRecvError::Disconnected) => Err(Error::ChannelClosed),
+        }
+    }
+}
+
+/// A participant in a protocol with messages of type `M`.
+pub trait Party<M> {
+    /// Return the messages to send before anything has been
+    /// received.  Only the client of a protocol sends anything here.
+    fn start(&mut self) -> Vec<M> {
+        Vec::new()
+    }
+
+    /// Handle `message` and return the messages to send in reply.
+    fn receive(&mut self, message: M) -> Result<Vec<M>, Error>;
+}
+
+/// An attacker sitting on the channel between client and server.
+/// Any closure `FnMut(Direction, M) -> Vec<(Direction, M)>` is a
+/// `MitM`.
+pub trait MitM<M> {
+    /// Intercept `message` travelling in `direction`, and return the
+    /// messages to deliver instead.  Returning the message unchanged
+    /// with the same direction passes it on; returning nothing drops
+    /// it, and messages in the reverse direction answer the sender
+    /// in place of the other party.
+    fn intercept(&mut self, direction: Direction, message: M) -> Vec<(Direction, M)>;
+}
+
+impl<M, F> MitM<M> for F
+    where F: FnMut(Direction, M) -> Vec<(Direction, M)>
+{
+    fn intercept(&mut self, direction: Direction, message: M) -> Vec<(Direction, M)> {
+        self(direction, message)
+    }
+}
+
+/// A `MitM` that passes every message on unchanged.
+pub struct Passthrough;
+
+impl<M> MitM<M> for Passthrough {
+    fn intercept(&mut self, direction: Direction, message: M) -> Vec<(Direction, M)> {
+        vec![(direction, message)]
+    }
+}
+
+/// Send all of `messages` on `endpoint`.
+fn send_all<M>(endpoint: &Endpoint<M>, messages: Vec<M>) -> Result<(), Error> {
+    for message in messages {
+        endpoint.send(message)?;
+    }
+    Ok(())
+}
+
+/// Run the protocol between `client` and `server` until no messages
+/// are left in flight, with every message passing through `mitm`.
+/// Returns the number of messages delivered to either party, or the
+/// first error returned by a party.
+pub fn run<M, C, S, X>(client: &mut C, server: &mut S, mitm: &mut X) -> Result<usize, Error>
+    where C: Party<M>, S: Party<M>, X: MitM<M> + ?Sized
+{
+    exchange(client, server, mitm, |_, _, _| ())
+}
+
+/// Like `run`, but record every message in `transcript`, both as it
+/// was sent and as it was delivered.
+pub fn run_recorded<M, C, S, X>(client: &mut C, server: &mut S, mitm: &mut X,
+                                transcript: &mut Transcript) -> Result<usize, Error>
+    where M: Encode, C: Party<M>, S: Party<M>, X: MitM<M> + ?Sized
+{
+    exchange(client, server, mitm, |stage, direction, message| transcript.record(stage, direction, message))
+}
+
+/// Run the protocol, calling `observe` for every message sent and
+/// delivered.
+fn exchange<M, C, S, X, F>(client: &mut C, server: &mut S, mitm: &mut X, mut observe: F)
+                           -> Result<usize, Error>
+    where C: Party<M>, S: Party<M>, X: MitM<M> + ?Sized, F: FnMut(Stage, Direction, &M)
+{
+    let (client_end, mitm_client) = duplex();
+    let (mitm_server, server_end) = duplex();
+    send_all(&client_end, client.start())?;
+
+    let mut delivered = 0;
+    let mut intercepted = VecDeque::new();
+    loop {
+        let mut progress = false;
+
+        // Everything the parties have sent goes through the attacker
+        // first.
+        while let Some(message) = mitm_client.try_recv()? {
+            observe(Stage::Sent, Direction::ClientToServer, &message);
+            intercepted.extend(mitm.intercept(Direction::ClientToServer, message));
+        }
+        while let Some(message) = mitm_server.try_recv()? {
+            observe(Stage::Sent, Direction::ServerToClient, &message);
+            intercepted.extend(mitm.intercept(Direction::ServerToClient, message));
+        }
+        while let Some((direction, message)) = intercepted.pop_front() {
+            match direction {
+                Direction::ClientToServer => mitm_server.send(message)?,
+                Direction::ServerToClient => mitm_client.send(message)?,
+            }
+        }
+
+        while let Some(message) = server_end.try_recv()? {
+            observe(Stage::Delivered, Direction::ClientToServer, &message);
+            delivered += 1;
+            progress = true;
+            send_all(&server_end, server.receive(message)?)?;
+        }
+        while let Some(message) = client_end.try_recv()? {
+            observe(Stage::Delivered, Direction::ServerToClient, &message);
+            delivered += 1;
+            progress = true;
+            send_all(&client_end, client.receive(message)?)?;
+        }
+
+        if !progress {
+            return Ok(delivered);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::{duplex, run, Direction, Party, Passthrough};
+    use crate::error::Error;
+
+    /// Client counting down from a start value.
+    struct Counter {
+        start: u32,
+        last: Option<u32>,
+    }
+
+    impl Party<u32> for Counter {
+        fn start(&mut self) -> Vec<u32> {
+            vec![self.start]
+        }
+
+        fn receive(&mut self, message: u32) -> Result<Vec<u32>, Error> {
+            self.last = Some(message);
+            Ok(if message == 0 { vec![] } else { vec![message - 1] })
+        }
+    }
+
+    /// Server answering each number with the number minus one.
+    struct Decrement;
+
+    impl Party<u32> for Decrement {
+        fn receive(&mut self, message: u32) -> Result<Vec<u32>, Error> {
+            match message {
+                0 => Err(Error::UnexpectedMessage),
+                n => Ok(vec![n - 1]),
+            }
+        }
+    }
+
+    #[test]
+    fn passthrough() {
+        let mut client = Counter { start: 5, last: None };
+        assert_eq!(6, run(&mut client, &mut Decrement, &mut Passthrough).unwrap());
+        assert_eq!(Some(0), client.last);
+    }
+
+    #[test]
+    fn observe() {
+        let mut client = Counter { start: 5, last: None };
+        let mut seen = Vec::new();
+        run(&mut client, &mut Decrement, &mut |d: Direction, m: u32| {
+            seen.push((d, m));
+            vec![(d, m)]
+        }).unwrap();
+        assert_eq!(vec![(Direction::ClientToServer, 5), (Direction::ServerToClient, 4),
+                        (Direction::ClientToServer, 3), (Direction::ServerToClient, 2),
+                        (Direction::ClientToServer, 1), (Direction::ServerToClient, 0)],
+                   seen);
+    }
+
+    #[test]
+    fn answer_in_place_of_server() {
+        // The attacker answers the client itself, and the server never
+        // sees a message.
+        let mut client = Counter { start: 7, last: None };
+        let delivered = run(&mut client, &mut Decrement, &mut |d: Direction, m: u32| {
+            match d {
+                Direction::ClientToServer if m > 0 => vec![(d.reverse(), 0)],
+                _ => vec![],
+            }
+        }).unwrap();
+        assert_eq!(1, delivered);
+        assert_eq!(Some(0), client.last);
+    }
+
+    #[test]
+    fn party_error() {
+        let mut client = Counter { start: 3, last: None };
+        match run(&mut client, &mut Decrement, &mut |d: Direction, _: u32| vec![(d, 0)]) {
+            Err(Error::UnexpectedMessage) => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn threads() {
+        let (a, b) = duplex();
+        let echo = thread::spawn(move || {
+            while let Ok(m) = b.recv() {
+                b.send(m * 2).unwrap();
+            }
+        });
+        a.send(21u32).unwrap();
+        assert_eq!(42, a.recv().unwrap());
+        drop(a);
+        echo.join().unwrap();
+    }
+
+    #[test]
+    fn closed() {
+        let (a, b) = duplex::<u32>();
+        drop(b);
+        match a.send(1) {
+            Err(Error::ChannelClosed) => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+        match a.try_recv() {
+            Err(Error::ChannelClosed) => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+}
@@ -0,0 +1,247 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! Diffie-Hellman key exchange with the echo bot from challenge 34.
+//!
+//! 1. C->S: `p`, `g`, `A = g^a mod p`
+//! 2. S->C: `B = g^b mod p`
+//! 3. C->S: `AES-CBC(HKDF-SHA256(s)[0:16], iv=random(16), msg) + iv`
+//! 4. S->C: the same message, encrypted again with a fresh IV
+//!
+//! where `s` is the shared secret `B^a = A^b mod p`.  The parties
+//! implement `channel::Party` for `Message`.
+
+use crate::bignum::BigUint;
+use crate::cipher::aes::{self, AesKey};
+use crate::error::Error;
+use crate::hash::sha256::Sha256;
+use crate::kdf;
+use crate::protocol::{Encode, push_field};
+use crate::protocol::channel::Party;
+
+/// Group parameters.
+#[derive(Clone, Debug)]
+pub struct Params {
+    /// Prime modulus.
+    pub p: BigUint,
+    /// Generator.
+    pub g: BigUint,
+}
+
+impl Params {
+    /// The parameters from challenge 33: the 1536 bit MODP prime from
+    /// RFC 3526 and `g = 2`.
+    pub fn nist() -> Params {
+        let p = BigUint::from_hex(
+            "ffffffffffffffffc90fdaa22168c234c4c6628b80dc1cd129024e088a67cc74\
+             020bbea63b139b22514a08798e3404ddef9519b3cd3a431b302b0a6df25f1437\
+             4fe1356d6d51c245e485b576625e7ec6f44c42e9a637ed6b0bff5cb6f406b7ed\
+             ee386bfb5a899fa5ae9f24117c4b1fe649286651ece45b3dc2007cb8a163bf05\
+             98da48361c55d39a69163fa8fd24cf5f83655d23dca3ad961c62f356208552bb\
+             9ed529077096966d670c354e4abc9804f1746c08ca237327ffffffffffffffff").unwrap();
+        Params {
+            p: p,
+            g: BigUint::from_u64(2),
+        }
+    }
+}
+
+/// Messages of the echo protocol.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Message {
+    /// Group parameters and the client's public key.
+    Hello {
+        /// Prime modulus.
+        p: BigUint,
+        /// Generator.
+        g: BigUint,
+        /// Client's public key.
+        public: BigUint,
+    },
+    /// Server's public key.
+    Public(BigUint),
+    /// Ciphertext followed by the IV.
+    Data(Vec<u8>),
+}
+
+impl Encode for Message {
+    fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        match *self {
+            Message::Hello { ref p, ref g, ref public } => {
+                out.push(1);
+                push_field(&mut out, &p.to_bytes_be());
+                push_field(&mut out, &g.to_bytes_be());
+                push_field(&mut out, &public.to_bytes_be());
+            }
+            Message::Public(ref public) => {
+                out.push(2);
+                push_field(&mut out, &public.to_bytes_be());
+            }
+            Message::Data(ref data) => {
+                out.push(3);
+                push_field(&mut out, data);
+            }
+        }
+        out
+    }
+}
+
+/// Derive the 16-byte AES key from the shared secret with
+/// HKDF-SHA256.
+pub fn derive_key(secret: &BigUint) -> AesKey {
+    let mut key = [0; 16];
+    key.copy_from_slice(&kdf::hkdf::<Sha256>(b"", &secret.to_bytes_be(), b"DH session key", 16));
+    AesKey::from(key)
+}
+
+/// Encrypt `message` in CBC mode with a random IV, and append the IV.
+pub fn encrypt(key: &AesKey, message: &[u8]) -> Vec<u8> {
+    let mut iv = [0; 16];
+    crate::random::fill_bytes(&mut iv);
+    let mut data = aes::encrypt_cbc(key, &iv, message);
+    data.extend_from_slice(&iv);
+    data
+}
+
+/// Decrypt a message produced by `encrypt`.
+pub fn decrypt(key: &AesKey, data: &[u8]) -> Result<Vec<u8>, Error> {
+    if data.len() < 16 {
+        return Err(Error::InvalidCiphertextLength {
+            len: data.len(),
+            block_size: 16,
+        });
+    }
+    let (ciphertext, iv_bytes) = data.split_at(data.len() - 16);
+    let mut iv = [0; 16];
+    iv.copy_from_slice(iv_bytes);
+    aes::decrypt_cbc(key, &iv, ciphertext)
+}
+
+/// Client, which sends a message and waits for the echo.
+pub struct Client {
+    params: Params,
+    secret: BigUint,
+    message: Vec<u8>,
+    key: Option<AesKey>,
+    echo: Option<Vec<u8>>,
+}
+
+impl Client {
+    /// Create a client that will send `message` to the echo bot.
+    pub fn new(params: Params, message: &[u8]) -> Client {
+        let secret = BigUint::random_below(&params.p);
+        Client {
+            params: params,
+            secret: secret,
+            message: message.to_vec(),
+            key: None,
+            echo: None,
+        }
+    }
+
+    /// Return the echo received from the server, if any.
+    pub fn echo(&self) -> Option<&[u8]> {
+        self.echo.as_ref().map(|e| &e[..])
+    }
+}
+
+impl Party<Message> for Client {
+    fn start(&mut self) -> Vec<Message> {
+        let public = self.params.g.modpow(&self.secret, &self.params.p);
+        vec![Message::Hello {
+            p: self.params.p.clone(),
+            g: self.params.g.clone(),
+            public: public,
+        }]
+    }
+
+    fn receive(&mut self, message: Message) -> Result<Vec<Message>, Error> {
+        match (message, self.key.is_some()) {
+            (Message::Public(server_public), false) => {
+                let key = derive_key(&server_public.modpow(&self.secret, &self.params.p));
+                let data = encrypt(&key, &self.message);
+                self.key = Some(key);
+                Ok(vec![Message::Data(data)])
+            }
+            (Message::Data(data), true) => {
+                let echo = decrypt(self.key.as_ref().unwrap(), &data)?;
+                self.echo = Some(echo);
+                Ok(vec![])
+            }
+            _ => Err(Error::UnexpectedMessage),
+        }
+    }
+}
+
+/// Server, which echoes every message back to the client.
+pub struct EchoBot {
+    key: Option<AesKey>,
+}
+
+impl EchoBot {
+    /// Create a new echo bot.
+    pub fn new() -> EchoBot {
+        EchoBot { key: None }
+    }
+}
+
+impl Default for EchoBot {
+    fn default() -> EchoBot {
+        EchoBot::new()
+    }
+}
+
+impl Party<Message> for EchoBot {
+    fn receive(&mut self, message: Message) -> Result<Vec<Message>, Error> {
+        match message {
+            Message::Hello { p, g, public } => {
+                let secret = BigUint::random_below(&p);
+                self.key = Some(derive_key(&public.modpow(&secret, &p)));
+                Ok(vec![Message::Public(g.modpow(&secret, &p))])
+            }
+            Message::Data(data) => {
+                let key = match self.key {
+                    Some(ref key) => key,
+                    None => return Err(Error::UnexpectedMessage),
+                };
+                let plaintext = decrypt(key, &data)?;
+                Ok(vec![Message::Data(encrypt(key, &plaintext))])
+            }
+            Message::Public(_) => Err(Error::UnexpectedMessage),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Params, Message, Client, EchoBot, derive_key, encrypt, decrypt};
+    use crate::bignum::BigUint;
+    use crate::error::Error;
+    use crate::protocol::channel::{self, Party, Passthrough};
+
+    #[test]
+    fn echo() {
+        let mut client = Client::new(Params::nist(), b"Hello, bot!");
+        let mut server = EchoBot::new();
+        assert_eq!(4, channel::run(&mut client, &mut server, &mut Passthrough).unwrap());
+        assert_eq!(Some(&b"Hello, bot!"[..]), client.echo());
+    }
+
+    #[test]
+    fn roundtrip() {
+        let key = derive_key(&BigUint::from_u64(12345));
+        let data = encrypt(&key, b"attack at dawn");
+        assert_eq!(32, data.len());
+        assert_eq!(b"attack at dawn".to_vec(), decrypt(&key, &data).unwrap());
+    }
+
+    #[test]
+    fn data_before_hello() {
+        let mut server = EchoBot::new();
+        match server.receive(Message::Data(vec![0; 32])) {
+            Err(Error::UnexpectedMessage) => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+}
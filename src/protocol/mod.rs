@@ -0,0 +1,180 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! Simulations of the key exchange and authentication protocols from
+//! the challenges.  The parties are plain structs whose methods
+//! correspond to the messages sent over the wire, so that attacks can
+//! take the place of either party.  They also implement
+//! `channel::Party`, so that a man in the middle can be placed on the
+//! channel between them.
+//!
+//! A `Transcript` records the bytes of every message in a run, see
+//! `channel::run_recorded`.
+
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use byteorder::{BigEndian, ByteOrder};
+
+use crate::codec::hex;
+use self::channel::Direction;
+
+pub mod channel;
+pub mod dh;
+pub mod record;
+pub mod srp;
+
+/// Messages that can be written as bytes, as they would be sent over
+/// a network.
+pub trait Encode {
+    /// Return the encoding of the message.
+    fn encode(&self) -> Vec<u8>;
+}
+
+impl Encode for Vec<u8> {
+    fn encode(&self) -> Vec<u8> {
+        self.clone()
+    }
+}
+
+/// Append `field` to `out`, preceded by its length as a 32 bit big
+/// endian number.  The message encodings are a tag byte followed by
+/// fields in this format.
+fn push_field(out: &mut Vec<u8>, field: &[u8]) {
+    let mut len = [0; 4];
+    BigEndian::write_u32(&mut len, field.len() as u32);
+    out.extend_from_slice(&len);
+    out.extend_from_slice(field);
+}
+
+/// Point at which a message was recorded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stage {
+    /// Sent by a party, as seen by the man in the middle.
+    Sent,
+    /// Delivered to a party, after the man in the middle.
+    Delivered,
+}
+
+/// A message in a transcript.
+#[derive(Clone, Debug)]
+pub struct Entry {
+    /// Direction of the message.
+    pub direction: Direction,
+    /// Whether the message was recorded when it was sent or when it
+    /// was delivered.
+    pub stage: Stage,
+    /// Time since the start of the transcript.
+    pub time: Duration,
+    /// Encoded message.
+    pub bytes: Vec<u8>,
+}
+
+impl Entry {
+    /// Return a hex dump of the message, with 16 bytes per line, each
+    /// line starting with the offset of its first byte.
+    pub fn hex_dump(&self) -> String {
+        let mut dump = String::new();
+        for (i, line) in self.bytes.chunks(16).enumerate() {
+            dump.push_str(&format!("{:04x}  {}\n", i * 16, hex::encode_with(line, " ", false, 1)));
+        }
+        dump
+    }
+}
+
+/// Record of the messages exchanged in a protocol run.
+#[derive(Clone, Debug)]
+pub struct Transcript {
+    start: Instant,
+    entries: Vec<Entry>,
+}
+
+impl Transcript {
+    /// Create an empty transcript.  Times are measured from now.
+    pub fn new() -> Transcript {
+        Transcript {
+            start: Instant::now(),
+            entries: Vec::new(),
+        }
+    }
+
+    /// Append `message` to the transcript.
+    pub fn record<M: Encode>(&mut self, stage: Stage, direction: Direction, message: &M) {
+        self.entries.push(Entry {
+            direction: direction,
+            stage: stage,
+            time: self.start.elapsed(),
+            bytes: message.encode(),
+        });
+    }
+
+    /// Return all entries, in the order they were recorded.
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// Return the encoded messages recorded at `stage` going in
+    /// `direction`, in order.
+    pub fn messages(&self, stage: Stage, direction: Direction) -> Vec<&[u8]> {
+        self.entries.iter()
+            .filter(|e| e.stage == stage && e.direction == direction)
+            .map(|e| &e.bytes[..])
+            .collect()
+    }
+}
+
+impl Default for Transcript {
+    fn default() -> Transcript {
+        Transcript::new()
+    }
+}
+
+impl fmt::Display for Transcript {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for e in &self.entries {
+            let stage = match e.stage {
+                Stage::Sent => "sent",
+                Stage::Delivered => "delivered",
+            };
+            writeln!(f, "{}.{:06} {} {}, {} bytes", e.time.as_secs(), e.time.subsec_micros(),
+                          e.direction, stage, e.bytes.len())?;
+            write!(f, "{}", e.hex_dump())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{push_field, Stage, Transcript};
+    use super::channel::Direction;
+
+    #[test]
+    fn field() {
+        let mut out = vec![7];
+        push_field(&mut out, b"abc");
+        assert_eq!(vec![7, 0, 0, 0, 3, b'a', b'b', b'c'], out);
+    }
+
+    #[test]
+    fn transcript() {
+        let mut t = Transcript::new();
+        t.record(Stage::Sent, Direction::ClientToServer, &b"hello".to_vec());
+        t.record(Stage::Delivered, Direction::ClientToServer, &b"HELLO".to_vec());
+        t.record(Stage::Sent, Direction::ServerToClient, &(0..20).collect::<Vec<u8>>());
+        assert_eq!(3, t.entries().len());
+        assert!(t.entries()[0].time <= t.entries()[2].time);
+        assert_eq!(vec![&b"HELLO"[..]], t.messages(Stage::Delivered, Direction::ClientToServer));
+        assert!(t.messages(Stage::Delivered, Direction::ServerToClient).is_empty());
+        assert_eq!("0000  00 01 02 03 04 05 06 07 08 09 0a 0b 0c 0d 0e 0f\n0010  10 11 12 13\n",
+                   t.entries()[2].hex_dump());
+    }
+
+    #[test]
+    fn display() {
+        let mut t = Transcript::new();
+        t.record(Stage::Delivered, Direction::ServerToClient, &vec![0xca, 0xfe]);
+        let s = t.to_string();
+        assert!(s.ends_with(" S->C delivered, 2 bytes\n0000  ca fe\n"), "{}", s);
+    }
+}
@@ -0,0 +1,310 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! A toy record layer in the style of SSL 3.0 and TLS 1.1, which
+//! protects records with MAC-then-encrypt: the data is followed by
+//! `HMAC-SHA1(mac_key, seq | type | length | data)`, padded, and
+//! encrypted with AES-128 in CBC mode under a random explicit IV.
+//! A record is
+//!
+//! ```text
+//! type (1) | length (2) | IV (16) | AES-CBC(data | MAC (20) | padding)
+//! ```
+//!
+//! where the length counts the IV and the ciphertext.  Unlike in the
+//! real protocols, the padding is PKCS#7, `n` bytes of value `n`.
+//!
+//! A `Reader` can check the padding like SSL 3.0, which only looks at
+//! the last byte (this is what POODLE exploits), or like TLS, which
+//! checks every padding byte.  Either way, it reports padding errors
+//! differently from MAC errors, and skips the MAC when the padding is
+//! invalid, so the padding check leaks both through the error and
+//! through the work done.  The work is counted in SHA-1 compression
+//! function calls, see `Reader::cost`, as a deterministic stand-in
+//! for the response time.  Lucky 13 exploits the much smaller timing
+//! difference that remains when the MAC is always computed.
+
+use byteorder::{BigEndian, ByteOrder};
+
+use crate::cipher::aes::{self, AesKey};
+use crate::error::Error;
+use crate::hash::sha1::Sha1;
+use crate::mac::hmac;
+use crate::padding::Padding;
+
+/// Block size of the cipher.
+pub const BLOCK_SIZE: usize = 16;
+
+/// Size of the MAC.
+pub const MAC_SIZE: usize = 20;
+
+/// Size of the record header.
+pub const HEADER_SIZE: usize = 3;
+
+/// Content type for application data.
+pub const APPLICATION_DATA: u8 = 23;
+
+/// How a `Reader` checks the padding.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PaddingCheck {
+    /// Only check that the last byte is a valid padding length.
+    Ssl3,
+    /// Check every byte of the padding.
+    Tls,
+}
+
+/// Padding that is neither added nor removed, for handling the
+/// record padding ourselves.
+struct Raw;
+
+impl Padding for Raw {
+    fn pad(&self, b: &[u8], _block_size: usize) -> Result<Vec<u8>, Error> {
+        Ok(b.to_vec())
+    }
+
+    fn unpad(&self, b: &[u8], _block_size: usize) -> Result<Vec<u8>, Error> {
+        Ok(b.to_vec())
+    }
+}
+
+/// Encryption and MAC keys for one direction of a connection.
+#[derive(Clone)]
+pub struct Keys {
+    enc: [u8; 16],
+    mac: [u8; MAC_SIZE],
+}
+
+impl Keys {
+    /// Create keys from the encryption key `enc` and the MAC key `mac`.
+    pub fn new(enc: [u8; 16], mac: [u8; MAC_SIZE]) -> Keys {
+        Keys {
+            enc: enc,
+            mac: mac,
+        }
+    }
+
+    /// Generate random keys.
+    pub fn random() -> Keys {
+        let mut keys = Keys::new([0; 16], [0; MAC_SIZE]);
+        crate::random::fill_bytes(&mut keys.enc);
+        crate::random::fill_bytes(&mut keys.mac);
+        keys
+    }
+}
+
+/// Compute the MAC of a record with sequence number `seq`.
+fn mac(keys: &Keys, seq: u64, content_type: u8, data: &[u8]) -> Vec<u8> {
+    let mut input = vec![0; 11];
+    BigEndian::write_u64(&mut input[..8], seq);
+    input[8] = content_type;
+    BigEndian::write_u16(&mut input[9..], data.len() as u16);
+    input.extend_from_slice(data);
+    hmac::hmac::<Sha1>(&keys.mac, &input)
+}
+
+/// Return the number of SHA-1 compression function calls for
+/// computing the MAC over `len` bytes of data.
+fn mac_cost(len: usize) -> usize {
+    // The inner hash covers the key block, the 11 byte header, the
+    // data and at least 9 bytes of MD padding; the outer hash covers
+    // the key block and the inner hash, which fit into two blocks.
+    1 + (11 + len + 9 + 63) / 64 + 2
+}
+
+/// Sending side of a connection.
+pub struct Writer {
+    keys: Keys,
+    seq: u64,
+}
+
+impl Writer {
+    /// Create a writer using `keys`, starting at sequence number zero.
+    pub fn new(keys: Keys) -> Writer {
+        Writer {
+            keys: keys,
+            seq: 0,
+        }
+    }
+
+    /// Protect `data` and return the record.
+    ///
+    /// # Panics
+    /// Panics if the record would be longer than 65535 bytes.
+    pub fn seal(&mut self, content_type: u8, data: &[u8]) -> Vec<u8> {
+        let mut plaintext = data.to_vec();
+        plaintext.extend(mac(&self.keys, self.seq, content_type, data));
+        let n = BLOCK_SIZE - plaintext.len() % BLOCK_SIZE;
+        plaintext.extend(vec![n as u8; n]);
+        self.seq += 1;
+
+        let mut iv = [0; BLOCK_SIZE];
+        crate::random::fill_bytes(&mut iv);
+        let key = AesKey::from(self.keys.enc);
+        let ciphertext = aes::encrypt_cbc_with(&key, &iv, &plaintext, &Raw).unwrap();
+        let len = BLOCK_SIZE + ciphertext.len();
+        assert!(len <= 0xffff, "record too long");
+
+        let mut record = vec![content_type, 0, 0];
+        BigEndian::write_u16(&mut record[1..], len as u16);
+        record.extend_from_slice(&iv);
+        record.extend(ciphertext);
+        record
+    }
+}
+
+/// Receiving side of a connection.
+pub struct Reader {
+    keys: Keys,
+    seq: u64,
+    check: PaddingCheck,
+    cost: usize,
+}
+
+impl Reader {
+    /// Create a reader using `keys`, starting at sequence number
+    /// zero, which checks the padding as specified by `check`.
+    pub fn new(keys: Keys, check: PaddingCheck) -> Reader {
+        Reader {
+            keys: keys,
+            seq: 0,
+            check: check,
+            cost: 0,
+        }
+    }
+
+    /// Return the number of SHA-1 compression function calls spent on
+    /// the last record.
+    pub fn cost(&self) -> usize {
+        self.cost
+    }
+
+    /// Decrypt and verify `record`, returning its content type and
+    /// data.  Returns `Error::InvalidCiphertextLength` if the record
+    /// is malformed, `Error::InvalidPadding` if the padding is
+    /// invalid, and `Error::InvalidTag` if the MAC does not match.
+    ///
+    /// The sequence number advances with every record, valid or not.
+    /// A real connection would be closed after an error; the attacks
+    /// keep both sides in step by passing on one record for each
+    /// record sent.
+    pub fn open(&mut self, record: &[u8]) -> Result<(u8, Vec<u8>), Error> {
+        let seq = self.seq;
+        self.seq += 1;
+        self.cost = 0;
+
+        let body_len = record.len().saturating_sub(HEADER_SIZE);
+        if record.len() < HEADER_SIZE || BigEndian::read_u16(&record[1..3]) as usize != body_len ||
+            body_len % BLOCK_SIZE != 0 || body_len < 3 * BLOCK_SIZE {
+            return Err(Error::InvalidCiphertextLength {
+                len: body_len,
+                block_size: BLOCK_SIZE,
+            });
+        }
+        let content_type = record[0];
+        let mut iv = [0; BLOCK_SIZE];
+        iv.copy_from_slice(&record[HEADER_SIZE..HEADER_SIZE + BLOCK_SIZE]);
+        let plaintext = aes::decrypt_cbc_with(&AesKey::from(self.keys.enc), &iv,
+                                                   &record[HEADER_SIZE + BLOCK_SIZE..], &Raw)?;
+
+        let l = plaintext.len();
+        let n = plaintext[l - 1] as usize;
+        let valid = n > 0 && n <= BLOCK_SIZE && l >= n + MAC_SIZE && match self.check {
+            PaddingCheck::Ssl3 => true,
+            PaddingCheck::Tls => plaintext[l - n..].iter().all(|&b| b as usize == n),
+        };
+        if !valid {
+            return Err(Error::InvalidPadding {
+                observed: plaintext[l - 1],
+                block_size: BLOCK_SIZE,
+            });
+        }
+
+        let (data, tag) = plaintext[..l - n].split_at(l - n - MAC_SIZE);
+        self.cost = mac_cost(data.len());
+        if !hmac::verify(&mac(&self.keys, seq, content_type, data), tag) {
+            return Err(Error::InvalidTag);
+        }
+        Ok((content_type, data.to_vec()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Keys, Writer, Reader, PaddingCheck, APPLICATION_DATA, HEADER_SIZE, BLOCK_SIZE};
+    use crate::error::Error;
+
+    fn connection(check: PaddingCheck) -> (Writer, Reader) {
+        let keys = Keys::random();
+        (Writer::new(keys.clone()), Reader::new(keys, check))
+    }
+
+    #[test]
+    fn roundtrip() {
+        let (mut writer, mut reader) = connection(PaddingCheck::Tls);
+        for len in 0..40 {
+            let data: Vec<u8> = (0..len).collect();
+            let record = writer.seal(APPLICATION_DATA, &data);
+            // The MAC and at least one byte of padding are added.
+            assert_eq!(0, (record.len() - HEADER_SIZE) % BLOCK_SIZE);
+            assert!(record.len() > HEADER_SIZE + BLOCK_SIZE + len as usize + 20);
+            assert_eq!((APPLICATION_DATA, data), reader.open(&record).unwrap());
+            assert!(reader.cost() >= 4);
+        }
+    }
+
+    #[test]
+    fn sequence_numbers() {
+        // Records cannot be replayed or reordered.
+        let (mut writer, mut reader) = connection(PaddingCheck::Tls);
+        let first = writer.seal(APPLICATION_DATA, b"first");
+        let second = writer.seal(APPLICATION_DATA, b"second");
+        match reader.open(&second) {
+            Err(Error::InvalidTag) => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+        match reader.open(&first) {
+            Err(Error::InvalidTag) => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn tampering() {
+        let (mut writer, mut reader) = connection(PaddingCheck::Tls);
+        let mut record = writer.seal(APPLICATION_DATA, b"YELLOW SUBMARINE");
+        record[0] = 22;
+        match reader.open(&record) {
+            Err(Error::InvalidTag) => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+        assert!(reader.cost() > 0);
+
+        let mut record = writer.seal(APPLICATION_DATA, b"YELLOW SUBMARINE");
+        record.truncate(record.len() - 1);
+        match reader.open(&record) {
+            Err(Error::InvalidCiphertextLength { .. }) => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn padding_checks() {
+        // 12 bytes of data and the MAC leave a full block of padding.
+        // Changing the first padding byte through the previous
+        // ciphertext block also garbles the MAC.  The SSL 3.0 check
+        // does not notice the padding and fails on the MAC, the TLS
+        // check rejects the padding without computing the MAC.
+        for &(check, noticed) in &[(PaddingCheck::Ssl3, false), (PaddingCheck::Tls, true)] {
+            let (mut writer, mut reader) = connection(check);
+            let mut record = writer.seal(APPLICATION_DATA, b"twelve bytes");
+            let len = record.len();
+            assert_eq!(HEADER_SIZE + 4 * BLOCK_SIZE, len);
+            record[len - 2 * BLOCK_SIZE] ^= 1;
+            match reader.open(&record) {
+                Err(Error::InvalidPadding { observed: 16, .. }) if noticed => assert_eq!(0, reader.cost()),
+                Err(Error::InvalidTag) if !noticed => assert!(reader.cost() > 0),
+                r => panic!("unexpected result: {:?}", r),
+            }
+        }
+    }
+}
@@ -0,0 +1,410 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! Secure Remote Password (SRP) as described in challenge 36, and the
+//! simplified variant from challenge 38 in the `simplified`
+//! submodule.
+//!
+//! The protocol runs as follows, with `N`, `g` and `k` agreed upon
+//! beforehand:
+//!
+//! 1. C->S: `A = g^a mod N`
+//! 2. S->C: `salt`, `B = k*v + g^b mod N`
+//! 3. Both compute `u = SHA256(A|B)` and the session key
+//!    `K = HKDF-SHA256(S)`, where `S` is the shared secret.  The
+//!    challenge uses `K = SHA256(S)`; deriving the key with HKDF
+//!    makes no difference to any of the attacks.
+//! 4. C->S: `HMAC-SHA256(K, salt)`, which the server validates.
+
+use crate::bignum::BigUint;
+use crate::hash::Digest;
+use crate::hash::sha256::Sha256;
+use crate::kdf;
+use crate::mac::hmac;
+use crate::protocol::dh;
+
+/// Group parameters and multiplier.
+#[derive(Clone, Debug)]
+pub struct Params {
+    /// Prime modulus.
+    pub n: BigUint,
+    /// Generator.
+    pub g: BigUint,
+    /// Multiplier parameter.
+    pub k: BigUint,
+}
+
+impl Params {
+    /// The parameters used in the challenges: the NIST prime from
+    /// challenge 33, `g = 2` and `k = 3`.
+    pub fn nist() -> Params {
+        let group = dh::Params::nist();
+        Params {
+            n: group.p,
+            g: group.g,
+            k: BigUint::from_u64(3),
+        }
+    }
+}
+
+/// Derive the private value `x = SHA256(salt|password)` from the
+/// password.
+pub fn hash_password(salt: &[u8], password: &[u8]) -> BigUint {
+    let mut d = Sha256::new();
+    d.update(salt);
+    d.update(password);
+    BigUint::from_bytes_be(&d.finish())
+}
+
+/// Calculate the scrambling parameter `u = SHA256(A|B)`.
+pub fn scramble(client_public: &BigUint, server_public: &BigUint) -> BigUint {
+    let mut d = Sha256::new();
+    d.update(&client_public.to_bytes_be());
+    d.update(&server_public.to_bytes_be());
+    BigUint::from_bytes_be(&d.finish())
+}
+
+/// Derive the 32-byte session key `K` from the shared secret with
+/// HKDF-SHA256.
+pub fn session_key(secret: &BigUint) -> Vec<u8> {
+    kdf::hkdf::<Sha256>(b"", &secret.to_bytes_be(), b"SRP session key", 32)
+}
+
+/// Calculate the proof of knowledge of the session key that the
+/// client sends to the server.
+pub fn proof(key: &[u8], salt: &[u8]) -> Vec<u8> {
+    hmac::hmac::<Sha256>(key, salt)
+}
+
+/// Generate a random salt.
+fn random_salt() -> Vec<u8> {
+    let mut salt = vec![0u8; 16];
+    crate::random::fill_bytes(&mut salt);
+    salt
+}
+
+/// Server side of the protocol, holding the verifier for a single
+/// user.
+pub struct Server {
+    params: Params,
+    salt: Vec<u8>,
+    verifier: BigUint,
+    key: Option<Vec<u8>>,
+}
+
+impl Server {
+    /// Register `password` with a new server.  Only the salt and the
+    /// verifier `v = g^x mod N` are stored.
+    pub fn new(params: Params, password: &[u8]) -> Server {
+        let salt = random_salt();
+        let x = hash_password(&salt, password);
+        let verifier = params.g.modpow(&x, &params.n);
+        Server {
+            params: params,
+            salt: salt,
+            verifier: verifier,
+            key: None,
+        }
+    }
+
+    /// Receive the client's public key `A` and answer with the salt
+    /// and the server's public key `B`.
+    pub fn exchange(&mut self, client_public: &BigUint) -> (Vec<u8>, BigUint) {
+        let p = &self.params;
+        let b = BigUint::random_below(&p.n);
+        let server_public = &(&(&p.k * &self.verifier) + &p.g.modpow(&b, &p.n)) % &p.n;
+        let u = scramble(client_public, &server_public);
+        let secret = (client_public * &self.verifier.modpow(&u, &p.n)).modpow(&b, &p.n);
+        self.key = Some(session_key(&secret));
+        (self.salt.clone(), server_public)
+    }
+
+    /// Check the client's proof against the session key established
+    /// in the last exchange.
+    pub fn verify(&self, client_proof: &[u8]) -> bool {
+        match self.key {
+            None => false,
+            Some(ref key) => hmac::verify(&proof(key, &self.salt), client_proof),
+        }
+    }
+}
+
+/// Client side of the protocol.
+pub struct Client {
+    params: Params,
+    password: Vec<u8>,
+    secret: BigUint,
+    public: BigUint,
+}
+
+impl Client {
+    /// Create a client that will log in with `password`.
+    pub fn new(params: Params, password: &[u8]) -> Client {
+        let secret = BigUint::random_below(&params.n);
+        let public = params.g.modpow(&secret, &params.n);
+        Client {
+            params: params,
+            password: password.to_vec(),
+            secret: secret,
+            public: public,
+        }
+    }
+
+    /// Return the public key `A` to send to the server.
+    pub fn public_key(&self) -> &BigUint {
+        &self.public
+    }
+
+    /// Compute the proof for the server's answer.
+    pub fn proof(&self, salt: &[u8], server_public: &BigUint) -> Vec<u8> {
+        let p = &self.params;
+        let u = scramble(&self.public, server_public);
+        let x = hash_password(salt, &self.password);
+        let kgx = &(&p.k * &p.g.modpow(&x, &p.n)) % &p.n;
+        let base = &(&(server_public + &p.n) - &kgx) % &p.n;
+        let secret = base.modpow(&(&self.secret + &(&u * &x)), &p.n);
+        proof(&session_key(&secret), salt)
+    }
+}
+
+/// Simplified SRP from challenge 38.  Here `B = g^b mod N` does not
+/// depend on the password and `u` is a random 128 bit number chosen
+/// by the server, which enables an offline dictionary attack for a
+/// man in the middle.
+///
+/// The parties implement `channel::Party` for `Message`, so that the
+/// attack can be run on the channel between them.
+pub mod simplified {
+    use crate::bignum::BigUint;
+    use crate::error::Error;
+    use crate::mac::hmac;
+    use crate::protocol::{Encode, push_field};
+    use crate::protocol::channel::Party;
+    use super::{Params, hash_password, session_key, proof, random_salt};
+
+    /// Messages of the simplified protocol.
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub enum Message {
+        /// C->S: the client's public key `A`.
+        Hello(BigUint),
+        /// S->C: the salt, the server's public key `B` and the
+        /// scrambling parameter `u`.
+        Challenge {
+            /// Salt for hashing the password.
+            salt: Vec<u8>,
+            /// Server's public key.
+            public: BigUint,
+            /// Scrambling parameter.
+            u: BigUint,
+        },
+        /// C->S: the client's proof.
+        Proof(Vec<u8>),
+        /// S->C: whether the server accepted the proof.
+        Accepted(bool),
+    }
+
+    impl Encode for Message {
+        fn encode(&self) -> Vec<u8> {
+            let mut out = Vec::new();
+            match *self {
+                Message::Hello(ref public) => {
+                    out.push(1);
+                    push_field(&mut out, &public.to_bytes_be());
+                }
+                Message::Challenge { ref salt, ref public, ref u } => {
+                    out.push(2);
+                    push_field(&mut out, salt);
+                    push_field(&mut out, &public.to_bytes_be());
+                    push_field(&mut out, &u.to_bytes_be());
+                }
+                Message::Proof(ref proof) => {
+                    out.push(3);
+                    push_field(&mut out, proof);
+                }
+                Message::Accepted(accepted) => {
+                    out.push(4);
+                    out.push(accepted as u8);
+                }
+            }
+            out
+        }
+    }
+
+    /// Calculate the server's view of the shared secret,
+    /// `S = (A * v^u)^b mod N`.
+    pub fn server_secret(params: &Params, client_public: &BigUint, verifier: &BigUint,
+                         u: &BigUint, b: &BigUint) -> BigUint {
+        let n = &params.n;
+        (&(client_public * &verifier.modpow(u, n)) % n).modpow(b, n)
+    }
+
+    /// Server side of the simplified protocol.
+    pub struct Server {
+        params: Params,
+        salt: Vec<u8>,
+        verifier: BigUint,
+        key: Option<Vec<u8>>,
+    }
+
+    impl Server {
+        /// Register `password` with a new server.
+        pub fn new(params: Params, password: &[u8]) -> Server {
+            let salt = random_salt();
+            let x = hash_password(&salt, password);
+            let verifier = params.g.modpow(&x, &params.n);
+            Server {
+                params: params,
+                salt: salt,
+                verifier: verifier,
+                key: None,
+            }
+        }
+
+        /// Receive the client's public key `A` and answer with the
+        /// salt, the server's public key `B` and the scrambling
+        /// parameter `u`.
+        pub fn exchange(&mut self, client_public: &BigUint) -> (Vec<u8>, BigUint, BigUint) {
+            let b = BigUint::random_below(&self.params.n);
+            let server_public = self.params.g.modpow(&b, &self.params.n);
+            let u = BigUint::random_below(&(&BigUint::one() << 128));
+            let secret = server_secret(&self.params, client_public, &self.verifier, &u, &b);
+            self.key = Some(session_key(&secret));
+            (self.salt.clone(), server_public, u)
+        }
+
+        /// Check the client's proof against the session key
+        /// established in the last exchange.
+        pub fn verify(&self, client_proof: &[u8]) -> bool {
+            match self.key {
+                None => false,
+                Some(ref key) => hmac::verify(&proof(key, &self.salt), client_proof),
+            }
+        }
+    }
+
+    impl Party<Message> for Server {
+        fn receive(&mut self, message: Message) -> Result<Vec<Message>, Error> {
+            match message {
+                Message::Hello(client_public) => {
+                    let (salt, public, u) = self.exchange(&client_public);
+                    Ok(vec![Message::Challenge { salt: salt, public: public, u: u }])
+                }
+                Message::Proof(client_proof) =>
+                    Ok(vec![Message::Accepted(self.verify(&client_proof))]),
+                _ => Err(Error::UnexpectedMessage),
+            }
+        }
+    }
+
+    /// Client side of the simplified protocol.
+    pub struct Client {
+        params: Params,
+        password: Vec<u8>,
+        secret: BigUint,
+        public: BigUint,
+        accepted: Option<bool>,
+    }
+
+    impl Client {
+        /// Create a client that will log in with `password`.
+        pub fn new(params: Params, password: &[u8]) -> Client {
+            let secret = BigUint::random_below(&params.n);
+            let public = params.g.modpow(&secret, &params.n);
+            Client {
+                params: params,
+                password: password.to_vec(),
+                secret: secret,
+                public: public,
+                accepted: None,
+            }
+        }
+
+        /// Return the public key `A` to send to the server.
+        pub fn public_key(&self) -> &BigUint {
+            &self.public
+        }
+
+        /// Return whether the server accepted the login, or `None` if
+        /// it has not answered the proof.
+        pub fn accepted(&self) -> Option<bool> {
+            self.accepted
+        }
+
+        /// Compute the proof for the server's answer, using
+        /// `S = B^(a + u*x) mod N`.
+        pub fn proof(&self, salt: &[u8], server_public: &BigUint, u: &BigUint) -> Vec<u8> {
+            let x = hash_password(salt, &self.password);
+            let secret = server_public.modpow(&(&self.secret + &(u * &x)), &self.params.n);
+            proof(&session_key(&secret), salt)
+        }
+    }
+
+    impl Party<Message> for Client {
+        fn start(&mut self) -> Vec<Message> {
+            vec![Message::Hello(self.public.clone())]
+        }
+
+        fn receive(&mut self, message: Message) -> Result<Vec<Message>, Error> {
+            match message {
+                Message::Challenge { salt, public, u } =>
+                    Ok(vec![Message::Proof(self.proof(&salt, &public, &u))]),
+                Message::Accepted(accepted) => {
+                    self.accepted = Some(accepted);
+                    Ok(vec![])
+                }
+                _ => Err(Error::UnexpectedMessage),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Params, Server, Client};
+    use super::simplified;
+    use crate::protocol::channel::{self, Passthrough};
+
+    #[test]
+    fn login_0() {
+        let mut server = Server::new(Params::nist(), b"hunter2");
+        let client = Client::new(Params::nist(), b"hunter2");
+        let (salt, b) = server.exchange(client.public_key());
+        assert!(server.verify(&client.proof(&salt, &b)));
+    }
+
+    #[test]
+    fn login_wrong_password() {
+        let mut server = Server::new(Params::nist(), b"hunter2");
+        let client = Client::new(Params::nist(), b"hunter3");
+        let (salt, b) = server.exchange(client.public_key());
+        assert!(!server.verify(&client.proof(&salt, &b)));
+    }
+
+    #[test]
+    fn simplified_login_0() {
+        let mut server = simplified::Server::new(Params::nist(), b"hunter2");
+        let client = simplified::Client::new(Params::nist(), b"hunter2");
+        let (salt, b, u) = server.exchange(client.public_key());
+        assert!(server.verify(&client.proof(&salt, &b, &u)));
+    }
+
+    #[test]
+    fn simplified_login_wrong_password() {
+        let mut server = simplified::Server::new(Params::nist(), b"hunter2");
+        let client = simplified::Client::new(Params::nist(), b"letmein");
+        let (salt, b, u) = server.exchange(client.public_key());
+        assert!(!server.verify(&client.proof(&salt, &b, &u)));
+    }
+
+    #[test]
+    fn simplified_login_channel() {
+        let mut server = simplified::Server::new(Params::nist(), b"hunter2");
+        let mut client = simplified::Client::new(Params::nist(), b"hunter2");
+        assert_eq!(4, channel::run(&mut client, &mut server, &mut Passthrough).unwrap());
+        assert_eq!(Some(true), client.accepted());
+
+        let mut client = simplified::Client::new(Params::nist(), b"letmein");
+        channel::run(&mut client, &mut server, &mut Passthrough).unwrap();
+        assert_eq!(Some(false), client.accepted());
+    }
+}
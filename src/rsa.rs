@@ -0,0 +1,300 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! Textbook RSA, as introduced in challenge 39.  There is no padding
+//! at all for encryption, which is exactly what the attacks in the
+//! later challenges need.  Signatures use PKCS#1 v1.5 padding, with
+//! both a correct and a deliberately sloppy verifier (challenge 42).
+//! PKCS#1 v1.5 encryption padding is available, too, together with
+//! the padding check that is used as an oracle in challenges 47/48.
+//! Do not use this for production!
+
+use crate::bignum::BigUint;
+use crate::error;
+use crate::hash::Digest;
+use crate::hash::sha1::Sha1;
+use crate::hash::sha256::Sha256;
+use crate::math;
+use crate::padding::pkcs1;
+
+/// Public RSA key.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PublicKey {
+    /// Public exponent.
+    pub e: BigUint,
+    /// Modulus.
+    pub n: BigUint,
+}
+
+/// Private RSA key.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PrivateKey {
+    /// Private exponent.
+    pub d: BigUint,
+    /// Modulus.
+    pub n: BigUint,
+}
+
+impl PublicKey {
+    /// Return the size of the modulus in bytes.
+    pub fn size(&self) -> usize {
+        (self.n.bits() + 7) / 8
+    }
+}
+
+impl PrivateKey {
+    /// Return the size of the modulus in bytes.
+    pub fn size(&self) -> usize {
+        (self.n.bits() + 7) / 8
+    }
+}
+
+/// Generate an RSA key pair with a modulus of `bits` bits and public
+/// exponent `e`.  Primes for which `e` is not invertible modulo the
+/// totient (which happens often for `e = 3`) are discarded and new
+/// ones are generated.
+pub fn generate_keypair(bits: usize, e: u64) -> (PublicKey, PrivateKey) {
+    let mut rng = ::rand::thread_rng();
+    let e = BigUint::from_u64(e);
+    let one = BigUint::one();
+    loop {
+        let p = math::gen_prime(bits / 2, &mut rng);
+        let q = math::gen_prime(bits - bits / 2, &mut rng);
+        if p == q {
+            continue;
+        }
+        let et = &(&p - &one) * &(&q - &one);
+        if let Some(d) = e.modinv(&et) {
+            let n = &p * &q;
+            return (PublicKey { e: e, n: n.clone() }, PrivateKey { d: d, n: n });
+        }
+    }
+}
+
+/// Apply the public key to the number `m`, that is, calculate
+/// `m^e mod n`.
+pub fn encrypt_int(key: &PublicKey, m: &BigUint) -> BigUint {
+    m.modpow(&key.e, &key.n)
+}
+
+/// Apply the private key to the number `c`, that is, calculate
+/// `c^d mod n`.
+pub fn decrypt_int(key: &PrivateKey, c: &BigUint) -> BigUint {
+    c.modpow(&key.d, &key.n)
+}
+
+/// Encrypt `plaintext`, interpreted as a big-endian number.  Returns
+/// an error if that number is not smaller than the modulus.  The
+/// ciphertext is padded to the size of the modulus.
+pub fn encrypt(key: &PublicKey, plaintext: &[u8]) -> Result<Vec<u8>, error::Error> {
+    let m = BigUint::from_bytes_be(plaintext);
+    if m >= key.n {
+        return Err(error::Error::MessageTooLong);
+    }
+    Ok(encrypt_int(key, &m).to_bytes_be_padded(key.size()))
+}
+
+/// Decrypt `ciphertext`.  Since textbook RSA works on numbers, any
+/// leading zero bytes of the original plaintext are lost.
+pub fn decrypt(key: &PrivateKey, ciphertext: &[u8]) -> Vec<u8> {
+    decrypt_int(key, &BigUint::from_bytes_be(ciphertext)).to_bytes_be()
+}
+
+/// Encrypt `plaintext` with PKCS#1 v1.5 padding (see
+/// `padding::pkcs1::encode`).  Returns an error if the plaintext is
+/// too long for the key.
+pub fn encrypt_pkcs1(key: &PublicKey, plaintext: &[u8]) -> Result<Vec<u8>, error::Error> {
+    let block = pkcs1::encode(plaintext, key.size())?;
+    Ok(encrypt_int(key, &BigUint::from_bytes_be(&block)).to_bytes_be_padded(key.size()))
+}
+
+/// Decrypt a PKCS#1 v1.5 padded `ciphertext`.  Returns an error
+/// describing the problem if the padding is invalid.
+pub fn decrypt_pkcs1(key: &PrivateKey, ciphertext: &[u8]) -> Result<Vec<u8>, error::Error> {
+    let block = decrypt_int(key, &BigUint::from_bytes_be(ciphertext)).to_bytes_be_padded(key.size());
+    pkcs1::decode(&block)
+}
+
+/// Check whether the decryption of `c` starts with the bytes `00 02`.
+/// This is the padding oracle of challenges 47 and 48: a server that
+/// reveals this single bit for arbitrary ciphertexts lets an attacker
+/// decrypt any message.
+pub fn pkcs1_conforming(key: &PrivateKey, c: &BigUint) -> bool {
+    let block = decrypt_int(key, c).to_bytes_be_padded(key.size());
+    block[0] == 0x00 && block[1] == 0x02
+}
+
+/// Hash functions that can be used in PKCS#1 v1.5 signatures.
+pub trait Pkcs1Digest: Digest {
+    /// DER encoding of the ASN.1 `DigestInfo` structure identifying
+    /// the hash function, up to (but excluding) the hash value.
+    const DIGEST_INFO: &'static [u8];
+}
+
+impl Pkcs1Digest for Sha1 {
+    const DIGEST_INFO: &'static [u8] =
+        &[0x30, 0x21, 0x30, 0x09, 0x06, 0x05, 0x2b, 0x0e, 0x03, 0x02, 0x1a, 0x05, 0x00,
+          0x04, 0x14];
+}
+
+impl Pkcs1Digest for Sha256 {
+    const DIGEST_INFO: &'static [u8] =
+        &[0x30, 0x31, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04,
+          0x02, 0x01, 0x05, 0x00, 0x04, 0x20];
+}
+
+/// Minimum number of 0xff padding bytes in a signature block.
+const MIN_SIGNATURE_PADDING: usize = 8;
+
+/// Build the EMSA-PKCS1-v1_5 encoding of `message` for a modulus of
+/// `len` bytes:
+///
+/// ```text
+/// 00 01 ff ff ... ff 00 DigestInfo HASH
+/// ```
+///
+/// Returns an error if the modulus is too short for the encoding.
+pub fn signature_block<D: Pkcs1Digest>(message: &[u8], len: usize) -> Result<Vec<u8>, error::Error> {
+    let hash = D::digest(message);
+    let t_len = D::DIGEST_INFO.len() + hash.len();
+    if len < t_len + 3 + MIN_SIGNATURE_PADDING {
+        return Err(error::Error::MessageTooLong);
+    }
+    let mut block = Vec::with_capacity(len);
+    block.push(0x00);
+    block.push(0x01);
+    block.extend(::std::iter::repeat(0xff).take(len - t_len - 3));
+    block.push(0x00);
+    block.extend(D::DIGEST_INFO);
+    block.extend(hash);
+    Ok(block)
+}
+
+/// Sign `message` with PKCS#1 v1.5 padding, using the hash function
+/// `D`.  The signature is as long as the modulus.
+pub fn sign<D: Pkcs1Digest>(key: &PrivateKey, message: &[u8]) -> Result<Vec<u8>, error::Error> {
+    let block = signature_block::<D>(message, key.size())?;
+    let s = decrypt_int(key, &BigUint::from_bytes_be(&block));
+    Ok(s.to_bytes_be_padded(key.size()))
+}
+
+/// Verify a PKCS#1 v1.5 `signature` on `message` by re-encoding the
+/// expected signature block and comparing it to the whole decrypted
+/// signature.
+pub fn verify<D: Pkcs1Digest>(key: &PublicKey, message: &[u8], signature: &[u8]) -> bool {
+    let s = BigUint::from_bytes_be(signature);
+    if s >= key.n {
+        return false;
+    }
+    let block = encrypt_int(key, &s).to_bytes_be_padded(key.size());
+    match signature_block::<D>(message, key.size()) {
+        Ok(expected) => block == expected,
+        Err(_) => false,
+    }
+}
+
+/// Verify a PKCS#1 v1.5 `signature` on `message` the broken way:
+/// the decrypted block is parsed from the left, and anything after
+/// the hash is ignored.  Since the hash is not checked to be
+/// right-justified, this verifier accepts forged signatures for small
+/// public exponents.  Do not use this, except for attacking it!
+pub fn verify_sloppy<D: Pkcs1Digest>(key: &PublicKey, message: &[u8], signature: &[u8]) -> bool {
+    let s = BigUint::from_bytes_be(signature);
+    if s >= key.n {
+        return false;
+    }
+    let block = encrypt_int(key, &s).to_bytes_be_padded(key.size());
+    if block.len() < 3 || block[0] != 0x00 || block[1] != 0x01 {
+        return false;
+    }
+    let mut i = 2;
+    while i < block.len() && block[i] == 0xff {
+        i += 1;
+    }
+    if i == 2 || i >= block.len() || block[i] != 0x00 {
+        return false;
+    }
+    let rest = &block[i + 1..];
+    let hash = D::digest(message);
+    rest.starts_with(D::DIGEST_INFO) && rest[D::DIGEST_INFO.len()..].starts_with(&hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{generate_keypair, encrypt, decrypt, encrypt_int, decrypt_int};
+    use super::{sign, verify, verify_sloppy, signature_block};
+    use super::{encrypt_pkcs1, decrypt_pkcs1, pkcs1_conforming};
+    use crate::hash::sha1::Sha1;
+    use crate::hash::sha256::Sha256;
+    use super::{PublicKey, PrivateKey};
+    use crate::bignum::BigUint;
+
+    #[test]
+    fn textbook_example() {
+        // p = 61, q = 53, e = 17, d = 2753
+        let public = PublicKey { e: BigUint::from_u64(17), n: BigUint::from_u64(3233) };
+        let private = PrivateKey { d: BigUint::from_u64(2753), n: BigUint::from_u64(3233) };
+        let c = encrypt_int(&public, &BigUint::from_u64(65));
+        assert_eq!(BigUint::from_u64(2790), c);
+        assert_eq!(BigUint::from_u64(65), decrypt_int(&private, &c));
+    }
+
+    #[test]
+    fn encrypt_decrypt_e3() {
+        let (public, private) = generate_keypair(256, 3);
+        assert_eq!(256, public.n.bits());
+        let msg = b"attack at dawn";
+        let c = encrypt(&public, msg).unwrap();
+        assert_eq!(32, c.len());
+        assert_eq!(msg.to_vec(), decrypt(&private, &c));
+    }
+
+    #[test]
+    fn encrypt_too_long() {
+        let (public, _) = generate_keypair(128, 65537);
+        assert!(encrypt(&public, &[0xff; 17]).is_err());
+    }
+
+    #[test]
+    fn signature_block_0() {
+        let block = signature_block::<Sha1>(b"hi mom", 64).unwrap();
+        assert_eq!(64, block.len());
+        assert_eq!(&[0x00, 0x01, 0xff], &block[..3]);
+        assert_eq!(0x00, block[64 - 36]);
+        assert!(signature_block::<Sha256>(b"hi mom", 32).is_err());
+    }
+
+    #[test]
+    fn sign_verify() {
+        let (public, private) = generate_keypair(512, 3);
+        let sig = sign::<Sha1>(&private, b"hi mom").unwrap();
+        assert!(verify::<Sha1>(&public, b"hi mom", &sig));
+        assert!(verify_sloppy::<Sha1>(&public, b"hi mom", &sig));
+        assert!(!verify::<Sha1>(&public, b"hi dad", &sig));
+        assert!(!verify_sloppy::<Sha1>(&public, b"hi dad", &sig));
+        assert!(!verify::<Sha256>(&public, b"hi mom", &sig));
+    }
+
+    #[test]
+    fn encrypt_decrypt_pkcs1() {
+        let (public, private) = generate_keypair(256, 3);
+        let msg = b"kick it, CC";
+        let c1 = encrypt_pkcs1(&public, msg).unwrap();
+        let c2 = encrypt_pkcs1(&public, msg).unwrap();
+        assert_eq!(32, c1.len());
+        assert!(c1 != c2);
+        assert!(pkcs1_conforming(&private, &BigUint::from_bytes_be(&c1)));
+        assert_eq!(msg.to_vec(), decrypt_pkcs1(&private, &c1).unwrap());
+        assert_eq!(msg.to_vec(), decrypt_pkcs1(&private, &c2).unwrap());
+        assert!(encrypt_pkcs1(&public, &[0x41; 22]).is_err());
+        assert!(encrypt_pkcs1(&public, &[0x41; 21]).is_ok());
+    }
+
+    #[test]
+    fn decrypt_pkcs1_invalid() {
+        let (public, private) = generate_keypair(256, 3);
+        let c = encrypt(&public, b"no padding at all").unwrap();
+        assert!(!pkcs1_conforming(&private, &BigUint::from_bytes_be(&c)));
+        assert!(decrypt_pkcs1(&private, &c).is_err());
+    }
+}
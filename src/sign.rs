@@ -0,0 +1,185 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! Common interface for the signature schemes in this crate, so that
+//! code which signs or verifies does not care which scheme, hash
+//! function or padding check is used.  In particular, the sloppy
+//! verifiers that the attacks exploit can be swapped for the strict
+//! ones, which must reject the forgeries.
+
+use std::marker::PhantomData;
+
+use crate::dsa;
+use crate::error::Error;
+use crate::rsa::{self, Pkcs1Digest};
+
+/// Something that produces signatures.
+pub trait Signer {
+    /// Type of the signatures produced.
+    type Signature;
+
+    /// Sign `message`.
+    fn sign(&self, message: &[u8]) -> Result<Self::Signature, Error>;
+}
+
+/// Something that checks signatures.
+pub trait Verifier {
+    /// Type of the signatures checked.
+    type Signature;
+
+    /// Return true if `signature` is a valid signature on `message`.
+    fn verify(&self, message: &[u8], signature: &Self::Signature) -> bool;
+}
+
+/// RSA signatures with PKCS#1 v1.5 padding and the hash function `D`.
+pub struct RsaSigner<D> {
+    key: rsa::PrivateKey,
+    digest: PhantomData<D>,
+}
+
+impl<D: Pkcs1Digest> RsaSigner<D> {
+    /// Create a signer using the private key `key`.
+    pub fn new(key: rsa::PrivateKey) -> RsaSigner<D> {
+        RsaSigner {
+            key: key,
+            digest: PhantomData,
+        }
+    }
+}
+
+impl<D: Pkcs1Digest> Signer for RsaSigner<D> {
+    type Signature = Vec<u8>;
+
+    fn sign(&self, message: &[u8]) -> Result<Vec<u8>, Error> {
+        rsa::sign::<D>(&self.key, message)
+    }
+}
+
+/// Strict verifier for RSA signatures made by `RsaSigner`.
+pub struct RsaVerifier<D> {
+    key: rsa::PublicKey,
+    digest: PhantomData<D>,
+}
+
+impl<D: Pkcs1Digest> RsaVerifier<D> {
+    /// Create a verifier using the public key `key`.
+    pub fn new(key: rsa::PublicKey) -> RsaVerifier<D> {
+        RsaVerifier {
+            key: key,
+            digest: PhantomData,
+        }
+    }
+}
+
+impl<D: Pkcs1Digest> Verifier for RsaVerifier<D> {
+    type Signature = Vec<u8>;
+
+    fn verify(&self, message: &[u8], signature: &Vec<u8>) -> bool {
+        rsa::verify::<D>(&self.key, message, signature)
+    }
+}
+
+/// Verifier for RSA signatures that does not check that the hash is
+/// right-justified, see `rsa::verify_sloppy`.  Vulnerable to
+/// Bleichenbacher's e=3 forgery.
+pub struct SloppyRsaVerifier<D> {
+    key: rsa::PublicKey,
+    digest: PhantomData<D>,
+}
+
+impl<D: Pkcs1Digest> SloppyRsaVerifier<D> {
+    /// Create a verifier using the public key `key`.
+    pub fn new(key: rsa::PublicKey) -> SloppyRsaVerifier<D> {
+        SloppyRsaVerifier {
+            key: key,
+            digest: PhantomData,
+        }
+    }
+}
+
+impl<D: Pkcs1Digest> Verifier for SloppyRsaVerifier<D> {
+    type Signature = Vec<u8>;
+
+    fn verify(&self, message: &[u8], signature: &Vec<u8>) -> bool {
+        rsa::verify_sloppy::<D>(&self.key, message, signature)
+    }
+}
+
+impl Signer for dsa::PrivateKey {
+    type Signature = dsa::Signature;
+
+    fn sign(&self, message: &[u8]) -> Result<dsa::Signature, Error> {
+        Ok(dsa::sign(self, message))
+    }
+}
+
+impl Verifier for dsa::PublicKey {
+    type Signature = dsa::Signature;
+
+    fn verify(&self, message: &[u8], signature: &dsa::Signature) -> bool {
+        dsa::verify(self, message, signature)
+    }
+}
+
+/// Verifier for DSA signatures that does not range-check `r` and `s`,
+/// see `dsa::verify_sloppy`.  Vulnerable to maliciously chosen
+/// generators.
+pub struct SloppyDsaVerifier(pub dsa::PublicKey);
+
+impl Verifier for SloppyDsaVerifier {
+    type Signature = dsa::Signature;
+
+    fn verify(&self, message: &[u8], signature: &dsa::Signature) -> bool {
+        dsa::verify_sloppy(&self.0, message, signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Signer, Verifier, RsaSigner, RsaVerifier, SloppyRsaVerifier, SloppyDsaVerifier};
+    use crate::attack;
+    use crate::bignum::BigUint;
+    use crate::dsa;
+    use crate::hash::sha1::Sha1;
+    use crate::rsa;
+
+    fn sign_verify<S, V>(signer: &S, verifier: &V) -> bool
+        where S: Signer, V: Verifier<Signature = S::Signature>
+    {
+        let sig = signer.sign(b"hi mom").unwrap();
+        verifier.verify(b"hi mom", &sig) && !verifier.verify(b"hi dad", &sig)
+    }
+
+    #[test]
+    fn rsa_sign_verify() {
+        let (public, private) = rsa::generate_keypair(512, 3);
+        let signer = RsaSigner::<Sha1>::new(private);
+        assert!(sign_verify(&signer, &RsaVerifier::<Sha1>::new(public.clone())));
+        assert!(sign_verify(&signer, &SloppyRsaVerifier::<Sha1>::new(public)));
+    }
+
+    #[test]
+    fn rsa_forgery() {
+        let (public, _) = rsa::generate_keypair(1024, 3);
+        let forged = attack::rsa::forge_signature_e3::<Sha1>(b"hi mom", 1024).unwrap();
+        assert!(SloppyRsaVerifier::<Sha1>::new(public.clone()).verify(b"hi mom", &forged));
+        assert!(!RsaVerifier::<Sha1>::new(public).verify(b"hi mom", &forged));
+    }
+
+    #[test]
+    fn dsa_sign_verify() {
+        let (public, private) = dsa::generate_keypair(&dsa::Params::challenge43());
+        assert!(sign_verify(&private, &public));
+        assert!(sign_verify(&private, &SloppyDsaVerifier(public)));
+    }
+
+    #[test]
+    fn dsa_forgery() {
+        let mut params = dsa::Params::challenge43();
+        params.g = BigUint::zero();
+        let (public, _) = dsa::generate_keypair(&params);
+        let forged = attack::dsa::magic_signature_g0();
+        assert!(SloppyDsaVerifier(public.clone()).verify(b"Hello, world", &forged));
+        assert!(!public.verify(b"Hello, world", &forged));
+    }
+}
@@ -0,0 +1,246 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! A web application issuing session tokens, as the target of the
+//! attacks on cookies in challenges 13, 16, 26 and 29.  The server
+//! creates the profile `user=<name>;admin=false` for a user name
+//! chosen by the attacker, and protects it in one of several ways,
+//! none of which stops an attacker from turning it into a profile
+//! with `admin=true`, except for the HMAC.
+//!
+//! The user name is sanitized by removing `;` and `=`.  When parsing
+//! a profile, fields without a `=` are ignored, and later fields
+//! override earlier ones with the same name.
+
+use crate::cipher::aes::{self, AesKey};
+use crate::error::Error;
+use crate::hash::Digest;
+use crate::hash::sha1::Sha1;
+use crate::mac::hmac;
+use crate::padding::PaddingScheme;
+
+/// How a server protects its tokens.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Protection {
+    /// AES-128 in ECB mode, with PKCS#7 padding.
+    Ecb,
+    /// AES-128 in CBC mode with PKCS#7 padding, preceded by a random
+    /// IV.
+    Cbc,
+    /// AES-128 in CTR mode, preceded by a random nonce.
+    Ctr,
+    /// The profile followed by `SHA1(key | profile)`.
+    PrefixMac,
+    /// The profile followed by `HMAC-SHA1(key, profile)`.
+    Hmac,
+}
+
+/// A parsed profile.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Profile {
+    /// The fields of the profile, in order.
+    pub fields: Vec<(String, String)>,
+}
+
+impl Profile {
+    /// Parse `data` as a profile.  Invalid UTF-8 is replaced.
+    pub fn parse(data: &[u8]) -> Profile {
+        let fields = String::from_utf8_lossy(data)
+            .split(';')
+            .filter_map(|field| {
+                let mut kv = field.splitn(2, '=');
+                match (kv.next(), kv.next()) {
+                    (Some(k), Some(v)) => Some((k.to_string(), v.to_string())),
+                    _ => None,
+                }
+            })
+            .collect();
+        Profile { fields: fields }
+    }
+
+    /// Return the value of the last field called `name`.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.fields.iter().rev().find(|f| f.0 == name).map(|f| &f.1[..])
+    }
+
+    /// Return true if the profile belongs to an administrator.
+    pub fn is_admin(&self) -> bool {
+        self.get("admin") == Some("true")
+    }
+}
+
+/// Return the profile for `user`, with `;` and `=` removed from the
+/// name.
+pub fn profile_for(user: &[u8]) -> Vec<u8> {
+    let mut profile = b"user=".to_vec();
+    profile.extend(user.iter().filter(|&&b| b != b';' && b != b'='));
+    profile.extend_from_slice(b";admin=false");
+    profile
+}
+
+/// Size of the MACs.
+const MAC_SIZE: usize = 20;
+
+/// Server issuing and checking tokens.
+pub struct Server {
+    protection: Protection,
+    key: [u8; 16],
+}
+
+impl Server {
+    /// Create a server with a random key, protecting its tokens with
+    /// `protection`.
+    pub fn new(protection: Protection) -> Server {
+        let mut key = [0; 16];
+        crate::random::fill_bytes(&mut key);
+        Server {
+            protection: protection,
+            key: key,
+        }
+    }
+
+    /// Return how the server protects its tokens.
+    pub fn protection(&self) -> Protection {
+        self.protection
+    }
+
+    /// Return a token for the profile of `user`, see `profile_for`.
+    pub fn issue_token(&self, user: &[u8]) -> Vec<u8> {
+        let profile = profile_for(user);
+        let key = AesKey::from(self.key);
+        let mut iv = [0; 16];
+        crate::random::fill_bytes(&mut iv);
+        match self.protection {
+            Protection::Ecb => aes::encrypt_ecb(&key, &profile),
+            Protection::Cbc => {
+                let mut token = iv.to_vec();
+                token.extend(aes::encrypt_cbc(&key, &iv, &profile));
+                token
+            }
+            Protection::Ctr => {
+                // The lower half is the block counter.
+                for b in &mut iv[8..] {
+                    *b = 0;
+                }
+                let mut token = iv.to_vec();
+                token.extend(aes::encrypt_ctr(&key, &iv, &profile));
+                token
+            }
+            Protection::PrefixMac => {
+                let mac = self.prefix_mac(&profile);
+                let mut token = profile;
+                token.extend(mac);
+                token
+            }
+            Protection::Hmac => {
+                let mac = hmac::hmac::<Sha1>(&self.key, &profile);
+                let mut token = profile;
+                token.extend(mac);
+                token
+            }
+        }
+    }
+
+    /// Check `token` and return the profile it contains.  Returns
+    /// `Error::InvalidCiphertextLength` or `Error::InvalidPadding` if
+    /// an encrypted token is malformed, and `Error::InvalidTag` if the
+    /// MAC of a signed token does not match.
+    pub fn parse_token(&self, token: &[u8]) -> Result<Profile, Error> {
+        let key = AesKey::from(self.key);
+        let profile = match self.protection {
+            Protection::Ecb => aes::decrypt_ecb_with(&key, token, &PaddingScheme::Pkcs7)?,
+            Protection::Cbc | Protection::Ctr => {
+                if token.len() < 16 {
+                    return Err(Error::InvalidCiphertextLength {
+                        len: token.len(),
+                        block_size: 16,
+                    });
+                }
+                let mut iv = [0; 16];
+                iv.copy_from_slice(&token[..16]);
+                if self.protection == Protection::Cbc {
+                    aes::decrypt_cbc_with(&key, &iv, &token[16..], &PaddingScheme::Pkcs7)?
+                } else {
+                    aes::decrypt_ctr(&key, &iv, &token[16..])
+                }
+            }
+            Protection::PrefixMac | Protection::Hmac => {
+                if token.len() < MAC_SIZE {
+                    return Err(Error::InvalidTag);
+                }
+                let (profile, tag) = token.split_at(token.len() - MAC_SIZE);
+                let expected = if self.protection == Protection::PrefixMac {
+                    self.prefix_mac(profile)
+                } else {
+                    hmac::hmac::<Sha1>(&self.key, profile)
+                };
+                if !hmac::verify(&expected, tag) {
+                    return Err(Error::InvalidTag);
+                }
+                profile.to_vec()
+            }
+        };
+        Ok(Profile::parse(&profile))
+    }
+
+    /// Compute the secret-prefix MAC `SHA1(key | message)`.
+    fn prefix_mac(&self, message: &[u8]) -> Vec<u8> {
+        let mut d = Sha1::new();
+        d.update(&self.key);
+        d.update(message);
+        d.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Profile, Protection, Server, profile_for};
+    use crate::error::Error;
+
+    static ALL: [Protection; 5] = [Protection::Ecb, Protection::Cbc, Protection::Ctr,
+                                   Protection::PrefixMac, Protection::Hmac];
+
+    #[test]
+    fn profile() {
+        assert_eq!(b"user=aliceadmintrue;admin=false".to_vec(), profile_for(b"alice;admin=true"));
+        let p = Profile::parse(b"user=bob;admin=false;junk;admin=true");
+        assert_eq!(3, p.fields.len());
+        assert_eq!(Some("bob"), p.get("user"));
+        assert!(p.is_admin());
+        assert!(!Profile::parse(&profile_for(b"eve")).is_admin());
+    }
+
+    #[test]
+    fn roundtrip() {
+        for &protection in &ALL {
+            let server = Server::new(protection);
+            let profile = server.parse_token(&server.issue_token(b"alice")).unwrap();
+            assert_eq!(Some("alice"), profile.get("user"));
+            assert!(!profile.is_admin());
+        }
+    }
+
+    #[test]
+    fn foreign_token() {
+        // Tokens from a server with another key are rejected, or at
+        // least do not parse into a profile.
+        for &protection in &ALL {
+            let token = Server::new(protection).issue_token(b"mallory");
+            if let Ok(profile) = Server::new(protection).parse_token(&token) {
+                assert!(profile.get("user") != Some("mallory"));
+            }
+        }
+    }
+
+    #[test]
+    fn truncated_mac() {
+        for &protection in &[Protection::PrefixMac, Protection::Hmac] {
+            let server = Server::new(protection);
+            let token = server.issue_token(b"alice");
+            match server.parse_token(&token[..token.len() - 1]) {
+                Err(Error::InvalidTag) => (),
+                r => panic!("unexpected result: {:?}", r),
+            }
+        }
+    }
+}
@@ -4,40 +4,68 @@
 //! Collection of XOR-base "encryption" routines.  This is no real
 //! crypto, but can be used to implement better ciphers.
 
+use byteorder::{ByteOrder, NativeEndian};
+
+use super::attack::{self, Report};
 use super::distance;
+use super::error::{Error, Result};
 use super::language;
 
+/// Minimum length of the key stream that `repeating` builds from the
+/// key, so that short keys still get XORed word by word.
+const REPEAT_BLOCK: usize = 64;
+
 /// Apply the byte `key` via XOR to all the bytes in `msg`, and return
 /// the result as a vector.
 pub fn one_byte(key: u8, msg: &[u8]) -> Vec<u8> {
-    msg.iter().map(|c| *c ^ key).collect()
+    repeating(&[key], msg)
 }
 
 /// Apply the key `key` to the message `msg` with XOR, by repeating
 /// the key as often as necessary.
 pub fn repeating(key: &[u8], msg: &[u8]) -> Vec<u8> {
     assert!(key.len() > 0);
-    
-    msg.chunks(key.len())
-        .flat_map(|chunk| xor_bytes(chunk, &key[0..chunk.len()]))
-        .collect()
+
+    // Repeat the key to a multiple of its length of at least
+    // REPEAT_BLOCK bytes, and apply that block by block.
+    let n = key.len() * ((REPEAT_BLOCK + key.len() - 1) / key.len());
+    let stream: Vec<u8> = key.iter().cycle().take(n).cloned().collect();
+    let mut res = msg.to_vec();
+    for chunk in res.chunks_mut(n) {
+        let l = chunk.len();
+        xor_into(chunk, &stream[..l]);
+    }
+    res
 }
 
 /// XOR all the corresponding bytes in `b0` and `b1`, respectively,
-/// and return the result as a vector.
+/// and return the result as a vector.  Returns
+/// `Error::LengthMismatch` if the input slices have different
+/// lengths.
+pub fn xor_bytes(b0: &[u8], b1: &[u8]) -> Result<Vec<u8>> {
+    Error::check_lengths(b0.len(), b1.len())?;
+
+    let mut res = b0.to_vec();
+    xor_into(&mut res, b1);
+    Ok(res)
+}
+
+/// XOR the bytes of `src` into the corresponding bytes of `dst`.  The
+/// bulk of the data is processed eight bytes at a time.
 ///
 /// # Panics
 /// Will panic if the input slices have different lengths.
-pub fn xor_bytes(b0: &[u8], b1: &[u8]) -> Vec<u8> {
-    assert_eq!(b0.len(), b1.len());
+pub fn xor_into(dst: &mut [u8], src: &[u8]) {
+    assert_eq!(dst.len(), src.len());
 
-    let l = b0.len();
-    let mut res = Vec::with_capacity(l);
-    
-    for i in 0..l {
-        res.push(b0[i] ^ b1[i]);
+    let words = dst.len() / 8 * 8;
+    for (d, s) in dst[..words].chunks_mut(8).zip(src[..words].chunks(8)) {
+        let x = NativeEndian::read_u64(d) ^ NativeEndian::read_u64(s);
+        NativeEndian::write_u64(d, x);
+    }
+    for (d, s) in dst[words..].iter_mut().zip(src[words..].iter()) {
+        *d ^= *s;
     }
-    res
 }
 
 pub fn score_english(msg: &[u8]) -> f32 {
@@ -47,50 +75,190 @@ pub fn score_english(msg: &[u8]) -> f32 {
     (language::english::score_string(msg) as f32) / (msg.len() as f32)
 }
 
-const THRESHOLD: f32 = 100.0;
+/// Measure of how plausible a candidate plaintext is, used to rank
+/// decryptions.  Higher scores are better.
+pub trait Scorer {
+    /// Return the score of `msg`.
+    fn score(&self, msg: &[u8]) -> f64;
+}
+
+impl<F: Fn(&[u8]) -> f64> Scorer for F {
+    fn score(&self, msg: &[u8]) -> f64 {
+        self(msg)
+    }
+}
 
-/// Attempt to crack a single-byte XOR encrypted message.  On success,
-/// the key byte is returned, `None` otherwise.
-pub fn crack_single_byte_xor(msg: &[u8]) -> Option<(u8, Vec<u8>)> {
-    let mut solutions = Vec::new();
-    for key in 0..255u8 {
-        let output = one_byte(key, &msg);
-        let score = score_english(&output);
-        solutions.push((score, key, output.clone()));
-    }
-    let mut sl = &mut solutions[..];
-    sl.sort_by(|&(s0, _, _), &(s1, _, _)| match s1.partial_cmp(&s0) {
+/// The default scorer, for English text in ASCII encoding, see
+/// `score_english`.
+#[derive(Clone, Copy, Debug)]
+pub struct English;
+
+impl Scorer for English {
+    fn score(&self, msg: &[u8]) -> f64 {
+        score_english(msg) as f64
+    }
+}
+
+/// Scorer for English text using the trigram and quadgram
+/// statistics of `language::english::score_ngram`.  It works better
+/// than `English` for short texts.
+#[derive(Clone, Copy, Debug)]
+pub struct Ngram;
+
+impl Scorer for Ngram {
+    fn score(&self, msg: &[u8]) -> f64 {
+        language::english::score_ngram(msg)
+    }
+}
+
+impl Scorer for language::Model {
+    fn score(&self, msg: &[u8]) -> f64 {
+        language::Model::score(self, msg)
+    }
+}
+
+impl Scorer for language::Language {
+    fn score(&self, msg: &[u8]) -> f64 {
+        language::Language::score(*self, msg)
+    }
+}
+
+/// Scorer for English text using the Markov model of
+/// `language::markov::log_likelihood`.  It works well even for
+/// plaintexts of only a few bytes.
+#[derive(Clone, Copy, Debug)]
+pub struct Markov;
+
+impl Scorer for Markov {
+    fn score(&self, msg: &[u8]) -> f64 {
+        language::markov::log_likelihood(msg)
+    }
+}
+
+/// Score per byte that `crack_single_byte_xor` requires to consider a
+/// decryption English text.
+pub const THRESHOLD: f64 = 100.0;
+
+/// Decrypt the single-byte XOR encrypted message `msg` with every
+/// possible key, and return the `n` best `(score, key, plaintext)`
+/// candidates according to `score_english`, best first.
+pub fn single_byte_xor_candidates(msg: &[u8], n: usize) -> Vec<(f64, u8, Vec<u8>)> {
+    single_byte_xor_candidates_with(msg, n, &English)
+}
+
+/// Like `single_byte_xor_candidates`, but rank the candidates with
+/// `scorer`.
+pub fn single_byte_xor_candidates_with<S: Scorer>(msg: &[u8], n: usize, scorer: &S)
+                                                  -> Vec<(f64, u8, Vec<u8>)> {
+    let mut solutions = Vec::with_capacity(256);
+    for key in 0..256u16 {
+        let output = one_byte(key as u8, &msg);
+        let score = scorer.score(&output);
+        solutions.push((score, key as u8, output));
+    }
+    solutions.sort_by(|&(s0, _, _), &(s1, _, _)| match s1.partial_cmp(&s0) {
                Some(o) => o,
                _ => ::std::cmp::Ordering::Less
     });
-    if sl[0].0 >= THRESHOLD {
-        Some((sl[0].1, sl[0].2.iter().cloned().collect()))
-    } else {
-        None
-    }
-}
-
-const MIN_KEYSIZE: usize = 2;
-const MAX_KEYSIZE: usize = 40;
-
-fn detect_keysize(c: &[u8], max_key_sizes: usize) -> Vec<usize> {
-    let mut scores = Vec::with_capacity(3);
-    for keysize in MIN_KEYSIZE..::std::cmp::min(c.len()/4, MAX_KEYSIZE+1) {
-        let i1 = &c[0..keysize];
-        let i2 = &c[keysize..keysize*2];
-        let i3 = &c[keysize*2..keysize*3];
-        let i4 = &c[keysize*3..keysize*4];
-        let dist = ((distance::hamming(i1, i2) +
-                     distance::hamming(i1, i3) +
-                     distance::hamming(i1, i4)) / 4) as f32 / keysize as f32;
-        scores.push((dist, keysize));
-    }
-    &scores[..].sort_by(|&(d1, _), &(d2, _)|
-                        match d1.partial_cmp(&d2) {
-                            None => ::std::cmp::Ordering::Less,
-                            Some(o) => o,
-                        });
-    scores.into_iter().take(max_key_sizes).map(|(_, k)| k).collect()
+    solutions.truncate(n);
+    solutions
+}
+
+/// Find out which of `lines` is single-byte XOR encrypted English
+/// text.  Each line is cracked, and the `(line, key, plaintext,
+/// score)` results for all lines are returned, best first.
+pub fn detect_single_byte_xor(lines: &[Vec<u8>]) -> Vec<(usize, u8, Vec<u8>, f32)> {
+    let mut results: Vec<(usize, u8, Vec<u8>, f32)> = lines.iter()
+        .enumerate()
+        .filter_map(|(i, line)| {
+            single_byte_xor_candidates(line, 1).pop().map(|(score, key, plain)| (i, key, plain, score as f32))
+        })
+        .collect();
+    results.sort_by(|a, b| match b.3.partial_cmp(&a.3) {
+        Some(o) => o,
+        _ => ::std::cmp::Ordering::Less
+    });
+    results
+}
+
+/// Attempt to crack a single-byte XOR encrypted message.  On success,
+/// the key byte and the plaintext are returned, `None` otherwise.
+/// The confidence of the report is the margin between the best and
+/// the second best key, see `attack::margin`.
+pub fn crack_single_byte_xor(msg: &[u8]) -> Option<Report<(u8, Vec<u8>)>> {
+    crack_single_byte_xor_threshold(msg, THRESHOLD)
+}
+
+/// Like `crack_single_byte_xor`, but the best decryption is only
+/// accepted if its score is at least `threshold`.
+pub fn crack_single_byte_xor_threshold(msg: &[u8], threshold: f64) -> Option<Report<(u8, Vec<u8>)>> {
+    crack_single_byte_xor_with(msg, threshold, &English)
+}
+
+/// Like `crack_single_byte_xor_threshold`, but score the decryptions
+/// with `scorer`.
+pub fn crack_single_byte_xor_with<S: Scorer>(msg: &[u8], threshold: f64, scorer: &S)
+                                             -> Option<Report<(u8, Vec<u8>)>> {
+    let mut candidates = single_byte_xor_candidates_with(msg, 2, scorer);
+    let second = candidates.pop().map(|c| c.0);
+    let (score, key, output) = candidates.pop().unwrap();
+    if score < threshold {
+        return None;
+    }
+    let mut report = Report::new((key, output), attack::margin(score, second.unwrap()));
+    report.note(format!("key {:#04x} scored {:.2}", key, score));
+    Some(report)
+}
+
+/// Parameters for `detect_keysize`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeysizeConfig {
+    /// Smallest key size to try.
+    pub min_keysize: usize,
+    /// Largest key size to try.
+    pub max_keysize: usize,
+    /// Number of pairs of adjacent blocks at the start of the
+    /// ciphertext that are compared.  Key sizes for which the
+    /// ciphertext is shorter than `block_pairs + 1` blocks are skipped.
+    pub block_pairs: usize,
+}
+
+impl Default for KeysizeConfig {
+    /// Key sizes from 2 to 40 bytes, comparing three pairs of blocks.
+    fn default() -> KeysizeConfig {
+        KeysizeConfig {
+            min_keysize: 2,
+            max_keysize: 40,
+            block_pairs: 3,
+        }
+    }
+}
+
+/// Estimate the key size of the repeating XOR encrypted message `c`.
+/// For each key size, the ciphertext is split into blocks of that
+/// size, and the average Hamming distance per byte between adjacent
+/// blocks is its score.  The right key size tends to have a low
+/// score, because the key cancels out.  Returns `(score, keysize)`
+/// pairs, lowest score first.
+pub fn detect_keysize(c: &[u8], config: &KeysizeConfig) -> Vec<(f32, usize)> {
+    let pairs = ::std::cmp::max(config.block_pairs, 1);
+    let mut scores = Vec::new();
+    for keysize in ::std::cmp::max(config.min_keysize, 1)..config.max_keysize + 1 {
+        if c.len() < keysize * (pairs + 1) {
+            break;
+        }
+        let total: f64 = (0..pairs)
+            .map(|i| distance::normalized_hamming(&c[keysize * i..keysize * (i + 1)],
+                                                  &c[keysize * (i + 1)..keysize * (i + 2)]))
+            .sum();
+        scores.push(((total / pairs as f64) as f32, keysize));
+    }
+    scores.sort_by(|&(d1, _), &(d2, _)|
+                   match d1.partial_cmp(&d2) {
+                       None => ::std::cmp::Ordering::Less,
+                       Some(o) => o,
+                   });
+    scores
 }
 
 fn transpose(c: &[u8], keysize: usize) -> Vec<Vec<u8>> {
@@ -104,18 +272,35 @@ fn transpose(c: &[u8], keysize: usize) -> Vec<Vec<u8>> {
     transposed
 }
 
-fn break_it(c: &[u8], keysize: usize) -> Vec<u8> {
+/// Progress reported by `crack_repeating_xor_with`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Event {
+    /// Key size `keysize` with the given `detect_keysize` score is
+    /// about to be tried.
+    KeySize { keysize: usize, score: f32 },
+    /// Column `column` of key size `keysize` was cracked, with the
+    /// best key byte `key` scoring `score`.
+    Column { keysize: usize, column: usize, key: u8, score: f64 },
+    /// The plaintext decrypted with the best key of size `keysize`
+    /// scored `score`.
+    Candidate { keysize: usize, score: f64 },
+}
+
+/// Find the key of length `keysize` by cracking each column of `c` as
+/// single-byte XOR, taking the best scoring key byte for each.
+fn break_it<S: Scorer>(c: &[u8], keysize: usize, scorer: &S, progress: &mut Option<&mut dyn FnMut(Event)>)
+                       -> Vec<u8> {
     let transposed = transpose(c, keysize);
     let mut key = Vec::with_capacity(keysize);
-    for i in 0..keysize {
-//        println!("{}", codec::hex::encode(&transposed[i]));
-        if let Some((k, _)) = crack_single_byte_xor(&transposed[i]) {
-//            println!("found key: {}", k);
-//            println!("{:?}", String::from_utf8_lossy(&d));
-            key.push(k);
-        } else {
-//            println!("cannot find key");
-            key.push(0);
+    for (i, column) in transposed.iter().enumerate() {
+        match single_byte_xor_candidates_with(column, 1, scorer).pop() {
+            Some((score, k, _)) => {
+                if let Some(ref mut p) = *progress {
+                    p(Event::Column { keysize: keysize, column: i, key: k, score: score });
+                }
+                key.push(k);
+            }
+            None => key.push(0),
         }
     }
     key
@@ -124,30 +309,88 @@ fn break_it(c: &[u8], keysize: usize) -> Vec<u8> {
 /// Attempt to decrypt message `c`, which is assumed to be encrypted
 /// with a repeating XOR scheme with a key length somewhere between 2
 /// and 40 bytes.  The plaintext is assumed to be English text in
-/// ASCII encoding.
-pub fn crack_repeating_xor(c: &[u8], max_key_sizes: usize) -> Vec<(Vec<u8>, Vec<u8>)> {
-    let keysizes = detect_keysize(&c, max_key_sizes);
-    let mut results = Vec::with_capacity(keysizes.len());
-    for keysize in keysizes {
-        println!("keysize: {}", keysize);
-        let key = break_it(&c, keysize);
+/// ASCII encoding.  The result holds a `(key, plaintext)` pair for
+/// each of the `max_key_sizes` most likely key sizes, best first, and
+/// the confidence is the margin between the best two of them, see
+/// `attack::margin`.
+pub fn crack_repeating_xor(c: &[u8], max_key_sizes: usize) -> Report<Vec<(Vec<u8>, Vec<u8>)>> {
+    crack_repeating_xor_with(c, max_key_sizes, &English, None)
+}
+
+/// Like `crack_repeating_xor`, but score candidate plaintexts with
+/// `scorer` instead of assuming English, and report the key sizes
+/// tried, the cracked columns and the resulting scores to `progress`.
+pub fn crack_repeating_xor_with<S: Scorer>(c: &[u8], max_key_sizes: usize, scorer: &S,
+                                           mut progress: Option<&mut dyn FnMut(Event)>)
+                                           -> Report<Vec<(Vec<u8>, Vec<u8>)>> {
+    let keysizes = detect_keysize(&c, &KeysizeConfig::default());
+    let mut results = Vec::with_capacity(max_key_sizes);
+    for (keysize_score, keysize) in keysizes.into_iter().take(max_key_sizes) {
+        if let Some(ref mut p) = progress {
+            p(Event::KeySize { keysize: keysize, score: keysize_score });
+        }
+        let key = break_it(&c, keysize, scorer, &mut progress);
         let decoded = repeating(&key, &c);
-        let score = score_english(&decoded);
+        let score = scorer.score(&decoded);
+        if let Some(ref mut p) = progress {
+            p(Event::Candidate { keysize: keysize, score: score });
+        }
         results.push((score, key, decoded));
     }
-    &results[..].sort_by(|&(d1, _, _), &(d2, _, _)|
+    results.sort_by(|&(d1, _, _), &(d2, _, _)|
                         match d2.partial_cmp(&d1) {
                             None => ::std::cmp::Ordering::Less,
                             Some(o) => o,
                         });
-    results.into_iter().map(|(_, k, d)| (k, d)).collect()
+    let confidence = match results.len() {
+        0 => 0.0,
+        1 => 1.0,
+        _ => attack::margin(results[0].0, results[1].0),
+    };
+    let mut report = Report::new(Vec::with_capacity(results.len()), confidence);
+    for (score, key, decoded) in results {
+        report.note(format!("key size {} scored {:.2}", key.len(), score));
+        report.result.push((key, decoded));
+    }
+    report
+}
+
+/// Return true for bytes that may appear in readable text: printable
+/// ASCII characters, tab and newlines.
+fn readable(b: u8) -> bool {
+    (b >= 0x20 && b < 0x7f) || b == b'\t' || b == b'\n' || b == b'\r'
+}
+
+/// Slide the guessed plaintext fragment `crib` across
+/// `ciphertext_xor`, the XOR of two ciphertexts encrypted with the
+/// same keystream, which equals the XOR of the two plaintexts.
+/// Wherever the crib occurs in one plaintext, XORing it in reveals
+/// the other plaintext at the same offset.  Returns the offsets where
+/// that gives readable text, together with the text.
+pub fn crib_drag(ciphertext_xor: &[u8], crib: &[u8]) -> Vec<(usize, Vec<u8>)> {
+    if crib.is_empty() || crib.len() > ciphertext_xor.len() {
+        return Vec::new();
+    }
+    let mut results = Vec::new();
+    for offset in 0..ciphertext_xor.len() - crib.len() + 1 {
+        let mut context = ciphertext_xor[offset..offset + crib.len()].to_vec();
+        xor_into(&mut context, crib);
+        if context.iter().all(|&b| readable(b)) {
+            results.push((offset, context));
+        }
+    }
+    results
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{one_byte, xor_bytes, crack_single_byte_xor, repeating};
-    use super::{crack_repeating_xor};
-    use ::codec;
+    use super::{one_byte, xor_bytes, xor_into, crack_single_byte_xor, repeating};
+    use super::{single_byte_xor_candidates, crack_single_byte_xor_threshold};
+    use super::{crack_repeating_xor, crack_repeating_xor_with, crack_single_byte_xor_with};
+    use super::{detect_keysize, KeysizeConfig, crib_drag, English, Event, detect_single_byte_xor};
+    use super::{single_byte_xor_candidates_with, Ngram, Markov};
+    use crate::error::Error;
+    use crate::codec;
     
     #[test]
     fn apply_empty() {
@@ -164,12 +407,13 @@ mod tests {
     }
 
     #[test]
-    #[should_panic]
-    fn xor_bytes_panic() {
+    fn xor_bytes_mismatch() {
         let b0 = [0, 1, 2];
         let b1 = [0, 1, 2, 3];
-        let expected = vec![0, 0, 0, 0];
-        assert_eq!(expected, xor_bytes(&b0, &b1));
+        match xor_bytes(&b0, &b1) {
+            Err(Error::LengthMismatch { left: 3, right: 4 }) => (),
+            r => panic!("unexpected result: {:?}", r),
+        }
     }
 
     #[test]
@@ -177,7 +421,7 @@ mod tests {
         let b0 = [0, 1, 2, 3];
         let b1 = [0, 1, 2, 3];
         let expected = vec![0, 0, 0, 0];
-        assert_eq!(expected, xor_bytes(&b0, &b1));
+        assert_eq!(expected, xor_bytes(&b0, &b1).unwrap());
     }
 
     #[test]
@@ -185,7 +429,23 @@ mod tests {
         let b0 = codec::hex::decode("1c0111001f010100061a024b53535009181c").unwrap();
         let b1 = codec::hex::decode("686974207468652062756c6c277320657965").unwrap();
         let expected = codec::hex::decode("746865206b696420646f6e277420706c6179").unwrap();
-        assert_eq!(expected, xor_bytes(&b0, &b1));
+        assert_eq!(expected, xor_bytes(&b0, &b1).unwrap());
+    }
+
+    quickcheck! {
+        fn prop_xor_bytes(xs: Vec<(u8, u8)>) -> bool {
+            let b0: Vec<u8> = xs.iter().map(|p| p.0).collect();
+            let b1: Vec<u8> = xs.iter().map(|p| p.1).collect();
+            let expected: Vec<u8> = xs.iter().map(|p| p.0 ^ p.1).collect();
+            let mut b2 = b0.clone();
+            xor_into(&mut b2, &b1);
+            xor_bytes(&b0, &b1).unwrap() == expected && b2 == expected
+        }
+
+        fn prop_repeating(key: Vec<u8>, msg: Vec<u8>) -> bool {
+            key.is_empty() ||
+                repeating(&key, &msg) == msg.iter().zip(key.iter().cycle()).map(|(m, k)| m ^ k).collect::<Vec<u8>>()
+        }
     }
 
     #[test]
@@ -196,7 +456,97 @@ mod tests {
                              77, 67, 39, 115, 32, 108, 105, 107, 101,
                              32, 97, 32, 112, 111, 117, 110, 100, 32,
                              111, 102, 32, 98, 97, 99, 111, 110]),
-                   res);
+                   res.result);
+        assert!(res.confidence > 0.0 && res.confidence <= 1.0);
+        assert_eq!(0, res.queries_used);
+    }
+
+    #[test]
+    fn candidates() {
+        let input = codec::hex::decode("1b37373331363f78151b7f2b783431333d78397828372d363c78373e783a393b3736").unwrap();
+        let res = single_byte_xor_candidates(&input, 5);
+        assert_eq!(5, res.len());
+        assert_eq!(88, res[0].1);
+        assert_eq!(&b"Cooking MC's like a pound of bacon"[..], &res[0].2[..]);
+        assert!(res.windows(2).all(|w| w[0].0 >= w[1].0));
+        assert_eq!(256, single_byte_xor_candidates(&input, 1000).len());
+        assert_eq!(None, crack_single_byte_xor_threshold(&input, res[0].0 + 1.0));
+        assert_eq!(Some(88), crack_single_byte_xor_threshold(&input, res[0].0).map(|r| r.result.0));
+    }
+
+    // Scores the fraction of bytes that are lowercase hex digits.
+    fn hex_digits(msg: &[u8]) -> f64 {
+        msg.iter().filter(|&&b| (b >= b'0' && b <= b'9') || (b >= b'a' && b <= b'f')).count() as f64 /
+            msg.len() as f64
+    }
+
+    #[test]
+    fn custom_scorer() {
+        let plain = b"deadbeef0123456789abcdef";
+        let input = one_byte(0x42, plain);
+        assert_eq!(Some((0x42, plain.to_vec())),
+                   crack_single_byte_xor_with(&input, 1.0, &hex_digits).map(|r| r.result));
+
+        // Mostly zero binary data, scored by the fraction of zeros.
+        let mut plain = vec![0; 300];
+        for i in 0..10 {
+            plain[200 + i * 9] = i as u8 + 1;
+        }
+        let zeros = |msg: &[u8]| msg.iter().filter(|&&b| b == 0).count() as f64 / msg.len() as f64;
+        let encrypted = repeating(b"KEY", &plain);
+        let decrypted = crack_repeating_xor_with(&encrypted, 5, &zeros, None);
+        assert!(decrypted.result.contains(&(b"KEY".to_vec(), plain)));
+    }
+
+    #[test]
+    fn detect_single_byte_xor_0() {
+        let lines = vec![
+            codec::hex::decode("0e3647e8592d35514a081243582536ed3de6734059001e3f535ce6271032").unwrap(),
+            one_byte(0x35, b"Now that the party is jumping\n"),
+            codec::hex::decode("334b041de124f73c18011a50e608097ac308ecee501337ec3e100854201d").unwrap(),
+            vec![],
+        ];
+        let results = detect_single_byte_xor(&lines);
+        assert_eq!(4, results.len());
+        assert_eq!((1, 0x35), (results[0].0, results[0].1));
+        assert_eq!(&b"Now that the party is jumping\n"[..], &results[0].2[..]);
+        assert!(results.windows(2).all(|w| w[0].3 >= w[1].3));
+    }
+
+    #[test]
+    fn ngram_scorer() {
+        let input = one_byte(0x2a, b"Now that the party is jumping");
+        let res = single_byte_xor_candidates_with(&input, 1, &Ngram);
+        assert_eq!(0x2a, res[0].1);
+    }
+
+    #[test]
+    fn model_scorer() {
+        let model = crate::language::Model::train(b"GET /index.html HTTP/1.1\r\nHost: example.com\r\n\
+                                               Accept: text/html\r\nConnection: close\r\n\r\n");
+        let input = one_byte(0x5c, b"GET /about.html HTTP/1.1\r\nHost: example.org\r\n\r\n");
+        let res = single_byte_xor_candidates_with(&input, 1, &model);
+        assert_eq!(0x5c, res[0].1);
+    }
+
+    #[test]
+    fn language_scorer() {
+        let input = one_byte(0x33, b"Der schnelle braune Fuchs springt ueber den faulen Hund.");
+        let res = single_byte_xor_candidates_with(&input, 1, &crate::language::Language::German);
+        assert_eq!(0x33, res[0].1);
+    }
+
+    #[test]
+    fn markov_scorer() {
+        let input = one_byte(0x7b, b"Go on, then");
+        let res = single_byte_xor_candidates_with(&input, 1, &Markov);
+        assert_eq!(0x7b, res[0].1);
+    }
+
+    #[test]
+    fn crack_key_ff() {
+        let input = one_byte(0xff, b"Now that the party is jumping");
+        assert_eq!(Some(0xff), single_byte_xor_candidates(&input, 1).pop().map(|r| r.1));
     }
 
     #[test]
@@ -224,15 +574,72 @@ mod tests {
         assert_eq!(expected, repeating(key, input));
     }
 
+    #[test]
+    fn detect_keysize_0() {
+        let input = b"Burning 'em, if you ain't quick and nimble\nI go crazy when I hear a cymbal";
+        let encrypted = repeating(b"ICE", input);
+        let scores = detect_keysize(&encrypted, &KeysizeConfig::default());
+        // 74 bytes contain four blocks of up to 18 bytes.
+        assert_eq!(17, scores.len());
+        assert!(scores.windows(2).all(|w| w[0].0 <= w[1].0));
+
+        let config = KeysizeConfig { min_keysize: 3, max_keysize: 30, block_pairs: 1 };
+        let scores = detect_keysize(&encrypted, &config);
+        assert_eq!(28, scores.len());
+        assert!(scores.iter().all(|&(_, k)| k >= 3 && k <= 30));
+
+        let config = KeysizeConfig { min_keysize: 2, max_keysize: 40, block_pairs: 20 };
+        assert_eq!(vec![(0.0, 2), (0.0, 3)], {
+            let mut s = detect_keysize(&[0; 80], &config);
+            s.sort_by_key(|p| p.1);
+            s
+        });
+    }
+
+    #[test]
+    fn crib_drag_0() {
+        let p1 = b"Now that the party is jumping";
+        let p2 = b"I have met them at close of day";
+        let keystream = one_byte(0x5c, &[0x17; 29]);
+        let c1 = xor_bytes(p1, &keystream).unwrap();
+        let c2 = xor_bytes(&p2[..29], &keystream).unwrap();
+        let hits = crib_drag(&xor_bytes(&c1, &c2).unwrap(), b" the ");
+        assert!(hits.contains(&(8, b"et th".to_vec())));
+        assert!(hits.iter().all(|&(o, ref t)| o + 5 <= 29 && t.len() == 5));
+        assert!(crib_drag(&c1, b"").is_empty());
+        assert!(crib_drag(&c1[..3], b"the ").is_empty());
+    }
+
+    #[test]
+    fn crack_repeating_progress() {
+        let encrypted = repeating(b"ICE", b"Burning 'em, if you ain't quick and nimble");
+        let mut events = Vec::new();
+        {
+            let mut record = |e: Event| events.push(e);
+            crack_repeating_xor_with(&encrypted, 2, &English, Some(&mut record));
+        }
+        let keysizes: Vec<usize> = events.iter()
+            .filter_map(|e| match *e { Event::KeySize { keysize, .. } => Some(keysize), _ => None })
+            .collect();
+        assert_eq!(2, keysizes.len());
+        let columns = events.iter().filter(|e| match **e { Event::Column { .. } => true, _ => false }).count();
+        assert_eq!(keysizes[0] + keysizes[1], columns);
+        match events[events.len() - 1] {
+            Event::Candidate { keysize, .. } => assert_eq!(keysizes[1], keysize),
+            ref e => panic!("unexpected event: {:?}", e),
+        }
+    }
+
     #[test]
     fn crack_repeating() {
         let key = b"ICE";
         let input = b"Burning 'em, if you ain't quick and nimble\nI go crazy when I hear a cymbal";
         let encrypted = repeating(key, input);
         let decrypted = crack_repeating_xor(&encrypted, 15);
+        assert_eq!(15, decrypted.notes.len());
         let mut found = false;
-        for &(_, ref d) in decrypted.iter() {
-            let scr = ::language::english::score_string(&d);
+        for &(_, ref d) in decrypted.result.iter() {
+            let scr = crate::language::english::score_string(&d);
             println!("{} {:?}", scr, String::from_utf8_lossy(&d));
             if &d[..] == &input[..] {
                 found = true;
@@ -0,0 +1,156 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! Solve the challenges through the library and check the known
+//! answers, so that changes to the attacks cannot quietly make them
+//! worse.
+
+use cryptopals::{codec, data, random, xor};
+use cryptopals::attack::{cbc, ecb};
+use cryptopals::cipher::aes::{self, AesKey};
+use cryptopals::padding::{pkcs7, PaddingScheme};
+
+/// First line of the plaintext of challenges 6, 7 and 10.
+const FUNKY_MUSIC: &'static str = "I'm back and I'm ringin' the bell \n";
+
+fn random_key() -> AesKey {
+    let mut k = [0u8; 16];
+    random::fill_bytes(&mut k);
+    AesKey::from(k)
+}
+
+#[test]
+fn challenge_1() {
+    let input = codec::hex::decode("49276d206b696c6c696e6720796f757220627261696e206c696b65206120706f69736f6e6f7573206d757368726f6f6d").unwrap();
+    assert_eq!("SSdtIGtpbGxpbmcgeW91ciBicmFpbiBsaWtlIGEgcG9pc29ub3VzIG11c2hyb29t",
+               codec::base64::encode(&input));
+}
+
+#[test]
+fn challenge_2() {
+    let b0 = codec::hex::decode("1c0111001f010100061a024b53535009181c").unwrap();
+    let b1 = codec::hex::decode("686974207468652062756c6c277320657965").unwrap();
+    assert_eq!("746865206b696420646f6e277420706c6179", codec::hex::encode(&xor::xor_bytes(&b0, &b1).unwrap()));
+}
+
+#[test]
+fn challenge_3() {
+    let input = codec::hex::decode("1b37373331363f78151b7f2b783431333d78397828372d363c78373e783a393b3736").unwrap();
+    let report = xor::crack_single_byte_xor(&input).unwrap();
+    assert_eq!((0x58, b"Cooking MC's like a pound of bacon".to_vec()), report.result);
+}
+
+#[test]
+fn challenge_4() {
+    let lines = data::load_hex_lines("data/4.txt").unwrap();
+    let candidates = xor::detect_single_byte_xor(&lines);
+    let &(i, k, ref plaintext, _) = candidates.first().unwrap();
+    assert_eq!((170, 0x35), (i, k));
+    assert_eq!(b"Now that the party is jumping\n".to_vec(), *plaintext);
+}
+
+#[test]
+fn challenge_5() {
+    let input = b"Burning 'em, if you ain't quick and nimble\nI go crazy when I hear a cymbal";
+    assert_eq!("0b3637272a2b2e63622c2e69692a23693a2a3c6324202d623d63343c2a26226324272765272a282b2f20430a652e2c652a3124333a653e2b2027630c692b20283165286326302e27282f",
+               codec::hex::encode(&xor::repeating(b"ICE", input)));
+}
+
+#[test]
+fn challenge_6() {
+    let c = data::load_base64_file("data/6.txt").unwrap();
+    let report = xor::crack_repeating_xor(&c, 4);
+    let (ref key, ref plaintext) = report.result[0];
+    assert_eq!(b"Terminator X: Bring the noise".to_vec(), *key);
+    assert!(plaintext.starts_with(FUNKY_MUSIC.as_bytes()));
+}
+
+#[test]
+fn challenge_7() {
+    let c = data::load_base64_file("data/7.txt").unwrap();
+    let key = AesKey::from_slice(b"YELLOW SUBMARINE").unwrap();
+    let plaintext = aes::decrypt_ecb(&key, &c).unwrap();
+    assert!(plaintext.starts_with(FUNKY_MUSIC.as_bytes()));
+}
+
+#[test]
+fn challenge_8() {
+    let lines = data::load_hex_lines("data/8.txt").unwrap();
+    let detected: Vec<usize> = lines.iter()
+        .enumerate()
+        .filter(|&(_, l)| aes::detect_ecb(l))
+        .map(|(i, _)| i)
+        .collect();
+    assert_eq!(vec![132], detected);
+
+    let scanned: Vec<(usize, usize)> = lines.iter()
+        .enumerate()
+        .filter_map(|(i, l)| ecb::detect_ecb_block_size(l).map(|(bs, _)| (i, bs)))
+        .collect();
+    assert_eq!(vec![(132, 16)], scanned);
+}
+
+#[test]
+fn challenge_9() {
+    assert_eq!(b"YELLOW SUBMARINE\x04\x04\x04\x04".to_vec(), pkcs7::pad(b"YELLOW SUBMARINE", 20).unwrap());
+}
+
+#[test]
+fn challenge_10() {
+    let c = data::load_base64_file("data/10.txt").unwrap();
+    let key = AesKey::from_slice(b"YELLOW SUBMARINE").unwrap();
+    let plaintext = aes::decrypt_cbc(&key, &[0; 16], &c).unwrap();
+    assert!(plaintext.starts_with(FUNKY_MUSIC.as_bytes()));
+}
+
+#[test]
+fn challenge_11() {
+    let input = [0u8; 48];
+    for _ in 0..20 {
+        let key = random_key();
+        let mut data = vec![0u8; random::gen_range(5, 11)];
+        data.extend(&input[..]);
+        data.extend(vec![0u8; random::gen_range(5, 11)]);
+        let ecb: bool = random::gen();
+        let c = if ecb {
+            aes::encrypt_ecb(&key, &data)
+        } else {
+            let mut iv = [0u8; 16];
+            random::fill_bytes(&mut iv);
+            aes::encrypt_cbc(&key, &iv, &data)
+        };
+        assert_eq!(ecb, aes::detect_ecb(&c));
+    }
+}
+
+#[test]
+fn challenge_12() {
+    let key = random_key();
+    let suffix = codec::base64::decode("Um9sbGluJyBpbiBteSA1LjAKV2l0aCBteSByYWctdG9wIGRvd24gc28gbXkg\
+                                        aGFpciBjYW4gYmxvdwpUaGUgZ2lybGllcyBvbiBzdGFuZGJ5IHdhdmluZyBq\
+                                        dXN0IHRvIHNheSBoaQpEaWQgeW91IHN0b3A/IE5vLCBJIGp1c3QgZHJvdmUg\
+                                        YnkK").unwrap();
+    let oracle = |input: &[u8]| {
+        let mut data = input.to_vec();
+        data.extend(&suffix);
+        aes::encrypt_ecb(&key, &data)
+    };
+    let report = ecb::decrypt_suffix(oracle);
+    assert_eq!(suffix, report.result);
+}
+
+#[test]
+fn challenge_17() {
+    let key = random_key();
+    let mut iv = [0u8; 16];
+    random::fill_bytes(&mut iv);
+    let plaintext = codec::base64::decode("MDAwMDAwTm93IHRoYXQgdGhlIHBhcnR5IGlzIGp1bXBpbmc=").unwrap();
+    let c = aes::encrypt_cbc(&key, &iv, &plaintext);
+    let oracle = |q: &[u8]| {
+        let mut qiv = [0u8; 16];
+        qiv.copy_from_slice(&q[..16]);
+        aes::decrypt_cbc_with(&key, &qiv, &q[16..], &PaddingScheme::Pkcs7).is_ok()
+    };
+    let report = cbc::padding_oracle(&iv, &c, oracle);
+    assert_eq!(plaintext, report.result);
+}
@@ -0,0 +1,66 @@
+// Copyright 2016 Martin Grabmueller. See the LICENSE file at the
+// top-level directory of this distribution for license information.
+
+//! Run the `cryptopals` binary and check its output and exit code.
+
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+const KEY: &'static str = "000102030405060708090a0b0c0d0e0f";
+const WRONG_KEY: &'static str = "0f0e0d0c0b0a09080706050403020100";
+const IV: &'static str = "101112131415161718191a1b1c1d1e1f";
+
+/// Run `cryptopals` with `args`, feeding it `input` on standard input.
+fn run(args: &[&str], input: &[u8]) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_cryptopals"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(input).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn aes_roundtrip() {
+    for mode in &["ecb", "cbc", "ctr"] {
+        let iv: &[&str] = if *mode == "ecb" { &[] } else { &["--iv", IV] };
+        let mut args = vec!["aes", "--mode", mode, "--key", KEY];
+        args.extend_from_slice(iv);
+        let encrypted = run(&args, b"attack at dawn");
+        assert!(encrypted.status.success());
+
+        args.push("-d");
+        let decrypted = run(&args, &encrypted.stdout);
+        assert!(decrypted.status.success());
+        assert_eq!(b"attack at dawn".to_vec(), decrypted.stdout);
+    }
+}
+
+#[test]
+fn aes_decrypt_wrong_key() {
+    for mode in &["ecb", "cbc"] {
+        let iv: &[&str] = if *mode == "ecb" { &[] } else { &["--iv", IV] };
+        let mut args = vec!["aes", "--mode", mode, "--key", KEY];
+        args.extend_from_slice(iv);
+        let encrypted = run(&args, b"attack at dawn");
+        assert!(encrypted.status.success());
+
+        let mut args = vec!["aes", "-d", "--mode", mode, "--key", WRONG_KEY];
+        args.extend_from_slice(iv);
+        let decrypted = run(&args, &encrypted.stdout);
+        assert_eq!(Some(1), decrypted.status.code());
+        assert!(decrypted.stdout.is_empty());
+        assert!(String::from_utf8_lossy(&decrypted.stderr).contains("Invalid padding"));
+    }
+}
+
+#[test]
+fn aes_decrypt_empty() {
+    let decrypted = run(&["aes", "-d", "--mode", "cbc", "--key", KEY, "--iv", IV], b"");
+    assert_eq!(Some(1), decrypted.status.code());
+    let truncated = run(&["aes", "-d", "--mode", "ecb", "--key", KEY], &[0; 15]);
+    assert_eq!(Some(1), truncated.status.code());
+}